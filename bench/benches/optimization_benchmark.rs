@@ -235,6 +235,7 @@ fn bench_no_clone_optimization(c: &mut Criterion) {
             },
             |(db, selected_tag)| {
                 // Cloning approach
+                #[allow(clippy::cloned_ref_to_slice_refs)]
                 black_box(db.search_tags(&[selected_tag.clone()]).unwrap());
             },
         );
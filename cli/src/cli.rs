@@ -1,6 +1,11 @@
 use bukurs::db::BukuDb;
 use bukurs::error::Result;
-use clap::{Parser, Subcommand};
+use bukurs::fuzzy::PickerKind;
+use bukurs::import_export::{BrowserType, DuplicateStrategy, FeedFormat};
+use bukurs::operations::{rating::MAX_RATING, timeframe::TimeFilter, BookmarkFilter, SelectorTerm};
+use bukurs::tags::{parse_tags, TagNormalization, TagSetOp};
+use clap::{CommandFactory, Parser, Subcommand};
+use std::ffi::OsString;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 
@@ -18,6 +23,87 @@ pub fn get_exe_name() -> &'static str {
     })
 }
 
+/// Subcommands whose positional arguments are bookmark selectors
+const SELECTOR_COMMANDS: &[&str] = &[
+    "print", "open", "delete", "update", "edit", "note", "similar", "pin", "unpin", "move", "star",
+    "unstar", "links", "read",
+];
+
+/// Subcommands whose keywords can drop sites with `-site:reddit.com`
+const KEYWORD_COMMANDS: &[&str] = &["search"];
+
+/// Move relative indices (`-1`, `-3..-1`) and excluded sites (`-site:reddit.com`)
+/// after a `--` separator
+///
+/// clap would otherwise read them as (unknown) short flags. Values of options,
+/// such as the `-archived` in `--tag -archived`, are left alone.
+pub fn escape_relative_indices<I: IntoIterator<Item = OsString>>(args: I) -> Vec<OsString> {
+    let args: Vec<OsString> = args.into_iter().collect();
+    let Some(pos) = args.iter().position(|a| {
+        a.to_str()
+            .is_some_and(|a| SELECTOR_COMMANDS.contains(&a) || KEYWORD_COMMANDS.contains(&a))
+    }) else {
+        return args;
+    };
+    let keywords = KEYWORD_COMMANDS.contains(&args[pos].to_str().unwrap_or_default());
+    let escapes = |token: &str| {
+        if keywords {
+            token.starts_with("-site:")
+        } else {
+            token.starts_with('-') && matches!(SelectorTerm::parse(token), Ok(Some(_)))
+        }
+    };
+    if args[pos + 1..].iter().any(|a| a == "--") {
+        return args;
+    }
+
+    let command = Cli::command();
+    let Some(sub) = args[pos]
+        .to_str()
+        .and_then(|name| command.find_subcommand(name))
+    else {
+        return args;
+    };
+
+    let mut kept = args[..=pos].to_vec();
+    let mut relative = Vec::new();
+    let mut expects_value = false;
+    for arg in &args[pos + 1..] {
+        let token = arg.to_str().unwrap_or_default();
+        if !expects_value && escapes(token) {
+            relative.push(arg.clone());
+            continue;
+        }
+        expects_value = option_takes_value(sub, token);
+        kept.push(arg.clone());
+    }
+
+    if !relative.is_empty() {
+        kept.push(OsString::from("--"));
+        kept.extend(relative);
+    }
+    kept
+}
+
+/// Whether `token` is an option of `command` whose value is the next argument
+fn option_takes_value(command: &clap::Command, token: &str) -> bool {
+    let arg = if let Some(long) = token.strip_prefix("--") {
+        if long.contains('=') {
+            return false;
+        }
+        command.get_arguments().find(|a| a.get_long() == Some(long))
+    } else if let Some(short) = token.strip_prefix('-') {
+        let mut chars = short.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => command.get_arguments().find(|a| a.get_short() == Some(c)),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    arg.is_some_and(|a| a.get_action().takes_values())
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, disable_version_flag = true)]
 pub struct Cli {
@@ -29,11 +115,23 @@ pub struct Cli {
     #[arg(long)]
     pub db: Option<PathBuf>,
 
+    /// Use the database of a profile from `profiles` in the config
+    #[arg(long, value_name = "NAME", conflicts_with = "db")]
+    pub profile: Option<String>,
+
     /// Optional custom configuration file path
     #[arg(long)]
     pub config: Option<PathBuf>,
 
-    /// Disable color output
+    /// Answer yes to confirmation prompts (for scripts and cron)
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Never prompt: fail when input, a picker or an editor would be needed
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Disable color output (overrides `color` in the config)
     #[arg(long)]
     pub nc: bool,
 
@@ -41,14 +139,39 @@ pub struct Cli {
     #[arg(short = 'g', long = "debug")]
     pub debug: bool,
 
+    /// Output format: json, yaml, toml, toon, `template:{id}\t{url}` or a template
+    /// name from `formats` in the config
     #[arg(short = 'f', long)]
     pub format: Option<String>,
 
+    /// Print tags as the stored `,a,b,` string in json, yaml and toml output
+    /// instead of a list (the format before tags were lists)
+    #[arg(long)]
+    pub raw_tags: bool,
+
+    /// Also print what add, update, delete and import changed as a JSON object on stdout
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = ["text", "json"])]
+    pub output: Option<String>,
+
+    /// Connect to the network for this command even with `default_offline` set
+    #[arg(long, global = true)]
+    pub online: bool,
+
+    /// Bookmark picker: the built-in one, or `picker.command` from the config (fzf, skim)
+    #[arg(long, global = true, value_parser = ["builtin", "external"])]
+    pub picker: Option<String>,
+
+    /// PEM bundle of extra certificate authorities to trust when fetching
+    /// (overrides `fetch.tls_ca_file`)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub cacert: Option<PathBuf>,
+
     /// Open selected bookmark in browser
     #[arg(short = 'o', long)]
     pub open: bool,
 
-    /// Limit number of results shown (shows last N entries)
+    /// Limit number of results shown (shows last N entries, the best N with search --rank,
+    /// or the first N query rows)
     #[arg(short = 'n', long)]
     pub limit: Option<usize>,
 
@@ -64,7 +187,7 @@ pub struct Cli {
 pub enum Commands {
     /// Add a new bookmark
     Add {
-        /// URL to bookmark
+        /// URL to bookmark, or - to read `url [tags] [title]` lines from stdin
         url: String,
 
         /// Comma-separated tags
@@ -82,11 +205,48 @@ pub enum Commands {
         /// Add without connecting to web
         #[arg(long)]
         offline: bool,
+
+        /// Don't suggest tags from the domain, page keywords and similar bookmarks
+        #[arg(long)]
+        no_suggest: bool,
+
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+
+        /// Add to the read-later queue (tagged `unread`, see `later`)
+        #[arg(long)]
+        later: bool,
+
+        /// Expire the bookmark after this long, e.g. 30d, 12h, 2w (see `expired`)
+        #[arg(long, value_name = "DURATION")]
+        expires: Option<String>,
+    },
+
+    /// Add a bookmark from a `bukurs://add?url=...&title=...&tags=...` link
+    ///
+    /// Register `bukurs add-url %u` as the system handler for `bukurs://` links
+    /// so the bookmarklet (see `bookmarklet`) can add pages with one click.
+    AddUrl {
+        /// The bukurs:// link
+        link: String,
+
+        /// Add without connecting to web
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Print a browser bookmarklet that sends the current page to `add-url`
+    Bookmarklet {
+        /// Comma-separated tags added to every page saved with the bookmarklet
+        #[arg(short, long)]
+        tags: Option<String>,
     },
 
     /// Update an existing bookmark
     Update {
         /// Bookmark indices, ranges (e.g., 1-5), or * for all
+        /// Negative indices count back from the most recent: -1, -3..-1
         /// When no edit options are provided, refreshes metadata from web
         #[arg(num_args = 0..)]
         ids: Vec<String>,
@@ -95,9 +255,10 @@ pub enum Commands {
         #[arg(long)]
         url: Option<String>,
 
-        /// Tag operations (supports: +add, -remove, ~old:new, or plain tag to add)
-        /// Examples: +urgent, -archived, ~todo:done
-        #[arg(short, long)]
+        /// Tag operations (supports: +add, -remove, ~old:new, or plain tag to add),
+        /// applied in order; separate several with commas or repeat the option
+        /// Examples: +urgent, -archived, ~todo:done, +urgent,-archived
+        #[arg(short, long, allow_hyphen_values = true)]
         tag: Option<Vec<String>>,
 
         /// New title
@@ -111,11 +272,20 @@ pub enum Commands {
         /// Disable web-fetch during auto-refresh
         #[arg(long)]
         immutable: Option<u8>,
+
+        /// Pick the bookmarks to update in the fuzzy picker (Tab to mark several)
+        #[arg(short, long)]
+        pick: bool,
+
+        /// Fetch timeout in seconds when refreshing (overrides `fetch.timeout_secs` in the config)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
     },
 
     /// Delete bookmark(s)
     Delete {
         /// Bookmark indices, ranges (e.g., 1-5), or * for all
+        /// Negative indices count back from the most recent: -1, -3..-1
         #[arg(num_args = 0..)]
         ids: Vec<String>,
 
@@ -126,11 +296,16 @@ pub enum Commands {
         /// Prevents reordering after deletion
         #[arg(long)]
         retain_order: bool,
+
+        /// Pick the bookmarks to delete in the fuzzy picker (Tab to mark several)
+        #[arg(short, long)]
+        pick: bool,
     },
 
     /// Print/list bookmarks
     Print {
-        /// Bookmark indices or ranges to print
+        /// Bookmark indices or ranges to print (e.g., 5, 1-5)
+        /// Negative indices count back from the most recent: -1, -3..-1
         #[arg(num_args = 0..)]
         ids: Vec<String>,
 
@@ -146,6 +321,35 @@ pub enum Commands {
         ///    7         => URL + Title + Tags (1 | 2 | 4)
         #[arg(short, long)]
         columns: Option<u8>,
+
+        /// Show bookmark, tag, unread and language counts instead of the bookmarks
+        #[arg(long)]
+        stats: bool,
+
+        /// Print the bookmarks in a collection (see `collection create`)
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["ids", "stats"])]
+        collection: Option<String>,
+
+        /// Only print bookmarks rated at least N stars
+        #[arg(long, value_name = "N", conflicts_with = "stats")]
+        min_rating: Option<u8>,
+
+        /// List the highest rated bookmarks first
+        #[arg(long, conflicts_with = "stats")]
+        by_rating: bool,
+
+        /// Only print bookmarks added on or after DATE (YYYY-MM-DD, UTC)
+        #[arg(long, value_name = "DATE", conflicts_with = "stats")]
+        added_after: Option<String>,
+
+        /// Only print bookmarks added within DURATION (e.g. 7d, 2w, 1y)
+        #[arg(long, value_name = "DURATION", conflicts_with = "stats")]
+        added_within: Option<String>,
+
+        /// Only print bookmarks not opened through bukurs within DURATION
+        /// (bookmarks never opened always match)
+        #[arg(long, value_name = "DURATION", conflicts_with = "stats")]
+        not_visited_since: Option<String>,
     },
 
     /// Search bookmarks
@@ -168,10 +372,90 @@ pub enum Commands {
         /// Search for keywords in specific fields
         #[arg(long)]
         markers: bool,
+
+        /// Only search bookmark notes (descriptions)
+        #[arg(long)]
+        notes: bool,
+
+        /// Run a command for each result instead of listing them, e.g. 'curl -I {url}'
+        /// Placeholders: {url} {id} {title} {tags} {desc}; without any, the URL is appended
+        #[arg(short = 'x', long, value_name = "CMD")]
+        exec: Option<String>,
+
+        /// Run a command once with all results; words with a placeholder repeat per result
+        #[arg(short = 'X', long, value_name = "CMD", conflicts_with = "exec")]
+        exec_batch: Option<String>,
+
+        /// Number of --exec commands run at once (default: one per CPU)
+        #[arg(short, long, conflicts_with = "exec_batch")]
+        jobs: Option<usize>,
+
+        /// Pick from the results and open the selection in the browser
+        #[arg(short = 'o', long, conflicts_with_all = ["exec", "exec_batch"])]
+        open: bool,
+
+        /// Only search the results of the previous search
+        #[arg(long, visible_alias = "refine")]
+        within_last: bool,
+
+        /// Order results best first; relevance weighs title and tag matches highest
+        #[arg(long, value_parser = ["relevance", "recent", "visits", "rating"])]
+        rank: Option<String>,
+
+        /// Only keep bookmarks rated at least N stars
+        #[arg(long, value_name = "N")]
+        min_rating: Option<u8>,
+
+        /// Only keep pages in this language, e.g. `de` (detected when fetched)
+        #[arg(long, value_name = "CODE")]
+        lang: Option<String>,
+
+        /// Only keep bookmarks added on or after DATE (YYYY-MM-DD, UTC)
+        #[arg(long, value_name = "DATE")]
+        added_after: Option<String>,
+
+        /// Only keep bookmarks added within DURATION (e.g. 7d, 2w, 1y)
+        #[arg(long, value_name = "DURATION")]
+        added_within: Option<String>,
+
+        /// Only keep bookmarks not opened through bukurs within DURATION
+        /// (bookmarks never opened always match)
+        #[arg(long, value_name = "DURATION")]
+        not_visited_since: Option<String>,
+
+        /// Match keyword prefixes, and similar words when nothing matches (forgives typos)
+        #[arg(long, conflicts_with = "regex")]
+        fuzzy: bool,
+
+        /// Also save the search as NAME, to repeat it with `saved run NAME`
+        #[arg(long, value_name = "NAME", conflicts_with = "within_last")]
+        save: Option<String>,
     },
 
-    /// Search bookmarks by tags
+    /// List, repeat or delete searches kept with `search --save`
+    Saved {
+        #[command(subcommand)]
+        action: SavedAction,
+    },
+
+    /// Give bookmarks short names to open them by, e.g. `alias set gh 42` then `open gh`
+    Alias {
+        #[command(subcommand)]
+        action: AliasAction,
+    },
+
+    /// Define, list or delete smart collections: named queries evaluated when used
+    Collection {
+        #[command(subcommand)]
+        action: CollectionAction,
+    },
+
+    /// Search bookmarks by tags, or add, remove or set tags on many at once
+    #[command(args_conflicts_with_subcommands = true)]
     Tag {
+        #[command(subcommand)]
+        action: Option<TagAction>,
+
         /// Tag keywords to search
         #[arg(num_args = 0..)]
         tags: Vec<String>,
@@ -179,9 +463,21 @@ pub enum Commands {
 
     /// Encrypt database
     Lock {
-        /// Number of hash iterations
+        /// Number of hash iterations (sha256 key derivation)
         #[arg(default_value = "8")]
         iterations: u32,
+
+        /// Key derivation: sha256 (compatible with buku) or argon2 (Argon2id, memory-hard)
+        #[arg(long, default_value = "sha256", value_parser = ["sha256", "argon2"])]
+        kdf: String,
+
+        /// Memory used by argon2, e.g. 256MB (default 64MB)
+        #[arg(long, value_name = "SIZE")]
+        memory: Option<String>,
+
+        /// Passes over memory made by argon2
+        #[arg(long, default_value_t = bukurs::crypto::ARGON2_DEFAULT_PASSES)]
+        passes: u32,
     },
 
     /// Decrypt database
@@ -191,10 +487,40 @@ pub enum Commands {
         iterations: u32,
     },
 
+    /// Encrypt the URL, title and description of bookmarks with a tag
+    Encrypt {
+        /// Tag of the bookmarks to encrypt
+        #[arg(long)]
+        tag: String,
+    },
+
+    /// Decrypt bookmarks encrypted with `encrypt`
+    Decrypt {
+        /// Only decrypt bookmarks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
     /// Import bookmarks from file
     Import {
-        /// File path to import from
+        /// File path to import from (format from extension: .html, .md, .org)
         file: String,
+
+        /// Report what would be imported without writing to the database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Import with multiple threads (default: only for large files)
+        #[arg(long)]
+        parallel: bool,
+
+        /// How to handle URLs that already exist: skip, merge (union tags), or overwrite
+        #[arg(long, value_name = "STRATEGY", default_value = "skip", value_parser = ["skip", "merge", "overwrite"])]
+        on_duplicate: String,
+
+        /// Write each skipped or merged bookmark with the reason to FILE (JSON Lines)
+        #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+        report: Option<PathBuf>,
     },
 
     /// Import bookmarks from browser profiles
@@ -210,17 +536,134 @@ pub enum Commands {
         /// Specific browsers to import from (comma-separated: chrome,firefox,edge,safari)
         #[arg(short, long, value_delimiter = ',')]
         browsers: Option<Vec<String>>,
+
+        /// Import from this bookmarks file or profile directory instead of the detected
+        /// ones, e.g. a backup copy of places.sqlite or Chrome's Bookmarks file
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["all", "browsers"])]
+        path: Option<PathBuf>,
+
+        /// Format of the --path file (detected from its contents by default)
+        #[arg(
+            long,
+            requires = "path",
+            conflicts_with_all = ["all", "browsers"],
+            value_parser = ["chrome", "firefox", "edge"]
+        )]
+        browser_type: Option<String>,
+
+        /// Report what would be imported without writing to the database
+        #[arg(long)]
+        dry_run: bool,
+
+        /// How to handle URLs that already exist: skip, merge (union tags), or overwrite
+        #[arg(long, value_name = "STRATEGY", default_value = "skip", value_parser = ["skip", "merge", "overwrite"])]
+        on_duplicate: String,
+    },
+
+    /// Bookmark frequently visited pages from browser history
+    ImportHistory {
+        /// Browsers whose history to read (comma-separated: chrome,firefox,edge)
+        #[arg(short, long, value_delimiter = ',', required_unless_present = "path")]
+        browser: Vec<String>,
+
+        /// Read this history database or profile directory instead, e.g. a copy of
+        /// places.sqlite or Chrome's History file
+        #[arg(long, value_name = "FILE", conflicts_with = "browser")]
+        path: Option<PathBuf>,
+
+        /// Format of the --path file (detected from its contents by default)
+        #[arg(long, conflicts_with = "browser", value_parser = ["chrome", "firefox", "edge"])]
+        browser_type: Option<String>,
+
+        /// Only count visits in this period, e.g. 30d, 12h, 2w (a bare number is days)
+        #[arg(long, value_name = "DURATION")]
+        since: Option<String>,
+
+        /// Only pages visited at least this many times
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        min_visits: u64,
+
+        /// List the candidate pages without choosing or bookmarking them
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Export bookmarks to file
     Export {
-        /// File path to export to
+        /// File path to export to (format from extension: .html, .md, .org, .atom, .rss)
         file: String,
+
+        /// Write a feed of the most recent bookmarks instead of using the extension
+        #[arg(short, long, value_parser = ["atom", "rss"])]
+        format: Option<String>,
+
+        /// Number of bookmarks in a feed (default: 50)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+
+        /// Export only the bookmarks in a collection
+        #[arg(long, value_name = "NAME")]
+        collection: Option<String>,
+
+        /// Only bookmarks with any of these tags (comma-separated), e.g. `public`
+        #[arg(short, long, value_delimiter = ',')]
+        tag: Vec<String>,
+
+        /// Only bookmarks matching any of these keywords, as `print <keywords>` selects
+        #[arg(short, long, num_args = 1.., value_name = "KEYWORD")]
+        search: Vec<String>,
+
+        /// Only these indices and ranges (comma-separated), e.g. `1-20,42` or `-10..-1`
+        #[arg(long, value_delimiter = ',', allow_hyphen_values = true)]
+        ids: Vec<String>,
+    },
+
+    /// Publish bookmarks as a static HTML page (index.html), e.g. for GitHub Pages
+    Publish {
+        /// Output directory, created if missing
+        dir: PathBuf,
+
+        /// Only bookmarks with any of these tags (comma-separated); default: all
+        #[arg(short, long, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Page title (default: Bookmarks)
+        #[arg(long)]
+        title: Option<String>,
+    },
+
+    /// Write the whole database to a self-describing dump file (see `restore`)
+    Dump {
+        /// Dump file to write, e.g. backup.bukurs
+        file: PathBuf,
+
+        /// Include the undo history
+        #[arg(long)]
+        undo: bool,
+
+        /// Leave out the snapshot of the current config
+        #[arg(long)]
+        no_config: bool,
+    },
+
+    /// Restore a dump written by `dump`, keeping bookmark IDs
+    Restore {
+        /// Dump file to read
+        file: PathBuf,
+
+        /// Replace the bookmarks already in the database (asks first; not undoable)
+        #[arg(long)]
+        replace: bool,
+
+        /// Also write the dump's config snapshot, unless a config file already exists
+        #[arg(long)]
+        restore_config: bool,
     },
 
     /// Open bookmark(s) in browser
     Open {
-        /// Bookmark indices to open
+        /// Bookmark indices, ranges or keywords to open
+        /// Negative indices count back from the most recent: -1, -3..-1
         #[arg(num_args = 0..)]
         ids: Vec<String>,
     },
@@ -228,10 +671,16 @@ pub enum Commands {
     /// Start interactive shell
     Shell,
 
-    /// Edit bookmark in $EDITOR
+    /// Edit bookmark(s) in $EDITOR
     Edit {
-        /// Bookmark ID to edit (if not provided, creates a new bookmark)
-        id: Option<usize>,
+        /// Bookmark indices or ranges to edit (if not provided, creates a new bookmark)
+        /// Selecting several bookmarks edits them together in one buffer
+        #[arg(num_args = 0..)]
+        ids: Vec<String>,
+
+        /// Edit all bookmarks matching these keywords
+        #[arg(short, long, num_args = 1.., value_name = "KEYWORD", conflicts_with = "ids")]
+        search: Vec<String>,
     },
 
     /// Undo last operation(s)
@@ -240,372 +689,2243 @@ pub enum Commands {
         #[arg(default_value = "1")]
         count: usize,
     },
-}
 
-// ============================================================================
-// Main Command Dispatcher
-// ============================================================================
+    /// Fetch metadata for bookmarks added offline or whose fetch failed
+    FetchPending {
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
 
-use crate::commands::{
-    add::AddCommand,
-    delete::DeleteCommand,
-    edit::EditCommand,
-    import_export::{ExportCommand, ImportBrowsersCommand, ImportCommand},
-    lock_unlock::{LockCommand, UnlockCommand},
-    misc::{NoCommand, OpenCommand, ShellCommand, UndoCommand},
-    print::PrintCommand,
-    search::SearchCommand,
-    tag::TagCommand,
-    update::UpdateCommand,
-    AppContext, CommandEnum,
-};
+    /// Watch bookmarked pages for changes: `monitor add 42`, then `monitor changes`
+    Monitor {
+        #[command(subcommand)]
+        action: MonitorAction,
+    },
 
-pub fn handle_args(
-    cli: Cli,
-    db: &BukuDb,
-    db_path: &std::path::Path,
-    config: &bukurs::config::Config,
-) -> Result<()> {
-    let ctx = AppContext {
-        db,
-        config,
-        db_path,
-    };
+    /// Compare the databases under `profiles` in the config and copy bookmarks between them
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
 
-    let command = match cli.command {
-        Some(Commands::Add {
-            url,
-            tag,
-            title,
-            comment,
-            offline,
-        }) => CommandEnum::Add(AddCommand {
-            url,
-            tag,
-            title,
-            comment,
-            offline,
-        }),
+    /// Per-domain automatic tag rules (configured under `autotag` in the config file)
+    Autotag {
+        #[command(subcommand)]
+        action: AutotagAction,
+    },
 
-        Some(Commands::Update {
-            ids,
-            url,
-            tag,
-            title,
-            comment,
-            immutable,
-        }) => CommandEnum::Update(UpdateCommand {
-            ids,
-            url,
-            tag,
-            title,
-            comment,
-            immutable,
-        }),
+    /// Edit a bookmark's notes (description) as Markdown in $EDITOR
+    Note {
+        /// Bookmark index (negative indices count back from the most recent)
+        id: String,
+    },
 
-        Some(Commands::Delete {
-            ids,
-            force,
-            retain_order: _,
-        }) => CommandEnum::Delete(DeleteCommand { ids, force }),
+    /// List bookmarks related to one by shared tags, domain and title words
+    Similar {
+        /// Bookmark index (negative indices count back from the most recent)
+        id: String,
 
-        Some(Commands::Print { ids, columns: _ }) => CommandEnum::Print(PrintCommand {
-            ids,
-            limit: cli.limit,
-            format: cli.format,
-            nc: cli.nc,
-        }),
+        /// Number of bookmarks to list
+        #[arg(short = 'n', long, default_value_t = bukurs::similar::DEFAULT_SIMILAR_LIMIT)]
+        limit: usize,
+    },
 
-        Some(Commands::Search {
-            keywords,
-            all,
-            deep,
-            regex,
-            markers: _,
-        }) => CommandEnum::Search(SearchCommand {
-            keywords,
-            all,
-            deep,
-            regex,
-            limit: cli.limit,
-            format: cli.format,
-            nc: cli.nc,
-            open: cli.open,
-        }),
+    /// Fetch a bookmarked page and bookmark links picked from it, with the page's tags
+    Links {
+        /// Bookmark index (negative indices count back from the most recent)
+        id: String,
 
-        Some(Commands::Tag { tags }) => CommandEnum::Tag(TagCommand {
-            tags,
-            limit: cli.limit,
-            format: cli.format,
-            nc: cli.nc,
-            open: cli.open,
-        }),
+        /// Only links to other sites
+        #[arg(long)]
+        external: bool,
 
-        Some(Commands::Lock { iterations }) => CommandEnum::Lock(LockCommand { iterations }),
+        /// Print the links (URL and text, tab-separated) instead of picking ones to bookmark
+        #[arg(long)]
+        list: bool,
 
-        Some(Commands::Unlock { iterations }) => CommandEnum::Unlock(UnlockCommand { iterations }),
+        /// Comma-separated tags for the new bookmarks, besides the page's own
+        #[arg(short, long)]
+        tag: Option<Vec<String>>,
 
-        Some(Commands::Import { file }) => CommandEnum::Import(ImportCommand { file }),
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
 
-        Some(Commands::ImportBrowsers {
-            list,
-            all,
-            browsers,
-        }) => CommandEnum::ImportBrowsers(ImportBrowsersCommand {
-            list,
-            all,
-            browsers,
-        }),
+    /// Run one SQL statement against the database and print the rows (`-f` picks the format)
+    ///
+    /// Read-only unless `--allow-write` is given. Bookmarks are in the `bookmarks` table
+    /// (columns id, URL, metadata for the title, tags, desc, ...); `SELECT sql FROM
+    /// sqlite_master` shows the whole schema.
+    Query {
+        /// The statement, e.g. "SELECT URL, visits FROM bookmarks ORDER BY visits DESC"
+        sql: String,
 
-        Some(Commands::Export { file }) => CommandEnum::Export(ExportCommand { file }),
+        /// Also run statements that change the database (not undoable)
+        #[arg(long)]
+        allow_write: bool,
+    },
 
-        Some(Commands::Open { ids }) => CommandEnum::Open(OpenCommand { ids }),
+    /// List the domains bookmarks are on, with how many each has (most first);
+    /// `-n` keeps the top N
+    Domains,
 
-        Some(Commands::Shell) => CommandEnum::Shell(ShellCommand),
+    /// Print a bookmarked page's main text, reader-mode style, with its reading time
+    Read {
+        /// Bookmark index (negative indices count back from the most recent)
+        id: String,
 
-        Some(Commands::Edit { id }) => CommandEnum::Edit(EditCommand { id }),
+        /// Wrap the text at this many columns (default: terminal width, at most 100)
+        #[arg(short, long)]
+        width: Option<usize>,
 
-        Some(Commands::Undo { count }) => CommandEnum::Undo(UndoCommand { count }),
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
 
-        None => CommandEnum::No(NoCommand {
-            keywords: cli.keywords,
-            open: cli.open,
-            format: cli.format,
-            nc: cli.nc,
-        }),
-    };
+    /// List bookmarks added with `--expires` whose time has passed (hidden from `print`)
+    Expired {
+        #[command(subcommand)]
+        action: ExpiredAction,
+    },
+
+    /// Pin bookmarks so they stay at the top of `print` and the fuzzy picker
+    Pin {
+        /// Bookmark indices or ranges (negative indices count back from the most recent)
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<String>,
+    },
+
+    /// Unpin bookmarks
+    Unpin {
+        /// Bookmark indices or ranges (negative indices count back from the most recent)
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<String>,
+    },
+
+    /// Rate bookmarks from 1 to 5 stars (0 clears the rating)
+    Rate {
+        /// Bookmark index or range (negative indices count back from the most recent)
+        id: String,
+        /// Stars, 1 to 5; 0 clears the rating
+        rating: u8,
+    },
+
+    /// Star bookmarks: rate them 5, the top rating
+    Star {
+        /// Bookmark indices or ranges (negative indices count back from the most recent)
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<String>,
+    },
+
+    /// Clear the rating of bookmarks
+    Unstar {
+        /// Bookmark indices or ranges (negative indices count back from the most recent)
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<String>,
+    },
+
+    /// Move a bookmark before or after another one, for a manually ordered list
+    Move {
+        /// Bookmark index to move
+        id: String,
+
+        /// Put it just before this bookmark
+        #[arg(
+            long,
+            value_name = "ID",
+            required_unless_present = "after",
+            conflicts_with = "after"
+        )]
+        before: Option<String>,
+
+        /// Put it just after this bookmark
+        #[arg(long, value_name = "ID")]
+        after: Option<String>,
+    },
+
+    /// List the read-later queue (bookmarks tagged `unread`), oldest first
+    Later {
+        #[command(subcommand)]
+        action: Option<LaterAction>,
+    },
+
+    /// Create, inspect and check the config file (~/.config/bukurs/config.yml or --config)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Convert the database to or from SQLCipher (needs the `sqlcipher` feature)
+    Cipher {
+        #[command(subcommand)]
+        action: CipherAction,
+    },
+
+    /// Run the scheduled jobs from the `daemon` config section until stopped
+    #[command(args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+
+        /// Run in the background, detached from the terminal
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Inspect the audit log of changes (enable it with `audit_log` in the config)
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// Serve the database to editor integrations until interrupted
+    Serve {
+        /// Speak gRPC (service `bukurs.v1.Bookmarks`, see lib/proto/bukurs.proto);
+        /// needs a build with `--features grpc`
+        #[arg(long)]
+        grpc: bool,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: String,
+    },
+
+    /// Print changes to the bookmarks, from any process, as JSON lines until interrupted
+    Watch {
+        /// Start after event SEQ instead of now (0 for every event kept)
+        #[arg(long, value_name = "SEQ")]
+        since: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AutotagAction {
+    /// Apply the rules to existing bookmarks and report the changes
+    Apply {
+        /// Report what would change without writing to the database
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExpiredAction {
+    /// List expired bookmarks, soonest expired first
+    List,
+    /// Delete expired bookmarks (undoable with `undo`)
+    Delete,
+}
+
+#[derive(Subcommand)]
+pub enum LaterAction {
+    /// Open the oldest unread bookmark and mark it read
+    Pop,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Write a commented default config file
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print effective settings and where each comes from
+    Show,
+    /// Report unknown keys and invalid values in the config file
+    Validate,
+}
+
+#[derive(Subcommand)]
+pub enum CipherAction {
+    /// Encrypt a plaintext database in place; then set `database.encrypted: true`
+    Encrypt,
+    /// Turn an encrypted database back into plain SQLite
+    Decrypt,
+}
+
+#[derive(Subcommand)]
+pub enum SavedAction {
+    /// Print every saved search
+    List,
+    /// Repeat a saved search (the global --limit, --format, --open and --nc apply)
+    Run { name: String },
+    /// Forget a saved search
+    Delete { name: String },
+}
+
+/// Bulk tag changes; the touched bookmarks' tags are deduplicated and sorted,
+/// and one `undo` reverts the whole change
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// Add comma-separated tags to the selected bookmarks
+    Add {
+        tags: String,
+        /// Bookmarks to tag: indices, ranges (1-50), * or search keywords
+        #[arg(long, value_name = "SELECTOR", num_args = 1.., required = true)]
+        to: Vec<String>,
+        /// Lowercase every tag of the bookmarks changed
+        #[arg(long)]
+        lowercase: bool,
+    },
+    /// Remove comma-separated tags from the selected bookmarks
+    Remove {
+        tags: String,
+        /// Bookmarks to untag: indices, ranges, * or search keywords
+        #[arg(long, value_name = "SELECTOR", num_args = 1.., required = true)]
+        from: Vec<String>,
+        /// Lowercase every tag of the bookmarks changed (and match case-insensitively)
+        #[arg(long)]
+        lowercase: bool,
+    },
+    /// Replace the tags of the selected bookmarks with comma-separated tags
+    Set {
+        tags: String,
+        /// Bookmarks to retag: indices, ranges, * or search keywords
+        #[arg(long, value_name = "SELECTOR", num_args = 1.., required = true)]
+        on: Vec<String>,
+        /// Lowercase the tags
+        #[arg(long)]
+        lowercase: bool,
+    },
+    /// Rewrite every bookmark's tags consistently, merging spellings of one tag
+    /// (all three fixes when none is chosen)
+    Normalize {
+        /// Lowercase tags (Rust and rust become one)
+        #[arg(long)]
+        lowercase: bool,
+        /// Strip whitespace around tags
+        #[arg(long)]
+        trim: bool,
+        /// Drop a tag repeated on one bookmark
+        #[arg(long)]
+        dedupe: bool,
+        /// Report the merges without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rewrite tags by a CSV file of `old_tag,new_tag` rows (an empty new tag
+    /// drops the tag) and `url_pattern,tag` rows (`*` is a wildcard), all at once
+    ApplyMap {
+        /// The mapping file
+        file: PathBuf,
+        /// Print each bookmark's old and new tags without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasAction {
+    /// Give bookmark ID the alias NAME, replacing any alias it had
+    Set { name: String, id: usize },
+    /// Take an alias away from its bookmark
+    Remove { name: String },
+    /// Print every alias with its bookmark
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum CollectionAction {
+    /// Define a collection, replacing one with the same name
+    ///
+    /// QUERY combines tag:NAME, url:TEXT, title:TEXT, desc:TEXT, saved:NAME
+    /// and plain words with AND, OR, NOT and parentheses, e.g.
+    /// 'tag:toread AND NOT tag:done'
+    Create { name: String, query: String },
+    /// Print every collection with its query
+    List,
+    /// Forget a collection; its bookmarks stay
+    Delete { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum MonitorAction {
+    /// Remember bookmark ID's page content, to notice when it changes
+    Add {
+        id: usize,
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+    /// Stop watching bookmark ID's page
+    Remove { id: usize },
+    /// Print every monitored bookmark with the result of its last check
+    List,
+    /// Fetch every monitored page now (the daemon's `monitor` job does this on a schedule)
+    Check {
+        /// Fetch timeout in seconds (overrides `fetch.timeout_secs` in the config)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+    },
+    /// Print the bookmarks whose page changed since their changes were last cleared
+    Changes {
+        /// Mark the printed changes as seen
+        #[arg(long)]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// Print every profile with its database file
+    List,
+    /// Print the URLs only one profile has, and the shared ones tagged differently
+    Diff { left: String, right: String },
+    /// Copy bookmarks of this database (see --profile) to another profile, in one transaction
+    Copy {
+        /// Bookmark indices or ranges (e.g., 1-5)
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<String>,
+
+        /// Profile to copy them to
+        #[arg(long, value_name = "NAME")]
+        to: String,
+
+        /// Delete them here once the copy has committed, keeping any the other
+        /// profile skipped
+        #[arg(long = "move")]
+        remove: bool,
+
+        /// How to handle URLs the other profile has: skip, merge (union tags), or overwrite
+        #[arg(long, value_name = "STRATEGY", default_value = "skip", value_parser = ["skip", "merge", "overwrite"])]
+        on_duplicate: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DaemonAction {
+    /// Show whether a daemon is running, with each job's last result and next run
+    Status,
+    /// Ask the running daemon to exit
+    Stop,
+}
+
+#[derive(Subcommand)]
+pub enum AuditAction {
+    /// Print the most recent entries
+    Tail {
+        /// Number of entries to print
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+    },
+    /// Print entries matching all of the given criteria
+    Search {
+        /// Operation: add, update, delete, import, fetch or undo
+        #[arg(long)]
+        operation: Option<String>,
+        /// Bookmark index the operation touched
+        #[arg(long)]
+        id: Option<usize>,
+        /// Where the change came from: cli, shell, server, plugin, python or ffi
+        #[arg(long)]
+        source: Option<String>,
+        /// Only entries from the last DAYS days
+        #[arg(long, value_name = "DAYS")]
+        days: Option<u64>,
+    },
+}
+
+// ============================================================================
+// Main Command Dispatcher
+// ============================================================================
+
+use crate::commands::{
+    add::AddCommand,
+    alias::{AliasCommand, AliasOp},
+    audit::{AuditCommand, AuditOp},
+    autotag::AutotagCommand,
+    bookmarklet::{AddUrlCommand, BookmarkletCommand},
+    cipher::{CipherCommand, CipherOp},
+    collection::{CollectionCommand, CollectionOp},
+    config::{ConfigCommand, ConfigOp},
+    daemon::{DaemonCommand, DaemonOp},
+    delete::DeleteCommand,
+    domains::DomainsCommand,
+    edit::EditCommand,
+    expired::ExpiredCommand,
+    fetch_pending::FetchPendingCommand,
+    import_export::{
+        DumpCommand, ExportCommand, ImportBrowsersCommand, ImportCommand, ImportHistoryCommand,
+        PublishCommand, RestoreCommand,
+    },
+    later::LaterCommand,
+    links::LinksCommand,
+    lock_unlock::{DecryptCommand, EncryptCommand, LockCommand, UnlockCommand},
+    misc::{NoCommand, OpenCommand, ShellCommand, UndoCommand},
+    monitor::{MonitorCommand, MonitorOp},
+    note::NoteCommand,
+    pin::{MoveCommand, PinCommand},
+    print::PrintCommand,
+    profile::{ProfileCommand, ProfileOp},
+    query::QueryCommand,
+    rating::RateCommand,
+    read::ReadCommand,
+    saved::{SavedCommand, SavedOp},
+    search::SearchCommand,
+    serve::ServeCommand,
+    similar::SimilarCommand,
+    tag::{ApplyTagMapCommand, NormalizeTagsCommand, RetagCommand, TagCommand},
+    update::UpdateCommand,
+    watch::WatchCommand,
+    AppContext, CommandEnum, OutputMode,
+};
+use crate::prompt::Interaction;
+
+/// The `config` subcommand, which runs without opening the database
+pub fn config_command(cli: &Cli) -> Option<ConfigCommand> {
+    let Some(Commands::Config { action }) = &cli.command else {
+        return None;
+    };
+    let op = match action {
+        ConfigAction::Init { force } => ConfigOp::Init { force: *force },
+        ConfigAction::Show => ConfigOp::Show,
+        ConfigAction::Validate => ConfigOp::Validate,
+    };
+    Some(ConfigCommand {
+        op,
+        path: cli.config.clone(),
+        db: cli.db.clone(),
+        profile: cli.profile.clone(),
+        nc: cli.nc,
+        online: cli.online,
+        picker: picker_kind(cli),
+        cacert: cli.cacert.clone(),
+    })
+}
+
+/// The `cipher` subcommand, which runs before the database is opened
+pub fn cipher_command(cli: &Cli) -> Option<CipherCommand> {
+    let Some(Commands::Cipher { action }) = &cli.command else {
+        return None;
+    };
+    let op = match action {
+        CipherAction::Encrypt => CipherOp::Encrypt,
+        CipherAction::Decrypt => CipherOp::Decrypt,
+    };
+    Some(CipherCommand { op })
+}
+
+/// The picker chosen with `--picker`, overriding `picker.kind`
+pub fn picker_kind(cli: &Cli) -> Option<PickerKind> {
+    cli.picker.as_deref().and_then(PickerKind::from_string)
+}
+
+/// Whether the command changes the database, so it holds the database lock while it runs
+///
+/// Commands that fetch pages or wait on an editor or picker before writing
+/// (`add`, `edit`, `note`, `update`, `links`, `import-history`, `fetch-pending`,
+/// `monitor add|check`) take the lock themselves around their writes instead.
+/// The shell and the daemon run many commands over a long time; they take the
+/// lock for each change.
+pub fn mutates_database(cli: &Cli) -> bool {
+    match &cli.command {
+        Some(Commands::Autotag { action }) => {
+            matches!(action, AutotagAction::Apply { dry_run: false })
+        }
+        Some(Commands::Later { action }) => action.is_some(),
+        Some(Commands::Alias { action }) => !matches!(action, AliasAction::List),
+        Some(Commands::Profile { action }) => {
+            matches!(action, ProfileAction::Copy { remove: true, .. })
+        }
+        Some(Commands::Tag { action, .. }) => match action {
+            Some(TagAction::Normalize { dry_run, .. } | TagAction::ApplyMap { dry_run, .. }) => {
+                !dry_run
+            }
+            action => action.is_some(),
+        },
+        Some(Commands::Expired { action }) => matches!(action, ExpiredAction::Delete),
+        Some(Commands::Import { dry_run, .. }) => !dry_run,
+        Some(Commands::Query { allow_write, .. }) => *allow_write,
+        Some(Commands::ImportBrowsers { dry_run, .. }) => !dry_run,
+        Some(Commands::Collection { action }) => !matches!(action, CollectionAction::List),
+        Some(Commands::Monitor { action }) => matches!(
+            action,
+            MonitorAction::Remove { .. } | MonitorAction::Changes { clear: true }
+        ),
+        Some(
+            Commands::Delete { .. }
+            | Commands::Lock { .. }
+            | Commands::Unlock { .. }
+            | Commands::Encrypt { .. }
+            | Commands::Decrypt { .. }
+            | Commands::Undo { .. }
+            | Commands::Pin { .. }
+            | Commands::Unpin { .. }
+            | Commands::Rate { .. }
+            | Commands::Star { .. }
+            | Commands::Unstar { .. }
+            | Commands::Move { .. }
+            | Commands::Restore { .. },
+        ) => true,
+        _ => false,
+    }
+}
+
+/// The command for `tag add|remove|set|normalize|apply-map`
+fn tag_action_command(action: TagAction) -> CommandEnum {
+    let (op, tags, selector, lowercase) = match action {
+        TagAction::Normalize {
+            lowercase,
+            trim,
+            dedupe,
+            dry_run,
+        } => {
+            let normalization = if lowercase || trim || dedupe {
+                TagNormalization {
+                    lowercase,
+                    trim,
+                    dedupe,
+                }
+            } else {
+                TagNormalization::all()
+            };
+            return CommandEnum::NormalizeTags(NormalizeTagsCommand {
+                normalization,
+                dry_run,
+            });
+        }
+        TagAction::ApplyMap { file, dry_run } => {
+            return CommandEnum::ApplyTagMap(ApplyTagMapCommand {
+                path: file,
+                dry_run,
+            });
+        }
+        TagAction::Add {
+            tags,
+            to,
+            lowercase,
+        } => (TagSetOp::Add, tags, to, lowercase),
+        TagAction::Remove {
+            tags,
+            from,
+            lowercase,
+        } => (TagSetOp::Remove, tags, from, lowercase),
+        TagAction::Set {
+            tags,
+            on,
+            lowercase,
+        } => (TagSetOp::Set, tags, on, lowercase),
+    };
+    CommandEnum::Retag(RetagCommand {
+        op,
+        tags: parse_tags(&tags),
+        // `--from "search terms"` is one argument holding several keywords
+        selector: selector
+            .iter()
+            .flat_map(|s| s.split_whitespace())
+            .map(String::from)
+            .collect(),
+        lowercase,
+    })
+}
+
+pub fn handle_args(
+    cli: Cli,
+    db: &BukuDb,
+    db_path: &std::path::Path,
+    config: &bukurs::config::Config,
+) -> Result<()> {
+    let ctx = AppContext {
+        db,
+        config,
+        db_path,
+        interaction: Interaction::new(cli.yes, cli.no_input),
+        output: OutputMode::from_flag(cli.output.as_deref()),
+    };
+    let nc = cli.nc || !config.color;
+
+    let command = match cli.command {
+        Some(Commands::Add {
+            url,
+            tag,
+            title,
+            comment,
+            offline,
+            no_suggest,
+            timeout,
+            later,
+            expires,
+        }) => CommandEnum::Add(AddCommand {
+            url,
+            tag,
+            title,
+            comment,
+            offline,
+            no_suggest,
+            timeout,
+            later,
+            expires,
+        }),
+
+        Some(Commands::AddUrl { link, offline }) => {
+            CommandEnum::AddUrl(AddUrlCommand { link, offline })
+        }
+
+        Some(Commands::Bookmarklet { tags }) => {
+            CommandEnum::Bookmarklet(BookmarkletCommand { tags })
+        }
+
+        Some(Commands::Update {
+            ids,
+            url,
+            tag,
+            title,
+            comment,
+            immutable,
+            pick,
+            timeout,
+        }) => CommandEnum::Update(UpdateCommand {
+            ids,
+            url,
+            tag,
+            title,
+            comment,
+            immutable,
+            pick,
+            timeout,
+        }),
+
+        Some(Commands::Delete {
+            ids,
+            force,
+            retain_order: _,
+            pick,
+        }) => CommandEnum::Delete(DeleteCommand { ids, force, pick }),
+
+        Some(Commands::Print {
+            ids,
+            columns: _,
+            stats,
+            collection,
+            min_rating,
+            by_rating,
+            added_after,
+            added_within,
+            not_visited_since,
+        }) => CommandEnum::Print(PrintCommand {
+            ids,
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+            stats,
+            collection,
+            min_rating,
+            by_rating,
+            time: TimeFilter {
+                added_after,
+                added_within,
+                not_visited_since,
+            },
+        }),
+
+        Some(Commands::Search {
+            keywords,
+            all,
+            deep,
+            regex,
+            markers: _,
+            notes,
+            exec,
+            exec_batch,
+            jobs,
+            open,
+            within_last,
+            rank,
+            min_rating,
+            lang,
+            added_after,
+            added_within,
+            not_visited_since,
+            fuzzy,
+            save,
+        }) => CommandEnum::Search(SearchCommand {
+            keywords,
+            all,
+            deep,
+            regex,
+            notes,
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+            open: open || cli.open,
+            exec,
+            exec_batch,
+            jobs,
+            within_last,
+            rank,
+            fuzzy,
+            save,
+            min_rating,
+            lang,
+            time: TimeFilter {
+                added_after,
+                added_within,
+                not_visited_since,
+            },
+        }),
+
+        Some(Commands::Saved { action }) => CommandEnum::Saved(SavedCommand {
+            op: match action {
+                SavedAction::List => SavedOp::List,
+                SavedAction::Run { name } => SavedOp::Run { name },
+                SavedAction::Delete { name } => SavedOp::Delete { name },
+            },
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+            open: cli.open,
+        }),
+
+        Some(Commands::Alias { action }) => CommandEnum::Alias(AliasCommand {
+            op: match action {
+                AliasAction::Set { name, id } => AliasOp::Set { name, id },
+                AliasAction::Remove { name } => AliasOp::Remove { name },
+                AliasAction::List => AliasOp::List,
+            },
+        }),
+
+        Some(Commands::Collection { action }) => CommandEnum::Collection(CollectionCommand {
+            op: match action {
+                CollectionAction::Create { name, query } => CollectionOp::Create { name, query },
+                CollectionAction::List => CollectionOp::List,
+                CollectionAction::Delete { name } => CollectionOp::Delete { name },
+            },
+        }),
+
+        Some(Commands::Tag {
+            action: Some(action),
+            ..
+        }) => tag_action_command(action),
+
+        Some(Commands::Tag { tags, .. }) => CommandEnum::Tag(TagCommand {
+            tags,
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+            open: cli.open,
+        }),
+
+        Some(Commands::Lock {
+            iterations,
+            kdf,
+            memory,
+            passes,
+        }) => CommandEnum::Lock(LockCommand {
+            iterations,
+            kdf,
+            memory,
+            passes,
+        }),
+
+        Some(Commands::Unlock { iterations }) => CommandEnum::Unlock(UnlockCommand { iterations }),
+
+        Some(Commands::Encrypt { tag }) => CommandEnum::Encrypt(EncryptCommand { tag }),
+
+        Some(Commands::Decrypt { tag }) => CommandEnum::Decrypt(DecryptCommand { tag }),
+
+        Some(Commands::Import {
+            file,
+            dry_run,
+            parallel,
+            on_duplicate,
+            report,
+        }) => CommandEnum::Import(ImportCommand {
+            file,
+            dry_run,
+            parallel,
+            on_duplicate: DuplicateStrategy::from_string(&on_duplicate).unwrap_or_default(),
+            format: cli.format,
+            report,
+        }),
+
+        Some(Commands::ImportBrowsers {
+            list,
+            all,
+            browsers,
+            path,
+            browser_type,
+            dry_run,
+            on_duplicate,
+        }) => CommandEnum::ImportBrowsers(ImportBrowsersCommand {
+            list,
+            all,
+            browsers,
+            path,
+            browser_type: browser_type.as_deref().and_then(BrowserType::from_string),
+            dry_run,
+            on_duplicate: DuplicateStrategy::from_string(&on_duplicate).unwrap_or_default(),
+            format: cli.format,
+        }),
+
+        Some(Commands::ImportHistory {
+            browser,
+            path,
+            browser_type,
+            since,
+            min_visits,
+            dry_run,
+        }) => CommandEnum::ImportHistory(ImportHistoryCommand {
+            browsers: browser,
+            path,
+            browser_type: browser_type.as_deref().and_then(BrowserType::from_string),
+            since,
+            min_visits,
+            dry_run,
+        }),
+
+        Some(Commands::Export {
+            file,
+            format,
+            limit,
+            collection,
+            tag,
+            search,
+            ids,
+        }) => CommandEnum::Export(ExportCommand {
+            file,
+            feed: format.as_deref().and_then(FeedFormat::from_string),
+            limit,
+            collection,
+            filter: BookmarkFilter {
+                tags: tag,
+                search,
+                ids,
+            },
+        }),
+
+        Some(Commands::Publish { dir, tags, title }) => {
+            CommandEnum::Publish(PublishCommand { dir, tags, title })
+        }
+
+        Some(Commands::Dump {
+            file,
+            undo,
+            no_config,
+        }) => CommandEnum::Dump(DumpCommand {
+            file,
+            undo,
+            no_config,
+        }),
+
+        Some(Commands::Restore {
+            file,
+            replace,
+            restore_config,
+        }) => CommandEnum::Restore(RestoreCommand {
+            file,
+            replace,
+            restore_config,
+        }),
+
+        Some(Commands::Open { ids }) => CommandEnum::Open(OpenCommand { ids }),
+
+        Some(Commands::Shell) => CommandEnum::Shell(ShellCommand),
+
+        Some(Commands::Edit { ids, search }) => CommandEnum::Edit(EditCommand { ids, search }),
+
+        Some(Commands::Undo { count }) => CommandEnum::Undo(UndoCommand { count }),
+
+        Some(Commands::FetchPending { timeout }) => {
+            CommandEnum::FetchPending(FetchPendingCommand { timeout })
+        }
+
+        Some(Commands::Monitor { action }) => {
+            let (op, timeout) = match action {
+                MonitorAction::Add { id, timeout } => (MonitorOp::Add { id }, timeout),
+                MonitorAction::Remove { id } => (MonitorOp::Remove { id }, None),
+                MonitorAction::List => (MonitorOp::List, None),
+                MonitorAction::Check { timeout } => (MonitorOp::Check, timeout),
+                MonitorAction::Changes { clear } => (MonitorOp::Changes { clear }, None),
+            };
+            CommandEnum::Monitor(MonitorCommand { op, timeout })
+        }
+
+        Some(Commands::Profile { action }) => CommandEnum::Profile(ProfileCommand {
+            op: match action {
+                ProfileAction::List => ProfileOp::List,
+                ProfileAction::Diff { left, right } => ProfileOp::Diff { left, right },
+                ProfileAction::Copy {
+                    ids,
+                    to,
+                    remove,
+                    on_duplicate,
+                } => ProfileOp::Copy {
+                    ids,
+                    to,
+                    on_duplicate: DuplicateStrategy::from_string(&on_duplicate).unwrap_or_default(),
+                    remove,
+                },
+            },
+        }),
+
+        Some(Commands::Autotag {
+            action: AutotagAction::Apply { dry_run },
+        }) => CommandEnum::Autotag(AutotagCommand { dry_run }),
+
+        Some(Commands::Note { id }) => CommandEnum::Note(NoteCommand { id }),
+
+        Some(Commands::Expired { action }) => CommandEnum::Expired(ExpiredCommand {
+            delete: matches!(action, ExpiredAction::Delete),
+        }),
+
+        Some(Commands::Pin { ids }) => CommandEnum::Pin(PinCommand { ids, pinned: true }),
+
+        Some(Commands::Unpin { ids }) => CommandEnum::Pin(PinCommand { ids, pinned: false }),
+
+        Some(Commands::Rate { id, rating }) => CommandEnum::Rate(RateCommand {
+            ids: vec![id],
+            rating,
+        }),
+
+        Some(Commands::Star { ids }) => CommandEnum::Rate(RateCommand {
+            ids,
+            rating: MAX_RATING,
+        }),
+
+        Some(Commands::Unstar { ids }) => CommandEnum::Rate(RateCommand { ids, rating: 0 }),
+
+        Some(Commands::Move { id, before, after }) => {
+            CommandEnum::Move(MoveCommand { id, before, after })
+        }
+
+        Some(Commands::Similar { id, limit }) => CommandEnum::Similar(SimilarCommand { id, limit }),
+
+        Some(Commands::Links {
+            id,
+            external,
+            list,
+            tag,
+            timeout,
+        }) => CommandEnum::Links(LinksCommand {
+            id,
+            external,
+            list,
+            tag,
+            timeout,
+        }),
+
+        Some(Commands::Query { sql, allow_write }) => CommandEnum::Query(QueryCommand {
+            sql,
+            allow_write,
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+        }),
+
+        Some(Commands::Domains) => CommandEnum::Domains(DomainsCommand {
+            limit: cli.limit,
+            format: cli.format,
+            nc,
+        }),
+
+        Some(Commands::Read { id, width, timeout }) => CommandEnum::Read(ReadCommand {
+            id,
+            width,
+            timeout,
+            nc,
+        }),
+
+        Some(Commands::Later { action }) => CommandEnum::Later(LaterCommand {
+            pop: matches!(action, Some(LaterAction::Pop)),
+        }),
+
+        Some(Commands::Daemon { action, detach }) => CommandEnum::Daemon(DaemonCommand {
+            op: match action {
+                None => DaemonOp::Run { detach },
+                Some(DaemonAction::Status) => DaemonOp::Status,
+                Some(DaemonAction::Stop) => DaemonOp::Stop,
+            },
+        }),
+
+        Some(Commands::Audit { action }) => CommandEnum::Audit(AuditCommand {
+            op: match action {
+                AuditAction::Tail { count } => AuditOp::Tail { count },
+                AuditAction::Search {
+                    operation,
+                    id,
+                    source,
+                    days,
+                } => AuditOp::Search {
+                    operation,
+                    id,
+                    source,
+                    days,
+                },
+            },
+        }),
+
+        Some(Commands::Serve { grpc, addr }) => CommandEnum::Serve(ServeCommand { grpc, addr }),
+
+        Some(Commands::Watch { since }) => CommandEnum::Watch(WatchCommand { since }),
+
+        Some(Commands::Config { .. }) => {
+            return Err("config is handled before the database is opened".into())
+        }
+
+        Some(Commands::Cipher { .. }) => {
+            return Err("cipher is handled before the database is opened".into())
+        }
+
+        None => CommandEnum::No(NoCommand {
+            keywords: cli.keywords,
+            open: cli.open,
+            format: cli.format,
+            nc,
+        }),
+    };
+
+    command.execute(&ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use rstest::rstest;
+
+    // Helper to parse CLI arguments from a string
+    fn parse_args(args: &str) -> std::result::Result<Cli, clap::Error> {
+        let args_vec: Vec<&str> = args.split_whitespace().collect();
+        Cli::try_parse_from(std::iter::once("buku").chain(args_vec))
+    }
+
+    // Helper to expect successful parsing
+    fn parse_args_ok(args: &str) -> Cli {
+        parse_args(args).expect("Failed to parse valid arguments")
+    }
+
+    #[test]
+    fn test_no_args() {
+        let cli = parse_args_ok("");
+        assert!(!cli.version);
+        assert_eq!(cli.db, None);
+        assert!(!cli.nc);
+        assert!(!cli.debug);
+        assert_eq!(cli.format, None);
+        assert!(!cli.open);
+        assert_eq!(cli.limit, None);
+        assert!(cli.keywords.is_empty());
+        assert!(cli.command.is_none());
+    }
+
+    #[rstest]
+    #[case("--version", true)]
+    #[case("-v", true)]
+    fn test_version_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.version, expected);
+    }
+
+    #[rstest]
+    #[case("--db /path/to/db.db", Some("/path/to/db.db"))]
+    #[case("--db custom.db", Some("custom.db"))]
+    fn test_db_path(#[case] args: &str, #[case] expected: Option<&str>) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.db.as_ref().map(|p| p.to_str().unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("--nc", true)]
+    #[case("", false)]
+    fn test_no_color_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.nc, expected);
+    }
+
+    #[rstest]
+    #[case("--picker external rust", Some(PickerKind::External))]
+    #[case("search rust --picker builtin", Some(PickerKind::Builtin))]
+    #[case("rust", None)]
+    fn test_picker_flag(#[case] args: &str, #[case] expected: Option<PickerKind>) {
+        assert_eq!(picker_kind(&parse_args_ok(args)), expected);
+    }
+
+    #[test]
+    fn test_picker_flag_rejects_unknown() {
+        assert!(Cli::try_parse_from(["bukurs", "--picker", "fzf"]).is_err());
+    }
+
+    #[test]
+    fn test_cacert_flag_is_global() {
+        let cli = parse_args_ok("fetch-pending --cacert /etc/ssl/corp.pem");
+        assert_eq!(cli.cacert, Some(PathBuf::from("/etc/ssl/corp.pem")));
+    }
+
+    #[rstest]
+    #[case("--raw-tags -f json", true)]
+    #[case("-f json", false)]
+    fn test_raw_tags_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.raw_tags, expected);
+    }
+
+    #[rstest]
+    #[case("--debug", true)]
+    #[case("-g", true)]
+    #[case("", false)]
+    fn test_debug_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.debug, expected);
+    }
+
+    #[rstest]
+    #[case("--format json", Some("json"))]
+    #[case("-f markdown", Some("markdown"))]
+    #[case("-f template:{id}\\t{url}", Some("template:{id}\\t{url}"))]
+    #[case("", None)]
+    fn test_format_option(#[case] args: &str, #[case] expected: Option<&str>) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.format.as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("--open", true)]
+    #[case("-o", true)]
+    #[case("", false)]
+    fn test_open_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.open, expected);
+    }
+
+    #[rstest]
+    #[case("--limit 10", Some(10))]
+    #[case("-n 5", Some(5))]
+    #[case("", None)]
+    fn test_limit_option(#[case] args: &str, #[case] expected: Option<usize>) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.limit, expected);
+    }
+
+    #[rstest]
+    #[case("rust programming", vec!["rust", "programming"])]
+    #[case("test", vec!["test"])]
+    #[case("", vec![])]
+    fn test_search_keywords(#[case] args: &str, #[case] expected: Vec<&str>) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.keywords, expected);
+    }
+
+    // Add command tests
+    #[rstest]
+    #[case("add https://example.com")]
+    #[case("add https://rust-lang.org --title Rust")]
+    #[case("add https://test.com --tag rust,programming")]
+    #[case("add https://test.com --comment Description")]
+    #[case("add https://test.com --offline")]
+    fn test_add_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(cli.command, Some(Commands::Add { .. })));
+    }
+
+    #[test]
+    fn test_add_command_with_all_options() {
+        let cli = parse_args_ok(
+            "add https://example.com --title Test --tag rust --tag test --comment Description --offline",
+        );
+        match cli.command {
+            Some(Commands::Add {
+                url,
+                tag,
+                title,
+                comment,
+                offline,
+                no_suggest,
+                timeout,
+                later,
+                expires,
+            }) => {
+                assert_eq!(url, "https://example.com");
+                assert_eq!(title, Some("Test".to_string()));
+                assert_eq!(tag, Some(vec!["rust".to_string(), "test".to_string()]));
+                assert_eq!(comment, Some("Description".to_string()));
+                assert!(offline);
+                assert!(!no_suggest);
+                assert_eq!(timeout, None);
+                assert!(!later);
+                assert_eq!(expires, None);
+            }
+            _ => panic!("Expected Add command"),
+        }
+    }
 
-    command.execute(&ctx)
-}
+    // Update command tests
+    #[rstest]
+    #[case("update 1 --url https://new.com")]
+    #[case("update 42 --title NewTitle")]
+    #[case("update 5 --tag updated")]
+    #[case("update 10 --comment UpdatedDescription")]
+    #[case("update 3 --immutable 1")]
+    fn test_update_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(cli.command, Some(Commands::Update { .. })));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::Parser;
-    use rstest::rstest;
+    #[test]
+    fn test_update_command_details() {
+        let cli = parse_args_ok("update 42");
+        assert!(matches!(cli.command, Some(Commands::Update { .. })));
 
-    // Helper to parse CLI arguments from a string
-    fn parse_args(args: &str) -> std::result::Result<Cli, clap::Error> {
-        let args_vec: Vec<&str> = args.split_whitespace().collect();
-        Cli::try_parse_from(std::iter::once("buku").chain(args_vec))
+        // Verify the ids are parsed correctly
+        if let Some(Commands::Update { ids, .. }) = cli.command {
+            assert_eq!(ids, vec!["42".to_string()]);
+        } else {
+            panic!("Expected Update command");
+        }
     }
 
-    // Helper to expect successful parsing
-    fn parse_args_ok(args: &str) -> Cli {
-        parse_args(args).expect("Failed to parse valid arguments")
+    #[rstest]
+    #[case("update 1-10 --tag +archived", &["1-10"], Some("+archived"), None)]
+    #[case("update * --immutable 1", &["*"], None, Some(1))]
+    #[case("update 10..-1 2 -t x", &["10..-1", "2"], Some("x"), None)]
+    #[case("update 3 --tag -archived", &["3"], Some("-archived"), None)]
+    fn test_update_selectors(
+        #[case] args: &str,
+        #[case] expected_ids: &[&str],
+        #[case] expected_tag: Option<&str>,
+        #[case] expected_immutable: Option<u8>,
+    ) {
+        let Some(Commands::Update {
+            ids,
+            tag,
+            immutable,
+            ..
+        }) = parse_args_ok(args).command
+        else {
+            panic!("Expected Update command");
+        };
+        assert_eq!(ids, expected_ids);
+        assert_eq!(tag, expected_tag.map(|t| vec![t.to_string()]));
+        assert_eq!(immutable, expected_immutable);
+    }
+
+    // Delete command tests
+    #[rstest]
+    #[case("delete 1")]
+    #[case("delete 1 2 3")]
+    #[case("delete 1-5")]
+    #[case("delete --force 1")]
+    #[case("delete --retain-order 1 2")]
+    fn test_delete_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(cli.command, Some(Commands::Delete { .. })));
     }
 
     #[test]
-    fn test_no_args() {
-        let cli = parse_args_ok("");
-        assert!(!cli.version);
-        assert_eq!(cli.db, None);
-        assert!(!cli.nc);
-        assert!(!cli.debug);
-        assert_eq!(cli.format, None);
-        assert!(!cli.open);
-        assert_eq!(cli.limit, None);
-        assert!(cli.keywords.is_empty());
-        assert!(cli.command.is_none());
+    fn test_delete_command_with_force() {
+        let cli = parse_args_ok("delete --force 1 2 3");
+        match cli.command {
+            Some(Commands::Delete { ids, force, .. }) => {
+                assert_eq!(ids, vec!["1", "2", "3"]);
+                assert!(force);
+            }
+            _ => panic!("Expected Delete command"),
+        }
     }
 
     #[rstest]
-    #[case("--version", true)]
-    #[case("-v", true)]
-    fn test_version_flag(#[case] args: &str, #[case] expected: bool) {
+    #[case("delete 3 --force --output json", OutputMode::Json)]
+    #[case("--output json import bookmarks.html", OutputMode::Json)]
+    #[case("update 1 --output text", OutputMode::Text)]
+    #[case("add https://example.com", OutputMode::Text)]
+    fn test_output_option(#[case] args: &str, #[case] expected: OutputMode) {
         let cli = parse_args_ok(args);
-        assert_eq!(cli.version, expected);
+        assert_eq!(OutputMode::from_flag(cli.output.as_deref()), expected);
+    }
+
+    #[test]
+    fn test_output_option_rejects_unknown_format() {
+        assert!(parse_args("delete 3 --output xml").is_err());
+    }
+
+    #[rstest]
+    #[case("print -1", "print -- -1")]
+    #[case("open -3..-1", "open -- -3..-1")]
+    #[case("delete -1 --force", "delete --force -- -1")]
+    #[case("update -2 --tag -archived", "update --tag -archived -- -2")]
+    #[case("update -1 -t -2024", "update -t -2024 -- -1")]
+    #[case("edit -3..-1", "edit -- -3..-1")]
+    #[case("print 1-5", "print 1-5")]
+    #[case("print -- -1", "print -- -1")]
+    #[case("search -1", "search -1")]
+    #[case(
+        "search rust -site:reddit.com -a",
+        "search rust -a -- -site:reddit.com"
+    )]
+    #[case("search site:github.com rust", "search site:github.com rust")]
+    fn test_escape_relative_indices(#[case] args: &str, #[case] expected: &str) {
+        let escaped = escape_relative_indices(args.split_whitespace().map(OsString::from));
+        let expected: Vec<OsString> = expected.split_whitespace().map(OsString::from).collect();
+        assert_eq!(escaped, expected);
+    }
+
+    #[test]
+    fn test_relative_indices_parse() {
+        let args = ["bukurs", "delete", "-3..-1", "-f"].map(OsString::from);
+        let cli = Cli::try_parse_from(escape_relative_indices(args)).unwrap();
+        match cli.command {
+            Some(Commands::Delete { ids, force, .. }) => {
+                assert_eq!(ids, vec!["-3..-1"]);
+                assert!(force);
+            }
+            _ => panic!("Expected Delete command"),
+        }
+    }
+
+    // Print command tests
+    #[rstest]
+    #[case("print")]
+    #[case("print 1")]
+    #[case("print 1 2 3")]
+    #[case("print --columns 5")]
+    fn test_print_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(cli.command, Some(Commands::Print { .. })));
+    }
+
+    // Search command tests
+    #[rstest]
+    #[case("search rust")]
+    #[case("search rust programming")]
+    #[case("search --all keyword1 keyword2")]
+    #[case("search --deep test")]
+    #[case("search --regex '^http'")]
+    #[case("search --markers tag:test")]
+    fn test_search_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(cli.command, Some(Commands::Search { .. })));
+    }
+
+    #[test]
+    fn test_search_command_all_flag() {
+        let cli = parse_args_ok("search --all rust web");
+        match cli.command {
+            Some(Commands::Search { keywords, all, .. }) => {
+                assert_eq!(keywords, vec!["rust", "web"]);
+                assert!(all);
+            }
+            _ => panic!("Expected Search command"),
+        }
+    }
+
+    #[test]
+    fn test_search_save_and_saved_commands() {
+        let cli = parse_args_ok("search rust async --save weekly-rust");
+        let Some(Commands::Search { keywords, save, .. }) = cli.command else {
+            panic!("Expected Search command");
+        };
+        assert_eq!(keywords, vec!["rust", "async"]);
+        assert_eq!(save.as_deref(), Some("weekly-rust"));
+        assert!(parse_args("search async --refine --save x").is_err());
+
+        let cli = parse_args_ok("saved run weekly-rust");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Saved {
+                action: SavedAction::Run { ref name }
+            }) if name == "weekly-rust"
+        ));
+        assert!(parse_args_ok("saved list").command.is_some());
+        assert!(parse_args("saved delete").is_err());
+    }
+
+    #[test]
+    fn test_collection_commands() {
+        let cli = Cli::try_parse_from([
+            "buku",
+            "collection",
+            "create",
+            "reading",
+            "tag:toread AND NOT tag:done",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Collection {
+                action: CollectionAction::Create { ref name, ref query }
+            }) if name == "reading" && query == "tag:toread AND NOT tag:done"
+        ));
+        assert!(parse_args("collection create reading").is_err());
+
+        let cli = parse_args_ok("print --collection reading");
+        let Some(Commands::Print { collection, .. }) = cli.command else {
+            panic!("Expected Print command");
+        };
+        assert_eq!(collection.as_deref(), Some("reading"));
+        assert!(parse_args("print 1-5 --collection reading").is_err());
+
+        let cli = parse_args_ok("export reading.html --collection reading");
+        let Some(Commands::Export { collection, .. }) = cli.command else {
+            panic!("Expected Export command");
+        };
+        assert_eq!(collection.as_deref(), Some("reading"));
+    }
+
+    #[test]
+    fn test_export_filters() {
+        let cli =
+            parse_args_ok("export public.html -t public,shared --search rust async --ids 1-20,-3");
+        let Some(Commands::Export {
+            tag, search, ids, ..
+        }) = cli.command
+        else {
+            panic!("Expected Export command");
+        };
+        assert_eq!(tag, vec!["public", "shared"]);
+        assert_eq!(search, vec!["rust", "async"]);
+        assert_eq!(ids, vec!["1-20", "-3"]);
+
+        let cli = parse_args_ok("export recent.md --ids -10..-1");
+        let Some(Commands::Export { ids, .. }) = cli.command else {
+            panic!("Expected Export command");
+        };
+        assert_eq!(ids, vec!["-10..-1"]);
+    }
+
+    #[test]
+    fn test_alias_commands() {
+        let cli = parse_args_ok("alias set gh 42");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Alias {
+                action: AliasAction::Set { ref name, id: 42 }
+            }) if name == "gh"
+        ));
+        assert!(mutates_database(&cli));
+        assert!(parse_args("alias set gh github").is_err());
+
+        let cli = parse_args_ok("alias list");
+        assert!(!mutates_database(&cli));
+    }
+
+    #[test]
+    fn test_profile_commands() {
+        let cli = parse_args_ok("--profile work profile copy 1-3 --to personal --move");
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Profile {
+                action: ProfileAction::Copy { ref to, remove: true, ref on_duplicate, .. }
+            }) if to == "personal" && on_duplicate == "skip"
+        ));
+        assert!(mutates_database(&cli));
+
+        let cli = parse_args_ok("profile diff work personal");
+        assert!(!mutates_database(&cli));
+        assert!(parse_args("profile copy 1").is_err());
+        assert!(parse_args("--db a.db --profile work profile list").is_err());
+    }
+
+    #[test]
+    fn test_monitor_commands() {
+        let cli = parse_args_ok("monitor add 42 --timeout 5");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Monitor {
+                action: MonitorAction::Add {
+                    id: 42,
+                    timeout: Some(5)
+                }
+            })
+        ));
+        assert!(parse_args("monitor add github").is_err());
+
+        let cli = parse_args_ok("monitor changes --clear");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Monitor {
+                action: MonitorAction::Changes { clear: true }
+            })
+        ));
+    }
+
+    #[rstest]
+    #[case("search async --within-last", true)]
+    #[case("search async --refine", true)]
+    #[case("search async", false)]
+    fn test_search_within_last_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Search { within_last, .. }) => assert_eq!(within_last, expected),
+            _ => panic!("Expected Search command"),
+        }
+    }
+
+    #[rstest]
+    #[case("search rust --open", true)]
+    #[case("search rust -o", true)]
+    #[case("search rust", false)]
+    fn test_search_open_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Search { open, .. }) => assert_eq!(open, expected),
+            _ => panic!("Expected Search command"),
+        }
+        assert!(parse_args("search rust --open --exec echo").is_err());
+    }
+
+    #[test]
+    fn test_add_expires_and_expired_command() {
+        let cli = parse_args_ok("add https://event.example.com --expires 30d");
+        match cli.command {
+            Some(Commands::Add { expires, .. }) => assert_eq!(expires.as_deref(), Some("30d")),
+            _ => panic!("Expected Add command"),
+        }
+        assert!(matches!(
+            parse_args_ok("expired list").command,
+            Some(Commands::Expired {
+                action: ExpiredAction::List
+            })
+        ));
+        assert!(mutates_database(&parse_args_ok("expired delete")));
+        assert!(!mutates_database(&parse_args_ok("expired list")));
+    }
+
+    #[rstest]
+    #[case("move 5 --before 2", Some("2"), None)]
+    #[case("move -1 --after 3", None, Some("3"))]
+    fn test_move_command(
+        #[case] args: &str,
+        #[case] expected_before: Option<&str>,
+        #[case] expected_after: Option<&str>,
+    ) {
+        let args = escape_relative_indices(
+            std::iter::once("bukurs")
+                .chain(args.split_whitespace())
+                .map(OsString::from),
+        );
+        match Cli::try_parse_from(args).unwrap().command {
+            Some(Commands::Move { before, after, .. }) => {
+                assert_eq!(before.as_deref(), expected_before);
+                assert_eq!(after.as_deref(), expected_after);
+            }
+            _ => panic!("Expected Move command"),
+        }
+    }
+
+    #[rstest]
+    #[case("move 5")]
+    #[case("move 5 --before 2 --after 3")]
+    #[case("pin")]
+    #[case("star")]
+    #[case("rate 5")]
+    fn test_move_and_pin_need_targets(#[case] args: &str) {
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn test_rating_commands() {
+        let cli = parse_args_ok("rate 3 4");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Rate { ref id, rating: 4 }) if id == "3"
+        ));
+        assert!(mutates_database(&cli));
+
+        let cli = Cli::try_parse_from(escape_relative_indices(
+            ["buku", "star", "-1"].map(OsString::from),
+        ))
+        .unwrap();
+        assert!(matches!(cli.command, Some(Commands::Star { ref ids }) if ids == &["-1"]));
+
+        let cli = parse_args_ok("print --min-rating 4 --by-rating");
+        let Some(Commands::Print {
+            min_rating,
+            by_rating,
+            ..
+        }) = cli.command
+        else {
+            panic!("Expected Print command");
+        };
+        assert_eq!(min_rating, Some(4));
+        assert!(by_rating);
+
+        let cli = parse_args_ok("search rust --min-rating 3 --rank rating");
+        let Some(Commands::Search {
+            min_rating, rank, ..
+        }) = cli.command
+        else {
+            panic!("Expected Search command");
+        };
+        assert_eq!(min_rating, Some(3));
+        assert_eq!(rank.as_deref(), Some("rating"));
+    }
+
+    #[test]
+    fn test_search_lang() {
+        let cli = parse_args_ok("search rust --lang de");
+        let Some(Commands::Search { lang, .. }) = cli.command else {
+            panic!("Expected Search command");
+        };
+        assert_eq!(lang.as_deref(), Some("de"));
+    }
+
+    // Tag command tests
+    #[rstest]
+    #[case("tag rust")]
+    #[case("tag programming web")]
+    #[case("tag")]
+    fn test_tag_command(#[case] args: &str) {
+        let cli = parse_args_ok(args);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tag { action: None, .. })
+        ));
+    }
+
+    #[test]
+    fn test_tag_bulk_actions() {
+        let cli = parse_args_ok("tag add read,Later --to 1-50 --lowercase");
+        assert!(mutates_database(&cli));
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tag {
+                action: Some(TagAction::Add { ref tags, ref to, lowercase: true }),
+                ..
+            }) if tags == "read,Later" && to == &["1-50"]
+        ));
+
+        let cli = parse_args_ok("tag set a,b,c --on 3 5 7");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tag {
+                action: Some(TagAction::Set { ref on, .. }),
+                ..
+            }) if on == &["3", "5", "7"]
+        ));
+
+        assert!(parse_args_ok("tag remove old --from rust")
+            .command
+            .is_some());
+        assert!(parse_args("tag remove old").is_err());
+        assert!(!mutates_database(&parse_args_ok("tag rust")));
+    }
+
+    #[rstest]
+    #[case("tag normalize", true)]
+    #[case("tag normalize --lowercase --dry-run", false)]
+    fn test_tag_normalize(#[case] args: &str, #[case] mutates: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(mutates_database(&cli), mutates);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tag {
+                action: Some(TagAction::Normalize { .. }),
+                ..
+            })
+        ));
+    }
+
+    #[rstest]
+    #[case("tag apply-map folders.csv", true)]
+    #[case("tag apply-map folders.csv --dry-run", false)]
+    fn test_tag_apply_map(#[case] args: &str, #[case] mutates: bool) {
+        let cli = parse_args_ok(args);
+        assert_eq!(mutates_database(&cli), mutates);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tag {
+                action: Some(TagAction::ApplyMap { ref file, .. }),
+                ..
+            }) if file == &PathBuf::from("folders.csv")
+        ));
+    }
+
+    #[rstest]
+    #[case("autotag apply", false)]
+    #[case("autotag apply --dry-run", true)]
+    fn test_autotag_command(#[case] args: &str, #[case] expected_dry_run: bool) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Autotag {
+                action: AutotagAction::Apply { dry_run },
+            }) => assert_eq!(dry_run, expected_dry_run),
+            _ => panic!("Expected Autotag command"),
+        }
+    }
+
+    #[rstest]
+    #[case("delete 5", false, false)]
+    #[case("--yes delete 5", true, false)]
+    #[case("delete 5 -y", true, false)]
+    #[case("--no-input print", false, true)]
+    #[case("search rust --no-input --yes", true, true)]
+    fn test_interaction_flags(
+        #[case] args: &str,
+        #[case] expected_yes: bool,
+        #[case] expected_no_input: bool,
+    ) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cli.yes, expected_yes);
+        assert_eq!(cli.no_input, expected_no_input);
+    }
+
+    #[rstest]
+    #[case("config init", Some(ConfigOp::Init { force: false }))]
+    #[case("config init --force", Some(ConfigOp::Init { force: true }))]
+    #[case("config show", Some(ConfigOp::Show))]
+    #[case("--config other.yml config validate", Some(ConfigOp::Validate))]
+    #[case("print", None)]
+    fn test_config_command(#[case] args: &str, #[case] expected: Option<ConfigOp>) {
+        let cli = parse_args_ok(args);
+        let command = config_command(&cli);
+        assert_eq!(command.as_ref().map(|c| c.op), expected);
+        if args.starts_with("--config") {
+            assert_eq!(command.unwrap().path, Some(PathBuf::from("other.yml")));
+        }
+    }
+
+    #[rstest]
+    #[case("cipher encrypt", Some(CipherOp::Encrypt))]
+    #[case("--db other.db cipher decrypt", Some(CipherOp::Decrypt))]
+    #[case("config show", None)]
+    fn test_cipher_command(#[case] args: &str, #[case] expected: Option<CipherOp>) {
+        let cli = parse_args_ok(args);
+        assert_eq!(cipher_command(&cli).map(|c| c.op), expected);
+    }
+
+    #[rstest]
+    #[case("daemon", DaemonOp::Run { detach: false })]
+    #[case("daemon --detach", DaemonOp::Run { detach: true })]
+    #[case("daemon status", DaemonOp::Status)]
+    #[case("daemon stop", DaemonOp::Stop)]
+    fn test_daemon_command(#[case] args: &str, #[case] expected: DaemonOp) {
+        let cli = parse_args_ok(args);
+        let Some(Commands::Daemon { action, detach }) = cli.command else {
+            panic!("Expected Daemon command");
+        };
+        let op = match action {
+            None => DaemonOp::Run { detach },
+            Some(DaemonAction::Status) => DaemonOp::Status,
+            Some(DaemonAction::Stop) => DaemonOp::Stop,
+        };
+        assert_eq!(op, expected);
+        assert!(parse_args("daemon --detach status").is_err());
+    }
+
+    #[rstest]
+    #[case("serve --grpc", true, "127.0.0.1:50051")]
+    #[case("serve --grpc --addr 0.0.0.0:6000", true, "0.0.0.0:6000")]
+    #[case("serve", false, "127.0.0.1:50051")]
+    fn test_serve_command(#[case] args: &str, #[case] grpc: bool, #[case] addr: &str) {
+        let cli = parse_args_ok(args);
+        assert!(!mutates_database(&cli));
+        let Some(Commands::Serve {
+            grpc: parsed_grpc,
+            addr: parsed_addr,
+        }) = cli.command
+        else {
+            panic!("Expected Serve command");
+        };
+        assert_eq!(parsed_grpc, grpc);
+        assert_eq!(parsed_addr, addr);
+    }
+
+    #[rstest]
+    #[case("note 5", "5")]
+    #[case("note -1", "-1")]
+    fn test_note_command(#[case] args: &str, #[case] expected_id: &str) {
+        let args = escape_relative_indices(
+            std::iter::once("bukurs")
+                .chain(args.split_whitespace())
+                .map(OsString::from),
+        );
+        match Cli::try_parse_from(args).unwrap().command {
+            Some(Commands::Note { id }) => assert_eq!(id, expected_id),
+            _ => panic!("Expected Note command"),
+        }
+    }
+
+    #[rstest]
+    #[case("similar 5", "5", 10)]
+    #[case("similar -1 -n 3", "-1", 3)]
+    #[case("similar --limit 2 7", "7", 2)]
+    fn test_similar_command(
+        #[case] args: &str,
+        #[case] expected_id: &str,
+        #[case] expected_limit: usize,
+    ) {
+        let args = escape_relative_indices(
+            std::iter::once("bukurs")
+                .chain(args.split_whitespace())
+                .map(OsString::from),
+        );
+        match Cli::try_parse_from(args).unwrap().command {
+            Some(Commands::Similar { id, limit }) => {
+                assert_eq!(id, expected_id);
+                assert_eq!(limit, expected_limit);
+            }
+            _ => panic!("Expected Similar command"),
+        }
+    }
+
+    #[rstest]
+    #[case("links 5", "5", false, false)]
+    #[case("links -1 --external", "-1", true, false)]
+    #[case("links 3 --list -t rust,docs", "3", false, true)]
+    fn test_links_command(
+        #[case] args: &str,
+        #[case] expected_id: &str,
+        #[case] expected_external: bool,
+        #[case] expected_list: bool,
+    ) {
+        let args = escape_relative_indices(
+            std::iter::once("bukurs")
+                .chain(args.split_whitespace())
+                .map(OsString::from),
+        );
+        let cli = Cli::try_parse_from(args).unwrap();
+        // Links waits on the network and the picker, and locks only to write
+        assert!(!mutates_database(&cli));
+        match cli.command {
+            Some(Commands::Links {
+                id, external, list, ..
+            }) => {
+                assert_eq!(id, expected_id);
+                assert_eq!(external, expected_external);
+                assert_eq!(list, expected_list);
+            }
+            _ => panic!("Expected Links command"),
+        }
+    }
+
+    #[rstest]
+    #[case("read 5", "5", None)]
+    #[case("read -1 -w 72", "-1", Some(72))]
+    fn test_read_command(
+        #[case] args: &str,
+        #[case] expected_id: &str,
+        #[case] expected_width: Option<usize>,
+    ) {
+        let args = escape_relative_indices(
+            std::iter::once("bukurs")
+                .chain(args.split_whitespace())
+                .map(OsString::from),
+        );
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(!mutates_database(&cli));
+        match cli.command {
+            Some(Commands::Read { id, width, .. }) => {
+                assert_eq!(id, expected_id);
+                assert_eq!(width, expected_width);
+            }
+            _ => panic!("Expected Read command"),
+        }
     }
 
     #[rstest]
-    #[case("--db /path/to/db.db", Some("/path/to/db.db"))]
-    #[case("--db custom.db", Some("custom.db"))]
-    fn test_db_path(#[case] args: &str, #[case] expected: Option<&str>) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.db.as_ref().map(|p| p.to_str().unwrap()), expected);
+    #[case(&["query", "SELECT count(*) FROM bookmarks"], false)]
+    #[case(&["query", "DELETE FROM undo_log", "--allow-write"], true)]
+    fn test_query_command(#[case] args: &[&str], #[case] writes: bool) {
+        let cli =
+            Cli::try_parse_from(std::iter::once("bukurs").chain(args.iter().copied())).unwrap();
+        assert_eq!(mutates_database(&cli), writes);
+        match cli.command {
+            Some(Commands::Query { sql, allow_write }) => {
+                assert_eq!(sql, args[1]);
+                assert_eq!(allow_write, writes);
+            }
+            _ => panic!("Expected Query command"),
+        }
     }
 
     #[rstest]
-    #[case("--nc", true)]
-    #[case("", false)]
-    fn test_no_color_flag(#[case] args: &str, #[case] expected: bool) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.nc, expected);
+    #[case("delete 3", true)]
+    #[case("add https://example.com", false)]
+    #[case("edit 3", false)]
+    #[case("note 3", false)]
+    #[case("update 3 --tag +read", false)]
+    #[case("fetch-pending", false)]
+    #[case("monitor add 3", false)]
+    #[case("monitor check", false)]
+    #[case("monitor remove 3", true)]
+    #[case("monitor changes", false)]
+    #[case("monitor changes --clear", true)]
+    #[case("collection create reading tag:toread", true)]
+    #[case("collection delete reading", true)]
+    #[case("collection list", false)]
+    fn test_command_lock_scope(#[case] args: &str, #[case] whole_command: bool) {
+        assert_eq!(mutates_database(&parse_args_ok(args)), whole_command);
     }
 
-    #[rstest]
-    #[case("--debug", true)]
-    #[case("-g", true)]
-    #[case("", false)]
-    fn test_debug_flag(#[case] args: &str, #[case] expected: bool) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.debug, expected);
+    #[test]
+    fn test_domains_command() {
+        let cli = parse_args_ok("-n 5 domains");
+        assert!(matches!(cli.command, Some(Commands::Domains)));
+        assert_eq!(cli.limit, Some(5));
+        assert!(!mutates_database(&cli));
     }
 
-    #[rstest]
-    #[case("--format json", Some("json"))]
-    #[case("-f markdown", Some("markdown"))]
-    #[case("", None)]
-    fn test_format_option(#[case] args: &str, #[case] expected: Option<&str>) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.format.as_deref(), expected);
+    #[test]
+    fn test_search_site_operators_parse() {
+        let args = [
+            "bukurs",
+            "search",
+            "site:github.com",
+            "-site:gist.github.com",
+            "rust",
+        ]
+        .map(OsString::from);
+        let cli = Cli::try_parse_from(escape_relative_indices(args)).unwrap();
+        match cli.command {
+            Some(Commands::Search { keywords, .. }) => assert_eq!(
+                keywords,
+                vec!["site:github.com", "rust", "-site:gist.github.com"]
+            ),
+            _ => panic!("Expected Search command"),
+        }
     }
 
-    #[rstest]
-    #[case("--open", true)]
-    #[case("-o", true)]
-    #[case("", false)]
-    fn test_open_flag(#[case] args: &str, #[case] expected: bool) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.open, expected);
+    #[test]
+    fn test_time_filter_options() {
+        match parse_args_ok("search rust --added-after 2024-01-01 --not-visited-since 1y").command {
+            Some(Commands::Search {
+                added_after,
+                added_within,
+                not_visited_since,
+                ..
+            }) => {
+                assert_eq!(added_after.as_deref(), Some("2024-01-01"));
+                assert_eq!(added_within, None);
+                assert_eq!(not_visited_since.as_deref(), Some("1y"));
+            }
+            _ => panic!("Expected Search command"),
+        }
+        match parse_args_ok("print --added-within 7d").command {
+            Some(Commands::Print { added_within, .. }) => {
+                assert_eq!(added_within.as_deref(), Some("7d"))
+            }
+            _ => panic!("Expected Print command"),
+        }
+        assert!(
+            Cli::try_parse_from(["bukurs", "print", "--stats", "--added-within", "7d"]).is_err()
+        );
     }
 
     #[rstest]
-    #[case("--limit 10", Some(10))]
-    #[case("-n 5", Some(5))]
-    #[case("", None)]
-    fn test_limit_option(#[case] args: &str, #[case] expected: Option<usize>) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.limit, expected);
+    #[case("search rust --rank relevance", Some("relevance"))]
+    #[case("search rust --rank visits", Some("visits"))]
+    #[case("search rust", None)]
+    fn test_search_rank_option(#[case] args: &str, #[case] expected: Option<&str>) {
+        match parse_args_ok(args).command {
+            Some(Commands::Search { rank, .. }) => assert_eq!(rank.as_deref(), expected),
+            _ => panic!("Expected Search command"),
+        }
+        assert!(parse_args("search rust --rank popular").is_err());
     }
 
-    #[rstest]
-    #[case("rust programming", vec!["rust", "programming"])]
-    #[case("test", vec!["test"])]
-    #[case("", vec![])]
-    fn test_search_keywords(#[case] args: &str, #[case] expected: Vec<&str>) {
-        let cli = parse_args_ok(args);
-        assert_eq!(cli.keywords, expected);
+    #[test]
+    fn test_search_fuzzy_flag() {
+        assert!(matches!(
+            parse_args_ok("search progrmming --fuzzy").command,
+            Some(Commands::Search { fuzzy: true, .. })
+        ));
+        assert!(parse_args("search rust --fuzzy --regex").is_err());
+    }
+
+    #[test]
+    fn test_search_notes_flag() {
+        let cli = parse_args_ok("search rust --notes");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Search { notes: true, .. })
+        ));
     }
 
-    // Add command tests
     #[rstest]
-    #[case("add https://example.com")]
-    #[case("add https://rust-lang.org --title Rust")]
-    #[case("add https://test.com --tag rust,programming")]
-    #[case("add https://test.com --comment Description")]
-    #[case("add https://test.com --offline")]
-    fn test_add_command(#[case] args: &str) {
+    #[case("later", false)]
+    #[case("later pop", true)]
+    fn test_later_command(#[case] args: &str, #[case] expected_pop: bool) {
         let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Add { .. })));
+        match cli.command {
+            Some(Commands::Later { action }) => {
+                assert_eq!(matches!(action, Some(LaterAction::Pop)), expected_pop)
+            }
+            _ => panic!("Expected Later command"),
+        }
     }
 
     #[test]
-    fn test_add_command_with_all_options() {
-        let cli = parse_args_ok(
-            "add https://example.com --title Test --tag rust --tag test --comment Description --offline",
-        );
+    fn test_add_url_and_bookmarklet_commands() {
+        let cli = parse_args_ok("add-url bukurs://add?url=https://a.com --offline");
         match cli.command {
-            Some(Commands::Add {
-                url,
-                tag,
-                title,
-                comment,
-                offline,
-            }) => {
-                assert_eq!(url, "https://example.com");
-                assert_eq!(title, Some("Test".to_string()));
-                assert_eq!(tag, Some(vec!["rust".to_string(), "test".to_string()]));
-                assert_eq!(comment, Some("Description".to_string()));
+            Some(Commands::AddUrl { link, offline }) => {
+                assert_eq!(link, "bukurs://add?url=https://a.com");
                 assert!(offline);
             }
-            _ => panic!("Expected Add command"),
+            _ => panic!("Expected AddUrl command"),
         }
-    }
 
-    // Update command tests
-    #[rstest]
-    #[case("update 1 --url https://new.com")]
-    #[case("update 42 --title NewTitle")]
-    #[case("update 5 --tag updated")]
-    #[case("update 10 --comment UpdatedDescription")]
-    #[case("update 3 --immutable 1")]
-    fn test_update_command(#[case] args: &str) {
-        let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Update { .. })));
+        let cli = parse_args_ok("bookmarklet --tags inbox");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Bookmarklet { tags: Some(t) }) if t == "inbox"
+        ));
     }
 
     #[test]
-    fn test_update_command_details() {
-        let cli = parse_args_ok("update 42");
-        assert!(matches!(cli.command, Some(Commands::Update { .. })));
-
-        // Verify the ids are parsed correctly
-        if let Some(Commands::Update { ids, .. }) = cli.command {
-            assert_eq!(ids, vec!["42".to_string()]);
-        } else {
-            panic!("Expected Update command");
+    fn test_search_exec_flags() {
+        let cli = Cli::try_parse_from([
+            "buku",
+            "search",
+            "rust",
+            "--exec",
+            "curl -I {url}",
+            "-j",
+            "4",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Search {
+                exec,
+                exec_batch,
+                jobs,
+                ..
+            }) => {
+                assert_eq!(exec.as_deref(), Some("curl -I {url}"));
+                assert_eq!(exec_batch, None);
+                assert_eq!(jobs, Some(4));
+            }
+            _ => panic!("Expected Search command"),
         }
-    }
 
-    // Delete command tests
-    #[rstest]
-    #[case("delete 1")]
-    #[case("delete 1 2 3")]
-    #[case("delete 1-5")]
-    #[case("delete --force 1")]
-    #[case("delete --retain-order 1 2")]
-    fn test_delete_command(#[case] args: &str) {
-        let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Delete { .. })));
+        let cli = parse_args_ok("search rust -X open");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Search { exec_batch: Some(c), .. }) if c == "open"
+        ));
+
+        assert!(parse_args("search rust -x echo -X echo").is_err());
+        assert!(parse_args("search rust -X echo -j 2").is_err());
     }
 
     #[test]
-    fn test_delete_command_with_force() {
-        let cli = parse_args_ok("delete --force 1 2 3");
+    fn test_import_history_command() {
+        let cli = parse_args_ok("import-history --browser firefox --since 30d --min-visits 5");
         match cli.command {
-            Some(Commands::Delete { ids, force, .. }) => {
-                assert_eq!(ids, vec!["1", "2", "3"]);
-                assert!(force);
+            Some(Commands::ImportHistory {
+                browser,
+                since,
+                min_visits,
+                dry_run,
+                ..
+            }) => {
+                assert_eq!(browser, vec!["firefox"]);
+                assert_eq!(since.as_deref(), Some("30d"));
+                assert_eq!(min_visits, 5);
+                assert!(!dry_run);
             }
-            _ => panic!("Expected Delete command"),
+            _ => panic!("Expected ImportHistory command"),
         }
+
+        let cli = parse_args_ok("import-history --path History --dry-run");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::ImportHistory {
+                min_visits: 3,
+                dry_run: true,
+                ..
+            })
+        ));
+
+        assert!(parse_args("import-history").is_err());
+        assert!(parse_args("import-history -b chrome --path History").is_err());
     }
 
-    // Print command tests
-    #[rstest]
-    #[case("print")]
-    #[case("print 1")]
-    #[case("print 1 2 3")]
-    #[case("print --columns 5")]
-    fn test_print_command(#[case] args: &str) {
-        let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Print { .. })));
+    #[test]
+    fn test_publish_command() {
+        let cli = parse_args_ok("publish out --tags public,rust --title Links");
+        match cli.command {
+            Some(Commands::Publish { dir, tags, title }) => {
+                assert_eq!(dir, PathBuf::from("out"));
+                assert_eq!(tags, vec!["public", "rust"]);
+                assert_eq!(title.as_deref(), Some("Links"));
+            }
+            _ => panic!("Expected Publish command"),
+        }
+
+        assert!(parse_args("publish").is_err());
     }
 
-    // Search command tests
-    #[rstest]
-    #[case("search rust")]
-    #[case("search rust programming")]
-    #[case("search --all keyword1 keyword2")]
-    #[case("search --deep test")]
-    #[case("search --regex '^http'")]
-    #[case("search --markers tag:test")]
-    fn test_search_command(#[case] args: &str) {
-        let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Search { .. })));
+    #[test]
+    fn test_dump_and_restore_commands() {
+        let cli = parse_args_ok("dump backup.bukurs --undo");
+        assert!(!mutates_database(&cli));
+        match cli.command {
+            Some(Commands::Dump {
+                file,
+                undo,
+                no_config,
+            }) => {
+                assert_eq!(file, PathBuf::from("backup.bukurs"));
+                assert!(undo);
+                assert!(!no_config);
+            }
+            _ => panic!("Expected Dump command"),
+        }
+
+        let cli = parse_args_ok("restore backup.bukurs --replace --restore-config");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Restore {
+                replace: true,
+                restore_config: true,
+                ..
+            })
+        ));
+        assert!(mutates_database(&cli));
+        assert!(parse_args("restore").is_err());
     }
 
     #[test]
-    fn test_search_command_all_flag() {
-        let cli = parse_args_ok("search --all rust web");
+    fn test_audit_commands() {
+        let cli = parse_args_ok("audit tail -n 3");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Audit {
+                action: AuditAction::Tail { count: 3 }
+            })
+        ));
+
+        let cli = parse_args_ok("audit search --operation delete --id 4 --days 7");
         match cli.command {
-            Some(Commands::Search { keywords, all, .. }) => {
-                assert_eq!(keywords, vec!["rust", "web"]);
-                assert!(all);
+            Some(Commands::Audit {
+                action:
+                    AuditAction::Search {
+                        operation,
+                        id,
+                        source,
+                        days,
+                    },
+            }) => {
+                assert_eq!(operation.as_deref(), Some("delete"));
+                assert_eq!(id, Some(4));
+                assert_eq!(source, None);
+                assert_eq!(days, Some(7));
             }
-            _ => panic!("Expected Search command"),
+            _ => panic!("Expected Audit search command"),
         }
     }
 
-    // Tag command tests
-    #[rstest]
-    #[case("tag rust")]
-    #[case("tag programming web")]
-    #[case("tag")]
-    fn test_tag_command(#[case] args: &str) {
-        let cli = parse_args_ok(args);
-        assert!(matches!(cli.command, Some(Commands::Tag { .. })));
+    #[test]
+    fn test_add_later_flag() {
+        let cli = parse_args_ok("add https://example.com --later");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Add { later: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_print_stats_flag() {
+        let cli = parse_args_ok("print --stats");
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Print { stats: true, .. })
+        ));
     }
 
     // Lock/Unlock command tests
@@ -617,7 +2937,7 @@ mod tests {
     fn test_lock_unlock_commands(#[case] args: &str, #[case] expected_iterations: u32) {
         let cli = parse_args_ok(args);
         match cli.command {
-            Some(Commands::Lock { iterations }) => {
+            Some(Commands::Lock { iterations, .. }) => {
                 assert_eq!(iterations, expected_iterations);
             }
             Some(Commands::Unlock { iterations }) => {
@@ -627,6 +2947,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_lock_kdf_options() {
+        let cli = parse_args_ok("lock --kdf argon2 --memory 256MB --passes 4");
+        let Some(Commands::Lock {
+            kdf,
+            memory,
+            passes,
+            ..
+        }) = cli.command
+        else {
+            panic!("Expected Lock command");
+        };
+        assert_eq!(kdf, "argon2");
+        assert_eq!(memory.as_deref(), Some("256MB"));
+        assert_eq!(passes, 4);
+
+        assert!(parse_args("lock --kdf scrypt").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_commands() {
+        let cli = parse_args_ok("encrypt --tag banking");
+        assert!(matches!(cli.command, Some(Commands::Encrypt { tag }) if tag == "banking"));
+        assert!(parse_args("encrypt").is_err());
+
+        let cli = parse_args_ok("decrypt");
+        assert!(matches!(cli.command, Some(Commands::Decrypt { tag: None })));
+        let cli = parse_args_ok("decrypt --tag banking");
+        assert!(
+            matches!(cli.command, Some(Commands::Decrypt { tag: Some(tag) }) if tag == "banking")
+        );
+    }
+
     // Import/Export command tests
     #[rstest]
     #[case("import bookmarks.html")]
@@ -660,6 +3013,7 @@ mod tests {
                 list,
                 all,
                 browsers,
+                ..
             }) => {
                 assert!(list);
                 assert!(!all);
@@ -695,6 +3049,112 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_import_browsers_from_path() {
+        let cli =
+            parse_args_ok("import-browsers --path /backup/places.sqlite --browser-type firefox");
+        match cli.command {
+            Some(Commands::ImportBrowsers {
+                path, browser_type, ..
+            }) => {
+                assert_eq!(path, Some(PathBuf::from("/backup/places.sqlite")));
+                assert_eq!(browser_type.as_deref(), Some("firefox"));
+            }
+            _ => panic!("Expected ImportBrowsers command"),
+        }
+    }
+
+    #[rstest]
+    #[case("import-browsers --all --path places.sqlite")]
+    #[case("import-browsers -b chrome --path Bookmarks")]
+    #[case("import-browsers --all --browser-type chrome")]
+    #[case("import-browsers --path Bookmarks --browser-type safari")]
+    fn test_import_browsers_path_conflicts(#[case] args: &str) {
+        assert!(parse_args(args).is_err());
+    }
+
+    #[rstest]
+    #[case("import bookmarks.html --dry-run", true)]
+    #[case("import bookmarks.html", false)]
+    fn test_import_dry_run_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Import { dry_run, .. }) => assert_eq!(dry_run, expected),
+            _ => panic!("Expected Import command"),
+        }
+    }
+
+    #[rstest]
+    #[case("import bookmarks.html", "skip")]
+    #[case("import bookmarks.html --on-duplicate merge", "merge")]
+    #[case("import bookmarks.html --on-duplicate overwrite", "overwrite")]
+    fn test_import_on_duplicate_flag(#[case] args: &str, #[case] expected: &str) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Import { on_duplicate, .. }) => assert_eq!(on_duplicate, expected),
+            _ => panic!("Expected Import command"),
+        }
+    }
+
+    #[rstest]
+    #[case("import bookmarks.html --parallel", true)]
+    #[case("import bookmarks.html", false)]
+    fn test_import_parallel_flag(#[case] args: &str, #[case] expected: bool) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Import { parallel, .. }) => assert_eq!(parallel, expected),
+            _ => panic!("Expected Import command"),
+        }
+    }
+
+    #[test]
+    fn test_import_report_flag() {
+        let cli = parse_args_ok("import bookmarks.html --report skipped.jsonl");
+        match cli.command {
+            Some(Commands::Import { report, .. }) => {
+                assert_eq!(report, Some(PathBuf::from("skipped.jsonl")))
+            }
+            _ => panic!("Expected Import command"),
+        }
+        assert!(parse_args("import bookmarks.html --dry-run --report skipped.jsonl").is_err());
+    }
+
+    #[rstest]
+    #[case("export feed.xml --format atom --limit 50", Some("atom"), Some(50))]
+    #[case("export feed.xml -f rss", Some("rss"), None)]
+    #[case("export bookmarks.html", None, None)]
+    fn test_export_feed_flags(
+        #[case] args: &str,
+        #[case] expected_format: Option<&str>,
+        #[case] expected_limit: Option<usize>,
+    ) {
+        let cli = parse_args_ok(args);
+        match cli.command {
+            Some(Commands::Export { format, limit, .. }) => {
+                assert_eq!(format.as_deref(), expected_format);
+                assert_eq!(limit, expected_limit);
+            }
+            _ => panic!("Expected Export command"),
+        }
+    }
+
+    #[test]
+    fn test_import_on_duplicate_rejects_unknown() {
+        assert!(parse_args("import bookmarks.html --on-duplicate keep").is_err());
+    }
+
+    #[test]
+    fn test_import_browsers_dry_run_flag() {
+        let cli = parse_args_ok("import-browsers --all --dry-run");
+        match cli.command {
+            Some(Commands::ImportBrowsers { all, dry_run, .. }) => {
+                assert!(all);
+                assert!(dry_run);
+            }
+            _ => panic!("Expected ImportBrowsers command"),
+        }
+    }
+
     // Open command tests
     #[rstest]
     #[case("open 1")]
@@ -713,19 +3173,32 @@ mod tests {
 
     // Edit command tests
     #[rstest]
-    #[case("edit 1", Some(1))]
-    #[case("edit 42", Some(42))]
-    #[case("edit", None)]
-    fn test_edit_command(#[case] args: &str, #[case] expected_id: Option<usize>) {
+    #[case("edit 1", vec!["1"], vec![])]
+    #[case("edit 42", vec!["42"], vec![])]
+    #[case("edit", vec![], vec![])]
+    #[case("edit 10-20 25", vec!["10-20", "25"], vec![])]
+    #[case("edit --search rust async", vec![], vec!["rust", "async"])]
+    #[case("edit -s rust", vec![], vec!["rust"])]
+    fn test_edit_command(
+        #[case] args: &str,
+        #[case] expected_ids: Vec<&str>,
+        #[case] expected_search: Vec<&str>,
+    ) {
         let cli = parse_args_ok(args);
         match cli.command {
-            Some(Commands::Edit { id }) => {
-                assert_eq!(id, expected_id);
+            Some(Commands::Edit { ids, search }) => {
+                assert_eq!(ids, expected_ids);
+                assert_eq!(search, expected_search);
             }
             _ => panic!("Expected Edit command"),
         }
     }
 
+    #[test]
+    fn test_edit_ids_conflict_with_search() {
+        assert!(Cli::try_parse_from(["bukurs", "edit", "1", "--search", "rust"]).is_err());
+    }
+
     // Undo command test
     #[test]
     fn test_undo_command() {
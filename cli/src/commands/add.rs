@@ -1,15 +1,21 @@
 use super::{AppContext, BukuCommand};
-use crate::fetch_ui::fetch_with_spinner;
+use crate::cli::get_exe_name;
+use crate::fetch_ui::{fetch_options, fetch_pool, fetch_with_spinner, ProgressBarSink};
+use bukurs::cancel::CancellationToken;
 use bukurs::error::Result;
-use bukurs::{fetch, utils};
+use bukurs::fetch;
+use bukurs::import_export::import::ParsedBookmark;
+use bukurs::import_export::DuplicateStrategy;
+use bukurs::operations::{expiry, later};
+use bukurs::progress::ProgressSink;
+use bukurs::service::{AddRequest, BookmarkService, ChangeSummary};
+use bukurs::suggest::{suggest_tags, DEFAULT_SUGGEST_LIMIT};
+use bukurs::tags::{format_tags, parse_tags};
+use bukurs::utils::format_rfc3339;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, OnceLock};
-
-static EMPTY_STRING: OnceLock<Arc<String>> = OnceLock::new();
-
-fn empty_string() -> Arc<String> {
-    EMPTY_STRING.get_or_init(|| Arc::new(String::new())).clone()
-}
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddCommand {
@@ -18,95 +24,296 @@ pub struct AddCommand {
     pub title: Option<String>,
     pub comment: Option<String>,
     pub offline: bool,
+    pub no_suggest: bool,
+    /// Per-request fetch timeout in seconds, overriding the config
+    pub timeout: Option<u64>,
+    /// Add to the read-later queue
+    pub later: bool,
+    /// How long until the bookmark expires, e.g. `30d`
+    pub expires: Option<String>,
 }
 
-impl BukuCommand for AddCommand {
-    fn execute(&self, ctx: &AppContext) -> Result<()> {
-        let tags = self.tag.as_deref().unwrap_or(&[]);
-
-        // Validate tags don't contain spaces
-        for t in tags {
-            if utils::has_spaces(t) {
-                return Err(bukurs::error::BukursError::InvalidInput(format!(
-                    "Invalid tag name: '{}' (tags cannot contain spaces)",
-                    t
-                )));
-            }
+/// Parse one `url [tags] [title]` line of bulk input
+/// Tags are comma-separated; use a lone `,` to give a title without tags
+fn parse_bulk_line(line: &str) -> Option<ParsedBookmark> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (url, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim_start();
+    let (tags, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    Some(ParsedBookmark {
+        url: url.to_string(),
+        title: title.trim().to_string(),
+        tags: format_tags(&parse_tags(tags)),
+        desc: String::new(),
+        parent_id: None,
+        created_at: None,
+        flags: 0,
+    })
+}
+
+/// Tags chosen at the suggestion prompt: empty or `y` accepts the suggestions,
+/// `n` declines them, anything else is taken as a replacement tag list
+fn parse_suggestion_answer(answer: &str, suggestions: &[String]) -> Vec<String> {
+    match answer.trim().to_lowercase().as_str() {
+        "" | "y" | "yes" => suggestions.to_vec(),
+        "n" | "no" => Vec::new(),
+        _ => parse_tags(answer),
+    }
+}
+
+/// Show suggested tags and let the user accept, decline or edit them
+fn prompt_suggested_tags(suggestions: &[String]) -> Result<Vec<String>> {
+    eprintln!("Suggested tags: {}", suggestions.join(", "));
+    eprint!("Accept? [Y/n] or type tags (comma-separated): ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(parse_suggestion_answer(&answer, suggestions))
+}
+
+impl AddCommand {
+    /// `--offline`, or `default_offline` in the config when `--online` isn't given
+    fn offline(&self, ctx: &AppContext) -> bool {
+        self.offline || ctx.config.default_offline
+    }
+
+    /// Add every `url [tags] [title]` line from `reader` as a single undoable batch
+    fn execute_bulk<R: BufRead>(&self, ctx: &AppContext, reader: R) -> Result<()> {
+        if self.title.is_some() || self.comment.is_some() || self.expires.is_some() {
+            return Err(bukurs::error::BukursError::InvalidInput(
+                "--title, --comment and --expires cannot be used when reading URLs from stdin"
+                    .to_string(),
+            ));
         }
+        let extra_tags: Vec<String> = self.tag.iter().flatten().flat_map(parse_tags).collect();
 
-        // Fetch metadata or use offline mode
-        let fetch_result = if self.offline {
-            fetch::FetchResult {
-                url: self.url.clone(),
-                title: empty_string(),
-                desc: empty_string(),
-                keywords: empty_string(),
+        // Deduplicate within the batch before fetching anything
+        let mut seen = HashSet::new();
+        let mut duplicates = 0;
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let Some(mut entry) = parse_bulk_line(&line?) else {
+                continue;
+            };
+            if !seen.insert(entry.url.clone()) {
+                duplicates += 1;
+                continue;
             }
-        } else {
-            match fetch_with_spinner(&self.url, &ctx.config.user_agent) {
-                Ok(result) => result,
-                Err(e) => {
-                    eprintln!("Warning: Failed to fetch metadata: {}", e);
-                    eprintln!("Continuing with manual entry...");
-                    fetch::FetchResult {
-                        url: self.url.clone(),
-                        title: empty_string(),
-                        desc: empty_string(),
-                        keywords: empty_string(),
+            if !extra_tags.is_empty() {
+                let mut tags = parse_tags(&entry.tags);
+                for tag in &extra_tags {
+                    if !tags.contains(tag) {
+                        tags.push(tag.clone());
                     }
                 }
+                entry.tags = format_tags(&tags);
             }
-        };
+            entries.push(entry);
+        }
 
-        // Determine final title
-        let _final_title: &str = if let Some(t) = self.title.as_ref() {
-            t.as_str()
-        } else if !fetch_result.title.is_empty() {
-            fetch_result.title.as_str()
+        if entries.is_empty() {
+            eprintln!("No URLs to add");
+            return Ok(());
+        }
+
+        // Word counts measured with `fetch.readability`
+        let mut word_counts: Vec<(String, usize)> = Vec::new();
+        // Detected page languages
+        let mut languages: Vec<(String, String)> = Vec::new();
+        // URLs left without metadata, queued for `fetch-pending`
+        let offline = self.offline(ctx);
+        let unfetched: Vec<String> = if offline {
+            entries.iter().map(|e| e.url.clone()).collect()
         } else {
-            self.url.as_str()
+            eprintln!("Fetching metadata for {} URL(s)...", entries.len());
+            let progress = ProgressBarSink::new();
+            progress.start("Fetching", Some(entries.len() as u64));
+
+            let options = fetch_options(ctx.config, self.timeout);
+            let (failed, measured): (Vec<_>, Vec<_>) = fetch_pool(&options)?.install(|| {
+                entries
+                    .par_iter_mut()
+                    .map(|entry| {
+                        let outcome = match fetch::fetch_data(&entry.url, &options) {
+                            Ok(result) => {
+                                if entry.title.is_empty() {
+                                    entry.title = result.title.to_string();
+                                }
+                                entry.desc = result.desc.to_string();
+                                if entry.tags == "," {
+                                    entry.tags = format_tags(&parse_tags(result.keywords.as_str()));
+                                }
+                                if let Some(tags) = result.apply_tags(&entry.tags) {
+                                    entry.tags = tags;
+                                }
+                                (
+                                    None,
+                                    Some((entry.url.clone(), result.word_count, result.lang)),
+                                )
+                            }
+                            Err(_) => (Some(entry.url.clone()), None),
+                        };
+                        progress.advance(1, None);
+                        outcome
+                    })
+                    .unzip()
+            });
+            progress.finish();
+            for (url, words, lang) in measured.into_iter().flatten() {
+                if words > 0 {
+                    word_counts.push((url.clone(), words));
+                }
+                if let Some(lang) = lang {
+                    languages.push((url, lang));
+                }
+            }
+            failed.into_iter().flatten().collect()
         };
 
-        // Determine final description
-        let _desc: &str = self
-            .comment
+        if self.later {
+            for entry in &mut entries {
+                entry.tags = later::with_unread_tag(&entry.tags);
+            }
+        }
+
+        let lock = ctx.db.write_lock()?;
+        let mut skipped = HashSet::new();
+        let stats = ctx
+            .db
+            .import_rec_batch_reported(
+                &entries,
+                DuplicateStrategy::Skip,
+                &CancellationToken::new(),
+                |_url| {},
+                |entry| {
+                    skipped.insert(entry.url);
+                },
+            )?
+            .unwrap_or_default();
+        // Bookmarks that were already there keep whatever metadata they have
+        let unfetched: Vec<String> = unfetched
+            .into_iter()
+            .filter(|url| !skipped.contains(url))
+            .collect();
+        ctx.db.set_word_counts(&word_counts)?;
+        ctx.db.set_languages(&languages)?;
+        let queued = ctx.db.queue_fetch(&unfetched)?;
+        drop(lock);
+
+        eprintln!("✓ Added {} bookmark(s)", stats.added);
+        if stats.skipped > 0 {
+            eprintln!("  {} already in the database", stats.skipped);
+        }
+        if duplicates > 0 {
+            eprintln!("  {} duplicate line(s) ignored", duplicates);
+        }
+        if !offline && !unfetched.is_empty() {
+            eprintln!(
+                "  {} added without metadata (fetch failed)",
+                unfetched.len()
+            );
+        }
+        if queued > 0 {
+            eprintln!(
+                "  {} queued for metadata; run `{} fetch-pending` when online",
+                queued,
+                get_exe_name()
+            );
+        }
+        let mut summary = ChangeSummary::from(&stats);
+        summary.command = "add".to_string();
+        summary.skipped += duplicates;
+        summary.queued = queued;
+        ctx.report(&summary)
+    }
+}
+
+impl BukuCommand for AddCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if self.url == "-" {
+            return self.execute_bulk(ctx, std::io::stdin().lock());
+        }
+
+        let options = fetch_options(ctx.config, self.timeout);
+        // Checked before fetching, so a typo doesn't cost a request
+        let expires_at = self
+            .expires
             .as_deref()
-            .unwrap_or(fetch_result.desc.as_str());
+            .map(|duration| expiry::expires_at(duration, expiry::now()))
+            .transpose()?;
 
-        // Build tags string
-        let tags_str = if tags.is_empty() {
-            format!(",{},", fetch_result.keywords)
+        // Fetch metadata unless offline; failures fall back to manual entry
+        let fetched = if self.offline(ctx) {
+            None
         } else {
-            format!(",{},", tags.join(","))
+            match fetch_with_spinner(&self.url, &options) {
+                Ok(result) => Some(result),
+                Err(e) => {
+                    eprintln!("Warning: Failed to fetch metadata: {}", e);
+                    eprintln!("Continuing with manual entry...");
+                    None
+                }
+            }
         };
 
-        // Add to database
-        let id_result = ctx.db.add_rec(
-            &self.url,
-            self.title.as_deref().unwrap_or(""),
-            &tags_str,
-            self.comment.as_deref().unwrap_or(""),
-            None, // parent_id
-        );
-
-        match id_result {
-            Ok(id) => {
-                eprintln!("Added bookmark at index {}", id);
-                Ok(())
+        // Offer tag suggestions when someone is there to answer
+        let given: Vec<String> = self.tag.iter().flatten().flat_map(parse_tags).collect();
+        let accepted = if self.no_suggest || !ctx.interaction.can_prompt() {
+            None
+        } else {
+            let keywords = fetched.as_ref().map_or("", |f| f.keywords.as_str());
+            let suggestions =
+                suggest_tags(ctx.db, &self.url, keywords, &given, DEFAULT_SUGGEST_LIMIT)?;
+            if suggestions.is_empty() {
+                None
+            } else {
+                Some(prompt_suggested_tags(&suggestions)?)
             }
-            Err(e) => {
-                if let rusqlite::Error::SqliteFailure(err, _) = &e {
-                    // SQLITE_CONSTRAINT_UNIQUE = 2067
-                    if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE {
-                        return Err(bukurs::error::BukursError::InvalidInput(format!(
-                            "Duplicate URL: {}",
-                            self.url
-                        )));
+        };
+
+        let tags = match accepted {
+            Some(accepted) => {
+                let mut all = given;
+                for tag in accepted {
+                    if !all.contains(&tag) {
+                        all.push(tag);
                     }
                 }
-                Err(bukurs::error::BukursError::Database(e))
+                Some(all)
             }
+            None if given.is_empty() => None,
+            None => Some(given),
+        };
+
+        let request = AddRequest {
+            url: self.url.clone(),
+            title: self.title.clone(),
+            tags,
+            desc: self.comment.clone(),
+            later: self.later,
+            expires_at,
+        };
+        let lock = ctx.db.write_lock()?;
+        let outcome = BookmarkService::new(ctx.db, options).add(&request, fetched.as_ref())?;
+        drop(lock);
+
+        eprintln!("Added bookmark at index {}", outcome.id);
+        if let Some(expires_at) = expires_at {
+            eprintln!("Expires {}", format_rfc3339(expires_at));
         }
+        if outcome.queued {
+            eprintln!(
+                "Queued for metadata; run `{} fetch-pending` when online",
+                get_exe_name()
+            );
+        }
+        ctx.report(&ChangeSummary::from(&outcome))
     }
 }
 
@@ -141,6 +348,8 @@ mod tests {
                 db: &self.db,
                 config: &self.config,
                 db_path: &self.db_path,
+                interaction: Default::default(),
+                output: Default::default(),
             }
         }
     }
@@ -161,6 +370,10 @@ mod tests {
             title: title.clone(),
             comment: comment.clone(),
             offline: true, // Offline to avoid network calls in tests
+            no_suggest: true,
+            timeout: None,
+            later: false,
+            expires: None,
         };
 
         let result = cmd.execute(&env.ctx());
@@ -169,7 +382,7 @@ mod tests {
         // Verify it was added
         let records = env
             .db
-            .search(&vec![url.to_string()], false, false, false)
+            .search(&[url.to_string()], false, false, false)
             .expect("Search failed");
         assert_eq!(records.len(), 1);
         assert_eq!(records[0].url, url);
@@ -184,4 +397,114 @@ mod tests {
             assert_eq!(records[0].tags, expected_tags);
         }
     }
+
+    #[rstest]
+    #[case("https://a.com", "https://a.com", ",", "")]
+    #[case("https://a.com rust,web", "https://a.com", ",rust,web,", "")]
+    #[case("https://a.com rust The Title", "https://a.com", ",rust,", "The Title")]
+    #[case(
+        "  https://a.com\t, Untagged title ",
+        "https://a.com",
+        ",",
+        "Untagged title"
+    )]
+    fn test_parse_bulk_line(
+        #[case] line: &str,
+        #[case] url: &str,
+        #[case] tags: &str,
+        #[case] title: &str,
+    ) {
+        let entry = parse_bulk_line(line).unwrap();
+        assert_eq!(
+            (
+                entry.url.as_str(),
+                entry.tags.as_str(),
+                entry.title.as_str()
+            ),
+            (url, tags, title)
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("   ")]
+    #[case("# comment")]
+    fn test_parse_bulk_line_skips(#[case] line: &str) {
+        assert!(parse_bulk_line(line).is_none());
+    }
+
+    #[rstest]
+    #[case("", vec!["rust", "github"])]
+    #[case("Y", vec!["rust", "github"])]
+    #[case("n\n", vec![])]
+    #[case("rust, tools", vec!["rust", "tools"])]
+    fn test_parse_suggestion_answer(#[case] answer: &str, #[case] expected: Vec<&str>) {
+        let suggestions = vec!["rust".to_string(), "github".to_string()];
+        assert_eq!(parse_suggestion_answer(answer, &suggestions), expected);
+    }
+
+    #[test]
+    fn test_bulk_add_is_single_batch() {
+        let env = TestEnv::new();
+        env.db
+            .add_rec("https://existing.com", "Existing", ",", "", None)
+            .unwrap();
+
+        let cmd = AddCommand {
+            url: "-".to_string(),
+            tag: Some(vec!["bulk".to_string()]),
+            title: None,
+            comment: None,
+            offline: true,
+            no_suggest: true,
+            timeout: None,
+            later: false,
+            expires: None,
+        };
+        let input = "https://a.com rust A\nhttps://b.com\nhttps://a.com\nhttps://existing.com\n";
+        cmd.execute_bulk(&env.ctx(), std::io::Cursor::new(input))
+            .unwrap();
+
+        let records = env.db.get_rec_all().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].title, "A");
+        assert_eq!(records[1].tags, ",rust,bulk,");
+        assert_eq!(records[2].tags, ",bulk,");
+        // Only what this call added waits for metadata
+        let pending: Vec<String> = env
+            .db
+            .get_pending_fetch()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.url)
+            .collect();
+        assert_eq!(pending, ["https://a.com", "https://b.com"]);
+
+        // One undo removes everything the bulk add inserted
+        let op = env.db.undo_last().unwrap();
+        assert_eq!(op, Some(("ADD".to_string(), 2)));
+        assert_eq!(env.db.get_rec_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_default_offline_queues_without_fetching() {
+        let mut env = TestEnv::new();
+        env.config.default_offline = true;
+        let cmd = AddCommand {
+            url: "http://127.0.0.1:9/".to_string(),
+            tag: None,
+            title: None,
+            comment: None,
+            offline: false,
+            no_suggest: true,
+            timeout: None,
+            later: false,
+            expires: None,
+        };
+        cmd.execute(&env.ctx()).unwrap();
+
+        let pending = env.db.get_pending_fetch().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].url, "http://127.0.0.1:9/");
+    }
 }
@@ -0,0 +1,49 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::{BukursError, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AliasOp {
+    /// Give bookmark `id` the alias `name`, replacing any alias it had
+    Set { name: String, id: usize },
+    /// Take the alias `name` away from its bookmark
+    Remove { name: String },
+    /// Print every alias with its bookmark
+    List,
+}
+
+/// Manage bookmark aliases, short names accepted by `open` and the shell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AliasCommand {
+    pub op: AliasOp,
+}
+
+impl BukuCommand for AliasCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        match &self.op {
+            AliasOp::Set { name, id } => {
+                ctx.db.set_alias(*id, Some(name))?;
+                eprintln!("Bookmark {} can now be opened as '{}'", id, name);
+                Ok(())
+            }
+            AliasOp::Remove { name } => {
+                let bookmark = ctx.db.get_rec_by_alias(name)?.ok_or_else(|| {
+                    BukursError::InvalidInput(format!("No bookmark has the alias '{}'", name))
+                })?;
+                ctx.db.set_alias(bookmark.id, None)?;
+                eprintln!("Removed alias '{}' from bookmark {}", name, bookmark.id);
+                Ok(())
+            }
+            AliasOp::List => {
+                let aliases = ctx.db.get_aliases()?;
+                if aliases.is_empty() {
+                    eprintln!("No aliases; add one with `alias set <NAME> <ID>`");
+                }
+                for (alias, bookmark) in &aliases {
+                    println!("{}: {} {}", alias, bookmark.id, bookmark.url);
+                }
+                Ok(())
+            }
+        }
+    }
+}
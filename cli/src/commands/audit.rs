@@ -0,0 +1,186 @@
+use super::{AppContext, BukuCommand};
+use bukurs::audit::{AuditEntry, AuditFilter, AuditLog, AuditOperation, AuditSource};
+use bukurs::error::{BukursError, Result};
+use bukurs::utils::format_rfc3339;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditOp {
+    /// Print the last `count` entries
+    Tail { count: usize },
+    /// Print entries matching every given criterion
+    Search {
+        operation: Option<String>,
+        id: Option<usize>,
+        source: Option<String>,
+        days: Option<u64>,
+    },
+}
+
+/// Inspect the audit log configured with `audit_log`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditCommand {
+    pub op: AuditOp,
+}
+
+impl BukuCommand for AuditCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let path = ctx.config.audit_log.as_ref().ok_or_else(|| {
+            BukursError::Config("audit log is off (set `audit_log` in the config)".to_string())
+        })?;
+        let entries = AuditLog::read(path)?;
+
+        let shown: Vec<&AuditEntry> = match &self.op {
+            AuditOp::Tail { count } => entries
+                .iter()
+                .skip(entries.len().saturating_sub(*count))
+                .collect(),
+            AuditOp::Search { .. } => {
+                let filter = self.filter()?;
+                entries.iter().filter(|e| filter.matches(e)).collect()
+            }
+        };
+
+        if shown.is_empty() {
+            eprintln!("No audit entries in {}", path.display());
+            return Ok(());
+        }
+        for entry in shown {
+            println!("{}", format_entry(entry));
+        }
+        Ok(())
+    }
+}
+
+impl AuditCommand {
+    fn filter(&self) -> Result<AuditFilter> {
+        let AuditOp::Search {
+            operation,
+            id,
+            source,
+            days,
+        } = &self.op
+        else {
+            return Ok(AuditFilter::default());
+        };
+
+        let operation = operation
+            .as_deref()
+            .map(|op| {
+                AuditOperation::from_string(op).ok_or_else(|| {
+                    BukursError::InvalidInput(format!(
+                        "Unknown operation '{}' (expected add, update, delete, import, fetch or undo)",
+                        op
+                    ))
+                })
+            })
+            .transpose()?;
+        let source = source
+            .as_deref()
+            .map(|s| {
+                AuditSource::from_string(s).ok_or_else(|| {
+                    BukursError::InvalidInput(format!(
+                        "Unknown source '{}' (expected cli, shell, server, plugin, python or ffi)",
+                        s
+                    ))
+                })
+            })
+            .transpose()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+
+        Ok(AuditFilter {
+            operation,
+            id: *id,
+            source,
+            since: days.map(|days| now - days as i64 * 86_400),
+        })
+    }
+}
+
+/// One entry per line: time, source, operation and bookmark ids
+fn format_entry(entry: &AuditEntry) -> String {
+    let ids: Vec<String> = entry.ids.iter().map(usize::to_string).collect();
+    format!(
+        "{}  {:<6} {:<6} {}",
+        format_rfc3339(entry.timestamp),
+        entry.source.to_string(),
+        entry.operation.to_string(),
+        ids.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    fn search(operation: Option<&str>, source: Option<&str>) -> AuditCommand {
+        AuditCommand {
+            op: AuditOp::Search {
+                operation: operation.map(str::to_string),
+                id: None,
+                source: source.map(str::to_string),
+                days: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let entry = AuditEntry {
+            timestamp: 0,
+            operation: AuditOperation::Delete,
+            ids: vec![3, 4],
+            source: AuditSource::Shell,
+        };
+        assert_eq!(
+            format_entry(&entry),
+            "1970-01-01T00:00:00Z  shell  delete 3,4"
+        );
+    }
+
+    #[rstest]
+    #[case(search(Some("ADD"), Some("cli")), true)]
+    #[case(search(Some("rename"), None), false)]
+    #[case(search(None, Some("browser")), false)]
+    fn test_search_filter_parsing(#[case] command: AuditCommand, #[case] ok: bool) {
+        assert_eq!(command.filter().is_ok(), ok);
+    }
+
+    #[test]
+    fn test_audit_requires_config() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let mut config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let tail = AuditCommand {
+            op: AuditOp::Tail { count: 10 },
+        };
+
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        assert!(tail.execute(&ctx).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        config.audit_log = Some(dir.path().join("audit.jsonl"));
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        tail.execute(&ctx).unwrap();
+        search(Some("add"), None).execute(&ctx).unwrap();
+    }
+}
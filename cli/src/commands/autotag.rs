@@ -0,0 +1,104 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// Apply the configured per-domain tag rules to existing bookmarks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutotagCommand {
+    pub dry_run: bool,
+}
+
+impl BukuCommand for AutotagCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let rules = &ctx.config.autotag;
+        if rules.is_empty() {
+            eprintln!("No autotag rules configured.");
+            eprintln!("Add rules to the config file, e.g.:");
+            eprintln!("  autotag:");
+            eprintln!("    github.com: code,github");
+            return Ok(());
+        }
+
+        let bookmarks = ctx.db.get_rec_all()?;
+        let changed = rules.retag(&bookmarks);
+        if changed.is_empty() {
+            eprintln!("All bookmarks already match the autotag rules.");
+            return Ok(());
+        }
+
+        for updated in &changed {
+            let old_tags = bookmarks
+                .iter()
+                .find(|b| b.id == updated.id)
+                .map_or(",", |b| b.tags.as_str());
+            eprintln!(
+                "  {}. {}: {} -> {}",
+                updated.id, updated.url, old_tags, updated.tags
+            );
+        }
+
+        if self.dry_run {
+            eprintln!(
+                "Dry run: {} bookmark(s) would be retagged. No changes were written to the database.",
+                changed.len()
+            );
+            return Ok(());
+        }
+
+        // One batch, so a single undo reverts the whole run
+        let (updated, failed) = ctx
+            .db
+            .update_rec_batch_with_tags(&changed, None, None, None, None, None)?;
+        eprintln!("✓ Retagged {} bookmark(s)", updated);
+        if failed > 0 {
+            eprintln!("  {} failed", failed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_autotag_apply_and_undo() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let mut config = Config::default();
+        config.autotag.insert("github.com", "code");
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+
+        let a = db
+            .add_rec("https://github.com/a", "A", ",rust,", "", None)
+            .unwrap();
+        let b = db
+            .add_rec("https://github.com/b", "B", ",", "", None)
+            .unwrap();
+        let c = db
+            .add_rec("https://example.com", "C", ",", "", None)
+            .unwrap();
+
+        AutotagCommand { dry_run: true }.execute(&ctx).unwrap();
+        assert_eq!(db.get_rec_by_id(a).unwrap().unwrap().tags, ",rust,");
+
+        AutotagCommand { dry_run: false }.execute(&ctx).unwrap();
+        assert_eq!(db.get_rec_by_id(a).unwrap().unwrap().tags, ",rust,code,");
+        assert_eq!(db.get_rec_by_id(b).unwrap().unwrap().tags, ",code,");
+        assert_eq!(db.get_rec_by_id(c).unwrap().unwrap().tags, ",");
+
+        // Whole run is one undo batch
+        db.undo_last().unwrap();
+        assert_eq!(db.get_rec_by_id(a).unwrap().unwrap().tags, ",rust,");
+        assert_eq!(db.get_rec_by_id(b).unwrap().unwrap().tags, ",");
+    }
+}
@@ -0,0 +1,92 @@
+use super::add::AddCommand;
+use super::{AppContext, BukuCommand};
+use bukurs::bookmarklet::{bookmarklet, parse_add_url};
+use bukurs::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// Add the bookmark described by a `bukurs://add?...` link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddUrlCommand {
+    pub link: String,
+    pub offline: bool,
+}
+
+impl AddUrlCommand {
+    fn to_add_command(&self) -> Result<AddCommand> {
+        let request = parse_add_url(&self.link)?;
+        Ok(AddCommand {
+            url: request.url,
+            tag: request.tags,
+            title: request.title,
+            comment: request.desc,
+            offline: self.offline,
+            // Launched by a browser or URL handler, with nobody at a terminal
+            no_suggest: true,
+            timeout: None,
+            later: request.later,
+            expires: None,
+        })
+    }
+}
+
+impl BukuCommand for AddUrlCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        self.to_add_command()?.execute(ctx)
+    }
+}
+
+/// Print a bookmarklet that sends the current page to `add-url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkletCommand {
+    pub tags: Option<String>,
+}
+
+impl BukuCommand for BookmarkletCommand {
+    fn execute(&self, _ctx: &AppContext) -> Result<()> {
+        println!("{}", bookmarklet(self.tags.as_deref()));
+        eprintln!("Save this as a bookmark's URL; it opens bukurs:// links, so register");
+        eprintln!("`bukurs add-url %u` as the handler for the bukurs scheme.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_add_url_adds_bookmark() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+
+        let cmd = AddUrlCommand {
+            link: "bukurs://add?url=https%3A%2F%2Fexample.com&title=Example&tags=rust%2Cweb"
+                .to_string(),
+            offline: true,
+        };
+        cmd.execute(&ctx).unwrap();
+
+        let all = db.get_rec_all().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].url, "https://example.com");
+        assert_eq!(all[0].title, "Example");
+        assert_eq!(all[0].tags, ",rust,web,");
+
+        let bad = AddUrlCommand {
+            link: "https://example.com".to_string(),
+            offline: true,
+        };
+        assert!(bad.execute(&ctx).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::prompt::Interaction;
+use bukurs::config::Config;
+use bukurs::db::cipher;
+use bukurs::error::{BukursError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherOp {
+    /// Rewrite a plaintext database as a SQLCipher one
+    Encrypt,
+    /// Rewrite a SQLCipher database as a plaintext one
+    Decrypt,
+}
+
+/// Convert the database between plaintext SQLite and SQLCipher
+///
+/// Runs before the database is opened, since neither form can be opened with
+/// the `database.encrypted` setting of the other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CipherCommand {
+    pub op: CipherOp,
+}
+
+impl CipherCommand {
+    pub fn run(&self, db_path: &Path, config: &Config, interaction: Interaction) -> Result<()> {
+        let _lock = config.database.lock_database(db_path)?;
+        match self.op {
+            CipherOp::Encrypt => {
+                if !cipher::is_plaintext(db_path)? {
+                    return Err(BukursError::InvalidInput(format!(
+                        "{} is encrypted already",
+                        db_path.display()
+                    )));
+                }
+                let key = cipher::find_key(config.database.key_command.as_deref(), || {
+                    interaction.require_input("The database key prompt")?;
+                    let key = rpassword::prompt_password("New database key: ")?;
+                    let confirm = rpassword::prompt_password("Confirm database key: ")?;
+                    if key != confirm {
+                        return Err("Keys do not match".into());
+                    }
+                    Ok(key)
+                })?;
+                cipher::encrypt_file(db_path, &key)?;
+                eprintln!("✓ Encrypted {} with SQLCipher", db_path.display());
+                if !config.database.encrypted {
+                    eprintln!("  Set `database.encrypted: true` in the config to open it");
+                }
+            }
+            CipherOp::Decrypt => {
+                let key = cipher::find_key(config.database.key_command.as_deref(), || {
+                    interaction.require_input("The database key prompt")?;
+                    Ok(rpassword::prompt_password("Database key: ")?)
+                })?;
+                cipher::decrypt_file(db_path, &key)?;
+                eprintln!("✓ Decrypted {}", db_path.display());
+                if config.database.encrypted {
+                    eprintln!("  Remove `database.encrypted: true` from the config to open it");
+                }
+            }
+        }
+        Ok(())
+    }
+}
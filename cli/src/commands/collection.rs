@@ -0,0 +1,61 @@
+use super::{AppContext, BukuCommand};
+use bukurs::collection::{self, Collection, CollectionExpr};
+use bukurs::error::{BukursError, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollectionOp {
+    /// Define (or redefine) a collection
+    Create { name: String, query: String },
+    /// Print every collection with its query
+    List,
+    /// Forget a collection; its bookmarks stay
+    Delete { name: String },
+}
+
+/// Manage smart collections, used with `print --collection` and `export --collection`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionCommand {
+    pub op: CollectionOp,
+}
+
+impl BukuCommand for CollectionCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        match &self.op {
+            CollectionOp::Create { name, query } => {
+                // Evaluating it first catches references to missing saved searches
+                let count = collection::evaluate(ctx.db, &CollectionExpr::parse(query)?)?.len();
+                let replaced = ctx.db.save_collection(&Collection {
+                    name: name.clone(),
+                    query: query.clone(),
+                })?;
+                let verb = if replaced { "Replaced" } else { "Created" };
+                eprintln!(
+                    "{} collection '{}' ({} bookmark(s) match now)",
+                    verb, name, count
+                );
+                Ok(())
+            }
+            CollectionOp::List => {
+                let collections = ctx.db.get_collections()?;
+                if collections.is_empty() {
+                    eprintln!("No collections; define one with `collection create <NAME> <QUERY>`");
+                }
+                for collection in &collections {
+                    println!("{}: {}", collection.name, collection.query);
+                }
+                Ok(())
+            }
+            CollectionOp::Delete { name } => {
+                if !ctx.db.delete_collection(name)? {
+                    return Err(BukursError::InvalidInput(format!(
+                        "No collection called '{}'",
+                        name
+                    )));
+                }
+                eprintln!("Deleted collection '{}'", name);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use bukurs::config::{Config, ConfigIssue, ConfigSource, DEFAULT_CONFIG_TEMPLATE};
+use bukurs::error::{BukursError, Result};
+use bukurs::fuzzy::PickerKind;
+use bukurs::workspace;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigOp {
+    /// Write the commented default config
+    Init { force: bool },
+    /// Print effective values and where they come from
+    Show,
+    /// Report unknown keys and invalid values
+    Validate,
+}
+
+/// Manage the config file
+///
+/// Runs before the database and config are loaded, so `validate` still works
+/// when the config file is broken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigCommand {
+    pub op: ConfigOp,
+    /// Config file given with `--config`
+    pub path: Option<PathBuf>,
+    /// `--db`, shown as overriding `db`
+    pub db: Option<PathBuf>,
+    /// `--profile`, shown as overriding `db` with that profile's database
+    pub profile: Option<String>,
+    /// `--nc`, shown as overriding `color`
+    pub nc: bool,
+    /// `--online`, shown as overriding `default_offline`
+    pub online: bool,
+    /// `--picker`, shown as overriding `picker.kind`
+    pub picker: Option<PickerKind>,
+    /// `--cacert`, shown as overriding `fetch.tls_ca_file`
+    pub cacert: Option<PathBuf>,
+}
+
+impl ConfigCommand {
+    pub fn run(&self) -> Result<()> {
+        let path = self.path.clone().unwrap_or_else(Config::default_path);
+        match self.op {
+            ConfigOp::Init { force } => init(&path, force),
+            ConfigOp::Show => self.show(&path),
+            ConfigOp::Validate => validate(&path),
+        }
+    }
+
+    fn show(&self, path: &Path) -> Result<()> {
+        let file = if path.exists() {
+            let contents = std::fs::read_to_string(path)?;
+            Some(serde_yaml::from_str::<serde_yaml::Value>(&contents)?)
+        } else {
+            None
+        };
+        let mut config: Config = match &file {
+            Some(value) => serde_yaml::from_value(value.clone())?,
+            None => Config::default(),
+        };
+
+        let mut overrides: Vec<(String, ConfigSource)> = config
+            .apply_env(std::env::vars())?
+            .into_iter()
+            .map(|key| (key, ConfigSource::Env))
+            .collect();
+        if let Some(db) = &self.db {
+            config.db = Some(db.clone());
+            overrides.push(("db".to_string(), ConfigSource::Cli));
+        } else if let Some(name) = &self.profile {
+            config.db = Some(config.profile_path(name)?);
+            overrides.push(("db".to_string(), ConfigSource::Cli));
+        }
+        let workspace = match config.db {
+            Some(_) => None,
+            None => workspace::find(&std::env::current_dir()?)?,
+        };
+        if let Some(workspace) = &workspace {
+            config.db = Some(workspace.db.clone());
+            overrides.push(("db".to_string(), ConfigSource::Workspace));
+        }
+        if self.nc {
+            config.color = false;
+            overrides.push(("color".to_string(), ConfigSource::Cli));
+        }
+        if self.online {
+            config.default_offline = false;
+            overrides.push(("default_offline".to_string(), ConfigSource::Cli));
+        }
+        if let Some(kind) = self.picker {
+            config.picker.kind = kind;
+            overrides.push(("picker.kind".to_string(), ConfigSource::Cli));
+        }
+        if let Some(path) = &self.cacert {
+            config.fetch.tls_ca_file = Some(path.clone());
+            overrides.push(("fetch.tls_ca_file".to_string(), ConfigSource::Cli));
+        }
+
+        eprintln!(
+            "Config file: {}{}",
+            path.display(),
+            if file.is_some() { "" } else { " (not found)" }
+        );
+        if let Some(workspace) = &workspace {
+            eprintln!("Workspace: {}", workspace.root.display());
+        }
+        let values = config.effective_values(file.as_ref(), &overrides);
+        let width = values.iter().map(|v| v.key.len()).max().unwrap_or(0);
+        for value in values {
+            println!(
+                "{:width$} = {}  ({})",
+                value.key,
+                value.value,
+                value.source,
+                width = width
+            );
+        }
+        Ok(())
+    }
+}
+
+fn init(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        return Err(BukursError::InvalidInput(format!(
+            "{} already exists (use --force to overwrite)",
+            path.display()
+        )));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, DEFAULT_CONFIG_TEMPLATE)?;
+    eprintln!("✓ Wrote default config to {}", path.display());
+    Ok(())
+}
+
+fn validate(path: &Path) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| BukursError::Config(format!("{}: {}", path.display(), e)))?;
+
+    let issues = Config::validate_str(&contents);
+    if issues.is_empty() {
+        eprintln!("✓ {} is valid", path.display());
+        return Ok(());
+    }
+
+    for issue in &issues {
+        eprintln!("{}: {}", path.display(), issue);
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| matches!(issue, ConfigIssue::Invalid(_)))
+        .count();
+    let unknown = issues.len() - errors;
+    Err(BukursError::Config(format!(
+        "{} error(s), {} unknown key(s)",
+        errors, unknown
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_writes_template_and_refuses_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("config.yml");
+
+        init(&path, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            DEFAULT_CONFIG_TEMPLATE
+        );
+        assert!(validate(&path).is_ok());
+
+        std::fs::write(&path, "user_agent: custom\n").unwrap();
+        assert!(init(&path, false).is_err());
+        init(&path, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            DEFAULT_CONFIG_TEMPLATE
+        );
+    }
+
+    #[test]
+    fn test_validate_fails_on_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yml");
+
+        std::fs::write(&path, "fetch:\n  timeout: 5\n").unwrap();
+        assert!(validate(&path).is_err());
+
+        assert!(validate(&dir.path().join("missing.yml")).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use super::{AppContext, BukuCommand};
+use crate::interrupt::InterruptGuard;
+use bukurs::daemon::{Daemon, DaemonPaths, DaemonStatus, JobStatus};
+use bukurs::error::{BukursError, Result};
+use bukurs::utils::format_rfc3339;
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long `daemon stop` waits for the daemon to exit
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DaemonOp {
+    /// Run the configured jobs until stopped, in the background with `detach`
+    Run { detach: bool },
+    /// Print the running daemon's jobs and their last results
+    Status,
+    /// Ask the running daemon to exit
+    Stop,
+}
+
+/// Run scheduled maintenance jobs from the `daemon` config section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonCommand {
+    pub op: DaemonOp,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+impl BukuCommand for DaemonCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let paths = DaemonPaths::for_db(ctx.db_path);
+        let running = DaemonStatus::read(&paths.status)?.filter(|s| s.is_alive(now()));
+
+        match self.op {
+            DaemonOp::Run { detach } => {
+                if let Some(status) = running {
+                    return Err(BukursError::Other(format!(
+                        "A daemon is already running for this database (pid {})",
+                        status.pid
+                    )));
+                }
+                if detach {
+                    return spawn_detached();
+                }
+
+                let daemon = Daemon::new(ctx.db, ctx.db_path, ctx.config, now())?;
+                eprintln!(
+                    "Daemon running {} job(s); status in {}",
+                    daemon.status().jobs.len(),
+                    paths.status.display()
+                );
+                let interrupt = InterruptGuard::install();
+                daemon.run(interrupt.token())?;
+                eprintln!("Daemon stopped");
+                Ok(())
+            }
+            DaemonOp::Status => {
+                match running {
+                    Some(status) => {
+                        println!(
+                            "Running (pid {}) since {}",
+                            status.pid,
+                            format_rfc3339(status.started_at)
+                        );
+                        for job in &status.jobs {
+                            println!("{}", format_job(job));
+                        }
+                    }
+                    None => println!("Not running"),
+                }
+                Ok(())
+            }
+            DaemonOp::Stop => {
+                let Some(status) = running else {
+                    eprintln!("No daemon is running for this database");
+                    return Ok(());
+                };
+                paths.request_stop()?;
+                let deadline = SystemTime::now() + STOP_TIMEOUT;
+                while SystemTime::now() < deadline {
+                    thread::sleep(Duration::from_millis(200));
+                    if !DaemonStatus::read(&paths.status)?.is_some_and(|s| s.running) {
+                        eprintln!("Daemon (pid {}) stopped", status.pid);
+                        return Ok(());
+                    }
+                }
+                // A running job finishes before the daemon looks at the stop file
+                eprintln!(
+                    "Asked daemon (pid {}) to stop; it exits once its current job is done",
+                    status.pid
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Re-run this command line without `--detach`, detached from the terminal
+fn spawn_detached() -> Result<()> {
+    let args: Vec<_> = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--detach")
+        .collect();
+    let child = Command::new(std::env::current_exe()?)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    eprintln!(
+        "Daemon started (pid {}); check it with `bukurs daemon status`",
+        child.id()
+    );
+    Ok(())
+}
+
+/// Job name and kind, schedule, last run with its result, and next run
+fn format_job(job: &JobStatus) -> String {
+    let last = match (job.last_run, &job.last_result) {
+        (Some(ts), Some(result)) => format!(
+            "{} {}: {}",
+            format_rfc3339(ts),
+            if job.last_ok { "ok" } else { "failed" },
+            result
+        ),
+        _ => "never run".to_string(),
+    };
+    let next = job
+        .next_run
+        .map_or_else(|| "never".to_string(), format_rfc3339);
+    format!(
+        "  {} ({}, {})\n    last: {}\n    next: {}",
+        job.name, job.run, job.schedule, last, next
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::daemon::JobKind;
+    use bukurs::db::BukuDb;
+
+    #[test]
+    fn test_format_job() {
+        let mut job = JobStatus {
+            name: "nightly".to_string(),
+            run: JobKind::Backup,
+            schedule: "30 3 * * *".to_string(),
+            last_run: None,
+            last_result: None,
+            last_ok: false,
+            next_run: Some(0),
+        };
+        assert_eq!(
+            format_job(&job),
+            "  nightly (backup, 30 3 * * *)\n    last: never run\n    next: 1970-01-01T00:00:00Z"
+        );
+
+        job.last_run = Some(0);
+        job.last_result = Some("disk full".to_string());
+        job.next_run = None;
+        assert!(format_job(&job)
+            .ends_with("last: 1970-01-01T00:00:00Z failed: disk full\n    next: never"));
+    }
+
+    #[test]
+    fn test_status_and_stop_without_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+        let db = BukuDb::init(&db_path).unwrap();
+        let config = Config::default();
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+
+        for op in [DaemonOp::Status, DaemonOp::Stop] {
+            DaemonCommand { op }.execute(&ctx).unwrap();
+        }
+        assert!(!DaemonPaths::for_db(&db_path).stop.exists());
+
+        // Without jobs there is nothing to run
+        let run = DaemonCommand {
+            op: DaemonOp::Run { detach: false },
+        };
+        assert!(run.execute(&ctx).is_err());
+    }
+}
@@ -1,19 +1,28 @@
 use super::{AppContext, BukuCommand};
 use bukurs::error::Result;
 use bukurs::operations;
+use bukurs::service::{BookmarkService, ChangeSummary};
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
-use std::io::{self, Write};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteCommand {
     pub ids: Vec<String>,
     pub force: bool,
+    /// Narrow the selection in the fuzzy picker before deleting
+    pub pick: bool,
 }
 
 impl BukuCommand for DeleteCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        let operation = operations::prepare_delete(&self.ids, ctx.db)?;
+        let service = BookmarkService::new(ctx.db, ctx.config.fetch_options());
+        let mut operation = service.select(&self.ids)?;
+
+        if self.pick && !operation.bookmarks.is_empty() {
+            ctx.interaction.require_input("The bookmark picker")?;
+            let picked = super::helpers::select_bookmarks(ctx, &operation.bookmarks, None)?;
+            operation = operations::BookmarkSelection::from_picked(picked);
+        }
 
         if operation.bookmarks.is_empty() {
             match operation.mode {
@@ -45,7 +54,7 @@ impl BukuCommand for DeleteCommand {
             eprintln!("  {}. {} - {}", bookmark.id, bookmark.title, bookmark.url);
         }
 
-        // Ask for confirmation unless --force
+        // Ask for confirmation unless --force or --yes
         let confirmed = if self.force {
             true
         } else {
@@ -63,19 +72,13 @@ impl BukuCommand for DeleteCommand {
                     )
                 }
             };
-
-            print!("{}", prompt);
-            io::stdout().flush()?;
-
-            let mut response = String::new();
-            io::stdin().read_line(&mut response)?;
-            let response = response.trim().to_lowercase();
-            response == "y" || response == "yes"
+            ctx.interaction.confirm(&prompt)?
         };
 
+        let mut summary = ChangeSummary::new("delete");
         if confirmed {
             // Show progress bar for batch deletes
-            if operation.selected_ids.len() > 1 {
+            let count = if operation.selected_ids.len() > 1 {
                 let pb = ProgressBar::new(operation.selected_ids.len() as u64);
                 pb.set_style(
                     ProgressStyle::default_bar()
@@ -86,21 +89,22 @@ impl BukuCommand for DeleteCommand {
                 pb.set_message("Deleting bookmarks");
 
                 // The actual deletion happens in the database layer
-                let count = operations::execute_delete(&operation, ctx.db)?;
+                let count = service.delete(&operation)?;
 
                 pb.set_position(count as u64);
                 pb.finish_and_clear();
-
-                eprintln!("Deleted {} bookmark(s).", count);
+                count
             } else {
-                let count = operations::execute_delete(&operation, ctx.db)?;
-                eprintln!("Deleted {} bookmark(s).", count);
-            }
+                service.delete(&operation)?
+            };
+            eprintln!("Deleted {} bookmark(s).", count);
+            summary.ids = operation.selected_ids.clone();
+            summary.deleted = count;
         } else {
             eprintln!("Deletion cancelled.");
         }
 
-        Ok(())
+        ctx.report(&summary)
     }
 }
 
@@ -135,6 +139,8 @@ mod tests {
                 db: &self.db,
                 config: &self.config,
                 db_path: &self.db_path,
+                interaction: Default::default(),
+                output: Default::default(),
             }
         }
     }
@@ -151,6 +157,7 @@ mod tests {
         let cmd = DeleteCommand {
             ids: vec![id.to_string()],
             force: true, // Force to skip confirmation in tests
+            pick: false,
         };
 
         let result = cmd.execute(&env.ctx());
@@ -0,0 +1,63 @@
+use super::{AppContext, BukuCommand};
+use crate::format::OutputFormat;
+use bukurs::db::query::QueryResult;
+use bukurs::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// List the domains bookmarks are on with how many each has, most first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainsCommand {
+    /// Show at most this many domains
+    pub limit: Option<usize>,
+    pub format: Option<String>,
+    pub nc: bool,
+}
+
+impl BukuCommand for DomainsCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let format = OutputFormat::resolve(self.format.as_deref(), ctx.config)?;
+        let mut counts = ctx.db.get_domain_counts()?;
+        if counts.is_empty() {
+            eprintln!("No bookmarks found");
+            return Ok(());
+        }
+        if let Some(limit) = self.limit {
+            counts.truncate(limit);
+        }
+        format.print_rows(&domain_rows(counts), self.nc)
+    }
+}
+
+/// Domain counts as rows of a `domain` and a `bookmarks` column
+fn domain_rows(counts: Vec<(String, usize)>) -> QueryResult {
+    QueryResult {
+        columns: vec!["domain".to_string(), "bookmarks".to_string()],
+        rows: counts
+            .into_iter()
+            .map(|(domain, count)| vec![domain.into(), count.into()])
+            .collect(),
+        changed: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_domain_rows() {
+        let rows = domain_rows(vec![
+            ("github.com".to_string(), 3),
+            ("docs.rs".to_string(), 1),
+        ]);
+        assert_eq!(rows.columns, vec!["domain", "bookmarks"]);
+        assert_eq!(
+            rows.rows,
+            vec![
+                vec![json!("github.com"), json!(3)],
+                vec![json!("docs.rs"), json!(1)]
+            ]
+        );
+    }
+}
@@ -1,95 +1,161 @@
 use super::{AppContext, BukuCommand};
-use bukurs::error::Result;
+use crate::cli::get_exe_name;
+use crate::editor::NewBookmark;
+use crate::fetch_ui::{fetch_options, fetch_with_spinner};
+use bukurs::error::{BukursError, Result};
+use bukurs::fetch::FetchResult;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::Selector;
+use bukurs::service::{AddRequest, BookmarkService};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditCommand {
-    pub id: Option<usize>,
+    pub ids: Vec<String>,
+    pub search: Vec<String>,
 }
 
 impl BukuCommand for EditCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        match self.id {
-            Some(bookmark_id) => {
-                // Edit existing bookmark
-                let bookmark = ctx
-                    .db
-                    .get_rec_by_id(bookmark_id)?
-                    .ok_or_else(|| format!("Bookmark {} not found", bookmark_id))?;
+        ctx.interaction.require_input("The editor")?;
+        let selector = if !self.search.is_empty() {
+            Selector::Keywords(self.search.clone())
+        } else if !self.ids.is_empty() {
+            Selector::parse(&self.ids)?
+        } else {
+            return create_bookmark(ctx);
+        };
 
-                eprintln!("Opening bookmark #{} in editor...", bookmark_id);
+        let mut bookmarks = selector.resolve(ctx.db)?.bookmarks;
+        match bookmarks.len() {
+            0 => Err("No bookmarks found".into()),
+            1 => edit_single(ctx, bookmarks.remove(0)),
+            _ => edit_batch(ctx, &bookmarks),
+        }
+    }
+}
+
+/// Edit one existing bookmark
+fn edit_single(ctx: &AppContext, bookmark: Bookmark) -> Result<()> {
+    let bookmark_id = bookmark.id;
+    eprintln!("Opening bookmark #{} in editor...", bookmark_id);
 
-                match crate::editor::edit_bookmark(&bookmark) {
-                    Ok(edited) => {
-                        match ctx.db.update_rec_partial(
-                            bookmark_id,
-                            Some(&edited.url),
-                            Some(&edited.title),
-                            Some(&edited.tags),
-                            Some(&edited.description),
-                            None,
-                        ) {
-                            Ok(()) => {
-                                eprintln!("Bookmark {} updated successfully", bookmark_id);
-                                Ok(())
-                            }
-                            Err(e) => {
-                                if let rusqlite::Error::SqliteFailure(err, _) = &e {
-                                    // SQLITE_CONSTRAINT_UNIQUE = 2067
-                                    if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
-                                    {
-                                        return Err(bukurs::error::BukursError::InvalidInput(
-                                            format!("Duplicate URL: {}", edited.url),
-                                        ));
-                                    }
-                                }
-                                Err(bukurs::error::BukursError::Database(e))
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Edit cancelled or failed: {}", e);
-                        Ok(())
-                    }
+    match crate::editor::edit_bookmark(&bookmark) {
+        Ok(edited) => {
+            let _lock = ctx.db.write_lock()?;
+            match ctx.db.update_rec_partial(
+                bookmark_id,
+                Some(&edited.url),
+                Some(&edited.title),
+                Some(&edited.tags),
+                Some(&edited.description),
+                None,
+            ) {
+                Ok(()) => {
+                    eprintln!("Bookmark {} updated successfully", bookmark_id);
+                    Ok(())
                 }
+                Err(e) => Err(duplicate_url_error(e, &edited.url)),
             }
-            None => {
-                // Create new bookmark
-                eprintln!("Opening editor to create new bookmark...");
+        }
+        Err(e) => {
+            eprintln!("Edit cancelled or failed: {}", e);
+            Ok(())
+        }
+    }
+}
 
-                match crate::editor::edit_new_bookmark() {
-                    Ok(new_bookmark) => {
-                        match ctx.db.add_rec(
-                            &new_bookmark.url,
-                            &new_bookmark.title,
-                            &new_bookmark.tags,
-                            &new_bookmark.description,
-                            None, // parent_id
-                        ) {
-                            Ok(id) => {
-                                eprintln!("✓ Created new bookmark at index {}", id);
-                                Ok(())
-                            }
-                            Err(e) => {
-                                if let rusqlite::Error::SqliteFailure(err, _) = &e {
-                                    // SQLITE_CONSTRAINT_UNIQUE = 2067
-                                    if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
-                                    {
-                                        return Err(bukurs::error::BukursError::InvalidInput(
-                                            format!("Duplicate URL: {}", new_bookmark.url),
-                                        ));
-                                    }
-                                }
-                                Err(bukurs::error::BukursError::Database(e))
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Creation cancelled or failed: {}", e);
-                        Ok(())
-                    }
-                }
+/// Edit several bookmarks in one buffer and apply the changes as one undoable batch
+fn edit_batch(ctx: &AppContext, bookmarks: &[Bookmark]) -> Result<()> {
+    eprintln!("Opening {} bookmarks in editor...", bookmarks.len());
+
+    let changed = match crate::editor::edit_bookmarks(bookmarks) {
+        Ok(changed) => changed,
+        Err(e) => {
+            eprintln!("Edit cancelled or failed: {}", e);
+            return Ok(());
+        }
+    };
+
+    if changed.is_empty() {
+        eprintln!("No changes made");
+        return Ok(());
+    }
+
+    let urls = changed
+        .iter()
+        .map(|b| b.url.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _lock = ctx.db.write_lock()?;
+    let updated = ctx
+        .db
+        .update_rec_batch_full(&changed)
+        .map_err(|e| duplicate_url_error(e, &urls))?;
+    eprintln!(
+        "✓ Updated {} bookmark(s) (run `bukurs undo` to revert)",
+        updated
+    );
+    Ok(())
+}
+
+/// Create a new bookmark from the editor template
+fn create_bookmark(ctx: &AppContext) -> Result<()> {
+    eprintln!("Opening editor to create new bookmark...");
+
+    let NewBookmark { bookmark, fetch } = match crate::editor::edit_new_bookmark() {
+        Ok(new_bookmark) => new_bookmark,
+        Err(e) => {
+            eprintln!("Creation cancelled or failed: {}", e);
+            return Ok(());
+        }
+    };
+
+    let options = fetch_options(ctx.config, None);
+    let needs_metadata = bookmark.title.is_empty() || bookmark.description.is_empty();
+    let fetched = if fetch && needs_metadata {
+        match fetch_with_spinner(&bookmark.url, &options) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                // Queued for `fetch-pending` by the service
+                eprintln!("Warning: Failed to fetch metadata: {}", e);
+                None
             }
         }
+    } else {
+        // Nothing to fetch; an empty result keeps the bookmark out of the fetch queue
+        Some(FetchResult::default())
+    };
+
+    let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    let request = AddRequest {
+        url: bookmark.url.clone(),
+        title: non_empty(&bookmark.title),
+        tags: non_empty(&bookmark.tags).map(|tags| vec![tags]),
+        desc: non_empty(&bookmark.description),
+        later: false,
+        expires_at: None,
+    };
+    let _lock = ctx.db.write_lock()?;
+    let outcome = BookmarkService::new(ctx.db, options).add(&request, fetched.as_ref())?;
+
+    eprintln!("✓ Created new bookmark at index {}", outcome.id);
+    if outcome.queued {
+        eprintln!(
+            "Queued for metadata; run `{} fetch-pending` when online",
+            get_exe_name()
+        );
+    }
+    Ok(())
+}
+
+/// Report unique constraint violations as a duplicate URL
+fn duplicate_url_error(e: rusqlite::Error, url: &str) -> BukursError {
+    if let rusqlite::Error::SqliteFailure(err, _) = &e {
+        // SQLITE_CONSTRAINT_UNIQUE = 2067
+        if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE {
+            return BukursError::InvalidInput(format!("Duplicate URL: {}", url));
+        }
     }
+    BukursError::Database(e)
 }
@@ -0,0 +1,191 @@
+//! Running external commands on bookmarks, like `fd --exec`
+//!
+//! The command line is split into words like a shell would, but is run
+//! directly rather than through a shell, so titles and URLs are never
+//! interpreted. Each word may use the output template placeholders
+//! (`{url}`, `{id}`, `{title}`, `{tags}`, `{desc}`); a command without any
+//! gets the URL appended.
+
+use crate::format::template::Template;
+use crate::shell::tokenize;
+use bukurs::error::{BukursError, Result};
+use bukurs::models::bookmark::Bookmark;
+use rayon::prelude::*;
+use std::io::Write;
+use std::process::Command;
+
+/// A parsed `--exec` command line
+#[derive(Debug)]
+pub struct ExecCommand {
+    program: String,
+    args: Vec<Template>,
+}
+
+impl ExecCommand {
+    pub fn parse(command: &str) -> Result<Self> {
+        let mut words = tokenize(command)?.into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| BukursError::InvalidInput("Empty --exec command".to_string()))?;
+        let mut args = words
+            .map(|word| Template::parse(&word))
+            .collect::<Result<Vec<_>>>()?;
+        if !args.iter().any(Template::has_fields) {
+            args.push(Template::parse("{url}")?);
+        }
+        Ok(Self { program, args })
+    }
+
+    /// Arguments for running once per bookmark
+    fn args_for(&self, bookmark: &Bookmark) -> Vec<String> {
+        self.args.iter().map(|arg| arg.render(bookmark)).collect()
+    }
+
+    /// Arguments for running once on every bookmark: each word with a
+    /// placeholder is repeated per bookmark, in order
+    fn batch_args(&self, bookmarks: &[Bookmark]) -> Vec<String> {
+        let mut args = Vec::new();
+        for arg in &self.args {
+            if arg.has_fields() {
+                args.extend(bookmarks.iter().map(|b| arg.render(b)));
+            } else {
+                // Renders the same for any bookmark
+                args.extend(bookmarks.first().map(|b| arg.render(b)));
+            }
+        }
+        args
+    }
+
+    /// Run the command for each bookmark, `jobs` at a time (default: one per CPU)
+    ///
+    /// Each command's output is printed in one piece when it finishes, so
+    /// parallel runs don't interleave. Fails if any command failed.
+    pub fn run_each(&self, bookmarks: &[Bookmark], jobs: Option<usize>) -> Result<()> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.unwrap_or(0))
+            .build()
+            .map_err(|e| BukursError::Other(e.to_string()))?;
+
+        let failed = pool.install(|| {
+            bookmarks
+                .par_iter()
+                .filter(|bookmark| !self.run(&self.args_for(bookmark), true))
+                .count()
+        });
+        check_failures(failed)
+    }
+
+    /// Run the command once with every bookmark's arguments
+    pub fn run_batch(&self, bookmarks: &[Bookmark]) -> Result<()> {
+        if bookmarks.is_empty() {
+            return Ok(());
+        }
+        let ok = self.run(&self.batch_args(bookmarks), false);
+        check_failures(usize::from(!ok))
+    }
+
+    /// Run with `args`, returning whether it succeeded
+    fn run(&self, args: &[String], capture: bool) -> bool {
+        let mut command = Command::new(&self.program);
+        command.args(args);
+
+        if !capture {
+            return match command.status() {
+                Ok(status) => status.success(),
+                Err(e) => {
+                    eprintln!("✗ {}: {}", self.program, e);
+                    false
+                }
+            };
+        }
+
+        match command.output() {
+            Ok(output) => {
+                // Hold both locks so one command's output stays together
+                let mut stdout = std::io::stdout().lock();
+                let mut stderr = std::io::stderr().lock();
+                let _ = stdout.write_all(&output.stdout);
+                let _ = stdout.flush();
+                let _ = stderr.write_all(&output.stderr);
+                output.status.success()
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {}", self.program, e);
+                false
+            }
+        }
+    }
+}
+
+fn check_failures(failed: usize) -> Result<()> {
+    if failed > 0 {
+        return Err(BukursError::Other(format!("{} command(s) failed", failed)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn bookmarks() -> Vec<Bookmark> {
+        vec![
+            Bookmark::new(
+                1,
+                "https://a.com".to_string(),
+                "A site".to_string(),
+                ",".to_string(),
+                String::new(),
+            ),
+            Bookmark::new(
+                2,
+                "https://b.com".to_string(),
+                "B".to_string(),
+                ",".to_string(),
+                String::new(),
+            ),
+        ]
+    }
+
+    #[rstest]
+    #[case("curl -I {url}", &["-I", "https://a.com"])]
+    #[case("echo", &["https://a.com"])]
+    #[case("echo '{id}: {title}' x", &["1: A site", "x"])]
+    fn test_args_for(#[case] command: &str, #[case] expected: &[&str]) {
+        let exec = ExecCommand::parse(command).unwrap();
+        assert_eq!(exec.args_for(&bookmarks()[0]), expected);
+    }
+
+    #[test]
+    fn test_batch_args() {
+        let exec = ExecCommand::parse("open -n {url} --").unwrap();
+        assert_eq!(
+            exec.batch_args(&bookmarks()),
+            vec!["-n", "https://a.com", "https://b.com", "--"]
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("echo {nope}")]
+    #[case("echo 'unterminated")]
+    fn test_parse_errors(#[case] command: &str) {
+        assert!(ExecCommand::parse(command).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_reports_failures() {
+        let ok = ExecCommand::parse("true {id}").unwrap();
+        ok.run_each(&bookmarks(), Some(2)).unwrap();
+        ok.run_batch(&bookmarks()).unwrap();
+
+        let failing = ExecCommand::parse("false {id}").unwrap();
+        assert!(failing.run_each(&bookmarks(), Some(1)).is_err());
+        assert!(ExecCommand::parse("bukurs-no-such-program")
+            .unwrap()
+            .run_batch(&bookmarks())
+            .is_err());
+    }
+}
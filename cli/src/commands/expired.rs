@@ -0,0 +1,45 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::expiry;
+use bukurs::utils::format_rfc3339;
+use serde::{Deserialize, Serialize};
+
+/// List bookmarks whose expiry has passed, or delete them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpiredCommand {
+    pub delete: bool,
+}
+
+impl BukuCommand for ExpiredCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let now = expiry::now();
+        if self.delete {
+            let deleted = expiry::delete_expired(ctx.db, now)?;
+            eprintln!("Deleted {} expired bookmark(s)", deleted);
+            return Ok(());
+        }
+
+        let entries = expiry::expired(ctx.db, now)?;
+        if entries.is_empty() {
+            eprintln!("No expired bookmarks.");
+            return Ok(());
+        }
+        for entry in &entries {
+            let bookmark = &entry.bookmark;
+            let title = if bookmark.title.is_empty() {
+                &bookmark.url
+            } else {
+                &bookmark.title
+            };
+            println!(
+                "{}. {} (expired {})",
+                bookmark.id,
+                title,
+                format_rfc3339(entry.expired_at)
+            );
+            println!("   > {}", bookmark.url);
+        }
+        eprintln!("{} expired", entries.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use super::{AppContext, BukuCommand};
+use crate::fetch_ui::{categorize_error, fetch_options, fetch_pool, ProgressBarSink};
+use bukurs::error::{BukursError, Result};
+use bukurs::fetch;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::progress::ProgressSink;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Fetch metadata for bookmarks queued while offline or after a failed fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchPendingCommand {
+    pub timeout: Option<u64>,
+}
+
+impl BukuCommand for FetchPendingCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let pending = ctx.db.get_pending_fetch()?;
+        if pending.is_empty() {
+            eprintln!("No bookmarks waiting for metadata.");
+            return Ok(());
+        }
+
+        let options = fetch_options(ctx.config, self.timeout);
+        // Fail once rather than count every bookmark as a failed fetch
+        if options.offline {
+            return Err(BukursError::Offline);
+        }
+
+        eprintln!("Fetching metadata for {} bookmark(s)...", pending.len());
+        let progress = ProgressBarSink::new();
+        progress.start("Fetching", Some(pending.len() as u64));
+
+        let results: Vec<(&Bookmark, Result<fetch::FetchResult>)> =
+            fetch_pool(&options)?.install(|| {
+                pending
+                    .par_iter()
+                    .map(|bookmark| {
+                        let result = fetch::fetch_data(&bookmark.url, &options);
+                        progress.advance(1, None);
+                        (bookmark, result)
+                    })
+                    .collect()
+            });
+        progress.finish();
+
+        // Fetching is done; other commands only wait for the writes
+        let _lock = ctx.db.write_lock()?;
+        let mut fetched = Vec::new();
+        let mut word_counts = Vec::new();
+        let mut languages = Vec::new();
+        let mut failed = 0;
+        for (bookmark, result) in results {
+            match result {
+                Ok(result) => {
+                    if result.word_count > 0 {
+                        word_counts.push((bookmark.url.as_str(), result.word_count));
+                    }
+                    if let Some(lang) = result.lang.clone() {
+                        languages.push((bookmark.url.as_str(), lang));
+                    }
+                    fetched.push(Bookmark {
+                        title: result.title.to_string(),
+                        description: result.desc.to_string(),
+                        ..bookmark.clone()
+                    })
+                }
+                Err(e) => {
+                    eprintln!(
+                        "✗ {}. {} ({})",
+                        bookmark.id,
+                        bookmark.url,
+                        categorize_error(&e)
+                    );
+                    ctx.db.record_fetch_failure(bookmark.id, &e.to_string())?;
+                    failed += 1;
+                }
+            }
+        }
+
+        // One batch, so a single undo reverts every filled-in title and description
+        let updated = ctx.db.apply_fetched_metadata(&fetched)?;
+        ctx.db.set_word_counts(&word_counts)?;
+        ctx.db.set_languages(&languages)?;
+        eprintln!(
+            "✓ Fetched {} bookmark(s), {} updated",
+            fetched.len(),
+            updated
+        );
+        if failed > 0 {
+            eprintln!("  {} still pending; run again later", failed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fetch_pending_keeps_failures_queued() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+
+        // .invalid never resolves, so the fetch fails without network access
+        db.add_rec("https://nonexistent.invalid/", "", ",", "", None)
+            .unwrap();
+        db.queue_fetch(&["https://nonexistent.invalid/"]).unwrap();
+
+        FetchPendingCommand { timeout: Some(5) }
+            .execute(&ctx)
+            .unwrap();
+        assert_eq!(db.get_pending_fetch().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_fetch_pending_empty_queue() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        assert!(FetchPendingCommand { timeout: None }.execute(&ctx).is_ok());
+    }
+}
@@ -1,32 +1,131 @@
-use crate::format::OutputFormat;
+use super::delete::DeleteCommand;
+use super::edit::EditCommand;
+use super::{AppContext, BukuCommand};
+use crate::format::{ListingBadges, OutputFormat};
+use crate::picker;
 use bukurs::browser;
+use bukurs::db::BukuDb;
 use bukurs::error::Result;
+use bukurs::fuzzy::{PickAction, Picked, PickerKind};
 use bukurs::models::bookmark::Bookmark;
 
-/// Helper function to handle fuzzy search selection and open/display the selected bookmark
+/// Helper function to handle fuzzy search selection and open/display the selected bookmarks
 ///
-/// This function is shared across multiple commands (NoCommand, SearchCommand, TagCommand)
+/// This function is shared across multiple commands (NoCommand, TagCommand)
 /// to avoid code duplication for the common pattern of:
-/// 1. Run fuzzy picker on bookmarks
-/// 2. Either open the selected bookmark in browser or display it
+/// 1. Run fuzzy picker on bookmarks (Tab marks several)
+/// 2. Either open the selected bookmarks in browser or display them, or run
+///    the action of the key they were picked with
+///
+/// With `--no-input` the picker is skipped and every record is used.
 pub fn handle_bookmark_selection(
+    ctx: &AppContext,
     records: &[Bookmark],
     query: Option<String>,
     open: bool,
-    format: Option<&str>,
+    format: &OutputFormat,
     nc: bool,
 ) -> Result<()> {
-    if let Some(selected) = bukurs::fuzzy::run_fuzzy_search(records, query)? {
-        if open {
-            eprintln!("Opening: {}", selected.url);
-            browser::open_url(&selected.url)?;
-        } else {
-            let output_format: OutputFormat = format
-                .map(OutputFormat::from_string)
-                .unwrap_or(OutputFormat::Colored);
-            let selected = vec![selected];
-            output_format.print_bookmarks(&selected, nc);
+    let Some(picked) = pick_bookmarks(ctx, records, query)? else {
+        return Ok(());
+    };
+    if run_pick_action(ctx, &picked)? {
+        return Ok(());
+    }
+
+    let selected = picked.bookmarks;
+    if open {
+        open_bookmarks(ctx.db, &selected)?;
+    } else {
+        format.print_listing(&selected, &ListingBadges::load(ctx.db)?, nc);
+    }
+    Ok(())
+}
+
+/// Run the fuzzy picker on `records`, or take them all with `--no-input`
+pub fn select_bookmarks(
+    ctx: &AppContext,
+    records: &[Bookmark],
+    query: Option<String>,
+) -> Result<Vec<Bookmark>> {
+    if ctx.interaction.no_input {
+        Ok(records.to_vec())
+    } else if ctx.config.picker.kind == PickerKind::External {
+        picker::run_external(&ctx.config.picker.command, records, query.as_deref())
+    } else {
+        bukurs::fuzzy::run_fuzzy_search_multi(records, query)
+    }
+}
+
+/// Run the fuzzy picker on `records` with the configured action keys, or take
+/// them all with `--no-input`; None when the picker is dismissed
+pub fn pick_bookmarks(
+    ctx: &AppContext,
+    records: &[Bookmark],
+    query: Option<String>,
+) -> Result<Option<Picked>> {
+    if records.is_empty() {
+        return Ok(None);
+    }
+    if ctx.interaction.no_input {
+        return Ok(Some(Picked {
+            action: PickAction::Select,
+            bookmarks: records.to_vec(),
+        }));
+    }
+    if ctx.config.picker.kind == PickerKind::External {
+        // Action keys belong to the built-in picker
+        let bookmarks =
+            picker::run_external(&ctx.config.picker.command, records, query.as_deref())?;
+        return Ok((!bookmarks.is_empty()).then_some(Picked {
+            action: PickAction::Select,
+            bookmarks,
+        }));
+    }
+    bukurs::fuzzy::run_fuzzy_search(records, query, &ctx.config.picker.keys)
+}
+
+/// Run the action bound to the key bookmarks were picked with; false when
+/// they were picked with Enter, which the command handles itself
+pub fn run_pick_action(ctx: &AppContext, picked: &Picked) -> Result<bool> {
+    let ids = || -> Vec<String> { picked.bookmarks.iter().map(|b| b.id.to_string()).collect() };
+    match picked.action {
+        PickAction::Select => return Ok(false),
+        PickAction::Open => open_bookmarks(ctx.db, &picked.bookmarks)?,
+        PickAction::CopyUrl => {
+            let urls: Vec<&str> = picked.bookmarks.iter().map(|b| b.url.as_str()).collect();
+            browser::copy_to_clipboard(&urls.join("\n"))?;
+            eprintln!("Copied {} URL(s) to the clipboard", urls.len());
         }
+        // Edit takes the lock itself once the editor closes
+        PickAction::Edit => EditCommand {
+            ids: ids(),
+            search: Vec::new(),
+        }
+        .execute(ctx)?,
+        PickAction::Delete => {
+            // Deleting waits for other bukurs processes, like `delete` does
+            let _lock = ctx.config.database.lock_database(ctx.db_path)?;
+            DeleteCommand {
+                ids: ids(),
+                force: false,
+                pick: false,
+            }
+            .execute(ctx)?;
+        }
+    }
+    Ok(true)
+}
+
+/// Open each bookmark in the browser, counting a visit for `search --rank visits`
+pub fn open_bookmarks(db: &BukuDb, bookmarks: &[Bookmark]) -> Result<()> {
+    for bookmark in bookmarks {
+        eprintln!("Opening: {}", bookmark.url);
+        browser::open_url(&bookmark.url)?;
+    }
+    let ids: Vec<usize> = bookmarks.iter().map(|b| b.id).collect();
+    if let Err(e) = db.record_visits(&ids) {
+        log::warn!("Failed to record visits: {}", e);
     }
     Ok(())
 }
@@ -1,53 +1,121 @@
 use super::{AppContext, BukuCommand};
 use crate::cli::get_exe_name;
-use bukurs::error::Result;
-use bukurs::import_export;
-use console::Term;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::fetch_ui::ProgressBarSink;
+use crate::interrupt::InterruptGuard;
+use bukurs::collection;
+use bukurs::config::Config;
+use bukurs::error::{BukursError, Result};
+use bukurs::import_export::history::{self, HistoryEntry, HistoryFilter};
+use bukurs::import_export::{
+    self, BrowserProfile, BrowserType, Dump, DumpOptions, DuplicateStrategy, FeedFormat,
+    ImportPreview, ImportReport, ImportStats, RestoreOptions,
+};
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::BookmarkFilter;
+use bukurs::service::ChangeSummary;
+use bukurs::utils;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Truncate URL to fit terminal width, accounting for spinner, counter, and prefix
-fn truncate_url_for_display(url: &str, profile_name: &str) -> String {
-    let term = Term::stdout();
-    let terminal_width = term.size().1 as usize;
-
-    // Calculate space used by: spinner (2) + space (1) + brackets (2) + counter (max ~5) + space (1) + "Importing from " (16) + profile_name + ": " (2)
-    let prefix = format!("Importing from {}: ", profile_name);
-    let overhead = 2 + 1 + 2 + 5 + 1 + prefix.len();
-
-    // Reserve some space for safety and avoid wrapping
-    let available_width = if terminal_width > overhead + 10 {
-        terminal_width - overhead
-    } else {
-        60 // Fallback to reasonable default
-    };
+/// Print the outcome of a dry-run import, as JSON when `-f json` is given
+fn print_preview(preview: &ImportPreview, format: Option<&str>) -> Result<()> {
+    if format == Some("json") {
+        println!("{}", serde_json::to_string_pretty(preview)?);
+    }
+    eprintln!(
+        "Dry run: {} bookmark(s) would be added, {} updated, {} skipped",
+        preview.added, preview.updated, preview.skipped
+    );
+    eprintln!("No changes were written to the database.");
+    Ok(())
+}
 
-    if url.len() <= available_width {
-        url.to_string()
-    } else if available_width > 3 {
-        format!("{}...", &url[..available_width.saturating_sub(3)])
-    } else {
-        "...".to_string()
+/// Print the outcome of an import, with a summary on stdout for `--output json`
+fn print_stats(ctx: &AppContext, stats: &ImportStats, source: &str) -> Result<()> {
+    eprintln!(
+        "✓ Successfully imported {} bookmark(s) from {}",
+        stats.added, source
+    );
+    if stats.updated > 0 || stats.skipped > 0 {
+        eprintln!("  {} updated, {} skipped", stats.updated, stats.skipped);
     }
+    ctx.report(&ChangeSummary::from(stats))
 }
 
+/// Files at least this large are imported in parallel without `--parallel`
+const PARALLEL_IMPORT_THRESHOLD: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportCommand {
     pub file: String,
+    pub dry_run: bool,
+    pub parallel: bool,
+    pub on_duplicate: DuplicateStrategy,
+    pub format: Option<String>,
+    /// Write the skipped and merged bookmarks here, one JSON object per line
+    pub report: Option<PathBuf>,
 }
 
 impl BukuCommand for ImportCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        let count = if ctx.config.import_threads > 1 {
-            eprintln!("Importing with {} threads...", ctx.config.import_threads);
-            import_export::import_bookmarks_parallel(ctx.db, &self.file, ctx.config.import_threads)?
+        if self.dry_run {
+            let preview = import_export::preview_bookmarks(ctx.db, &self.file, self.on_duplicate)?;
+            return print_preview(&preview, self.format.as_deref());
+        }
+
+        let large_file = std::fs::metadata(&self.file)
+            .map(|m| m.len() >= PARALLEL_IMPORT_THRESHOLD)
+            .unwrap_or(false);
+        let threads = if self.parallel {
+            ctx.config.import_threads.max(2)
         } else {
-            import_export::import_bookmarks(ctx.db, &self.file)?
+            ctx.config.import_threads
         };
-        eprintln!(
-            "✓ Successfully imported {} bookmark(s) from {}",
-            count, self.file
-        );
+        let report = self
+            .report
+            .as_deref()
+            .map(ImportReport::create)
+            .transpose()?;
+
+        let stats = if threads > 1 && (self.parallel || large_file) {
+            eprintln!("Importing with {} threads...", threads);
+
+            let interrupt = InterruptGuard::install();
+            import_export::import_bookmarks_parallel_with_progress(
+                ctx.db,
+                &self.file,
+                threads,
+                self.on_duplicate,
+                &ProgressBarSink::new(),
+                interrupt.token(),
+                report.as_ref(),
+            )?
+        } else {
+            import_export::import_bookmarks_with_progress(
+                ctx.db,
+                &self.file,
+                self.on_duplicate,
+                &ProgressBarSink::new(),
+                report.as_ref(),
+            )?
+        };
+        if let (Some(report), Some(path)) = (report, &self.report) {
+            let entries = report.finish()?;
+            eprintln!(
+                "  {} skipped or merged bookmark(s) listed in {}",
+                entries,
+                path.display()
+            );
+        }
+        print_stats(ctx, &stats, &self.file)?;
+        if stats.cancelled {
+            eprintln!(
+                "Interrupted: the counts above were committed; unfinished chunks were rolled back"
+            );
+            return Err(BukursError::Cancelled);
+        }
         Ok(())
     }
 }
@@ -57,6 +125,13 @@ pub struct ImportBrowsersCommand {
     pub list: bool,
     pub all: bool,
     pub browsers: Option<Vec<String>>,
+    /// Bookmarks file or profile directory to import instead of detected profiles
+    pub path: Option<PathBuf>,
+    /// Format of `path`, detected from the file when unset
+    pub browser_type: Option<BrowserType>,
+    pub dry_run: bool,
+    pub on_duplicate: DuplicateStrategy,
+    pub format: Option<String>,
 }
 
 impl BukuCommand for ImportBrowsersCommand {
@@ -72,46 +147,41 @@ impl BukuCommand for ImportBrowsersCommand {
                     eprintln!("  • {}", profile.display_string());
                 }
             }
+        } else if let Some(path) = &self.path {
+            let profile = BrowserProfile::from_path(path, self.browser_type.clone())?;
+            let profiles = std::slice::from_ref(&profile);
+            if self.dry_run {
+                let preview = import_export::preview_profiles(ctx.db, profiles, self.on_duplicate)?;
+                print_preview(&preview, self.format.as_deref())?;
+            } else {
+                let stats = import_export::import_profiles_with_progress(
+                    ctx.db,
+                    profiles,
+                    self.on_duplicate,
+                    &ProgressBarSink::new(),
+                )?;
+                print_stats(ctx, &stats, "browsers")?;
+            }
+        } else if self.dry_run && (self.all || self.browsers.is_some()) {
+            let names = if self.all {
+                None
+            } else {
+                self.browsers.as_deref()
+            };
+            let preview = import_export::preview_browsers(ctx.db, names, self.on_duplicate)?;
+            print_preview(&preview, self.format.as_deref())?;
         } else if self.all {
             // Import from all detected browsers with progress bar
             eprintln!("Importing from all detected browsers...");
 
-            // Create progress bar with spinner
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} [{pos}] {msg}")
-                    .unwrap(),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
             let result = import_export::auto_import_all_with_progress(
                 ctx.db,
-                |profile, _current, _total, url| {
-                    if let Some(u) = url {
-                        // Increment position for display (this is just for showing progress, not actual count)
-                        pb.inc(1);
-                        // Truncate URL based on terminal width
-                        let display_url = truncate_url_for_display(u, &profile.display_string());
-                        pb.set_message(format!(
-                            "Importing from {}: {}",
-                            profile.display_string(),
-                            display_url
-                        ));
-                    } else {
-                        // Switching to new profile, reset counter
-                        pb.set_position(0);
-                        pb.set_message(format!("Importing from {}", profile.display_string()));
-                    }
-                },
+                self.on_duplicate,
+                &ProgressBarSink::new(),
             );
 
-            pb.finish_and_clear();
-
             match result {
-                Ok(count) => {
-                    eprintln!("✓ Successfully imported {} total bookmark(s)", count);
-                }
+                Ok(stats) => print_stats(ctx, &stats, "browsers")?,
                 Err(e) => {
                     eprintln!("Error during import: {}", e);
                     return Err(e);
@@ -121,50 +191,22 @@ impl BukuCommand for ImportBrowsersCommand {
             // Import from specific browsers with progress bar
             eprintln!("Importing from selected browsers: {:?}", browser_list);
 
-            // Create progress bar with spinner
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} [{pos}] {msg}")
-                    .unwrap(),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
             let result = import_export::import_from_selected_browsers_with_progress(
                 ctx.db,
                 browser_list,
-                |profile, _current, _total, url| {
-                    if let Some(u) = url {
-                        // Increment position for display (this is just for showing progress, not actual count)
-                        pb.inc(1);
-                        // Truncate URL based on terminal width
-                        let display_url = truncate_url_for_display(u, &profile.display_string());
-                        pb.set_message(format!(
-                            "Importing from {}: {}",
-                            profile.display_string(),
-                            display_url
-                        ));
-                    } else {
-                        // Switching to new profile, reset counter
-                        pb.set_position(0);
-                        pb.set_message(format!("Importing from {}", profile.display_string()));
-                    }
-                },
+                self.on_duplicate,
+                &ProgressBarSink::new(),
             );
 
-            pb.finish_and_clear();
-
             match result {
-                Ok(count) => {
-                    eprintln!("✓ Successfully imported {} total bookmark(s)", count);
-                }
+                Ok(stats) => print_stats(ctx, &stats, "browsers")?,
                 Err(e) => {
                     eprintln!("Error: {}", e);
                     return Err(e);
                 }
             }
         } else {
-            eprintln!("Error: Please specify --list, --all, or --browsers");
+            eprintln!("Error: Please specify --list, --all, --browsers or --path");
             eprintln!("Examples:");
             eprintln!("  {} import-browsers --list", get_exe_name());
             eprintln!("  {} import-browsers --all", get_exe_name());
@@ -172,21 +214,329 @@ impl BukuCommand for ImportBrowsersCommand {
                 "  {} import-browsers --browsers chrome,firefox",
                 get_exe_name()
             );
+            eprintln!(
+                "  {} import-browsers --path ~/backup/places.sqlite",
+                get_exe_name()
+            );
             return Err("No import option specified".into());
         }
         Ok(())
     }
 }
 
+/// Bookmark frequently visited pages from browser history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportHistoryCommand {
+    pub browsers: Vec<String>,
+    /// History database or profile directory to read instead of detected profiles
+    pub path: Option<PathBuf>,
+    pub browser_type: Option<BrowserType>,
+    /// Only visits in this period, e.g. `30d`
+    pub since: Option<String>,
+    pub min_visits: u64,
+    pub dry_run: bool,
+}
+
+impl ImportHistoryCommand {
+    /// Candidate pages from every source, most visited first, without ones
+    /// already bookmarked
+    fn candidates(&self, ctx: &AppContext) -> Result<Vec<HistoryEntry>> {
+        let since = match &self.since {
+            Some(since) => {
+                let secs = utils::parse_duration(since).ok_or_else(|| {
+                    BukursError::InvalidInput(format!(
+                        "Invalid duration '{}' (expected e.g. 30d, 12h or 2w)",
+                        since
+                    ))
+                })?;
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                Some(now.saturating_sub(secs) as i64)
+            }
+            None => None,
+        };
+        let filter = HistoryFilter {
+            since,
+            min_visits: self.min_visits,
+        };
+
+        let sources = match &self.path {
+            Some(path) => vec![history::history_source_from_path(
+                path,
+                self.browser_type.clone(),
+            )?],
+            None => history::detect_history(&self.browsers)?
+                .into_iter()
+                .map(|(profile, path)| (profile.browser, path))
+                .collect(),
+        };
+        if sources.is_empty() {
+            return Err(BukursError::Browser(
+                "No history found for the given browsers".to_string(),
+            ));
+        }
+
+        let mut entries: Vec<HistoryEntry> = Vec::new();
+        for (browser, path) in &sources {
+            for entry in history::read_history(browser, path, filter)? {
+                // The same page in several profiles counts once, with its best count
+                match entries.iter_mut().find(|e| e.url == entry.url) {
+                    Some(existing) if existing.visits < entry.visits => *existing = entry,
+                    Some(_) => {}
+                    None => entries.push(entry),
+                }
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.visits));
+        history::without_bookmarked(ctx.db, entries)
+    }
+}
+
+impl BukuCommand for ImportHistoryCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let entries = self.candidates(ctx)?;
+        if entries.is_empty() {
+            eprintln!(
+                "No unbookmarked pages visited at least {} time(s)",
+                self.min_visits
+            );
+            return Ok(());
+        }
+
+        if self.dry_run {
+            for entry in &entries {
+                println!("{:>5}  {}  {}", entry.visits, entry.url, entry.title);
+            }
+            eprintln!(
+                "Dry run: {} page(s) could be bookmarked; nothing was written",
+                entries.len()
+            );
+            return Ok(());
+        }
+
+        // Pick with the bookmark picker: Tab marks pages, Enter bookmarks them
+        let chosen: Vec<HistoryEntry> = if ctx.interaction.assume_yes || ctx.interaction.no_input {
+            entries
+        } else {
+            let items: Vec<Bookmark> = entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    Bookmark::new(
+                        i + 1,
+                        entry.url.clone(),
+                        entry.title.clone(),
+                        ",".to_string(),
+                        format!("{} visits", entry.visits),
+                    )
+                })
+                .collect();
+            eprintln!(
+                "{} page(s) found; Tab marks the ones to bookmark, Enter confirms (--yes takes all)",
+                entries.len()
+            );
+            let picked = super::helpers::select_bookmarks(ctx, &items, None)?;
+            picked
+                .iter()
+                .map(|bookmark| entries[bookmark.id - 1].clone())
+                .collect()
+        };
+        if chosen.is_empty() {
+            eprintln!("Nothing selected");
+            return Ok(());
+        }
+
+        let lock = ctx.db.write_lock()?;
+        let stats = ctx.db.import_rec_batch(
+            &history::to_bookmarks(&chosen),
+            DuplicateStrategy::Skip,
+            |_url| {},
+        )?;
+        drop(lock);
+        print_stats(ctx, &stats, "history")?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportCommand {
     pub file: String,
+    pub feed: Option<FeedFormat>,
+    pub limit: Option<usize>,
+    /// Export only the bookmarks in this collection
+    pub collection: Option<String>,
+    /// Export only the bookmarks passing these tag, keyword and ID filters
+    pub filter: BookmarkFilter,
 }
 
 impl BukuCommand for ExportCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        import_export::export_bookmarks(ctx.db, &self.file)?;
+        let mut only: Option<HashSet<usize>> = self
+            .collection
+            .as_deref()
+            .map(|name| collection::bookmarks(ctx.db, name))
+            .transpose()?
+            .map(|bookmarks| bookmarks.iter().map(|b| b.id).collect());
+        if !self.filter.is_empty() {
+            let passed: HashSet<usize> = self
+                .filter
+                .apply(ctx.db.get_rec_all()?)?
+                .iter()
+                .map(|b| b.id)
+                .collect();
+            only = Some(match only {
+                Some(in_collection) => &in_collection & &passed,
+                None => passed,
+            });
+        }
+        if only.as_ref().is_some_and(HashSet::is_empty) {
+            eprintln!("No bookmarks matched; the export is empty");
+        }
+        match self.feed {
+            Some(format) => import_export::export_feed_selection(
+                ctx.db,
+                &self.file,
+                format,
+                self.limit.unwrap_or(import_export::DEFAULT_FEED_LIMIT),
+                only.as_ref(),
+            )?,
+            None => import_export::export_selection_with_progress(
+                ctx.db,
+                &self.file,
+                only.as_ref(),
+                &ProgressBarSink::new(),
+            )?,
+        }
         eprintln!("Exported bookmarks to {}", self.file);
         Ok(())
     }
 }
+
+/// Write the selected bookmarks as a static site to a directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishCommand {
+    pub dir: PathBuf,
+    pub tags: Vec<String>,
+    pub title: Option<String>,
+}
+
+impl BukuCommand for PublishCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let title = self
+            .title
+            .as_deref()
+            .unwrap_or(import_export::DEFAULT_SITE_TITLE);
+        let count = import_export::publish_site(ctx.db, &self.dir, &self.tags, title)?;
+        if count == 0 {
+            eprintln!("No bookmarks matched; wrote an empty page");
+        }
+        eprintln!(
+            "Published {} bookmark(s) to {}",
+            count,
+            self.dir.join("index.html").display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpCommand {
+    pub file: PathBuf,
+    /// Include the undo history
+    pub undo: bool,
+    /// Leave out the config snapshot
+    pub no_config: bool,
+}
+
+impl BukuCommand for DumpCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let options = DumpOptions {
+            undo_log: self.undo,
+        };
+        let config = (!self.no_config).then_some(ctx.config);
+        let dump = Dump::create(ctx.db, options, config)?;
+        dump.write(&self.file)?;
+        eprintln!(
+            "Dumped {} bookmark(s) (schema version {}) to {}",
+            dump.bookmarks.len(),
+            dump.schema_version,
+            self.file.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreCommand {
+    pub file: PathBuf,
+    /// Replace the bookmarks already in the database
+    pub replace: bool,
+    /// Write the dump's config snapshot to the config file, if there is none yet
+    pub restore_config: bool,
+}
+
+impl BukuCommand for RestoreCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let dump = Dump::read(&self.file)?;
+        if dump.is_from_newer_schema() {
+            eprintln!(
+                "Warning: {} comes from a newer bukurs ({}); fields this version doesn't know are dropped",
+                self.file.display(),
+                dump.bukurs_version
+            );
+        }
+        if self.replace {
+            let prompt = format!(
+                "Replace all bookmarks in {} with the {} from the dump? [y/N]: ",
+                ctx.db_path.display(),
+                dump.bookmarks.len()
+            );
+            if !ctx.interaction.confirm(&prompt)? {
+                eprintln!("Restore cancelled.");
+                return Ok(());
+            }
+        }
+
+        let stats = dump.restore(
+            ctx.db,
+            RestoreOptions {
+                replace: self.replace,
+            },
+        )?;
+        eprintln!(
+            "Restored {} bookmark(s) and {} undo entries from {}",
+            stats.bookmarks,
+            stats.undo_entries,
+            self.file.display()
+        );
+
+        if self.restore_config {
+            restore_config(&dump)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a dump's config snapshot where the config file is loaded from,
+/// never overwriting an existing file
+fn restore_config(dump: &Dump) -> Result<()> {
+    let Some(config) = &dump.config else {
+        eprintln!("The dump has no config snapshot");
+        return Ok(());
+    };
+    let path = Config::default_path();
+    if path.exists() {
+        eprintln!(
+            "Kept the existing config file {}; the dump's config was not restored",
+            path.display()
+        );
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, config)?;
+    eprintln!("Restored config to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use super::helpers::open_bookmarks;
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::later;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// List the read-later queue, or open and mark read its oldest bookmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaterCommand {
+    pub pop: bool,
+}
+
+impl BukuCommand for LaterCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if self.pop {
+            return match later::pop(ctx.db)? {
+                Some(bookmark) => {
+                    open_bookmarks(ctx.db, std::slice::from_ref(&bookmark))?;
+                    eprintln!("Marked bookmark {} as read", bookmark.id);
+                    Ok(())
+                }
+                None => {
+                    eprintln!("Nothing to read.");
+                    Ok(())
+                }
+            };
+        }
+
+        let queue = later::queue(ctx.db)?;
+        if queue.is_empty() {
+            eprintln!("Nothing to read. Add bookmarks with `add --later`.");
+            return Ok(());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        for entry in &queue {
+            let bookmark = &entry.bookmark;
+            let title = if bookmark.title.is_empty() {
+                &bookmark.url
+            } else {
+                &bookmark.title
+            };
+            println!(
+                "{}. {} ({})",
+                bookmark.id,
+                title,
+                format_age(entry.added_at, now)
+            );
+            println!("   > {}", bookmark.url);
+        }
+        eprintln!("{} unread", queue.len());
+        Ok(())
+    }
+}
+
+/// How long ago a bookmark was added, in whole days
+fn format_age(added_at: i64, now: i64) -> String {
+    if added_at <= 0 {
+        return "added: unknown".to_string();
+    }
+    match (now - added_at).max(0) / 86_400 {
+        0 => "added today".to_string(),
+        1 => "added 1 day ago".to_string(),
+        days => format!("added {} days ago", days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 1_000_000, "added: unknown")]
+    #[case(1_000_000, 1_000_100, "added today")]
+    #[case(1_000_000, 1_000_000 + 86_400, "added 1 day ago")]
+    #[case(1_000_000, 1_000_000 + 10 * 86_400 + 5, "added 10 days ago")]
+    #[case(1_000_000, 999_000, "added today")]
+    fn test_format_age(#[case] added_at: i64, #[case] now: i64, #[case] expected: &str) {
+        assert_eq!(format_age(added_at, now), expected);
+    }
+}
@@ -0,0 +1,178 @@
+use super::helpers::select_bookmarks;
+use super::{AppContext, BukuCommand};
+use crate::cli::get_exe_name;
+use crate::fetch_ui::fetch_options;
+use bukurs::error::Result;
+use bukurs::fetch::{self, PageLink};
+use bukurs::import_export::import::ParsedBookmark;
+use bukurs::import_export::DuplicateStrategy;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::Selector;
+use bukurs::service::ChangeSummary;
+use bukurs::tags::{format_tags, parse_tags};
+use bukurs::utils::url_domain;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Fetch a bookmarked page and bookmark links picked from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinksCommand {
+    pub id: String,
+    /// Only links to other sites
+    pub external: bool,
+    /// Print the links instead of picking ones to bookmark
+    pub list: bool,
+    /// Tags for the new bookmarks besides the page's own
+    pub tag: Option<Vec<String>>,
+    /// Fetch timeout in seconds, overriding the config
+    pub timeout: Option<u64>,
+}
+
+impl BukuCommand for LinksCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let bookmarks = Selector::parse(std::slice::from_ref(&self.id))?
+            .resolve(ctx.db)?
+            .bookmarks;
+        if bookmarks.len() != 1 {
+            return Err(format!("Bookmark {} not found", self.id).into());
+        }
+        let page = &bookmarks[0];
+
+        eprintln!("Fetching links from {}", page.url);
+        let links = fetch::fetch_links(&page.url, &fetch_options(ctx.config, self.timeout))?;
+        let found = links.len();
+        let known: HashSet<String> = ctx.db.get_rec_all()?.into_iter().map(|b| b.url).collect();
+        let links = self.new_links(page, links, &known);
+        if links.is_empty() {
+            eprintln!(
+                "No new links on the page ({} found, none left to bookmark)",
+                found
+            );
+            return Ok(());
+        }
+
+        if self.list {
+            for link in &links {
+                println!("{}\t{}", link.url, link.text);
+            }
+            return Ok(());
+        }
+
+        // New bookmarks inherit the page's tags; the picker shows them as candidates
+        let mut tags = parse_tags(&page.tags);
+        for tag in self.tag.iter().flatten().flat_map(parse_tags) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        let tags = format_tags(&tags);
+        let candidates: Vec<Bookmark> = links
+            .into_iter()
+            .enumerate()
+            .map(|(i, link)| Bookmark::new(i + 1, link.url, link.text, tags.clone(), String::new()))
+            .collect();
+        let picked = select_bookmarks(ctx, &candidates, None)?;
+        if picked.is_empty() {
+            return Ok(());
+        }
+
+        let entries: Vec<ParsedBookmark> = picked
+            .into_iter()
+            .map(|b| ParsedBookmark {
+                url: b.url,
+                title: b.title,
+                tags: b.tags,
+                desc: String::new(),
+                parent_id: None,
+                created_at: None,
+                flags: 0,
+            })
+            .collect();
+        let lock = ctx.db.write_lock()?;
+        let stats = ctx
+            .db
+            .import_rec_batch(&entries, DuplicateStrategy::Skip, |_url| {})?;
+        // Titles come from the link text; descriptions are fetched later
+        let urls: Vec<&str> = entries.iter().map(|e| e.url.as_str()).collect();
+        let queued = ctx.db.queue_fetch(&urls)?;
+        drop(lock);
+        eprintln!("✓ Added {} bookmark(s) from {}", stats.added, page.url);
+        if queued > 0 {
+            eprintln!(
+                "  {} queued for metadata; run `{} fetch-pending` to fetch it",
+                queued,
+                get_exe_name()
+            );
+        }
+        let mut summary = ChangeSummary::from(&stats);
+        summary.command = "links".to_string();
+        summary.queued = queued;
+        ctx.report(&summary)
+    }
+}
+
+impl LinksCommand {
+    /// `links` without those already bookmarked and, with `external`, those
+    /// on the page's own site
+    fn new_links(
+        &self,
+        page: &Bookmark,
+        mut links: Vec<PageLink>,
+        known: &HashSet<String>,
+    ) -> Vec<PageLink> {
+        links.retain(|link| !known.contains(&link.url));
+        if self.external {
+            let domain = url_domain(&page.url);
+            links.retain(|link| url_domain(&link.url) != domain);
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(url: &str) -> PageLink {
+        PageLink {
+            url: url.to_string(),
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_new_links() {
+        let page = Bookmark::new(
+            1,
+            "https://example.com/guide".to_string(),
+            String::new(),
+            ",".to_string(),
+            String::new(),
+        );
+        let links = vec![
+            link("https://example.com/intro"),
+            link("https://other.org/"),
+            link("https://known.org/"),
+        ];
+        let known = HashSet::from(["https://known.org/".to_string()]);
+        let mut command = LinksCommand {
+            id: "1".to_string(),
+            external: false,
+            list: true,
+            tag: None,
+            timeout: None,
+        };
+
+        let urls =
+            |links: Vec<PageLink>| -> Vec<String> { links.into_iter().map(|l| l.url).collect() };
+        assert_eq!(
+            urls(command.new_links(&page, links.clone(), &known)),
+            vec!["https://example.com/intro", "https://other.org/"]
+        );
+        command.external = true;
+        assert_eq!(
+            urls(command.new_links(&page, links, &known)),
+            vec!["https://other.org/"]
+        );
+    }
+}
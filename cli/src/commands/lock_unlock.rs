@@ -1,15 +1,65 @@
 use super::{AppContext, BukuCommand};
 use bukurs::crypto;
-use bukurs::error::Result;
+use bukurs::crypto::{Kdf, ARGON2_DEFAULT_MEMORY_KIB, ARGON2_MAX_MEMORY_KIB};
+use bukurs::error::{BukursError, Result};
+use bukurs::operations::encrypted;
+use bukurs::utils::parse_size;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockCommand {
     pub iterations: u32,
+    /// `sha256` or `argon2`
+    pub kdf: String,
+    /// Argon2 memory, e.g. `256MB`
+    pub memory: Option<String>,
+    /// Argon2 passes
+    pub passes: u32,
+}
+
+impl LockCommand {
+    fn kdf(&self) -> Result<Kdf> {
+        if self.kdf != "argon2" {
+            if self.memory.is_some() {
+                return Err(BukursError::InvalidInput(
+                    "--memory only applies to --kdf argon2".to_string(),
+                ));
+            }
+            if self.iterations == 0 {
+                return Err(BukursError::InvalidInput(
+                    "--iterations must be at least 1".to_string(),
+                ));
+            }
+            return Ok(Kdf::Sha256 {
+                iterations: self.iterations,
+            });
+        }
+
+        let memory_kib = match &self.memory {
+            Some(memory) => parse_size(memory)
+                .map(|bytes| bytes / 1024)
+                .filter(|kib| (8..=ARGON2_MAX_MEMORY_KIB as u64).contains(kib))
+                .ok_or_else(|| {
+                    BukursError::InvalidInput(format!(
+                        "Invalid memory size '{}' (expected 8KB to 4GB, e.g. 64MB)",
+                        memory
+                    ))
+                })? as u32,
+            None => ARGON2_DEFAULT_MEMORY_KIB,
+        };
+        if self.passes == 0 {
+            return Err(BukursError::InvalidInput(
+                "--passes must be at least 1".to_string(),
+            ));
+        }
+        Ok(Kdf::argon2id(memory_kib, self.passes))
+    }
 }
 
 impl BukuCommand for LockCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let kdf = self.kdf()?;
+        ctx.interaction.require_input("The password prompt")?;
         let password = rpassword::prompt_password("Enter password: ")?;
         let confirm = rpassword::prompt_password("Confirm password: ")?;
         if password != confirm {
@@ -18,12 +68,12 @@ impl BukuCommand for LockCommand {
 
         let enc_path = ctx.db_path.with_extension("db.enc");
         println!(
-            "Encrypting {} to {} with {} iterations...",
+            "Encrypting {} to {} ({})...",
             ctx.db_path.display(),
             enc_path.display(),
-            self.iterations
+            kdf
         );
-        crypto::BukuCrypt::encrypt_file(self.iterations, ctx.db_path, &enc_path, &password)?;
+        crypto::BukuCrypt::encrypt_file_with_kdf(kdf, ctx.db_path, &enc_path, &password)?;
         eprintln!("Encryption complete.");
         Ok(())
     }
@@ -36,6 +86,7 @@ pub struct UnlockCommand {
 
 impl BukuCommand for UnlockCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
+        ctx.interaction.require_input("The password prompt")?;
         let password = rpassword::prompt_password("Enter password: ")?;
         let enc_path = if ctx.db_path.extension().is_some_and(|ext| ext == "enc") {
             ctx.db_path.to_path_buf()
@@ -49,14 +100,115 @@ impl BukuCommand for UnlockCommand {
             enc_path.with_extension("db")
         };
 
+        // Versioned files record their own key derivation; the flag is for legacy ones
+        let format = crypto::BukuCrypt::inspect(&enc_path)?;
+        let kdf = match &format {
+            crypto::EncryptedFormat::Versioned(header) => header.kdf,
+            crypto::EncryptedFormat::Legacy => Kdf::Sha256 {
+                iterations: self.iterations,
+            },
+        };
         println!(
-            "Decrypting {} to {} with {} iterations...",
+            "Decrypting {} to {} ({})...",
             enc_path.display(),
             out_path.display(),
-            self.iterations
+            kdf
         );
         crypto::BukuCrypt::decrypt_file(self.iterations, &out_path, &enc_path, &password)?;
         eprintln!("Decryption complete.");
+        if format == crypto::EncryptedFormat::Legacy {
+            eprintln!("This file has no integrity check; run `lock` again to add one.");
+        }
+        Ok(())
+    }
+}
+
+/// Encrypt the URL, title and description of bookmarks with a tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptCommand {
+    pub tag: String,
+}
+
+impl BukuCommand for EncryptCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        ctx.interaction.require_input("The password prompt")?;
+        let password = rpassword::prompt_password("Enter password: ")?;
+        let confirm = rpassword::prompt_password("Confirm password: ")?;
+        if password != confirm {
+            return Err("Passwords do not match".into());
+        }
+
+        let kdf = Kdf::argon2id(ARGON2_DEFAULT_MEMORY_KIB, crypto::ARGON2_DEFAULT_PASSES);
+        let count = encrypted::encrypt_tagged(ctx.db, &self.tag, kdf, &password)?;
+        eprintln!(
+            "Encrypted {} bookmark(s) tagged '{}'. This can't be undone; use `decrypt` to reverse it.",
+            count, self.tag
+        );
+        Ok(())
+    }
+}
+
+/// Decrypt bookmarks encrypted by [`EncryptCommand`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecryptCommand {
+    /// Only bookmarks with this tag; all encrypted ones when unset
+    pub tag: Option<String>,
+}
+
+impl BukuCommand for DecryptCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        ctx.interaction.require_input("The password prompt")?;
+        let password = rpassword::prompt_password("Enter password: ")?;
+
+        let summary = encrypted::decrypt_tagged(ctx.db, self.tag.as_deref(), &password)?;
+        eprintln!("Decrypted {} bookmark(s).", summary.decrypted);
+        if summary.other_password > 0 {
+            eprintln!(
+                "{} bookmark(s) were encrypted with a different password and left as they are.",
+                summary.other_password
+            );
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn lock(kdf: &str, memory: Option<&str>, passes: u32) -> LockCommand {
+        lock_with_iterations(8, kdf, memory, passes)
+    }
+
+    fn lock_with_iterations(
+        iterations: u32,
+        kdf: &str,
+        memory: Option<&str>,
+        passes: u32,
+    ) -> LockCommand {
+        LockCommand {
+            iterations,
+            kdf: kdf.to_string(),
+            memory: memory.map(str::to_string),
+            passes,
+        }
+    }
+
+    #[rstest]
+    #[case(lock("sha256", None, 3), Some(Kdf::Sha256 { iterations: 8 }))]
+    #[case(lock("argon2", None, 3), Some(Kdf::argon2id(64 * 1024, 3)))]
+    #[case(lock("argon2", Some("256MB"), 2), Some(Kdf::argon2id(256 * 1024, 2)))]
+    #[case(lock("argon2", Some("1GiB"), 1), Some(Kdf::argon2id(1024 * 1024, 1)))]
+    #[case(lock("argon2", Some("512k"), 1), Some(Kdf::argon2id(512, 1)))]
+    #[case(lock("argon2", Some("4KB"), 1), None)]
+    #[case(lock("argon2", Some("8GB"), 1), None)]
+    #[case(lock("argon2", Some("lots"), 1), None)]
+    #[case(lock("argon2", None, 0), None)]
+    #[case(lock("sha256", Some("64MB"), 3), None)]
+    #[case(lock_with_iterations(0, "sha256", None, 3), None)]
+    #[case(lock_with_iterations(1, "sha256", None, 3), Some(Kdf::Sha256 { iterations: 1 }))]
+    fn test_lock_kdf(#[case] command: LockCommand, #[case] expected: Option<Kdf>) {
+        assert_eq!(command.kdf().ok(), expected);
+    }
+}
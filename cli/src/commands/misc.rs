@@ -1,7 +1,9 @@
 use super::{AppContext, BukuCommand};
+use crate::format::OutputFormat;
 use crate::interactive;
-use bukurs::browser;
 use bukurs::error::Result;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::{expiry, ordering, SelectionMode, Selector};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,22 +11,43 @@ pub struct OpenCommand {
     pub ids: Vec<String>,
 }
 
+impl OpenCommand {
+    /// The bookmark named by a lone alias argument, which wins over a keyword search
+    fn alias(&self, ctx: &AppContext) -> Result<Option<Bookmark>> {
+        match self.ids.as_slice() {
+            [name] => Ok(ctx.db.get_rec_by_alias(name)?),
+            _ => Ok(None),
+        }
+    }
+}
+
 impl BukuCommand for OpenCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
         if self.ids.is_empty() {
-            eprintln!("Opening random bookmark (not implemented yet)");
+            // No IDs: pick one or more bookmarks (Tab to mark) and open them all
+            let mut records = expiry::hide_expired(ctx.db, ctx.db.get_rec_all()?, expiry::now())?;
+            ordering::sort_for_display(ctx.db, &mut records)?;
+            if records.is_empty() {
+                eprintln!("No bookmarks found");
+                return Ok(());
+            }
+            ctx.interaction.require_input("The bookmark picker")?;
+            let selected = crate::commands::helpers::select_bookmarks(ctx, &records, None)?;
+            crate::commands::helpers::open_bookmarks(ctx.db, &selected)?;
+        } else if let Some(bookmark) = self.alias(ctx)? {
+            crate::commands::helpers::open_bookmarks(ctx.db, &[bookmark])?;
         } else {
-            for arg in &self.ids {
-                if let Ok(id) = arg.parse::<usize>() {
-                    if let Some(rec) = ctx.db.get_rec_by_id(id)? {
-                        eprintln!("Opening: {}", rec.url);
-                        browser::open_url(&rec.url)?;
-                    } else {
-                        eprintln!("Index {} not found", id);
-                    }
-                } else {
-                    eprintln!("Invalid index: {}", arg);
-                }
+            let selection = Selector::parse(&self.ids)?.resolve(ctx.db)?;
+            if selection.bookmarks.is_empty() {
+                eprintln!("No bookmarks found");
+            } else if let SelectionMode::ByKeywords(_) = selection.mode {
+                // Keywords can match many bookmarks; choose which to open
+                ctx.interaction.require_input("The bookmark picker")?;
+                let selected =
+                    crate::commands::helpers::select_bookmarks(ctx, &selection.bookmarks, None)?;
+                crate::commands::helpers::open_bookmarks(ctx.db, &selected)?;
+            } else {
+                crate::commands::helpers::open_bookmarks(ctx.db, &selection.bookmarks)?;
             }
         }
         Ok(())
@@ -36,7 +59,8 @@ pub struct ShellCommand;
 
 impl BukuCommand for ShellCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        interactive::run(ctx.db)?;
+        ctx.interaction.require_input("The interactive shell")?;
+        interactive::run_with_context(ctx)?;
         Ok(())
     }
 }
@@ -118,8 +142,10 @@ impl BukuCommand for NoCommand {
             // Use FTS5 search to filter records
             ctx.db.search(&self.keywords, false, false, false)?
         } else {
-            // No keywords, get all records
-            ctx.db.get_rec_all()?
+            // No keywords, get unexpired records, pinned and manually ordered ones first
+            let mut records = expiry::hide_expired(ctx.db, ctx.db.get_rec_all()?, expiry::now())?;
+            ordering::sort_for_display(ctx.db, &mut records)?;
+            records
         };
 
         if records.is_empty() {
@@ -135,10 +161,11 @@ impl BukuCommand for NoCommand {
         };
 
         crate::commands::helpers::handle_bookmark_selection(
+            ctx,
             &records,
             query,
             self.open,
-            self.format.as_deref(),
+            &OutputFormat::resolve(self.format.as_deref(), ctx.config)?,
             self.nc,
         )?;
         Ok(())
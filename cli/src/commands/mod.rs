@@ -1,25 +1,83 @@
+use crate::prompt::Interaction;
 use bukurs::config::Config;
 use bukurs::db::BukuDb;
 use bukurs::error::Result;
+use bukurs::service::ChangeSummary;
 use std::path::Path;
 
 pub struct AppContext<'a> {
     pub db: &'a BukuDb,
     pub config: &'a Config,
     pub db_path: &'a Path,
+    pub interaction: Interaction,
+    pub output: OutputMode,
+}
+
+/// How commands that change bookmarks report the result (`--output`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Messages on stderr only
+    #[default]
+    Text,
+    /// A [`ChangeSummary`] object on stdout as well
+    Json,
+}
+
+impl OutputMode {
+    pub fn from_flag(flag: Option<&str>) -> Self {
+        match flag {
+            Some("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+}
+
+impl AppContext<'_> {
+    /// Print `summary` on stdout when `--output json` was given
+    pub fn report(&self, summary: &ChangeSummary) -> Result<()> {
+        if self.output == OutputMode::Json {
+            println!("{}", serde_json::to_string(summary)?);
+        }
+        Ok(())
+    }
 }
 
 pub mod add;
+pub mod alias;
+pub mod audit;
+pub mod autotag;
+pub mod bookmarklet;
+pub mod cipher;
+pub mod collection;
+pub mod config;
+pub mod daemon;
 pub mod delete;
+pub mod domains;
 pub mod edit;
+pub mod exec;
+pub mod expired;
+pub mod fetch_pending;
 pub mod helpers;
 pub mod import_export;
+pub mod later;
+pub mod links;
 pub mod lock_unlock;
 pub mod misc;
+pub mod monitor;
+pub mod note;
+pub mod pin;
 pub mod print;
+pub mod profile;
+pub mod query;
+pub mod rating;
+pub mod read;
+pub mod saved;
 pub mod search;
+pub mod serve;
+pub mod similar;
 pub mod tag;
 pub mod update;
+pub mod watch;
 
 pub trait BukuCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()>;
@@ -28,20 +86,53 @@ pub trait BukuCommand {
 /// Enum-based dispatch for commands (avoids Box<dyn BukuCommand>)
 pub enum CommandEnum {
     Add(add::AddCommand),
+    Alias(alias::AliasCommand),
+    Audit(audit::AuditCommand),
+    AddUrl(bookmarklet::AddUrlCommand),
+    Autotag(autotag::AutotagCommand),
+    Bookmarklet(bookmarklet::BookmarkletCommand),
+    Collection(collection::CollectionCommand),
+    Daemon(daemon::DaemonCommand),
     Update(update::UpdateCommand),
     Delete(delete::DeleteCommand),
+    Domains(domains::DomainsCommand),
     Print(print::PrintCommand),
+    Query(query::QueryCommand),
+    Saved(saved::SavedCommand),
     Search(search::SearchCommand),
+    Serve(serve::ServeCommand),
+    Similar(similar::SimilarCommand),
+    Links(links::LinksCommand),
+    Read(read::ReadCommand),
     Tag(tag::TagCommand),
+    Retag(tag::RetagCommand),
+    NormalizeTags(tag::NormalizeTagsCommand),
+    ApplyTagMap(tag::ApplyTagMapCommand),
     Lock(lock_unlock::LockCommand),
     Unlock(lock_unlock::UnlockCommand),
+    Encrypt(lock_unlock::EncryptCommand),
+    Decrypt(lock_unlock::DecryptCommand),
     Import(import_export::ImportCommand),
     ImportBrowsers(import_export::ImportBrowsersCommand),
+    ImportHistory(import_export::ImportHistoryCommand),
     Export(import_export::ExportCommand),
+    Publish(import_export::PublishCommand),
+    Dump(import_export::DumpCommand),
+    Restore(import_export::RestoreCommand),
     Open(misc::OpenCommand),
     Shell(misc::ShellCommand),
     Edit(edit::EditCommand),
+    Expired(expired::ExpiredCommand),
+    FetchPending(fetch_pending::FetchPendingCommand),
+    Later(later::LaterCommand),
+    Monitor(monitor::MonitorCommand),
+    Profile(profile::ProfileCommand),
+    Note(note::NoteCommand),
+    Pin(pin::PinCommand),
+    Rate(rating::RateCommand),
+    Move(pin::MoveCommand),
     Undo(misc::UndoCommand),
+    Watch(watch::WatchCommand),
     No(misc::NoCommand),
 }
 
@@ -49,20 +140,53 @@ impl CommandEnum {
     pub fn execute(&self, ctx: &AppContext) -> Result<()> {
         match self {
             Self::Add(cmd) => cmd.execute(ctx),
+            Self::Audit(cmd) => cmd.execute(ctx),
+            Self::AddUrl(cmd) => cmd.execute(ctx),
+            Self::Autotag(cmd) => cmd.execute(ctx),
+            Self::Bookmarklet(cmd) => cmd.execute(ctx),
+            Self::Alias(cmd) => cmd.execute(ctx),
+            Self::Collection(cmd) => cmd.execute(ctx),
+            Self::Daemon(cmd) => cmd.execute(ctx),
             Self::Update(cmd) => cmd.execute(ctx),
             Self::Delete(cmd) => cmd.execute(ctx),
+            Self::Domains(cmd) => cmd.execute(ctx),
             Self::Print(cmd) => cmd.execute(ctx),
+            Self::Query(cmd) => cmd.execute(ctx),
+            Self::Saved(cmd) => cmd.execute(ctx),
             Self::Search(cmd) => cmd.execute(ctx),
+            Self::Serve(cmd) => cmd.execute(ctx),
+            Self::Similar(cmd) => cmd.execute(ctx),
+            Self::Links(cmd) => cmd.execute(ctx),
+            Self::Read(cmd) => cmd.execute(ctx),
             Self::Tag(cmd) => cmd.execute(ctx),
+            Self::Retag(cmd) => cmd.execute(ctx),
+            Self::NormalizeTags(cmd) => cmd.execute(ctx),
+            Self::ApplyTagMap(cmd) => cmd.execute(ctx),
             Self::Lock(cmd) => cmd.execute(ctx),
             Self::Unlock(cmd) => cmd.execute(ctx),
+            Self::Encrypt(cmd) => cmd.execute(ctx),
+            Self::Decrypt(cmd) => cmd.execute(ctx),
             Self::Import(cmd) => cmd.execute(ctx),
             Self::ImportBrowsers(cmd) => cmd.execute(ctx),
+            Self::ImportHistory(cmd) => cmd.execute(ctx),
             Self::Export(cmd) => cmd.execute(ctx),
+            Self::Publish(cmd) => cmd.execute(ctx),
+            Self::Dump(cmd) => cmd.execute(ctx),
+            Self::Restore(cmd) => cmd.execute(ctx),
             Self::Open(cmd) => cmd.execute(ctx),
             Self::Shell(cmd) => cmd.execute(ctx),
             Self::Edit(cmd) => cmd.execute(ctx),
+            Self::Expired(cmd) => cmd.execute(ctx),
+            Self::FetchPending(cmd) => cmd.execute(ctx),
+            Self::Later(cmd) => cmd.execute(ctx),
+            Self::Monitor(cmd) => cmd.execute(ctx),
+            Self::Profile(cmd) => cmd.execute(ctx),
+            Self::Note(cmd) => cmd.execute(ctx),
+            Self::Pin(cmd) => cmd.execute(ctx),
+            Self::Rate(cmd) => cmd.execute(ctx),
+            Self::Move(cmd) => cmd.execute(ctx),
             Self::Undo(cmd) => cmd.execute(ctx),
+            Self::Watch(cmd) => cmd.execute(ctx),
             Self::No(cmd) => cmd.execute(ctx),
         }
     }
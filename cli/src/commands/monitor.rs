@@ -0,0 +1,175 @@
+use super::{AppContext, BukuCommand};
+use crate::fetch_ui::{fetch_options, ProgressBarSink};
+use crate::interrupt::InterruptGuard;
+use bukurs::error::{BukursError, Result};
+use bukurs::monitor::{self, Monitor};
+use bukurs::utils::format_rfc3339;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MonitorOp {
+    /// Start watching bookmark `id`'s page, from its current content
+    Add { id: usize },
+    /// Stop watching bookmark `id`'s page
+    Remove { id: usize },
+    /// Print every monitored bookmark with its last check
+    List,
+    /// Fetch every monitored page now and flag the ones that changed
+    Check,
+    /// Print the bookmarks whose page changed, optionally marking them seen
+    Changes { clear: bool },
+}
+
+/// Watch bookmarked pages for content changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorCommand {
+    pub op: MonitorOp,
+    pub timeout: Option<u64>,
+}
+
+impl BukuCommand for MonitorCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        match &self.op {
+            MonitorOp::Add { id } => {
+                let options = fetch_options(ctx.config, self.timeout);
+                if monitor::add(ctx.db, *id, &options)? {
+                    eprintln!(
+                        "Bookmark {} was monitored already; its content was re-read",
+                        id
+                    );
+                } else {
+                    eprintln!("Monitoring bookmark {} for changes", id);
+                }
+                Ok(())
+            }
+            MonitorOp::Remove { id } => {
+                if !ctx.db.remove_monitor(*id)? {
+                    return Err(BukursError::InvalidInput(format!(
+                        "Bookmark {} isn't monitored",
+                        id
+                    )));
+                }
+                eprintln!("Stopped monitoring bookmark {}", id);
+                Ok(())
+            }
+            MonitorOp::List => {
+                let monitors = ctx.db.get_monitors()?;
+                if monitors.is_empty() {
+                    eprintln!("No monitored bookmarks; add one with `monitor add <ID>`");
+                }
+                for monitor in &monitors {
+                    let state = match (&monitor.last_error, monitor.changed) {
+                        (Some(error), _) => format!("check failed: {}", error),
+                        (None, true) => "changed".to_string(),
+                        (None, false) => "unchanged".to_string(),
+                    };
+                    println!(
+                        "{}. {} ({}, checked {})",
+                        monitor.id,
+                        monitor.url,
+                        state,
+                        format_rfc3339(monitor.checked_at)
+                    );
+                }
+                Ok(())
+            }
+            MonitorOp::Check => self.check(ctx),
+            MonitorOp::Changes { clear } => {
+                let changed: Vec<Monitor> = ctx
+                    .db
+                    .get_monitors()?
+                    .into_iter()
+                    .filter(|m| m.changed)
+                    .collect();
+                if changed.is_empty() {
+                    eprintln!("No monitored page changed.");
+                    return Ok(());
+                }
+                for monitor in &changed {
+                    let title = if monitor.title.is_empty() {
+                        &monitor.url
+                    } else {
+                        &monitor.title
+                    };
+                    println!(
+                        "{}. {} (changed {})",
+                        monitor.id,
+                        title,
+                        format_rfc3339(monitor.changed_at.unwrap_or(monitor.checked_at))
+                    );
+                    println!("   > {}", monitor.url);
+                }
+                if *clear {
+                    let ids: Vec<usize> = changed.iter().map(|m| m.id).collect();
+                    let cleared = ctx.db.clear_monitor_changes(&ids)?;
+                    eprintln!("Cleared {} change(s)", cleared);
+                } else {
+                    eprintln!(
+                        "{} changed; `monitor changes --clear` marks them seen",
+                        changed.len()
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl MonitorCommand {
+    fn check(&self, ctx: &AppContext) -> Result<()> {
+        let options = fetch_options(ctx.config, self.timeout);
+        let interrupt = InterruptGuard::install();
+        let outcome = monitor::check(ctx.db, &options, &ProgressBarSink::new(), interrupt.token())?;
+        drop(interrupt);
+
+        for (id, error) in &outcome.failed {
+            eprintln!("✗ {}. {}", id, error);
+        }
+        eprintln!(
+            "✓ Checked {} page(s), {} changed",
+            outcome.checked,
+            outcome.changed.len()
+        );
+        if !outcome.changed.is_empty() {
+            eprintln!("  See them with `monitor changes`");
+        }
+        if outcome.cancelled {
+            eprintln!("Check interrupted; the rest keep their previous state");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_changes_clear_marks_changes_seen() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        let id = db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+        db.add_monitor(id, "old", 1).unwrap();
+        db.record_monitor_hash(id, "new", 2).unwrap();
+
+        let command = |op| MonitorCommand { op, timeout: None };
+        command(MonitorOp::Changes { clear: true })
+            .execute(&ctx)
+            .unwrap();
+        assert!(!db.get_monitors().unwrap()[0].changed);
+
+        command(MonitorOp::Remove { id }).execute(&ctx).unwrap();
+        assert!(command(MonitorOp::Remove { id }).execute(&ctx).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::Selector;
+use serde::{Deserialize, Serialize};
+
+/// Edit a bookmark's description as Markdown notes in $EDITOR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteCommand {
+    pub id: String,
+}
+
+impl BukuCommand for NoteCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        ctx.interaction.require_input("The editor")?;
+        let mut bookmarks = Selector::parse(std::slice::from_ref(&self.id))?
+            .resolve(ctx.db)?
+            .bookmarks;
+        if bookmarks.len() != 1 {
+            return Err(format!("Bookmark {} not found", self.id).into());
+        }
+        let bookmark = bookmarks.remove(0);
+
+        eprintln!("Opening notes for bookmark #{} in editor...", bookmark.id);
+        match crate::editor::edit_note(&bookmark) {
+            Ok(note) => {
+                let _lock = ctx.db.write_lock()?;
+                ctx.db
+                    .update_rec_partial(bookmark.id, None, None, None, Some(&note), None)?;
+                eprintln!("Notes for bookmark {} saved", bookmark.id);
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("Edit cancelled or failed: {}", e);
+                Ok(())
+            }
+        }
+    }
+}
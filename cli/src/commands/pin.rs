@@ -0,0 +1,65 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::ordering::{self, Placement};
+use bukurs::operations::Selector;
+use serde::{Deserialize, Serialize};
+
+/// Pin bookmarks to the top of listings, or unpin them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinCommand {
+    pub ids: Vec<String>,
+    pub pinned: bool,
+}
+
+impl BukuCommand for PinCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if self.ids.is_empty() {
+            return Err("No bookmarks given".into());
+        }
+        let selected = Selector::parse(&self.ids)?.resolve(ctx.db)?.selected_ids;
+        if selected.is_empty() {
+            eprintln!("No bookmarks found");
+            return Ok(());
+        }
+
+        let changed = ordering::pin(ctx.db, &selected, self.pinned)?;
+        let action = if self.pinned { "Pinned" } else { "Unpinned" };
+        eprintln!("{} {} bookmark(s)", action, changed);
+        Ok(())
+    }
+}
+
+/// Move a bookmark before or after another one in the manual order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveCommand {
+    pub id: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl BukuCommand for MoveCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let id = resolve_one(ctx, &self.id)?;
+        let placement = match (&self.before, &self.after) {
+            (Some(before), None) => Placement::Before(resolve_one(ctx, before)?),
+            (None, Some(after)) => Placement::After(resolve_one(ctx, after)?),
+            _ => return Err("Give exactly one of --before or --after".into()),
+        };
+
+        ordering::move_bookmark(ctx.db, id, placement)?;
+        match placement {
+            Placement::Before(other) => eprintln!("Moved bookmark {} before {}", id, other),
+            Placement::After(other) => eprintln!("Moved bookmark {} after {}", id, other),
+        }
+        Ok(())
+    }
+}
+
+/// ID of the single bookmark an index selects
+fn resolve_one(ctx: &AppContext, index: &str) -> Result<usize> {
+    let selected = Selector::parse(&[index])?.resolve(ctx.db)?.selected_ids;
+    match selected[..] {
+        [id] => Ok(id),
+        _ => Err(format!("Bookmark {} not found", index).into()),
+    }
+}
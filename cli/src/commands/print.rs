@@ -1,8 +1,12 @@
 use super::{AppContext, BukuCommand};
-use crate::format::OutputFormat;
+use crate::format::{ListingBadges, OutputFormat};
+use bukurs::collection;
 use bukurs::error::Result;
-use bukurs::operations;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::timeframe::TimeFilter;
+use bukurs::operations::{self, expiry, later, ordering, rating};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrintCommand {
@@ -10,10 +14,40 @@ pub struct PrintCommand {
     pub limit: Option<usize>,
     pub format: Option<String>,
     pub nc: bool,
+    /// Print counts instead of bookmarks
+    pub stats: bool,
+    /// Print the bookmarks in this collection instead of `ids`
+    pub collection: Option<String>,
+    /// Only print bookmarks rated at least this many stars
+    pub min_rating: Option<u8>,
+    /// Highest rated first
+    pub by_rating: bool,
+    /// Only print bookmarks added or last visited within these bounds
+    pub time: TimeFilter,
 }
 
 impl BukuCommand for PrintCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if self.stats {
+            println!("Bookmarks: {}", ctx.db.get_rec_all()?.len());
+            println!("Tags:      {}", ctx.db.get_all_tags()?.len());
+            println!("Unread:    {}", later::count(ctx.db)?);
+            let languages = ctx.db.get_languages()?;
+            if !languages.is_empty() {
+                println!("Languages: {}", language_counts(languages.values()));
+            }
+            return Ok(());
+        }
+
+        if let Some(name) = &self.collection {
+            let records = collection::bookmarks(ctx.db, name)?;
+            if records.is_empty() {
+                eprintln!("No bookmarks in collection '{}'.", name);
+                return Ok(());
+            }
+            return self.print_listing(ctx, records);
+        }
+
         // Use the prepare_print operation
         let operation = operations::prepare_print(&self.ids, ctx.db)?;
 
@@ -30,20 +64,70 @@ impl BukuCommand for PrintCommand {
             return Ok(());
         }
 
-        // Apply limit if specified
-        let mut records = operation.bookmarks;
-        if let Some(limit) = self.limit {
-            let start = records.len().saturating_sub(limit);
-            records = records.into_iter().skip(start).collect();
+        if operation.mode == operations::SelectionMode::All {
+            return self.print_listing(ctx, operation.bookmarks);
         }
+        self.print_records(ctx, operation.bookmarks)
+    }
+}
 
-        let format: OutputFormat = self
-            .format
-            .as_deref()
-            .map(OutputFormat::from_string)
-            .unwrap_or(OutputFormat::Colored);
+impl PrintCommand {
+    /// Print a whole listing (every bookmark, or a collection) the way `print` shows one
+    fn print_listing(&self, ctx: &AppContext, records: Vec<Bookmark>) -> Result<()> {
+        // Expired bookmarks are listed by `expired` instead
+        let mut records = expiry::hide_expired(ctx.db, records, expiry::now())?;
+        // Pinned and manually ordered bookmarks first
+        ordering::sort_for_display(ctx.db, &mut records)?;
+        self.print_records(ctx, records)
+    }
 
-        format.print_bookmarks(&records, self.nc);
+    fn print_records(&self, ctx: &AppContext, mut records: Vec<Bookmark>) -> Result<()> {
+        if let Some(min) = self.min_rating {
+            records = rating::at_least(ctx.db, records, min)?;
+        }
+        records = self.time.apply(ctx.db, records, expiry::now())?;
+        if self.by_rating {
+            rating::sort_by_rating(ctx.db, &mut records)?;
+        }
+
+        // Apply limit if specified; sorted by rating, the best ones are kept
+        if let Some(limit) = self.limit {
+            if self.by_rating {
+                records.truncate(limit);
+            } else {
+                let start = records.len().saturating_sub(limit);
+                records = records.into_iter().skip(start).collect();
+            }
+        }
+
+        let format = OutputFormat::resolve(self.format.as_deref(), ctx.config)?;
+        format.print_listing(&records, &ListingBadges::load(ctx.db)?, self.nc);
         Ok(())
     }
 }
+
+/// How many bookmarks each language has, most common first: `en 12, de 3`
+fn language_counts<'a>(languages: impl Iterator<Item = &'a String>) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for lang in languages {
+        *counts.entry(lang).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts
+        .iter()
+        .map(|(lang, n)| format!("{} {}", lang, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_counts() {
+        let languages = ["de", "en", "fr", "en", "de", "en"].map(String::from);
+        assert_eq!(language_counts(languages.iter()), "en 3, de 2, fr 1");
+    }
+}
@@ -0,0 +1,202 @@
+use super::{AppContext, BukuCommand};
+use bukurs::audit::AuditSource;
+use bukurs::config::DEFAULT_PROFILE;
+use bukurs::db::BukuDb;
+use bukurs::error::{BukursError, Result};
+use bukurs::import_export::DuplicateStrategy;
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations;
+use bukurs::profile;
+use bukurs::service::ChangeSummary;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfileOp {
+    /// Print every profile with its database file
+    List,
+    /// Print the URLs only one of two profiles has, and the shared ones
+    /// tagged differently
+    Diff { left: String, right: String },
+    /// Copy the selected bookmarks of the current database to profile `to`
+    Copy {
+        ids: Vec<String>,
+        to: String,
+        on_duplicate: DuplicateStrategy,
+        remove: bool,
+    },
+}
+
+/// Compare named databases and copy bookmarks between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileCommand {
+    pub op: ProfileOp,
+}
+
+impl BukuCommand for ProfileCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        match &self.op {
+            ProfileOp::List => {
+                if !ctx.config.profiles.contains_key(DEFAULT_PROFILE) {
+                    println!("{}: {}", DEFAULT_PROFILE, ctx.config.db_path().display());
+                }
+                for (name, path) in &ctx.config.profiles {
+                    println!("{}: {}", name, path.display());
+                }
+                if ctx.config.profiles.is_empty() {
+                    eprintln!("Add more under `profiles` in the config, then use `--profile NAME`");
+                }
+                Ok(())
+            }
+            ProfileOp::Diff { left, right } => {
+                let diff = profile::diff(&open_profile(ctx, left)?, &open_profile(ctx, right)?)?;
+                print_only(left, &diff.only_left);
+                print_only(right, &diff.only_right);
+                if !diff.differing_tags.is_empty() {
+                    println!("Tagged differently:");
+                    for (l, r) in &diff.differing_tags {
+                        println!("  {}", l.url);
+                        println!("    {} {}. {}", left, l.id, l.tags.trim_matches(','));
+                        println!("    {} {}. {}", right, r.id, r.tags.trim_matches(','));
+                    }
+                }
+                eprintln!(
+                    "{} only in {}, {} only in {}, {} tagged differently, {} identical",
+                    diff.only_left.len(),
+                    left,
+                    diff.only_right.len(),
+                    right,
+                    diff.differing_tags.len(),
+                    diff.common
+                );
+                Ok(())
+            }
+            ProfileOp::Copy {
+                ids,
+                to,
+                on_duplicate,
+                remove,
+            } => {
+                let ids = operations::parse_ranges(ids, ctx.db)?;
+                if ids.is_empty() {
+                    return Err(BukursError::InvalidInput(
+                        "No bookmarks selected to copy".to_string(),
+                    ));
+                }
+                let path = ctx.config.profile_path(to)?;
+                // Opening the target may migrate it, so it's locked from the start
+                let _lock = ctx.config.database.lock_database(&path)?;
+                let target = ctx.config.open_db(Some(&path), AuditSource::Cli)?;
+                let stats = profile::copy(ctx.db, &target, &ids, *on_duplicate, *remove)?;
+                let import = &stats.import;
+
+                if *remove {
+                    eprintln!("✓ Moved {} bookmark(s) to {}", stats.removed, to);
+                } else {
+                    eprintln!(
+                        "✓ Copied {} bookmark(s) to {}",
+                        import.added + import.updated,
+                        to
+                    );
+                }
+                if import.updated > 0 || import.skipped > 0 {
+                    eprintln!(
+                        "  {} new, {} updated, {} skipped",
+                        import.added, import.updated, import.skipped
+                    );
+                }
+                if !stats.kept.is_empty() {
+                    let kept: Vec<String> = stats.kept.iter().map(usize::to_string).collect();
+                    eprintln!(
+                        "  Kept {} here, as {} skipped them: {}",
+                        kept.len(),
+                        to,
+                        kept.join(" ")
+                    );
+                }
+                let mut summary = ChangeSummary::from(import);
+                summary.deleted = stats.removed;
+                ctx.report(&summary)
+            }
+        }
+    }
+}
+
+fn open_profile(ctx: &AppContext, name: &str) -> Result<BukuDb> {
+    let path = ctx.config.profile_path(name)?;
+    if !path.exists() {
+        return Err(BukursError::InvalidInput(format!(
+            "Profile '{}' has no database yet ({})",
+            name,
+            path.display()
+        )));
+    }
+    ctx.config.open_db(Some(&path), AuditSource::Cli)
+}
+
+fn print_only(name: &str, bookmarks: &[Bookmark]) {
+    if bookmarks.is_empty() {
+        return;
+    }
+    println!("Only in {}:", name);
+    for bookmark in bookmarks {
+        println!("  {}. {}", bookmark.id, bookmark.url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_copy_moves_to_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            db: Some(dir.path().join("work.db")),
+            profiles: BTreeMap::from([("personal".to_string(), dir.path().join("personal.db"))]),
+            ..Config::default()
+        };
+        let db = config.open_db(None, AuditSource::Cli).unwrap();
+        let db_path = config.db_path();
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+        db.add_rec("https://b.com", "B", ",", "", None).unwrap();
+        db.add_rec("https://c.com", "C", ",", "", None).unwrap();
+        let personal = config
+            .open_db(Some(&dir.path().join("personal.db")), AuditSource::Cli)
+            .unwrap();
+        personal
+            .add_rec("https://b.com", "Mine", ",", "", None)
+            .unwrap();
+
+        ProfileCommand {
+            op: ProfileOp::Copy {
+                ids: vec!["1-2".to_string()],
+                to: "personal".to_string(),
+                on_duplicate: DuplicateStrategy::Skip,
+                remove: true,
+            },
+        }
+        .execute(&ctx)
+        .unwrap();
+
+        let urls = |db: &BukuDb| -> Vec<String> {
+            db.get_rec_all()
+                .unwrap()
+                .into_iter()
+                .map(|b| b.url)
+                .collect()
+        };
+        assert_eq!(urls(&personal), ["https://b.com", "https://a.com"]);
+        // The duplicate the target skipped stays in the source
+        assert_eq!(urls(&db), ["https://b.com", "https://c.com"]);
+        assert!(open_profile(&ctx, "home").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use super::{AppContext, BukuCommand};
+use crate::format::OutputFormat;
+use bukurs::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// Run SQL against the bookmark database and print what it returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryCommand {
+    pub sql: String,
+    /// Run statements that change the database too
+    pub allow_write: bool,
+    /// Print at most this many rows
+    pub limit: Option<usize>,
+    pub format: Option<String>,
+    pub nc: bool,
+}
+
+impl BukuCommand for QueryCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let format = OutputFormat::resolve(self.format.as_deref(), ctx.config)?;
+        let mut result = ctx.db.run_query(&self.sql, self.allow_write)?;
+
+        if let Some(changed) = result.changed {
+            eprintln!("✓ {} row(s) changed (not undoable)", changed);
+            return Ok(());
+        }
+        if result.columns.is_empty() {
+            return Ok(());
+        }
+        if let Some(limit) = self.limit {
+            result.rows.truncate(limit);
+        }
+        if result.rows.is_empty() {
+            eprintln!("No rows.");
+            return Ok(());
+        }
+        format.print_rows(&result, self.nc)
+    }
+}
@@ -0,0 +1,38 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::rating;
+use bukurs::operations::Selector;
+use serde::{Deserialize, Serialize};
+
+/// Rate bookmarks; `star` and `unstar` rate them 5 and 0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateCommand {
+    pub ids: Vec<String>,
+    /// Stars, 1 to 5; 0 clears the rating
+    pub rating: u8,
+}
+
+impl BukuCommand for RateCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if self.ids.is_empty() {
+            return Err("No bookmarks given".into());
+        }
+        let selected = Selector::parse(&self.ids)?.resolve(ctx.db)?.selected_ids;
+        if selected.is_empty() {
+            eprintln!("No bookmarks found");
+            return Ok(());
+        }
+
+        let changed = rating::rate(ctx.db, &selected, self.rating)?;
+        if self.rating == 0 {
+            eprintln!("Cleared the rating of {} bookmark(s)", changed);
+        } else {
+            eprintln!(
+                "Rated {} bookmark(s) {}",
+                changed,
+                rating::stars(self.rating)
+            );
+        }
+        Ok(())
+    }
+}
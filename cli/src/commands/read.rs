@@ -0,0 +1,185 @@
+use super::{AppContext, BukuCommand};
+use crate::fetch_ui::fetch_options;
+use crate::output::theme::Theme;
+use bukurs::error::Result;
+use bukurs::fetch::readability::{self, Article, Block};
+use bukurs::operations::Selector;
+use console::Term;
+use owo_colors::Style;
+use serde::{Deserialize, Serialize};
+
+/// Widest the text is wrapped to, however wide the terminal
+const MAX_WIDTH: usize = 100;
+
+/// Print a bookmarked page's main text, like a browser's reader mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadCommand {
+    pub id: String,
+    /// Wrap width in columns; the terminal's width (at most 100) by default
+    pub width: Option<usize>,
+    /// Fetch timeout in seconds, overriding the config
+    pub timeout: Option<u64>,
+    pub nc: bool,
+}
+
+impl BukuCommand for ReadCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let bookmarks = Selector::parse(std::slice::from_ref(&self.id))?
+            .resolve(ctx.db)?
+            .bookmarks;
+        if bookmarks.len() != 1 {
+            return Err(format!("Bookmark {} not found", self.id).into());
+        }
+        let page = &bookmarks[0];
+
+        let article =
+            readability::fetch_article(&page.url, &fetch_options(ctx.config, self.timeout))?;
+        if article.blocks.is_empty() {
+            return Err(format!("No article text found on {}", page.url).into());
+        }
+
+        let width = self
+            .width
+            .unwrap_or_else(|| (Term::stdout().size().1 as usize).min(MAX_WIDTH));
+        let theme = (!self.nc).then(Theme::current);
+        print!(
+            "{}",
+            render(&article, &page.url, width.max(20), theme.as_ref())
+        );
+        Ok(())
+    }
+}
+
+/// The article as terminal text wrapped to `width`, styled with `theme` if given
+fn render(article: &Article, url: &str, width: usize, theme: Option<&Theme>) -> String {
+    let paint = |style: fn(&Theme) -> Style, text: &str| match theme {
+        Some(theme) => style(theme).style(text).to_string(),
+        None => text.to_string(),
+    };
+
+    let mut out = String::new();
+    if !article.title.is_empty() {
+        out.push_str(&paint(|t| t.title, &article.title));
+        out.push('\n');
+    }
+    out.push_str(&paint(|t| t.url, url));
+    out.push('\n');
+    let words = article.word_count();
+    out.push_str(&format!(
+        "{} · {} words\n",
+        readability::reading_time(words),
+        words
+    ));
+
+    for block in &article.blocks {
+        out.push('\n');
+        match block {
+            Block::Heading(text) => {
+                for line in wrap(text, width) {
+                    out.push_str(&paint(|t| t.title, &line));
+                    out.push('\n');
+                }
+            }
+            Block::Paragraph(text) => out.push_str(&indented(text, width, "", "")),
+            Block::Item(text) => out.push_str(&indented(
+                text,
+                width,
+                &paint(|t| t.highlight, "  •"),
+                "   ",
+            )),
+            Block::Quote(text) => {
+                let bar = paint(|t| t.highlight, "  │");
+                out.push_str(&indented(text, width, &bar, &bar))
+            }
+            Block::Code(text) => {
+                for line in text.lines() {
+                    out.push_str("    ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `text` wrapped to `width` (a marker takes four columns), the first line
+/// after `first` and the rest after `rest`
+fn indented(text: &str, width: usize, first: &str, rest: &str) -> String {
+    let indent = if first.is_empty() { 0 } else { 4 };
+    let mut out = String::new();
+    for (i, line) in wrap(text, width - indent).into_iter().enumerate() {
+        let marker = if i == 0 { first } else { rest };
+        if !marker.is_empty() {
+            out.push_str(marker);
+            out.push(' ');
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Break `text` into lines of at most `width` characters at spaces; longer
+/// words get a line of their own
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("one two three four", 9, vec!["one two", "three", "four"])]
+    #[case("one two", 80, vec!["one two"])]
+    #[case("a verylongword b", 4, vec!["a", "verylongword", "b"])]
+    #[case("", 10, vec![])]
+    fn test_wrap(#[case] text: &str, #[case] width: usize, #[case] expected: Vec<&str>) {
+        assert_eq!(wrap(text, width), expected);
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let article = Article {
+            title: "A Post".to_string(),
+            blocks: vec![
+                Block::Heading("Intro".to_string()),
+                Block::Paragraph("Some words that wrap around".to_string()),
+                Block::Item("A point".to_string()),
+                Block::Quote("Wise words here".to_string()),
+                Block::Code("let x = 1;".to_string()),
+            ],
+        };
+        let text = render(&article, "https://example.com/post", 16, None);
+        assert_eq!(
+            text,
+            "A Post\n\
+             https://example.com/post\n\
+             ~1 min · 15 words\n\
+             \n\
+             Intro\n\
+             \n\
+             Some words that\n\
+             wrap around\n\
+             \n  • A point\n\
+             \n  │ Wise words\n  │ here\n\
+             \n    let x = 1;\n"
+        );
+    }
+}
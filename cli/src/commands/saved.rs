@@ -0,0 +1,150 @@
+use super::search::SearchCommand;
+use super::{AppContext, BukuCommand};
+use bukurs::error::{BukursError, Result};
+use bukurs::search::SavedSearch;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedOp {
+    /// Print every saved search
+    List,
+    /// Repeat a saved search
+    Run { name: String },
+    /// Forget a saved search
+    Delete { name: String },
+}
+
+/// Manage searches kept with `search --save`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedCommand {
+    pub op: SavedOp,
+    pub limit: Option<usize>,
+    pub format: Option<String>,
+    pub nc: bool,
+    pub open: bool,
+}
+
+impl BukuCommand for SavedCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        match &self.op {
+            SavedOp::List => {
+                let searches = ctx.db.get_saved_searches()?;
+                if searches.is_empty() {
+                    eprintln!("No saved searches; keep one with `search <KEYWORDS> --save <NAME>`");
+                }
+                for search in &searches {
+                    println!("{}", describe(search));
+                }
+                Ok(())
+            }
+            SavedOp::Run { name } => {
+                let saved = ctx
+                    .db
+                    .get_saved_search(name)?
+                    .ok_or_else(|| not_found(name))?;
+                let command = SearchCommand {
+                    limit: self.limit,
+                    format: self.format.clone(),
+                    nc: self.nc,
+                    open: self.open,
+                    ..SearchCommand::from_saved(&saved)
+                };
+                command.execute(ctx)
+            }
+            SavedOp::Delete { name } => {
+                if !ctx.db.delete_saved_search(name)? {
+                    return Err(not_found(name));
+                }
+                eprintln!("Deleted saved search '{}'", name);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn not_found(name: &str) -> BukursError {
+    BukursError::InvalidInput(format!("No saved search called '{}'", name))
+}
+
+/// `name: keywords (options)`
+fn describe(search: &SavedSearch) -> String {
+    let mut options = Vec::new();
+    if search.all {
+        options.push("all".to_string());
+    }
+    if search.regex {
+        options.push("regex".to_string());
+    }
+    if search.notes {
+        options.push("notes".to_string());
+    }
+    if search.fuzzy {
+        options.push("fuzzy".to_string());
+    }
+    if let Some(rank) = search.rank {
+        options.push(format!("rank {}", rank));
+    }
+    if let Some(min) = search.min_rating {
+        options.push(format!("rating {}+", min));
+    }
+    if let Some(lang) = &search.lang {
+        options.push(format!("lang {}", lang));
+    }
+    if let Some(after) = &search.time.added_after {
+        options.push(format!("added after {}", after));
+    }
+    if let Some(within) = &search.time.added_within {
+        options.push(format!("added within {}", within));
+    }
+    if let Some(since) = &search.time.not_visited_since {
+        options.push(format!("not visited in {}", since));
+    }
+
+    let mut line = format!("{}: {}", search.name, search.keywords.join(" "));
+    if !options.is_empty() {
+        line.push_str(&format!(" ({})", options.join(", ")));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::search::SearchRank;
+
+    #[test]
+    fn test_describe() {
+        let mut search = SavedSearch {
+            name: "weekly-rust".to_string(),
+            keywords: vec!["rust".to_string(), "async".to_string()],
+            ..SavedSearch::default()
+        };
+        assert_eq!(describe(&search), "weekly-rust: rust async");
+
+        search.all = true;
+        search.rank = Some(SearchRank::Recent);
+        assert_eq!(
+            describe(&search),
+            "weekly-rust: rust async (all, rank recent)"
+        );
+
+        search.min_rating = Some(4);
+        assert_eq!(
+            describe(&search),
+            "weekly-rust: rust async (all, rank recent, rating 4+)"
+        );
+
+        search.lang = Some("de".to_string());
+        assert_eq!(
+            describe(&search),
+            "weekly-rust: rust async (all, rank recent, rating 4+, lang de)"
+        );
+
+        search.lang = None;
+        search.time.added_within = Some("30d".to_string());
+        assert_eq!(
+            describe(&search),
+            "weekly-rust: rust async (all, rank recent, rating 4+, added within 30d)"
+        );
+    }
+}
@@ -1,6 +1,17 @@
+use super::exec::ExecCommand;
+use super::helpers::{open_bookmarks, pick_bookmarks, run_pick_action};
 use super::{AppContext, BukuCommand};
-use bukurs::error::Result;
+use crate::format::{ListingBadges, OutputFormat};
+use bukurs::composite::{CompositeDb, Source, SourcedBookmark};
+use bukurs::error::{BukursError, Result};
+use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::timeframe::TimeFilter;
+use bukurs::operations::ResultSet;
+use bukurs::search::{KeywordMatch, SavedSearch, ScoredBookmark, SearchRank};
+use bukurs::service::{BookmarkService, SearchQuery};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchCommand {
@@ -8,39 +19,248 @@ pub struct SearchCommand {
     pub all: bool,
     pub deep: bool,
     pub regex: bool,
+    /// Only match descriptions (notes)
+    pub notes: bool,
     pub limit: Option<usize>,
     pub format: Option<String>,
     pub nc: bool,
     pub open: bool,
+    /// Run this command per result instead of showing the picker
+    pub exec: Option<String>,
+    /// Run this command once with all results
+    pub exec_batch: Option<String>,
+    /// Commands run at once with `exec` (default: one per CPU)
+    pub jobs: Option<usize>,
+    /// Only search the results of the previous search
+    pub within_last: bool,
+    /// Order results best first: relevance, recent, visits or rating (default from config)
+    pub rank: Option<String>,
+    /// Match prefixes, then similar words when nothing matches
+    pub fuzzy: bool,
+    /// Also keep the search under this name for `saved run`
+    pub save: Option<String>,
+    /// Only keep bookmarks rated at least this many stars
+    pub min_rating: Option<u8>,
+    /// Only keep bookmarks whose page is in this language, e.g. `de`
+    pub lang: Option<String>,
+    /// Only keep bookmarks added or last visited within these bounds
+    pub time: TimeFilter,
 }
 
 impl BukuCommand for SearchCommand {
     fn execute(&self, ctx: &AppContext) -> Result<()> {
-        let any = !self.all;
-        eprintln!("Searching for: {:?}", self.keywords);
-        let mut records = ctx.db.search(&self.keywords, any, self.deep, self.regex)?;
+        let results_path = results_path(ctx.db_path);
+        let within = if self.within_last {
+            let previous = results_path
+                .as_deref()
+                .map(ResultSet::read)
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| {
+                    BukursError::InvalidInput("No previous search to refine".to_string())
+                })?;
+            eprintln!(
+                "Searching for: {:?} within {} results for {:?}",
+                self.keywords,
+                previous.ids.len(),
+                previous.keywords
+            );
+            Some(previous)
+        } else {
+            eprintln!("Searching for: {:?}", self.keywords);
+            None
+        };
+        let explicit_rank = self
+            .rank
+            .as_deref()
+            .map(|rank| {
+                SearchRank::from_string(rank).ok_or_else(|| {
+                    BukursError::InvalidInput(format!(
+                        "Unknown rank '{}': use relevance, recent, visits or rating",
+                        rank
+                    ))
+                })
+            })
+            .transpose()?;
+        let rank = explicit_rank.or(ctx.config.search.rank);
+
+        if let Some(name) = &self.save {
+            let replaced = ctx.db.save_search(&self.saved(name, explicit_rank))?;
+            let verb = if replaced { "Replaced" } else { "Saved" };
+            eprintln!("{} saved search '{}'", verb, name);
+        }
+        if !self.keywords.is_empty() {
+            // Offered for completion by the interactive shell
+            if let Err(e) = ctx.db.record_search_query(&self.keywords.join(" ")) {
+                log::warn!("Failed to record the search in the history: {}", e);
+            }
+        }
+
+        let query = SearchQuery {
+            keywords: self.keywords.clone(),
+            all: self.all,
+            regex: self.regex,
+            limit: self.limit,
+            notes: self.notes,
+            within,
+            rank,
+            weights: ctx.config.search.weights,
+            keyword_match: if self.fuzzy {
+                KeywordMatch::Fuzzy
+            } else {
+                ctx.config.search.keyword_match
+            },
+            min_rating: self.min_rating,
+            lang: self.lang.clone(),
+            time: self.time.clone(),
+        };
+        let (results, shared) = search_with_shared(ctx, &query)?;
+        let records: Vec<Bookmark> = results.iter().map(|r| r.bookmark.clone()).collect();
 
         if records.is_empty() {
-            eprintln!("No bookmarks found matching the search criteria.");
-            return Ok(());
+            if shared.is_empty() {
+                eprintln!("No bookmarks found matching the search criteria.");
+            }
+            return self.print_shared(ctx, &shared);
+        }
+
+        // Remembered for the next `--within-last`; an empty result would leave nothing to refine
+        if let Some(path) = &results_path {
+            if let Err(e) = ResultSet::new(&self.keywords, &records).write(path) {
+                log::warn!("Failed to save search results to {}: {}", path.display(), e);
+            }
         }
 
-        // Apply limit if specified
-        if let Some(limit) = self.limit {
-            let start = records.len().saturating_sub(limit);
-            records = records.into_iter().skip(start).collect();
+        if let Some(command) = &self.exec {
+            return ExecCommand::parse(command)?.run_each(&records, self.jobs);
+        }
+        if let Some(command) = &self.exec_batch {
+            return ExecCommand::parse(command)?.run_batch(&records);
         }
 
-        // Run fuzzy picker on the filtered records and handle selection
-        crate::commands::helpers::handle_bookmark_selection(
-            &records,
-            Some(self.keywords.join(" ")),
-            self.open,
-            self.format.as_deref(),
+        // Run fuzzy picker on the filtered records, then open or print the selection,
+        // or run the action of the key it was picked with
+        let Some(picked) = pick_bookmarks(ctx, &records, Some(self.keywords.join(" ")))? else {
+            return Ok(());
+        };
+        if run_pick_action(ctx, &picked)? {
+            return Ok(());
+        }
+        if self.open {
+            return open_bookmarks(ctx.db, &picked.bookmarks);
+        }
+        let selected: HashSet<usize> = picked.bookmarks.iter().map(|b| b.id).collect();
+        let selected: Vec<_> = results
+            .into_iter()
+            .filter(|r| selected.contains(&r.bookmark.id))
+            .collect();
+        OutputFormat::resolve(self.format.as_deref(), ctx.config)?.print_scored(
+            &selected,
+            &ListingBadges::load(ctx.db)?,
             self.nc,
-        )?;
+        );
+        self.print_shared(ctx, &shared)
+    }
+}
+
+impl SearchCommand {
+    /// A search like a saved one, with no output options set
+    pub fn from_saved(saved: &SavedSearch) -> Self {
+        Self {
+            keywords: saved.keywords.clone(),
+            all: saved.all,
+            deep: false,
+            regex: saved.regex,
+            notes: saved.notes,
+            limit: None,
+            format: None,
+            nc: false,
+            open: false,
+            exec: None,
+            exec_batch: None,
+            jobs: None,
+            within_last: false,
+            rank: saved.rank.map(|rank| rank.to_string()),
+            fuzzy: saved.fuzzy,
+            save: None,
+            min_rating: saved.min_rating,
+            lang: saved.lang.clone(),
+            time: saved.time.clone(),
+        }
+    }
+
+    /// List the matches from `shared_db` after the user's own; they aren't
+    /// offered to the picker, `--open` or `--exec`, whose IDs are personal
+    fn print_shared(&self, ctx: &AppContext, shared: &[SourcedBookmark]) -> Result<()> {
+        if shared.is_empty() {
+            return Ok(());
+        }
+        let format = OutputFormat::resolve(self.format.as_deref(), ctx.config)?;
+        if matches!(format, OutputFormat::Json) {
+            // Each object carries "source": "shared"
+            for result in shared {
+                println!("{}", serde_json::to_string(result)?);
+            }
+            return Ok(());
+        }
+        eprintln!("{} more in the shared database (read-only):", shared.len());
+        let results: Vec<ScoredBookmark> = shared.iter().map(|r| r.result.clone()).collect();
+        format.print_scored(&results, &ListingBadges::default(), self.nc);
         Ok(())
     }
+
+    /// This search as `--save` stores it; output options aren't kept
+    fn saved(&self, name: &str, rank: Option<SearchRank>) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            keywords: self.keywords.clone(),
+            all: self.all,
+            regex: self.regex,
+            notes: self.notes,
+            rank,
+            fuzzy: self.fuzzy,
+            min_rating: self.min_rating,
+            lang: self.lang.clone(),
+            time: self.time.clone(),
+        }
+    }
+}
+
+/// Run `query` on the database, and on `shared_db` when one is configured
+///
+/// An unreachable shared database (a network drive that isn't mounted) only
+/// warns, so searching your own bookmarks keeps working.
+fn search_with_shared(
+    ctx: &AppContext,
+    query: &SearchQuery,
+) -> Result<(Vec<ScoredBookmark>, Vec<SourcedBookmark>)> {
+    let fetch = ctx.config.fetch_options();
+    let composite = match &ctx.config.shared_db {
+        None => None,
+        Some(path) => match CompositeDb::open(ctx.db, path, &ctx.config.database) {
+            Ok(composite) => Some(composite),
+            Err(e) => {
+                eprintln!("Warning: {}; searching your bookmarks only", e);
+                None
+            }
+        },
+    };
+    let Some(composite) = composite else {
+        return Ok((
+            BookmarkService::new(ctx.db, fetch).search_scored(query)?,
+            Vec::new(),
+        ));
+    };
+    let (personal, shared): (Vec<_>, Vec<_>) = composite
+        .search_scored(query, &fetch)?
+        .into_iter()
+        .partition(|r| r.source == Source::Personal);
+    Ok((personal.into_iter().map(|r| r.result).collect(), shared))
+}
+
+/// Where results are remembered between searches; in-memory databases keep none
+fn results_path(db_path: &Path) -> Option<PathBuf> {
+    (db_path != Path::new(":memory:")).then(|| ResultSet::path_for(db_path))
 }
 
 #[cfg(test)]
@@ -74,6 +294,8 @@ mod tests {
                 db: &self.db,
                 config: &self.config,
                 db_path: &self.db_path,
+                interaction: Default::default(),
+                output: Default::default(),
             }
         }
     }
@@ -103,10 +325,21 @@ mod tests {
             all: false,
             deep: false,
             regex: false,
+            notes: false,
             limit: None,
             format: None,
             nc: true, // No color for tests
             open: false,
+            exec: None,
+            exec_batch: None,
+            jobs: None,
+            within_last: false,
+            rank: None,
+            fuzzy: false,
+            save: None,
+            min_rating: None,
+            lang: None,
+            time: Default::default(),
         };
 
         // We can't easily capture stdout/stderr here to verify output,
@@ -118,4 +351,37 @@ mod tests {
         }
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_search_with_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared_path = dir.path().join("shared.db");
+        let shared = BukuDb::init(&shared_path).unwrap();
+        shared
+            .add_rec("https://rust-lang.org", "Rust", ",", "", None)
+            .unwrap();
+        shared
+            .add_rec("https://docs.rs", "Rust docs", ",", "", None)
+            .unwrap();
+
+        let mut env = TestEnv::new();
+        env.db
+            .add_rec("https://docs.rs", "Docs", ",rust,", "", None)
+            .unwrap();
+        let query = SearchQuery {
+            keywords: vec!["rust".to_string()],
+            ..SearchQuery::default()
+        };
+
+        env.config.shared_db = Some(shared_path);
+        let (personal, shared) = search_with_shared(&env.ctx(), &query).unwrap();
+        assert_eq!(personal.len(), 1);
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].result.bookmark.url, "https://rust-lang.org");
+
+        // A missing shared database leaves the personal results
+        env.config.shared_db = Some(dir.path().join("missing.db"));
+        let (personal, shared) = search_with_shared(&env.ctx(), &query).unwrap();
+        assert_eq!((personal.len(), shared.len()), (1, 0));
+    }
 }
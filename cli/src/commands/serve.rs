@@ -0,0 +1,52 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::{BukursError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Serve the database to other programs until interrupted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServeCommand {
+    /// Speak gRPC (the only protocol so far)
+    pub grpc: bool,
+    pub addr: String,
+}
+
+impl BukuCommand for ServeCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        if !self.grpc {
+            return Err(BukursError::InvalidInput(
+                "no protocol given; gRPC is the only one so far (serve --grpc)".to_string(),
+            ));
+        }
+        self.serve_grpc(ctx)
+    }
+}
+
+impl ServeCommand {
+    #[cfg(feature = "grpc")]
+    fn serve_grpc(&self, ctx: &AppContext) -> Result<()> {
+        use crate::interrupt::InterruptGuard;
+        use bukurs::audit::AuditSource;
+        use bukurs::grpc::{self, GrpcService};
+        use std::net::SocketAddr;
+
+        let addr: SocketAddr = self.addr.parse().map_err(|e| {
+            BukursError::InvalidInput(format!("invalid address '{}': {}", self.addr, e))
+        })?;
+        // A connection of its own, so changes are audited as the server's
+        let db = ctx.config.open_db(Some(ctx.db_path), AuditSource::Server)?;
+        let service = GrpcService::new(db, ctx.config.fetch_options(), ctx.config.search.clone());
+
+        eprintln!("Serving gRPC on {} (Ctrl-C to stop)", addr);
+        let interrupt = InterruptGuard::install();
+        grpc::serve(service, addr, interrupt.token())?;
+        eprintln!("Server stopped");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    fn serve_grpc(&self, _ctx: &AppContext) -> Result<()> {
+        Err(BukursError::Other(
+            "this bukurs was built without gRPC support (the `grpc` feature)".to_string(),
+        ))
+    }
+}
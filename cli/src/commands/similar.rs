@@ -0,0 +1,114 @@
+use super::{AppContext, BukuCommand};
+use bukurs::error::Result;
+use bukurs::operations::Selector;
+use bukurs::similar::{similar_to, SimilarBookmark};
+use serde::{Deserialize, Serialize};
+
+/// List the bookmarks most similar to one bookmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarCommand {
+    pub id: String,
+    pub limit: usize,
+}
+
+impl BukuCommand for SimilarCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let bookmarks = Selector::parse(std::slice::from_ref(&self.id))?
+            .resolve(ctx.db)?
+            .bookmarks;
+        if bookmarks.len() != 1 {
+            return Err(format!("Bookmark {} not found", self.id).into());
+        }
+        let target = &bookmarks[0];
+
+        let similar = similar_to(ctx.db, target.id, self.limit)?;
+        if similar.is_empty() {
+            eprintln!("No bookmarks similar to {}", target.id);
+            return Ok(());
+        }
+        for entry in &similar {
+            println!("{}", format_similar(entry));
+        }
+        Ok(())
+    }
+}
+
+/// Id, title and score, then the URL and what the bookmarks have in common
+fn format_similar(entry: &SimilarBookmark) -> String {
+    let bookmark = &entry.bookmark;
+    let title = if bookmark.title.is_empty() {
+        &bookmark.url
+    } else {
+        &bookmark.title
+    };
+    let mut reasons = Vec::new();
+    if !entry.shared_tags.is_empty() {
+        reasons.push(format!("tags: {}", entry.shared_tags.join(", ")));
+    }
+    if entry.same_domain {
+        reasons.push("same domain".to_string());
+    }
+    let mut out = format!(
+        "{}. {} ({:.2})\n   > {}",
+        bookmark.id, title, entry.score, bookmark.url
+    );
+    if !reasons.is_empty() {
+        out.push_str(&format!("\n   # {}", reasons.join("; ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bukurs::config::Config;
+    use bukurs::db::BukuDb;
+    use bukurs::models::bookmark::Bookmark;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_format_similar() {
+        let entry = SimilarBookmark {
+            bookmark: Bookmark::new(
+                4,
+                "https://doc.rust-lang.org/std".to_string(),
+                "Standard library".to_string(),
+                ",rust,docs,".to_string(),
+                String::new(),
+            ),
+            score: 0.7,
+            shared_tags: vec!["docs".to_string(), "rust".to_string()],
+            same_domain: true,
+        };
+        assert_eq!(
+            format_similar(&entry),
+            "4. Standard library (0.70)\n   > https://doc.rust-lang.org/std\n   # tags: docs, rust; same domain"
+        );
+    }
+
+    #[test]
+    fn test_similar_command() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let config = Config::default();
+        let db_path = PathBuf::from(":memory:");
+        let ctx = AppContext {
+            db: &db,
+            config: &config,
+            db_path: &db_path,
+            interaction: Default::default(),
+            output: Default::default(),
+        };
+        db.add_rec("https://a.com", "A", ",rust,", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "B", ",rust,", "", None)
+            .unwrap();
+
+        let cmd = |id: &str| SimilarCommand {
+            id: id.to_string(),
+            limit: 10,
+        };
+        cmd("1").execute(&ctx).unwrap();
+        cmd("-1").execute(&ctx).unwrap();
+        assert!(cmd("9").execute(&ctx).is_err());
+    }
+}
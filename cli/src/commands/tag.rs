@@ -1,6 +1,11 @@
 use super::{AppContext, BukuCommand};
+use crate::format::OutputFormat;
 use bukurs::error::Result;
+use bukurs::service::{BookmarkService, ChangeSummary};
+use bukurs::tag_map::TagMap;
+use bukurs::tags::{TagNormalization, TagSetOp};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Command to search bookmarks by tags with fuzzy search support
 ///
@@ -34,6 +39,7 @@ impl BukuCommand for TagCommand {
             eprintln!("Selecting from {} unique tags...", tags.len());
 
             // Run fuzzy picker on tags
+            ctx.interaction.require_input("The tag picker")?;
             if let Some(selected_tag) = bukurs::fuzzy::run_fuzzy_tag_search(&tags)? {
                 eprintln!("Selected tag: {}", selected_tag);
 
@@ -53,10 +59,11 @@ impl BukuCommand for TagCommand {
 
                 // Run fuzzy picker on the bookmarks and handle selection
                 crate::commands::helpers::handle_bookmark_selection(
+                    ctx,
                     &records,
                     None,
                     self.open,
-                    self.format.as_deref(),
+                    &OutputFormat::resolve(self.format.as_deref(), ctx.config)?,
                     self.nc,
                 )?;
             }
@@ -76,13 +83,129 @@ impl BukuCommand for TagCommand {
 
             // Run fuzzy picker on the filtered records and handle selection
             crate::commands::helpers::handle_bookmark_selection(
+                ctx,
                 &records,
                 Some(self.tags.join(" ")),
                 self.open,
-                self.format.as_deref(),
+                &OutputFormat::resolve(self.format.as_deref(), ctx.config)?,
                 self.nc,
             )?;
         }
         Ok(())
     }
 }
+
+/// Add, remove or set tags on every bookmark a selector matches (`tag add|remove|set`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetagCommand {
+    pub op: TagSetOp,
+    pub tags: Vec<String>,
+    /// Indices, ranges, `*` or search keywords
+    pub selector: Vec<String>,
+    pub lowercase: bool,
+}
+
+impl BukuCommand for RetagCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let service = BookmarkService::new(ctx.db, ctx.config.fetch_options());
+        let bookmarks = service.select(&self.selector)?.bookmarks;
+        if bookmarks.is_empty() {
+            eprintln!("No bookmarks found");
+            return Ok(());
+        }
+
+        let outcome = service.retag(&bookmarks, &self.tags, self.op, self.lowercase)?;
+        eprintln!("Retagged {} bookmark(s)", outcome.updated);
+        if outcome.updated < outcome.matched {
+            eprintln!(
+                "{} bookmark(s) already had those tags",
+                outcome.matched - outcome.updated
+            );
+        }
+        ctx.report(&ChangeSummary {
+            command: "tag".to_string(),
+            ..ChangeSummary::from(&outcome)
+        })
+    }
+}
+
+/// Rewrite every bookmark's tags consistently (`tag normalize`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeTagsCommand {
+    pub normalization: TagNormalization,
+    pub dry_run: bool,
+}
+
+impl BukuCommand for NormalizeTagsCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let service = BookmarkService::new(ctx.db, ctx.config.fetch_options());
+        let outcome = service.normalize_tags(&self.normalization, self.dry_run)?;
+
+        for merge in &outcome.merges {
+            let from: Vec<String> = merge.from.iter().map(|t| format!("{:?}", t)).collect();
+            println!("{} -> {:?}", from.join(", "), merge.tag);
+        }
+        if outcome.ids.is_empty() {
+            eprintln!("Tags are already normalized");
+            return Ok(());
+        }
+        if self.dry_run {
+            eprintln!(
+                "Would rewrite the tags of {} bookmark(s) ({} merge(s)); nothing was changed",
+                outcome.ids.len(),
+                outcome.merges.len()
+            );
+            return Ok(());
+        }
+        eprintln!(
+            "Rewrote the tags of {} bookmark(s) ({} merge(s))",
+            outcome.ids.len(),
+            outcome.merges.len()
+        );
+        ctx.report(&ChangeSummary {
+            ids: outcome.ids.clone(),
+            updated: outcome.ids.len(),
+            ..ChangeSummary::new("tag")
+        })
+    }
+}
+
+/// Rewrite tags by the rules of a CSV mapping file (`tag apply-map`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyTagMapCommand {
+    pub path: PathBuf,
+    pub dry_run: bool,
+}
+
+impl BukuCommand for ApplyTagMapCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let map = TagMap::parse(&std::fs::read_to_string(&self.path)?)?;
+        if map.is_empty() {
+            eprintln!("No rules in {}", self.path.display());
+            return Ok(());
+        }
+        let service = BookmarkService::new(ctx.db, ctx.config.fetch_options());
+        let changes = service.apply_tag_map(&map, self.dry_run)?;
+
+        for change in &changes {
+            println!("{}: {} -> {}", change.id, change.before, change.after);
+        }
+        if changes.is_empty() {
+            eprintln!("No bookmarks to retag");
+            return Ok(());
+        }
+        if self.dry_run {
+            eprintln!(
+                "Would retag {} bookmark(s); nothing was changed",
+                changes.len()
+            );
+            return Ok(());
+        }
+        eprintln!("Retagged {} bookmark(s)", changes.len());
+        ctx.report(&ChangeSummary {
+            ids: changes.iter().map(|c| c.id).collect(),
+            updated: changes.len(),
+            ..ChangeSummary::new("tag")
+        })
+    }
+}
@@ -1,11 +1,10 @@
 use super::{AppContext, BukuCommand};
 use crate::cli::get_exe_name;
-use crate::fetch_ui::fetch_with_spinner;
-use crate::tag_ops::{apply_tag_operations, parse_tag_operations};
-use bukurs::error::Result;
+use crate::fetch_ui::{fetch_options, ProgressBarSink};
+use crate::interrupt::InterruptGuard;
+use bukurs::error::{BukursError, Result};
 use bukurs::operations;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use rayon::prelude::*;
+use bukurs::service::{BookmarkService, ChangeSummary, FieldChanges};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +15,10 @@ pub struct UpdateCommand {
     pub title: Option<String>,
     pub comment: Option<String>,
     pub immutable: Option<u8>,
+    /// Choose the bookmarks to update in the fuzzy picker
+    pub pick: bool,
+    /// Per-request fetch timeout in seconds when refreshing, overriding the config
+    pub timeout: Option<u64>,
 }
 
 impl BukuCommand for UpdateCommand {
@@ -26,6 +29,25 @@ impl BukuCommand for UpdateCommand {
             || self.comment.is_some()
             || self.immutable.is_some();
 
+        if self.pick {
+            // Candidates are the given IDs/keywords, or every bookmark when none are given
+            ctx.interaction.require_input("The bookmark picker")?;
+            let candidates = operations::prepare_print(&self.ids, ctx.db)?.bookmarks;
+            let picked = super::helpers::select_bookmarks(ctx, &candidates, None)?;
+            if picked.is_empty() {
+                eprintln!("No bookmarks selected.");
+                return Ok(());
+            }
+
+            let cmd = UpdateCommand {
+                ids: picked.iter().map(|b| b.id.to_string()).collect(),
+                pick: false,
+                timeout: None,
+                ..self.clone()
+            };
+            return cmd.execute(ctx);
+        }
+
         if self.ids.is_empty() {
             eprintln!("Usage: {} update <ID|RANGE|*> [OPTIONS]", get_exe_name());
             eprintln!("Examples:");
@@ -58,123 +80,59 @@ impl BukuCommand for UpdateCommand {
 
         if has_edit_options {
             // Field update mode
-            let operation = operations::prepare_print(&self.ids, ctx.db)?;
-            let bookmarks = operation.bookmarks;
+            let service = BookmarkService::new(ctx.db, fetch_options(ctx.config, self.timeout));
+            let bookmarks = service.select(&self.ids)?.bookmarks;
 
             if bookmarks.is_empty() {
                 eprintln!("No bookmarks found");
                 return Ok(());
             }
 
-            let url_ref = self.url.as_deref();
-            let title_str = self.title.as_deref();
-            let desc_ref = self.comment.as_deref();
-            let tag_operations = self.tag.as_ref().map(|tags| parse_tag_operations(tags));
+            let changes = FieldChanges {
+                url: self.url.clone(),
+                title: self.title.clone(),
+                desc: self.comment.clone(),
+                tags: self.tag.clone(),
+                immutable: self.immutable,
+            };
 
             if bookmarks.len() > 1 {
-                // Batch update mode with parallel processing and progress bar
                 eprintln!("Updating {} bookmark(s)...", bookmarks.len());
+            }
 
-                let multi = MultiProgress::new();
-                let pb = multi.add(ProgressBar::new(bookmarks.len() as u64));
-                pb.set_style(
-                    ProgressStyle::default_bar()
-                        .template("{msg} [{bar:40.cyan/blue}] {pos}/{len}")
-                        .unwrap()
-                        .progress_chars("=>-"),
-                );
-                pb.set_message("Processing bookmarks");
-
-                // Now perform the batch update in a single transaction
-                let result = if let Some(ref ops) = tag_operations {
-                    // Compute updates for each bookmark in parallel
-                    let updated_bookmarks: Vec<_> = bookmarks
-                        .par_iter()
-                        .map(|bookmark| {
-                            let mut updated = bookmark.clone();
-                            updated.tags = apply_tag_operations(&bookmark.tags, ops);
-                            pb.inc(1);
-                            updated
-                        })
-                        .collect();
-
-                    pb.finish_and_clear();
-
-                    ctx.db.update_rec_batch_with_tags(
-                        &updated_bookmarks,
-                        url_ref,
-                        title_str,
-                        desc_ref,
-                        self.immutable,
-                    )
-                } else {
-                    // No tag operations, just count progress and use original bookmarks
-                    bookmarks.par_iter().for_each(|_| pb.inc(1));
-                    pb.finish_and_clear();
-
-                    ctx.db.update_rec_batch(
-                        &bookmarks,
-                        url_ref,
-                        title_str,
-                        None,
-                        desc_ref,
-                        self.immutable,
-                    )
-                };
-
-                match result {
-                    Ok((success_count, failed_count)) => {
-                        eprintln!();
-                        if success_count > 0 {
-                            eprintln!("✓ Successfully updated {} bookmark(s)", success_count);
-                        }
-                        if failed_count > 0 {
-                            eprintln!("✗ Failed to update {} bookmark(s)", failed_count);
-                        }
+            let lock = ctx.db.write_lock()?;
+            let result = service.update_bookmarks(&bookmarks, &changes);
+            drop(lock);
+            let summary = match result {
+                Ok(outcome) if bookmarks.len() == 1 => {
+                    if outcome.updated > 0 {
+                        eprintln!("✓ Updated bookmark {}", bookmarks[0].id);
                     }
-                    Err(e) => {
-                        eprintln!("✗ Batch update failed: {}", e);
-                        eprintln!("All changes have been rolled back.");
+                    for error in &outcome.errors {
+                        eprintln!("✗ {}", error);
                     }
+                    ChangeSummary::from(&outcome)
                 }
-            } else {
-                // Single bookmark update
-                let bookmark = &bookmarks[0];
-
-                let final_tags = if let Some(ref ops) = tag_operations {
-                    let new_tags = apply_tag_operations(&bookmark.tags, ops);
-                    Some(new_tags)
-                } else {
-                    None
-                };
-
-                let tags_ref = final_tags.as_deref();
-
-                match ctx.db.update_rec_partial(
-                    bookmark.id,
-                    url_ref,
-                    title_str,
-                    tags_ref,
-                    desc_ref,
-                    None, // parent_id
-                ) {
-                    Ok(()) => {
-                        eprintln!("✓ Updated bookmark {}", bookmark.id);
+                Ok(outcome) => {
+                    eprintln!();
+                    if outcome.updated > 0 {
+                        eprintln!("✓ Successfully updated {} bookmark(s)", outcome.updated);
                     }
-                    Err(e) => {
-                        if let rusqlite::Error::SqliteFailure(err, _) = &e {
-                            // SQLITE_CONSTRAINT_UNIQUE = 2067
-                            if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE {
-                                eprintln!("✗ Bookmark {}: URL already exists", bookmark.id);
-                            } else {
-                                eprintln!("✗ Bookmark {}: {}", bookmark.id, e);
-                            }
-                        } else {
-                            eprintln!("✗ Bookmark {}: {}", bookmark.id, e);
-                        }
+                    if outcome.failed > 0 {
+                        eprintln!("✗ Failed to update {} bookmark(s)", outcome.failed);
                     }
+                    ChangeSummary::from(&outcome)
                 }
-            }
+                Err(e) => {
+                    eprintln!("✗ Batch update failed: {}", e);
+                    eprintln!("All changes have been rolled back.");
+                    ChangeSummary {
+                        errors: vec![e.to_string()],
+                        ..ChangeSummary::new("update")
+                    }
+                }
+            };
+            ctx.report(&summary)?;
         } else {
             // Refresh metadata mode
             let operation = operations::prepare_print(&self.ids, ctx.db)?;
@@ -185,62 +143,29 @@ impl BukuCommand for UpdateCommand {
                 return Ok(());
             }
 
-            eprintln!("Refreshing metadata for {} bookmark(s)...", bookmarks.len());
-
-            let multi = MultiProgress::new();
-            let pb = multi.add(ProgressBar::new(bookmarks.len() as u64));
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{msg} [{bar:40.cyan/blue}] {pos}/{len}")
-                    .unwrap()
-                    .progress_chars("=>-"),
-            );
-            pb.set_message("Overall progress");
-
-            let mut success_count = 0;
-            let mut failed_count = 0;
-            let mut failed_ids: Vec<usize> = Vec::new();
-
-            for bookmark in &bookmarks {
-                match fetch_with_spinner(&bookmark.url, &ctx.config.user_agent) {
-                    Ok(fetch_result) => {
-                        let new_title = if !fetch_result.title.is_empty() {
-                            Some(fetch_result.title.as_str())
-                        } else {
-                            None
-                        };
+            if ctx.config.default_offline {
+                eprintln!(
+                    "Offline: not refreshing {} bookmark(s) (pass --online to fetch)",
+                    bookmarks.len()
+                );
+                return Ok(());
+            }
 
-                        let new_desc = if !fetch_result.desc.is_empty() {
-                            Some(fetch_result.desc.as_str())
-                        } else {
-                            None
-                        };
+            eprintln!("Refreshing metadata for {} bookmark(s)...", bookmarks.len());
 
-                        match ctx.db.update_rec_partial(
-                            bookmark.id,
-                            None,
-                            new_title,
-                            None,
-                            new_desc,
-                            None,
-                        ) {
-                            Ok(()) => success_count += 1,
-                            Err(_) => {
-                                failed_count += 1;
-                                failed_ids.push(bookmark.id);
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        failed_count += 1;
-                        failed_ids.push(bookmark.id);
-                    }
-                }
-                pb.inc(1);
+            let service = BookmarkService::new(ctx.db, fetch_options(ctx.config, self.timeout));
+            let interrupt = InterruptGuard::install();
+            let outcome =
+                service.refresh(&bookmarks, &ProgressBarSink::new(), interrupt.token())?;
+            drop(interrupt);
+            ctx.report(&ChangeSummary::from(&outcome))?;
+            let success_count = outcome.refreshed;
+            let failed_count = outcome.failed.len();
+            let failed_ids: Vec<usize> = outcome.failed.iter().map(|(id, _)| *id).collect();
+            for (id, error) in &outcome.failed {
+                log::debug!("Refreshing bookmark {} failed: {}", id, error);
             }
 
-            pb.finish_and_clear();
-
             if success_count > 0 {
                 eprintln!("✓ Successfully refreshed {} bookmark(s)", success_count);
             }
@@ -264,6 +189,13 @@ impl BukuCommand for UpdateCommand {
                         .join(" ")
                 );
             }
+            if outcome.cancelled {
+                eprintln!(
+                    "Interrupted: {} bookmark(s) left unchanged",
+                    bookmarks.len() - success_count - failed_count
+                );
+                return Err(BukursError::Cancelled);
+            }
         }
 
         Ok(())
@@ -301,6 +233,8 @@ mod tests {
                 db: &self.db,
                 config: &self.config,
                 db_path: &self.db_path,
+                interaction: Default::default(),
+                output: Default::default(),
             }
         }
     }
@@ -327,6 +261,8 @@ mod tests {
             title: Some("New Title".to_string()),
             comment: Some("New Desc".to_string()),
             immutable: None,
+            pick: false,
+            timeout: None,
         };
 
         let result = cmd.execute(&env.ctx());
@@ -0,0 +1,32 @@
+use super::{AppContext, BukuCommand};
+use crate::interrupt::InterruptGuard;
+use bukurs::error::Result;
+use bukurs::events::EventWatcher;
+use serde::{Deserialize, Serialize};
+
+/// Print change events as JSON lines until interrupted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchCommand {
+    /// Start after this event instead of at the newest one
+    pub since: Option<i64>,
+}
+
+impl BukuCommand for WatchCommand {
+    fn execute(&self, ctx: &AppContext) -> Result<()> {
+        let mut watcher = match self.since {
+            Some(seq) => EventWatcher::after(seq),
+            None => EventWatcher::new(ctx.db)?,
+        };
+        let interrupt = InterruptGuard::install();
+        loop {
+            let events = watcher.wait(ctx.db, interrupt.token())?;
+            if events.is_empty() {
+                // Interrupted
+                return Ok(());
+            }
+            for event in events {
+                println!("{}", serde_json::to_string(&event)?);
+            }
+        }
+    }
+}
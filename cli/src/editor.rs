@@ -4,9 +4,17 @@ use std::env;
 use std::fs;
 use std::io::Write;
 use std::process::Command;
-use tempfile::NamedTempFile;
+use std::sync::OnceLock;
 use thiserror::Error;
 
+/// Editor command from the config (`editor` / `BUKURS_EDITOR`), preferred over $EDITOR
+static EDITOR_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Use `editor` instead of $EDITOR for the rest of the run
+pub fn set_editor(editor: String) {
+    let _ = EDITOR_OVERRIDE.set(editor);
+}
+
 #[derive(Debug, Error)]
 pub enum EditorError {
     #[error("Failed to create temporary file: {0}")]
@@ -20,23 +28,204 @@ pub enum EditorError {
 
     #[error("URL cannot be empty")]
     EmptyUrl,
+
+    #[error("Invalid URL '{0}' (expected e.g. https://example.com)")]
+    InvalidUrl(String),
+
+    #[error("Invalid tag name: '{0}' (tags cannot contain spaces)")]
+    InvalidTag(String),
+
+    #[error("Invalid fetch value '{0}' (expected yes or no)")]
+    InvalidFetch(String),
+
+    #[error("No changes made")]
+    Cancelled,
+
+    #[error("Bookmark block is missing its id line")]
+    MissingId,
+
+    #[error("Invalid bookmark id '{0}'")]
+    InvalidId(String),
+
+    #[error("Bookmark {0} was not part of the edited selection")]
+    UnknownId(usize),
+
+    #[error("Bookmark {0} appears more than once")]
+    DuplicateId(usize),
 }
 
 pub type Result<T> = std::result::Result<T, EditorError>;
 
 pub fn edit_bookmark(bookmark: &Bookmark) -> Result<Bookmark> {
-    // Get editor from environment, default to vim
-    let editor = env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
-
-    // Create temporary file with bookmark data in YAML format
-    let mut temp_file = NamedTempFile::new()?;
-
     // Write bookmark as YAML
     let yaml_content = format!(
         "# Edit bookmark (lines starting with # are comments)\n\
          # Save and exit to update, or exit without saving to cancel\n\
          \n\
-         id: {}\n\
+         {}",
+        format_bookmark(bookmark)
+    );
+
+    let edited_content = open_in_editor(&yaml_content, "")?;
+
+    // Parse the edited YAML
+    parse_edited_bookmark(&edited_content, bookmark.id)
+}
+
+/// Edit several bookmarks in a single buffer
+///
+/// Each bookmark is written as a YAML-style block separated by `---` lines.
+/// Returns only the bookmarks whose fields were changed in the editor.
+pub fn edit_bookmarks(bookmarks: &[Bookmark]) -> Result<Vec<Bookmark>> {
+    let mut content = format!(
+        "# Edit {} bookmarks (lines starting with # are comments)\n\
+         # Blocks are separated by --- lines; keep each id line intact\n\
+         # Delete a block to leave that bookmark unchanged\n\
+         # Save and exit to apply all changes as one undoable batch\n",
+        bookmarks.len()
+    );
+    for bookmark in bookmarks {
+        content.push_str("\n---\n");
+        content.push_str(&format_bookmark(bookmark));
+    }
+
+    let edited_content = open_in_editor(&content, "")?;
+    let edited = parse_edited_batch(&edited_content, bookmarks)?;
+
+    Ok(changed_bookmarks(bookmarks, edited))
+}
+
+/// Edit a bookmark's description as a Markdown document
+///
+/// Returns the new notes with line endings normalized and trailing
+/// whitespace trimmed; leaving them unchanged cancels.
+pub fn edit_note(bookmark: &Bookmark) -> Result<String> {
+    let edited_content = open_in_editor(&bookmark.description, ".md")?;
+    let note = normalize_note(&edited_content);
+    if note == normalize_note(&bookmark.description) {
+        return Err(EditorError::Cancelled);
+    }
+    Ok(note)
+}
+
+/// Use `\n` line endings and drop trailing whitespace on every line
+fn normalize_note(content: &str) -> String {
+    content
+        .lines()
+        .map(utils::trim_end_simd)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Template shown when creating a bookmark
+const NEW_BOOKMARK_TEMPLATE: &str = "\
+# Create new bookmark (lines starting with # are comments)
+# Save and exit to create, or exit without saving to cancel
+#
+# url:         required, e.g. https://example.com
+# title:       optional, fetched from the page when left empty
+# tags:        optional, comma separated, e.g. rust, programming
+# fetch:       yes to fill empty fields from the page after saving, no to skip
+# description: optional, indented lines below 'description: |'
+
+url: 
+title: 
+tags: 
+fetch: yes
+description: |
+  
+";
+
+/// Marks the validation error written at the top of a re-opened template
+const ERROR_PREFIX: &str = "# Error:";
+
+/// A bookmark entered in the creation template
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBookmark {
+    pub bookmark: Bookmark,
+    /// Fetch the page's metadata for fields left empty
+    pub fetch: bool,
+}
+
+/// Edit a new bookmark template to create a bookmark
+///
+/// Invalid input re-opens the editor with the error noted at the top, so
+/// nothing typed is lost. Leaving the buffer unchanged cancels.
+pub fn edit_new_bookmark() -> Result<NewBookmark> {
+    let mut content = NEW_BOOKMARK_TEMPLATE.to_string();
+    loop {
+        let edited_content = open_in_editor(&content, "")?;
+        if edited_content == content {
+            return Err(EditorError::Cancelled);
+        }
+
+        match parse_new_bookmark(&edited_content) {
+            Ok(new_bookmark) => return Ok(new_bookmark),
+            Err(e) => content = annotate_error(&edited_content, &e),
+        }
+    }
+}
+
+/// Parse and validate the creation template
+fn parse_new_bookmark(content: &str) -> Result<NewBookmark> {
+    // ID 0 is replaced by the database
+    let bookmark = parse_edited_bookmark(content, 0)?;
+    validate_url(&bookmark.url)?;
+    if let Some(tag) = bookmark
+        .tags
+        .split(',')
+        .map(utils::trim_both_simd)
+        .find(|t| utils::has_spaces(t))
+    {
+        return Err(EditorError::InvalidTag(tag.to_string()));
+    }
+
+    // Only unindented lines are fields; indented ones belong to the description
+    let fetch = content
+        .lines()
+        .find_map(|line| line.strip_prefix("fetch:"))
+        .map(utils::trim_both_simd)
+        .unwrap_or("yes");
+    let fetch = match fetch.to_lowercase().as_str() {
+        "" | "yes" | "y" | "true" => true,
+        "no" | "n" | "false" => false,
+        _ => return Err(EditorError::InvalidFetch(fetch.to_string())),
+    };
+
+    Ok(NewBookmark { bookmark, fetch })
+}
+
+/// Require a scheme (`https:`, `mailto:`, ...) and no whitespace
+fn validate_url(url: &str) -> Result<()> {
+    let valid_scheme = url.split_once(':').is_some_and(|(scheme, rest)| {
+        !rest.is_empty()
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if !valid_scheme || url.chars().any(char::is_whitespace) {
+        return Err(EditorError::InvalidUrl(url.to_string()));
+    }
+    Ok(())
+}
+
+/// Put the error at the top of the buffer, replacing any earlier one
+fn annotate_error(content: &str, error: &EditorError) -> String {
+    let mut annotated = format!("{} {}\n", ERROR_PREFIX, error);
+    for line in content.lines().filter(|l| !l.starts_with(ERROR_PREFIX)) {
+        annotated.push_str(line);
+        annotated.push('\n');
+    }
+    annotated
+}
+
+/// Format a bookmark as a YAML-style block
+fn format_bookmark(bookmark: &Bookmark) -> String {
+    format!(
+        "id: {}\n\
          url: {}\n\
          title: {}\n\
          tags: {}\n\
@@ -46,9 +235,21 @@ pub fn edit_bookmark(bookmark: &Bookmark) -> Result<Bookmark> {
         bookmark.title,
         bookmark.tags,
         bookmark.description.replace("\n", "\n  ")
-    );
+    )
+}
 
-    temp_file.write_all(yaml_content.as_bytes())?;
+/// Write content to a temporary file, open it in $EDITOR and return the saved content
+/// `suffix` (e.g. ".md") lets the editor pick its syntax highlighting
+fn open_in_editor(content: &str, suffix: &str) -> Result<String> {
+    // Configured editor first, then the environment, default to vim
+    let editor = EDITOR_OVERRIDE
+        .get()
+        .cloned()
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vim".to_string());
+
+    let mut temp_file = tempfile::Builder::new().suffix(suffix).tempfile()?;
+    temp_file.write_all(content.as_bytes())?;
 
     let temp_path = temp_file.path().to_owned();
     let temp_path_str = temp_path.to_string_lossy();
@@ -64,51 +265,7 @@ pub fn edit_bookmark(bookmark: &Bookmark) -> Result<Bookmark> {
     }
 
     // Read edited content
-    let edited_content = fs::read_to_string(&temp_path)?;
-
-    // Parse the edited YAML
-    parse_edited_bookmark(&edited_content, bookmark.id)
-}
-
-/// Edit a new bookmark template to create a bookmark
-pub fn edit_new_bookmark() -> Result<Bookmark> {
-    // Get editor from environment, default to vim
-    let editor = env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
-
-    // Create temporary file with template
-    let mut temp_file = NamedTempFile::new()?;
-
-    // Write template as YAML
-    let template_content = "\
-# Create new bookmark (lines starting with # are comments)\n\
-# Save and exit to create, or exit without saving to cancel\n\
-# URL is required, other fields are optional\n\
-\n\
-url: \n\
-title: \n\
-tags: \n\
-description: |\n\
-  \n";
-
-    temp_file.write_all(template_content.as_bytes())?;
-
-    let temp_path = temp_file.path().to_owned();
-    let temp_path_str = temp_path.to_string_lossy();
-
-    // Open editor
-    let status = build_editor_command(&editor, &temp_path_str)
-        .status()
-        .map_err(|e| EditorError::EditorLaunch(editor.clone(), e))?;
-
-    if !status.success() {
-        return Err(EditorError::EditorExitFailure);
-    }
-
-    // Read edited content
-    let edited_content = fs::read_to_string(&temp_path)?;
-
-    // Parse the edited YAML with ID 0 (will be assigned by database)
-    parse_edited_bookmark(&edited_content, 0)
+    Ok(fs::read_to_string(&temp_path)?)
 }
 
 /// Build the command to launch the editor via shell
@@ -200,6 +357,71 @@ fn parse_edited_bookmark(content: &str, original_id: usize) -> Result<Bookmark>
     ))
 }
 
+/// Parse a batch buffer into bookmarks, matching each block to its original by id
+fn parse_edited_batch(content: &str, originals: &[Bookmark]) -> Result<Vec<Bookmark>> {
+    let mut blocks: Vec<String> = vec![String::new()];
+    for line in content.lines() {
+        // Only unindented separators split blocks; description lines are indented
+        if line.trim_end() == "---" {
+            blocks.push(String::new());
+            continue;
+        }
+        let block = blocks.last_mut().expect("blocks is never empty");
+        block.push_str(line);
+        block.push('\n');
+    }
+
+    let mut edited: Vec<Bookmark> = Vec::new();
+    for block in &blocks {
+        let has_content = block.lines().any(|line| {
+            let trimmed = utils::trim_both_simd(line);
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        });
+        if !has_content {
+            continue;
+        }
+
+        let id_value = block
+            .lines()
+            .map(utils::trim_both_simd)
+            .find_map(|line| line.strip_prefix("id:"))
+            .ok_or(EditorError::MissingId)?;
+        let id_value = utils::trim_both_simd(id_value);
+        let id: usize = id_value
+            .parse()
+            .map_err(|_| EditorError::InvalidId(id_value.to_string()))?;
+
+        if !originals.iter().any(|b| b.id == id) {
+            return Err(EditorError::UnknownId(id));
+        }
+        if edited.iter().any(|b| b.id == id) {
+            return Err(EditorError::DuplicateId(id));
+        }
+
+        edited.push(parse_edited_bookmark(block, id)?);
+    }
+
+    Ok(edited)
+}
+
+/// Keep the edited bookmarks whose fields differ from their originals
+fn changed_bookmarks(originals: &[Bookmark], edited: Vec<Bookmark>) -> Vec<Bookmark> {
+    edited
+        .into_iter()
+        .filter(|bookmark| {
+            originals
+                .iter()
+                .find(|b| b.id == bookmark.id)
+                .is_some_and(|original| {
+                    original.url.trim() != bookmark.url
+                        || original.title.trim() != bookmark.title
+                        || original.tags.trim() != bookmark.tags
+                        || original.description.trim() != bookmark.description
+                })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -413,4 +635,143 @@ description: |
         assert_eq!(result.tags, ",tag-1,tag_2,tag.3,");
         assert_eq!(result.description, "Special chars: !@#$%");
     }
+
+    fn sample_batch() -> Vec<Bookmark> {
+        vec![
+            Bookmark::new(
+                1,
+                "https://a.com".into(),
+                "A".into(),
+                ",a,".into(),
+                "First\nline".into(),
+            ),
+            Bookmark::new(
+                2,
+                "https://b.com".into(),
+                "B".into(),
+                ",b,".into(),
+                "".into(),
+            ),
+        ]
+    }
+
+    fn format_batch(bookmarks: &[Bookmark]) -> String {
+        bookmarks
+            .iter()
+            .map(format_bookmark)
+            .collect::<Vec<_>>()
+            .join("---\n")
+    }
+
+    #[test]
+    fn test_parse_edited_batch_roundtrip_has_no_changes() {
+        let originals = sample_batch();
+        let edited = parse_edited_batch(&format_batch(&originals), &originals).unwrap();
+        assert_eq!(edited.len(), 2);
+        assert!(changed_bookmarks(&originals, edited).is_empty());
+    }
+
+    #[test]
+    fn test_parse_edited_batch_detects_changes() {
+        let originals = sample_batch();
+        let content = format_batch(&originals).replace("tags: ,b,", "tags: ,b,rust,");
+        let changed = changed_bookmarks(
+            &originals,
+            parse_edited_batch(&content, &originals).unwrap(),
+        );
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, 2);
+        assert_eq!(changed[0].tags, ",b,rust,");
+    }
+
+    #[test]
+    fn test_parse_edited_batch_removed_block_is_ignored() {
+        let originals = sample_batch();
+        let content = format!("# header\n---\n{}", format_bookmark(&originals[1]));
+        let edited = parse_edited_batch(&content, &originals).unwrap();
+        assert_eq!(edited.len(), 1);
+        assert_eq!(edited[0].id, 2);
+    }
+
+    #[test]
+    fn test_parse_edited_batch_indented_separator_stays_in_description() {
+        let originals = sample_batch();
+        let content = "id: 1\nurl: https://a.com\ndescription: |\n  before\n  ---\n  after\n";
+        let edited = parse_edited_batch(content, &originals).unwrap();
+        assert_eq!(edited.len(), 1);
+        assert_eq!(edited[0].description, "before\n---\nafter");
+    }
+
+    #[rstest]
+    #[case("url: https://a.com\n")]
+    #[case("id: abc\nurl: https://a.com\n")]
+    #[case("id: 7\nurl: https://a.com\n")]
+    #[case("id: 1\nurl: https://a.com\n---\nid: 1\nurl: https://a.org\n")]
+    #[case("id: 1\nurl:\n")]
+    fn test_parse_edited_batch_errors(#[case] content: &str) {
+        assert!(parse_edited_batch(content, &sample_batch()).is_err());
+    }
+
+    #[test]
+    fn test_parse_new_bookmark_template_requires_url() {
+        assert!(matches!(
+            parse_new_bookmark(NEW_BOOKMARK_TEMPLATE),
+            Err(EditorError::EmptyUrl)
+        ));
+    }
+
+    #[test]
+    fn test_parse_new_bookmark_filled_template() {
+        let content = NEW_BOOKMARK_TEMPLATE
+            .replace("url: \n", "url: https://example.com\n")
+            .replace("tags: \n", "tags: rust, cli\n")
+            .replace("fetch: yes", "fetch: no");
+        let new_bookmark = parse_new_bookmark(&content).unwrap();
+        assert_eq!(new_bookmark.bookmark.url, "https://example.com");
+        assert_eq!(new_bookmark.bookmark.tags, "rust, cli");
+        assert!(!new_bookmark.fetch);
+    }
+
+    #[test]
+    fn test_parse_new_bookmark_fetch_defaults_to_yes() {
+        let new_bookmark = parse_new_bookmark("url: https://example.com\n").unwrap();
+        assert!(new_bookmark.fetch);
+    }
+
+    #[rstest]
+    #[case("url: example.com\n")]
+    #[case("url: https://exa mple.com\n")]
+    #[case("url: 1http://example.com\n")]
+    #[case("url: https://example.com\ntags: rust lang\n")]
+    #[case("url: https://example.com\nfetch: maybe\n")]
+    fn test_parse_new_bookmark_invalid(#[case] content: &str) {
+        assert!(parse_new_bookmark(content).is_err());
+    }
+
+    #[rstest]
+    #[case("https://example.com")]
+    #[case("mailto:someone@example.com")]
+    #[case("file:///home/user/notes.txt")]
+    #[case("git+ssh://host/repo")]
+    fn test_validate_url_accepts(#[case] url: &str) {
+        assert!(validate_url(url).is_ok());
+    }
+
+    #[test]
+    fn test_annotate_error_replaces_previous_error() {
+        let first = annotate_error("url: bad\n", &EditorError::InvalidUrl("bad".into()));
+        assert!(first.starts_with("# Error: Invalid URL 'bad'"));
+        assert!(first.ends_with("url: bad\n"));
+
+        let second = annotate_error(&first, &EditorError::EmptyUrl);
+        assert_eq!(second, "# Error: URL cannot be empty\nurl: bad\n");
+    }
+
+    #[rstest]
+    #[case("# Title\r\n\r\nSome text  \r\n", "# Title\n\nSome text")]
+    #[case("\n\n- a\n- b\n\n", "- a\n- b")]
+    #[case("plain", "plain")]
+    fn test_normalize_note(#[case] content: &str, #[case] expected: &str) {
+        assert_eq!(normalize_note(content), expected);
+    }
 }
@@ -1,12 +1,33 @@
-use bukurs::error::Result;
-use bukurs::fetch;
+use bukurs::config::Config;
+use bukurs::error::{BukursError, Result};
+use bukurs::fetch::{self, FetchOptions};
+use bukurs::progress::{ProgressEvent, ProgressSink};
+use console::Term;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::Mutex;
+
+/// Fetch settings from the config, with a command-line `--timeout` taking precedence
+pub fn fetch_options(config: &Config, timeout: Option<u64>) -> FetchOptions {
+    let mut options = config.fetch_options();
+    if let Some(timeout) = timeout {
+        options.timeout_secs = timeout;
+    }
+    options
+}
+
+/// Threads for fetching many pages, at most `fetch.concurrency` at a time
+pub fn fetch_pool(options: &FetchOptions) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(options.concurrency)
+        .build()
+        .map_err(|e| BukursError::Other(e.to_string()))
+}
 
 /// Fetch metadata with visual spinner feedback
 ///
 /// Shows an animated spinner while fetching, then displays success/failure status
 /// with categorized error messages.
-pub fn fetch_with_spinner(url: &str, user_agent: &str) -> Result<fetch::FetchResult> {
+pub fn fetch_with_spinner(url: &str, options: &FetchOptions) -> Result<fetch::FetchResult> {
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -18,7 +39,7 @@ pub fn fetch_with_spinner(url: &str, user_agent: &str) -> Result<fetch::FetchRes
     spinner.set_message(format!("Fetching: {}", url_display));
     spinner.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    let result = fetch::fetch_data(url, Some(user_agent));
+    let result = fetch::fetch_data(url, options);
 
     match &result {
         Ok(_) => spinner.finish_with_message(format!("✓ {}", url_display)),
@@ -31,6 +52,81 @@ pub fn fetch_with_spinner(url: &str, user_agent: &str) -> Result<fetch::FetchRes
     result
 }
 
+/// Draws library progress events as an indicatif bar
+///
+/// Each phase gets a fresh bar: a counted bar when the total is known, a
+/// spinner otherwise. The current item (usually a URL) follows the label.
+#[derive(Default)]
+pub struct ProgressBarSink {
+    bar: Mutex<Option<ProgressBar>>,
+    label: Mutex<String>,
+}
+
+impl ProgressBarSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProgressSink for ProgressBarSink {
+    fn event(&self, event: ProgressEvent<'_>) {
+        match event {
+            ProgressEvent::Start { label, total } => {
+                let pb = match total {
+                    Some(total) => {
+                        let pb = ProgressBar::new(total);
+                        pb.set_style(
+                            ProgressStyle::default_bar()
+                                .template("{msg} [{bar:40.cyan/blue}] {pos}/{len}")
+                                .unwrap()
+                                .progress_chars("=>-"),
+                        );
+                        pb
+                    }
+                    None => {
+                        let pb = ProgressBar::new_spinner();
+                        pb.set_style(
+                            ProgressStyle::default_spinner()
+                                .template("{spinner:.green} [{pos}] {msg}")
+                                .unwrap(),
+                        );
+                        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+                        pb
+                    }
+                };
+                pb.set_message(label.to_string());
+                *self.label.lock().unwrap() = label.to_string();
+                if let Some(old) = self.bar.lock().unwrap().replace(pb) {
+                    old.finish_and_clear();
+                }
+            }
+            ProgressEvent::Advance { count, item } => {
+                if let Some(pb) = self.bar.lock().unwrap().as_ref() {
+                    pb.inc(count);
+                    if let Some(item) = item {
+                        let label = self.label.lock().unwrap();
+                        // Spinner, counter and brackets, or the 40-column bar and its counts
+                        let decoration = if pb.length().is_some() { 56 } else { 10 };
+                        let overhead = label.len() + 2 + decoration;
+                        let width = Term::stderr().size().1 as usize;
+                        let max_len = if width > overhead + 10 {
+                            width - overhead
+                        } else {
+                            60
+                        };
+                        pb.set_message(format!("{}: {}", label, truncate_url(item, max_len)));
+                    }
+                }
+            }
+            ProgressEvent::Finish => {
+                if let Some(pb) = self.bar.lock().unwrap().take() {
+                    pb.finish_and_clear();
+                }
+            }
+        }
+    }
+}
+
 /// Truncate URL to specified length with ellipsis
 pub fn truncate_url(url: &str, max_len: usize) -> String {
     if url.len() > max_len {
@@ -43,6 +139,9 @@ pub fn truncate_url(url: &str, max_len: usize) -> String {
 
 /// Categorize error for user-friendly display
 pub fn categorize_error(error: &bukurs::error::BukursError) -> &'static str {
+    if matches!(error, bukurs::error::BukursError::Tls(_)) {
+        return "tls error";
+    }
     let error_str = error.to_string();
 
     if error_str.contains("403") {
@@ -102,6 +201,29 @@ mod tests {
         assert_eq!(categorize_error(&error), expected);
     }
 
+    #[test]
+    fn test_categorize_tls_error() {
+        let error = bukurs::error::BukursError::Tls("certificate not trusted (404)".to_string());
+        assert_eq!(categorize_error(&error), "tls error");
+    }
+
+    #[test]
+    fn test_progress_bar_sink_phases() {
+        let sink = ProgressBarSink::new();
+        sink.start("Importing", Some(2));
+        sink.advance(1, Some("https://example.com"));
+        assert_eq!(
+            sink.bar.lock().unwrap().as_ref().map(|pb| pb.position()),
+            Some(1)
+        );
+        sink.finish();
+        assert!(sink.bar.lock().unwrap().is_none());
+
+        // Events outside a phase are ignored
+        sink.advance(1, None);
+        sink.finish();
+    }
+
     #[test]
     fn test_truncate_url_boundary() {
         // Test exact boundary
@@ -141,6 +263,22 @@ mod tests {
         assert_eq!(categorize_error(&error), "timeout");
     }
 
+    #[rstest]
+    #[case(None, 30)]
+    #[case(Some(5), 5)]
+    fn test_fetch_options_timeout_override(#[case] timeout: Option<u64>, #[case] expected: u64) {
+        let options = fetch_options(&Config::default(), timeout);
+        assert_eq!(options.timeout_secs, expected);
+        assert!(options.user_agent.is_some());
+    }
+
+    fn test_options() -> FetchOptions {
+        FetchOptions {
+            user_agent: Some("Mozilla/5.0 Test".to_string()),
+            ..FetchOptions::default()
+        }
+    }
+
     // Tests for fetch_with_spinner
     // Testing strategy: Since fetch_with_spinner depends on real network calls,
     // we test the parts we can control (error handling, URL truncation) and
@@ -150,7 +288,7 @@ mod tests {
     fn test_fetch_with_spinner_invalid_url() {
         // Test with malformed URL (no network required)
         // This tests error handling path
-        let result = fetch_with_spinner("not-a-valid-url", "Mozilla/5.0 Test");
+        let result = fetch_with_spinner("not-a-valid-url", &test_options());
 
         assert!(result.is_err(), "Should fail with invalid URL");
     }
@@ -158,7 +296,7 @@ mod tests {
     #[test]
     fn test_fetch_with_spinner_empty_url() {
         // Test with empty URL
-        let result = fetch_with_spinner("", "Mozilla/5.0 Test");
+        let result = fetch_with_spinner("", &test_options());
 
         assert!(result.is_err(), "Should fail with empty URL");
     }
@@ -168,7 +306,7 @@ mod tests {
         // Test that long URLs get truncated in display (no network needed)
         // Use .invalid TLD which is reserved and guaranteed not to resolve
         let very_long_url = format!("https://nonexistent.invalid/{}", "a".repeat(100));
-        let result = fetch_with_spinner(&very_long_url, "Mozilla/5.0 Test");
+        let result = fetch_with_spinner(&very_long_url, &test_options());
 
         // The function should complete without panic
         // Will fail with DNS error since .invalid never resolves
@@ -180,7 +318,7 @@ mod tests {
         // Test with non-existent domain (tests DNS error handling)
         let result = fetch_with_spinner(
             "https://this-domain-definitely-does-not-exist-12345.com",
-            "Mozilla/5.0 Test",
+            &test_options(),
         );
 
         assert!(result.is_err(), "Should fail with DNS error");
@@ -197,11 +335,10 @@ mod tests {
     #[ignore]
     fn test_fetch_with_spinner_network_success() {
         // Test with example.com (very stable)
-        let result = fetch_with_spinner("http://example.com", "Mozilla/5.0 Test");
+        let result = fetch_with_spinner("http://example.com", &test_options());
 
         // Note: Success depends on network and example.com being available
-        if result.is_ok() {
-            let fetch_result = result.unwrap();
+        if let Ok(fetch_result) = result {
             assert!(!fetch_result.url.is_empty());
         }
         // We don't fail if network is unavailable
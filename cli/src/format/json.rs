@@ -1,10 +1,33 @@
+use crate::format::structured::StructuredBookmark;
 use crate::format::traits::BookmarkFormat;
 use bukurs::models::bookmark::Bookmark;
+use bukurs::search::ScoredBookmark;
+use serde::Serialize;
 
 pub struct JsonBookmark<'a>(pub &'a Bookmark);
 
 impl<'a> BookmarkFormat for JsonBookmark<'a> {
     fn to_string(&self) -> String {
-        serde_json::to_string_pretty(self.0).unwrap()
+        serde_json::to_string_pretty(&StructuredBookmark::new(self.0)).unwrap()
+    }
+}
+
+/// A ranked search result, with its `score` next to the bookmark fields
+pub struct JsonScoredBookmark<'a>(pub &'a ScoredBookmark);
+
+#[derive(Serialize)]
+struct Scored<'a> {
+    #[serde(flatten)]
+    bookmark: StructuredBookmark<'a>,
+    score: Option<f64>,
+}
+
+impl<'a> BookmarkFormat for JsonScoredBookmark<'a> {
+    fn to_string(&self) -> String {
+        let scored = Scored {
+            bookmark: StructuredBookmark::new(&self.0.bookmark),
+            score: self.0.score,
+        };
+        serde_json::to_string_pretty(&scored).unwrap()
     }
 }
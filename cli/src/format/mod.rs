@@ -1,25 +1,86 @@
 use crate::{
     format::{
-        json::JsonBookmark, plain::PlainBookmark, toml::TomlBookmark, toon::ToonBookmark,
-        traits::BookmarkFormat, yaml::YamlBookmark,
+        json::{JsonBookmark, JsonScoredBookmark},
+        plain::PlainBookmark,
+        rows::Record,
+        template::{Template, TemplateBookmark},
+        toml::TomlBookmark,
+        toon::ToonBookmark,
+        traits::BookmarkFormat,
+        yaml::YamlBookmark,
+    },
+    output::{
+        colorize::{Colorize, ColorizeBookmark},
+        theme::Theme,
     },
-    output::colorize::{Colorize, ColorizeBookmark},
 };
+use bukurs::config::Config;
+use bukurs::db::query::QueryResult;
+use bukurs::db::BukuDb;
+use bukurs::error::{BukursError, Result};
+use bukurs::models::bookmark::Bookmark;
+use bukurs::search::ScoredBookmark;
+use std::collections::HashMap;
 
 pub mod json;
 pub mod plain;
+pub mod rows;
+pub mod structured;
+pub mod template;
 pub mod toml;
 pub mod toon;
 pub mod traits;
 pub mod yaml;
 
-#[derive(Clone, Copy)]
+/// Prefix of an inline `-f` template
+const TEMPLATE_PREFIX: &str = "template:";
+
+/// What listings show after a bookmark's title
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Badges {
+    /// Stars; 0 when unrated
+    pub rating: u8,
+    /// Words in the page's main text, shown as a reading time; 0 when not measured
+    pub word_count: usize,
+}
+
+impl Badges {
+    pub fn is_empty(&self) -> bool {
+        self.rating == 0 && self.word_count == 0
+    }
+}
+
+/// Badges of every bookmark that has any
+#[derive(Debug, Default)]
+pub struct ListingBadges {
+    ratings: HashMap<usize, u8>,
+    word_counts: HashMap<usize, usize>,
+}
+
+impl ListingBadges {
+    pub fn load(db: &BukuDb) -> Result<Self> {
+        Ok(Self {
+            ratings: db.get_ratings()?,
+            word_counts: db.get_word_counts()?,
+        })
+    }
+
+    pub fn get(&self, id: usize) -> Badges {
+        Badges {
+            rating: self.ratings.get(&id).copied().unwrap_or(0),
+            word_count: self.word_counts.get(&id).copied().unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum OutputFormat {
     Json,
     Yaml,
     Toml,
     Toon,
     Colored,
+    Template(Template),
 }
 
 impl OutputFormat {
@@ -33,11 +94,25 @@ impl OutputFormat {
         }
     }
 
-    pub fn print_bookmarks(
-        self,
-        records: &Vec<bukurs::models::bookmark::Bookmark>,
-        no_color: bool,
-    ) {
+    /// Resolve `-f`: a built-in format, `template:<template>` or a template
+    /// named under `formats` in the config
+    pub fn resolve(format: Option<&str>, config: &Config) -> Result<Self> {
+        let Some(format) = format else {
+            return Ok(OutputFormat::Colored);
+        };
+        if let Some(template) = format.strip_prefix(TEMPLATE_PREFIX) {
+            return Ok(OutputFormat::Template(Template::parse(template)?));
+        }
+        match (Self::from_string(format), config.formats.get(format)) {
+            (OutputFormat::Colored, Some(template)) => {
+                Ok(OutputFormat::Template(Template::parse(template)?))
+            }
+            (builtin, _) => Ok(builtin),
+        }
+    }
+
+    /// Print bookmarks; listings show each one's badges after its title
+    pub fn print_listing(&self, records: &[Bookmark], badges: &ListingBadges, no_color: bool) {
         match self {
             OutputFormat::Json => {
                 for b in records {
@@ -59,15 +134,124 @@ impl OutputFormat {
                     println!("{}", ToonBookmark(b).to_string());
                 }
             }
+            OutputFormat::Template(template) => {
+                for b in records {
+                    println!("{}", TemplateBookmark(b, template).to_string());
+                }
+            }
             OutputFormat::Colored => {
                 for b in records {
-                    if no_color {
-                        println!("{}", PlainBookmark(b).to_string());
-                    } else {
-                        println!("{}", ColorizeBookmark(b).to_colored());
+                    let badges = badges.get(b.id);
+                    match (badges.is_empty(), no_color) {
+                        (true, true) => println!("{}", PlainBookmark(b).to_string()),
+                        (true, false) => println!("{}", ColorizeBookmark(b).to_colored()),
+                        (false, true) => println!("{}", PlainBookmark(b).with_badges(badges)),
+                        (false, false) => {
+                            println!("{}", ColorizeBookmark(b).with_badges(badges))
+                        }
                     }
                 }
             }
         }
     }
+
+    /// Print rows of named columns (query results, domain counts): one
+    /// object per row, or a table of the columns
+    pub fn print_rows(&self, result: &QueryResult, no_color: bool) -> Result<()> {
+        let records = || {
+            result
+                .rows
+                .iter()
+                .map(|values| Record::new(&result.columns, values))
+        };
+        match self {
+            OutputFormat::Json => {
+                for record in records() {
+                    println!("{}", serde_json::to_string_pretty(&record)?);
+                }
+            }
+            OutputFormat::Yaml => {
+                for record in records() {
+                    println!("{}", serde_yaml::to_string(&record)?);
+                }
+            }
+            OutputFormat::Toml => {
+                for record in records() {
+                    let toml = ::toml::to_string_pretty(&record.without_nulls())
+                        .map_err(|e| BukursError::Other(e.to_string()))?;
+                    println!("{}", toml);
+                }
+            }
+            OutputFormat::Template(_) => {
+                return Err(BukursError::InvalidInput(
+                    "Templates only format bookmarks; use json, yaml or toml for these rows"
+                        .to_string(),
+                ))
+            }
+            OutputFormat::Toon | OutputFormat::Colored => {
+                let theme = (!no_color).then(Theme::current);
+                print!("{}", rows::table(result, theme.as_ref()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Print ranked search results; JSON includes each result's score
+    pub fn print_scored(&self, records: &[ScoredBookmark], badges: &ListingBadges, no_color: bool) {
+        match self {
+            OutputFormat::Json => {
+                for r in records {
+                    println!("{}", JsonScoredBookmark(r).to_string());
+                }
+            }
+            _ => {
+                let bookmarks: Vec<Bookmark> = records.iter().map(|r| r.bookmark.clone()).collect();
+                self.print_listing(&bookmarks, badges, no_color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn config() -> Config {
+        Config {
+            formats: [("tsv".to_string(), "{id}\\t{url}".to_string())].into(),
+            ..Config::default()
+        }
+    }
+
+    fn render(format: &OutputFormat) -> Option<String> {
+        let bookmark = bukurs::models::bookmark::Bookmark::new(
+            3,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",".to_string(),
+            String::new(),
+        );
+        match format {
+            OutputFormat::Template(template) => {
+                Some(TemplateBookmark(&bookmark, template).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    #[rstest]
+    #[case(None, None)]
+    #[case(Some("json"), None)]
+    #[case(Some("template:{id} {title}"), Some("3 Example"))]
+    #[case(Some("tsv"), Some("3\thttps://example.com"))]
+    fn test_resolve(#[case] format: Option<&str>, #[case] expected: Option<&str>) {
+        let resolved = OutputFormat::resolve(format, &config()).unwrap();
+        assert_eq!(render(&resolved).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_resolve_invalid_template() {
+        assert!(OutputFormat::resolve(Some("template:{nope}"), &config()).is_err());
+    }
 }
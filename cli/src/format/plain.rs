@@ -1,21 +1,37 @@
-use crate::format::traits::BookmarkFormat;
+use crate::format::{traits::BookmarkFormat, Badges};
+use bukurs::fetch::readability::reading_time;
 use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::rating::stars;
 use bukurs::tags::parse_tags;
 
 pub struct PlainBookmark<'a>(pub &'a Bookmark);
 
 impl<'a> BookmarkFormat for PlainBookmark<'a> {
     fn to_string(&self) -> String {
+        self.with_badges(Badges::default())
+    }
+}
+
+impl PlainBookmark<'_> {
+    /// The listing, with the rating as stars and the reading time after the title
+    pub fn with_badges(&self, badges: Badges) -> String {
         let mut s = String::new();
         let id = self.0.id.to_string();
-        s.push_str(&format!("{}. {}\n", id, self.0.title,));
+        s.push_str(&format!("{}. {}", id, self.0.title));
+        if badges.rating > 0 {
+            s.push_str(&format!(" {}", stars(badges.rating)));
+        }
+        if badges.word_count > 0 {
+            s.push_str(&format!(" {}", reading_time(badges.word_count)));
+        }
+        s.push('\n');
         let padding = id.len() + 3;
         // padding for alignment
         s.push_str(&format!("{:>padding$} {}\n", ">", self.0.url));
 
-        // Only show description if non-empty
-        if !self.0.description.trim().is_empty() {
-            s.push_str(&format!("{:>padding$} {}\n", "+", self.0.description));
+        // Only show description if non-empty; multi-line notes show their first line
+        if let Some(summary) = self.0.description_summary() {
+            s.push_str(&format!("{:>padding$} {}\n", "+", summary));
         }
 
         // Parse tags and only show if non-empty
@@ -0,0 +1,170 @@
+//! Query results (`bukurs query`): rows of named columns rather than bookmarks
+
+use crate::output::theme::Theme;
+use bukurs::db::query::QueryResult;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+/// Widest a table cell gets before it is cut short
+const MAX_CELL_WIDTH: usize = 60;
+
+/// One row as a map of column names to values, in column order
+pub struct Record<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+    /// Leave out null values, which TOML can't represent
+    skip_null: bool,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(columns: &'a [String], values: &'a [Value]) -> Self {
+        Self {
+            columns,
+            values,
+            skip_null: false,
+        }
+    }
+
+    pub fn without_nulls(self) -> Self {
+        Self {
+            skip_null: true,
+            ..self
+        }
+    }
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            if !(self.skip_null && value.is_null()) {
+                map.serialize_entry(column, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// The rows as an aligned table under a header, styled with `theme` if given
+///
+/// Numbers are right-aligned; nulls are left blank and long or multi-line
+/// text is cut to one line.
+pub fn table(result: &QueryResult, theme: Option<&Theme>) -> String {
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(cell).collect())
+        .collect();
+    let widths: Vec<usize> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let header = result
+        .columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, &width)| format!("{:<width$}", column))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let rule = widths
+        .iter()
+        .map(|&width| "─".repeat(width))
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    let mut out = String::new();
+    match theme {
+        Some(theme) => out.push_str(&theme.title.style(header.trim_end()).to_string()),
+        None => out.push_str(header.trim_end()),
+    }
+    out.push('\n');
+    out.push_str(&rule);
+    out.push('\n');
+    for (row, values) in cells.iter().zip(&result.rows) {
+        let line = row
+            .iter()
+            .zip(values)
+            .zip(&widths)
+            .map(|((text, value), &width)| match value {
+                Value::Number(_) => format!("{:>width$}", text),
+                _ => format!("{:<width$}", text),
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// A value as one table cell
+fn cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.split_whitespace().collect::<Vec<_>>().join(" "),
+        other => other.to_string(),
+    };
+    if text.chars().count() > MAX_CELL_WIDTH {
+        let cut: String = text.chars().take(MAX_CELL_WIDTH - 1).collect();
+        format!("{}…", cut)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result() -> QueryResult {
+        QueryResult {
+            columns: vec!["id".to_string(), "title".to_string(), "n".to_string()],
+            rows: vec![
+                vec![json!(1), json!("Rust\nLang"), Value::Null],
+                vec![json!(12), json!("x".repeat(70)), json!(3)],
+            ],
+            changed: None,
+        }
+    }
+
+    #[test]
+    fn test_table_plain() {
+        let long = format!("{}…", "x".repeat(59));
+        assert_eq!(
+            table(&result(), None),
+            format!(
+                "id  title{}  n\n\
+                 ──  {}  ─\n \
+                 1  Rust Lang\n\
+                 12  {}  3\n",
+                " ".repeat(55),
+                "─".repeat(60),
+                long
+            )
+        );
+    }
+
+    #[test]
+    fn test_record_serializes_in_column_order() {
+        let result = result();
+        let record = Record::new(&result.columns, &result.rows[0]);
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"id":1,"title":"Rust\nLang","n":null}"#
+        );
+        let values = [json!(2), Value::Null, json!(1.5)];
+        let record = Record::new(&result.columns, &values).without_nulls();
+        assert_eq!(toml::to_string(&record).unwrap(), "id = 2\nn = 1.5\n");
+    }
+}
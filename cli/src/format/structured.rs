@@ -0,0 +1,99 @@
+//! The bookmark shape JSON, YAML and TOML output share
+//!
+//! Tags come out as a list; `--raw-tags` keeps the stored `,a,b,` string for
+//! scripts written against older output.
+
+use bukurs::models::bookmark::Bookmark;
+use bukurs::tags::parse_tags;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Print tags as the stored string (`--raw-tags`)
+static RAW_TAGS: OnceLock<bool> = OnceLock::new();
+
+/// Print tags as the stored string for the rest of the run
+pub fn set_raw_tags() {
+    let _ = RAW_TAGS.set(true);
+}
+
+fn raw_tags() -> bool {
+    RAW_TAGS.get().copied().unwrap_or(false)
+}
+
+/// A bookmark's fields for structured output
+#[derive(Debug, Serialize)]
+pub struct StructuredBookmark<'a> {
+    pub id: usize,
+    pub url: &'a str,
+    pub title: &'a str,
+    pub tags: Tags<'a>,
+    pub description: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Tags<'a> {
+    List(Vec<String>),
+    Raw(&'a str),
+}
+
+impl<'a> StructuredBookmark<'a> {
+    /// Tags as a list unless `--raw-tags` was given
+    pub fn new(bookmark: &'a Bookmark) -> Self {
+        Self::with_raw_tags(bookmark, raw_tags())
+    }
+
+    pub fn with_raw_tags(bookmark: &'a Bookmark, raw: bool) -> Self {
+        let tags = if raw {
+            Tags::Raw(&bookmark.tags)
+        } else {
+            Tags::List(parse_tags(&bookmark.tags))
+        };
+        Self {
+            id: bookmark.id,
+            url: &bookmark.url,
+            title: &bookmark.title,
+            tags,
+            description: &bookmark.description,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn bookmark(tags: &str) -> Bookmark {
+        Bookmark::new(
+            2,
+            "https://rust-lang.org".to_string(),
+            "Rust".to_string(),
+            tags.to_string(),
+            String::new(),
+        )
+    }
+
+    #[rstest]
+    #[case(",rust,cli,", false, r#""tags":["rust","cli"]"#)]
+    #[case(",", false, r#""tags":[]"#)]
+    #[case(",rust,cli,", true, r#""tags":",rust,cli,""#)]
+    fn test_json_tags(#[case] tags: &str, #[case] raw: bool, #[case] expected: &str) {
+        let bookmark = bookmark(tags);
+        let json =
+            serde_json::to_string(&StructuredBookmark::with_raw_tags(&bookmark, raw)).unwrap();
+        assert!(json.contains(expected), "{}", json);
+    }
+
+    #[test]
+    fn test_yaml_and_toml_tags() {
+        let bookmark = bookmark(",rust,cli,");
+        let structured = StructuredBookmark::with_raw_tags(&bookmark, false);
+        assert!(serde_yaml::to_string(&structured)
+            .unwrap()
+            .contains("tags:\n- rust\n- cli\n"));
+        assert!(toml::to_string(&structured)
+            .unwrap()
+            .contains("tags = [\"rust\", \"cli\"]"));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::format::traits::BookmarkFormat;
+use bukurs::error::{BukursError, Result};
+use bukurs::models::bookmark::Bookmark;
+use bukurs::tags::parse_tags;
+
+/// Bookmark fields usable as `{placeholders}` in a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Url,
+    Title,
+    Tags,
+    Desc,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "id" | "index" => Some(Self::Id),
+            "url" => Some(Self::Url),
+            "title" => Some(Self::Title),
+            "tags" => Some(Self::Tags),
+            "desc" | "description" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// A user-defined output line such as `{id}\t{url}\t{tags}`
+///
+/// `\t`, `\n` and `\\` are unescaped so templates can be typed in a shell;
+/// `{{` and `}}` are literal braces. Tags are joined with commas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(BukursError::InvalidInput(format!(
+                            "Unclosed '{{{}' in template",
+                            name
+                        )));
+                    }
+                    let field = Field::from_name(name.trim()).ok_or_else(|| {
+                        BukursError::InvalidInput(format!(
+                            "Unknown template field '{{{}}}' (expected id, url, title, tags or desc)",
+                            name
+                        ))
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => {
+                    return Err(BukursError::InvalidInput(
+                        "Unmatched '}' in template (use '}}' for a literal brace)".to_string(),
+                    ))
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Whether the template has any `{placeholder}`
+    pub fn has_fields(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field(_)))
+    }
+
+    pub fn render(&self, bookmark: &Bookmark) -> String {
+        let mut s = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => s.push_str(text),
+                Segment::Field(Field::Id) => s.push_str(&bookmark.id.to_string()),
+                Segment::Field(Field::Url) => s.push_str(&bookmark.url),
+                Segment::Field(Field::Title) => s.push_str(&bookmark.title),
+                Segment::Field(Field::Tags) => s.push_str(&parse_tags(&bookmark.tags).join(",")),
+                Segment::Field(Field::Desc) => s.push_str(&bookmark.description),
+            }
+        }
+        s
+    }
+}
+
+pub struct TemplateBookmark<'a>(pub &'a Bookmark, pub &'a Template);
+
+impl<'a> BookmarkFormat for TemplateBookmark<'a> {
+    fn to_string(&self) -> String {
+        self.1.render(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn bookmark() -> Bookmark {
+        Bookmark::new(
+            7,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",rust,web,".to_string(),
+            "Notes".to_string(),
+        )
+    }
+
+    #[rstest]
+    #[case(r"{id}\t{url}\t{tags}", "7\thttps://example.com\trust,web")]
+    #[case("[{title}]({url})", "[Example](https://example.com)")]
+    #[case("{ id } {description}", "7 Notes")]
+    #[case("{{{id}}}", "{7}")]
+    #[case(r"a\\b\n{index}", "a\\b\n7")]
+    #[case("plain text", "plain text")]
+    fn test_template_render(#[case] template: &str, #[case] expected: &str) {
+        let template = Template::parse(template).unwrap();
+        assert_eq!(
+            TemplateBookmark(&bookmark(), &template).to_string(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("{nope}")]
+    #[case("{url")]
+    #[case("url}")]
+    fn test_template_parse_errors(#[case] template: &str) {
+        assert!(Template::parse(template).is_err());
+    }
+
+    #[test]
+    fn test_template_empty_tags() {
+        let mut b = bookmark();
+        b.tags = ",".to_string();
+        let template = Template::parse("{id}:{tags}").unwrap();
+        assert_eq!(TemplateBookmark(&b, &template).to_string(), "7:");
+    }
+}
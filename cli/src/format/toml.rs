@@ -1,3 +1,4 @@
+use crate::format::structured::StructuredBookmark;
 use crate::format::traits::BookmarkFormat;
 use bukurs::models::bookmark::Bookmark;
 
@@ -5,6 +6,6 @@ pub struct TomlBookmark<'a>(pub &'a Bookmark);
 
 impl<'a> BookmarkFormat for TomlBookmark<'a> {
     fn to_string(&self) -> String {
-        toml::to_string_pretty(self.0).unwrap()
+        toml::to_string_pretty(&StructuredBookmark::new(self.0)).unwrap()
     }
 }
@@ -1,3 +1,4 @@
+use crate::format::structured::StructuredBookmark;
 use crate::format::traits::BookmarkFormat;
 use bukurs::models::bookmark::Bookmark;
 
@@ -5,6 +6,6 @@ pub struct YamlBookmark<'a>(pub &'a Bookmark);
 
 impl BookmarkFormat for YamlBookmark<'_> {
     fn to_string(&self) -> String {
-        serde_yaml::to_string(self.0).unwrap()
+        serde_yaml::to_string(&StructuredBookmark::new(self.0)).unwrap()
     }
 }
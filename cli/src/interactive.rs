@@ -1,22 +1,40 @@
 use bukurs::db::BukuDb;
 use bukurs::error::Result;
-use bukurs::config::Config;
+use bukurs::import_export::DuplicateStrategy;
+use bukurs::operations::{Selector, SelectorTerm};
 use rustyline::error::ReadlineError;
-use rustyline::DefaultEditor;
+use rustyline::history::DefaultHistory;
+use rustyline::{CompletionType, Editor};
+use std::path::PathBuf;
 use crate::commands::{AppContext, BukuCommand};
 use crate::commands::add::AddCommand;
 use crate::commands::update::UpdateCommand;
 use crate::commands::delete::DeleteCommand;
+use crate::commands::edit::EditCommand;
+use crate::commands::later::LaterCommand;
+use crate::commands::pin::{MoveCommand, PinCommand};
 use crate::commands::search::SearchCommand;
 use crate::commands::tag::TagCommand;
 use crate::commands::misc::{NoCommand, OpenCommand, UndoCommand};
 use crate::commands::print::PrintCommand;
+use crate::commands::collection::{CollectionCommand, CollectionOp};
 use crate::commands::import_export::{ImportCommand, ExportCommand, ImportBrowsersCommand};
 use crate::commands::lock_unlock::{LockCommand, UnlockCommand};
+use crate::shell::{self, ShellHelper};
 
 pub fn run_with_context(ctx: &AppContext) -> Result<()> {
-    let mut rl =
-        DefaultEditor::new().map_err(|e| bukurs::error::BukursError::Other(e.to_string()))?;
+    let config = rustyline::Config::builder()
+        .max_history_size(shell::MAX_HISTORY)
+        .map_err(|e| bukurs::error::BukursError::Other(e.to_string()))?
+        .completion_type(CompletionType::List)
+        .build();
+    let mut rl: Editor<ShellHelper, DefaultHistory> = Editor::with_config(config)
+        .map_err(|e| bukurs::error::BukursError::Other(e.to_string()))?;
+    rl.set_helper(Some(ShellHelper::new(ctx.db, &ctx.config.shell)));
+
+    // A missing history file just means this is the first session
+    let history_path = shell::history_path();
+    let _ = rl.load_history(&history_path);
 
     println!("bukurs interactive mode - type '?' for help");
 
@@ -55,19 +73,14 @@ pub fn run_with_context(ctx: &AppContext) -> Result<()> {
             }
         }
     }
-    Ok(())
-}
 
-// Legacy entry point - creates a default context
-pub fn run(db: &BukuDb) -> Result<()> {
-    let config = Config::default();
-    let db_path = std::path::PathBuf::from("bookmarks.db");
-    let ctx = AppContext {
-        db,
-        config: &config,
-        db_path: &db_path,
-    };
-    run_with_context(&ctx)
+    if let Some(dir) = history_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(e) = rl.save_history(&history_path) {
+        eprintln!("Warning: Failed to save history to {}: {}", history_path.display(), e);
+    }
+    Ok(())
 }
 
 fn print_help() {
@@ -78,6 +91,8 @@ INTERACTIVE MODE COMMANDS (All CLI features supported!):
 SEARCH & BROWSE:
     s [keywords...]        Search bookmarks with ANY keyword (fuzzy picker)
     S [keywords...]        Search bookmarks with ALL keywords (fuzzy picker)
+    s [keywords...] --refine
+                           Search only the results of the previous search
     t [tags...]            Search by tags (or fuzzy pick if no tags given)
     [number]               Open bookmark by ID in browser
     ls                     List all bookmarks (fuzzy picker)
@@ -87,31 +102,47 @@ ADD & MODIFY:
                            Add new bookmark
                            Example: a https://rust-lang.org rust,programming \"Rust\" \"Official\"
     
-    u <id> [options]       Update bookmark
+    u <id|range|*> [options]
+                           Update bookmarks (no options: refresh from the web)
                            Options: --url <url> -t tag1,tag2 --title \"Title\" -c \"Comment\"
+                                    --immutable 0|1 -p (pick from the targets)
                            Example: u 5 -t +urgent
-                           Example: u 5 --url https://new-url.com
+                           Example: u 1-10 -t +archived
+                           Example: u * --immutable 1
     
     e <id>                 Edit bookmark in $EDITOR
 
 DELETE:
-    d <id|range> [-f]      Delete bookmark(s)
+    d <id|range> [-f] [-p] Delete bookmark(s), -p to pick (Tab marks)
                            Examples: d 5, d 1-10, d 5 -f (force, no confirm)
 
 PRINT:
     p <id|range>           Print bookmarks
                            Examples: p 5, p 1-10, p *
+    c [name]               List collections, or print the bookmarks in one
 
 IMPORT/EXPORT:
-    import <file>          Import bookmarks from HTML/JSON file
-    export <file>          Export bookmarks to HTML file
-    import-browsers [-l] [-a]
+    import <file> [--dry-run] [--parallel] [--on-duplicate skip|merge|overwrite]
+                           Import bookmarks from HTML/Markdown/Org file
+    export <file> [--collection NAME]
+                           Export bookmarks to HTML/Markdown/Org file
+    import-browsers [-l] [-a] [--dry-run] [--on-duplicate skip|merge|overwrite]
                            Import from browsers (-l: list, -a: all)
 
+ORDER:
+    pin <id|range>         Keep bookmarks at the top of listings (unpin to undo)
+    move <id> --before|--after <other-id>
+                           Manually order bookmarks
+
+READ LATER:
+    later                  List unread bookmarks, oldest first
+    later pop              Open the oldest unread bookmark and mark it read
+
 OPEN:
-    open <id>              Open bookmark in browser
+    open <id|alias>        Open bookmark in browser
     o <id>                 Alias for 'open'
     <id>                   Direct shorthand (just type the number)
+    <alias>                Open the bookmark with that alias (see `bukurs alias`)
 
 DATABASE:
     lock [iter]            Encrypt database (default: 8 iterations)
@@ -126,6 +157,7 @@ HELP & EXIT:
 EXAMPLES:
     s rust programming     # Search ANY keyword and fuzzy pick
     S rust error           # Search ALL keywords and fuzzy pick
+    s async --refine       # Narrow the previous search to 'async'
     t                      # Fuzzy pick from all tags
     t rust                 # Search by tag and fuzzy pick
     ls                     # List all and fuzzy pick
@@ -152,27 +184,52 @@ EXAMPLES:
     unlock                 # Decrypt database
     undo 3                 # Undo last 3 operations
 
-TIP: All commands reuse the exact same code as CLI mode for consistency!
+TIP: Quote arguments containing spaces (a <url> rust \"My Title\"), press Tab to
+     complete commands, tags, bookmark IDs and earlier searches (after s or S),
+     and use Up/Down for history.
+     History is kept across sessions. Aliases and macros from the 'shell'
+     section of the config file work like any other command.
 "
     );
 }
 
 fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
-    // Parse the command line using shell-like parsing
-    let parts: Vec<&str> = line.split_whitespace().collect();
+    // Parse the command line using shell-like parsing, so quoted titles stay whole,
+    // expanding aliases and macros; a failing command stops the rest of a macro
+    for words in shell::expand(line, &ctx.config.shell)? {
+        run_command(ctx, &words)?;
+    }
+    Ok(())
+}
+
+fn run_command(ctx: &AppContext, words: &[String]) -> Result<()> {
+    let parts: Vec<&str> = words.iter().map(String::as_str).collect();
     if parts.is_empty() {
         return Ok(());
     }
 
     let cmd = parts[0];
     let args = &parts[1..];
+
+    // Changes wait for other bukurs processes, like one-shot commands do
+    let _lock = if mutates_database(cmd, args) {
+        ctx.config.database.lock_database(ctx.db_path)?
+    } else {
+        None
+    };
     
     match cmd {
         // Search commands - reuse existing command structures
         "s" => {
-            let keywords: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            // `--refine` searches the previous results only
+            let within_last = args.contains(&"--refine");
+            let keywords: Vec<String> = args
+                .iter()
+                .filter(|s| **s != "--refine")
+                .map(|s| s.to_string())
+                .collect();
             if keywords.is_empty() {
-                println!("Usage: s keyword [...]");
+                println!("Usage: s keyword [...] [--refine]");
                 return Ok(());
             }
             let command = SearchCommand {
@@ -180,17 +237,34 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 all: false,  // ANY
                 deep: false,
                 regex: false,
+                notes: false,
                 limit: None,
                 format: None,
-                nc: false,
+                nc: !ctx.config.color,
                 open: false,
+                exec: None,
+                exec_batch: None,
+                jobs: None,
+                within_last,
+                rank: None,
+                fuzzy: false,
+                save: None,
+                min_rating: None,
+                lang: None,
+                time: Default::default(),
             };
             command.execute(ctx)
         }
         "S" => {
-            let keywords: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            // `--refine` searches the previous results only
+            let within_last = args.contains(&"--refine");
+            let keywords: Vec<String> = args
+                .iter()
+                .filter(|s| **s != "--refine")
+                .map(|s| s.to_string())
+                .collect();
             if keywords.is_empty() {
-                println!("Usage: S keyword [...]");
+                println!("Usage: S keyword [...] [--refine]");
                 return Ok(());
             }
             let command = SearchCommand {
@@ -198,10 +272,21 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 all: true,  // ALL
                 deep: false,
                 regex: false,
+                notes: false,
                 limit: None,
                 format: None,
-                nc: false,
+                nc: !ctx.config.color,
                 open: false,
+                exec: None,
+                exec_batch: None,
+                jobs: None,
+                within_last,
+                rank: None,
+                fuzzy: false,
+                save: None,
+                min_rating: None,
+                lang: None,
+                time: Default::default(),
             };
             command.execute(ctx)
         }
@@ -211,7 +296,7 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 tags,
                 limit: None,
                 format: None,
-                nc: false,
+                nc: !ctx.config.color,
                 open: false,
             };
             command.execute(ctx)
@@ -221,7 +306,7 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 keywords: vec![],
                 open: false,
                 format: None,
-                nc: false,
+                nc: !ctx.config.color,
             };
             command.execute(ctx)
         }
@@ -257,6 +342,10 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 title,
                 comment,
                 offline: false,
+                no_suggest: false,
+                timeout: None,
+                later: false,
+                expires: None,
             };
             command.execute(ctx)
         }
@@ -264,46 +353,61 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
         // Update - simplified parsing
         "u" | "update" => {
             if args.is_empty() {
-                println!("Usage: u <id> [--url <url>] [-t tag1,tag2] [--title <title>] [-c <comment>]");
+                println!("Usage: u <id|range|*> [--url <url>] [-t tag1,tag2] [--title <title>]");
+                println!("         [-c <comment>] [--immutable 0|1] [-p]");
                 println!("Example: u 5 -t +urgent");
+                println!("Example: u 1-10 -t +archived");
+                println!("Example: u * --immutable 1");
                 println!("Example: u 5 --url https://new-url.com");
                 println!("Note: For complex updates, use 'e <id>' to edit in $EDITOR");
                 return Ok(());
             }
             
-            let id_str = args[0].to_string();
-            let ids = vec![id_str];
-            
-            // Simple argument parsing
+            // Targets are anything the selector parser takes (IDs, ranges, *,
+            // -1, keywords), as with `update` on the command line
+            let mut ids = Vec::new();
             let mut url = None;
-            let mut tag = None;
+            let mut tag: Option<Vec<String>> = None;
             let mut title = None;
             let mut comment = None;
+            let mut immutable = None;
+            let mut pick = false;
             
-            let mut i = 1;
+            let mut i = 0;
             while i < args.len() {
+                let value = args.get(i + 1).map(|v| v.to_string());
                 match args[i] {
-                    "--url" if i + 1 < args.len() => {
-                        url = Some(args[i + 1].to_string());
-                        i += 2;
+                    "--url" if value.is_some() => url = value,
+                    "-t" | "--tag" if value.is_some() => {
+                        tag.get_or_insert_with(Vec::new).extend(value)
                     }
-                    "-t" if i + 1 < args.len() => {
-                        tag = Some(vec![args[i + 1].to_string()]);
-                        i += 2;
+                    "--title" if value.is_some() => title = value,
+                    "-c" | "--comment" if value.is_some() => comment = value,
+                    "--immutable" if value.is_some() => {
+                        let flag = args[i + 1];
+                        immutable = Some(flag.parse::<u8>().map_err(|_| {
+                            format!("Invalid --immutable value '{}' (expected 0 or 1)", flag)
+                        })?);
                     }
-                    "--title" if i + 1 < args.len() => {
-                        title = Some(args[i + 1].to_string());
-                        i += 2;
+                    "-p" | "--pick" => {
+                        pick = true;
+                        i += 1;
+                        continue;
                     }
-                    "-c" if i + 1 < args.len() => {
-                        comment = Some(args[i + 1].to_string());
-                        i += 2;
+                    arg if !arg.starts_with('-')
+                        || matches!(SelectorTerm::parse(arg), Ok(Some(_))) =>
+                    {
+                        ids.push(arg.to_string());
+                        i += 1;
+                        continue;
                     }
-                    _ => {
-                        println!("Unknown option: {}", args[i]);
+                    arg => {
+                        println!("Unknown option: {}", arg);
                         i += 1;
+                        continue;
                     }
                 }
+                i += 2;
             }
             
             let command = UpdateCommand {
@@ -312,7 +416,9 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 tag,
                 title,
                 comment,
-                immutable: None,
+                immutable,
+                pick,
+                timeout: None,
             };
             command.execute(ctx)
         }
@@ -321,18 +427,21 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
         "d" | "delete" | "del" => {
             let ids: Vec<String> = args.iter().map(|s| s.to_string()).collect();
             if ids.is_empty() {
-                println!("Usage: d <id|range> [-f]");
+                println!("Usage: d <id|range|keyword> [-f] [-p]");
                 println!("Example: d 5");
                 println!("Example: d 1-10 -f");
+                println!("Example: d github -p   (pick from matches, Tab to mark)");
                 return Ok(());
             }
             
             let force = ids.contains(&"-f".to_string());
-            let ids: Vec<String> = ids.into_iter().filter(|s| s != "-f").collect();
+            let pick = ids.contains(&"-p".to_string());
+            let ids: Vec<String> = ids.into_iter().filter(|s| s != "-f" && s != "-p").collect();
             
             let command = DeleteCommand {
                 ids,
                 force,
+                pick,
             };
             command.execute(ctx)
         }
@@ -348,6 +457,7 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 println!("Example: p 5");
                 println!("Example: p 1-10");
                 println!("Example: p *");
+                println!("Example: p -1      (most recently added)");
                 return Ok(());
             }
             
@@ -355,10 +465,35 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 ids,
                 limit: None,
                 format: None,
-                nc: false,
+                nc: !ctx.config.color,
+                stats: false,
+                collection: None,
+                min_rating: None,
+                by_rating: false,
+                time: Default::default(),
             };
             command.execute(ctx)
         }
+
+        // Collections, listed like folders
+        "c" | "collection" => match args.first() {
+            None => CollectionCommand {
+                op: CollectionOp::List,
+            }
+            .execute(ctx),
+            Some(name) => PrintCommand {
+                ids: Vec::new(),
+                limit: None,
+                format: None,
+                nc: !ctx.config.color,
+                stats: false,
+                collection: Some(name.to_string()),
+                min_rating: None,
+                by_rating: false,
+                time: Default::default(),
+            }
+            .execute(ctx),
+        },
         
         // Import/Export
         "import" => {
@@ -370,6 +505,11 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
             
             let command = ImportCommand {
                 file: args[0].to_string(),
+                dry_run: args.contains(&"--dry-run"),
+                parallel: args.contains(&"--parallel"),
+                on_duplicate: parse_on_duplicate(args),
+                format: None,
+                report: None,
             };
             command.execute(ctx)
         }
@@ -383,6 +523,13 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
             
             let command = ExportCommand {
                 file: args[0].to_string(),
+                feed: None,
+                limit: None,
+                collection: match args {
+                    [_, "--collection", name, ..] => Some(name.to_string()),
+                    _ => None,
+                },
+                filter: Default::default(),
             };
             command.execute(ctx)
         }
@@ -396,6 +543,15 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 list,
                 all,
                 browsers,
+                path: args
+                    .iter()
+                    .position(|a| *a == "--path")
+                    .and_then(|i| args.get(i + 1))
+                    .map(PathBuf::from),
+                browser_type: None,
+                dry_run: args.contains(&"--dry-run"),
+                on_duplicate: parse_on_duplicate(args),
+                format: None,
             };
             command.execute(ctx)
         }
@@ -404,8 +560,9 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
         "open" | "o" => {
             let ids: Vec<String> = args.iter().map(|s| s.to_string()).collect();
             if ids.is_empty() {
-                println!("Usage: open <id>");
+                println!("Usage: open <id|range>");
                 println!("Example: open 5");
+                println!("Example: open -3..-1   (last three added)");
                 return Ok(());
             }
             
@@ -421,7 +578,12 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
                 args[0].parse::<u32>().unwrap_or(8)
             };
             
-            let command = LockCommand { iterations };
+            let command = LockCommand {
+                iterations,
+                kdf: "sha256".to_string(),
+                memory: None,
+                passes: bukurs::crypto::ARGON2_DEFAULT_PASSES,
+            };
             command.execute(ctx)
         }
         
@@ -437,6 +599,44 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
             command.execute(ctx)
         }
         
+        // Read-later queue
+        "later" => {
+            let command = LaterCommand {
+                pop: args.first() == Some(&"pop"),
+            };
+            command.execute(ctx)
+        }
+        
+        // Pinning and manual order
+        "pin" | "unpin" => {
+            if args.is_empty() {
+                println!("Usage: {} <id|range>", cmd);
+                return Ok(());
+            }
+            let command = PinCommand {
+                ids: args.iter().map(|s| s.to_string()).collect(),
+                pinned: cmd == "pin",
+            };
+            command.execute(ctx)
+        }
+
+        "move" => {
+            let (before, after) = match args {
+                [_, "--before", other] => (Some(other.to_string()), None),
+                [_, "--after", other] => (None, Some(other.to_string())),
+                _ => {
+                    println!("Usage: move <id> --before|--after <other-id>");
+                    return Ok(());
+                }
+            };
+            let command = MoveCommand {
+                id: args[0].to_string(),
+                before,
+                after,
+            };
+            command.execute(ctx)
+        }
+
         // Undo
         "undo" => {
             let count = if args.is_empty() {
@@ -454,29 +654,63 @@ fn handle_command(ctx: &AppContext, line: &str) -> Result<()> {
     }
 }
 
+/// Whether a shell command changes the database (see `cli::mutates_database`)
+fn mutates_database(cmd: &str, args: &[&str]) -> bool {
+    match cmd {
+        // `add`, `update` and `edit` lock themselves once fetching or editing is done
+        "d" | "delete" | "del" | "import" | "import-browsers" | "lock" | "unlock" | "undo"
+        | "pin" | "unpin" | "move" => true,
+        "later" => args.first() == Some(&"pop"),
+        _ => false,
+    }
+}
+
 // Edit handler (still needs special handling for editor interaction)
+/// Read the value following `--on-duplicate`, defaulting to skip
+fn parse_on_duplicate(args: &[&str]) -> DuplicateStrategy {
+    args.iter()
+        .position(|a| *a == "--on-duplicate")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|s| DuplicateStrategy::from_string(s))
+        .unwrap_or_default()
+}
+
 fn handle_edit_interactive(ctx: &AppContext, args: &[&str]) -> Result<()> {
     if args.is_empty() {
-        println!("Usage: e <bookmark_id>");
-        println!("Example: e 5");
+        println!("Usage: e <bookmark_id|range>");
+        println!("Example: e 5, e 10-20 (ranges open in one buffer)");
         return Ok(());
     }
 
-    let bookmark_id = match args[0].parse::<usize>() {
-        Ok(id) => id,
-        Err(_) => {
+    // A single index, including relative ones like -1
+    let term = match SelectorTerm::parse(args[0]) {
+        Ok(Some(term @ (SelectorTerm::Id(_) | SelectorTerm::Last(_)))) if args.len() == 1 => term,
+        // Ranges and multiple indices are edited together
+        Ok(Some(_)) => {
+            let command = EditCommand {
+                ids: args.iter().map(|s| s.to_string()).collect(),
+                search: Vec::new(),
+            };
+            return command.execute(ctx);
+        }
+        Ok(None) => {
             println!("Invalid bookmark ID: {}", args[0]);
             return Ok(());
         }
+        Err(e) => {
+            println!("Invalid bookmark ID: {}", e);
+            return Ok(());
+        }
     };
 
-    let bookmark = match ctx.db.get_rec_by_id(bookmark_id)? {
+    let bookmark = match Selector::Terms(vec![term]).resolve(ctx.db)?.bookmarks.pop() {
         Some(b) => b,
         None => {
-            println!("Bookmark {} not found", bookmark_id);
+            println!("Bookmark {} not found", args[0]);
             return Ok(());
         }
     };
+    let bookmark_id = bookmark.id;
 
     println!("Opening bookmark #{} in editor...", bookmark_id);
 
@@ -489,6 +723,7 @@ fn handle_edit_interactive(ctx: &AppContext, args: &[&str]) -> Result<()> {
     };
 
     // Update the database
+    let _lock = ctx.db.write_lock()?;
     match ctx.db.update_rec_partial(
         bookmark_id,
         Some(&edited.url),
@@ -515,17 +750,33 @@ fn handle_edit_interactive(ctx: &AppContext, args: &[&str]) -> Result<()> {
     Ok(())
 }
 
-// Open by ID (when command is just a number)
+// Open by ID (when command is just an index, range or -N)
 fn handle_open_by_id(db: &BukuDb, cmd: &str) -> Result<()> {
-    if let Ok(id) = cmd.parse::<usize>() {
-        if let Some(rec) = db.get_rec_by_id(id)? {
-            println!("Opening: {}", rec.url);
-            bukurs::browser::open_url(&rec.url)?;
-        } else {
-            println!("Bookmark {} not found", id);
+    match SelectorTerm::parse(cmd) {
+        Ok(Some(term)) => {
+            let bookmarks = Selector::Terms(vec![term]).resolve(db)?.bookmarks;
+            if bookmarks.is_empty() {
+                println!("Bookmark {} not found", cmd);
+            }
+            for rec in bookmarks {
+                println!("Opening: {}", rec.url);
+                bukurs::browser::open_url(&rec.url)?;
+                if let Err(e) = db.record_visits(&[rec.id]) {
+                    log::warn!("Failed to record visit: {}", e);
+                }
+            }
         }
-    } else {
-        println!("Unknown command: {}. Type '?' for help", cmd);
+        Ok(None) => match db.get_rec_by_alias(cmd)? {
+            Some(rec) => {
+                println!("Opening: {}", rec.url);
+                bukurs::browser::open_url(&rec.url)?;
+                if let Err(e) = db.record_visits(&[rec.id]) {
+                    log::warn!("Failed to record visit: {}", e);
+                }
+            }
+            None => println!("Unknown command: {}. Type '?' for help", cmd),
+        },
+        Err(e) => println!("Invalid index: {}", e),
     }
     Ok(())
 }
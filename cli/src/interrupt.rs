@@ -0,0 +1,55 @@
+use bukurs::cancel::CancellationToken;
+use signal_hook::consts::SIGINT;
+use signal_hook::SigId;
+
+/// Cancels a token on Ctrl-C while alive
+///
+/// The first Ctrl-C asks the operation to stop and keep what it finished; a
+/// second one exits immediately. Dropping the guard restores the default
+/// handling, so the shell isn't left with a stale handler.
+pub struct InterruptGuard {
+    token: CancellationToken,
+    handlers: Vec<SigId>,
+}
+
+impl InterruptGuard {
+    pub fn install() -> Self {
+        let token = CancellationToken::new();
+        let mut handlers = Vec::new();
+        // Registered first, so it sees the flag before the first Ctrl-C sets it
+        match signal_hook::flag::register_conditional_shutdown(SIGINT, 130, token.flag()) {
+            Ok(id) => handlers.push(id),
+            Err(e) => log::warn!("Couldn't install Ctrl-C handler: {}", e),
+        }
+        match signal_hook::flag::register(SIGINT, token.flag()) {
+            Ok(id) => handlers.push(id),
+            Err(e) => log::warn!("Couldn't install Ctrl-C handler: {}", e),
+        }
+        Self { token, handlers }
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        for id in self.handlers.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_starts_uncancelled() {
+        let guard = InterruptGuard::install();
+        assert!(!guard.token().is_cancelled());
+        guard.token().cancel();
+        assert!(guard.token().is_cancelled());
+    }
+}
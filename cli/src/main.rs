@@ -4,14 +4,17 @@ mod editor;
 mod fetch_ui;
 mod format;
 mod interactive;
+mod interrupt;
 mod output;
-mod tag_ops;
+mod picker;
+mod prompt;
+mod shell;
 
-use bukurs::{config, db, error::Result, utils};
+use bukurs::{audit, config, db, error::Result};
 use clap::Parser;
 
 fn main() -> Result<()> {
-    let args = cli::Cli::parse();
+    let args = cli::Cli::parse_from(cli::escape_relative_indices(std::env::args_os()));
 
     // Initialize logger
     env_logger::init();
@@ -21,24 +24,91 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let db_path = if let Some(path) = &args.db {
-        path.clone()
+    if let Some(command) = cli::config_command(&args) {
+        return command.run();
+    }
+
+    // Load configuration; BUKURS_* environment variables override the file
+    let mut cfg = if let Some(config_path) = &args.config {
+        let mut cfg = config::Config::load_from_path(config_path)?;
+        cfg.apply_env(std::env::vars())?;
+        cfg
     } else {
-        utils::get_default_dbdir().join("bookmarks.db")
+        config::Config::load()
     };
 
+    // --online overrides `default_offline` and BUKURS_OFFLINE
+    if args.online {
+        cfg.default_offline = false;
+    }
+    if let Some(kind) = cli::picker_kind(&args) {
+        cfg.picker.kind = kind;
+    }
+    if let Some(path) = &args.cacert {
+        cfg.fetch.tls_ca_file = Some(path.clone());
+    }
+
+    // --db and --profile override `db` from the config and BUKURS_DB, which
+    // override a workspace's `.bukurs.toml`
+    let db_path = match (&args.db, &args.profile) {
+        (Some(path), _) => path.clone(),
+        (None, Some(name)) => cfg.profile_path(name)?,
+        (None, None) => cfg.db_path_in(&std::env::current_dir()?)?,
+    };
+
+    let interaction = prompt::Interaction::new(args.yes, args.no_input);
+    if let Some(command) = cli::cipher_command(&args) {
+        return command.run(&db_path, &cfg, interaction);
+    }
+    if cfg.database.encrypted && db_path.exists() && db::cipher::is_plaintext(&db_path)? {
+        return Err(bukurs::error::BukursError::Config(format!(
+            "database.encrypted is set but {} is plain SQLite; run `bukurs cipher encrypt` first",
+            db_path.display()
+        )));
+    }
+    cfg.database.unlock(|| {
+        interaction.require_input("The database key prompt")?;
+        Ok(rpassword::prompt_password("Database key: ")?)
+    })?;
+
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    let db = db::BukuDb::init(&db_path)?;
+    if let Some(editor) = &cfg.editor {
+        editor::set_editor(editor.clone());
+    }
+
+    if args.raw_tags {
+        format::structured::set_raw_tags();
+    }
+
+    match output::theme::Theme::from_config(&cfg.colors) {
+        Ok(theme) => theme.set_current(),
+        Err(e) => eprintln!("Warning: {}; using the default colors", e),
+    }
 
-    // Load configuration
-    let cfg = if let Some(config_path) = &args.config {
-        config::Config::load_from_path(config_path)?
+    // Commands that change bookmarks hold the lock until they finish; the rest
+    // take it only to create or migrate the schema, so reading never waits
+    // for a long import
+    let mutates = cli::mutates_database(&args);
+    let lock = if mutates || db::BukuDb::needs_migration(&db_path, &cfg.database)? {
+        cfg.database.lock_database(&db_path)?
     } else {
-        config::Config::load()
+        None
     };
+    let mut db = db::BukuDb::init_with_options(&db_path, &cfg.database)?;
+    let _lock = lock.filter(|_| mutates);
+    db.set_autotag_rules(cfg.autotag.clone());
+    let source = if matches!(args.command, Some(cli::Commands::Shell)) {
+        audit::AuditSource::Shell
+    } else {
+        audit::AuditSource::Cli
+    };
+    db.set_source(source);
+    if let Some(path) = &cfg.audit_log {
+        db.set_audit_log(Some(audit::AuditLog::new(path, source)));
+    }
 
     cli::handle_args(args, &db, &db_path, &cfg)?;
 
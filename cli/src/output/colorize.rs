@@ -1,6 +1,9 @@
+use super::theme::Theme;
+use crate::format::Badges;
+use bukurs::fetch::readability::reading_time;
 use bukurs::models::bookmark::Bookmark;
+use bukurs::operations::rating::stars;
 use bukurs::tags::parse_tags;
-use owo_colors::OwoColorize;
 
 pub trait Colorize {
     fn to_colored(&self) -> String;
@@ -10,31 +13,57 @@ pub struct ColorizeBookmark<'a>(pub &'a Bookmark);
 
 impl<'a> Colorize for ColorizeBookmark<'a> {
     fn to_colored(&self) -> String {
+        self.with_badges(Badges::default())
+    }
+}
+
+impl ColorizeBookmark<'_> {
+    /// The colored listing, with the rating as stars and the reading time after the title
+    pub fn with_badges(&self, badges: Badges) -> String {
+        let theme = Theme::current();
         let mut s = String::new();
         let id = self.0.id.to_string();
         s.push_str(&format!(
-            "{}. {}\n",
-            id.bright_blue(),
-            self.0.title.bold().green(),
+            "{}. {}",
+            theme.id.style(&id),
+            theme.title.style(&self.0.title),
         ));
+        if badges.rating > 0 {
+            s.push_str(&format!(" {}", theme.rating.style(stars(badges.rating))));
+        }
+        if badges.word_count > 0 {
+            s.push_str(&format!(
+                " {}",
+                theme.desc.style(reading_time(badges.word_count))
+            ));
+        }
+        s.push('\n');
         let padding = id.len() + 3;
         // padding for alignment
         s.push_str(&format!(
             "{:>padding$} {}\n",
-            ">".red(),
-            self.0.url.yellow()
+            theme.highlight.style(">"),
+            theme.url.style(&self.0.url)
         ));
 
-        // Only show description if non-empty
-        if !self.0.description.trim().is_empty() {
-            s.push_str(&format!("{:>padding$} {}\n", "+".red(), self.0.description));
+        // Only show description if non-empty; multi-line notes show their first line
+        if let Some(summary) = self.0.description_summary() {
+            s.push_str(&format!(
+                "{:>padding$} {}\n",
+                theme.highlight.style("+"),
+                theme.desc.style(summary)
+            ));
         }
 
         // Parse tags and only show if non-empty
         let tags = parse_tags(&self.0.tags);
         if !tags.is_empty() {
             let tags_str = tags.join(", ");
-            s.push_str(&format!("{:>padding$} {}\n", "#".red(), tags_str.blue()));
+            s.push_str(&format!(
+                "{:>padding$} {}\n",
+                theme.highlight.style("#"),
+                theme.tags.style(tags_str)
+            ));
         }
         s
     }
@@ -175,4 +204,41 @@ mod tests {
             "Should not have description line for empty description"
         );
     }
+
+    #[test]
+    fn test_colorize_bookmark_badges() {
+        let bookmark = Bookmark::new(
+            1,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",".to_string(),
+            "".to_string(),
+        );
+
+        let rated = ColorizeBookmark(&bookmark).with_badges(Badges {
+            rating: 3,
+            word_count: 2400,
+        });
+        let first_line = rated.lines().next().unwrap();
+        assert!(first_line.contains("Example") && first_line.contains("★★★☆☆"));
+        assert!(first_line.contains("~12 min"));
+        assert!(!ColorizeBookmark(&bookmark).to_colored().contains('★'));
+    }
+
+    #[test]
+    fn test_colorize_bookmark_multiline_notes() {
+        let bookmark = Bookmark::new(
+            1,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",".to_string(),
+            "Summary line\n\nMore details\nand more".to_string(),
+        );
+
+        let colorized = ColorizeBookmark(&bookmark).to_colored();
+
+        // Only the first line is shown, flagged as having more notes
+        assert!(colorized.contains("Summary line [+notes]"));
+        assert!(!colorized.contains("More details"));
+    }
 }
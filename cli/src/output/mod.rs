@@ -1 +1,2 @@
 pub mod colorize;
+pub mod theme;
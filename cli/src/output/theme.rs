@@ -0,0 +1,217 @@
+use bukurs::config::ColorConfig;
+use bukurs::error::{BukursError, Result};
+use owo_colors::{DynColors, Style, XtermColors};
+use std::sync::OnceLock;
+
+/// Built-in theme names, as accepted by `colors.theme`
+pub const THEMES: &[&str] = &["default", "solarized", "mono"];
+
+/// Theme set from the config at startup
+static CURRENT: OnceLock<Theme> = OnceLock::new();
+
+/// Styles for each part of a colored bookmark listing
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub id: Style,
+    pub url: Style,
+    pub title: Style,
+    pub tags: Style,
+    pub desc: Style,
+    /// The `>`, `+` and `#` markers
+    pub highlight: Style,
+    /// Stars of rated bookmarks
+    pub rating: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            id: Style::new().bright_blue(),
+            url: Style::new().yellow(),
+            title: Style::new().bold().green(),
+            tags: Style::new().blue(),
+            desc: Style::new(),
+            highlight: Style::new().red(),
+            rating: Style::new().yellow(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn solarized() -> Self {
+        Self {
+            id: Style::new().truecolor(0x26, 0x8b, 0xd2),
+            url: Style::new().truecolor(0xb5, 0x89, 0x00),
+            title: Style::new().bold().truecolor(0x85, 0x99, 0x00),
+            tags: Style::new().truecolor(0x2a, 0xa1, 0x98),
+            desc: Style::new().truecolor(0x93, 0xa1, 0xa1),
+            highlight: Style::new().truecolor(0xcb, 0x4b, 0x16),
+            rating: Style::new().truecolor(0xb5, 0x89, 0x00),
+        }
+    }
+
+    /// No colors, only emphasis
+    pub fn mono() -> Self {
+        Self {
+            id: Style::new().bold(),
+            url: Style::new().underline(),
+            title: Style::new().bold(),
+            tags: Style::new().italic(),
+            desc: Style::new(),
+            highlight: Style::new().dimmed(),
+            rating: Style::new().bold(),
+        }
+    }
+
+    /// Built-in theme by name
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "solarized" => Some(Self::solarized()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// The configured theme with per-field overrides applied
+    pub fn from_config(config: &ColorConfig) -> Result<Self> {
+        let mut theme = Self::named(&config.theme).ok_or_else(|| {
+            BukursError::Config(format!(
+                "unknown color theme '{}' (expected one of: {})",
+                config.theme,
+                THEMES.join(", ")
+            ))
+        })?;
+
+        let fields = [
+            (&mut theme.id, &config.id, "id"),
+            (&mut theme.url, &config.url, "url"),
+            (&mut theme.title, &config.title, "title"),
+            (&mut theme.tags, &config.tags, "tags"),
+            (&mut theme.desc, &config.desc, "desc"),
+            (&mut theme.highlight, &config.highlight, "highlight"),
+            (&mut theme.rating, &config.rating, "rating"),
+        ];
+        for (style, spec, field) in fields {
+            if let Some(spec) = spec {
+                *style = parse_style(spec)
+                    .map_err(|e| BukursError::Config(format!("colors.{}: {}", field, e)))?;
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Use this theme for colored output for the rest of the run
+    pub fn set_current(self) {
+        let _ = CURRENT.set(self);
+    }
+
+    /// The theme set at startup, or the default one
+    pub fn current() -> Self {
+        CURRENT.get().copied().unwrap_or_default()
+    }
+}
+
+/// Parse a color spec such as `bold green`, `#268bd2`, `208` or `none`
+fn parse_style(spec: &str) -> std::result::Result<Style, String> {
+    let mut style = Style::new();
+    let mut has_color = false;
+
+    for word in spec.split_whitespace() {
+        let word = word.to_ascii_lowercase();
+        style = match word.as_str() {
+            "none" | "plain" => Style::new(),
+            "bold" => style.bold(),
+            "dimmed" | "dim" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            _ => {
+                if has_color {
+                    return Err(format!("more than one color in '{}'", spec));
+                }
+                has_color = true;
+                style.color(parse_color(&word).ok_or_else(|| {
+                    format!(
+                        "unknown color '{}' (expected a name, #rrggbb or 0-255)",
+                        word
+                    )
+                })?)
+            }
+        };
+    }
+    Ok(style)
+}
+
+fn parse_color(word: &str) -> Option<DynColors> {
+    if let Ok(code) = word.parse::<u8>() {
+        return Some(DynColors::Xterm(XtermColors::from(code)));
+    }
+    // owo-colors spells bright colors with a space ("bright blue")
+    word.replace(['_', '-'], " ").parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn paint(style: Style) -> String {
+        style.style("x").to_string()
+    }
+
+    #[rstest]
+    #[case("green", Style::new().green())]
+    #[case("bold green", Style::new().bold().green())]
+    #[case("bright_blue", Style::new().bright_blue())]
+    #[case("Bright-Blue underline", Style::new().bright_blue().underline())]
+    #[case("#268bd2", Style::new().truecolor(0x26, 0x8b, 0xd2))]
+    #[case("208", Style::new().color(XtermColors::from(208)))]
+    #[case("none", Style::new())]
+    fn test_parse_style(#[case] spec: &str, #[case] expected: Style) {
+        assert_eq!(paint(parse_style(spec).unwrap()), paint(expected));
+    }
+
+    #[rstest]
+    #[case("chartreuse")]
+    #[case("#12345")]
+    #[case("256")]
+    #[case("red blue")]
+    fn test_parse_style_invalid(#[case] spec: &str) {
+        assert!(parse_style(spec).is_err());
+    }
+
+    #[test]
+    fn test_from_config_overrides_theme_fields() {
+        let config = ColorConfig {
+            theme: "mono".to_string(),
+            url: Some("cyan".to_string()),
+            ..ColorConfig::default()
+        };
+        let theme = Theme::from_config(&config).unwrap();
+        assert_eq!(paint(theme.url), paint(Style::new().cyan()));
+        assert_eq!(paint(theme.title), paint(Theme::mono().title));
+    }
+
+    #[rstest]
+    #[case("neon", None)]
+    #[case("mono", Some("sparkly"))]
+    fn test_from_config_errors(#[case] theme: &str, #[case] id: Option<&str>) {
+        let config = ColorConfig {
+            theme: theme.to_string(),
+            id: id.map(str::to_string),
+            ..ColorConfig::default()
+        };
+        assert!(Theme::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_builtin_themes() {
+        for name in THEMES {
+            assert!(Theme::named(name).is_some());
+        }
+        // mono emphasizes without any color codes
+        let title = paint(Theme::mono().title);
+        assert!(!title.contains("38;"));
+        assert!(!title.contains("[3"));
+    }
+}
@@ -0,0 +1,142 @@
+//! Picking bookmarks with an external fuzzy finder such as fzf or skim
+//!
+//! Each bookmark goes to the finder's stdin as one `id<TAB>title<TAB>url<TAB>tags`
+//! line; the lines it prints back are the selection, matched by their leading ID.
+
+use bukurs::error::{BukursError, Result};
+use bukurs::models::bookmark::Bookmark;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Run `command` over `bookmarks` and return the ones picked, in the order
+/// printed; empty when the finder is cancelled or exits with an error
+///
+/// `{query}` in the command is replaced by the search keywords.
+pub fn run_external(
+    command: &str,
+    bookmarks: &[Bookmark],
+    query: Option<&str>,
+) -> Result<Vec<Bookmark>> {
+    let words: Vec<String> = crate::shell::tokenize(command)?
+        .into_iter()
+        .map(|word| word.replace("{query}", query.unwrap_or("")))
+        .collect();
+    let Some((program, args)) = words.split_first() else {
+        return Err(BukursError::FuzzySearch(
+            "picker.command is empty".to_string(),
+        ));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| BukursError::FuzzySearch(format!("Failed to run '{}': {}", program, e)))?;
+
+    let input: String = bookmarks.iter().map(candidate_line).collect();
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Feed the finder from another thread so a full stdout pipe can't block us
+    let writer = std::thread::spawn(move || match stdin.write_all(input.as_bytes()) {
+        // The finder may exit before reading everything
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    });
+
+    let output = child.wait_with_output()?;
+    writer.join().expect("picker input thread panicked")?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_selection(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter_map(|id| bookmarks.iter().find(|b| b.id == id).cloned())
+        .collect())
+}
+
+/// One tab-separated candidate line, with tabs and newlines in fields turned into spaces
+fn candidate_line(bookmark: &Bookmark) -> String {
+    let field = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+    format!(
+        "{}\t{}\t{}\t{}\n",
+        bookmark.id,
+        field(&bookmark.title),
+        field(&bookmark.url),
+        field(bookmark.tags.trim_matches(','))
+    )
+}
+
+/// The IDs leading the selected lines, each once, in output order
+fn parse_selection(output: &str) -> Vec<usize> {
+    let mut ids = Vec::new();
+    for line in output.lines() {
+        let Some(id) = line
+            .split('\t')
+            .next()
+            .and_then(|id| id.trim().parse().ok())
+        else {
+            continue;
+        };
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_candidate_line() {
+        let bookmark = Bookmark::new(
+            7,
+            "https://example.com".to_string(),
+            "Tabs\there".to_string(),
+            ",rust,cli,".to_string(),
+            "desc".to_string(),
+        );
+        assert_eq!(
+            candidate_line(&bookmark),
+            "7\tTabs here\thttps://example.com\trust,cli\n"
+        );
+    }
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("3\tTitle\thttps://a.com\t\n", vec![3])]
+    #[case("5\ta\n2\tb\n5\ta\n", vec![5, 2])]
+    #[case("not an id\tx\n\n4\n", vec![4])]
+    fn test_parse_selection(#[case] output: &str, #[case] expected: Vec<usize>) {
+        assert_eq!(parse_selection(output), expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_external() {
+        let bookmarks: Vec<Bookmark> = (1..=3)
+            .map(|i| {
+                Bookmark::new(
+                    i,
+                    format!("https://{}.com", i),
+                    format!("Site {}", i),
+                    ",".to_string(),
+                    String::new(),
+                )
+            })
+            .collect();
+        let ids = |command: &str, query| -> Vec<usize> {
+            run_external(command, &bookmarks, query)
+                .unwrap()
+                .iter()
+                .map(|b| b.id)
+                .collect()
+        };
+        assert_eq!(ids("grep {query}", Some("Site 2")), vec![2]);
+        assert_eq!(ids("tail -n 2", None), vec![2, 3]);
+        assert_eq!(ids("false", None), Vec::<usize>::new());
+        assert!(run_external("", &bookmarks, None).is_err());
+    }
+}
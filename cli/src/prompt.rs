@@ -0,0 +1,106 @@
+use bukurs::error::{BukursError, Result};
+use std::io::{self, IsTerminal, Write};
+
+/// How commands may ask the user for input, from `--yes` and `--no-input`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interaction {
+    /// Answer yes to every confirmation
+    pub assume_yes: bool,
+    /// Fail instead of prompting, picking or opening an editor
+    pub no_input: bool,
+    /// Whether stdin is a terminal someone can answer from
+    pub terminal: bool,
+}
+
+impl Interaction {
+    pub fn new(assume_yes: bool, no_input: bool) -> Self {
+        Self {
+            assume_yes,
+            no_input,
+            terminal: io::stdin().is_terminal(),
+        }
+    }
+
+    /// Ask a yes/no question on stderr
+    ///
+    /// Never reads from a non-terminal stdin, so scripts fail fast instead of
+    /// hanging or consuming piped input.
+    pub fn confirm(&self, question: &str) -> Result<bool> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        self.require_input("Confirmation")?;
+        if !self.terminal {
+            return Err(BukursError::InvalidInput(
+                "Confirmation needed but stdin is not a terminal (pass --yes to confirm)"
+                    .to_string(),
+            ));
+        }
+
+        eprint!("{}", question);
+        io::stderr().flush()?;
+        let mut response = String::new();
+        io::stdin().read_line(&mut response)?;
+        let response = response.trim().to_lowercase();
+        Ok(response == "y" || response == "yes")
+    }
+
+    /// Fail when `what` (a picker, editor or password prompt) can't ask the user
+    pub fn require_input(&self, what: &str) -> Result<()> {
+        if self.no_input {
+            return Err(BukursError::InvalidInput(format!(
+                "{} needs input, but --no-input was given",
+                what
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether optional prompts (such as tag suggestions) should be shown
+    pub fn can_prompt(&self) -> bool {
+        !self.no_input && !self.assume_yes && self.terminal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn interaction(assume_yes: bool, no_input: bool, terminal: bool) -> Interaction {
+        Interaction {
+            assume_yes,
+            no_input,
+            terminal,
+        }
+    }
+
+    #[rstest]
+    #[case(interaction(true, false, false))]
+    #[case(interaction(true, true, false))]
+    #[case(interaction(true, false, true))]
+    fn test_confirm_assume_yes(#[case] interaction: Interaction) {
+        assert!(interaction.confirm("Delete? ").unwrap());
+    }
+
+    #[rstest]
+    #[case(interaction(false, true, true))]
+    #[case(interaction(false, false, false))]
+    fn test_confirm_fails_fast_without_input(#[case] interaction: Interaction) {
+        assert!(interaction.confirm("Delete? ").is_err());
+    }
+
+    #[rstest]
+    #[case(interaction(false, false, true), true, true)]
+    #[case(interaction(false, false, false), false, true)]
+    #[case(interaction(true, false, true), false, true)]
+    #[case(interaction(false, true, true), false, false)]
+    fn test_prompt_availability(
+        #[case] interaction: Interaction,
+        #[case] can_prompt: bool,
+        #[case] input_ok: bool,
+    ) {
+        assert_eq!(interaction.can_prompt(), can_prompt);
+        assert_eq!(interaction.require_input("Picker").is_ok(), input_ok);
+    }
+}
@@ -0,0 +1,535 @@
+//! Line editing support for the interactive shell: tokenizing, completion and history
+
+use bukurs::config::ShellConfig;
+use bukurs::db::BukuDb;
+use bukurs::error::{BukursError, Result};
+use bukurs::utils;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::path::PathBuf;
+
+/// Commands offered when completing the first word
+const COMMANDS: &[&str] = &[
+    "s",
+    "S",
+    "t",
+    "tag",
+    "ls",
+    "list",
+    "a",
+    "add",
+    "u",
+    "update",
+    "d",
+    "delete",
+    "del",
+    "e",
+    "edit",
+    "p",
+    "print",
+    "c",
+    "collection",
+    "import",
+    "export",
+    "import-browsers",
+    "open",
+    "o",
+    "lock",
+    "unlock",
+    "undo",
+    "later",
+    "pin",
+    "unpin",
+    "move",
+    "help",
+    "quit",
+    "exit",
+];
+
+/// Commands whose arguments are bookmark indices
+const ID_COMMANDS: &[&str] = &[
+    "u", "update", "d", "delete", "del", "e", "edit", "p", "print", "open", "o", "pin", "unpin",
+    "move",
+];
+
+/// Maximum number of entries kept in the history file
+pub const MAX_HISTORY: usize = 1000;
+
+/// Where the shell history is persisted between sessions
+pub fn history_path() -> PathBuf {
+    utils::get_default_dbdir().join("bukurs_history")
+}
+
+/// Split a line into words like a POSIX shell would
+///
+/// Single quotes keep everything literally, double quotes allow `\"` and `\\`
+/// escapes, and a backslash outside quotes escapes the next character.
+pub fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated('\'')),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unterminated('"')),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated('"')),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+fn unterminated(quote: char) -> BukursError {
+    BukursError::InvalidInput(format!("Unterminated {} quote", quote))
+}
+
+/// Upper bound on the commands one line may expand to
+const MAX_EXPANDED_COMMANDS: usize = 64;
+
+/// Expand aliases and macros into the commands to run, already split into words
+///
+/// Aliases replace the first word and keep the rest of the line; macros run
+/// their `;`-separated commands in order. Either may refer to others, but not
+/// back to themselves.
+pub fn expand(line: &str, config: &ShellConfig) -> Result<Vec<Vec<String>>> {
+    let mut commands = Vec::new();
+    expand_into(line, config, &mut Vec::new(), &mut commands)?;
+    Ok(commands)
+}
+
+fn expand_into(
+    line: &str,
+    config: &ShellConfig,
+    expanding: &mut Vec<String>,
+    commands: &mut Vec<Vec<String>>,
+) -> Result<()> {
+    let words = tokenize(line)?;
+    let Some(name) = words.first() else {
+        return Ok(());
+    };
+
+    let macro_body = config.macros.get(name);
+    let alias_body = config.aliases.get(name);
+    if macro_body.is_none() && alias_body.is_none() {
+        if commands.len() == MAX_EXPANDED_COMMANDS {
+            return Err(BukursError::InvalidInput(format!(
+                "Expansion of '{}' exceeds {} commands",
+                expanding.first().unwrap_or(name),
+                MAX_EXPANDED_COMMANDS
+            )));
+        }
+        commands.push(words);
+        return Ok(());
+    }
+
+    if expanding.contains(name) {
+        return Err(BukursError::InvalidInput(format!(
+            "Recursive alias or macro: {} -> {}",
+            expanding.join(" -> "),
+            name
+        )));
+    }
+    expanding.push(name.clone());
+
+    if let Some(body) = macro_body {
+        if words.len() > 1 {
+            return Err(BukursError::InvalidInput(format!(
+                "Macro '{}' takes no arguments",
+                name
+            )));
+        }
+        for command in split_commands(body)? {
+            expand_into(command, config, expanding, commands)?;
+        }
+    } else if let Some(body) = alias_body {
+        // Keep the arguments as typed so their quoting survives
+        let line = line.trim_start();
+        let rest = &line[line.find(char::is_whitespace).unwrap_or(line.len())..];
+        expand_into(&format!("{}{}", body, rest), config, expanding, commands)?;
+    }
+
+    expanding.pop();
+    Ok(())
+}
+
+/// Split a macro body on `;` outside of quotes
+fn split_commands(body: &str) -> Result<Vec<&str>> {
+    let mut commands = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('"') | None, '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';') => {
+                commands.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if let Some(q) = quote {
+        return Err(unterminated(q));
+    }
+    commands.push(&body[start..]);
+
+    Ok(commands)
+}
+
+/// What the word under the cursor should complete to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompletionKind {
+    Command,
+    Tag,
+    Id,
+    /// The whole search query, from earlier searches
+    Query,
+    None,
+}
+
+/// Decide what to complete from the words preceding the one under the cursor
+fn completion_kind(previous: &[&str]) -> CompletionKind {
+    let Some((&cmd, args)) = previous.split_first() else {
+        return CompletionKind::Command;
+    };
+
+    match cmd {
+        "s" | "S" => CompletionKind::Query,
+        "t" | "tag" => CompletionKind::Tag,
+        // a <url> [tags] [title] [comment]
+        "a" | "add" if args.len() == 1 => CompletionKind::Tag,
+        "u" | "update" if args.last() == Some(&"-t") => CompletionKind::Tag,
+        "u" | "update" if !args.is_empty() => CompletionKind::None,
+        _ if ID_COMMANDS.contains(&cmd) => CompletionKind::Id,
+        _ => CompletionKind::None,
+    }
+}
+
+/// Offset of the tag being typed within a word like `+rust,-to` or `~old:ne`
+fn tag_offset(word: &str) -> usize {
+    let start = word.rfind([',', ':']).map_or(0, |i| i + 1);
+    let prefix = word[start..]
+        .chars()
+        .take_while(|c| matches!(c, '+' | '-' | '~'))
+        .count();
+    start + prefix
+}
+
+/// Offset of the first argument in `line`, or its end when there is none
+fn arguments_offset(line: &str) -> usize {
+    let command = line.trim_start();
+    let command_end =
+        line.len() - command.len() + command.find(char::is_whitespace).unwrap_or(command.len());
+    line.len() - line[command_end..].trim_start().len()
+}
+
+/// Tab completion for commands, tags, bookmark indices and search queries
+pub struct ShellHelper<'a> {
+    db: &'a BukuDb,
+    /// User-defined alias and macro names, completed alongside the commands
+    shortcuts: Vec<String>,
+}
+
+impl<'a> ShellHelper<'a> {
+    pub fn new(db: &'a BukuDb, config: &ShellConfig) -> Self {
+        let shortcuts = config
+            .aliases
+            .keys()
+            .chain(config.macros.keys())
+            .cloned()
+            .collect();
+        Self { db, shortcuts }
+    }
+
+    fn candidates(&self, kind: CompletionKind, prefix: &str) -> Vec<Pair> {
+        let plain = |s: &str| Pair {
+            display: s.to_string(),
+            replacement: s.to_string(),
+        };
+
+        match kind {
+            CompletionKind::Command => COMMANDS
+                .iter()
+                .copied()
+                .chain(self.shortcuts.iter().map(String::as_str))
+                .filter(|c| c.starts_with(prefix))
+                .map(plain)
+                .collect(),
+            CompletionKind::Tag => self
+                .db
+                .get_all_tags()
+                .unwrap_or_default()
+                .iter()
+                .filter(|t| t.starts_with(prefix))
+                .map(|t| plain(t))
+                .collect(),
+            CompletionKind::Id => self
+                .db
+                .get_rec_all()
+                .unwrap_or_default()
+                .iter()
+                .map(|b| (b.id.to_string(), b))
+                .filter(|(id, _)| id.starts_with(prefix))
+                .map(|(id, b)| {
+                    let title: String = b.title.chars().take(50).collect();
+                    Pair {
+                        display: format!("{} {}", id, title),
+                        replacement: id,
+                    }
+                })
+                .collect(),
+            CompletionKind::Query => self
+                .db
+                .search_history()
+                .unwrap_or_default()
+                .iter()
+                .filter(|q| q.starts_with(prefix))
+                .map(|q| plain(q))
+                .collect(),
+            CompletionKind::None => Vec::new(),
+        }
+    }
+}
+
+impl Completer for ShellHelper<'_> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let word_start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let previous: Vec<&str> = before[..word_start].split_whitespace().collect();
+        let word = &before[word_start..];
+
+        let kind = completion_kind(&previous);
+        let start = match kind {
+            CompletionKind::Tag => word_start + tag_offset(word),
+            CompletionKind::Query => arguments_offset(before),
+            _ => word_start,
+        };
+        Ok((start, self.candidates(kind, &before[start..])))
+    }
+}
+
+impl Hinter for ShellHelper<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper<'_> {}
+
+impl Validator for ShellHelper<'_> {}
+
+impl Helper for ShellHelper<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("a https://x.com rust", vec!["a", "https://x.com", "rust"])]
+    #[case(r#"a https://x.com rust "My Title""#, vec!["a", "https://x.com", "rust", "My Title"])]
+    #[case("u 5 --title 'It''s'", vec!["u", "5", "--title", "Its"])]
+    #[case(r#"u 5 --title "say \"hi\"""#, vec!["u", "5", "--title", r#"say "hi""#])]
+    #[case(r"s C:\\path two\ words", vec!["s", r"C:\path", "two words"])]
+    #[case(r#"a x "" y"#, vec!["a", "x", "", "y"])]
+    #[case("  p   1-5  ", vec!["p", "1-5"])]
+    #[case("", vec![])]
+    fn test_tokenize(#[case] line: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(tokenize(line).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(r#"a x "open"#)]
+    #[case("a x 'open")]
+    fn test_tokenize_unterminated(#[case] line: &str) {
+        assert!(tokenize(line).is_err());
+    }
+
+    #[rstest]
+    #[case(vec![], CompletionKind::Command)]
+    #[case(vec!["t"], CompletionKind::Tag)]
+    #[case(vec!["t", "rust"], CompletionKind::Tag)]
+    #[case(vec!["a", "https://x.com"], CompletionKind::Tag)]
+    #[case(vec!["a", "https://x.com", "rust"], CompletionKind::None)]
+    #[case(vec!["u"], CompletionKind::Id)]
+    #[case(vec!["u", "5", "-t"], CompletionKind::Tag)]
+    #[case(vec!["u", "5", "--title"], CompletionKind::None)]
+    #[case(vec!["d"], CompletionKind::Id)]
+    #[case(vec!["p", "1"], CompletionKind::Id)]
+    #[case(vec!["import"], CompletionKind::None)]
+    #[case(vec!["s"], CompletionKind::Query)]
+    #[case(vec!["S", "rust"], CompletionKind::Query)]
+    fn test_completion_kind(#[case] previous: Vec<&str>, #[case] expected: CompletionKind) {
+        assert_eq!(completion_kind(&previous), expected);
+    }
+
+    #[rstest]
+    #[case("ru", 0)]
+    #[case("+ru", 1)]
+    #[case("+rust,-to", 7)]
+    #[case("~old:ne", 5)]
+    fn test_tag_offset(#[case] word: &str, #[case] expected: usize) {
+        assert_eq!(tag_offset(word), expected);
+    }
+
+    #[rstest]
+    #[case("s ", 2)]
+    #[case("s rust as", 2)]
+    #[case("  S   rust", 6)]
+    #[case("s", 1)]
+    fn test_arguments_offset(#[case] line: &str, #[case] expected: usize) {
+        assert_eq!(arguments_offset(line), expected);
+    }
+
+    #[test]
+    fn test_complete_from_database() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "A", ",rust,cli,", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "B", ",ruby,", "", None)
+            .unwrap();
+        let config = shell_config(&[("rd", "s rust --deep")], &[]);
+        let helper = ShellHelper::new(&db, &config);
+
+        let replacements = |kind, prefix| -> Vec<String> {
+            helper
+                .candidates(kind, prefix)
+                .into_iter()
+                .map(|p| p.replacement)
+                .collect()
+        };
+        assert_eq!(
+            replacements(CompletionKind::Tag, "ru"),
+            vec!["ruby", "rust"]
+        );
+        assert_eq!(replacements(CompletionKind::Id, "2"), vec!["2"]);
+        assert_eq!(
+            replacements(CompletionKind::Command, "im"),
+            vec!["import", "import-browsers"]
+        );
+        assert_eq!(replacements(CompletionKind::Command, "r"), vec!["rd"]);
+
+        db.record_search_query("rust").unwrap();
+        db.record_search_query("go").unwrap();
+        db.record_search_query("rust async").unwrap();
+        assert_eq!(
+            replacements(CompletionKind::Query, "ru"),
+            vec!["rust async", "rust"]
+        );
+    }
+
+    fn shell_config(aliases: &[(&str, &str)], macros: &[(&str, &str)]) -> ShellConfig {
+        let map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        ShellConfig {
+            aliases: map(aliases),
+            macros: map(macros),
+        }
+    }
+
+    #[rstest]
+    #[case("rd", vec![vec!["s", "rust", "--deep"]])]
+    #[case(r#"rd "two words""#, vec![vec!["s", "rust", "--deep", "two words"]])]
+    #[case("cleanup", vec![vec!["p", "-1"], vec!["s", "rust", "--deep"], vec!["undo"]])]
+    #[case("both", vec![vec!["p", "-1"], vec!["s", "rust", "--deep"], vec!["undo"], vec!["p", "*"]])]
+    #[case("p 5", vec![vec!["p", "5"]])]
+    #[case(r#"s "a;b""#, vec![vec!["s", "a;b"]])]
+    fn test_expand(#[case] line: &str, #[case] expected: Vec<Vec<&str>>) {
+        let config = shell_config(
+            &[("rd", "s rust --deep"), ("last", "p -1")],
+            &[("cleanup", "last; rd ; undo"), ("both", "cleanup; p *")],
+        );
+        assert_eq!(expand(line, &config).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("loop")]
+    #[case("ping")]
+    #[case("self")]
+    #[case("cleanup now")]
+    #[case("boom")]
+    fn test_expand_errors(#[case] line: &str) {
+        let config = shell_config(
+            &[("self", "self -x"), ("ping", "pong")],
+            &[
+                ("loop", "p 1; loop"),
+                ("pong", "ping"),
+                ("cleanup", "undo"),
+                ("boom", "b; b; b; b"),
+                ("b", "c; c; c; c"),
+                ("c", "p; p; p; p; p"),
+            ],
+        );
+        assert!(expand(line, &config).is_err());
+    }
+
+    #[rstest]
+    #[case("a; b", vec!["a", " b"])]
+    #[case(r#"s "x;y"; p"#, vec![r#"s "x;y""#, " p"])]
+    #[case(r"s x\;y", vec![r"s x\;y"])]
+    fn test_split_commands(#[case] body: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(split_commands(body).unwrap(), expected);
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * C interface to the bukurs bookmark engine (libbukurs_ffi)
+ *
+ * Functions that can fail return -1 (or NULL) and leave a message for
+ * bukurs_last_error() on the calling thread. Bookmarks and tag lists are
+ * returned as UTF-8 JSON in the same shape as `bukurs --format json`; every
+ * returned string belongs to the caller and is released with
+ * bukurs_string_free(). A database handle may move between threads but must
+ * not be used from two at once.
+ */
+
+#ifndef BUKURS_H
+#define BUKURS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this interface; bumped whenever a function changes incompatibly */
+#define BUKURS_ABI_VERSION 1
+
+typedef struct BukursDb BukursDb;
+
+/* Library version, e.g. "0.1.0"; static, not to be freed */
+const char *bukurs_version(void);
+
+/* BUKURS_ABI_VERSION of the library at runtime */
+uint32_t bukurs_abi_version(void);
+
+/* Message of the last failure on this thread, or NULL; not to be freed */
+const char *bukurs_last_error(void);
+
+/* Open (creating if needed) the database at `path`, or the CLI's database when
+ * `path` is NULL; ":memory:" opens a throwaway one. Returns NULL on failure. */
+BukursDb *bukurs_open(const char *path);
+
+/* Close a database; NULL is ignored */
+void bukurs_close(BukursDb *db);
+
+/* Release a string returned by the library; NULL is ignored */
+void bukurs_string_free(char *s);
+
+/* Bookmarks matching any of the whitespace-separated keywords (all of them
+ * when `all` is non-zero), as a JSON array */
+char *bukurs_search(const BukursDb *db, const char *keywords, int all);
+
+/* The bookmark with `id` as a JSON object, or NULL */
+char *bukurs_get(const BukursDb *db, uint64_t id);
+
+/* Every bookmark, in index order, as a JSON array */
+char *bukurs_list(const BukursDb *db);
+
+/* Every tag in use, sorted, as a JSON array of strings */
+char *bukurs_tags(const BukursDb *db);
+
+/* Add a bookmark without fetching its page; `title`, `tags` (comma-separated)
+ * and `desc` may be NULL. Returns the new ID, or -1 */
+int64_t bukurs_add(const BukursDb *db, const char *url, const char *title,
+                   const char *tags, const char *desc);
+
+/* Change bookmark `id`; NULL fields are left alone, and `tags` takes
+ * comma-separated operations (+tag, -tag, ~old:new) or bare tags to add.
+ * Returns 0, or -1 */
+int bukurs_update(const BukursDb *db, uint64_t id, const char *url,
+                  const char *title, const char *tags, const char *desc);
+
+/* Delete bookmark `id`; returns 0, or -1 */
+int bukurs_delete(const BukursDb *db, uint64_t id);
+
+/* Undo the last change; returns 1 if something was undone, 0 if there was
+ * nothing to undo, or -1 */
+int bukurs_undo(const BukursDb *db);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BUKURS_H */
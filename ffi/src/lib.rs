@@ -0,0 +1,432 @@
+//! C ABI for native frontends (GTK, Qt, Swift, ...), declared in `include/bukurs.h`
+//!
+//! Functions that can fail return -1 (or null) and leave a message for
+//! [`bukurs_last_error`] on the calling thread. Bookmarks and tag lists come
+//! back as JSON in the same shape as `bukurs --format json`; every string the
+//! library returns belongs to the caller and is released with
+//! [`bukurs_string_free`]. A database handle may move between threads but
+//! must not be used from two at once.
+
+use bukurs::audit::AuditSource;
+use bukurs::config::Config;
+use bukurs::db::BukuDb;
+use bukurs::error::{BukursError, Result};
+use bukurs::fetch::FetchOptions;
+use bukurs::search::SearchOptions;
+use bukurs::service::{AddRequest, BookmarkService, FieldChanges, SearchQuery};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+/// Bumped whenever a function changes incompatibly; see [`bukurs_abi_version`]
+pub const BUKURS_ABI_VERSION: u32 = 1;
+
+/// An open database
+pub struct BukursDb {
+    db: BukuDb,
+    fetch: FetchOptions,
+    search: SearchOptions,
+}
+
+impl BukursDb {
+    fn service(&self) -> BookmarkService<'_> {
+        BookmarkService::new(&self.db, self.fetch.clone())
+    }
+
+    /// Run a change under the database lock, like the CLI's mutating commands
+    fn write<T>(&self, change: impl FnOnce(BookmarkService<'_>) -> Result<T>) -> Result<T> {
+        let _lock = self
+            .db
+            .connection_options()
+            .lock_database(self.db.get_path())?;
+        change(self.service())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning its errors and panics into `failed` and the last error
+fn guard<T>(failed: T, f: impl FnOnce() -> Result<T>) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_last_error(&e.to_string());
+            failed
+        }
+        Err(_) => {
+            set_last_error("Internal error in bukurs");
+            failed
+        }
+    }
+}
+
+/// An optional UTF-8 string argument; null means unset
+unsafe fn optional<'a>(s: *const c_char, name: &str) -> Result<Option<&'a str>> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|_| BukursError::InvalidInput(format!("{} is not valid UTF-8", name)))
+}
+
+/// A required UTF-8 string argument
+unsafe fn required<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    optional(s, name)?.ok_or_else(|| BukursError::InvalidInput(format!("{} is null", name)))
+}
+
+unsafe fn handle<'a>(db: *const BukursDb) -> Result<&'a BukursDb> {
+    db.as_ref()
+        .ok_or_else(|| BukursError::InvalidInput("database handle is null".to_string()))
+}
+
+/// Comma-separated tags (or tag operations) as a list
+fn tag_list(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn json(value: &impl Serialize) -> Result<*mut c_char> {
+    let json = serde_json::to_string(value).map_err(|e| BukursError::Json(e.to_string()))?;
+    // JSON escapes control characters, so it never contains a NUL
+    Ok(CString::new(json).unwrap_or_default().into_raw())
+}
+
+/// The library version, e.g. "0.1.0"; a static string, not to be freed
+#[no_mangle]
+pub extern "C" fn bukurs_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Version of this C interface, [`BUKURS_ABI_VERSION`]
+#[no_mangle]
+pub extern "C" fn bukurs_abi_version() -> u32 {
+    BUKURS_ABI_VERSION
+}
+
+/// Message of the last failure on this thread, or null; valid until the
+/// next failing call on the thread, not to be freed
+#[no_mangle]
+pub extern "C" fn bukurs_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Open (creating if needed) the database at `path`, or the CLI's database
+/// when `path` is null; settings come from the bukurs config file
+///
+/// Returns null on failure. Close the handle with [`bukurs_close`].
+///
+/// # Safety
+///
+/// `path` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_open(path: *const c_char) -> *mut BukursDb {
+    guard(ptr::null_mut(), || {
+        let path = optional(path, "path")?;
+        let config = Config::load();
+        let db = config.open_db(path.map(Path::new), AuditSource::Ffi)?;
+        Ok(Box::into_raw(Box::new(BukursDb {
+            db,
+            fetch: config.fetch_options(),
+            search: config.search,
+        })))
+    })
+}
+
+/// Close a database opened by [`bukurs_open`]; null is ignored
+///
+/// # Safety
+///
+/// `db` must be null or a handle from `bukurs_open` that isn't used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_close(db: *mut BukursDb) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+/// Release a string returned by the library; null is ignored
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library, freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Search for whitespace-separated `keywords`, matching any of them (or all
+/// when `all` is non-zero); returns a JSON array of bookmarks, or null
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open` and `keywords` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_search(
+    db: *const BukursDb,
+    keywords: *const c_char,
+    all: c_int,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let db = handle(db)?;
+        let query = SearchQuery {
+            keywords: required(keywords, "keywords")?
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            all: all != 0,
+            rank: db.search.rank,
+            weights: db.search.weights,
+            keyword_match: db.search.keyword_match,
+            ..SearchQuery::default()
+        };
+        json(&db.service().search_query(&query)?)
+    })
+}
+
+/// The bookmark with `id` as a JSON object, or null
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_get(db: *const BukursDb, id: u64) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let id = id as usize;
+        match handle(db)?.db.get_rec_by_id(id)? {
+            Some(bookmark) => json(&bookmark),
+            None => Err(BukursError::BookmarkNotFound(id)),
+        }
+    })
+}
+
+/// Every bookmark, in index order, as a JSON array
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_list(db: *const BukursDb) -> *mut c_char {
+    guard(ptr::null_mut(), || json(&handle(db)?.db.get_rec_all()?))
+}
+
+/// Every tag in use, sorted, as a JSON array of strings
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_tags(db: *const BukursDb) -> *mut c_char {
+    guard(ptr::null_mut(), || json(&handle(db)?.db.get_all_tags()?))
+}
+
+/// Add a bookmark without fetching its page (it is queued for
+/// `bukurs fetch-pending`); `tags` is comma-separated. Returns the new ID, or -1
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`, `url` a NUL-terminated string,
+/// and `title`, `tags` and `desc` null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_add(
+    db: *const BukursDb,
+    url: *const c_char,
+    title: *const c_char,
+    tags: *const c_char,
+    desc: *const c_char,
+) -> i64 {
+    guard(-1, || {
+        let db = handle(db)?;
+        let request = AddRequest {
+            url: required(url, "url")?.to_string(),
+            title: optional(title, "title")?.map(str::to_string),
+            tags: optional(tags, "tags")?.map(tag_list),
+            desc: optional(desc, "desc")?.map(str::to_string),
+            ..AddRequest::default()
+        };
+        let outcome = db.write(|service| service.add(&request, None))?;
+        Ok(outcome.id as i64)
+    })
+}
+
+/// Change bookmark `id`; null fields are left alone, and `tags` takes
+/// comma-separated tag operations (`+tag`, `-tag`, `~old:new`) or bare tags
+/// to add. Returns 0, or -1
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`, and `url`, `title`, `tags` and
+/// `desc` null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_update(
+    db: *const BukursDb,
+    id: u64,
+    url: *const c_char,
+    title: *const c_char,
+    tags: *const c_char,
+    desc: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let db = handle(db)?;
+        let changes = FieldChanges {
+            url: optional(url, "url")?.map(str::to_string),
+            title: optional(title, "title")?.map(str::to_string),
+            desc: optional(desc, "desc")?.map(str::to_string),
+            tags: optional(tags, "tags")?.map(tag_list),
+            immutable: None,
+        };
+        let outcome = db.write(|service| service.update_many(&[id.to_string()], &changes))?;
+        if let Some(error) = outcome.errors.first() {
+            return Err(BukursError::InvalidInput(error.clone()));
+        }
+        if outcome.matched == 0 {
+            return Err(BukursError::BookmarkNotFound(id as usize));
+        }
+        Ok(0)
+    })
+}
+
+/// Delete bookmark `id`; returns 0, or -1
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_delete(db: *const BukursDb, id: u64) -> c_int {
+    guard(-1, || {
+        let outcome = handle(db)?.write(|service| service.delete_by_selector(&[id.to_string()]))?;
+        if outcome.deleted.is_empty() {
+            return Err(BukursError::BookmarkNotFound(id as usize));
+        }
+        Ok(0)
+    })
+}
+
+/// Undo the last change; returns 1 if something was undone, 0 if the
+/// history is empty, or -1
+///
+/// # Safety
+///
+/// `db` must be a handle from `bukurs_open`.
+#[no_mangle]
+pub unsafe extern "C" fn bukurs_undo(db: *const BukursDb) -> c_int {
+    guard(-1, || {
+        let undone = handle(db)?.write(|service| Ok(service.db().undo_last()?))?;
+        Ok(c_int::from(undone.is_some()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    /// Take ownership of a returned string
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null(), "{:?}", last_error());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        bukurs_string_free(s);
+        owned
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(bukurs_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_round_trip_through_the_c_api() {
+        unsafe {
+            let db = bukurs_open(c(":memory:").as_ptr());
+            assert!(!db.is_null());
+
+            let id = bukurs_add(
+                db,
+                c("https://rust-lang.org").as_ptr(),
+                c("Rust").as_ptr(),
+                c("lang, systems").as_ptr(),
+                ptr::null(),
+            );
+            assert_eq!(id, 1);
+
+            let found: serde_json::Value =
+                serde_json::from_str(&take(bukurs_search(db, c("rust").as_ptr(), 0))).unwrap();
+            assert_eq!(found[0]["url"], "https://rust-lang.org");
+            assert_eq!(found[0]["tags"], ",lang,systems,");
+
+            assert_eq!(
+                bukurs_update(
+                    db,
+                    1,
+                    ptr::null(),
+                    ptr::null(),
+                    c("-lang").as_ptr(),
+                    ptr::null()
+                ),
+                0
+            );
+            let bookmark: serde_json::Value =
+                serde_json::from_str(&take(bukurs_get(db, 1))).unwrap();
+            assert_eq!(bookmark["tags"], ",systems,");
+            assert_eq!(take(bukurs_tags(db)), r#"["systems"]"#);
+
+            assert_eq!(bukurs_delete(db, 1), 0);
+            assert_eq!(take(bukurs_list(db)), "[]");
+            assert_eq!(bukurs_undo(db), 1);
+            assert_eq!(take(bukurs_list(db)).matches("\"id\"").count(), 1);
+
+            bukurs_close(db);
+        }
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        unsafe {
+            let db = bukurs_open(c(":memory:").as_ptr());
+
+            assert!(bukurs_get(db, 42).is_null());
+            assert!(last_error().contains("42"));
+            assert_eq!(bukurs_delete(db, 42), -1);
+
+            assert_eq!(
+                bukurs_add(db, ptr::null(), ptr::null(), ptr::null(), ptr::null()),
+                -1
+            );
+            assert!(last_error().contains("url"));
+
+            assert!(bukurs_list(ptr::null()).is_null());
+            assert!(last_error().contains("null"));
+
+            bukurs_close(db);
+            bukurs_close(ptr::null_mut());
+            bukurs_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(bukurs_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(bukurs_abi_version(), BUKURS_ABI_VERSION);
+    }
+}
@@ -0,0 +1,227 @@
+//! Async wrappers around the blocking database and fetch APIs
+//!
+//! SQLite and the metadata fetcher block, so every call here runs on tokio's
+//! blocking thread pool. They must be awaited from within a tokio runtime.
+
+use crate::db::{BukuDb, BukuDbPool, ConnectionOptions};
+use crate::error::{BukursError, Result};
+use crate::fetch::{FetchOptions, FetchResult};
+use crate::models::bookmark::Bookmark;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::task::JoinError;
+
+/// Connections opened by [`AsyncBukuDb::init`]
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+/// Run blocking work off the async executor
+async fn blocking<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err(join_error(e)))
+}
+
+fn join_error(e: JoinError) -> BukursError {
+    if e.is_panic() {
+        // Surface the panic to the caller as if the work ran inline
+        std::panic::resume_unwind(e.into_panic());
+    }
+    BukursError::Other(format!("Background task cancelled: {}", e))
+}
+
+/// Async handle to a bookmark database, cheap to clone and share between tasks
+#[derive(Clone)]
+pub struct AsyncBukuDb {
+    pool: Arc<BukuDbPool>,
+}
+
+impl AsyncBukuDb {
+    /// Open or create the database at `db_path`
+    pub async fn init(db_path: impl Into<PathBuf>, options: ConnectionOptions) -> Result<Self> {
+        let db_path = db_path.into();
+        let pool =
+            blocking(move || Ok(BukuDbPool::new(&db_path, &options, DEFAULT_POOL_SIZE)?)).await?;
+        Ok(Self::from_pool(pool))
+    }
+
+    /// Wrap an existing connection pool
+    pub fn from_pool(pool: BukuDbPool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// The underlying pool, for sync access
+    pub fn pool(&self) -> &BukuDbPool {
+        &self.pool
+    }
+
+    /// Run `f` with a pooled connection on the blocking thread pool
+    ///
+    /// Gives access to the whole sync `BukuDb` API for operations without a
+    /// dedicated async wrapper.
+    pub async fn call<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&BukuDb) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = Arc::clone(&self.pool);
+        blocking(move || {
+            let db = pool.get()?;
+            f(&db)
+        })
+        .await
+    }
+
+    pub async fn add_rec(
+        &self,
+        url: String,
+        title: String,
+        tags: String,
+        desc: String,
+        parent_id: Option<usize>,
+    ) -> Result<usize> {
+        self.call(move |db| Ok(db.add_rec(&url, &title, &tags, &desc, parent_id)?))
+            .await
+    }
+
+    pub async fn get_rec_by_id(&self, id: usize) -> Result<Option<Bookmark>> {
+        self.call(move |db| Ok(db.get_rec_by_id(id)?)).await
+    }
+
+    pub async fn get_rec_all(&self) -> Result<Vec<Bookmark>> {
+        self.call(|db| Ok(db.get_rec_all()?)).await
+    }
+
+    pub async fn search(
+        &self,
+        keywords: Vec<String>,
+        any: bool,
+        regex: bool,
+    ) -> Result<Vec<Bookmark>> {
+        self.call(move |db| Ok(db.search(&keywords, any, false, regex)?))
+            .await
+    }
+
+    pub async fn delete_rec(&self, id: usize) -> Result<()> {
+        self.call(move |db| Ok(db.delete_rec(id)?)).await
+    }
+
+    pub async fn undo_last(&self) -> Result<Option<(String, usize)>> {
+        self.call(|db| Ok(db.undo_last()?)).await
+    }
+}
+
+/// Fetch metadata for a URL without blocking the executor
+pub async fn fetch_data(url: String, options: FetchOptions) -> Result<FetchResult> {
+    blocking(move || crate::fetch::fetch_data(&url, &options)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_async_crud() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+
+        runtime().block_on(async {
+            let db = AsyncBukuDb::init(db_path, ConnectionOptions::default())
+                .await
+                .unwrap();
+            let id = db
+                .add_rec(
+                    "https://example.com".to_string(),
+                    "Example".to_string(),
+                    ",test,".to_string(),
+                    String::new(),
+                    None,
+                )
+                .await
+                .unwrap();
+
+            let rec = db.get_rec_by_id(id).await.unwrap().unwrap();
+            assert_eq!(rec.title, "Example");
+
+            let found = db
+                .search(vec!["example".to_string()], true, false)
+                .await
+                .unwrap();
+            assert_eq!(found.len(), 1);
+
+            db.delete_rec(id).await.unwrap();
+            assert!(db.get_rec_all().await.unwrap().is_empty());
+
+            db.undo_last().await.unwrap();
+            assert_eq!(db.get_rec_all().await.unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_async_concurrent_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+
+        runtime().block_on(async {
+            let db = AsyncBukuDb::init(db_path, ConnectionOptions::default())
+                .await
+                .unwrap();
+            let tasks: Vec<_> = (0..16)
+                .map(|i| {
+                    let db = db.clone();
+                    tokio::spawn(async move {
+                        db.add_rec(
+                            format!("https://{}.com", i),
+                            String::new(),
+                            ",".to_string(),
+                            String::new(),
+                            None,
+                        )
+                        .await
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap().unwrap();
+            }
+            assert_eq!(db.get_rec_all().await.unwrap().len(), 16);
+        });
+    }
+
+    #[test]
+    fn test_async_call_error() {
+        runtime().block_on(async {
+            let db =
+                AsyncBukuDb::from_pool(BukuDbPool::from_db(BukuDb::init_in_memory().unwrap(), 1));
+            let result: Result<()> = db
+                .call(|_| Err(BukursError::InvalidInput("nope".to_string())))
+                .await;
+            assert!(matches!(result, Err(BukursError::InvalidInput(_))));
+        });
+    }
+
+    #[test]
+    fn test_async_fetch_error() {
+        runtime().block_on(async {
+            let options = FetchOptions {
+                timeout_secs: 2,
+                ..FetchOptions::default()
+            };
+            // Nothing listens on the discard port
+            let result = fetch_data("http://127.0.0.1:9/".to_string(), options).await;
+            assert!(result.is_err());
+        });
+    }
+}
@@ -0,0 +1,279 @@
+//! Audit trail of mutating operations
+//!
+//! When enabled (`audit_log` in the config), every change to the bookmarks
+//! table appends one JSON line with when it happened, what kind of change it
+//! was, which bookmarks it touched and where it came from. Unlike the undo log,
+//! entries are never removed, and undos are recorded too.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Kind of change recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    Add,
+    Update,
+    Delete,
+    Import,
+    Fetch,
+    Undo,
+}
+
+impl AuditOperation {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "add" => Some(Self::Add),
+            "update" => Some(Self::Update),
+            "delete" => Some(Self::Delete),
+            "import" => Some(Self::Import),
+            "fetch" => Some(Self::Fetch),
+            "undo" => Some(Self::Undo),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuditOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "add",
+            Self::Update => "update",
+            Self::Delete => "delete",
+            Self::Import => "import",
+            Self::Fetch => "fetch",
+            Self::Undo => "undo",
+        })
+    }
+}
+
+/// Frontend that made a change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSource {
+    #[default]
+    Cli,
+    Shell,
+    Server,
+    Plugin,
+    /// The Python bindings
+    Python,
+    /// The C library (`bukurs-ffi`)
+    Ffi,
+}
+
+impl AuditSource {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "cli" => Some(Self::Cli),
+            "shell" => Some(Self::Shell),
+            "server" => Some(Self::Server),
+            "plugin" => Some(Self::Plugin),
+            "python" => Some(Self::Python),
+            "ffi" => Some(Self::Ffi),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuditSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Cli => "cli",
+            Self::Shell => "shell",
+            Self::Server => "server",
+            Self::Plugin => "plugin",
+            Self::Python => "python",
+            Self::Ffi => "ffi",
+        })
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix seconds
+    pub timestamp: i64,
+    pub operation: AuditOperation,
+    /// Bookmarks the operation touched
+    pub ids: Vec<usize>,
+    pub source: AuditSource,
+}
+
+/// Criteria for `audit search`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub operation: Option<AuditOperation>,
+    pub id: Option<usize>,
+    pub source: Option<AuditSource>,
+    /// Only entries at or after this Unix timestamp
+    pub since: Option<i64>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.operation.is_none_or(|op| entry.operation == op)
+            && self.id.is_none_or(|id| entry.ids.contains(&id))
+            && self.source.is_none_or(|source| entry.source == source)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+    }
+}
+
+/// Append-only JSON lines file of [`AuditEntry`]s
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    source: AuditSource,
+}
+
+impl AuditLog {
+    /// Log to `path`, attributing every entry to `source`
+    pub fn new(path: impl Into<PathBuf>, source: AuditSource) -> Self {
+        Self {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry for an operation on `ids`
+    pub fn record(&self, operation: AuditOperation, ids: &[usize]) -> Result<()> {
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+            operation,
+            ids: ids.to_vec(),
+            source: self.source,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        // One write per entry keeps concurrent appenders from interleaving lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Read every entry, oldest first; a missing file is an empty log
+    ///
+    /// Lines that don't parse (e.g. a partial write) are skipped.
+    pub fn read(path: &Path) -> Result<Vec<AuditEntry>> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("Skipping audit log line {}: {}", number + 1, e),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn entry(operation: AuditOperation, ids: &[usize], source: AuditSource) -> AuditEntry {
+        AuditEntry {
+            timestamp: 1_700_000_000,
+            operation,
+            ids: ids.to_vec(),
+            source,
+        }
+    }
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("audit.jsonl");
+        let log = AuditLog::new(&path, AuditSource::Shell);
+
+        log.record(AuditOperation::Add, &[1]).unwrap();
+        log.record(AuditOperation::Delete, &[2, 3]).unwrap();
+
+        let entries = AuditLog::read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, AuditOperation::Add);
+        assert_eq!(entries[1].ids, vec![2, 3]);
+        assert!(entries.iter().all(|e| e.source == AuditSource::Shell));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"operation\":\"add\""));
+    }
+
+    #[test]
+    fn test_read_missing_and_malformed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        assert!(AuditLog::read(&path).unwrap().is_empty());
+
+        fs::write(
+            &path,
+            "{\"timestamp\":1,\"operation\":\"undo\",\"ids\":[4],\"source\":\"cli\"}\n\
+             {\"timestamp\":2,\"oper\n\n",
+        )
+        .unwrap();
+        let entries = AuditLog::read(&path).unwrap();
+        assert_eq!(
+            entries,
+            vec![AuditEntry {
+                timestamp: 1,
+                operation: AuditOperation::Undo,
+                ids: vec![4],
+                source: AuditSource::Cli,
+            }]
+        );
+    }
+
+    #[rstest]
+    #[case(AuditFilter::default(), true)]
+    #[case(AuditFilter { operation: Some(AuditOperation::Update), ..Default::default() }, true)]
+    #[case(AuditFilter { operation: Some(AuditOperation::Add), ..Default::default() }, false)]
+    #[case(AuditFilter { id: Some(5), ..Default::default() }, true)]
+    #[case(AuditFilter { id: Some(6), ..Default::default() }, false)]
+    #[case(AuditFilter { source: Some(AuditSource::Cli), ..Default::default() }, false)]
+    #[case(AuditFilter { since: Some(1_700_000_000), ..Default::default() }, true)]
+    #[case(AuditFilter { since: Some(1_700_000_001), ..Default::default() }, false)]
+    fn test_filter(#[case] filter: AuditFilter, #[case] expected: bool) {
+        let entry = entry(AuditOperation::Update, &[4, 5], AuditSource::Server);
+        assert_eq!(filter.matches(&entry), expected);
+    }
+
+    #[rstest]
+    #[case("ADD", Some(AuditOperation::Add))]
+    #[case("fetch", Some(AuditOperation::Fetch))]
+    #[case("rename", None)]
+    fn test_operation_from_string(#[case] s: &str, #[case] expected: Option<AuditOperation>) {
+        assert_eq!(AuditOperation::from_string(s), expected);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::models::bookmark::Bookmark;
+use crate::tags::{format_tags, parse_tags};
+use crate::utils::{domain_matches, url_domain};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Per-domain automatic tag rules, configured as `domain: tags`
+///
+/// ```yaml
+/// autotag:
+///   github.com: code,github
+///   news.ycombinator.com: news
+/// ```
+///
+/// A rule matches its domain and every subdomain of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AutotagRules(BTreeMap<String, String>);
+
+impl AutotagRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule tagging bookmarks on `domain` with comma-separated `tags`
+    pub fn insert(&mut self, domain: &str, tags: &str) {
+        self.0.insert(domain.to_string(), tags.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Tags of every rule matching the URL's domain, in rule order without duplicates
+    pub fn tags_for(&self, url: &str) -> Vec<String> {
+        let Some(domain) = url_domain(url) else {
+            return Vec::new();
+        };
+
+        let mut tags: Vec<String> = Vec::new();
+        for (rule, rule_tags) in &self.0 {
+            if domain_matches(&domain, rule) {
+                for tag in parse_tags(rule_tags) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+        }
+        tags
+    }
+
+    /// Merge the tags of matching rules into a stored `,a,b,` tag string
+    /// Returns None if no rule adds a tag the bookmark doesn't already have
+    pub fn apply(&self, url: &str, tags: &str) -> Option<String> {
+        let mut merged = parse_tags(tags);
+        let before = merged.len();
+        for tag in self.tags_for(url) {
+            if !merged.contains(&tag) {
+                merged.push(tag);
+            }
+        }
+        (merged.len() > before).then(|| format_tags(&merged))
+    }
+
+    /// Bookmarks whose tags the rules would change, with the new tags applied
+    pub fn retag(&self, bookmarks: &[Bookmark]) -> Vec<Bookmark> {
+        bookmarks
+            .iter()
+            .filter_map(|bookmark| {
+                let tags = self.apply(&bookmark.url, &bookmark.tags)?;
+                Some(Bookmark {
+                    tags,
+                    ..bookmark.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn rules() -> AutotagRules {
+        let mut rules = AutotagRules::new();
+        rules.insert("github.com", "code,github");
+        rules.insert("rust-lang.org", "rust");
+        rules
+    }
+
+    #[rstest]
+    #[case("https://github.com/rust-lang/rust", vec!["code", "github"])]
+    #[case("https://www.GitHub.com/", vec!["code", "github"])]
+    #[case("https://gist.github.com/abc", vec!["code", "github"])]
+    #[case("https://notgithub.com/", vec![])]
+    #[case("https://blog.rust-lang.org/", vec!["rust"])]
+    #[case("not a url", vec![])]
+    fn test_tags_for(#[case] url: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(rules().tags_for(url), expected);
+    }
+
+    #[rstest]
+    #[case(",", Some(",code,github,"))]
+    #[case(",code,", Some(",code,github,"))]
+    #[case(",github,code,", None)]
+    fn test_apply(#[case] tags: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            rules().apply("https://github.com/x", tags).as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_retag_returns_only_changed() {
+        let bookmarks = vec![
+            Bookmark::new(
+                1,
+                "https://github.com/a".into(),
+                "".into(),
+                ",".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                2,
+                "https://github.com/b".into(),
+                "".into(),
+                ",code,github,".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                3,
+                "https://example.com".into(),
+                "".into(),
+                ",".into(),
+                "".into(),
+            ),
+        ];
+        let changed = rules().retag(&bookmarks);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, 1);
+        assert_eq!(changed[0].tags, ",code,github,");
+    }
+
+    #[test]
+    fn test_rules_from_yaml() {
+        let rules: AutotagRules =
+            serde_yaml::from_str("github.com: code,github\nrust-lang.org: rust\n").unwrap();
+        assert_eq!(rules, self::rules());
+    }
+}
@@ -0,0 +1,147 @@
+//! `bukurs://` links, so a browser bookmarklet or an OS URL handler can add pages
+//!
+//! A link looks like `bukurs://add?url=...&title=...&tags=a,b&desc=...&later=1`,
+//! with query values percent-encoded. Only `url` is required; missing fields are
+//! filled from the page's metadata as with `add`.
+
+use crate::error::{BukursError, Result};
+use crate::service::AddRequest;
+use crate::tags::parse_tags;
+use crate::utils::percent_decode;
+
+/// URL scheme handled by `add-url`
+pub const SCHEME: &str = "bukurs";
+
+/// Parse a `bukurs://add?...` link into the bookmark it asks to add
+pub fn parse_add_url(link: &str) -> Result<AddRequest> {
+    let invalid = |msg: &str| BukursError::InvalidInput(format!("{}: {}", msg, link));
+
+    let rest = link
+        .trim()
+        .strip_prefix(SCHEME)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or_else(|| invalid("Not a bukurs:// link"))?;
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if action.trim_matches('/') != "add" {
+        return Err(invalid("Unsupported bukurs:// action (expected add)"));
+    }
+
+    let mut request = AddRequest::default();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        // Form encoding writes spaces as '+'
+        let value = percent_decode(&value.replace('+', " "));
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match key {
+            "url" => request.url = value.to_string(),
+            "title" => request.title = Some(value.to_string()),
+            "tags" => request.tags = Some(parse_tags(value)),
+            "desc" | "description" | "comment" => request.desc = Some(value.to_string()),
+            "later" => request.later = matches!(value, "1" | "true" | "yes"),
+            _ => log::debug!("Ignoring unknown bukurs:// parameter '{}'", key),
+        }
+    }
+
+    if request.url.is_empty() {
+        return Err(invalid("Missing url parameter in link"));
+    }
+    Ok(request)
+}
+
+/// JavaScript bookmarklet that sends the current page to bukurs
+///
+/// The page's URL, title and selected text (as the description) are passed
+/// along; `tags` are added to every bookmark saved with it.
+pub fn bookmarklet(tags: Option<&str>) -> String {
+    let mut js = format!(
+        "javascript:(function(){{location.href='{}://add?url='+encodeURIComponent(location.href)\
+         +'&title='+encodeURIComponent(document.title)\
+         +'&desc='+encodeURIComponent(String(window.getSelection()))",
+        SCHEME
+    );
+    if let Some(tags) = tags.map(str::trim).filter(|t| !t.is_empty()) {
+        js.push_str(&format!(
+            "+'&tags='+encodeURIComponent('{}')",
+            escape_js(tags)
+        ));
+    }
+    js.push_str(";})();");
+    js
+}
+
+/// Escape text for a single-quoted JavaScript string
+fn escape_js(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            // Keep the bookmarklet from closing a surrounding <script> or href
+            '<' => out.push_str("\\x3c"),
+            '"' => out.push_str("\\x22"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_parse_add_url_all_fields() {
+        let request = parse_add_url(
+            "bukurs://add?url=https%3A%2F%2Fexample.com%2Fa%3Fb%3D1&title=An+Example%20Page\
+             &tags=rust,web&desc=Some%20notes&later=1",
+        )
+        .unwrap();
+        assert_eq!(request.url, "https://example.com/a?b=1");
+        assert_eq!(request.title.as_deref(), Some("An Example Page"));
+        assert_eq!(
+            request.tags,
+            Some(vec!["rust".to_string(), "web".to_string()])
+        );
+        assert_eq!(request.desc.as_deref(), Some("Some notes"));
+        assert!(request.later);
+    }
+
+    #[rstest]
+    #[case("bukurs://add?url=https://a.com")]
+    #[case("bukurs:add?url=https://a.com")]
+    #[case("bukurs:///add/?url=https://a.com&title=&tags=&x=y")]
+    fn test_parse_add_url_minimal(#[case] link: &str) {
+        let request = parse_add_url(link).unwrap();
+        assert_eq!(request.url, "https://a.com");
+        assert_eq!(request.title, None);
+        assert_eq!(request.tags, None);
+        assert!(!request.later);
+    }
+
+    #[rstest]
+    #[case("https://a.com")]
+    #[case("bukurs://delete?url=https://a.com")]
+    #[case("bukurs://add?title=No+URL")]
+    #[case("bukurs://add")]
+    fn test_parse_add_url_errors(#[case] link: &str) {
+        assert!(parse_add_url(link).is_err());
+    }
+
+    #[test]
+    fn test_bookmarklet() {
+        let js = bookmarklet(None);
+        assert!(js.starts_with("javascript:"));
+        assert!(js.contains("bukurs://add?url='+encodeURIComponent(location.href)"));
+        assert!(!js.contains("&tags="));
+
+        let js = bookmarklet(Some("it's,<b>"));
+        assert!(js.contains("+'&tags='+encodeURIComponent('it\\'s,\\x3cb>')"));
+        assert!(js.ends_with(";})();"));
+    }
+}
@@ -1,4 +1,40 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
 pub fn open_url(url: &str) -> crate::error::Result<()> {
     open::that(url)?;
     Ok(())
 }
+
+/// Clipboard commands tried in order, each reading the text on stdin
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+/// Put `text` on the system clipboard with the first clipboard command available
+pub fn copy_to_clipboard(text: &str) -> crate::error::Result<()> {
+    for command in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(crate::error::BukursError::Browser(
+        "No clipboard command found (install wl-copy, xclip or xsel)".to_string(),
+    ))
+}
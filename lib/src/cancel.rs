@@ -0,0 +1,55 @@
+//! Cooperative cancellation for long-running operations
+//!
+//! Parallel imports and metadata refreshes check a [`CancellationToken`]
+//! between units of work. Work already committed is kept; a parallel import
+//! rolls back the chunk it was writing. The CLI cancels the token on Ctrl-C.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag asking an operation to stop early
+///
+/// Clones share the flag, so one can be handed to a signal handler while
+/// another is passed to the operation.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+
+    /// The underlying flag, for APIs that set an `AtomicBool` (e.g. signal handlers)
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+
+        let other = CancellationToken::new();
+        other.flag().store(true, Ordering::SeqCst);
+        assert!(other.is_cancelled());
+    }
+}
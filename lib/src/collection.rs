@@ -0,0 +1,460 @@
+//! Smart collections: named filters evaluated whenever they are used
+//!
+//! A collection is a query kept under a name, like a virtual folder whose
+//! contents follow the bookmarks: `print --collection reading` and
+//! `export --collection reading` show whatever matches it at the time.
+//!
+//! Queries combine terms with `AND`, `OR`, `NOT` and parentheses; terms next
+//! to each other without an operator must both match, and `AND` binds tighter
+//! than `OR`. A term is one of
+//!
+//! - `tag:NAME`: bookmarks carrying the tag (case-insensitively)
+//! - `url:TEXT`, `title:TEXT`, `desc:TEXT`: the field contains the text
+//! - `saved:NAME`: bookmarks the saved search of that name finds
+//! - any other word: the URL, title, tags or description contains it
+//!
+//! Double quotes keep spaces and operator words in a term:
+//! `tag:toread AND NOT (tag:done OR title:"on hold")`.
+
+#[cfg(feature = "full")]
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+#[cfg(feature = "full")]
+use crate::search::KeywordMatch;
+#[cfg(feature = "full")]
+use crate::service::{BookmarkService, SearchQuery};
+use crate::tags::parse_tags;
+use std::collections::{HashMap, HashSet};
+
+/// A query kept under a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collection {
+    pub name: String,
+    pub query: String,
+}
+
+/// A parsed collection query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectionExpr {
+    Tag(String),
+    Url(String),
+    Title(String),
+    Desc(String),
+    Saved(String),
+    /// Anywhere in the URL, title, tags or description
+    Text(String),
+    Not(Box<CollectionExpr>),
+    And(Box<CollectionExpr>, Box<CollectionExpr>),
+    Or(Box<CollectionExpr>, Box<CollectionExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+fn invalid(query: &str, reason: &str) -> BukursError {
+    BukursError::InvalidInput(format!("Invalid collection query '{}': {}", query, reason))
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut term = String::new();
+                let mut quoted = false;
+                let mut had_quotes = false;
+                while let Some(&c) = chars.peek() {
+                    if c == '"' {
+                        quoted = !quoted;
+                        had_quotes = true;
+                    } else if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    } else {
+                        term.push(c);
+                    }
+                    chars.next();
+                }
+                if quoted {
+                    return Err(invalid(query, "unterminated quote"));
+                }
+                tokens.push(match term.as_str() {
+                    "AND" if !had_quotes => Token::And,
+                    "OR" if !had_quotes => Token::Or,
+                    "NOT" if !had_quotes => Token::Not,
+                    _ => Token::Term(term),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<CollectionExpr> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = CollectionExpr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<CollectionExpr> {
+        let mut left = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                // Adjacent terms
+                Some(Token::Term(_) | Token::Open | Token::Not) => {}
+                _ => return Ok(left),
+            }
+            left = CollectionExpr::And(Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<CollectionExpr> {
+        match self.advance() {
+            Some(Token::Not) => Ok(CollectionExpr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.advance() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(invalid(self.query, "missing ')'")),
+                }
+            }
+            Some(Token::Term(term)) => term_expr(self.query, &term),
+            Some(Token::Close) => Err(invalid(self.query, "unexpected ')'")),
+            Some(Token::And) => Err(invalid(self.query, "AND needs a term before it")),
+            Some(Token::Or) => Err(invalid(self.query, "OR needs a term before it")),
+            None => Err(invalid(self.query, "a term is missing at the end")),
+        }
+    }
+}
+
+fn term_expr(query: &str, term: &str) -> Result<CollectionExpr> {
+    if term.is_empty() {
+        return Err(invalid(query, "empty term"));
+    }
+    let field = term.split_once(':').and_then(|(field, value)| {
+        let expr: fn(String) -> CollectionExpr = match field {
+            "tag" => CollectionExpr::Tag,
+            "url" => CollectionExpr::Url,
+            "title" => CollectionExpr::Title,
+            "desc" => CollectionExpr::Desc,
+            "saved" => CollectionExpr::Saved,
+            _ => return None,
+        };
+        Some((field, value, expr))
+    });
+    match field {
+        Some((field, "", _)) => Err(invalid(query, &format!("'{}:' needs a value", field))),
+        Some((_, value, expr)) => Ok(expr(value.to_string())),
+        None => Ok(CollectionExpr::Text(term.to_string())),
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl CollectionExpr {
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut parser = Parser {
+            query,
+            tokens: tokenize(query)?,
+            pos: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(invalid(query, "it is empty"));
+        }
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(invalid(query, "unexpected ')'"));
+        }
+        Ok(expr)
+    }
+
+    /// Names of the saved searches the query refers to, without repeats
+    pub fn saved_searches(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_saved(&mut names);
+        names
+    }
+
+    fn collect_saved<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Saved(name) if !names.contains(&name.as_str()) => names.push(name),
+            Self::Not(expr) => expr.collect_saved(names),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.collect_saved(names);
+                right.collect_saved(names);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `bookmark` belongs to the collection
+    ///
+    /// `saved` holds the IDs each saved search in [`Self::saved_searches`]
+    /// finds; a saved search missing from it matches nothing.
+    pub fn matches(&self, bookmark: &Bookmark, saved: &HashMap<String, HashSet<usize>>) -> bool {
+        match self {
+            Self::Tag(tag) => parse_tags(&bookmark.tags)
+                .iter()
+                .any(|t| t.to_lowercase() == tag.to_lowercase()),
+            Self::Url(text) => contains(&bookmark.url, text),
+            Self::Title(text) => contains(&bookmark.title, text),
+            Self::Desc(text) => contains(&bookmark.description, text),
+            Self::Saved(name) => saved
+                .get(name)
+                .is_some_and(|ids| ids.contains(&bookmark.id)),
+            Self::Text(text) => [
+                &bookmark.url,
+                &bookmark.title,
+                &bookmark.tags,
+                &bookmark.description,
+            ]
+            .iter()
+            .any(|field| contains(field, text)),
+            Self::Not(expr) => !expr.matches(bookmark, saved),
+            Self::And(left, right) => {
+                left.matches(bookmark, saved) && right.matches(bookmark, saved)
+            }
+            Self::Or(left, right) => {
+                left.matches(bookmark, saved) || right.matches(bookmark, saved)
+            }
+        }
+    }
+}
+
+/// The bookmarks matching `expr` now, in index order
+#[cfg(feature = "full")]
+pub fn evaluate(db: &BukuDb, expr: &CollectionExpr) -> Result<Vec<Bookmark>> {
+    let mut saved = HashMap::new();
+    for name in expr.saved_searches() {
+        let search = db.get_saved_search(name)?.ok_or_else(|| {
+            BukursError::InvalidInput(format!("No saved search called '{}'", name))
+        })?;
+        let query = SearchQuery {
+            keywords: search.keywords,
+            all: search.all,
+            regex: search.regex,
+            notes: search.notes,
+            min_rating: search.min_rating,
+            lang: search.lang,
+            time: search.time,
+            keyword_match: if search.fuzzy {
+                KeywordMatch::Fuzzy
+            } else {
+                KeywordMatch::Exact
+            },
+            ..SearchQuery::default()
+        };
+        // Searching doesn't fetch, so the fetch options don't matter
+        let ids = BookmarkService::new(db, Default::default())
+            .search_scored(&query)?
+            .into_iter()
+            .map(|result| result.bookmark.id)
+            .collect();
+        saved.insert(name.to_string(), ids);
+    }
+    Ok(db
+        .get_rec_all()?
+        .into_iter()
+        .filter(|bookmark| expr.matches(bookmark, &saved))
+        .collect())
+}
+
+/// The bookmarks in the collection called `name` now, in index order
+#[cfg(feature = "full")]
+pub fn bookmarks(db: &BukuDb, name: &str) -> Result<Vec<Bookmark>> {
+    let collection = db
+        .get_collection(name)?
+        .ok_or_else(|| BukursError::InvalidInput(format!("No collection called '{}'", name)))?;
+    evaluate(db, &CollectionExpr::parse(&collection.query)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn tag(name: &str) -> CollectionExpr {
+        CollectionExpr::Tag(name.to_string())
+    }
+
+    fn not(expr: CollectionExpr) -> CollectionExpr {
+        CollectionExpr::Not(Box::new(expr))
+    }
+
+    fn and(left: CollectionExpr, right: CollectionExpr) -> CollectionExpr {
+        CollectionExpr::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: CollectionExpr, right: CollectionExpr) -> CollectionExpr {
+        CollectionExpr::Or(Box::new(left), Box::new(right))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            CollectionExpr::parse("tag:toread AND NOT tag:done").unwrap(),
+            and(tag("toread"), not(tag("done")))
+        );
+        // AND binds tighter than OR; adjacent terms are ANDed
+        assert_eq!(
+            CollectionExpr::parse("tag:a OR tag:b tag:c").unwrap(),
+            or(tag("a"), and(tag("b"), tag("c")))
+        );
+        assert_eq!(
+            CollectionExpr::parse("(tag:a OR tag:b) AND rust").unwrap(),
+            and(
+                or(tag("a"), tag("b")),
+                CollectionExpr::Text("rust".to_string())
+            )
+        );
+        assert_eq!(
+            CollectionExpr::parse(r#"title:"on hold" "OR" saved:weekly"#).unwrap(),
+            and(
+                and(
+                    CollectionExpr::Title("on hold".to_string()),
+                    CollectionExpr::Text("OR".to_string())
+                ),
+                CollectionExpr::Saved("weekly".to_string())
+            )
+        );
+        // Not a field, so a plain word
+        assert_eq!(
+            CollectionExpr::parse("https://example.com").unwrap(),
+            CollectionExpr::Text("https://example.com".to_string())
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("tag:a AND")]
+    #[case("OR tag:a")]
+    #[case("(tag:a")]
+    #[case("tag:a)")]
+    #[case("tag:")]
+    #[case(r#"title:"open"#)]
+    fn test_parse_rejects(#[case] query: &str) {
+        assert!(CollectionExpr::parse(query).is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let bookmark = Bookmark::new(
+            7,
+            "https://blog.rust-lang.org".to_string(),
+            "Async Rust".to_string(),
+            ",ToRead,rust,".to_string(),
+            "Long read".to_string(),
+        );
+        let saved = HashMap::from([("weekly".to_string(), HashSet::from([7]))]);
+        let matches = |query: &str| {
+            CollectionExpr::parse(query)
+                .unwrap()
+                .matches(&bookmark, &saved)
+        };
+
+        assert!(matches("tag:toread AND NOT tag:done"));
+        assert!(!matches("tag:read"));
+        assert!(matches("url:RUST-LANG title:async desc:long"));
+        assert!(matches("toread"));
+        assert!(matches("saved:weekly"));
+        assert!(!matches("saved:other"));
+        assert!(matches("tag:done OR (tag:rust AND NOT url:example)"));
+    }
+
+    #[test]
+    fn test_saved_searches() {
+        let expr = CollectionExpr::parse("saved:a OR (saved:b AND NOT saved:a)").unwrap();
+        assert_eq!(expr.saved_searches(), vec!["a", "b"]);
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn test_bookmarks() {
+        use crate::search::SavedSearch;
+
+        let db = BukuDb::init_in_memory().unwrap();
+        let toread = db
+            .add_rec("https://a.example", "Rust tips", ",toread,", "", None)
+            .unwrap();
+        db.add_rec("https://b.example", "Done", ",toread,done,", "", None)
+            .unwrap();
+        let other = db
+            .add_rec("https://c.example", "Rust news", ",", "", None)
+            .unwrap();
+
+        db.save_collection(&Collection {
+            name: "reading".to_string(),
+            query: "tag:toread AND NOT tag:done".to_string(),
+        })
+        .unwrap();
+        let ids = |name: &str| -> Vec<usize> {
+            bookmarks(&db, name).unwrap().iter().map(|b| b.id).collect()
+        };
+        assert_eq!(ids("reading"), vec![toread]);
+
+        // Evaluated live: a new bookmark joins without touching the collection
+        let later = db
+            .add_rec("https://d.example", "Later", ",toread,", "", None)
+            .unwrap();
+        assert_eq!(ids("reading"), vec![toread, later]);
+
+        db.save_search(&SavedSearch {
+            name: "rust".to_string(),
+            keywords: vec!["rust".to_string()],
+            ..SavedSearch::default()
+        })
+        .unwrap();
+        db.save_collection(&Collection {
+            name: "rust-unread".to_string(),
+            query: "saved:rust NOT tag:toread".to_string(),
+        })
+        .unwrap();
+        assert_eq!(ids("rust-unread"), vec![other]);
+
+        assert!(bookmarks(&db, "missing").is_err());
+        let dangling = CollectionExpr::parse("saved:missing").unwrap();
+        assert!(evaluate(&db, &dangling).is_err());
+    }
+}
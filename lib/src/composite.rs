@@ -0,0 +1,173 @@
+//! A personal database with a read-only shared database layered under it
+//!
+//! A team can keep a bookmark file on a network drive or in a repository and
+//! have every member search it alongside their own bookmarks. The shared file
+//! is opened read-only ([`BukuDb::open_read_only`]), so it is never migrated
+//! or written to; every change goes to the personal database.
+
+use crate::db::{BukuDb, ConnectionOptions};
+use crate::error::{BukursError, Result};
+use crate::fetch::FetchOptions;
+use crate::search::ScoredBookmark;
+use crate::service::{BookmarkService, SearchQuery};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// Which database of a [`CompositeDb`] a bookmark comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Personal,
+    Shared,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Personal => "personal",
+            Self::Shared => "shared",
+        })
+    }
+}
+
+/// A search result with the database it was found in
+///
+/// IDs are those of that database; a shared bookmark's ID means nothing in
+/// the personal one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcedBookmark {
+    #[serde(flatten)]
+    pub result: ScoredBookmark,
+    pub source: Source,
+}
+
+/// A personal database with a read-only shared one layered under it
+pub struct CompositeDb<'a> {
+    personal: &'a BukuDb,
+    shared: BukuDb,
+}
+
+impl<'a> CompositeDb<'a> {
+    pub fn new(personal: &'a BukuDb, shared: BukuDb) -> Self {
+        Self { personal, shared }
+    }
+
+    /// Layer the database at `shared` under `personal`, opened read-only
+    ///
+    /// The shared file is expected to be plaintext: the personal database's
+    /// SQLCipher key isn't used for it.
+    pub fn open(personal: &'a BukuDb, shared: &Path, options: &ConnectionOptions) -> Result<Self> {
+        let options = ConnectionOptions {
+            key: None,
+            ..options.clone()
+        };
+        let shared_db = BukuDb::open_read_only(shared, &options).map_err(|e| {
+            BukursError::Config(format!("shared database {}: {}", shared.display(), e))
+        })?;
+        Ok(Self::new(personal, shared_db))
+    }
+
+    /// The database every change goes to
+    pub fn personal(&self) -> &'a BukuDb {
+        self.personal
+    }
+
+    /// The read-only shared database
+    pub fn shared(&self) -> &BukuDb {
+        &self.shared
+    }
+
+    /// Run `query` on both databases: the personal results, then the shared
+    /// ones whose URL the personal database doesn't have
+    ///
+    /// Each database is searched, ranked and limited on its own, since scores
+    /// and IDs don't carry over between them. `query.within` holds personal
+    /// IDs, so it narrows the personal results only.
+    pub fn search_scored(
+        &self,
+        query: &SearchQuery,
+        fetch: &FetchOptions,
+    ) -> Result<Vec<SourcedBookmark>> {
+        let personal = BookmarkService::new(self.personal, fetch.clone()).search_scored(query)?;
+        let shared_query = SearchQuery {
+            within: None,
+            ..query.clone()
+        };
+        let shared =
+            BookmarkService::new(&self.shared, fetch.clone()).search_scored(&shared_query)?;
+
+        let personal_urls: HashSet<String> = self
+            .personal
+            .get_rec_all()?
+            .into_iter()
+            .map(|b| b.url)
+            .collect();
+        let sourced = |result, source| SourcedBookmark { result, source };
+        Ok(personal
+            .into_iter()
+            .map(|r| sourced(r, Source::Personal))
+            .chain(
+                shared
+                    .into_iter()
+                    .filter(|r| !personal_urls.contains(&r.bookmark.url))
+                    .map(|r| sourced(r, Source::Shared)),
+            )
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_unions_both_sources() {
+        let personal = BukuDb::init_in_memory().unwrap();
+        personal
+            .add_rec("https://docs.rs", "Rust docs", ",rust,", "", None)
+            .unwrap();
+        let shared = BukuDb::init_in_memory().unwrap();
+        shared
+            .add_rec("https://docs.rs", "Docs", ",rust,team,", "", None)
+            .unwrap();
+        shared
+            .add_rec("https://rust-lang.org", "Rust", ",team,", "", None)
+            .unwrap();
+        shared
+            .add_rec("https://python.org", "Python", ",team,", "", None)
+            .unwrap();
+
+        let composite = CompositeDb::new(&personal, shared);
+        let query = SearchQuery {
+            keywords: vec!["rust".to_string()],
+            ..SearchQuery::default()
+        };
+        let results = composite
+            .search_scored(&query, &FetchOptions::default())
+            .unwrap();
+        let found: Vec<(&str, Source)> = results
+            .iter()
+            .map(|r| (r.result.bookmark.url.as_str(), r.source))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("https://docs.rs", Source::Personal),
+                ("https://rust-lang.org", Source::Shared)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_missing_shared_database() {
+        let personal = BukuDb::init_in_memory().unwrap();
+        let result = CompositeDb::open(
+            &personal,
+            Path::new("/nonexistent/shared.db"),
+            &ConnectionOptions::default(),
+        );
+        assert!(matches!(result, Err(BukursError::Config(_))));
+    }
+}
@@ -1,6 +1,15 @@
+use crate::audit::{AuditLog, AuditSource};
+use crate::autotag::AutotagRules;
+use crate::daemon::DaemonConfig;
+use crate::db::{BukuDb, ConnectionOptions};
+use crate::fetch::FetchOptions;
+use crate::fuzzy::PickerOptions;
+use crate::search::SearchOptions;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +21,135 @@ pub struct Config {
     /// Number of threads for parallel bookmark imports
     #[serde(default = "default_import_threads")]
     pub import_threads: usize,
+
+    /// Per-domain tags added automatically to new and imported bookmarks
+    #[serde(default, skip_serializing_if = "AutotagRules::is_empty")]
+    pub autotag: AutotagRules,
+
+    /// Network settings for metadata fetching
+    #[serde(default)]
+    pub fetch: FetchOptions,
+
+    /// Stay offline unless `--online` is given: add queues bookmarks for
+    /// `fetch-pending`, update doesn't refresh and other fetches fail
+    #[serde(default)]
+    pub default_offline: bool,
+
+    /// SQLite connection tuning (journal mode, busy timeout, ...)
+    #[serde(default)]
+    pub database: ConnectionOptions,
+
+    /// Search ranking and column weights
+    #[serde(default)]
+    pub search: SearchOptions,
+
+    /// Bookmark picker: built-in or external, and its keys
+    #[serde(default)]
+    pub picker: PickerOptions,
+
+    /// Interactive shell aliases and macros
+    #[serde(default, skip_serializing_if = "ShellConfig::is_empty")]
+    pub shell: ShellConfig,
+
+    /// Database file, used when `--db` isn't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db: Option<PathBuf>,
+
+    /// Other databases by name, for `--profile` and `bukurs profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, PathBuf>,
+
+    /// Read-only database searched along with this one, e.g. a team file on a
+    /// network drive (see [`crate::composite`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_db: Option<PathBuf>,
+
+    /// Editor command for `edit` and `note`, preferred over $EDITOR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+
+    /// JSON-lines file every change to the bookmarks is appended to (off when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<PathBuf>,
+
+    /// Colored output; `--nc` turns it off for one run
+    #[serde(default = "default_color")]
+    pub color: bool,
+
+    /// Color theme and per-field colors for bookmark listings
+    #[serde(default)]
+    pub colors: ColorConfig,
+
+    /// Named output templates for `-f <name>`, e.g. `tsv: '{id}\t{url}\t{tags}'`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub formats: BTreeMap<String, String>,
+
+    /// Scheduled jobs run by `bukurs daemon`
+    #[serde(default, skip_serializing_if = "DaemonConfig::is_empty")]
+    pub daemon: DaemonConfig,
+}
+
+/// Colors for bookmark listings
+///
+/// Field colors override the theme. A color is a space-separated list of
+/// modifiers (`bold`, `dimmed`, `italic`, `underline`) and at most one color:
+/// a name (`green`, `bright_blue`), `#rrggbb`, an ANSI 256-color number or `none`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorConfig {
+    /// Built-in theme: `default`, `solarized` or `mono`
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    /// The `>`, `+` and `#` markers in front of the url, description and tags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+    /// The stars after a rated bookmark's title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            theme: default_theme(),
+            id: None,
+            url: None,
+            title: None,
+            tags: None,
+            desc: None,
+            highlight: None,
+            rating: None,
+        }
+    }
+}
+
+/// Interactive shell shortcuts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShellConfig {
+    /// Command replacements; arguments typed after an alias are appended
+    /// e.g. `rd: s rust --deep`
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Commands run in sequence, separated by `;`
+    /// e.g. `cleanup: check; dedupe; prune-undo`
+    #[serde(default)]
+    pub macros: BTreeMap<String, String>,
+}
+
+impl ShellConfig {
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.macros.is_empty()
+    }
 }
 
 impl Default for Config {
@@ -19,6 +157,334 @@ impl Default for Config {
         Self {
             user_agent: default_user_agent(),
             import_threads: default_import_threads(),
+            autotag: AutotagRules::default(),
+            fetch: FetchOptions::default(),
+            default_offline: false,
+            database: ConnectionOptions::default(),
+            search: SearchOptions::default(),
+            picker: PickerOptions::default(),
+            shell: ShellConfig::default(),
+            db: None,
+            profiles: BTreeMap::new(),
+            shared_db: None,
+            editor: None,
+            audit_log: None,
+            color: true,
+            colors: ColorConfig::default(),
+            formats: BTreeMap::new(),
+            daemon: DaemonConfig::default(),
+        }
+    }
+}
+
+/// Profile name of the `db` database when `profiles` doesn't define it
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Commented default configuration written by `bukurs config init`
+///
+/// Settings that are commented out keep their built-in defaults.
+pub const DEFAULT_CONFIG_TEMPLATE: &str = r#"# bukurs configuration
+# Run `bukurs config show` to see effective values and `bukurs config validate`
+# to check this file after editing it.
+#
+# Every setting can be overridden with an environment variable named after its
+# key, e.g. BUKURS_FETCH_TIMEOUT_SECS=10 or BUKURS_DATABASE_JOURNAL_MODE=delete.
+
+# User-agent for HTTP requests (defaults to a desktop Safari user-agent)
+# user_agent: "Mozilla/5.0 ..."
+
+# Threads for parallel imports (defaults to the number of CPUs, at most 8)
+# import_threads: 4
+
+# Database file used when --db isn't given; when unset, a `.bukurs.toml` in
+# the working directory or a parent (a workspace) can name one
+# db: /home/me/bookmarks.db
+
+# Other databases, used with `--profile NAME` and compared or copied between
+# with `bukurs profile`; `default` is the database above unless listed here
+# profiles:
+#   work: /home/me/work/bookmarks.db
+
+# Read-only database whose bookmarks `search` lists after yours, marked as
+# shared, e.g. a team file on a network drive; it is never written to
+# shared_db: /mnt/team/bookmarks.db
+
+# Editor for `edit` and `note` (defaults to $EDITOR, then vim)
+# editor: code --wait
+
+# Append every add, update, delete, import, fetch and undo to this JSON-lines
+# file; read it back with `bukurs audit tail` and `bukurs audit search`
+# audit_log: /home/me/.local/share/bukurs/audit.jsonl
+
+# Colored output (--nc or NO_COLOR disables it)
+color: true
+
+# Listing colors: a built-in theme (default, solarized or mono) plus optional
+# per-field overrides. Colors are names (green, bright_blue), #rrggbb or ANSI
+# 256-color numbers, optionally with bold, dimmed, italic or underline.
+colors:
+  theme: default
+  # id: bright_blue
+  # title: bold green
+  # url: yellow
+  # tags: blue
+  # desc: none
+  # highlight: red
+  # rating: yellow
+
+# Never connect to the network unless --online is given: add queues bookmarks
+# for `fetch-pending` and update doesn't refresh (BUKURS_OFFLINE=true also works)
+# default_offline: true
+
+# Metadata fetching
+fetch:
+  # Seconds allowed per request (0 disables the timeout)
+  timeout_secs: 30
+  # Redirects followed before giving up (0 disables redirects)
+  max_redirects: 10
+  # Overrides the top-level user_agent for fetching
+  # user_agent: "bukurs"
+  # Without a proxy here, HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY apply
+  # proxy: http://127.0.0.1:8080
+  # Extra trusted certificate authorities as a PEM bundle (also --cacert)
+  # tls_ca_file: /etc/ssl/certs/corp-ca.pem
+  # accept_language: en-US,en;q=0.8
+  # Ask GitHub, YouTube and arXiv APIs for details and tag their bookmarks
+  enrich: true
+  # github_token: ghp_...
+  # youtube_api_key: AIza...
+  # Extract each page's main text to show reading times in listings
+  readability: false
+  # Milliseconds between requests to the same site (0 disables the delay)
+  host_delay_ms: 500
+  # Retries when a site answers 429 or 503, honoring its Retry-After
+  max_retries: 2
+  # Pages fetched at once when adding or fetching many (0: one per CPU)
+  concurrency: 8
+
+# SQLite connection tuning
+database:
+  # delete, truncate, persist, memory, wal or off
+  journal_mode: wal
+  # Milliseconds to wait for a locked database
+  busy_timeout_ms: 5000
+  # off, normal, full or extra
+  synchronous: normal
+  foreign_keys: true
+  # Commands that change the database wait for each other across processes,
+  # up to lock_timeout_ms milliseconds
+  lock: true
+  lock_timeout_ms: 30000
+  # Keep the database encrypted on disk with SQLCipher (needs bukurs built with
+  # the `sqlcipher` feature; convert an existing file with `bukurs cipher
+  # encrypt`). The key comes from BUKURS_DB_KEY, else the first line printed by
+  # key_command, else a prompt
+  encrypted: false
+  # key_command: secret-tool lookup service bukurs
+
+# Search ranking
+search:
+  # Order used when `search --rank` isn't given: relevance, recent, visits or rating
+  # (unset keeps results in index order)
+  # rank: relevance
+  # How much a keyword match in each field counts towards relevance
+  weights:
+    url: 1.0
+    title: 10.0
+    tags: 5.0
+    desc: 2.0
+  # How bare keywords match: exact (whole words), prefix (as if written `prog*`)
+  # or fuzzy (prefixes, then similar words when nothing matches, like --fuzzy)
+  keyword_match: exact
+
+# The bookmark picker: builtin, or external to run `command` (fzf, skim, ...)
+# with one `id<TAB>title<TAB>url<TAB>tags` line per bookmark on stdin; it
+# prints the chosen lines. {query} is replaced by the search keywords.
+# Keys act on the bookmarks picked in the built-in picker, like ctrl-o, alt-e,
+# ctrl-alt-x or f2 (Enter picks and Tab marks regardless)
+# picker:
+#   kind: builtin
+#   command: fzf --multi --delimiter='\t' --with-nth=2.. --query={query}
+#   keys:
+#     open: ctrl-o
+#     copy_url: ctrl-y
+#     edit: alt-e
+#     delete: alt-d
+#     # Show or hide each entry's full URL, tags and description
+#     preview: alt-p
+
+# Tags added to bookmarks by domain (subdomains match too)
+# autotag:
+#   github.com: code,github
+
+# Output templates used with `-f <name>`; fields are {id}, {url}, {title},
+# {tags} and {desc}
+# formats:
+#   tsv: '{id}\t{url}\t{tags}'
+#   markdown: "- [{title}]({url})"
+
+# Interactive shell shortcuts
+# shell:
+#   aliases:
+#     rd: s rust --deep
+#   macros:
+#     review: p -3..-1; t todo
+
+# Jobs run by `bukurs daemon` on cron schedules (UTC): check reports broken
+# links, refresh re-fetches metadata, backup copies the database (into dir,
+# default backups/ next to it, keeping the newest keep copies, default 7),
+# expire deletes bookmarks added with --expires once they have expired,
+# monitor flags monitored pages that changed (see `bukurs monitor`)
+# daemon:
+#   jobs:
+#     nightly-backup:
+#       run: backup
+#       schedule: "30 3 * * *"
+#     links:
+#       run: check
+#       schedule: "@weekly"
+"#;
+
+/// Every key the config file understands, as dotted paths
+const KNOWN_KEYS: &[&str] = &[
+    "user_agent",
+    "import_threads",
+    "db",
+    "profiles",
+    "shared_db",
+    "editor",
+    "audit_log",
+    "color",
+    "colors",
+    "colors.theme",
+    "colors.id",
+    "colors.url",
+    "colors.title",
+    "colors.tags",
+    "colors.desc",
+    "colors.highlight",
+    "colors.rating",
+    "autotag",
+    "default_offline",
+    "fetch",
+    "fetch.user_agent",
+    "fetch.timeout_secs",
+    "fetch.max_redirects",
+    "fetch.proxy",
+    "fetch.tls_ca_file",
+    "fetch.accept_language",
+    "fetch.enrich",
+    "fetch.github_token",
+    "fetch.youtube_api_key",
+    "fetch.readability",
+    "fetch.host_delay_ms",
+    "fetch.max_retries",
+    "fetch.concurrency",
+    "database",
+    "database.journal_mode",
+    "database.busy_timeout_ms",
+    "database.synchronous",
+    "database.foreign_keys",
+    "database.lock",
+    "database.lock_timeout_ms",
+    "database.encrypted",
+    "database.key_command",
+    "search",
+    "search.rank",
+    "search.weights",
+    "search.weights.url",
+    "search.weights.title",
+    "search.weights.tags",
+    "search.weights.desc",
+    "search.keyword_match",
+    "picker",
+    "picker.kind",
+    "picker.command",
+    "picker.keys",
+    "picker.keys.open",
+    "picker.keys.copy_url",
+    "picker.keys.edit",
+    "picker.keys.delete",
+    "picker.keys.preview",
+    "shell",
+    "shell.aliases",
+    "shell.macros",
+    "formats",
+    "daemon",
+    "daemon.jobs",
+];
+
+/// Sections whose keys are chosen by the user (domains, alias names)
+const FREE_FORM_KEYS: &[&str] = &[
+    "autotag",
+    "shell.aliases",
+    "shell.macros",
+    "formats",
+    "profiles",
+    "daemon.jobs",
+];
+
+/// Keys holding credentials, masked by `config show`
+const SECRET_KEYS: &[&str] = &["fetch.github_token", "fetch.youtube_api_key"];
+
+/// Prefix of the environment variables overriding config keys
+///
+/// `fetch.timeout_secs` is overridden by `BUKURS_FETCH_TIMEOUT_SECS`.
+const ENV_PREFIX: &str = "BUKURS_";
+
+/// Short environment variable names for commonly overridden keys
+const ENV_ALIASES: &[(&str, &str)] = &[
+    ("BUKURS_COLORS", "color"),
+    ("BUKURS_FETCH_TIMEOUT", "fetch.timeout_secs"),
+    ("BUKURS_OFFLINE", "default_offline"),
+];
+
+/// Where an effective config value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    Cli,
+    Workspace,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Default => "default",
+            Self::File => "file",
+            Self::Env => "env",
+            Self::Cli => "cli",
+            Self::Workspace => "workspace",
+        })
+    }
+}
+
+/// One effective setting, as shown by `bukurs config show`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValue {
+    /// Dotted path, e.g. `fetch.timeout_secs`
+    pub key: String,
+    /// The value in YAML syntax
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+/// A problem found while validating a config file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigIssue {
+    /// A key the config doesn't understand (likely a typo); it is ignored
+    UnknownKey(String),
+    /// The file isn't valid YAML or a value has the wrong type
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+            Self::Invalid(message) => f.write_str(message),
         }
     }
 }
@@ -30,12 +496,108 @@ fn default_user_agent() -> String {
         .to_string()
 }
 
+fn default_color() -> bool {
+    true
+}
+
+fn default_theme() -> String {
+    "default".to_string()
+}
+
 fn default_import_threads() -> usize {
     // Use number of CPUs, but cap at 8 for reasonable resource usage
     num_cpus::get().min(8)
 }
 
 impl Config {
+    /// Fetch settings, with `fetch.user_agent` falling back to the top-level `user_agent`
+    /// and network access off with `default_offline`
+    pub fn fetch_options(&self) -> FetchOptions {
+        let mut options = self.fetch.clone();
+        if options.user_agent.is_none() {
+            options.user_agent = Some(self.user_agent.clone());
+        }
+        options.offline = self.default_offline;
+        options
+    }
+
+    /// The database to use: `db` (or `BUKURS_DB`), else the default location
+    pub fn db_path(&self) -> PathBuf {
+        self.db
+            .clone()
+            .unwrap_or_else(|| crate::utils::get_default_dbdir().join("bookmarks.db"))
+    }
+
+    /// The database for commands run in `dir`: `db` (or `BUKURS_DB`), else
+    /// the database of the [workspace](crate::workspace) `dir` is in, else the
+    /// default location
+    pub fn db_path_in(&self, dir: &Path) -> crate::error::Result<PathBuf> {
+        if self.db.is_some() {
+            return Ok(self.db_path());
+        }
+        Ok(match crate::workspace::find(dir)? {
+            Some(workspace) => workspace.db,
+            None => self.db_path(),
+        })
+    }
+
+    /// Database file of the profile `name` (see `profiles`)
+    ///
+    /// `default` names [`db_path`](Self::db_path) unless a profile of that
+    /// name is configured.
+    pub fn profile_path(&self, name: &str) -> crate::error::Result<PathBuf> {
+        match self.profiles.get(name) {
+            Some(path) => Ok(path.clone()),
+            None if name == DEFAULT_PROFILE => Ok(self.db_path()),
+            None => {
+                let known: Vec<&str> = std::iter::once(DEFAULT_PROFILE)
+                    .chain(self.profiles.keys().map(String::as_str))
+                    .collect();
+                Err(crate::error::BukursError::Config(format!(
+                    "Unknown profile '{}' (profiles: {})",
+                    name,
+                    known.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Open `path` (by default [`db_path`](Self::db_path)) the way the CLI does,
+    /// for programs embedding bukurs
+    ///
+    /// Creates the directory, takes the database lock while migrating, and
+    /// applies the connection settings, autotag rules and audit log. Audit
+    /// entries and change events are attributed to `source`. The key of an
+    /// encrypted database must come from `BUKURS_DB_KEY` or
+    /// `database.key_command` unless [`ConnectionOptions::unlock`] found it
+    /// already, as there is no one to prompt.
+    pub fn open_db(
+        &self,
+        path: Option<&Path>,
+        source: AuditSource,
+    ) -> crate::error::Result<BukuDb> {
+        let path = path.map_or_else(|| self.db_path(), Path::to_path_buf);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = self.database.clone();
+        options.unlock(|| {
+            Err(crate::error::BukursError::Config(format!(
+                "{} is encrypted: set {} or database.key_command",
+                path.display(),
+                crate::db::cipher::KEY_ENV
+            )))
+        })?;
+        let _lock = options.lock_database(&path)?;
+        let mut db = BukuDb::init_with_options(&path, &options)?;
+        db.set_autotag_rules(self.autotag.clone());
+        db.set_source(source);
+        if let Some(audit) = &self.audit_log {
+            db.set_audit_log(Some(AuditLog::new(audit, source)));
+        }
+        Ok(db)
+    }
+
     /// Load configuration from a file path
     pub fn load_from_path(path: &Path) -> crate::error::Result<Self> {
         let contents = fs::read_to_string(path)?;
@@ -44,11 +606,12 @@ impl Config {
     }
 
     /// Load configuration from default location (~/.config/bukurs/config.yml)
+    /// with `BUKURS_*` environment overrides applied on top
     /// Falls back to default config if file doesn't exist
     pub fn load() -> Self {
-        let config_path = crate::utils::get_config_dir().join("config.yml");
+        let config_path = Self::default_path();
 
-        if config_path.exists() {
+        let mut config = if config_path.exists() {
             match Self::load_from_path(&config_path) {
                 Ok(config) => config,
                 Err(e) => {
@@ -62,7 +625,115 @@ impl Config {
             }
         } else {
             Self::default()
+        };
+
+        if let Err(e) = config.apply_env(std::env::vars()) {
+            eprintln!("Warning: Ignoring environment overrides: {}", e);
         }
+        config
+    }
+
+    /// Override settings from `BUKURS_*` variables, returning the keys that changed
+    ///
+    /// Values are read as YAML scalars (`BUKURS_COLOR=false`), falling back to
+    /// plain strings. Variables that don't name a config key are ignored.
+    /// A non-empty `NO_COLOR` turns colors off unless `BUKURS_COLOR(S)` is set.
+    pub fn apply_env(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> crate::error::Result<Vec<String>> {
+        let mut overrides: Vec<(String, String, String)> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                if name == "NO_COLOR" {
+                    return (!value.is_empty())
+                        .then(|| ("color".to_string(), name, "false".to_string()));
+                }
+                env_key(&name).map(|key| (key, name, value))
+            })
+            .collect();
+        // Applied in order, so later ones win: NO_COLOR, short aliases, full names
+        overrides.sort_by_key(|(_, name, _)| {
+            if name == "NO_COLOR" {
+                0
+            } else if ENV_ALIASES.iter().any(|(alias, _)| alias == name) {
+                1
+            } else {
+                2
+            }
+        });
+
+        let mut current = serde_yaml::to_value(&*self)?;
+        let mut keys = Vec::new();
+        for (key, name, raw) in overrides {
+            let with_value = |value| {
+                let mut next = current.clone();
+                set_value(&mut next, &key, value);
+                serde_yaml::from_value::<Config>(next.clone()).map(|_| next)
+            };
+            let as_string = serde_yaml::Value::String(raw.clone());
+            let result = match serde_yaml::from_str::<serde_yaml::Value>(&raw) {
+                // `BUKURS_EDITOR=123` is still a valid (string) editor
+                Ok(value) if !value.is_string() && !value.is_mapping() && !value.is_sequence() => {
+                    with_value(value).or_else(|e| with_value(as_string).map_err(|_| e))
+                }
+                _ => with_value(as_string),
+            };
+            current = result.map_err(|e| {
+                crate::error::BukursError::Config(format!("{}={}: {}", name, raw, e))
+            })?;
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        *self = serde_yaml::from_value(current)?;
+        Ok(keys)
+    }
+
+    /// Default config file location (~/.config/bukurs/config.yml)
+    pub fn default_path() -> PathBuf {
+        crate::utils::get_config_dir().join("config.yml")
+    }
+
+    /// Check config file contents for unknown keys and invalid values
+    pub fn validate_str(contents: &str) -> Vec<ConfigIssue> {
+        let value: serde_yaml::Value = match serde_yaml::from_str(contents) {
+            Ok(value) => value,
+            Err(e) => return vec![ConfigIssue::Invalid(e.to_string())],
+        };
+
+        let mut issues = Vec::new();
+        collect_unknown_keys(&value, "", &mut issues);
+        // Parsing the text (not the value) keeps the key path in error messages
+        if let Err(e) = serde_yaml::from_str::<Config>(contents) {
+            issues.push(ConfigIssue::Invalid(e.to_string()));
+        }
+        issues
+    }
+
+    /// Effective settings with where each comes from
+    ///
+    /// `file` is the parsed config file, if one was loaded. `overrides` lists
+    /// keys set by environment variables or command-line flags, later entries winning.
+    pub fn effective_values(
+        &self,
+        file: Option<&serde_yaml::Value>,
+        overrides: &[(String, ConfigSource)],
+    ) -> Vec<ConfigValue> {
+        let mut values = Vec::new();
+        if let Ok(effective) = serde_yaml::to_value(self) {
+            flatten_values(&effective, file, "", &mut values);
+        }
+        for value in &mut values {
+            if let Some((_, source)) = overrides.iter().rev().find(|(key, _)| *key == value.key) {
+                value.source = *source;
+            }
+            if SECRET_KEYS.contains(&value.key.as_str()) {
+                value.value = "'********'".to_string();
+            }
+        }
+        values
     }
 
     /// Save configuration to a file path
@@ -82,11 +753,118 @@ impl Config {
         let config_path = crate::utils::get_config_dir().join("config.yml");
         self.save_to_path(&config_path)
     }
+
+    /// The configuration as YAML with the credentials left out, for copies
+    /// that travel further than the config file, like dump snapshots
+    pub fn to_yaml_without_secrets(&self) -> crate::error::Result<String> {
+        let mut value = serde_yaml::to_value(self)?;
+        for key in SECRET_KEYS {
+            let Some((section, field)) = key.split_once('.') else {
+                continue;
+            };
+            if let Some(serde_yaml::Value::Mapping(map)) = value.get_mut(section) {
+                map.remove(field);
+            }
+        }
+        Ok(serde_yaml::to_string(&value)?)
+    }
+}
+
+/// Join a dotted key path
+fn child_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Config key overridden by an environment variable, if any
+fn env_key(name: &str) -> Option<String> {
+    if let Some((_, key)) = ENV_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(key.to_string());
+    }
+    let suffix = name.strip_prefix(ENV_PREFIX)?;
+    KNOWN_KEYS
+        .iter()
+        .filter(|key| is_scalar_key(key))
+        .find(|key| key.replace('.', "_").eq_ignore_ascii_case(suffix))
+        .map(|key| key.to_string())
+}
+
+/// Keys holding a single value rather than a section
+fn is_scalar_key(key: &str) -> bool {
+    let section = format!("{}.", key);
+    !FREE_FORM_KEYS.contains(&key) && !KNOWN_KEYS.iter().any(|k| k.starts_with(&section))
+}
+
+/// Set a dotted key in a YAML mapping, creating sections as needed
+fn set_value(root: &mut serde_yaml::Value, key: &str, value: serde_yaml::Value) {
+    let mut node = root;
+    for part in key.split('.') {
+        if !node.is_mapping() {
+            *node = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+        let map = node.as_mapping_mut().expect("just made a mapping");
+        node = map
+            .entry(serde_yaml::Value::String(part.to_string()))
+            .or_insert(serde_yaml::Value::Null);
+    }
+    *node = value;
+}
+
+fn collect_unknown_keys(value: &serde_yaml::Value, prefix: &str, issues: &mut Vec<ConfigIssue>) {
+    let serde_yaml::Value::Mapping(map) = value else {
+        return;
+    };
+    if FREE_FORM_KEYS.contains(&prefix) {
+        return;
+    }
+
+    for (key, child) in map {
+        let key = child_key(prefix, key.as_str().unwrap_or("?"));
+        if KNOWN_KEYS.contains(&key.as_str()) {
+            collect_unknown_keys(child, &key, issues);
+        } else {
+            issues.push(ConfigIssue::UnknownKey(key));
+        }
+    }
+}
+
+fn flatten_values(
+    effective: &serde_yaml::Value,
+    file: Option<&serde_yaml::Value>,
+    prefix: &str,
+    values: &mut Vec<ConfigValue>,
+) {
+    match effective {
+        serde_yaml::Value::Mapping(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let name = key.as_str().unwrap_or("?");
+                let file_child = file.and_then(|f| f.get(name));
+                flatten_values(child, file_child, &child_key(prefix, name), values);
+            }
+        }
+        _ => {
+            let value = serde_yaml::to_string(effective).unwrap_or_default();
+            values.push(ConfigValue {
+                key: prefix.to_string(),
+                value: value.trim_end().to_string(),
+                source: if file.is_some() {
+                    ConfigSource::File
+                } else {
+                    ConfigSource::Default
+                },
+            });
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::{JournalMode, Synchronous};
+    use rstest::rstest;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -103,12 +881,40 @@ mod tests {
         let original = Config {
             user_agent: "Custom User Agent".to_string(),
             import_threads: 4,
+            autotag: AutotagRules::default(),
+            fetch: FetchOptions::default(),
+            default_offline: true,
+            database: ConnectionOptions::default(),
+            search: SearchOptions::default(),
+            picker: PickerOptions::default(),
+            shell: ShellConfig::default(),
+            db: Some(PathBuf::from("/tmp/bookmarks.db")),
+            profiles: BTreeMap::from([("work".to_string(), PathBuf::from("/tmp/work.db"))]),
+            shared_db: Some(PathBuf::from("/mnt/team/bookmarks.db")),
+            editor: Some("nano".to_string()),
+            audit_log: Some(PathBuf::from("/tmp/audit.jsonl")),
+            color: false,
+            formats: BTreeMap::from([("md".to_string(), "[{title}]({url})".to_string())]),
+            colors: ColorConfig {
+                theme: "mono".to_string(),
+                tags: Some("#268bd2".to_string()),
+                ..ColorConfig::default()
+            },
+            daemon: DaemonConfig::default(),
         };
 
         original.save_to_path(config_path).unwrap();
         let loaded = Config::load_from_path(config_path).unwrap();
 
         assert_eq!(original.user_agent, loaded.user_agent);
+        assert_eq!(original.db, loaded.db);
+        assert_eq!(original.shared_db, loaded.shared_db);
+        assert_eq!(original.editor, loaded.editor);
+        assert_eq!(original.audit_log, loaded.audit_log);
+        assert_eq!(original.color, loaded.color);
+        assert_eq!(original.default_offline, loaded.default_offline);
+        assert_eq!(original.colors, loaded.colors);
+        assert_eq!(original.formats, loaded.formats);
     }
 
     #[test]
@@ -134,4 +940,344 @@ mod tests {
         // Should use default for missing field
         assert_eq!(config.user_agent, default_user_agent());
     }
+
+    #[test]
+    fn test_profile_path() {
+        let config = Config {
+            db: Some(PathBuf::from("/tmp/bookmarks.db")),
+            profiles: BTreeMap::from([("work".to_string(), PathBuf::from("/tmp/work.db"))]),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.profile_path("work").unwrap(),
+            PathBuf::from("/tmp/work.db")
+        );
+        assert_eq!(
+            config.profile_path(DEFAULT_PROFILE).unwrap(),
+            config.db_path()
+        );
+        let error = config.profile_path("home").unwrap_err().to_string();
+        assert!(error.contains("default, work"), "{}", error);
+    }
+
+    #[test]
+    fn test_db_path_in_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(crate::workspace::WORKSPACE_FILE),
+            "db = \"links.db\"",
+        )
+        .unwrap();
+
+        let config = Config::default();
+        assert_eq!(
+            config.db_path_in(dir.path()).unwrap(),
+            dir.path().join("links.db")
+        );
+        let config = Config {
+            db: Some(PathBuf::from("/tmp/bookmarks.db")),
+            ..Config::default()
+        };
+        assert_eq!(config.db_path_in(dir.path()).unwrap(), config.db_path());
+    }
+
+    #[test]
+    fn test_open_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            db: Some(dir.path().join("data").join("bookmarks.db")),
+            audit_log: Some(dir.path().join("audit.jsonl")),
+            ..Config::default()
+        };
+        config.autotag.insert("rust-lang.org", "rust");
+
+        let db = config.open_db(None, AuditSource::Python).unwrap();
+        assert_eq!(db.get_path(), config.db_path());
+        assert_eq!(
+            db.autotag_rules().apply("https://rust-lang.org", ","),
+            Some(",rust,".to_string())
+        );
+        assert!(db.audit_log().is_some());
+
+        let memory = config
+            .open_db(Some(Path::new(":memory:")), AuditSource::Cli)
+            .unwrap();
+        assert!(memory.is_in_memory());
+    }
+
+    #[test]
+    fn test_fetch_options_user_agent_fallback() {
+        let mut config = Config {
+            user_agent: "Top Level".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.fetch_options().user_agent.as_deref(),
+            Some("Top Level")
+        );
+
+        config.fetch.user_agent = Some("Fetch Specific".to_string());
+        assert_eq!(
+            config.fetch_options().user_agent.as_deref(),
+            Some("Fetch Specific")
+        );
+    }
+
+    #[test]
+    fn test_load_autotag_rules() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_path = temp_file.path();
+
+        fs::write(config_path, "autotag:\n  github.com: code,github\n").unwrap();
+
+        let config = Config::load_from_path(config_path).unwrap();
+        assert_eq!(
+            config.autotag.tags_for("https://github.com/rust-lang"),
+            vec!["code", "github"]
+        );
+    }
+
+    #[test]
+    fn test_load_database_options() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_path = temp_file.path();
+
+        fs::write(
+            config_path,
+            "database:\n  journal_mode: delete\n  busy_timeout_ms: 250\n  lock: false\n",
+        )
+        .unwrap();
+
+        let config = Config::load_from_path(config_path).unwrap();
+        assert_eq!(config.database.journal_mode, JournalMode::Delete);
+        assert_eq!(config.database.busy_timeout_ms, 250);
+        assert!(!config.database.lock);
+        // Unset fields keep their defaults
+        assert_eq!(config.database.synchronous, Synchronous::Normal);
+        assert!(config.database.foreign_keys);
+    }
+
+    #[test]
+    fn test_load_shell_config() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_path = temp_file.path();
+
+        fs::write(
+            config_path,
+            "shell:\n  aliases:\n    rd: s rust --deep\n  macros:\n    cleanup: check; dedupe\n",
+        )
+        .unwrap();
+
+        let config = Config::load_from_path(config_path).unwrap();
+        assert_eq!(config.shell.aliases["rd"], "s rust --deep");
+        assert_eq!(config.shell.macros["cleanup"], "check; dedupe");
+    }
+
+    #[test]
+    fn test_default_template_is_valid_and_matches_defaults() {
+        assert_eq!(Config::validate_str(DEFAULT_CONFIG_TEMPLATE), vec![]);
+
+        let from_template: Config = serde_yaml::from_str(DEFAULT_CONFIG_TEMPLATE).unwrap();
+        assert_eq!(from_template.fetch, FetchOptions::default());
+        assert_eq!(from_template.database, ConnectionOptions::default());
+        assert_eq!(from_template.search, SearchOptions::default());
+        assert_eq!(from_template.colors, ColorConfig::default());
+        assert!(from_template.autotag.is_empty());
+        assert!(from_template.shell.is_empty());
+    }
+
+    #[test]
+    fn test_known_keys_cover_serialized_config() {
+        let config = Config {
+            autotag: serde_yaml::from_str("github.com: code").unwrap(),
+            shell: serde_yaml::from_str("aliases: {rd: s rust}\nmacros: {m: p}").unwrap(),
+            formats: BTreeMap::from([("tsv".to_string(), "{id}".to_string())]),
+            profiles: BTreeMap::from([("work".to_string(), PathBuf::from("/tmp/work.db"))]),
+            daemon: serde_yaml::from_str("jobs: {b: {run: backup, schedule: '@daily'}}").unwrap(),
+            ..Config::default()
+        };
+        for value in config.effective_values(None, &[]) {
+            let known = KNOWN_KEYS.contains(&value.key.as_str())
+                || FREE_FORM_KEYS
+                    .iter()
+                    .any(|section| value.key.starts_with(&format!("{}.", section)));
+            assert!(known, "{} is missing from KNOWN_KEYS", value.key);
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_keys_and_type_errors() {
+        let issues = Config::validate_str(
+            "user_agnet: x\nfetch:\n  timeout: 5\nautotag:\n  example.com: docs\n",
+        );
+        assert_eq!(
+            issues,
+            vec![
+                ConfigIssue::UnknownKey("user_agnet".to_string()),
+                ConfigIssue::UnknownKey("fetch.timeout".to_string()),
+            ]
+        );
+
+        let issues = Config::validate_str("fetch:\n  timeout_secs: soon\n");
+        assert!(
+            matches!(issues.as_slice(), [ConfigIssue::Invalid(msg)] if msg.contains("timeout_secs"))
+        );
+
+        let issues = Config::validate_str("invalid: yaml: content:");
+        assert!(matches!(issues.as_slice(), [ConfigIssue::Invalid(_)]));
+    }
+
+    #[test]
+    fn test_effective_values_sources() {
+        let file: serde_yaml::Value = serde_yaml::from_str("fetch:\n  timeout_secs: 5\n").unwrap();
+        let config: Config = serde_yaml::from_value(file.clone()).unwrap();
+        let values = config.effective_values(Some(&file), &[]);
+
+        let find = |key: &str| values.iter().find(|v| v.key == key).unwrap();
+        assert_eq!(find("fetch.timeout_secs").value, "5");
+        assert_eq!(find("fetch.timeout_secs").source, ConfigSource::File);
+        assert_eq!(find("fetch.max_redirects").source, ConfigSource::Default);
+        assert_eq!(find("database.journal_mode").value, "wal");
+    }
+
+    #[test]
+    fn test_effective_values_mask_secrets() {
+        let mut config = Config::default();
+        config.fetch.github_token = Some("ghp_secret".to_string());
+        let values = config.effective_values(None, &[]);
+
+        let token = values
+            .iter()
+            .find(|v| v.key == "fetch.github_token")
+            .unwrap();
+        assert!(!token.value.contains("ghp_secret"));
+        assert!(values.iter().all(|v| v.key != "fetch.youtube_api_key"));
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let mut config = Config::default();
+        let keys = config
+            .apply_env(env(&[
+                ("BUKURS_FETCH_TIMEOUT", "7"),
+                ("BUKURS_DATABASE_JOURNAL_MODE", "delete"),
+                ("BUKURS_DB", "/tmp/other.db"),
+                ("BUKURS_EDITOR", "code --wait"),
+                ("BUKURS_COLORS", "false"),
+                ("BUKURS_FETCH_PROXY", "http://127.0.0.1:8080"),
+                ("BUKURS_UNRELATED", "x"),
+                ("HOME", "/root"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.fetch.timeout_secs, 7);
+        assert_eq!(config.database.journal_mode, JournalMode::Delete);
+        assert_eq!(config.db, Some(PathBuf::from("/tmp/other.db")));
+        assert_eq!(config.editor.as_deref(), Some("code --wait"));
+        assert!(!config.color);
+        assert_eq!(config.fetch.proxy.as_deref(), Some("http://127.0.0.1:8080"));
+        assert_eq!(keys.len(), 6);
+        assert!(keys.contains(&"fetch.timeout_secs".to_string()));
+    }
+
+    #[test]
+    fn test_offline_env_turns_fetching_off() {
+        let mut config = Config::default();
+        assert!(!config.fetch_options().offline);
+        let keys = config
+            .apply_env(env(&[("BUKURS_OFFLINE", "true")]))
+            .unwrap();
+        assert_eq!(keys, vec!["default_offline".to_string()]);
+        let options = config.fetch_options();
+        assert!(options.offline);
+        assert!(matches!(
+            options.client(),
+            Err(crate::error::BukursError::Offline)
+        ));
+    }
+
+    #[rstest]
+    #[case("BUKURS_USER_AGENT", "12345", "user_agent")]
+    #[case("BUKURS_EDITOR", "true", "editor")]
+    fn test_apply_env_numbers_and_bools_as_strings(
+        #[case] name: &str,
+        #[case] value: &str,
+        #[case] key: &str,
+    ) {
+        let mut config = Config::default();
+        config.apply_env(env(&[(name, value)])).unwrap();
+        let values = config.effective_values(None, &[]);
+        let found = values.iter().find(|v| v.key == key).unwrap();
+        assert_eq!(found.value, format!("'{}'", value));
+    }
+
+    #[test]
+    fn test_apply_env_full_name_beats_alias() {
+        let mut config = Config::default();
+        config
+            .apply_env(env(&[
+                ("BUKURS_FETCH_TIMEOUT_SECS", "5"),
+                ("BUKURS_FETCH_TIMEOUT", "9"),
+            ]))
+            .unwrap();
+        assert_eq!(config.fetch.timeout_secs, 5);
+    }
+
+    #[test]
+    fn test_apply_env_invalid_value_keeps_config() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env(env(&[("BUKURS_FETCH_TIMEOUT", "soon")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("BUKURS_FETCH_TIMEOUT=soon"));
+        assert_eq!(config.fetch.timeout_secs, 30);
+    }
+
+    #[test]
+    fn test_effective_values_overrides() {
+        let mut config = Config::default();
+        let keys = config
+            .apply_env(env(&[("BUKURS_IMPORT_THREADS", "2")]))
+            .unwrap();
+        let mut overrides: Vec<_> = keys.into_iter().map(|k| (k, ConfigSource::Env)).collect();
+        overrides.push(("color".to_string(), ConfigSource::Cli));
+
+        let values = config.effective_values(None, &overrides);
+        let find = |key: &str| values.iter().find(|v| v.key == key).unwrap();
+        assert_eq!(find("import_threads").source, ConfigSource::Env);
+        assert_eq!(find("color").source, ConfigSource::Cli);
+        assert_eq!(find("user_agent").source, ConfigSource::Default);
+    }
+
+    #[rstest]
+    #[case(&[("NO_COLOR", "1")], false)]
+    #[case(&[("NO_COLOR", "")], true)]
+    #[case(&[("NO_COLOR", "1"), ("BUKURS_COLORS", "true")], true)]
+    #[case(&[("BUKURS_COLORS", "true"), ("NO_COLOR", "1")], true)]
+    fn test_apply_env_no_color(#[case] vars: &[(&str, &str)], #[case] expected: bool) {
+        let mut config = Config::default();
+        config.apply_env(env(vars)).unwrap();
+        assert_eq!(config.color, expected);
+    }
+
+    #[test]
+    fn test_load_color_config() {
+        let config: Config =
+            serde_yaml::from_str("colors:\n  theme: mono\n  url: underline cyan\n").unwrap();
+        assert_eq!(config.colors.theme, "mono");
+        assert_eq!(config.colors.url.as_deref(), Some("underline cyan"));
+        assert_eq!(config.colors.title, None);
+
+        let mut config = Config::default();
+        config
+            .apply_env(env(&[("BUKURS_COLORS_THEME", "solarized")]))
+            .unwrap();
+        assert_eq!(config.colors.theme, "solarized");
+    }
 }
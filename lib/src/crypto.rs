@@ -1,82 +1,519 @@
+//! Database encryption for `lock` and `unlock`
+//!
+//! Encrypted files start with a versioned header naming the key derivation
+//! and its parameters, so they can change without breaking old files:
+//!
+//! | field          | size                                  |
+//! |----------------|---------------------------------------|
+//! | magic          | 8 bytes, `BUKURSEN`                   |
+//! | version        | 1 byte                                |
+//! | KDF id         | 1 byte                                |
+//! | KDF params     | 2-byte length (LE), then the params   |
+//! | salt           | 1-byte length, then the salt          |
+//! | IV             | 16 bytes                              |
+//! | plaintext size | 8 bytes (LE)                          |
+//! | key check      | 16 bytes                              |
+//!
+//! The database follows, encrypted with AES-256-CBC (PKCS#7 padding), and
+//! then an HMAC-SHA256 tag over the header and ciphertext. The key check
+//! tells a wrong password apart from a damaged file: it only matches when
+//! the password is right, while the tag only matches when nothing changed.
+//!
+//! The KDF is either buku's iterated SHA-256 (id 1, params: iterations) or
+//! Argon2id (id 2, params: memory in KiB, passes and lanes, each a LE u32).
+//!
+//! Files from before the header existed (size, salt, IV and a SHA-256 of
+//! the plaintext, then the ciphertext) are still decrypted.
+
+mod fields;
+
+use crate::error::{BukursError, Result};
 use aes::Aes256;
+use argon2::{Algorithm, Argon2, Version};
 use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
 use rand::{rng, RngCore};
 use sha2::{Digest, Sha256};
 use std::fs::{self, File};
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+
+pub use fields::{is_encrypted_field, FieldCipher, FieldDecryptor, FIELD_MARKER};
 
 type Aes256CbcEnc = cbc::Encryptor<Aes256>;
 type Aes256CbcDec = cbc::Decryptor<Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// First bytes of a versioned encrypted file
+pub const MAGIC: &[u8; 8] = b"BUKURSEN";
+
+/// Current format version
+pub const FORMAT_VERSION: u8 = 1;
+
+const AES_BLOCK: usize = 16;
+const TAG_SIZE: usize = 32;
+const KEY_CHECK_SIZE: usize = 16;
+
+/// Argon2id memory when none is given: 64 MiB
+pub const ARGON2_DEFAULT_MEMORY_KIB: u32 = 64 * 1024;
+/// Argon2id passes when none are given
+pub const ARGON2_DEFAULT_PASSES: u32 = 3;
+/// Most memory Argon2id may use: 4 GiB
+pub const ARGON2_MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+/// Most lanes Argon2id may use
+pub const ARGON2_MAX_LANES: u32 = 64;
+
+/// Key derivation function of an encrypted file, with its parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// SHA-256 of password and salt, re-hashed `iterations` times (buku's scheme)
+    Sha256 { iterations: u32 },
+    /// Argon2id (RFC 9106), memory-hard against GPU guessing
+    Argon2id {
+        memory_kib: u32,
+        passes: u32,
+        lanes: u32,
+    },
+}
+
+impl Kdf {
+    const SHA256_ID: u8 = 1;
+    const ARGON2ID_ID: u8 = 2;
+
+    /// Argon2id with the given memory and passes, on a single lane
+    pub fn argon2id(memory_kib: u32, passes: u32) -> Self {
+        Self::Argon2id {
+            memory_kib,
+            passes,
+            lanes: 1,
+        }
+    }
+
+    fn id(&self) -> u8 {
+        match self {
+            Self::Sha256 { .. } => Self::SHA256_ID,
+            Self::Argon2id { .. } => Self::ARGON2ID_ID,
+        }
+    }
+
+    fn params(&self) -> Vec<u8> {
+        match self {
+            Self::Sha256 { iterations } => iterations.to_le_bytes().to_vec(),
+            Self::Argon2id {
+                memory_kib,
+                passes,
+                lanes,
+            } => [memory_kib, passes, lanes]
+                .iter()
+                .flat_map(|n| n.to_le_bytes())
+                .collect(),
+        }
+    }
+
+    fn from_parts(id: u8, params: &[u8]) -> Result<Self> {
+        let invalid = || BukursError::Corrupted("invalid key derivation parameters".to_string());
+        let words: Vec<u32> = params
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        match (id, params.len(), &words[..]) {
+            (Self::SHA256_ID, 4, &[iterations]) if iterations > 0 => {
+                Ok(Self::Sha256 { iterations })
+            }
+            (Self::ARGON2ID_ID, 12, &[memory_kib, passes, lanes])
+                if argon2_params(memory_kib, passes, lanes).is_some() =>
+            {
+                Ok(Self::Argon2id {
+                    memory_kib,
+                    passes,
+                    lanes,
+                })
+            }
+            (Self::SHA256_ID | Self::ARGON2ID_ID, _, _) => Err(invalid()),
+            _ => Err(BukursError::Crypto(format!(
+                "unsupported key derivation function {} (written by a newer bukurs?)",
+                id
+            ))),
+        }
+    }
+
+    fn derive(&self, password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+        match *self {
+            Self::Sha256 { iterations } => {
+                check_iterations(iterations)?;
+                Ok(BukuCrypt::derive_key(password, salt, iterations))
+            }
+            Self::Argon2id {
+                memory_kib,
+                passes,
+                lanes,
+            } => {
+                let params = argon2_params(memory_kib, passes, lanes).ok_or_else(|| {
+                    BukursError::Crypto(format!("invalid Argon2id parameters ({})", self))
+                })?;
+                let mut key = [0u8; 32];
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), salt, &mut key)
+                    .map_err(|e| BukursError::Crypto(format!("Argon2id: {}", e)))?;
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// SHA-256 key derivation hashes at least once, or the key isn't 32 bytes
+fn check_iterations(iterations: u32) -> Result<()> {
+    if iterations == 0 {
+        return Err(BukursError::Crypto(
+            "SHA-256 key derivation needs at least 1 iteration".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Argon2id parameters for a 32-byte key, if they are within bounds
+///
+/// Memory and lanes come from file headers, so they are capped before
+/// anything is allocated for them.
+fn argon2_params(memory_kib: u32, passes: u32, lanes: u32) -> Option<argon2::Params> {
+    if memory_kib > ARGON2_MAX_MEMORY_KIB || lanes > ARGON2_MAX_LANES {
+        return None;
+    }
+    argon2::Params::new(memory_kib, passes, lanes, Some(32)).ok()
+}
+
+impl std::fmt::Display for Kdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sha256 { iterations } => write!(f, "SHA-256, {} iterations", iterations),
+            Self::Argon2id {
+                memory_kib,
+                passes,
+                lanes,
+            } => {
+                let memory = if memory_kib % 1024 == 0 {
+                    format!("{} MiB", memory_kib / 1024)
+                } else {
+                    format!("{} KiB", memory_kib)
+                };
+                write!(
+                    f,
+                    "Argon2id, {}, {} passes, {} lane(s)",
+                    memory, passes, lanes
+                )
+            }
+        }
+    }
+}
+
+/// Header of a versioned encrypted file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncHeader {
+    pub version: u8,
+    pub kdf: Kdf,
+    pub salt: Vec<u8>,
+    pub iv: [u8; 16],
+    /// Size of the decrypted database
+    pub size: u64,
+    key_check: [u8; KEY_CHECK_SIZE],
+}
+
+/// Layout of an encrypted file, as found by [`BukuCrypt::inspect`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncryptedFormat {
+    /// Written before the header existed: no integrity tag
+    Legacy,
+    Versioned(EncHeader),
+}
+
+/// Encryption and authentication keys derived from the password
+struct Keys {
+    enc: [u8; 32],
+    mac: [u8; 32],
+    check: [u8; KEY_CHECK_SIZE],
+}
+
+impl Keys {
+    fn derive(kdf: &Kdf, password: &str, salt: &[u8]) -> Result<Self> {
+        let master = kdf.derive(password, salt)?;
+        let mac = hmac(&master, b"bukurs authentication key");
+        let mut check = [0u8; KEY_CHECK_SIZE];
+        check.copy_from_slice(&hmac(&mac, b"bukurs key check")[..KEY_CHECK_SIZE]);
+        Ok(Self {
+            enc: hmac(&master, b"bukurs encryption key"),
+            mac,
+            check,
+        })
+    }
+}
+
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = new_mac(key);
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Whether `check` is the key check of `keys`, compared in constant time
+fn key_check_matches(keys: &Keys, check: &[u8]) -> bool {
+    keys.check[..].ct_eq(check).into()
+}
+
+/// Read until `buf` is full or the input ends, returning the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+impl EncHeader {
+    fn to_bytes(&self) -> Vec<u8> {
+        let params = self.kdf.params();
+        let mut bytes = Vec::with_capacity(64 + params.len() + self.salt.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(self.version);
+        bytes.push(self.kdf.id());
+        bytes.extend_from_slice(&(params.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&params);
+        bytes.push(self.salt.len() as u8);
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&self.iv);
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes.extend_from_slice(&self.key_check);
+        bytes
+    }
+
+    /// Read a header, returning it with its raw bytes; None for a legacy file
+    fn read(reader: &mut impl Read) -> Result<Option<(Self, Vec<u8>)>> {
+        let mut magic = [0u8; 8];
+        let magic_len = read_full(reader, &mut magic)?;
+        if magic_len < magic.len() || &magic != MAGIC {
+            return Ok(None);
+        }
+
+        let mut raw = magic.to_vec();
+        let mut take = |n: usize| -> Result<Vec<u8>> {
+            let mut buf = vec![0u8; n];
+            if read_full(reader, &mut buf)? < n {
+                return Err(BukursError::Corrupted("header is truncated".to_string()));
+            }
+            raw.extend_from_slice(&buf);
+            Ok(buf)
+        };
+
+        let version = take(1)?[0];
+        if version != FORMAT_VERSION {
+            return Err(BukursError::Crypto(format!(
+                "unsupported format version {} (written by a newer bukurs?)",
+                version
+            )));
+        }
+        let kdf_id = take(1)?[0];
+        let params_len = take(2)?;
+        let params = take(u16::from_le_bytes([params_len[0], params_len[1]]) as usize)?;
+        let kdf = Kdf::from_parts(kdf_id, &params)?;
+        let salt_len = take(1)?[0] as usize;
+        let salt = take(salt_len)?;
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&take(16)?);
+        let mut size = [0u8; 8];
+        size.copy_from_slice(&take(8)?);
+        let mut key_check = [0u8; KEY_CHECK_SIZE];
+        key_check.copy_from_slice(&take(KEY_CHECK_SIZE)?);
+
+        let header = Self {
+            version,
+            kdf,
+            salt,
+            iv,
+            size: u64::from_le_bytes(size),
+            key_check,
+        };
+        Ok(Some((header, raw)))
+    }
+}
 
 pub struct BukuCrypt;
 
 impl BukuCrypt {
-    const BLOCKSIZE: usize = 0x10000; // 64 KB
     const SALT_SIZE: usize = 0x20;
     const CHUNKSIZE: usize = 0x80000; // 512 KB
 
+    /// Encrypt `dbfile` into `encfile` in the current format, with the SHA-256 KDF
     pub fn encrypt_file(
         iterations: u32,
         dbfile: &Path,
         encfile: &Path,
         password: &str,
-    ) -> crate::error::Result<()> {
-        let dbhash = Self::get_filehash(dbfile)?;
+    ) -> Result<()> {
+        Self::encrypt_file_with_kdf(Kdf::Sha256 { iterations }, dbfile, encfile, password)
+    }
+
+    /// Encrypt `dbfile` into `encfile`, deriving the key with `kdf`
+    pub fn encrypt_file_with_kdf(
+        kdf: Kdf,
+        dbfile: &Path,
+        encfile: &Path,
+        password: &str,
+    ) -> Result<()> {
         let filesize = fs::metadata(dbfile)?.len();
 
-        let mut salt = [0u8; Self::SALT_SIZE];
+        let mut salt = vec![0u8; Self::SALT_SIZE];
         rng().fill_bytes(&mut salt);
-
         let mut iv = [0u8; 16];
         rng().fill_bytes(&mut iv);
 
-        let key = Self::derive_key(password, &salt, iterations);
-        let encryptor = Aes256CbcEnc::new(&key.into(), &iv.into());
-
-        let mut infp = File::open(dbfile)?;
-        let mut outfp = File::create(encfile)?;
-
-        outfp.write_all(&filesize.to_le_bytes())?;
-        outfp.write_all(&salt)?;
-        outfp.write_all(&iv)?;
-        outfp.write_all(&dbhash)?;
+        let keys = Keys::derive(&kdf, password, &salt)?;
+        let header = EncHeader {
+            version: FORMAT_VERSION,
+            kdf,
+            salt,
+            iv,
+            size: filesize,
+            key_check: keys.check,
+        };
+
+        let mut infp = BufReader::new(File::open(dbfile)?);
+        Self::write_atomically(encfile, |outfp| {
+            let mut mac = new_mac(&keys.mac);
+            let header_bytes = header.to_bytes();
+            outfp.write_all(&header_bytes)?;
+            mac.update(&header_bytes);
+
+            let mut encryptor = Aes256CbcEnc::new(&keys.enc.into(), &iv.into());
+            let mut buffer = vec![0u8; Self::CHUNKSIZE + AES_BLOCK];
+            let mut total = 0u64;
+            loop {
+                let read_bytes = read_full(&mut infp, &mut buffer[..Self::CHUNKSIZE])?;
+                total += read_bytes as u64;
+                let last = read_bytes < Self::CHUNKSIZE;
+                let mut len = read_bytes;
+                if last {
+                    // PKCS#7: always at least one byte, a whole block when already aligned
+                    let padding = AES_BLOCK - read_bytes % AES_BLOCK;
+                    buffer[len..len + padding].fill(padding as u8);
+                    len += padding;
+                }
+
+                for block in buffer[..len].chunks_mut(AES_BLOCK) {
+                    encryptor.encrypt_block_mut(
+                        cbc::cipher::generic_array::GenericArray::from_mut_slice(block),
+                    );
+                }
+                outfp.write_all(&buffer[..len])?;
+                mac.update(&buffer[..len]);
+                if last {
+                    break;
+                }
+            }
+            if total != filesize {
+                return Err(BukursError::Crypto(format!(
+                    "{} changed while it was being encrypted",
+                    dbfile.display()
+                )));
+            }
 
-        let mut buffer = vec![0u8; Self::CHUNKSIZE];
-        let mut encryptor = encryptor;
+            outfp.write_all(&mac.finalize().into_bytes())?;
+            Ok(())
+        })
+    }
 
-        loop {
-            let read_bytes = infp.read(&mut buffer)?;
-            if read_bytes == 0 {
-                break;
-            }
+    /// Decrypt `encfile` into `dbfile`
+    ///
+    /// `iterations` is only used for legacy files; versioned files carry
+    /// their own. Fails with [`BukursError::WrongPassword`] or
+    /// [`BukursError::Corrupted`] without writing `dbfile`.
+    pub fn decrypt_file(
+        iterations: u32,
+        dbfile: &Path,
+        encfile: &Path,
+        password: &str,
+    ) -> Result<()> {
+        let mut infp = BufReader::new(File::open(encfile)?);
+        let Some((header, header_bytes)) = EncHeader::read(&mut infp)? else {
+            return Self::decrypt_legacy(iterations, dbfile, encfile, password);
+        };
+
+        let keys = Keys::derive(&header.kdf, password, &header.salt)?;
+        if !key_check_matches(&keys, &header.key_check) {
+            return Err(BukursError::WrongPassword);
+        }
 
-            let chunk = &buffer[..read_bytes];
-            // Padding
-            let padding_len = 16 - (read_bytes % 16);
-            let padding_len = if padding_len == 0 { 0 } else { padding_len };
+        let file_len = fs::metadata(encfile)?.len();
+        let body_len = file_len
+            .checked_sub((header_bytes.len() + TAG_SIZE) as u64)
+            .filter(|len| *len > 0 && len % AES_BLOCK as u64 == 0 && *len > header.size)
+            .ok_or_else(|| BukursError::Corrupted("file is truncated".to_string()))?;
 
-            let mut padded_chunk = chunk.to_vec();
-            if padding_len > 0 {
-                padded_chunk.extend(std::iter::repeat_n(b' ', padding_len));
-            }
+        // Authenticate everything before decrypting any of it
+        let mut mac = new_mac(&keys.mac);
+        mac.update(&header_bytes);
+        let mut buffer = vec![0u8; Self::CHUNKSIZE];
+        let mut remaining = body_len;
+        while remaining > 0 {
+            let want = remaining.min(Self::CHUNKSIZE as u64) as usize;
+            infp.read_exact(&mut buffer[..want])?;
+            mac.update(&buffer[..want]);
+            remaining -= want as u64;
+        }
+        let mut tag = [0u8; TAG_SIZE];
+        infp.read_exact(&mut tag)?;
+        if mac.verify_slice(&tag).is_err() {
+            return Err(BukursError::Corrupted("integrity check failed".to_string()));
+        }
 
-            for block_chunk in padded_chunk.chunks_mut(16) {
-                let block = cbc::cipher::generic_array::GenericArray::from_mut_slice(block_chunk);
-                encryptor.encrypt_block_mut(block);
+        infp.seek(SeekFrom::Start(header_bytes.len() as u64))?;
+        let mut decryptor = Aes256CbcDec::new(&keys.enc.into(), &header.iv.into());
+        Self::write_atomically(dbfile, |outfp| {
+            let mut remaining = body_len;
+            let mut written = 0u64;
+            while remaining > 0 {
+                let want = remaining.min(Self::CHUNKSIZE as u64) as usize;
+                infp.read_exact(&mut buffer[..want])?;
+                for block in buffer[..want].chunks_mut(AES_BLOCK) {
+                    decryptor.decrypt_block_mut(
+                        cbc::cipher::generic_array::GenericArray::from_mut_slice(block),
+                    );
+                }
+                // The tag covers the size, so anything past it is padding
+                let keep = (header.size - written).min(want as u64) as usize;
+                outfp.write_all(&buffer[..keep])?;
+                written += keep as u64;
+                remaining -= want as u64;
             }
-            outfp.write_all(&padded_chunk)?;
-        }
+            Ok(())
+        })
+    }
 
-        Ok(())
+    /// Find out how an encrypted file was written, without a password
+    pub fn inspect(encfile: &Path) -> Result<EncryptedFormat> {
+        let mut infp = BufReader::new(File::open(encfile)?);
+        Ok(match EncHeader::read(&mut infp)? {
+            Some((header, _)) => EncryptedFormat::Versioned(header),
+            None => EncryptedFormat::Legacy,
+        })
     }
 
-    pub fn decrypt_file(
+    /// Decrypt a file written before the versioned header
+    ///
+    /// These carry a hash of the plaintext but no authentication, so a wrong
+    /// password can't be told apart from a damaged file.
+    fn decrypt_legacy(
         iterations: u32,
         dbfile: &Path,
         encfile: &Path,
         password: &str,
-    ) -> crate::error::Result<()> {
+    ) -> Result<()> {
+        check_iterations(iterations)?;
         let mut infp = File::open(encfile)?;
 
         let mut size_bytes = [0u8; 8];
@@ -90,59 +527,73 @@ impl BukuCrypt {
         infp.read_exact(&mut iv)?;
 
         let key = Self::derive_key(password, &salt, iterations);
-        let decryptor = Aes256CbcDec::new(&key.into(), &iv.into());
+        let mut decryptor = Aes256CbcDec::new(&key.into(), &iv.into());
 
         let mut enchash = [0u8; 32];
         infp.read_exact(&mut enchash)?;
 
-        let mut outfp = File::create(dbfile)?;
-        let mut buffer = vec![0u8; Self::CHUNKSIZE];
-        let mut decryptor = decryptor;
-
-        loop {
-            let read_bytes = infp.read(&mut buffer)?;
-            if read_bytes == 0 {
-                break;
+        Self::write_atomically(dbfile, |outfp| {
+            let mut buffer = vec![0u8; Self::CHUNKSIZE];
+            let mut hasher = Sha256::new();
+            let mut written = 0u64;
+
+            loop {
+                let read_bytes = read_full(&mut infp, &mut buffer)?;
+                if read_bytes == 0 {
+                    break;
+                }
+
+                // The encrypted file is padded to 16 bytes
+                let chunk = &mut buffer[..read_bytes];
+                for block_chunk in chunk.chunks_mut(AES_BLOCK) {
+                    if block_chunk.len() < AES_BLOCK {
+                        break;
+                    }
+                    let block =
+                        cbc::cipher::generic_array::GenericArray::from_mut_slice(block_chunk);
+                    decryptor.decrypt_block_mut(block);
+                }
+
+                let keep = (size - written).min(read_bytes as u64) as usize;
+                outfp.write_all(&chunk[..keep])?;
+                hasher.update(&chunk[..keep]);
+                written += keep as u64;
             }
 
-            let chunk = &mut buffer[..read_bytes];
-            // Decrypt in place
-            // Since we read chunks, they should be multiples of 16 (except maybe if file is corrupted or end?)
-            // The encrypted file is padded to 16 bytes.
-
-            for block_chunk in chunk.chunks_mut(16) {
-                let block = cbc::cipher::generic_array::GenericArray::from_mut_slice(block_chunk);
-                decryptor.decrypt_block_mut(block);
+            let dbhash: [u8; 32] = hasher.finalize().into();
+            if dbhash != enchash {
+                return Err(BukursError::Crypto(
+                    "Decryption failed: hash mismatch (wrong password or corrupted file)"
+                        .to_string(),
+                ));
             }
-
-            outfp.write_all(chunk)?;
-        }
-
-        outfp.set_len(size)?;
-
-        let dbhash = Self::get_filehash(dbfile)?;
-        if dbhash != enchash {
-            fs::remove_file(dbfile)?;
-            return Err("Decryption failed: Hash mismatch".into());
-        }
-
-        Ok(())
+            Ok(())
+        })
     }
 
-    fn get_filehash(filepath: &Path) -> Result<[u8; 32], std::io::Error> {
-        let mut file = File::open(filepath)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; Self::BLOCKSIZE];
-
-        loop {
-            let count = file.read(&mut buffer)?;
-            if count == 0 {
-                break;
-            }
-            hasher.update(&buffer[..count]);
+    /// Write `path` through a temporary file next to it, renamed into place
+    /// once `write` succeeds, so a crash never leaves a partial file behind
+    fn write_atomically(
+        path: &Path,
+        write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+    ) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let result = File::create(&tmp)
+            .map_err(BukursError::from)
+            .and_then(|file| {
+                let mut outfp = BufWriter::new(file);
+                write(&mut outfp)?;
+                let file = outfp.into_inner().map_err(|e| e.into_error())?;
+                file.sync_all()?;
+                Ok(fs::rename(&tmp, path)?)
+            });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
         }
-
-        Ok(hasher.finalize().into())
+        result
     }
 
     fn derive_key(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
@@ -151,12 +602,10 @@ impl BukuCrypt {
         let key_material = format!("{}{}", password, salt_str).into_bytes();
 
         let mut key = [0u8; 32];
-        // Initial hash
         // Python loop:
         // for _ in range(self.iterations):
         //     key = self._sha256(key).digest()
-        // Wait, the python code starts with the concatenated string as `key`.
-        // Then hashes it `iterations` times.
+        // starting from the concatenated string, hashed `iterations` times.
 
         let mut current_hash = key_material;
 
@@ -174,36 +623,259 @@ impl BukuCrypt {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
     use std::fs;
 
+    /// Write `data` in the pre-header format, as older versions did
+    fn encrypt_legacy(iterations: u32, data: &[u8], encfile: &Path, password: &str) {
+        let salt = [7u8; 32];
+        let iv = [9u8; 16];
+        let key = BukuCrypt::derive_key(password, &salt, iterations);
+        let mut encryptor = Aes256CbcEnc::new(&key.into(), &iv.into());
+
+        let mut body = data.to_vec();
+        let padding = (AES_BLOCK - body.len() % AES_BLOCK) % AES_BLOCK;
+        body.extend(std::iter::repeat_n(b' ', padding));
+        for block in body.chunks_mut(AES_BLOCK) {
+            encryptor.encrypt_block_mut(cbc::cipher::generic_array::GenericArray::from_mut_slice(
+                block,
+            ));
+        }
+
+        let mut out = File::create(encfile).unwrap();
+        out.write_all(&(data.len() as u64).to_le_bytes()).unwrap();
+        out.write_all(&salt).unwrap();
+        out.write_all(&iv).unwrap();
+        out.write_all(&Sha256::digest(data)).unwrap();
+        out.write_all(&body).unwrap();
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        let tag = hmac(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = tag.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
     #[test]
-    fn test_encrypt_decrypt() {
-        let dbfile = Path::new("test_crypto.db");
-        let encfile = Path::new("test_crypto.db.enc");
-        let password = "password123";
+    fn test_argon2id_rfc9106() {
+        let params = argon2::ParamsBuilder::new()
+            .m_cost(32)
+            .t_cost(3)
+            .p_cost(4)
+            .data(argon2::AssociatedData::new(&[4; 12]).unwrap())
+            .output_len(32)
+            .build()
+            .unwrap();
+        let argon2 =
+            Argon2::new_with_secret(&[3; 8], Algorithm::Argon2id, Version::V0x13, params).unwrap();
+        let mut tag = [0u8; 32];
+        argon2
+            .hash_password_into(&[1; 32], &[2; 16], &mut tag)
+            .unwrap();
+        let hex: String = tag.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[rstest]
+    #[case(b"dummy data for encryption test".to_vec())]
+    #[case(Vec::new())]
+    #[case(vec![0u8; 32])]
+    #[case((0..BukuCrypt::CHUNKSIZE + 5).map(|i| i as u8).collect())]
+    fn test_encrypt_decrypt(#[case] data: Vec<u8>) {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        fs::write(&dbfile, &data).unwrap();
+
+        BukuCrypt::encrypt_file(8, &dbfile, &encfile, "password123").unwrap();
+        fs::remove_file(&dbfile).unwrap();
+        // The file's own iteration count is used, not the one passed in
+        BukuCrypt::decrypt_file(1, &dbfile, &encfile, "password123").unwrap();
+
+        assert_eq!(fs::read(&dbfile).unwrap(), data);
+    }
 
-        // Create dummy DB file
-        let mut file = File::create(dbfile).unwrap();
-        file.write_all(b"dummy data for encryption test").unwrap();
+    #[test]
+    fn test_inspect_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        fs::write(&dbfile, b"data").unwrap();
+        BukuCrypt::encrypt_file(8, &dbfile, &encfile, "pw").unwrap();
+
+        let EncryptedFormat::Versioned(header) = BukuCrypt::inspect(&encfile).unwrap() else {
+            panic!("expected a versioned header");
+        };
+        assert_eq!(header.version, FORMAT_VERSION);
+        assert_eq!(header.kdf, Kdf::Sha256 { iterations: 8 });
+        assert_eq!(header.salt.len(), BukuCrypt::SALT_SIZE);
+        assert_eq!(header.size, 4);
+        assert!(fs::read(&encfile).unwrap().starts_with(MAGIC));
+    }
 
-        // Encrypt
-        BukuCrypt::encrypt_file(8, dbfile, encfile, password).unwrap();
+    #[test]
+    fn test_argon2id_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        fs::write(&dbfile, b"argon2 protected").unwrap();
+
+        let kdf = Kdf::argon2id(64, 2);
+        BukuCrypt::encrypt_file_with_kdf(kdf, &dbfile, &encfile, "pw").unwrap();
+        let EncryptedFormat::Versioned(header) = BukuCrypt::inspect(&encfile).unwrap() else {
+            panic!("expected a versioned header");
+        };
+        assert_eq!(header.kdf, kdf);
+        assert_eq!(
+            header.kdf.to_string(),
+            "Argon2id, 64 KiB, 2 passes, 1 lane(s)"
+        );
+
+        fs::remove_file(&dbfile).unwrap();
+        assert!(matches!(
+            BukuCrypt::decrypt_file(8, &dbfile, &encfile, "wrong"),
+            Err(BukursError::WrongPassword)
+        ));
+        BukuCrypt::decrypt_file(8, &dbfile, &encfile, "pw").unwrap();
+        assert_eq!(fs::read(&dbfile).unwrap(), b"argon2 protected");
+    }
 
-        // Remove original DB
-        fs::remove_file(dbfile).unwrap();
+    #[test]
+    fn test_kdf_params_round_trip() {
+        assert_eq!(
+            Kdf::argon2id(ARGON2_DEFAULT_MEMORY_KIB, 3).to_string(),
+            "Argon2id, 64 MiB, 3 passes, 1 lane(s)"
+        );
+        for kdf in [Kdf::Sha256 { iterations: 8 }, Kdf::argon2id(65536, 3)] {
+            assert_eq!(Kdf::from_parts(kdf.id(), &kdf.params()).unwrap(), kdf);
+        }
+        // Argon2id needs at least 8 KiB per lane
+        assert!(Kdf::from_parts(2, &Kdf::argon2id(4, 3).params()).is_err());
+        for (memory_kib, lanes) in [
+            (ARGON2_MAX_MEMORY_KIB + 1, 1),
+            (u32::MAX, 1),
+            (u32::MAX, u32::MAX),
+            (ARGON2_MAX_MEMORY_KIB, ARGON2_MAX_LANES + 1),
+        ] {
+            let kdf = Kdf::Argon2id {
+                memory_kib,
+                passes: 1,
+                lanes,
+            };
+            assert!(matches!(
+                Kdf::from_parts(2, &kdf.params()),
+                Err(BukursError::Corrupted(_))
+            ));
+        }
+        assert!(matches!(
+            Kdf::from_parts(1, &0u32.to_le_bytes()),
+            Err(BukursError::Corrupted(_))
+        ));
+        assert!(matches!(
+            Kdf::from_parts(9, &[]),
+            Err(BukursError::Crypto(_))
+        ));
+        assert!(Kdf::Sha256 { iterations: 0 }
+            .derive("pw", &[0; 32])
+            .is_err());
+    }
 
-        // Decrypt
-        BukuCrypt::decrypt_file(8, dbfile, encfile, password).unwrap();
+    #[test]
+    fn test_crafted_argon2_header_is_corrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        fs::write(&dbfile, b"data").unwrap();
+        BukuCrypt::encrypt_file_with_kdf(Kdf::argon2id(64, 1), &dbfile, &encfile, "pw").unwrap();
+        fs::remove_file(&dbfile).unwrap();
+
+        // Memory is the first KDF param, after magic, version, id and length
+        let mut bytes = fs::read(&encfile).unwrap();
+        bytes[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&encfile, &bytes).unwrap();
+        assert!(matches!(
+            BukuCrypt::inspect(&encfile),
+            Err(BukursError::Corrupted(_))
+        ));
+        assert!(matches!(
+            BukuCrypt::decrypt_file(8, &dbfile, &encfile, "pw"),
+            Err(BukursError::Corrupted(_))
+        ));
+        assert!(!dbfile.exists());
+    }
 
-        // Verify content
-        let mut file = File::open(dbfile).unwrap();
-        let mut content = Vec::new();
-        file.read_to_end(&mut content).unwrap();
+    #[test]
+    fn test_wrong_password_vs_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        fs::write(&dbfile, vec![42u8; 1000]).unwrap();
+        BukuCrypt::encrypt_file(8, &dbfile, &encfile, "right").unwrap();
+        fs::remove_file(&dbfile).unwrap();
+
+        assert!(matches!(
+            BukuCrypt::decrypt_file(8, &dbfile, &encfile, "wrong"),
+            Err(BukursError::WrongPassword)
+        ));
+
+        let original = fs::read(&encfile).unwrap();
+        let mut flipped = original.clone();
+        let last = flipped.len() - TAG_SIZE - 1;
+        flipped[last] ^= 1;
+        fs::write(&encfile, &flipped).unwrap();
+        assert!(matches!(
+            BukuCrypt::decrypt_file(8, &dbfile, &encfile, "right"),
+            Err(BukursError::Corrupted(_))
+        ));
+
+        fs::write(&encfile, &original[..original.len() - 40]).unwrap();
+        assert!(matches!(
+            BukuCrypt::decrypt_file(8, &dbfile, &encfile, "right"),
+            Err(BukursError::Corrupted(_))
+        ));
+        assert!(!dbfile.exists());
+    }
 
-        assert_eq!(content, b"dummy data for encryption test");
+    #[test]
+    fn test_decrypt_legacy_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        encrypt_legacy(8, b"old archive", &encfile, "pw");
+
+        assert_eq!(
+            BukuCrypt::inspect(&encfile).unwrap(),
+            EncryptedFormat::Legacy
+        );
+        BukuCrypt::decrypt_file(8, &dbfile, &encfile, "pw").unwrap();
+        assert_eq!(fs::read(&dbfile).unwrap(), b"old archive");
+
+        fs::remove_file(&dbfile).unwrap();
+        assert!(BukuCrypt::decrypt_file(8, &dbfile, &encfile, "other").is_err());
+        assert!(!dbfile.exists());
+    }
 
-        // Cleanup
-        fs::remove_file(dbfile).unwrap();
-        fs::remove_file(encfile).unwrap();
+    #[test]
+    fn test_failed_decrypt_keeps_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let dbfile = dir.path().join("test.db");
+        let encfile = dir.path().join("test.db.enc");
+        encrypt_legacy(8, b"old archive", &encfile, "pw");
+
+        // A failure part-way through leaves neither a partial file nor a temp file
+        fs::write(&dbfile, b"current database").unwrap();
+        assert!(BukuCrypt::decrypt_file(8, &dbfile, &encfile, "other").is_err());
+        assert_eq!(fs::read(&dbfile).unwrap(), b"current database");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        BukuCrypt::encrypt_file(8, &dbfile, &encfile, "pw").unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
     }
 }
@@ -0,0 +1,613 @@
+//! Scheduled maintenance jobs run by `bukurs daemon`
+//!
+//! Jobs are configured under `daemon.jobs` in the config file, each with a
+//! cron-like [`Schedule`]:
+//!
+//! ```yaml
+//! daemon:
+//!   jobs:
+//!     nightly-backup:
+//!       run: backup
+//!       schedule: "30 3 * * *"
+//!       keep: 14
+//!     links:
+//!       run: check
+//!       schedule: "@weekly"
+//!     cleanup:
+//!       run: expire
+//!       schedule: "@daily"
+//!     pages:
+//!       run: monitor
+//!       schedule: "0 */6 * * *"
+//! ```
+//!
+//! A running daemon keeps a JSON status file next to the database
+//! (`bookmarks.daemon.json` for `bookmarks.db`) and stops when a stop file
+//! appears beside it, so `daemon status` and `daemon stop` work from any shell
+//! without signals.
+
+use crate::cancel::CancellationToken;
+use crate::config::Config;
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::fetch::check_link;
+use crate::monitor;
+use crate::operations::expiry;
+use crate::progress::NoProgress;
+use crate::schedule::Schedule;
+use crate::service::BookmarkService;
+use crate::utils::format_rfc3339;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Backups kept by a backup job when `keep` isn't set
+pub const DEFAULT_BACKUP_KEEP: usize = 7;
+
+/// How often a running daemon rewrites its status file, in seconds
+pub const HEARTBEAT_SECS: i64 = 60;
+
+/// How often the daemon wakes to look for due jobs and stop requests
+const TICK: Duration = Duration::from_secs(1);
+
+/// Prefix and extension of backup file names
+const BACKUP_PREFIX: &str = "bukurs-";
+const BACKUP_EXT: &str = ".db";
+
+/// What a job does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// Report bookmarks whose URL no longer resolves
+    Check,
+    /// Re-fetch every bookmark's title and description
+    Refresh,
+    /// Copy the database into a backup directory, pruning old copies
+    Backup,
+    /// Delete bookmarks whose expiry has passed (see `add --expires`)
+    Expire,
+    /// Re-hash monitored pages and flag the ones that changed (see [`crate::monitor`])
+    Monitor,
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Check => "check",
+            Self::Refresh => "refresh",
+            Self::Backup => "backup",
+            Self::Expire => "expire",
+            Self::Monitor => "monitor",
+        })
+    }
+}
+
+/// One configured job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobConfig {
+    pub run: JobKind,
+    /// Cron expression, see [`crate::schedule`]
+    pub schedule: String,
+    /// Backup directory; `backups/` next to the database when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Number of backups kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+/// Jobs for `bukurs daemon`, configured under `daemon` in the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Jobs by name
+    #[serde(default)]
+    pub jobs: BTreeMap<String, JobConfig>,
+}
+
+impl DaemonConfig {
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+/// Files a daemon uses to talk to `daemon status` and `daemon stop`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonPaths {
+    pub status: PathBuf,
+    pub stop: PathBuf,
+}
+
+impl DaemonPaths {
+    /// Paths for the daemon serving the database at `db_path`
+    pub fn for_db(db_path: &Path) -> Self {
+        Self {
+            status: db_path.with_extension("daemon.json"),
+            stop: db_path.with_extension("daemon.stop"),
+        }
+    }
+
+    /// Ask a running daemon to stop at its next tick
+    pub fn request_stop(&self) -> Result<()> {
+        fs::write(&self.stop, "")?;
+        Ok(())
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stop.exists()
+    }
+}
+
+/// Last and next run of one job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub run: JobKind,
+    pub schedule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<i64>,
+    /// Summary of the last run, or its error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_result: Option<String>,
+    #[serde(default)]
+    pub last_ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run: Option<i64>,
+}
+
+/// Contents of the status file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: i64,
+    /// Last time the daemon wrote this file
+    pub heartbeat: i64,
+    /// False once the daemon has exited
+    pub running: bool,
+    pub jobs: Vec<JobStatus>,
+}
+
+impl DaemonStatus {
+    /// Read a status file; None when there is none
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|e| BukursError::Json(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the status file through a temporary file, so readers never see half of it
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| BukursError::Json(e.to_string()))?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Whether the daemon is running, judged by a recent heartbeat
+    ///
+    /// A daemon that was killed can't clear `running`, so a heartbeat older
+    /// than two intervals counts as stopped.
+    pub fn is_alive(&self, now: i64) -> bool {
+        self.running && now - self.heartbeat <= 2 * HEARTBEAT_SECS
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Report bookmarks whose links are broken
+fn check_links(db: &BukuDb, config: &Config, cancel: &CancellationToken) -> Result<String> {
+    let options = config.fetch_options();
+    let client = options.client()?;
+    let bookmarks = db.get_rec_all()?;
+    let mut broken = Vec::new();
+    for bookmark in &bookmarks {
+        if cancel.is_cancelled() {
+            return Err(BukursError::Cancelled);
+        }
+        if let Err(e) = check_link(&client, &options, &bookmark.url) {
+            log::info!(
+                "Bookmark {} ({}) is broken: {}",
+                bookmark.id,
+                bookmark.url,
+                e
+            );
+            broken.push(bookmark.id.to_string());
+        }
+    }
+    Ok(if broken.is_empty() {
+        format!("{} links ok", bookmarks.len())
+    } else {
+        format!(
+            "{} of {} links broken: {}",
+            broken.len(),
+            bookmarks.len(),
+            broken.join(",")
+        )
+    })
+}
+
+fn refresh(db: &BukuDb, config: &Config, cancel: &CancellationToken) -> Result<String> {
+    let service = BookmarkService::new(db, config.fetch_options());
+    let outcome = service.refresh(&db.get_rec_all()?, &NoProgress, cancel)?;
+    if outcome.cancelled {
+        return Err(BukursError::Cancelled);
+    }
+    Ok(format!(
+        "{} refreshed, {} failed",
+        outcome.refreshed,
+        outcome.failed.len()
+    ))
+}
+
+fn check_monitors(db: &BukuDb, config: &Config, cancel: &CancellationToken) -> Result<String> {
+    let outcome = monitor::check(db, &config.fetch_options(), &NoProgress, cancel)?;
+    if outcome.cancelled {
+        return Err(BukursError::Cancelled);
+    }
+    Ok(format!(
+        "{} checked, {} changed, {} failed",
+        outcome.checked,
+        outcome.changed.len(),
+        outcome.failed.len()
+    ))
+}
+
+/// Name of the backup taken at `ts`; these sort by time
+fn backup_name(ts: i64) -> String {
+    let stamp: String = format_rfc3339(ts)
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect();
+    format!("{}{}{}", BACKUP_PREFIX, stamp, BACKUP_EXT)
+}
+
+/// Copy the database into `dir`, then delete all but the newest `keep` backups
+pub fn backup(db: &BukuDb, dir: &Path, keep: usize, ts: i64) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(backup_name(ts));
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    db.backup_to(&path)?;
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(BACKUP_EXT))
+        })
+        .collect();
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in &backups[..excess] {
+        fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+/// Run one job now, returning a one-line summary
+///
+/// Jobs take turns with bukurs commands changing the same database, holding
+/// the lock only while they write: `check` and `backup` only read, and
+/// `refresh` and `monitor` fetch without it.
+pub fn run_job(
+    db: &BukuDb,
+    db_path: &Path,
+    config: &Config,
+    job: &JobConfig,
+    cancel: &CancellationToken,
+) -> Result<String> {
+    match job.run {
+        JobKind::Check => check_links(db, config, cancel),
+        JobKind::Refresh => refresh(db, config, cancel),
+        JobKind::Backup => {
+            let dir = job.dir.clone().unwrap_or_else(|| {
+                db_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("."))
+                    .join("backups")
+            });
+            let path = backup(db, &dir, job.keep.unwrap_or(DEFAULT_BACKUP_KEEP), now())?;
+            Ok(format!("saved {}", path.display()))
+        }
+        JobKind::Expire => {
+            let _lock = db.write_lock()?;
+            let deleted = expiry::delete_expired(db, now())?;
+            Ok(format!("{} expired bookmarks deleted", deleted))
+        }
+        JobKind::Monitor => check_monitors(db, config, cancel),
+    }
+}
+
+/// Scheduler state of a running daemon
+pub struct Daemon<'a> {
+    db: &'a BukuDb,
+    db_path: &'a Path,
+    config: &'a Config,
+    jobs: Vec<(JobConfig, Schedule)>,
+    status: DaemonStatus,
+    paths: DaemonPaths,
+}
+
+impl<'a> Daemon<'a> {
+    /// Check the configured jobs and schedule their first runs after `now`
+    pub fn new(db: &'a BukuDb, db_path: &'a Path, config: &'a Config, now: i64) -> Result<Self> {
+        if config.daemon.is_empty() {
+            return Err(BukursError::Config(
+                "no daemon jobs configured (add them under `daemon.jobs` in the config)"
+                    .to_string(),
+            ));
+        }
+
+        let mut jobs = Vec::new();
+        let mut statuses = Vec::new();
+        for (name, job) in &config.daemon.jobs {
+            let schedule: Schedule = job
+                .schedule
+                .parse()
+                .map_err(|e| BukursError::Config(format!("daemon job '{}': {}", name, e)))?;
+            let next_run = schedule.next_after(now).ok_or_else(|| {
+                BukursError::Config(format!(
+                    "daemon job '{}': schedule '{}' never fires",
+                    name, schedule
+                ))
+            })?;
+            statuses.push(JobStatus {
+                name: name.clone(),
+                run: job.run,
+                schedule: schedule.to_string(),
+                last_run: None,
+                last_result: None,
+                last_ok: false,
+                next_run: Some(next_run),
+            });
+            jobs.push((job.clone(), schedule));
+        }
+
+        Ok(Self {
+            db,
+            db_path,
+            config,
+            jobs,
+            status: DaemonStatus {
+                pid: std::process::id(),
+                started_at: now,
+                heartbeat: now,
+                running: true,
+                jobs: statuses,
+            },
+            paths: DaemonPaths::for_db(db_path),
+        })
+    }
+
+    pub fn status(&self) -> &DaemonStatus {
+        &self.status
+    }
+
+    /// Run the jobs due at `now`, returning how many ran
+    pub fn run_due(&mut self, now: i64, cancel: &CancellationToken) -> usize {
+        let mut ran = 0;
+        for ((job, schedule), status) in self.jobs.iter().zip(self.status.jobs.iter_mut()) {
+            if cancel.is_cancelled() {
+                break;
+            }
+            if status.next_run.is_none_or(|next| next > now) {
+                continue;
+            }
+
+            log::info!("Running daemon job '{}'", status.name);
+            let result = run_job(self.db, self.db_path, self.config, job, cancel);
+            let finished = self::now().max(now);
+            status.last_run = Some(now);
+            status.last_ok = result.is_ok();
+            status.last_result = Some(match result {
+                Ok(summary) => summary,
+                Err(e) => e.to_string(),
+            });
+            status.next_run = schedule.next_after(finished);
+            ran += 1;
+        }
+        ran
+    }
+
+    /// Write the status file with a fresh heartbeat
+    pub fn write_status(&mut self, now: i64) -> Result<()> {
+        self.status.heartbeat = now;
+        self.status.write(&self.paths.status)
+    }
+
+    /// Run jobs as they come due until cancelled or asked to stop
+    pub fn run(mut self, cancel: &CancellationToken) -> Result<()> {
+        // A stop request left over from an earlier daemon isn't meant for this one
+        let _ = fs::remove_file(&self.paths.stop);
+        self.write_status(now())?;
+
+        let mut last_write = now();
+        while !cancel.is_cancelled() && !self.paths.stop_requested() {
+            let now = now();
+            let ran = self.run_due(now, cancel);
+            if ran > 0 || now - last_write >= HEARTBEAT_SECS {
+                self.write_status(now)?;
+                last_write = now;
+            }
+            std::thread::sleep(TICK);
+        }
+
+        self.status.running = false;
+        let _ = fs::remove_file(&self.paths.stop);
+        self.write_status(now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-05-01T12:00:00Z
+    const NOON: i64 = 1_714_564_800;
+
+    fn config(jobs: &str) -> Config {
+        Config {
+            daemon: serde_yaml::from_str(jobs).unwrap(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_daemon_paths_for_db() {
+        let paths = DaemonPaths::for_db(Path::new("/data/bookmarks.db"));
+        assert_eq!(paths.status, PathBuf::from("/data/bookmarks.daemon.json"));
+        assert_eq!(paths.stop, PathBuf::from("/data/bookmarks.daemon.stop"));
+    }
+
+    #[test]
+    fn test_backup_prunes_old_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+
+        for day in 0..4 {
+            backup(&db, dir.path(), 2, NOON + day * 86_400).unwrap();
+        }
+        let mut names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["bukurs-20240503T120000Z.db", "bukurs-20240504T120000Z.db"]
+        );
+
+        let copy = BukuDb::open(&dir.path().join(&names[1])).unwrap();
+        assert_eq!(copy.get_rec_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_daemon_requires_valid_jobs() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let path = Path::new("bookmarks.db");
+
+        let empty = Config::default();
+        assert!(Daemon::new(&db, path, &empty, NOON).is_err());
+        let bad = config("jobs: {b: {run: backup, schedule: '* * *'}}");
+        assert!(Daemon::new(&db, path, &bad, NOON).is_err());
+        let never = config("jobs: {b: {run: backup, schedule: '0 0 30 2 *'}}");
+        assert!(Daemon::new(&db, path, &never, NOON).is_err());
+    }
+
+    #[test]
+    fn test_run_due_runs_and_reschedules() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+        let db = BukuDb::init(&db_path).unwrap();
+        let config = config(&format!(
+            "jobs:\n  nightly: {{run: backup, schedule: '0 3 * * *', dir: '{}'}}",
+            dir.path().join("copies").display()
+        ));
+        let cancel = CancellationToken::new();
+
+        let mut daemon = Daemon::new(&db, &db_path, &config, NOON).unwrap();
+        let next = daemon.status().jobs[0].next_run.unwrap();
+        assert_eq!(format_rfc3339(next), "2024-05-02T03:00:00Z");
+
+        assert_eq!(daemon.run_due(next - 60, &cancel), 0);
+        assert_eq!(daemon.run_due(next, &cancel), 1);
+        let job = &daemon.status().jobs[0];
+        assert!(job.last_ok, "{:?}", job.last_result);
+        assert_eq!(job.last_run, Some(next));
+        assert!(job.next_run.unwrap() > next);
+        assert_eq!(fs::read_dir(dir.path().join("copies")).unwrap().count(), 1);
+
+        daemon.write_status(next).unwrap();
+        let status = DaemonStatus::read(&DaemonPaths::for_db(&db_path).status)
+            .unwrap()
+            .unwrap();
+        assert_eq!(&status, daemon.status());
+        assert!(status.is_alive(next + HEARTBEAT_SECS));
+        assert!(!status.is_alive(next + 3 * HEARTBEAT_SECS));
+    }
+
+    #[test]
+    fn test_only_writing_jobs_wait_for_the_lock() {
+        use crate::db::lock::DbLock;
+        use crate::db::ConnectionOptions;
+        use std::sync::mpsc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+        let options = ConnectionOptions {
+            lock_timeout_ms: 100,
+            ..ConnectionOptions::default()
+        };
+        let db = BukuDb::init_with_options(&db_path, &options).unwrap();
+        let job = |run| JobConfig {
+            run,
+            schedule: "@daily".to_string(),
+            dir: Some(dir.path().join("copies")),
+            keep: None,
+        };
+
+        // Another bukurs command holds the lock for a while
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder = {
+            let db_path = db_path.clone();
+            std::thread::spawn(move || {
+                let _lock = DbLock::acquire(&db_path, Duration::ZERO).unwrap();
+                locked_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+            })
+        };
+        locked_rx.recv().unwrap();
+
+        let config = Config::default();
+        let cancel = CancellationToken::new();
+        run_job(&db, &db_path, &config, &job(JobKind::Backup), &cancel).unwrap();
+        assert!(matches!(
+            run_job(&db, &db_path, &config, &job(JobKind::Expire), &cancel),
+            Err(BukursError::Locked(_))
+        ));
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+        run_job(&db, &db_path, &config, &job(JobKind::Expire), &cancel).unwrap();
+    }
+
+    #[test]
+    fn test_run_stops_on_stop_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+        let db = BukuDb::init(&db_path).unwrap();
+        let config = config("jobs: {b: {run: backup, schedule: '@yearly'}}");
+        let paths = DaemonPaths::for_db(&db_path);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        Daemon::new(&db, &db_path, &config, now())
+            .unwrap()
+            .run(&cancel)
+            .unwrap();
+
+        let status = DaemonStatus::read(&paths.status).unwrap().unwrap();
+        assert!(!status.running);
+        assert!(!paths.stop.exists());
+        assert_eq!(
+            DaemonStatus::read(&dir.path().join("none.json")).unwrap(),
+            None
+        );
+    }
+}
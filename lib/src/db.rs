@@ -1,13 +1,334 @@
+use crate::audit::{AuditLog, AuditOperation, AuditSource};
+use crate::autotag::AutotagRules;
+use crate::cancel::CancellationToken;
+use crate::collection::{Collection, CollectionExpr};
 use crate::commands::{UndoCommand, UndoLogData};
+use crate::crypto::FIELD_MARKER;
+use crate::events::{BookmarkEvent, EventBus, EVENT_RETENTION};
+use crate::import_export::dump::{DumpedBookmark, UndoEntry};
+use crate::import_export::import::{
+    is_importable_url, resolve_duplicate, DuplicateStrategy, ImportStats, ParsedBookmark,
+    ReportEntry, ReportReason,
+};
 use crate::models::bookmark::Bookmark;
+use crate::monitor::Monitor;
+use crate::search::{SavedSearch, SearchWeights, SiteFilter};
+use crate::tags::{apply_tag_ops, TagOp};
 use crate::utils;
-use rusqlite::{Connection, Result};
+use rusqlite::backup::Progress;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{
+    Connection, OpenFlags, OptionalExtension, Result, Transaction, TransactionBehavior,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod cipher;
+pub mod lock;
+mod migrations;
+pub mod query;
+
+pub use migrations::SCHEMA_VERSION;
+
+/// Path of in-memory databases, as given to `--db` or [`BukuDb::init`]
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// Queries kept in the search history
+pub const SEARCH_HISTORY_LIMIT: usize = 1000;
+
+/// Minimum lock wait for import workers, which write concurrently
+const WORKER_BUSY_TIMEOUT_MS: u64 = 30_000;
+
+/// SQLite journal mode (`PRAGMA journal_mode`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    #[default]
+    Wal,
+    Off,
+}
+
+impl JournalMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::Delete => "DELETE",
+            Self::Truncate => "TRUNCATE",
+            Self::Persist => "PERSIST",
+            Self::Memory => "MEMORY",
+            Self::Wal => "WAL",
+            Self::Off => "OFF",
+        }
+    }
+}
+
+/// SQLite durability level (`PRAGMA synchronous`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    #[default]
+    Normal,
+    Full,
+    Extra,
+}
+
+impl Synchronous {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Normal => "NORMAL",
+            Self::Full => "FULL",
+            Self::Extra => "EXTRA",
+        }
+    }
+}
+
+/// Connection tuning, configured under `database` in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionOptions {
+    /// Journal mode; WAL lets readers and a writer work at the same time
+    pub journal_mode: JournalMode,
+    /// How long to wait for a locked database, in milliseconds (0 fails immediately)
+    pub busy_timeout_ms: u64,
+    /// Durability level; NORMAL is safe with WAL and much faster than FULL
+    pub synchronous: Synchronous,
+    /// Enforce foreign key constraints
+    pub foreign_keys: bool,
+    /// Make commands that change the database wait for each other (see [`lock::DbLock`])
+    pub lock: bool,
+    /// How long to wait for another process's command to finish, in milliseconds
+    pub lock_timeout_ms: u64,
+    /// The database is a SQLCipher file, opened with a key (see [`cipher`])
+    pub encrypted: bool,
+    /// Command printing the key of an encrypted database, e.g. a keyring lookup
+    pub key_command: Option<String>,
+    /// The key connections are opened with, filled by [`ConnectionOptions::unlock`]
+    #[serde(skip)]
+    pub key: Option<cipher::DbKey>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            journal_mode: JournalMode::Wal,
+            busy_timeout_ms: 5_000,
+            synchronous: Synchronous::Normal,
+            foreign_keys: true,
+            lock: true,
+            lock_timeout_ms: 30_000,
+            encrypted: false,
+            key_command: None,
+            key: None,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    /// Take the lock on `db_path` when enabled; `None` when locking is off or
+    /// the database is in memory, where no other process can reach it
+    pub fn lock_database(&self, db_path: &Path) -> crate::error::Result<Option<lock::DbLock>> {
+        if !self.lock || db_path == Path::new(MEMORY_PATH) {
+            return Ok(None);
+        }
+        lock::DbLock::acquire(db_path, Duration::from_millis(self.lock_timeout_ms)).map(Some)
+    }
+
+    /// Find the key of an encrypted database (see [`cipher::find_key`]), unless
+    /// it isn't encrypted or the key is known already
+    pub fn unlock(
+        &mut self,
+        prompt: impl FnOnce() -> crate::error::Result<String>,
+    ) -> crate::error::Result<()> {
+        if self.encrypted && self.key.is_none() {
+            self.key = Some(cipher::find_key(self.key_command.as_deref(), prompt)?);
+        }
+        Ok(())
+    }
+}
+
+/// How searches find bookmarks, see [`BukuDb::search_backend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchBackend {
+    /// The `bookmarks_fts` full-text index, ranked by relevance
+    Fts5,
+    /// Substring matching with LIKE, when SQLite lacks FTS5 or the `fts` feature is off
+    Like,
+}
+
+impl std::fmt::Display for SearchBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Fts5 => "fts5",
+            Self::Like => "like",
+        })
+    }
+}
+
+/// SQL condition matching bookmarks that aren't encrypted (see `crypto::FieldCipher`)
+fn plaintext_condition() -> String {
+    format!("URL NOT LIKE '{}%'", FIELD_MARKER)
+}
+
+/// Insert `record` as a new bookmark, returning its ID
+///
+/// This and [`update_fields`] are the only places bookmark text is written;
+/// titles and tags are stored composed (NFC), so text typed or imported
+/// either way compares equal.
+fn insert_bookmark(conn: &Connection, record: &ParsedBookmark, created_at: i64) -> Result<usize> {
+    conn.prepare_cached(
+        "INSERT INTO bookmarks (URL, metadata, tags, desc, parent_id, flags, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?
+    .execute((
+        &record.url,
+        utils::nfc(&record.title),
+        utils::nfc(&record.tags),
+        &record.desc,
+        record.parent_id,
+        record.flags,
+        record.created_at.unwrap_or(created_at),
+    ))?;
+    Ok(conn.last_insert_rowid() as usize)
+}
+
+/// Columns a bookmark update writes; `None` leaves a column as it is
+#[derive(Default)]
+struct FieldUpdate<'a> {
+    url: Option<&'a str>,
+    title: Option<&'a str>,
+    tags: Option<&'a str>,
+    desc: Option<&'a str>,
+    parent_id: Option<Option<usize>>,
+    flags: Option<u8>,
+}
+
+impl<'a> FieldUpdate<'a> {
+    /// Every text column set to `bookmark`'s
+    fn overwrite(bookmark: &'a Bookmark) -> Self {
+        Self {
+            url: Some(&bookmark.url),
+            title: Some(&bookmark.title),
+            tags: Some(&bookmark.tags),
+            desc: Some(&bookmark.description),
+            ..Self::default()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.url.is_none()
+            && self.title.is_none()
+            && self.tags.is_none()
+            && self.desc.is_none()
+            && self.parent_id.is_none()
+            && self.flags.is_none()
+    }
+}
+
+/// Write `fields` to bookmark `id`, returning the number of rows changed
+///
+/// Titles and tags are stored composed (NFC), as by [`insert_bookmark`].
+fn update_fields(conn: &Connection, id: usize, fields: &FieldUpdate) -> Result<usize> {
+    let title = fields.title.map(utils::nfc);
+    let tags = fields.tags.map(utils::nfc);
+    let mut columns = Vec::new();
+    let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+    if let Some(url) = &fields.url {
+        columns.push("URL");
+        params.push(url);
+    }
+    if let Some(title) = &title {
+        columns.push("metadata");
+        params.push(title);
+    }
+    if let Some(tags) = &tags {
+        columns.push("tags");
+        params.push(tags);
+    }
+    if let Some(desc) = &fields.desc {
+        columns.push("desc");
+        params.push(desc);
+    }
+    if let Some(parent_id) = &fields.parent_id {
+        columns.push("parent_id");
+        params.push(parent_id);
+    }
+    if let Some(flags) = &fields.flags {
+        columns.push("flags");
+        params.push(flags);
+    }
+    if columns.is_empty() {
+        return Ok(0);
+    }
+    params.push(&id);
+
+    let assignments: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+        .collect();
+    conn.prepare_cached(&format!(
+        "UPDATE bookmarks SET {} WHERE id = ?{}",
+        assignments.join(", "),
+        params.len()
+    ))?
+    .execute(params.as_slice())
+}
+
+/// Make the SQL functions bukurs queries rely on available on `conn`:
+/// `fold_diacritics(text)` is [`utils::fold_diacritics`]
+fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "fold_diacritics",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            Ok(ctx
+                .get::<Option<String>>(0)?
+                .map(|s| utils::fold_diacritics(&s)))
+        },
+    )
+}
+
+/// Escape `%`, `_` and `\` for a LIKE pattern with `ESCAPE '\'`
+fn escape_like(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A `saved_searches` row
+fn saved_search(name: &str, query: &str) -> crate::error::Result<SavedSearch> {
+    Ok(SavedSearch {
+        name: name.to_string(),
+        ..serde_json::from_str(query)?
+    })
+}
 
 pub struct BukuDb {
     conn: Connection,
     db_path: PathBuf,
+    autotag: AutotagRules,
+    options: ConnectionOptions,
+    audit: Option<AuditLog>,
+    /// Frontend this connection's changes are attributed to in events
+    source: AuditSource,
+    events: EventBus,
+    search_backend: SearchBackend,
 }
 
 impl BukuDb {
@@ -30,196 +351,468 @@ impl BukuDb {
     pub fn init_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
         let db = Self {
+            search_backend: Self::detect_search_backend(&conn),
             conn,
-            db_path: PathBuf::from(":memory:"),
+            db_path: PathBuf::from(MEMORY_PATH),
+            autotag: AutotagRules::default(),
+            audit: None,
+            source: AuditSource::default(),
+            events: EventBus::new(),
+            options: ConnectionOptions::default(),
         };
+        db.apply_options()?;
         db.setup_tables()?;
         Ok(db)
     }
 
     pub fn init(db_path: &Path) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        let db = Self {
-            conn,
-            db_path: db_path.to_path_buf(),
-        };
+        Self::init_with_options(db_path, &ConnectionOptions::default())
+    }
+
+    /// Open or create the database at `db_path` with the given connection tuning
+    pub fn init_with_options(db_path: &Path, options: &ConnectionOptions) -> Result<Self> {
+        let db = Self::connect(db_path, options)?;
         db.setup_tables()?;
         Ok(db)
     }
 
+    /// Whether opening `db_path` with [`Self::init_with_options`] would create
+    /// it or migrate its schema
+    pub fn needs_migration(db_path: &Path, options: &ConnectionOptions) -> Result<bool> {
+        if !db_path.exists() {
+            return Ok(true);
+        }
+        let db = Self::connect(db_path, options)?;
+        Ok(migrations::current_version(&db.conn)? < SCHEMA_VERSION)
+    }
+
     /// Open an existing database without creating tables (for worker threads)
     pub fn open(db_path: &Path) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        Ok(Self {
+        Self::open_with_options(db_path, &ConnectionOptions::default())
+    }
+
+    /// Open an existing database with the given connection tuning, without creating tables
+    pub fn open_with_options(db_path: &Path, options: &ConnectionOptions) -> Result<Self> {
+        let db = Self::connect(db_path, options)?;
+        // Workers write concurrently, wait for the lock instead of failing with SQLITE_BUSY
+        let timeout = options.busy_timeout_ms.max(WORKER_BUSY_TIMEOUT_MS);
+        db.conn.busy_timeout(Duration::from_millis(timeout))?;
+        Ok(db)
+    }
+
+    /// Open an existing database for reading only, without touching its schema
+    ///
+    /// SQLite rejects every write through the connection, so a file others
+    /// maintain (the shared layer of a [`CompositeDb`](crate::composite::CompositeDb))
+    /// is never changed.
+    pub fn open_read_only(db_path: &Path, options: &ConnectionOptions) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        Self::with_connection(
+            Connection::open_with_flags(db_path, flags)?,
+            db_path,
+            options,
+        )
+    }
+
+    /// Open a connection and apply `options`, without touching the schema
+    fn connect(db_path: &Path, options: &ConnectionOptions) -> Result<Self> {
+        Self::with_connection(Connection::open(db_path)?, db_path, options)
+    }
+
+    fn with_connection(
+        conn: Connection,
+        db_path: &Path,
+        options: &ConnectionOptions,
+    ) -> Result<Self> {
+        // Nothing can be read from a SQLCipher file before the key is set
+        if let Some(key) = &options.key {
+            cipher::apply_key(&conn, key)?;
+        }
+        let db = Self {
+            search_backend: Self::detect_search_backend(&conn),
             conn,
             db_path: db_path.to_path_buf(),
-        })
+            autotag: AutotagRules::default(),
+            audit: None,
+            source: AuditSource::default(),
+            events: EventBus::new(),
+            options: options.clone(),
+        };
+        db.apply_options()?;
+        Ok(db)
     }
 
-    /// Get the database file path
-    pub fn get_path(&self) -> &Path {
-        &self.db_path
+    /// Schema version recorded in the database (see [`SCHEMA_VERSION`])
+    pub fn schema_version(&self) -> Result<u32> {
+        migrations::current_version(&self.conn)
     }
 
-    fn setup_tables(&self) -> Result<()> {
-        // Apply performance optimizations
-        // Use WAL mode for better concurrency and write performance
-        let _ = self.set_journal_mode("WAL");
-        // Use NORMAL synchronous mode for better write performance while remaining safe in WAL mode
-        self.conn.execute("PRAGMA synchronous = NORMAL", [])?;
-        // Store temp tables in memory
-        self.conn.execute("PRAGMA temp_store = MEMORY", [])?;
-        // Increase cache size to ~64MB
-        self.conn.execute("PRAGMA cache_size = -64000", [])?;
+    /// How searches on this connection find bookmarks
+    pub fn search_backend(&self) -> SearchBackend {
+        self.search_backend
+    }
+
+    /// Use FTS5 when the `fts` feature is on and this SQLite has the module
+    fn detect_search_backend(conn: &Connection) -> SearchBackend {
+        if !cfg!(feature = "fts") {
+            return SearchBackend::Like;
+        }
+        let probe = conn
+            .execute(
+                "CREATE VIRTUAL TABLE temp.bukurs_fts5_probe USING fts5(x)",
+                [],
+            )
+            .and_then(|_| conn.execute("DROP TABLE temp.bukurs_fts5_probe", []));
+        match probe {
+            Ok(_) => SearchBackend::Fts5,
+            Err(e) => {
+                log::warn!(
+                    "SQLite has no FTS5 ({}); searching without the full-text index",
+                    e
+                );
+                SearchBackend::Like
+            }
+        }
+    }
+
+    /// Connection tuning this database was opened with
+    pub fn connection_options(&self) -> &ConnectionOptions {
+        &self.options
+    }
+
+    /// Take the [database lock](lock::DbLock) for a change, when enabled
+    ///
+    /// Long operations take it around each batch of writes rather than for
+    /// their whole run, so other commands aren't kept waiting on the network.
+    pub fn write_lock(&self) -> crate::error::Result<Option<lock::DbLock>> {
+        self.options.lock_database(&self.db_path)
+    }
+
+    /// Begin a transaction that takes the write lock up front
+    ///
+    /// A deferred transaction that has to upgrade its lock fails with SQLITE_BUSY
+    /// straight away instead of waiting out the busy timeout, which breaks
+    /// concurrent writers (import workers, pooled connections).
+    fn write_transaction(&self) -> Result<Transaction<'_>> {
+        Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+    }
 
+    /// Apply the connection PRAGMAs from `self.options`
+    fn apply_options(&self) -> Result<()> {
+        register_functions(&self.conn)?;
+        let options = &self.options;
+        // In-memory databases only support MEMORY or OFF; keep whatever SQLite picks
+        let _ = self.set_journal_mode(options.journal_mode.as_sql());
+        self.conn
+            .busy_timeout(Duration::from_millis(options.busy_timeout_ms))?;
         self.conn.execute(
-            "CREATE TABLE if not exists bookmarks (
-                id integer PRIMARY KEY,
-                URL text NOT NULL UNIQUE,
-                metadata text default '',
-                tags text default ',',
-                desc text default '',
-                flags integer default 0,
-                parent_id integer default NULL
-            )",
+            &format!("PRAGMA synchronous = {}", options.synchronous.as_sql()),
             [],
         )?;
-
         self.conn.execute(
-            "CREATE TABLE if not exists undo_log (
-                id integer PRIMARY KEY AUTOINCREMENT,
-                timestamp integer,
-                operation text,
-                bookmark_id integer,
-                batch_id text,
-                -- Bookmark fields for undo
-                url text,
-                title text,
-                tags text,
-                desc text,
-                parent_id integer,
-                flags integer
-            )",
+            &format!(
+                "PRAGMA foreign_keys = {}",
+                if options.foreign_keys { "ON" } else { "OFF" }
+            ),
             [],
         )?;
+        // Store temp tables in memory
+        self.conn.execute("PRAGMA temp_store = MEMORY", [])?;
+        // Increase cache size to ~64MB
+        self.conn.execute("PRAGMA cache_size = -64000", [])?;
+        Ok(())
+    }
 
-        // Migration: Add batch_id column if it doesn't exist (for existing databases)
-        let has_batch_id: bool = {
-            let mut stmt = self.conn.prepare_cached("PRAGMA table_info(undo_log)")?;
-            let rows = stmt.query_map([], |row| {
-                let name: String = row.get(1)?;
-                Ok(name)
-            })?;
-
-            let mut found = false;
-            for row in rows {
-                if row? == "batch_id" {
-                    found = true;
-                    break;
-                }
-            }
-            found
-        };
+    /// Get the database file path
+    pub fn get_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// Whether the database lives in memory and is gone once this connection closes
+    pub fn is_in_memory(&self) -> bool {
+        self.db_path == Path::new(MEMORY_PATH)
+    }
+
+    /// Set the per-domain tag rules applied to imported bookmarks
+    pub fn set_autotag_rules(&mut self, rules: AutotagRules) {
+        self.autotag = rules;
+    }
+
+    pub fn autotag_rules(&self) -> &AutotagRules {
+        &self.autotag
+    }
+
+    /// Record every change to the bookmarks in `audit` (None disables auditing)
+    pub fn set_audit_log(&mut self, audit: Option<AuditLog>) {
+        self.audit = audit;
+    }
+
+    /// Attribute this connection's change events to `source`
+    pub fn set_source(&mut self, source: AuditSource) {
+        self.source = source;
+    }
+
+    pub fn source(&self) -> AuditSource {
+        self.source
+    }
+
+    /// Subscribers to the changes made through this connection
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Publish changes to `bus`, e.g. one shared by several connections
+    pub fn set_event_bus(&mut self, bus: EventBus) {
+        self.events = bus;
+    }
+
+    /// Write a consistent copy of the database to `path`, which must not exist
+    ///
+    /// Uses `VACUUM INTO`, so the copy is compacted and safe to take while
+    /// other connections are writing.
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        self.conn
+            .execute("VACUUM INTO ?1", [path.to_string_lossy().as_ref()])?;
+        Ok(())
+    }
 
-        if !has_batch_id {
-            self.conn
-                .execute("ALTER TABLE undo_log ADD COLUMN batch_id text", [])?;
+    /// Save the whole database to `path`, replacing any file there
+    ///
+    /// Works for in-memory databases too, so embedders can persist an ephemeral
+    /// one; the file is written next to `path` first and then renamed over it.
+    pub fn snapshot_to(&self, path: &Path) -> crate::error::Result<()> {
+        let tmp = path.with_extension("snapshot.tmp");
+        self.conn
+            .backup(rusqlite::MAIN_DB, &tmp, None::<fn(Progress)>)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Load a database file (e.g. from [`snapshot_to`](Self::snapshot_to)) into a
+    /// new in-memory database; the file itself is never written
+    ///
+    /// Snapshots from older versions are migrated in memory.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(rusqlite::Error::InvalidPath(path.to_path_buf()));
         }
+        let mut conn = Connection::open_in_memory()?;
+        conn.restore(rusqlite::MAIN_DB, path, None::<fn(Progress)>)?;
+        let db = Self {
+            search_backend: Self::detect_search_backend(&conn),
+            conn,
+            db_path: PathBuf::from(MEMORY_PATH),
+            autotag: AutotagRules::default(),
+            audit: None,
+            source: AuditSource::default(),
+            events: EventBus::new(),
+            options: ConnectionOptions::default(),
+        };
+        db.apply_options()?;
+        db.setup_tables()?;
+        Ok(db)
+    }
 
-        // Migration: Add parent_id column if it doesn't exist
-        let has_parent_id: bool = {
-            let mut stmt = self.conn.prepare_cached("PRAGMA table_info(bookmarks)")?;
-            let rows = stmt.query_map([], |row| {
-                let name: String = row.get(1)?;
-                Ok(name)
-            })?;
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
 
-            let mut found = false;
-            for row in rows {
-                if row? == "parent_id" {
-                    found = true;
-                    break;
-                }
-            }
-            found
+    /// Announce a committed change: append it to the audit log, if enabled,
+    /// and publish a [`BookmarkEvent`]
+    ///
+    /// Failures only warn, since the change itself can't be taken back.
+    fn record_change(&self, operation: AuditOperation, ids: &[usize]) {
+        if ids.is_empty() {
+            return;
+        }
+        self.audit(operation, ids);
+        match self.append_event(operation, ids) {
+            Ok(event) => self.events.publish(&event),
+            Err(e) => log::warn!("Failed to record change event: {}", e),
+        }
+    }
+
+    fn audit(&self, operation: AuditOperation, ids: &[usize]) {
+        let Some(audit) = &self.audit else {
+            return;
         };
+        if let Err(e) = audit.record(operation, ids) {
+            log::warn!(
+                "Failed to write audit log {}: {}",
+                audit.path().display(),
+                e
+            );
+        }
+    }
 
-        if !has_parent_id {
-            self.conn.execute(
-                "ALTER TABLE bookmarks ADD COLUMN parent_id INTEGER DEFAULT NULL",
-                [],
-            )?;
+    /// Bookmarks changed in a batch, read from its undo entries before commit
+    fn batch_changed_ids(&self, tx: &Transaction, batch_id: &str) -> Result<Vec<usize>> {
+        let mut stmt =
+            tx.prepare_cached("SELECT bookmark_id FROM undo_log WHERE batch_id = ?1 ORDER BY id")?;
+        let ids = stmt
+            .query_map([batch_id], |row| row.get(0))?
+            .collect::<Result<Vec<usize>>>()?;
+        Ok(ids)
+    }
+
+    /// Add a change to the `events` table, dropping those past the retention
+    fn append_event(&self, operation: AuditOperation, ids: &[usize]) -> Result<BookmarkEvent> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let encoded = serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string());
+        self.conn
+            .prepare_cached(
+                "INSERT INTO events (timestamp, operation, ids, source) VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute((
+                timestamp,
+                operation.to_string(),
+                encoded,
+                self.source.to_string(),
+            ))?;
+        let seq = self.conn.last_insert_rowid();
+        self.conn
+            .prepare_cached("DELETE FROM events WHERE seq <= ?1")?
+            .execute([seq - EVENT_RETENTION])?;
+
+        Ok(BookmarkEvent {
+            seq,
+            timestamp,
+            operation,
+            ids: ids.to_vec(),
+            source: self.source,
+        })
+    }
+
+    /// Change events after `seq`, oldest first (see [`crate::events`])
+    pub fn events_since(&self, seq: i64) -> Result<Vec<BookmarkEvent>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT seq, timestamp, operation, ids, source FROM events WHERE seq > ?1 ORDER BY seq",
+        )?;
+        let rows = stmt.query_map([seq], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let (seq, timestamp, operation, ids, source) = row?;
+            // Written by a newer bukurs with operations this one doesn't know
+            let Some(operation) = AuditOperation::from_string(&operation) else {
+                continue;
+            };
+            events.push(BookmarkEvent {
+                seq,
+                timestamp,
+                operation,
+                ids: serde_json::from_str(&ids).unwrap_or_default(),
+                source: AuditSource::from_string(&source).unwrap_or_default(),
+            });
         }
+        Ok(events)
+    }
 
-        // Migration: Add flags column if it doesn't exist
-        let has_flags: bool = {
-            let mut stmt = self.conn.prepare_cached("PRAGMA table_info(bookmarks)")?;
-            let rows = stmt.query_map([], |row| {
-                let name: String = row.get(1)?;
-                Ok(name)
-            })?;
+    /// Sequence number of the newest change event, 0 if there are none
+    pub fn last_event_seq(&self) -> Result<i64> {
+        self.conn
+            .query_row("SELECT COALESCE(MAX(seq), 0) FROM events", [], |row| {
+                row.get(0)
+            })
+    }
 
-            let mut found = false;
-            for row in rows {
-                if row? == "flags" {
-                    found = true;
-                    break;
-                }
-            }
-            found
-        };
+    fn setup_tables(&self) -> Result<()> {
+        migrations::migrate(&self.conn)?;
 
-        if !has_flags {
-            self.conn.execute(
-                "ALTER TABLE bookmarks ADD COLUMN flags INTEGER DEFAULT 0",
-                [],
-            )?;
+        match self.search_backend {
+            SearchBackend::Fts5 => self.setup_fts(),
+            // Triggers would fail on every write without the fts5 module; a
+            // build with it rebuilds the index when it finds them missing
+            SearchBackend::Like => self.drop_fts_triggers(),
         }
+    }
 
-        if cfg!(debug_assertions) {
-            self.conn
-                .execute("DROP TABLE IF EXISTS bookmarks_fts", [])?;
+    /// Create the FTS5 index and the triggers keeping it in sync
+    fn setup_fts(&self) -> Result<()> {
+        // Indexes from before diacritics folding are recreated, then rebuilt below
+        let table_sql: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'bookmarks_fts'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if table_sql.is_some_and(|sql| !sql.contains("remove_diacritics")) {
+            self.drop_fts_triggers()?;
+            self.conn.execute("DROP TABLE bookmarks_fts", [])?;
         }
 
         // Create FTS5 virtual table for fast full-text search
-        // Using a regular FTS5 table (not content-less) for simplicity and reliability
+        // Using a regular FTS5 table (not content-less) for simplicity and reliability.
+        // remove_diacritics 2 lets "cafe" match "café" (and the other way round)
         self.conn.execute(
             r#"CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_fts USING fts5(
                 url,
                 metadata,
                 tags,
                 desc,
-                tokenize = 'unicode61'
+                tokenize = 'unicode61 remove_diacritics 2'
             )"#,
             [],
         )?;
 
-        if cfg!(debug_assertions) {
+        // Triggers are current when they skip encrypted bookmarks; older ones indexed every row
+        let trigger_sql: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = 'bookmarks_ai'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let current = trigger_sql.is_some_and(|sql| sql.contains(FIELD_MARKER));
+
+        if !current {
             // Drop existing triggers if they exist (to handle upgrades)
-            self.conn
-                .execute("DROP TRIGGER IF EXISTS bookmarks_ai", [])?;
-            self.conn
-                .execute("DROP TRIGGER IF EXISTS bookmarks_au", [])?;
-            self.conn
-                .execute("DROP TRIGGER IF EXISTS bookmarks_ad", [])?;
+            self.drop_fts_triggers()?;
         }
 
+        // Encrypted bookmarks are kept out of the index
+        let plaintext = plaintext_condition();
+
         // Trigger to keep FTS5 table in sync on INSERT
         self.conn.execute(
-            "CREATE TRIGGER IF NOT EXISTS bookmarks_ai AFTER INSERT ON bookmarks BEGIN
-                INSERT INTO bookmarks_fts(rowid, url, metadata, tags, desc)
-                VALUES (new.id, new.URL, new.metadata, new.tags, new.desc);
-            END",
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS bookmarks_ai AFTER INSERT ON bookmarks
+                WHEN new.{} BEGIN
+                    INSERT INTO bookmarks_fts(rowid, url, metadata, tags, desc)
+                    VALUES (new.id, new.URL, new.metadata, new.tags, new.desc);
+                END",
+                plaintext
+            ),
             [],
         )?;
 
-        // Trigger to keep FTS5 table in sync on UPDATE
+        // Trigger to keep FTS5 table in sync on UPDATE; a row leaves the index
+        // when it is encrypted and comes back when it is decrypted
         self.conn.execute(
-            "CREATE TRIGGER IF NOT EXISTS bookmarks_au AFTER UPDATE ON bookmarks BEGIN
-                UPDATE bookmarks_fts
-                SET url = new.URL, metadata = new.metadata, tags = new.tags, desc = new.desc
-                WHERE rowid = old.id;
-            END",
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS bookmarks_au AFTER UPDATE ON bookmarks BEGIN
+                    DELETE FROM bookmarks_fts WHERE rowid = old.id;
+                    INSERT INTO bookmarks_fts(rowid, url, metadata, tags, desc)
+                    SELECT new.id, new.URL, new.metadata, new.tags, new.desc
+                    WHERE new.{};
+                END",
+                plaintext
+            ),
             [],
         )?;
 
@@ -231,25 +824,16 @@ impl BukuDb {
             [],
         )?;
 
-        // Create index on tags column for better performance when listing/searching tags
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_bookmarks_tags ON bookmarks(tags)",
-            [],
-        )?;
-
-        // Populate FTS5 table if it's empty but bookmarks exist (migration)
-        let fts_count: i64 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM bookmarks_fts", [], |row| row.get(0))?;
-        let bookmarks_count: i64 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0))?;
-
-        if fts_count == 0 && bookmarks_count > 0 {
-            // Migrate existing bookmarks to FTS5
+        // Without current triggers the index may have missed changes (a new table,
+        // an upgrade, or writes from a build without FTS5), so rebuild it
+        if !current {
+            self.conn.execute("DELETE FROM bookmarks_fts", [])?;
             self.conn.execute(
-                "INSERT INTO bookmarks_fts(rowid, url, metadata, tags, desc)
-                SELECT id, URL, metadata, tags, desc FROM bookmarks",
+                &format!(
+                    "INSERT INTO bookmarks_fts(rowid, url, metadata, tags, desc)
+                    SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE {}",
+                    plaintext
+                ),
                 [],
             )?;
         }
@@ -257,22 +841,89 @@ impl BukuDb {
         Ok(())
     }
 
+    fn drop_fts_triggers(&self) -> Result<()> {
+        self.conn
+            .execute("DROP TRIGGER IF EXISTS bookmarks_ai", [])?;
+        self.conn
+            .execute("DROP TRIGGER IF EXISTS bookmarks_au", [])?;
+        self.conn
+            .execute("DROP TRIGGER IF EXISTS bookmarks_ad", [])?;
+        Ok(())
+    }
+
     /// Helper function to quote and escape keywords for FTS5 queries
     /// Prevents FTS5 syntax errors by treating keywords as literal phrases
+    ///
+    /// A trailing `*` is kept outside the quotes, so `prog*` matches "programming".
     fn quote_fts5_keywords(keywords: &[String], column_prefix: Option<&str>) -> Vec<String> {
         keywords
             .iter()
             .map(|k| {
-                let escaped = k.replace('"', "\"\"");
+                let (word, star) = match k.strip_suffix('*') {
+                    Some(word) if !word.is_empty() => (word, "*"),
+                    _ => (k.as_str(), ""),
+                };
+                let escaped = word.replace('"', "\"\"");
                 if let Some(prefix) = column_prefix {
-                    format!("{}:\"{}\"", prefix, escaped)
+                    format!("{}:\"{}\"{}", prefix, escaped, star)
                 } else {
-                    format!("\"{}\"", escaped)
+                    format!("\"{}\"{}", escaped, star)
                 }
             })
             .collect()
     }
 
+    /// Search without FTS5: bookmarks where one of `columns` contains any (or all)
+    /// of `keywords`, ignoring case and diacritics, oldest first
+    ///
+    /// LIKE only folds ASCII case, so both sides are folded with the
+    /// `fold_diacritics` SQL function and compared with `instr`.
+    fn search_like(
+        &self,
+        columns: &[&str],
+        keywords: &[String],
+        any: bool,
+    ) -> Result<Vec<Bookmark>> {
+        let keywords: Vec<String> = keywords.iter().map(|k| utils::fold_diacritics(k)).collect();
+        if keywords.is_empty() {
+            // Nothing to match: no bookmark has any of them, every one has all of them
+            return self.query_like(if any { "0" } else { "1" }, &[]);
+        }
+        let condition = (1..=keywords.len())
+            .map(|i| {
+                let in_columns: Vec<String> = columns
+                    .iter()
+                    .map(|column| format!("instr(fold_diacritics({}), ?{}) > 0", column, i))
+                    .collect();
+                format!("({})", in_columns.join(" OR "))
+            })
+            .collect::<Vec<_>>()
+            .join(if any { " OR " } else { " AND " });
+        self.query_like(&condition, &keywords)
+    }
+
+    /// Bookmarks that aren't encrypted and match `condition`, with `patterns` bound in order
+    fn query_like(&self, condition: &str, patterns: &[String]) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, URL, metadata, tags, desc FROM bookmarks
+             WHERE {} AND ({}) ORDER BY id",
+            plaintext_condition(),
+            condition
+        ))?;
+        let bookmarks = stmt
+            .query_map(rusqlite::params_from_iter(patterns), |row| {
+                Ok(Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(bookmarks)
+    }
+
     pub fn add_rec(
         &self,
         url: &str,
@@ -281,26 +932,29 @@ impl BukuDb {
         desc: &str,
         parent_id: Option<usize>,
     ) -> Result<usize> {
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
 
         // Get flags value (default 0 for new bookmarks)
         let flags = 0;
 
-        // Insert bookmark
-        {
-            let mut stmt = tx.prepare_cached(
-                "INSERT INTO bookmarks (URL, metadata, tags, desc, parent_id, flags) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            )?;
-            stmt.execute((url, title, tags, desc, parent_id, flags))?;
-        }
-        let id = tx.last_insert_rowid() as usize;
-
-        // Log undo information with individual columns
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
             .as_secs() as i64;
 
+        let record = ParsedBookmark {
+            url: url.to_string(),
+            title: title.to_string(),
+            tags: tags.to_string(),
+            desc: desc.to_string(),
+            parent_id,
+            created_at: None,
+            flags,
+        };
+        let id = insert_bookmark(&tx, &record, timestamp)?;
+
+        // Log undo information with individual columns
+
         {
             let mut stmt = tx.prepare_cached(
                 "INSERT INTO undo_log (timestamp, operation, bookmark_id, url, title, tags, desc, parent_id, flags)
@@ -312,6 +966,7 @@ impl BukuDb {
         }
 
         tx.commit()?;
+        self.record_change(AuditOperation::Add, &[id]);
         Ok(id)
     }
 
@@ -355,20 +1010,74 @@ impl BukuDb {
         Ok(records)
     }
 
-    pub fn update_rec_partial(
-        &self,
-        id: usize,
-        url: Option<&str>,
-        title: Option<&str>,
-        tags: Option<&str>,
-        desc: Option<&str>,
-        parent_id: Option<Option<usize>>,
-    ) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
-
-        // Fetch current state for undo within transaction
-        let (old_url, old_title, old_tags, old_desc, old_parent_id, old_flags): (
-            String,
+    /// Get the most recently added bookmarks with their creation timestamps, newest first
+    /// Bookmarks without a timestamp (added before it was recorded) sort last, by ID
+    pub fn get_rec_recent(&self, limit: usize) -> Result<Vec<(Bookmark, i64)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, URL, metadata, tags, desc, created_at FROM bookmarks
+             ORDER BY created_at DESC, id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok((
+                Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ),
+                row.get(5)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    /// Get the bookmarks carrying `tag` with their creation timestamps, oldest first
+    pub fn get_rec_tagged_by_age(&self, tag: &str) -> Result<Vec<(Bookmark, i64)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, URL, metadata, tags, desc, created_at FROM bookmarks
+             WHERE tags LIKE '%,' || ?1 || ',%'
+             ORDER BY created_at ASC, id ASC",
+        )?;
+        let rows = stmt.query_map([tag], |row| {
+            Ok((
+                Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ),
+                row.get(5)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    pub fn update_rec_partial(
+        &self,
+        id: usize,
+        url: Option<&str>,
+        title: Option<&str>,
+        tags: Option<&str>,
+        desc: Option<&str>,
+        parent_id: Option<Option<usize>>,
+    ) -> Result<()> {
+        let tx = self.write_transaction()?;
+
+        // Fetch current state for undo within transaction
+        let (old_url, old_title, old_tags, old_desc, old_parent_id, old_flags): (
+            String,
             String,
             String,
             String,
@@ -417,59 +1126,20 @@ impl BukuDb {
             ))?;
         }
 
-        // Build and execute update query
-        let mut updates = Vec::new();
-        let mut params: Vec<(&str, &dyn rusqlite::ToSql)> = Vec::new();
-
-        // Store parent_id value to extend its lifetime
-        let parent_id_val = parent_id.flatten();
-
-        if url.is_some() {
-            updates.push("URL = :url");
-        }
-        if title.is_some() {
-            updates.push("metadata = :title");
-        }
-        if tags.is_some() {
-            updates.push("tags = :tags");
-        }
-        if desc.is_some() {
-            updates.push("desc = :desc");
-        }
-        if parent_id.is_some() {
-            updates.push("parent_id = :parent_id");
-        }
-
-        if updates.is_empty() {
+        let fields = FieldUpdate {
+            url,
+            title,
+            tags,
+            desc,
+            parent_id,
+            flags: None,
+        };
+        if fields.is_empty() {
             return Ok(());
         }
-
-        // Pre-allocate capacity for query string to avoid reallocations
-        let mut query = String::with_capacity(64 + updates.len() * 20);
-        query.push_str("UPDATE bookmarks SET ");
-        query.push_str(&updates.join(", "));
-        query.push_str(" WHERE id = :id");
-
-        // Add params
-        if let Some(ref u) = url {
-            params.push((":url", u));
-        }
-        if let Some(ref t) = title {
-            params.push((":title", t));
-        }
-        if let Some(ref tg) = tags {
-            params.push((":tags", tg));
-        }
-        if let Some(ref d) = desc {
-            params.push((":desc", d));
-        }
-        if parent_id.is_some() {
-            params.push((":parent_id", &parent_id_val));
-        }
-        params.push((":id", &id));
-
-        tx.execute(&query, params.as_slice())?;
+        update_fields(&tx, id, &fields)?;
         tx.commit()?;
+        self.record_change(AuditOperation::Update, &[id]);
         Ok(())
     }
 
@@ -487,11 +1157,19 @@ impl BukuDb {
         if bookmarks.is_empty() {
             return Ok((0, 0));
         }
+        let fields = FieldUpdate {
+            url,
+            title,
+            tags: tags_opt,
+            desc,
+            parent_id: None,
+            flags: immutable,
+        };
 
         // Generate a unique batch_id using UUID v4
         let batch_id = uuid::Uuid::new_v4().to_string();
 
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -526,54 +1204,10 @@ impl BukuDb {
                 )?;
             }
 
-            // Build update query
-            let mut query = "UPDATE bookmarks SET ".to_string();
-            let mut updates = Vec::new();
-            let immutable_val = immutable.unwrap_or(0);
-
-            if url.is_some() {
-                updates.push("URL = :url");
-            }
-            if title.is_some() {
-                updates.push("metadata = :title");
-            }
-            if tags_opt.is_some() {
-                updates.push("tags = :tags");
-            }
-            if desc.is_some() {
-                updates.push("desc = :desc");
-            }
-            if immutable.is_some() {
-                updates.push("flags = :flags");
-            }
-
-            if updates.is_empty() {
+            if fields.is_empty() {
                 continue;
             }
-
-            query.push_str(&updates.join(", "));
-            query.push_str(" WHERE id = :id");
-
-            let mut params: Vec<(&str, &dyn rusqlite::ToSql)> = Vec::new();
-
-            if let Some(ref u) = url {
-                params.push((":url", u));
-            }
-            if let Some(ref t) = title {
-                params.push((":title", t));
-            }
-            if let Some(ref tg) = tags_opt {
-                params.push((":tags", tg));
-            }
-            if let Some(ref d) = desc {
-                params.push((":desc", d));
-            }
-            if immutable.is_some() {
-                params.push((":flags", &immutable_val));
-            }
-            params.push((":id", &bookmark.id));
-
-            match tx.execute(&query, params.as_slice()) {
+            match update_fields(&tx, bookmark.id, &fields) {
                 Ok(_) => success_count += 1,
                 Err(_) => {
                     // On any failure, rollback the entire batch
@@ -582,16 +1216,20 @@ impl BukuDb {
             }
         }
 
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
         tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed_ids);
         Ok((success_count, failed_count))
     }
 
     /// Update multiple bookmarks with pre-computed tags in a single transaction with a shared batch_id for undo
-    /// This variant accepts bookmarks with their final tag values already computed
+    /// This variant accepts bookmarks with their final tag values already computed,
+    /// or with `tag_ops` applies those to each bookmark's tags as stored when it's written
     /// Returns (success_count, failed_count)
     pub fn update_rec_batch_with_tags(
         &self,
         bookmarks: &[Bookmark],
+        tag_ops: Option<&[TagOp]>,
         url: Option<&str>,
         title: Option<&str>,
         desc: Option<&str>,
@@ -604,7 +1242,7 @@ impl BukuDb {
         // Generate a unique batch_id using UUID v4
         let batch_id = uuid::Uuid::new_v4().to_string();
 
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -631,6 +1269,12 @@ impl BukuDb {
                 .ok()
             };
 
+            // Tag operations see the tags as stored, inside this transaction
+            let tags = match (tag_ops, &current) {
+                (Some(ops), Some((_, _, old_tags, ..))) => apply_tag_ops(old_tags, ops),
+                _ => bookmark.tags.clone(),
+            };
+
             // Log undo with batch_id
             if let Some((old_url, old_title, old_tags, old_desc, parent_id, flags)) = current {
                 tx.execute(
@@ -639,66 +1283,271 @@ impl BukuDb {
                 )?;
             }
 
-            // Build update query - use tags from the bookmark object
-            let mut query = "UPDATE bookmarks SET ".to_string();
-            let mut updates = Vec::new();
-            let immutable_val = immutable.unwrap_or(0);
-
-            if url.is_some() {
-                updates.push("URL = :url");
-            }
-            if title.is_some() {
-                updates.push("metadata = :title");
-            }
             // Always update tags from the bookmark's tags field
-            updates.push("tags = :tags");
-            if desc.is_some() {
-                updates.push("desc = :desc");
-            }
-            if immutable.is_some() {
-                updates.push("flags = :flags");
+            let fields = FieldUpdate {
+                url,
+                title,
+                tags: Some(&tags),
+                desc,
+                parent_id: None,
+                flags: immutable,
+            };
+            match update_fields(&tx, bookmark.id, &fields) {
+                Ok(_) => success_count += 1,
+                Err(_) => {
+                    // On any failure, rollback the entire batch
+                    return Err(rusqlite::Error::ExecuteReturnedResults);
+                }
             }
+        }
+
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed_ids);
+        Ok((success_count, failed_count))
+    }
+
+    /// Overwrite the URL, title, tags and description of each bookmark in a single
+    /// transaction with a shared batch_id for undo
+    /// Any failure (e.g. a duplicate URL) rolls back the whole batch
+    /// Returns the number of bookmarks updated
+    pub fn update_rec_batch_full(&self, bookmarks: &[Bookmark]) -> Result<usize> {
+        if bookmarks.is_empty() {
+            return Ok(0);
+        }
+
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        let tx = self.write_transaction()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
 
-            if updates.is_empty() {
+        let mut updated = 0;
+        for bookmark in bookmarks {
+            let current = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT URL, metadata, tags, desc, parent_id, flags FROM bookmarks WHERE id = ?1",
+                )?;
+                stmt.query_row([bookmark.id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<usize>>(4)?,
+                        row.get::<_, i32>(5)?,
+                    ))
+                })
+                .optional()?
+            };
+            let Some((old_url, old_title, old_tags, old_desc, parent_id, flags)) = current else {
                 continue;
-            }
+            };
 
-            query.push_str(&updates.join(", "));
-            query.push_str(" WHERE id = :id");
+            tx.execute(
+                "INSERT INTO undo_log (timestamp, operation, bookmark_id, batch_id, url, title, tags, desc, parent_id, flags) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                (timestamp, "UPDATE", bookmark.id, &batch_id, old_url, old_title, old_tags, old_desc, parent_id, flags),
+            )?;
+            updated += update_fields(&tx, bookmark.id, &FieldUpdate::overwrite(bookmark))?;
+        }
 
-            let mut params: Vec<(&str, &dyn rusqlite::ToSql)> = Vec::new();
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed_ids);
+        Ok(updated)
+    }
 
-            if let Some(ref u) = url {
-                params.push((":url", u));
-            }
-            if let Some(ref t) = title {
-                params.push((":title", t));
+    /// Overwrite the URL, title, tags and description of each bookmark in a single
+    /// transaction without undo entries, also dropping the undo history they already have
+    /// For changes whose previous values must not be kept, like encryption
+    /// Returns the number of bookmarks updated
+    pub fn update_rec_batch_forget_history(&self, bookmarks: &[Bookmark]) -> Result<usize> {
+        if bookmarks.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.write_transaction()?;
+        let mut updated = 0;
+        let mut ids = Vec::with_capacity(bookmarks.len());
+        for bookmark in bookmarks {
+            let changed = update_fields(&tx, bookmark.id, &FieldUpdate::overwrite(bookmark))?;
+            if changed > 0 {
+                tx.execute("DELETE FROM undo_log WHERE bookmark_id = ?1", [bookmark.id])?;
+                ids.push(bookmark.id);
             }
-            // Use the tags from the bookmark
-            params.push((":tags", &bookmark.tags));
-            if let Some(ref d) = desc {
-                params.push((":desc", d));
+            updated += changed;
+        }
+
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &ids);
+        Ok(updated)
+    }
+
+    /// Import parsed bookmarks in a single transaction with a shared batch_id for undo
+    /// Existing URLs are handled according to `on_duplicate`
+    /// The progress_callback receives the URL of each bookmark before it is processed
+    pub fn import_rec_batch<F>(
+        &self,
+        records: &[ParsedBookmark],
+        on_duplicate: DuplicateStrategy,
+        progress_callback: F,
+    ) -> Result<ImportStats>
+    where
+        F: FnMut(&str),
+    {
+        let stats = self.import_rec_batch_cancellable(
+            records,
+            on_duplicate,
+            &CancellationToken::new(),
+            progress_callback,
+        )?;
+        Ok(stats.unwrap_or_default())
+    }
+
+    /// Like [`Self::import_rec_batch`], but checks `cancel` before each bookmark
+    /// Returns None if cancelled, with the whole batch rolled back
+    pub fn import_rec_batch_cancellable<F>(
+        &self,
+        records: &[ParsedBookmark],
+        on_duplicate: DuplicateStrategy,
+        cancel: &CancellationToken,
+        progress_callback: F,
+    ) -> Result<Option<ImportStats>>
+    where
+        F: FnMut(&str),
+    {
+        self.import_rec_batch_reported(records, on_duplicate, cancel, progress_callback, |_| {})
+    }
+
+    /// Like [`Self::import_rec_batch_cancellable`], also handing `report` each
+    /// bookmark that was skipped or merged rather than added, once the batch
+    /// is committed
+    ///
+    /// URLs that can't be bookmarked (see [`is_importable_url`]) are skipped.
+    pub fn import_rec_batch_reported<F, R>(
+        &self,
+        records: &[ParsedBookmark],
+        on_duplicate: DuplicateStrategy,
+        cancel: &CancellationToken,
+        mut progress_callback: F,
+        mut report: R,
+    ) -> Result<Option<ImportStats>>
+    where
+        F: FnMut(&str),
+        R: FnMut(ReportEntry),
+    {
+        let mut stats = ImportStats::default();
+        if records.is_empty() {
+            return Ok(Some(stats));
+        }
+        let mut entries = Vec::new();
+
+        let batch_id = uuid::Uuid::new_v4().to_string();
+
+        // Take the write lock up front so the duplicate lookups stay valid
+        // while other connections import concurrently
+        let tx = self.write_transaction()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+
+        for record in records {
+            if cancel.is_cancelled() {
+                // Dropping the transaction rolls it back
+                return Ok(None);
             }
-            if immutable.is_some() {
-                params.push((":flags", &immutable_val));
+            progress_callback(&record.url);
+
+            if !is_importable_url(&record.url) {
+                entries.push(ReportEntry::new(ReportReason::InvalidUrl, record, None));
+                stats.skipped += 1;
+                continue;
             }
-            params.push((":id", &bookmark.id));
 
-            match tx.execute(&query, params.as_slice()) {
-                Ok(_) => success_count += 1,
-                Err(_) => {
-                    // On any failure, rollback the entire batch
-                    return Err(rusqlite::Error::ExecuteReturnedResults);
+            let tagged;
+            let record = match self.autotag.apply(&record.url, &record.tags) {
+                Some(tags) => {
+                    tagged = ParsedBookmark {
+                        tags,
+                        ..record.clone()
+                    };
+                    &tagged
                 }
-            }
+                None => record,
+            };
+
+            let current = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT id, metadata, tags, desc, parent_id, flags FROM bookmarks WHERE URL = ?1",
+                )?;
+                stmt.query_row([&record.url], |row| {
+                    Ok((
+                        Bookmark::new(
+                            row.get(0)?,
+                            record.url.clone(),
+                            row.get(1)?,
+                            row.get(2)?,
+                            row.get(3)?,
+                        ),
+                        row.get::<_, Option<usize>>(4)?,
+                        row.get::<_, i32>(5)?,
+                    ))
+                })
+                .optional()?
+            };
+
+            let Some((existing, parent_id, flags)) = current else {
+                let id = insert_bookmark(&tx, record, timestamp)?;
+                tx.execute(
+                    "INSERT INTO undo_log (timestamp, operation, bookmark_id, batch_id, url, title, tags, desc, parent_id, flags) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    (timestamp, "ADD", id, &batch_id, &record.url, &record.title, &record.tags, &record.desc, record.parent_id, record.flags),
+                )?;
+                stats.added += 1;
+                continue;
+            };
+
+            let Some(resolved) = resolve_duplicate(&existing, record, on_duplicate) else {
+                entries.push(ReportEntry::new(
+                    ReportReason::Duplicate,
+                    record,
+                    Some(existing.id),
+                ));
+                stats.skipped += 1;
+                continue;
+            };
+
+            tx.execute(
+                "INSERT INTO undo_log (timestamp, operation, bookmark_id, batch_id, url, title, tags, desc, parent_id, flags) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                (timestamp, "UPDATE", existing.id, &batch_id, &existing.url, &existing.title, &existing.tags, &existing.description, parent_id, flags),
+            )?;
+            let fields = FieldUpdate {
+                title: Some(&resolved.title),
+                tags: Some(&resolved.tags),
+                desc: Some(&resolved.description),
+                ..FieldUpdate::default()
+            };
+            update_fields(&tx, existing.id, &fields)?;
+            let reason = match on_duplicate {
+                DuplicateStrategy::Overwrite => ReportReason::Overwritten,
+                _ => ReportReason::Merged,
+            };
+            entries.push(ReportEntry::new(reason, record, Some(existing.id)));
+            stats.updated += 1;
         }
 
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
         tx.commit()?;
-        Ok((success_count, failed_count))
+        self.record_change(AuditOperation::Import, &changed_ids);
+        for entry in entries {
+            report(entry);
+        }
+        Ok(Some(stats))
     }
 
     pub fn delete_rec(&self, id: usize) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
 
         // Fetch current state for undo within transaction
         let (url, title, tags, desc, parent_id, flags): (
@@ -748,6 +1597,7 @@ impl BukuDb {
             stmt.execute([id])?;
         }
         tx.commit()?;
+        self.record_change(AuditOperation::Delete, &[id]);
         Ok(())
     }
 
@@ -761,7 +1611,7 @@ impl BukuDb {
         // Generate a unique batch_id using UUID v4
         let batch_id = uuid::Uuid::new_v4().to_string();
 
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.write_transaction()?;
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -802,197 +1652,1251 @@ impl BukuDb {
             }
         }
 
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
         tx.commit()?;
+        self.record_change(AuditOperation::Delete, &changed_ids);
         Ok(deleted_count)
     }
 
-    pub fn search(
-        &self,
-        keywords: &[String],
-        any: bool,
-        _deep: bool, // Deep is implicit with FTS5
-        regex: bool,
-    ) -> Result<Vec<Bookmark>> {
-        // Handle regex search separately (fallback to old method)
-        if regex {
-            let all_recs = self.get_rec_all()?;
-            let re = regex::Regex::new(&keywords[0])
-                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    /// Pin or unpin bookmarks; returns how many changed
+    pub fn set_pinned(&self, ids: &[usize], pinned: bool) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let mut changed = Vec::new();
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE bookmarks SET pinned = ?1 WHERE id = ?2 AND pinned IS NOT ?1",
+            )?;
+            for &id in ids {
+                if stmt.execute((pinned, id))? > 0 {
+                    changed.push(id);
+                }
+            }
+        }
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed);
+        Ok(changed.len())
+    }
 
-            let filtered = all_recs
-                .into_iter()
-                .filter(|b| {
-                    re.is_match(&b.url)
-                        || re.is_match(&b.title)
-                        || re.is_match(&b.tags)
-                        || re.is_match(&b.description)
-                })
-                .collect();
-            return Ok(filtered);
+    /// Give bookmarks a rating (0 clears it); returns how many changed
+    pub fn set_rating(&self, ids: &[usize], rating: u8) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let mut changed = Vec::new();
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE bookmarks SET rating = ?1 WHERE id = ?2 AND rating IS NOT ?1",
+            )?;
+            for &id in ids {
+                if stmt.execute((rating, id))? > 0 {
+                    changed.push(id);
+                }
+            }
         }
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed);
+        Ok(changed.len())
+    }
 
-        // No keywords - return all
-        if keywords.is_empty() {
-            return self.get_rec_all();
+    /// Ratings of the rated bookmarks, keyed by ID
+    pub fn get_ratings(&self) -> Result<HashMap<usize, u8>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, rating FROM bookmarks WHERE rating > 0")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Store the word counts measured for the bookmarks with these URLs
+    pub fn set_word_counts<S: AsRef<str>>(&self, counts: &[(S, usize)]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        {
+            let mut stmt =
+                tx.prepare_cached("UPDATE bookmarks SET word_count = ?2 WHERE URL = ?1")?;
+            for (url, words) in counts {
+                stmt.execute((url.as_ref(), words))?;
+            }
         }
+        tx.commit()
+    }
 
-        // Build FTS5 query
-        let query: std::borrow::Cow<str> = if keywords.len() == 1
-            && (utils::has_char(b'"', keywords[0].as_str())
-                || keywords[0].contains(" OR ")
-                || keywords[0].contains(" AND "))
+    /// Word counts of the bookmarks that have been measured, keyed by ID
+    pub fn get_word_counts(&self) -> Result<HashMap<usize, usize>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, word_count FROM bookmarks WHERE word_count > 0")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Store the languages detected for the bookmarks with these URLs
+    pub fn set_languages<S: AsRef<str>>(&self, languages: &[(S, String)]) -> Result<()> {
+        let tx = self.write_transaction()?;
         {
-            // User provided FTS5 query syntax - use as is
-            std::borrow::Cow::Borrowed(&keywords[0])
-        } else {
-            // Simple keywords - quote each to treat as literal phrase and avoid FTS5 syntax errors
-            let quoted_keywords = Self::quote_fts5_keywords(keywords, None);
-            let join_op = if any { " OR " } else { " AND " };
-            std::borrow::Cow::Owned(quoted_keywords.join(join_op))
-        };
+            let mut stmt = tx.prepare_cached("UPDATE bookmarks SET lang = ?2 WHERE URL = ?1")?;
+            for (url, lang) in languages {
+                stmt.execute((url.as_ref(), lang))?;
+            }
+        }
+        tx.commit()
+    }
 
-        // Query FTS5 table to get matching bookmark IDs (ranked by relevance)
+    /// Languages of the bookmarks whose language is known, keyed by ID
+    pub fn get_languages(&self) -> Result<HashMap<usize, String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, lang FROM bookmarks WHERE lang IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Bookmark IDs in display order: pinned first, then by manual position
+    /// (bookmarks never moved come after those that were), then by ID
+    pub fn get_display_order(&self) -> Result<Vec<usize>> {
         let mut stmt = self.conn.prepare_cached(
-            "SELECT rowid FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1 ORDER BY rank",
+            "SELECT id FROM bookmarks
+             ORDER BY pinned DESC, position IS NULL, position, id",
         )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<usize>>>()?;
+        Ok(ids)
+    }
 
-        let ids: Vec<usize> = stmt
-            .query_map([&query], |row| row.get::<_, i64>(0).map(|id| id as usize))?
-            .collect::<Result<Vec<_>>>()?;
+    /// Give the bookmarks manual positions in the order of `ids`
+    ///
+    /// Only rows whose position changes are written.
+    pub fn set_positions(&self, ids: &[usize]) -> Result<()> {
+        let tx = self.write_transaction()?;
+        let mut changed = Vec::new();
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE bookmarks SET position = ?1 WHERE id = ?2 AND position IS NOT ?1",
+            )?;
+            for (position, &id) in ids.iter().enumerate() {
+                if stmt.execute((position as i64 + 1, id))? > 0 {
+                    changed.push(id);
+                }
+            }
+        }
+        tx.commit()?;
+        self.record_change(AuditOperation::Update, &changed);
+        Ok(())
+    }
 
-        if ids.is_empty() {
-            return Ok(Vec::new());
+    /// Set when a bookmark expires (Unix seconds), or `None` to keep it for good
+    pub fn set_expiry(&self, id: usize, expires_at: Option<i64>) -> Result<()> {
+        let changed = self.conn.execute(
+            "UPDATE bookmarks SET expires_at = ?1 WHERE id = ?2",
+            (expires_at, id),
+        )?;
+        if changed == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
         }
+        self.record_change(AuditOperation::Update, &[id]);
+        Ok(())
+    }
 
-        // Fetch full bookmark data for matching IDs
-        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        let query_str = format!(
-            "SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE id IN ({})",
-            placeholders
-        );
+    /// Give bookmark `id` a unique short name to open it by, or take it away with `None`
+    ///
+    /// Aliases can't contain spaces or commas, or look like an index or range.
+    pub fn set_alias(&self, id: usize, alias: Option<&str>) -> crate::error::Result<()> {
+        if let Some(alias) = alias {
+            if alias.is_empty()
+                || utils::has_spaces(alias)
+                || alias.contains(',')
+                || crate::operations::is_id_or_range(alias)
+            {
+                return Err(crate::error::BukursError::InvalidInput(format!(
+                    "'{}' isn't a valid alias (it can't be empty, contain spaces or commas, or look like an index)",
+                    alias
+                )));
+            }
+            if let Some(other) = self.get_rec_by_alias(alias)? {
+                if other.id != id {
+                    return Err(crate::error::BukursError::InvalidInput(format!(
+                        "Alias '{}' is already used by bookmark {}",
+                        alias, other.id
+                    )));
+                }
+            }
+        }
+        let changed = self
+            .conn
+            .execute("UPDATE bookmarks SET alias = ?1 WHERE id = ?2", (alias, id))?;
+        if changed == 0 {
+            return Err(crate::error::BukursError::BookmarkNotFound(id));
+        }
+        self.record_change(AuditOperation::Update, &[id]);
+        Ok(())
+    }
 
-        let mut stmt = self.conn.prepare(&query_str)?;
-        let params: Vec<&dyn rusqlite::ToSql> =
-            ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+    /// The bookmark called `alias`, if any
+    pub fn get_rec_by_alias(&self, alias: &str) -> Result<Option<Bookmark>> {
+        self.conn
+            .query_row(
+                "SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE alias = ?1",
+                [alias],
+                |row| {
+                    Ok(Bookmark::new(
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .optional()
+    }
 
-        let bookmarks = stmt
-            .query_map(params.as_slice(), |row| {
-                Ok(Bookmark::new(
+    /// The alias of bookmark `id`, if it has one
+    pub fn get_alias(&self, id: usize) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT alias FROM bookmarks WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?
+            .flatten())
+    }
+
+    /// Every alias with its bookmark, by alias
+    pub fn get_aliases(&self) -> Result<Vec<(String, Bookmark)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT alias, id, URL, metadata, tags, desc FROM bookmarks
+             WHERE alias IS NOT NULL ORDER BY alias",
+        )?;
+        let aliases = stmt
+            .query_map([], |row| {
+                Ok((
                     row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
+                    Bookmark::new(
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ),
                 ))
             })?
             .collect::<Result<Vec<_>>>()?;
-
-        Ok(bookmarks)
+        Ok(aliases)
     }
 
-    pub fn search_tags(&self, tags: &[String]) -> Result<Vec<Bookmark>> {
-        // No tags - return all
-        if tags.is_empty() {
-            return self.get_rec_all();
+    /// Store `search` under its name; returns whether it replaced one
+    pub fn save_search(&self, search: &SavedSearch) -> crate::error::Result<bool> {
+        if search.name.is_empty() || utils::has_spaces(&search.name) {
+            return Err(crate::error::BukursError::InvalidInput(format!(
+                "'{}' isn't a valid name for a saved search (it can't be empty or contain spaces)",
+                search.name
+            )));
+        }
+        let query = serde_json::to_string(search)?;
+        let tx = self.write_transaction()?;
+        let replaced = tx.execute(
+            "UPDATE saved_searches SET query = ?2 WHERE name = ?1",
+            (&search.name, &query),
+        )? > 0;
+        if !replaced {
+            tx.execute(
+                "INSERT INTO saved_searches (name, query) VALUES (?1, ?2)",
+                (&search.name, &query),
+            )?;
         }
+        tx.commit()?;
+        Ok(replaced)
+    }
 
-        // Build FTS5 query targeting the tags column specifically
-        let quoted_tags = Self::quote_fts5_keywords(tags, Some("tags"));
-        let query = quoted_tags.join(" OR ");
+    pub fn get_saved_search(&self, name: &str) -> crate::error::Result<Option<SavedSearch>> {
+        let query: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT query FROM saved_searches WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        query.map(|query| saved_search(name, &query)).transpose()
+    }
 
-        // Query FTS5 table to get matching bookmark IDs
-        let mut stmt = self.conn.prepare_cached(
-            "SELECT rowid FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1 ORDER BY rank",
+    /// Every saved search, by name
+    pub fn get_saved_searches(&self) -> crate::error::Result<Vec<SavedSearch>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT name, query FROM saved_searches ORDER BY name")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        rows.iter()
+            .map(|(name, query)| saved_search(name, query))
+            .collect()
+    }
+
+    /// Returns whether there was a saved search called `name`
+    pub fn delete_saved_search(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM saved_searches WHERE name = ?1", [name])?
+            > 0)
+    }
+
+    /// Remember `query` for completion, keeping the newest [`SEARCH_HISTORY_LIMIT`]
+    pub fn record_search_query(&self, query: &str) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        self.conn.execute(
+            // REPLACE gives the row a new rowid, which orders queries made within a second
+            "INSERT OR REPLACE INTO search_history (query, last_used) VALUES (?1, ?2)",
+            (query, now),
         )?;
+        self.conn.execute(
+            "DELETE FROM search_history WHERE query NOT IN
+             (SELECT query FROM search_history ORDER BY last_used DESC, rowid DESC LIMIT ?1)",
+            [SEARCH_HISTORY_LIMIT],
+        )?;
+        Ok(())
+    }
 
-        let ids: Vec<usize> = stmt
-            .query_map([&query], |row| row.get::<_, i64>(0).map(|id| id as usize))?
-            .collect::<Result<Vec<_>>>()?;
+    /// Previous search queries, most recent first
+    pub fn search_history(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT query FROM search_history ORDER BY last_used DESC, rowid DESC",
+        )?;
+        let queries = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        Ok(queries)
+    }
 
-        if ids.is_empty() {
-            return Ok(Vec::new());
+    /// Store `collection`, checking its query parses; returns whether it replaced one
+    pub fn save_collection(&self, collection: &Collection) -> crate::error::Result<bool> {
+        if collection.name.is_empty() || utils::has_spaces(&collection.name) {
+            return Err(crate::error::BukursError::InvalidInput(format!(
+                "'{}' isn't a valid name for a collection (it can't be empty or contain spaces)",
+                collection.name
+            )));
         }
+        CollectionExpr::parse(&collection.query)?;
+        let replaced = self.get_collection(&collection.name)?.is_some();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO collections (name, query) VALUES (?1, ?2)",
+            (&collection.name, &collection.query),
+        )?;
+        Ok(replaced)
+    }
 
-        // Fetch full bookmark data for matching IDs
-        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        let query_str = format!(
-            "SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE id IN ({})",
-            placeholders
-        );
-
-        let mut stmt = self.conn.prepare(&query_str)?;
-        let params: Vec<&dyn rusqlite::ToSql> =
-            ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+    pub fn get_collection(&self, name: &str) -> Result<Option<Collection>> {
+        self.conn
+            .query_row(
+                "SELECT name, query FROM collections WHERE name = ?1",
+                [name],
+                |row| {
+                    Ok(Collection {
+                        name: row.get(0)?,
+                        query: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
 
-        let bookmarks = stmt
-            .query_map(params.as_slice(), |row| {
-                Ok(Bookmark::new(
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                ))
+    /// Every collection, by name
+    pub fn get_collections(&self) -> Result<Vec<Collection>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT name, query FROM collections ORDER BY name")?;
+        let collections = stmt
+            .query_map([], |row| {
+                Ok(Collection {
+                    name: row.get(0)?,
+                    query: row.get(1)?,
+                })
             })?
             .collect::<Result<Vec<_>>>()?;
+        Ok(collections)
+    }
 
-        Ok(bookmarks)
+    /// Returns whether there was a collection called `name`
+    pub fn delete_collection(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM collections WHERE name = ?1", [name])?
+            > 0)
     }
 
-    /// Get all unique tags from the database
-    /// Returns a sorted list of unique tags (excluding empty tags)
-    pub fn get_all_tags(&self) -> Result<Vec<String>> {
-        // Using the index on tags column for better performance
-        let mut stmt = self
+    /// Monitor bookmark `id` for page changes, starting from `hash`
+    ///
+    /// Returns whether it was monitored already; its hash is then replaced and
+    /// any flagged change cleared.
+    pub fn add_monitor(&self, id: usize, hash: &str, now: i64) -> crate::error::Result<bool> {
+        if self.get_rec_by_id(id)?.is_none() {
+            return Err(crate::error::BukursError::BookmarkNotFound(id));
+        }
+        let replaced = self
             .conn
-            .prepare_cached("SELECT DISTINCT tags FROM bookmarks WHERE tags != ','")?;
+            .query_row(
+                "SELECT 1 FROM monitors WHERE bookmark_id = ?1",
+                [id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO monitors (bookmark_id, hash, checked_at) VALUES (?1, ?2, ?3)",
+            (id, hash, now),
+        )?;
+        Ok(replaced)
+    }
 
-        let tags_result: Result<Vec<String>> = stmt
-            .query_map([], |row| row.get(0))?
-            .collect::<Result<Vec<_>>>();
+    /// Returns whether bookmark `id` was monitored
+    pub fn remove_monitor(&self, id: usize) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM monitors WHERE bookmark_id = ?1", [id])?
+            > 0)
+    }
 
-        let tags_strings = tags_result?;
+    /// Every monitored bookmark, by ID
+    pub fn get_monitors(&self) -> Result<Vec<Monitor>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT b.id, b.URL, b.metadata, m.hash, m.checked_at, m.changed_at, m.changed,
+                m.last_error
+             FROM monitors m JOIN bookmarks b ON b.id = m.bookmark_id
+             ORDER BY b.id",
+        )?;
+        let monitors = stmt
+            .query_map([], |row| {
+                Ok(Monitor {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    title: row.get(2)?,
+                    hash: row.get(3)?,
+                    checked_at: row.get(4)?,
+                    changed_at: row.get(5)?,
+                    changed: row.get(6)?,
+                    last_error: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(monitors)
+    }
 
-        // Parse all tag strings and collect unique tags
-        let mut unique_tags = std::collections::HashSet::new();
+    /// Store the hash a check of bookmark `id` found; returns whether it
+    /// differs from the stored one, in which case the change is flagged
+    pub fn record_monitor_hash(&self, id: usize, hash: &str, now: i64) -> Result<bool> {
+        let tx = self.write_transaction()?;
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT hash FROM monitors WHERE bookmark_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let changed = previous.is_some_and(|previous| previous != hash);
+        if changed {
+            tx.execute(
+                "UPDATE monitors SET hash = ?2, checked_at = ?3, changed_at = ?3, changed = 1,
+                    last_error = NULL
+                 WHERE bookmark_id = ?1",
+                (id, hash, now),
+            )?;
+        } else {
+            tx.execute(
+                "UPDATE monitors SET checked_at = ?2, last_error = NULL WHERE bookmark_id = ?1",
+                (id, now),
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
 
-        for tags_str in tags_strings {
-            // Tags are stored as ",tag1,tag2," - split by comma and filter empty strings
-            for tag in tags_str.split(',') {
-                let trimmed = tag.trim();
-                if !trimmed.is_empty() {
-                    unique_tags.insert(trimmed.to_string());
-                }
+    /// Record a failed check of bookmark `id`; its hash is kept
+    pub fn record_monitor_failure(&self, id: usize, error: &str, now: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE monitors SET checked_at = ?2, last_error = ?3 WHERE bookmark_id = ?1",
+            (id, now, error),
+        )?;
+        Ok(())
+    }
+
+    /// Mark the page changes of `ids` as seen; returns the number cleared
+    pub fn clear_monitor_changes(&self, ids: &[usize]) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let mut cleared = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE monitors SET changed = 0 WHERE bookmark_id = ?1 AND changed = 1",
+            )?;
+            for id in ids {
+                cleared += stmt.execute([id])?;
             }
         }
+        tx.commit()?;
+        Ok(cleared)
+    }
 
-        // Convert to sorted vector
-        let mut tags_vec: Vec<String> = unique_tags.into_iter().collect();
-        tags_vec.sort();
+    /// Count one visit for each of `ids`, after bukurs opened them in a browser
+    ///
+    /// Visits only feed `search --rank visits` and `--not-visited-since`, so they
+    /// aren't audited or undoable.
+    pub fn record_visits(&self, ids: &[usize]) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+        let tx = self.write_transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE bookmarks SET visits = COALESCE(visits, 0) + 1, visited_at = ?2 WHERE id = ?1",
+            )?;
+            for id in ids {
+                stmt.execute((id, now))?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
-        Ok(tags_vec)
+    /// When each bookmark was added and how often it was visited, keyed by ID
+    pub fn get_rank_keys(&self) -> Result<HashMap<usize, (i64, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, created_at, visits FROM bookmarks")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, usize>(0)?,
+                (
+                    row.get::<_, Option<i64>>(1)?.unwrap_or(0),
+                    row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                ),
+            ))
+        })?;
+        rows.collect()
     }
 
-    /// Undo the last operation or batch of operations
-    /// Returns Some((operation_type, count)) on success, None if nothing to undo
-    pub fn undo_last(&self) -> Result<Option<(String, usize)>> {
-        let tx = self.conn.unchecked_transaction()?;
+    /// Bookmarks that expired at or before `now`, with their expiry, soonest first
+    pub fn get_rec_expired(&self, now: i64) -> Result<Vec<(Bookmark, i64)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, URL, metadata, tags, desc, expires_at FROM bookmarks
+             WHERE expires_at IS NOT NULL AND expires_at <= ?1
+             ORDER BY expires_at ASC, id ASC",
+        )?;
+        let rows = stmt.query_map([now], |row| {
+            Ok((
+                Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ),
+                row.get(5)?,
+            ))
+        })?;
 
-        // Get the most recent undo log entry
-        let mut stmt = tx.prepare_cached(
-            "SELECT id, operation, bookmark_id, batch_id FROM undo_log ORDER BY id DESC LIMIT 1",
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    /// Every bookmark with all stored fields, by ID, for a dump
+    pub fn dump_bookmarks(&self) -> Result<Vec<DumpedBookmark>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, URL, metadata, tags, desc, parent_id, flags, created_at, pinned,
+                    position, expires_at, visits, alias, rating, word_count, lang, visited_at
+             FROM bookmarks ORDER BY id ASC",
         )?;
-        let mut rows = stmt.query([])?;
+        let rows = stmt.query_map([], |row| {
+            Ok(DumpedBookmark {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                tags: row
+                    .get::<_, Option<String>>(3)?
+                    .unwrap_or_else(|| ",".to_string()),
+                desc: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                parent_id: row.get(5)?,
+                flags: row.get::<_, Option<i32>>(6)?.unwrap_or(0),
+                created_at: row.get::<_, Option<i64>>(7)?.unwrap_or(0),
+                pinned: row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+                position: row.get(9)?,
+                expires_at: row.get(10)?,
+                visits: row.get::<_, Option<i64>>(11)?.unwrap_or(0),
+                alias: row.get(12)?,
+                rating: row.get::<_, Option<u8>>(13)?.unwrap_or(0),
+                word_count: row.get::<_, Option<usize>>(14)?.unwrap_or(0),
+                lang: row.get(15)?,
+                visited_at: row.get(16)?,
+            })
+        })?;
+        rows.collect()
+    }
 
-        if let Some(row) = rows.next()? {
-            let _log_id: usize = row.get(0)?;
-            let operation: String = row.get(1)?;
-            let _bookmark_id: usize = row.get(2)?;
-            let batch_id: Option<String> = row.get(3)?;
-            drop(rows);
-            drop(stmt);
+    /// The undo log, oldest first, for a dump
+    pub fn dump_undo_log(&self) -> Result<Vec<UndoEntry>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT timestamp, operation, bookmark_id, batch_id, url, title, tags, desc,
+                    parent_id, flags
+             FROM undo_log ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(UndoEntry {
+                timestamp: row.get::<_, Option<i64>>(0)?.unwrap_or(0),
+                operation: row.get(1)?,
+                bookmark_id: row.get(2)?,
+                batch_id: row.get(3)?,
+                url: row.get(4)?,
+                title: row.get(5)?,
+                tags: row.get(6)?,
+                desc: row.get(7)?,
+                parent_id: row.get(8)?,
+                flags: row.get(9)?,
+            })
+        })?;
+        rows.collect()
+    }
 
-            let mut affected_count = 0;
+    /// Replace every bookmark, the undo log and the fetch queue with a dump's contents
+    ///
+    /// Bookmarks keep their IDs. Without `undo_log` the history is cleared, since
+    /// it describes the bookmarks being replaced. Not undoable.
+    pub fn restore_dump(
+        &self,
+        bookmarks: &[DumpedBookmark],
+        undo_log: Option<&[UndoEntry]>,
+    ) -> Result<()> {
+        let tx = self.write_transaction()?;
+        tx.execute("DELETE FROM pending_fetch", [])?;
+        tx.execute("DELETE FROM undo_log", [])?;
+        tx.execute("DELETE FROM bookmarks", [])?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO bookmarks (id, URL, metadata, tags, desc, parent_id, flags,
+                    created_at, pinned, position, expires_at, visits, alias, rating, word_count,
+                    lang, visited_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                    ?17)",
+            )?;
+            for b in bookmarks {
+                stmt.execute(rusqlite::params![
+                    b.id,
+                    b.url,
+                    b.title,
+                    b.tags,
+                    b.desc,
+                    b.parent_id,
+                    b.flags,
+                    b.created_at,
+                    b.pinned,
+                    b.position,
+                    b.expires_at,
+                    b.visits,
+                    b.alias,
+                    b.rating,
+                    b.word_count,
+                    b.lang,
+                    b.visited_at,
+                ])?;
+            }
+        }
+
+        if let Some(entries) = undo_log {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO undo_log (timestamp, operation, bookmark_id, batch_id, url, title,
+                    tags, desc, parent_id, flags)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for e in entries {
+                stmt.execute(rusqlite::params![
+                    e.timestamp,
+                    e.operation,
+                    e.bookmark_id,
+                    e.batch_id,
+                    e.url,
+                    e.title,
+                    e.tags,
+                    e.desc,
+                    e.parent_id,
+                    e.flags,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        let ids: Vec<usize> = bookmarks.iter().map(|b| b.id).collect();
+        self.record_change(AuditOperation::Import, &ids);
+        Ok(())
+    }
+
+    /// Queue the bookmarks with these URLs for a later metadata fetch
+    /// Bookmarks that already have both a title and a description are not queued
+    /// Returns the number of bookmarks newly queued
+    pub fn queue_fetch<S: AsRef<str>>(&self, urls: &[S]) -> Result<usize> {
+        let tx = self.write_transaction()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+
+        let mut queued = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO pending_fetch (bookmark_id, queued_at)
+                 SELECT id, ?2 FROM bookmarks WHERE URL = ?1 AND (metadata = '' OR desc = '')",
+            )?;
+            for url in urls {
+                queued += stmt.execute((url.as_ref(), timestamp))?;
+            }
+        }
+        tx.commit()?;
+        Ok(queued)
+    }
+
+    /// Get bookmarks waiting for a metadata fetch, oldest first
+    pub fn get_pending_fetch(&self) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT b.id, b.URL, b.metadata, b.tags, b.desc
+             FROM pending_fetch p JOIN bookmarks b ON b.id = p.bookmark_id
+             ORDER BY p.queued_at, p.bookmark_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Bookmark::new(
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row?);
+        }
+        Ok(records)
+    }
+
+    /// Record a failed fetch attempt; the bookmark stays queued
+    pub fn record_fetch_failure(&self, id: usize, error: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE pending_fetch SET attempts = attempts + 1, last_error = ?2 WHERE bookmark_id = ?1",
+            (id, error),
+        )?;
+        Ok(())
+    }
+
+    /// Fill in fetched titles and descriptions in a single transaction with a shared batch_id
+    /// for undo, and remove the bookmarks from the fetch queue
+    ///
+    /// Only empty fields are filled, so titles given when adding are kept.
+    /// Returns the number of bookmarks changed
+    pub fn apply_fetched_metadata(&self, fetched: &[Bookmark]) -> Result<usize> {
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        let tx = self.write_transaction()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+
+        let mut updated = 0;
+        for bookmark in fetched {
+            let current = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT URL, metadata, tags, desc, parent_id, flags FROM bookmarks WHERE id = ?1",
+                )?;
+                stmt.query_row([bookmark.id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<usize>>(4)?,
+                        row.get::<_, i32>(5)?,
+                    ))
+                })
+                .optional()?
+            };
+            tx.execute(
+                "DELETE FROM pending_fetch WHERE bookmark_id = ?1",
+                [bookmark.id],
+            )?;
+
+            let Some((url, title, tags, desc, parent_id, flags)) = current else {
+                continue;
+            };
+            let new_title = if title.is_empty() {
+                &bookmark.title
+            } else {
+                &title
+            };
+            let new_desc = if desc.is_empty() {
+                &bookmark.description
+            } else {
+                &desc
+            };
+            if *new_title == title && *new_desc == desc {
+                continue;
+            }
+
+            tx.execute(
+                "INSERT INTO undo_log (timestamp, operation, bookmark_id, batch_id, url, title, tags, desc, parent_id, flags)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                (timestamp, "UPDATE", bookmark.id, &batch_id, &url, &title, &tags, &desc, parent_id, flags),
+            )?;
+            let fields = FieldUpdate {
+                title: Some(new_title),
+                desc: Some(new_desc),
+                ..FieldUpdate::default()
+            };
+            update_fields(&tx, bookmark.id, &fields)?;
+            updated += 1;
+        }
+
+        // Drop queue entries whose bookmarks have since been deleted
+        tx.execute(
+            "DELETE FROM pending_fetch WHERE bookmark_id NOT IN (SELECT id FROM bookmarks)",
+            [],
+        )?;
+        let changed_ids = self.batch_changed_ids(&tx, &batch_id)?;
+        tx.commit()?;
+        self.record_change(AuditOperation::Fetch, &changed_ids);
+        Ok(updated)
+    }
+
+    pub fn search(
+        &self,
+        keywords: &[String],
+        any: bool,
+        _deep: bool, // Deep is implicit with FTS5
+        regex: bool,
+    ) -> Result<Vec<Bookmark>> {
+        // Handle regex search separately (fallback to old method)
+        if regex {
+            let all_recs = self.get_rec_all()?;
+            let re = regex::Regex::new(&keywords[0])
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+            let filtered = all_recs
+                .into_iter()
+                .filter(|b| {
+                    re.is_match(&b.url)
+                        || re.is_match(&b.title)
+                        || re.is_match(&b.tags)
+                        || re.is_match(&b.description)
+                })
+                .collect();
+            return Ok(filtered);
+        }
+
+        // No keywords - return all
+        if keywords.is_empty() {
+            return self.get_rec_all();
+        }
+
+        if self.search_backend == SearchBackend::Like {
+            // FTS5 query syntax can't be honoured; match its words instead
+            let words: Vec<String> = keywords
+                .iter()
+                .flat_map(|k| k.split(" OR ").flat_map(|k| k.split(" AND ")))
+                .map(|k| k.replace('"', "").trim().trim_end_matches('*').to_string())
+                .filter(|k| !k.is_empty())
+                .collect();
+            let any = any || (keywords.len() == 1 && keywords[0].contains(" OR "));
+            return self.search_like(&["URL", "metadata", "tags", "desc"], &words, any);
+        }
+
+        let query = Self::fts5_query(keywords, any);
+
+        // Query FTS5 table to get matching bookmark IDs (ranked by relevance)
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT rowid FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1 ORDER BY rank",
+        )?;
+
+        let ids: Vec<usize> = stmt
+            .query_map([&query], |row| row.get::<_, i64>(0).map(|id| id as usize))?
+            .collect::<Result<Vec<_>>>()?;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Fetch full bookmark data for matching IDs
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let query_str = format!(
+            "SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE id IN ({})",
+            placeholders
+        );
+
+        let mut stmt = self.conn.prepare(&query_str)?;
+        let params: Vec<&dyn rusqlite::ToSql> =
+            ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+
+        let bookmarks = stmt
+            .query_map(params.as_slice(), |row| {
+                Ok(Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(bookmarks)
+    }
+
+    /// Like [`search`](Self::search), but best match first, with each bookmark's
+    /// bm25 score under `weights` (higher is better)
+    ///
+    /// Scores are `None` for regex searches, searches without keywords and the
+    /// LIKE backend, which don't rank matches; those keep the order `search` gives.
+    pub fn search_scored(
+        &self,
+        keywords: &[String],
+        any: bool,
+        regex: bool,
+        weights: &SearchWeights,
+    ) -> Result<Vec<(Bookmark, Option<f64>)>> {
+        if regex || keywords.is_empty() || self.search_backend == SearchBackend::Like {
+            return Ok(self
+                .search(keywords, any, true, regex)?
+                .into_iter()
+                .map(|b| (b, None))
+                .collect());
+        }
+
+        // bm25 is lower for better matches; negate it so scores read higher-is-better.
+        // Weights follow the column order of bookmarks_fts (url, metadata, tags, desc).
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT b.id, b.URL, b.metadata, b.tags, b.desc,
+                    -bm25(bookmarks_fts, ?2, ?3, ?4, ?5) AS score
+             FROM bookmarks_fts JOIN bookmarks b ON b.id = bookmarks_fts.rowid
+             WHERE bookmarks_fts MATCH ?1
+             ORDER BY score DESC, b.id ASC",
+        )?;
+        let query = Self::fts5_query(keywords, any);
+        let rows = stmt.query_map(
+            rusqlite::params![
+                query.as_ref(),
+                weights.url,
+                weights.title,
+                weights.tags,
+                weights.desc
+            ],
+            |row| {
+                Ok((
+                    Bookmark::new(
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ),
+                    Some(row.get(5)?),
+                ))
+            },
+        )?;
+        rows.collect()
+    }
+
+    /// FTS5 MATCH expression for search keywords
+    fn fts5_query(keywords: &[String], any: bool) -> std::borrow::Cow<'_, str> {
+        if keywords.len() == 1
+            && (utils::has_char(b'"', keywords[0].as_str())
+                || keywords[0].contains(" OR ")
+                || keywords[0].contains(" AND "))
+        {
+            // User provided FTS5 query syntax - use as is
+            std::borrow::Cow::Borrowed(&keywords[0])
+        } else {
+            // Simple keywords - quote each to treat as literal phrase and avoid FTS5 syntax errors
+            let quoted_keywords = Self::quote_fts5_keywords(keywords, None);
+            let join_op = if any { " OR " } else { " AND " };
+            std::borrow::Cow::Owned(quoted_keywords.join(join_op))
+        }
+    }
+
+    /// Search only the descriptions (notes) of bookmarks
+    pub fn search_notes(
+        &self,
+        keywords: &[String],
+        any: bool,
+        regex: bool,
+    ) -> Result<Vec<Bookmark>> {
+        if regex {
+            let re = regex::Regex::new(&keywords[0])
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            return Ok(self
+                .get_rec_all()?
+                .into_iter()
+                .filter(|b| re.is_match(&b.description))
+                .collect());
+        }
+
+        // No keywords - every bookmark with notes
+        if keywords.is_empty() {
+            return Ok(self
+                .get_rec_all()?
+                .into_iter()
+                .filter(|b| !b.description.trim().is_empty())
+                .collect());
+        }
+
+        if self.search_backend == SearchBackend::Like {
+            // LIKE matches substrings, so prefixes need no marker
+            let words: Vec<String> = keywords
+                .iter()
+                .map(|k| k.trim_end_matches('*').to_string())
+                .collect();
+            return self.search_like(&["desc"], &words, any);
+        }
+
+        let quoted = Self::quote_fts5_keywords(keywords, Some("desc"));
+        let join_op = if any { " OR " } else { " AND " };
+        let query = quoted.join(join_op);
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT rowid FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1 ORDER BY rank",
+        )?;
+        let ids: std::collections::HashSet<usize> = stmt
+            .query_map([&query], |row| row.get::<_, i64>(0).map(|id| id as usize))?
+            .collect::<Result<_>>()?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .get_rec_all()?
+            .into_iter()
+            .filter(|b| ids.contains(&b.id))
+            .collect())
+    }
+
+    pub fn search_tags(&self, tags: &[String]) -> Result<Vec<Bookmark>> {
+        // No tags - return all
+        if tags.is_empty() {
+            return self.get_rec_all();
+        }
+
+        if self.search_backend == SearchBackend::Like {
+            // Whole tags, since LIKE can't match the words within them
+            let condition = vec!["tags LIKE ? ESCAPE '\\'"; tags.len()].join(" OR ");
+            let patterns: Vec<String> = tags
+                .iter()
+                .map(|t| format!("%,{},%", escape_like(t)))
+                .collect();
+            return self.query_like(&condition, &patterns);
+        }
+
+        // Build FTS5 query targeting the tags column specifically
+        let quoted_tags = Self::quote_fts5_keywords(tags, Some("tags"));
+        let query = quoted_tags.join(" OR ");
+
+        // Query FTS5 table to get matching bookmark IDs
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT rowid FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1 ORDER BY rank",
+        )?;
+
+        let ids: Vec<usize> = stmt
+            .query_map([&query], |row| row.get::<_, i64>(0).map(|id| id as usize))?
+            .collect::<Result<Vec<_>>>()?;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Fetch full bookmark data for matching IDs
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let query_str = format!(
+            "SELECT id, URL, metadata, tags, desc FROM bookmarks WHERE id IN ({})",
+            placeholders
+        );
+
+        let mut stmt = self.conn.prepare(&query_str)?;
+        let params: Vec<&dyn rusqlite::ToSql> =
+            ids.iter().map(|id| id as &dyn rusqlite::ToSql).collect();
+
+        let bookmarks = stmt
+            .query_map(params.as_slice(), |row| {
+                Ok(Bookmark::new(
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(bookmarks)
+    }
+
+    /// Get the tag strings of every bookmark whose URL is on `domain` (see `utils::url_domain`)
+    pub fn get_tags_for_domain(&self, domain: &str) -> Result<Vec<String>> {
+        // LIKE narrows the scan; the exact host comparison happens below
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT URL, tags FROM bookmarks WHERE URL LIKE '%' || ?1 || '%' AND tags != ','",
+        )?;
+        let rows = stmt.query_map([domain], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut tags = Vec::new();
+        for row in rows {
+            let (url, row_tags) = row?;
+            if crate::utils::url_domain(&url).as_deref() == Some(domain) {
+                tags.push(row_tags);
+            }
+        }
+        Ok(tags)
+    }
+
+    /// IDs of the bookmarks `filter` keeps (`site:` and `-site:` in searches)
+    ///
+    /// Sites to keep become URL-prefix conditions (`http://site`,
+    /// `https://www.site`...) that narrow the scan; the host comparison
+    /// happens below.
+    pub fn get_ids_on_sites(&self, filter: &SiteFilter) -> Result<HashSet<usize>> {
+        let patterns: Vec<String> = filter
+            .include
+            .iter()
+            .flat_map(|site| {
+                let site = site
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                [format!("http%://{}%", site), format!("http%://%.{}%", site)]
+            })
+            .collect();
+        let mut sql = "SELECT id, URL FROM bookmarks".to_string();
+        if !patterns.is_empty() {
+            let conditions: Vec<&str> = patterns.iter().map(|_| "URL LIKE ? ESCAPE '\\'").collect();
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" OR "));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(&patterns), |row| {
+            Ok((row.get::<_, usize>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut ids = HashSet::new();
+        for row in rows {
+            let (id, url) = row?;
+            if filter.matches(&url) {
+                ids.insert(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Domains (see `utils::url_domain`) with the number of bookmarks on
+    /// each, most bookmarked first, then by name
+    pub fn get_domain_counts(&self) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare_cached("SELECT URL FROM bookmarks")?;
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for url in stmt.query_map([], |row| row.get::<_, String>(0))? {
+            if let Some(domain) = utils::url_domain(&url?) {
+                *counts.entry(domain).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        Ok(counts)
+    }
+
+    /// IDs of bookmarks added at or after `added_after` and not opened through
+    /// bukurs since `not_visited_since` (Unix seconds); None leaves that bound out
+    ///
+    /// Bookmarks never opened always count as not visited since the cutoff.
+    pub fn get_ids_in_timeframe(
+        &self,
+        added_after: Option<i64>,
+        not_visited_since: Option<i64>,
+    ) -> Result<HashSet<usize>> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(after) = added_after {
+            conditions.push("COALESCE(created_at, 0) >= ?");
+            params.push(after);
+        }
+        if let Some(since) = not_visited_since {
+            conditions.push("(visited_at IS NULL OR visited_at < ?)");
+            params.push(since);
+        }
+        let mut sql = "SELECT id FROM bookmarks".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(&params), |row| {
+            row.get::<_, usize>(0)
+        })?;
+        rows.collect()
+    }
+
+    /// Get all unique tags from the database
+    /// Returns a sorted list of unique tags (excluding empty tags)
+    pub fn get_all_tags(&self) -> Result<Vec<String>> {
+        // Using the index on tags column for better performance
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT DISTINCT tags FROM bookmarks WHERE tags != ','")?;
+
+        let tags_result: Result<Vec<String>> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>>>();
+
+        let tags_strings = tags_result?;
+
+        // Parse all tag strings and collect unique tags
+        let mut unique_tags = std::collections::HashSet::new();
+
+        for tags_str in tags_strings {
+            // Tags are stored as ",tag1,tag2," - split by comma and filter empty strings
+            for tag in tags_str.split(',') {
+                let trimmed = tag.trim();
+                if !trimmed.is_empty() {
+                    unique_tags.insert(trimmed.to_string());
+                }
+            }
+        }
+
+        // Convert to sorted vector
+        let mut tags_vec: Vec<String> = unique_tags.into_iter().collect();
+        tags_vec.sort();
+
+        Ok(tags_vec)
+    }
+
+    /// Undo the last operation or batch of operations
+    /// Returns Some((operation_type, count)) on success, None if nothing to undo
+    pub fn undo_last(&self) -> Result<Option<(String, usize)>> {
+        let tx = self.write_transaction()?;
+
+        // Get the most recent undo log entry
+        let mut stmt = tx.prepare_cached(
+            "SELECT id, operation, bookmark_id, batch_id FROM undo_log ORDER BY id DESC LIMIT 1",
+        )?;
+        let mut rows = stmt.query([])?;
+
+        if let Some(row) = rows.next()? {
+            let _log_id: usize = row.get(0)?;
+            let operation: String = row.get(1)?;
+            let bookmark_id: usize = row.get(2)?;
+            let batch_id: Option<String> = row.get(3)?;
+            drop(rows);
+            drop(stmt);
+
+            let mut affected_count = 0;
+            let mut changed_ids = Vec::new();
 
             if let Some(batch_id_val) = batch_id {
                 // This is a batch operation - undo all entries with the same batch_id
@@ -1021,6 +2925,7 @@ impl BukuDb {
 
                 // Create command objects and execute undo for each operation
                 for (log_entry_id, data) in batch_ops {
+                    changed_ids.push(data.bookmark_id);
                     if let Some(command) = UndoCommand::from_undo_log(data) {
                         command.undo(self)?;
                     }
@@ -1062,9 +2967,11 @@ impl BukuDb {
                     tx.execute("DELETE FROM undo_log WHERE id = ?1", [log_id])?;
                 }
                 affected_count = 1;
+                changed_ids.push(bookmark_id);
             }
 
             tx.commit()?;
+            self.record_change(AuditOperation::Undo, &changed_ids);
             Ok(Some((operation, affected_count)))
         } else {
             Ok(None)
@@ -1072,16 +2979,451 @@ impl BukuDb {
     }
 }
 
+/// Thread-safe handle to a database, shareable across threads (`Send + Sync`)
+///
+/// `BukuDb` owns a single SQLite connection and can't be shared. The pool opens
+/// up to `max_size` connections to the same file on demand; [`BukuDbPool::get`]
+/// lends one out and it returns to the pool when the guard is dropped. The guard
+/// derefs to `BukuDb`, so the usual API is available on it.
+pub struct BukuDbPool {
+    db_path: PathBuf,
+    options: ConnectionOptions,
+    autotag: AutotagRules,
+    audit: Option<AuditLog>,
+    source: AuditSource,
+    events: EventBus,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<BukuDb>,
+    open: usize,
+}
+
+impl BukuDbPool {
+    /// Create a pool for the database at `db_path`, creating its tables if needed
+    pub fn new(db_path: &Path, options: &ConnectionOptions, max_size: usize) -> Result<Self> {
+        let db = BukuDb::init_with_options(db_path, options)?;
+        Ok(Self::with_first(db, max_size.max(1)))
+    }
+
+    /// Wrap an already open database
+    ///
+    /// In-memory databases are private to their connection, so they get a pool
+    /// of one that serializes access to it.
+    pub fn from_db(db: BukuDb, max_size: usize) -> Self {
+        let max_size = if db.is_in_memory() {
+            1
+        } else {
+            max_size.max(1)
+        };
+        Self::with_first(db, max_size)
+    }
+
+    fn with_first(db: BukuDb, max_size: usize) -> Self {
+        Self {
+            db_path: db.db_path.clone(),
+            options: db.options.clone(),
+            autotag: db.autotag.clone(),
+            audit: db.audit.clone(),
+            source: db.source,
+            events: db.events.clone(),
+            max_size,
+            state: Mutex::new(PoolState {
+                idle: vec![db],
+                open: 1,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Borrow a connection, waiting for one to be returned if all are in use
+    pub fn get(&self) -> Result<PooledDb<'_>> {
+        let mut state = self.lock_state();
+        loop {
+            if let Some(db) = state.idle.pop() {
+                return Ok(PooledDb {
+                    pool: self,
+                    db: Some(db),
+                });
+            }
+            if state.open < self.max_size {
+                // Reserve the slot, then connect without holding the lock
+                state.open += 1;
+                drop(state);
+                return match BukuDb::connect(&self.db_path, &self.options) {
+                    Ok(mut db) => {
+                        db.set_autotag_rules(self.autotag.clone());
+                        db.set_audit_log(self.audit.clone());
+                        db.set_source(self.source);
+                        db.set_event_bus(self.events.clone());
+                        Ok(PooledDb {
+                            pool: self,
+                            db: Some(db),
+                        })
+                    }
+                    Err(e) => {
+                        self.lock_state().open -= 1;
+                        self.available.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            state = self
+                .available
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Maximum number of open connections
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Get the database file path
+    pub fn get_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// Set the per-domain tag rules applied to imported bookmarks, on every connection
+    pub fn set_autotag_rules(&mut self, rules: AutotagRules) {
+        let state = self
+            .state
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for db in &mut state.idle {
+            db.set_autotag_rules(rules.clone());
+        }
+        self.autotag = rules;
+    }
+
+    /// Set the audit log every connection records changes to
+    pub fn set_audit_log(&mut self, audit: Option<AuditLog>) {
+        let state = self
+            .state
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for db in &mut state.idle {
+            db.set_audit_log(audit.clone());
+        }
+        self.audit = audit;
+    }
+
+    /// Subscribers to the changes made through any of the pool's connections
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PoolState> {
+        // A panic while holding the lock can't leave the idle list inconsistent
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn release(&self, db: BukuDb) {
+        self.lock_state().idle.push(db);
+        self.available.notify_one();
+    }
+}
+
+/// A connection borrowed from a [`BukuDbPool`], returned to it on drop
+pub struct PooledDb<'a> {
+    pool: &'a BukuDbPool,
+    db: Option<BukuDb>,
+}
+
+impl Deref for PooledDb<'_> {
+    type Target = BukuDb;
+
+    fn deref(&self) -> &BukuDb {
+        self.db.as_ref().expect("connection already returned")
+    }
+}
+
+impl DerefMut for PooledDb<'_> {
+    fn deref_mut(&mut self) -> &mut BukuDb {
+        self.db.as_mut().expect("connection already returned")
+    }
+}
+
+impl Drop for PooledDb<'_> {
+    fn drop(&mut self) {
+        if let Some(db) = self.db.take() {
+            self.pool.release(db);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn setup_test_db() -> BukuDb {
-        // Use in-memory database for faster tests
-        let db = BukuDb::init(Path::new(":memory:")).unwrap();
-        db
+    fn setup_test_db() -> BukuDb {
+        // Use in-memory database for faster tests
+        let db = BukuDb::init(Path::new(":memory:")).unwrap();
+        db
+    }
+    use std::path::Path;
+
+    fn pragma<T: rusqlite::types::FromSql>(db: &BukuDb, name: &str) -> T {
+        db.conn
+            .query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_connection_options_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BukuDb::init(&dir.path().join("bookmarks.db")).unwrap();
+
+        assert_eq!(pragma::<String>(&db, "journal_mode"), "wal");
+        assert_eq!(pragma::<i64>(&db, "busy_timeout"), 5000);
+        assert_eq!(pragma::<i64>(&db, "synchronous"), 1); // NORMAL
+        assert_eq!(pragma::<i64>(&db, "foreign_keys"), 1);
+    }
+
+    #[test]
+    fn test_open_read_only_rejects_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.db");
+        BukuDb::init(&path)
+            .unwrap()
+            .add_rec("https://rust-lang.org", "Rust", ",lang,", "", None)
+            .unwrap();
+
+        let db = BukuDb::open_read_only(&path, &ConnectionOptions::default()).unwrap();
+        assert_eq!(
+            db.search(&["rust".to_string()], false, false, false)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(db
+            .add_rec("https://example.com", "", ",", "", None)
+            .is_err());
+        assert!(BukuDb::open_read_only(
+            &dir.path().join("missing.db"),
+            &ConnectionOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_in_memory_database_is_not_locked() {
+        let db = BukuDb::init_in_memory().unwrap();
+        assert!(db.is_in_memory());
+        assert!(db
+            .connection_options()
+            .lock_database(db.get_path())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_snapshot_and_load_round_trip() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust-lang.org", "Rust", ",lang,", "", None)
+            .unwrap();
+        db.add_rec("https://example.com", "Example", ",", "", None)
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.db");
+        db.snapshot_to(&path).unwrap();
+        // Snapshots replace whatever was there before
+        db.delete_rec(2).unwrap();
+        db.snapshot_to(&path).unwrap();
+        assert!(!path.with_extension("snapshot.tmp").exists());
+
+        let loaded = BukuDb::load_from(&path).unwrap();
+        assert!(loaded.is_in_memory());
+        assert_eq!(loaded.get_rec_all().unwrap().len(), 1);
+        assert_eq!(
+            loaded
+                .search(&["rust".to_string()], false, false, false)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The undo history comes along, and changes stay out of the file
+        assert_eq!(loaded.undo_last().unwrap(), Some(("DELETE".to_string(), 1)));
+        assert_eq!(loaded.get_rec_all().unwrap().len(), 2);
+        assert_eq!(
+            BukuDb::load_from(&path)
+                .unwrap()
+                .get_rec_all()
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert!(BukuDb::load_from(&dir.path().join("missing.db")).is_err());
+    }
+
+    #[test]
+    fn test_connection_options_custom() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ConnectionOptions {
+            journal_mode: JournalMode::Delete,
+            busy_timeout_ms: 250,
+            synchronous: Synchronous::Full,
+            foreign_keys: false,
+            ..ConnectionOptions::default()
+        };
+        let db = BukuDb::init_with_options(&dir.path().join("bookmarks.db"), &options).unwrap();
+
+        assert_eq!(pragma::<String>(&db, "journal_mode"), "delete");
+        assert_eq!(pragma::<i64>(&db, "busy_timeout"), 250);
+        assert_eq!(pragma::<i64>(&db, "synchronous"), 2); // FULL
+        assert_eq!(pragma::<i64>(&db, "foreign_keys"), 0);
+
+        // Import workers wait at least WORKER_BUSY_TIMEOUT_MS for the lock
+        let worker = BukuDb::open_with_options(db.get_path(), &options).unwrap();
+        assert_eq!(
+            pragma::<i64>(&worker, "busy_timeout"),
+            WORKER_BUSY_TIMEOUT_MS as i64
+        );
+    }
+
+    #[test]
+    fn test_pool_shared_across_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = BukuDbPool::new(
+            &dir.path().join("bookmarks.db"),
+            &ConnectionOptions::default(),
+            4,
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            for t in 0..8 {
+                let pool = &pool;
+                scope.spawn(move || {
+                    for i in 0..10 {
+                        let db = pool.get().unwrap();
+                        db.add_rec(&format!("https://t{}.com/{}", t, i), "", ",", "", None)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(pool.get().unwrap().get_rec_all().unwrap().len(), 80);
+        assert!(pool.lock_state().open <= pool.max_size());
+    }
+
+    #[test]
+    fn test_pool_in_memory_single_connection() {
+        let pool = BukuDbPool::from_db(BukuDb::init_in_memory().unwrap(), 8);
+        assert_eq!(pool.max_size(), 1);
+
+        pool.get()
+            .unwrap()
+            .add_rec("https://example.com", "", ",", "", None)
+            .unwrap();
+        // The same connection comes back, so the data is still there
+        assert_eq!(pool.get().unwrap().get_rec_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_pool_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BukuDbPool>();
+    }
+
+    #[test]
+    fn test_search_notes_only_matches_descriptions() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust.com", "Rust", ",rust,", "", None)
+            .unwrap();
+        db.add_rec("https://a.com", "A", ",", "Notes about\nrust macros", None)
+            .unwrap();
+        db.add_rec("https://b.com", "B", ",", "Go notes", None)
+            .unwrap();
+
+        let ids = |found: Vec<Bookmark>| found.iter().map(|b| b.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(db.search_notes(&["rust".into()], true, false).unwrap()),
+            vec![2]
+        );
+        assert_eq!(
+            ids(db.search_notes(&["ma.ros".into()], true, true).unwrap()),
+            vec![2]
+        );
+        assert_eq!(ids(db.search_notes(&[], true, false).unwrap()), vec![2, 3]);
+        assert!(db
+            .search_notes(&["rust".into(), "go".into()], false, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_update_rec_batch_full() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "A", ",a,", "", None).unwrap();
+        db.add_rec("https://b.com", "B", ",b,", "", None).unwrap();
+        db.add_rec("https://c.com", "C", ",c,", "", None).unwrap();
+
+        let edited = vec![
+            Bookmark::new(
+                1,
+                "https://a.org".into(),
+                "A2".into(),
+                ",x,".into(),
+                "d".into(),
+            ),
+            Bookmark::new(
+                3,
+                "https://c.com".into(),
+                "C2".into(),
+                ",c,".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                99,
+                "https://gone.com".into(),
+                "".into(),
+                ",".into(),
+                "".into(),
+            ),
+        ];
+        assert_eq!(db.update_rec_batch_full(&edited).unwrap(), 2);
+        let rec = db.get_rec_by_id(1).unwrap().unwrap();
+        assert_eq!(
+            (rec.url.as_str(), rec.title.as_str()),
+            ("https://a.org", "A2")
+        );
+
+        // A duplicate URL rolls back the whole batch
+        let clash = vec![
+            Bookmark::new(
+                2,
+                "https://b.org".into(),
+                "B".into(),
+                ",b,".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                3,
+                "https://a.org".into(),
+                "C".into(),
+                ",c,".into(),
+                "".into(),
+            ),
+        ];
+        assert!(db.update_rec_batch_full(&clash).is_err());
+        assert_eq!(db.get_rec_by_id(2).unwrap().unwrap().url, "https://b.com");
+
+        // One undo restores every bookmark in the batch
+        assert_eq!(db.undo_last().unwrap(), Some(("UPDATE".to_string(), 2)));
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().title, "A");
+        assert_eq!(db.get_rec_by_id(3).unwrap().unwrap().title, "C");
     }
-    use std::path::Path;
 
     #[test]
     fn test_add_rec() {
@@ -1233,7 +3575,7 @@ mod tests {
         .unwrap();
 
         let results = db
-            .search(&vec!["rust".to_string()], true, false, false)
+            .search(&["rust".to_string()], true, false, false)
             .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].title, "Rust");
@@ -1261,7 +3603,7 @@ mod tests {
 
         let results = db
             .search(
-                &vec!["rust".to_string(), "python".to_string()],
+                &["rust".to_string(), "python".to_string()],
                 true,
                 false,
                 false,
@@ -1292,7 +3634,7 @@ mod tests {
 
         let results = db
             .search(
-                &vec!["rust".to_string(), "programming".to_string()],
+                &["rust".to_string(), "programming".to_string()],
                 false,
                 false,
                 false,
@@ -1322,7 +3664,7 @@ mod tests {
         )
         .unwrap();
 
-        let results = db.search_tags(&vec!["rust".to_string()]).unwrap();
+        let results = db.search_tags(&["rust".to_string()]).unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].title, "Rust");
     }
@@ -1443,7 +3785,7 @@ mod tests {
     #[test]
     fn test_empty_search() {
         let db = setup_test_db();
-        let results = db.search(&vec![], true, false, false).unwrap();
+        let results = db.search(&[], true, false, false).unwrap();
         assert_eq!(results.len(), 0);
     }
 
@@ -1583,6 +3925,9 @@ mod tests {
     #[case(&["rust", "python"], true, 2, "")] // OR - matches both
     #[case(&["rust", "programming"], false, 1, "Rust")] // AND - matches only Rust
     #[case(&["nonexistent"], true, 0, "")]
+    #[case(&["pyth*"], true, 1, "Python")] // Prefix
+    #[case(&["prog*", "rus*"], false, 1, "Rust")]
+    #[case(&["prog"], true, 0, "")] // Bare keywords match whole words
     fn test_search_variations(
         #[case] keywords: &[&str],
         #[case] any: bool,
@@ -1893,180 +4238,462 @@ mod tests {
 
         // Undo all operations
         let mut undo_count = 0;
-        while let Some(_) = db.undo_last().unwrap() {
+        while db.undo_last().unwrap().is_some() {
             undo_count += 1;
         }
 
-        assert_eq!(undo_count, expected_undos);
+        assert_eq!(undo_count, expected_undos);
+
+        // Verify all bookmarks are gone
+        for id in ids {
+            assert!(db.get_rec_by_id(id).unwrap().is_none());
+        }
+    }
+
+    #[rstest]
+    #[case(Some("https://new.com"), None, None, None)]
+    #[case(None, Some("New Title"), None, None)]
+    #[case(None, None, Some(",new,tags,"), None)]
+    #[case(None, None, None, Some("New desc"))]
+    #[case(
+        Some("https://new.com"),
+        Some("New Title"),
+        Some(",new,"),
+        Some("New desc")
+    )]
+    fn test_update_rec_partial_updates(
+        #[case] url: Option<&str>,
+        #[case] title: Option<&str>,
+        #[case] tags: Option<&str>,
+        #[case] desc: Option<&str>,
+    ) {
+        let db = setup_test_db();
+        let id = db
+            .add_rec(
+                "https://original.com",
+                "Original Title",
+                ",original,",
+                "Original desc",
+                None,
+            )
+            .unwrap();
+
+        db.update_rec_partial(id, url, title, tags, desc, None)
+            .unwrap();
+
+        let bookmark = db.get_rec_by_id(id).unwrap().unwrap();
+
+        assert_eq!(bookmark.url, url.unwrap_or("https://original.com"));
+        assert_eq!(bookmark.title, title.unwrap_or("Original Title"));
+        assert_eq!(bookmark.tags, tags.unwrap_or(",original,"));
+        assert_eq!(bookmark.description, desc.unwrap_or("Original desc"));
+    }
+
+    #[test]
+    fn test_quote_fts5_keywords_without_prefix() {
+        let keywords = vec![
+            "test".to_string(),
+            "foo\"bar".to_string(),
+            "baz".to_string(),
+        ];
+        let quoted = BukuDb::quote_fts5_keywords(&keywords, None);
+
+        assert_eq!(quoted.len(), 3);
+        assert_eq!(quoted[0], "\"test\"");
+        assert_eq!(quoted[1], "\"foo\"\"bar\""); // Escaped quotes
+        assert_eq!(quoted[2], "\"baz\"");
+    }
+
+    #[test]
+    fn test_quote_fts5_keywords_with_prefix() {
+        let keywords = vec!["rust".to_string(), "c++".to_string()];
+        let quoted = BukuDb::quote_fts5_keywords(&keywords, Some("tags"));
+
+        assert_eq!(quoted.len(), 2);
+        assert_eq!(quoted[0], "tags:\"rust\"");
+        assert_eq!(quoted[1], "tags:\"c++\"");
+    }
+
+    #[test]
+    fn test_quote_fts5_keywords_keeps_prefix_star() {
+        let keywords = vec!["prog*".to_string(), "*".to_string()];
+        let quoted = BukuDb::quote_fts5_keywords(&keywords, None);
+        assert_eq!(quoted, vec!["\"prog\"*", "\"*\""]);
+    }
+
+    // === New Tests for Improved Coverage ===
+
+    /// Test undo with missing bookmark data in undo_log
+    #[test]
+    fn test_undo_with_missing_bookmark() {
+        let db = setup_test_db();
+        let id = db
+            .add_rec("https://example.com", "Test", ",test,", "Desc", None)
+            .unwrap();
+
+        // Manually insert incomplete undo log entry (missing required fields)
+        db.conn
+            .execute(
+                "INSERT INTO undo_log (timestamp, operation, bookmark_id) VALUES (?1, ?2, ?3)",
+                (12345, "UPDATE", id),
+            )
+            .unwrap();
+
+        // undo_last should handle gracefully (not crash)
+        let result = db.undo_last();
+        assert!(result.is_ok());
+    }
+
+    /// Test command data serialization in undo_log
+    #[test]
+    fn test_undo_log_stores_individual_columns() {
+        let db = setup_test_db();
+
+        // Add a bookmark
+        let id = db
+            .add_rec(
+                "https://test.com",
+                "Test Title",
+                ",rust,",
+                "Test Description",
+                None,
+            )
+            .unwrap();
+
+        // Verify individual columns were stored in undo_log
+        let mut stmt = db
+            .conn
+            .prepare("SELECT url, title, tags, desc, parent_id, flags FROM undo_log WHERE bookmark_id = ?1")
+            .unwrap();
+
+        let (url, title, tags, desc, parent_id, flags): (
+            String,
+            String,
+            String,
+            String,
+            Option<usize>,
+            i32,
+        ) = stmt
+            .query_row([id], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap();
+
+        assert_eq!(url, "https://test.com");
+        assert_eq!(title, "Test Title");
+        assert_eq!(tags, ",rust,");
+        assert_eq!(desc, "Test Description");
+        assert_eq!(parent_id, None);
+        assert_eq!(flags, 0);
+    }
+
+    /// Test undo_last doesn't create nested transactions
+    #[test]
+    fn test_undo_last_transaction_management() {
+        let db = setup_test_db();
+
+        // Add multiple operations
+        db.add_rec("https://test1.com", "Test 1", ",test,", "Desc", None)
+            .unwrap();
+        db.add_rec("https://test2.com", "Test 2", ",test,", "Desc", None)
+            .unwrap();
+        db.add_rec("https://test3.com", "Test 3", ",test,", "Desc", None)
+            .unwrap();
+
+        // Multiple undo_last calls should all succeed (no nested transaction errors)
+        assert!(db.undo_last().is_ok());
+        assert!(db.undo_last().is_ok());
+        assert!(db.undo_last().is_ok());
+        assert_eq!(db.undo_last().unwrap(), None); // No more to undo
+    }
+
+    #[test]
+    fn test_batch_delete_and_undo() {
+        let db = setup_test_db();
+
+        // Create multiple bookmarks
+        let id1 = db
+            .add_rec(
+                "https://example1.com",
+                "Example 1",
+                ",test,",
+                "Desc 1",
+                None,
+            )
+            .unwrap();
+        let id2 = db
+            .add_rec(
+                "https://example2.com",
+                "Example 2",
+                ",test,",
+                "Desc 2",
+                None,
+            )
+            .unwrap();
+        let id3 = db
+            .add_rec(
+                "https://example3.com",
+                "Example 3",
+                ",test,",
+                "Desc 3",
+                None,
+            )
+            .unwrap();
+
+        // Store original bookmarks for verification
+        let orig1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        let orig2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        let orig3 = db.get_rec_by_id(id3).unwrap().unwrap();
+
+        // Batch delete all three bookmarks
+        let deleted_count = db.delete_rec_batch(&[id1, id2, id3]).unwrap();
+        assert_eq!(deleted_count, 3);
+
+        // Verify all were deleted
+        assert!(db.get_rec_by_id(id1).unwrap().is_none());
+        assert!(db.get_rec_by_id(id2).unwrap().is_none());
+        assert!(db.get_rec_by_id(id3).unwrap().is_none());
+
+        // Undo once - should restore all three
+        let undo_result = db.undo_last().unwrap();
+        assert_eq!(undo_result, Some(("DELETE".to_string(), 3)));
+
+        // Verify all three are restored with original data
+        let restored1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        let restored2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        let restored3 = db.get_rec_by_id(id3).unwrap().unwrap();
+
+        assert_eq!(restored1.url, orig1.url);
+        assert_eq!(restored1.title, orig1.title);
+        assert_eq!(restored1.tags, orig1.tags);
+        assert_eq!(restored1.description, orig1.description);
+
+        assert_eq!(restored2.url, orig2.url);
+        assert_eq!(restored2.title, orig2.title);
+        assert_eq!(restored2.tags, orig2.tags);
+        assert_eq!(restored2.description, orig2.description);
+
+        assert_eq!(restored3.url, orig3.url);
+        assert_eq!(restored3.title, orig3.title);
+        assert_eq!(restored3.tags, orig3.tags);
+        assert_eq!(restored3.description, orig3.description);
+    }
+
+    #[test]
+    fn test_batch_delete_partial() {
+        let db = setup_test_db();
+
+        // Create bookmarks where only some IDs exist
+        let id1 = db
+            .add_rec(
+                "https://example1.com",
+                "Example 1",
+                ",test,",
+                "Desc 1",
+                None,
+            )
+            .unwrap();
+        let id2 = db
+            .add_rec(
+                "https://example2.com",
+                "Example 2",
+                ",test,",
+                "Desc 2",
+                None,
+            )
+            .unwrap();
+
+        // Try to delete including a non-existent ID
+        let deleted_count = db.delete_rec_batch(&[id1, 999, id2]).unwrap();
+        assert_eq!(deleted_count, 2); // Only the two valid ones should be deleted
 
-        // Verify all bookmarks are gone
-        for id in ids {
-            assert!(db.get_rec_by_id(id).unwrap().is_none());
-        }
+        // Verify the valid ones were deleted
+        assert!(db.get_rec_by_id(id1).unwrap().is_none());
+        assert!(db.get_rec_by_id(id2).unwrap().is_none());
     }
 
-    #[rstest]
-    #[case(Some("https://new.com"), None, None, None)]
-    #[case(None, Some("New Title"), None, None)]
-    #[case(None, None, Some(",new,tags,"), None)]
-    #[case(None, None, None, Some("New desc"))]
-    #[case(
-        Some("https://new.com"),
-        Some("New Title"),
-        Some(",new,"),
-        Some("New desc")
-    )]
-    fn test_update_rec_partial_updates(
-        #[case] url: Option<&str>,
-        #[case] title: Option<&str>,
-        #[case] tags: Option<&str>,
-        #[case] desc: Option<&str>,
-    ) {
+    #[test]
+    fn test_batch_update_with_tags_and_undo() {
         let db = setup_test_db();
-        let id = db
+
+        // Create multiple bookmarks with different tags
+        let id1 = db
             .add_rec(
-                "https://original.com",
-                "Original Title",
-                ",original,",
-                "Original desc",
+                "https://example1.com",
+                "Example 1",
+                ",tag1,tag2,",
+                "Desc 1",
                 None,
             )
             .unwrap();
-
-        db.update_rec_partial(id, url, title, tags, desc, None)
+        let id2 = db
+            .add_rec(
+                "https://example2.com",
+                "Example 2",
+                ",tag3,",
+                "Desc 2",
+                None,
+            )
+            .unwrap();
+        let id3 = db
+            .add_rec(
+                "https://example3.com",
+                "Example 3",
+                ",tag1,tag3,",
+                "Desc 3",
+                None,
+            )
             .unwrap();
 
-        let bookmark = db.get_rec_by_id(id).unwrap().unwrap();
+        // Store original tags
+        let orig1_tags = db.get_rec_by_id(id1).unwrap().unwrap().tags;
+        let orig2_tags = db.get_rec_by_id(id2).unwrap().unwrap().tags;
+        let orig3_tags = db.get_rec_by_id(id3).unwrap().unwrap().tags;
 
-        assert_eq!(bookmark.url, url.unwrap_or("https://original.com"));
-        assert_eq!(bookmark.title, title.unwrap_or("Original Title"));
-        assert_eq!(bookmark.tags, tags.unwrap_or(",original,"));
-        assert_eq!(bookmark.description, desc.unwrap_or("Original desc"));
-    }
+        // Create bookmarks with updated tags
+        let mut bm1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        bm1.tags = ",newtag1,newtag2,".to_string();
+        let mut bm2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        bm2.tags = ",newtag3,".to_string();
+        let mut bm3 = db.get_rec_by_id(id3).unwrap().unwrap();
+        bm3.tags = ",newtag1,newtag3,".to_string();
 
-    #[test]
-    fn test_quote_fts5_keywords_without_prefix() {
-        let keywords = vec![
-            "test".to_string(),
-            "foo\"bar".to_string(),
-            "baz".to_string(),
-        ];
-        let quoted = BukuDb::quote_fts5_keywords(&keywords, None);
+        // Batch update with tags
+        let result = db.update_rec_batch_with_tags(&[bm1, bm2, bm3], None, None, None, None, None);
+        assert!(result.is_ok());
+        let (success, _fail) = result.unwrap();
+        assert_eq!(success, 3);
 
-        assert_eq!(quoted.len(), 3);
-        assert_eq!(quoted[0], "\"test\"");
-        assert_eq!(quoted[1], "\"foo\"\"bar\""); // Escaped quotes
-        assert_eq!(quoted[2], "\"baz\"");
-    }
+        // Verify all tags were updated
+        assert_eq!(
+            db.get_rec_by_id(id1).unwrap().unwrap().tags,
+            ",newtag1,newtag2,"
+        );
+        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().tags, ",newtag3,");
+        assert_eq!(
+            db.get_rec_by_id(id3).unwrap().unwrap().tags,
+            ",newtag1,newtag3,"
+        );
 
-    #[test]
-    fn test_quote_fts5_keywords_with_prefix() {
-        let keywords = vec!["rust".to_string(), "c++".to_string()];
-        let quoted = BukuDb::quote_fts5_keywords(&keywords, Some("tags"));
+        // Undo once - should revert all three tags
+        let undo_result = db.undo_last().unwrap();
+        assert_eq!(undo_result, Some(("UPDATE".to_string(), 3)));
 
-        assert_eq!(quoted.len(), 2);
-        assert_eq!(quoted[0], "tags:\"rust\"");
-        assert_eq!(quoted[1], "tags:\"c++\"");
+        // Verify all tags are reverted to original
+        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().tags, orig1_tags);
+        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().tags, orig2_tags);
+        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().tags, orig3_tags);
     }
 
-    // === New Tests for Improved Coverage ===
-
-    /// Test undo with missing bookmark data in undo_log
     #[test]
-    fn test_undo_with_missing_bookmark() {
+    fn test_batch_update_with_mixed_fields_and_undo() {
         let db = setup_test_db();
-        let id = db
-            .add_rec("https://example.com", "Test", ",test,", "Desc", None)
-            .unwrap();
 
-        // Manually insert incomplete undo log entry (missing required fields)
-        db.conn
-            .execute(
-                "INSERT INTO undo_log (timestamp, operation, bookmark_id) VALUES (?1, ?2, ?3)",
-                (12345, "UPDATE", id),
-            )
+        // Create bookmarks
+        let id1 = db
+            .add_rec("https://example1.com", "Title 1", ",tag1,", "Desc 1", None)
+            .unwrap();
+        let id2 = db
+            .add_rec("https://example2.com", "Title 2", ",tag2,", "Desc 2", None)
             .unwrap();
 
-        // undo_last should handle gracefully (not crash)
-        let result = db.undo_last();
+        // Store original values
+        let orig1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        let orig2 = db.get_rec_by_id(id2).unwrap().unwrap();
+
+        // Update with tags and other fields
+        let mut bm1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        bm1.tags = ",updated,".to_string();
+        let mut bm2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        bm2.tags = ",updated,".to_string();
+
+        // Batch update with title, desc, and tags
+        let result = db.update_rec_batch_with_tags(
+            &[bm1, bm2],
+            None,
+            None,
+            Some("Updated Title"),
+            Some("Updated Desc"),
+            None,
+        );
         assert!(result.is_ok());
-    }
+        let (success, _fail) = result.unwrap();
+        assert_eq!(success, 2);
 
-    /// Test command data serialization in undo_log
-    #[test]
-    fn test_undo_log_stores_individual_columns() {
-        let db = setup_test_db();
+        // Verify all fields were updated
+        let updated1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        assert_eq!(updated1.title, "Updated Title");
+        assert_eq!(updated1.description, "Updated Desc");
+        assert_eq!(updated1.tags, ",updated,");
 
-        // Add a bookmark
-        let id = db
-            .add_rec(
-                "https://test.com",
-                "Test Title",
-                ",rust,",
-                "Test Description",
-                None,
-            )
-            .unwrap();
+        let updated2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        assert_eq!(updated2.title, "Updated Title");
+        assert_eq!(updated2.description, "Updated Desc");
+        assert_eq!(updated2.tags, ",updated,");
 
-        // Verify individual columns were stored in undo_log
-        let mut stmt = db
-            .conn
-            .prepare("SELECT url, title, tags, desc, parent_id, flags FROM undo_log WHERE bookmark_id = ?1")
-            .unwrap();
+        // Undo - should revert all fields
+        let undo_result = db.undo_last().unwrap();
+        assert_eq!(undo_result, Some(("UPDATE".to_string(), 2)));
 
-        let (url, title, tags, desc, parent_id, flags): (
-            String,
-            String,
-            String,
-            String,
-            Option<usize>,
-            i32,
-        ) = stmt
-            .query_row([id], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                    row.get(5)?,
-                ))
-            })
-            .unwrap();
+        // Verify all fields are reverted
+        let reverted1 = db.get_rec_by_id(id1).unwrap().unwrap();
+        assert_eq!(reverted1.title, orig1.title);
+        assert_eq!(reverted1.description, orig1.description);
+        assert_eq!(reverted1.tags, orig1.tags);
 
-        assert_eq!(url, "https://test.com");
-        assert_eq!(title, "Test Title");
-        assert_eq!(tags, ",rust,");
-        assert_eq!(desc, "Test Description");
-        assert_eq!(parent_id, None);
-        assert_eq!(flags, 0);
+        let reverted2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        assert_eq!(reverted2.title, orig2.title);
+        assert_eq!(reverted2.description, orig2.description);
+        assert_eq!(reverted2.tags, orig2.tags);
     }
 
-    /// Test undo_last doesn't create nested transactions
     #[test]
-    fn test_undo_last_transaction_management() {
+    fn test_batch_update_applies_tag_ops_to_stored_tags() {
         let db = setup_test_db();
-
-        // Add multiple operations
-        db.add_rec("https://test1.com", "Test 1", ",test,", "Desc", None)
+        let id1 = db
+            .add_rec("https://example1.com", "", ",todo,web,", "", None)
             .unwrap();
-        db.add_rec("https://test2.com", "Test 2", ",test,", "Desc", None)
+        let id2 = db
+            .add_rec("https://example2.com", "", ",archived,", "", None)
             .unwrap();
-        db.add_rec("https://test3.com", "Test 3", ",test,", "Desc", None)
+        let snapshot = db.get_rec_all().unwrap();
+        // Changed after the bookmarks were read; the operations still see it
+        db.update_rec_partial(id2, None, None, Some(",archived,rust,"), None, None)
+            .unwrap();
+
+        let ops = crate::tags::parse_tag_ops(&["+urgent,-archived", "~todo:done"]).unwrap();
+        let (success, _) = db
+            .update_rec_batch_with_tags(&snapshot, Some(&ops[..]), None, None, None, None)
             .unwrap();
+        assert_eq!(success, 2);
+        assert_eq!(
+            db.get_rec_by_id(id1).unwrap().unwrap().tags,
+            ",done,urgent,web,"
+        );
+        assert_eq!(
+            db.get_rec_by_id(id2).unwrap().unwrap().tags,
+            ",rust,urgent,"
+        );
 
-        // Multiple undo_last calls should all succeed (no nested transaction errors)
-        assert!(db.undo_last().is_ok());
-        assert!(db.undo_last().is_ok());
-        assert!(db.undo_last().is_ok());
-        assert_eq!(db.undo_last().unwrap(), None); // No more to undo
+        assert_eq!(db.undo_last().unwrap(), Some(("UPDATE".to_string(), 2)));
+        assert_eq!(
+            db.get_rec_by_id(id2).unwrap().unwrap().tags,
+            ",archived,rust,"
+        );
     }
 
     #[test]
-    fn test_batch_delete_and_undo() {
+    fn test_multiple_batch_operations_undo_order() {
         let db = setup_test_db();
 
-        // Create multiple bookmarks
+        // First batch: Add bookmarks
         let id1 = db
             .add_rec(
                 "https://example1.com",
@@ -2095,306 +4722,935 @@ mod tests {
             )
             .unwrap();
 
-        // Store original bookmarks for verification
-        let orig1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        let orig2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        let orig3 = db.get_rec_by_id(id3).unwrap().unwrap();
+        // Second batch: Update bookmarks
+        let bookmarks = vec![
+            db.get_rec_by_id(id1).unwrap().unwrap(),
+            db.get_rec_by_id(id2).unwrap().unwrap(),
+            db.get_rec_by_id(id3).unwrap().unwrap(),
+        ];
+        db.update_rec_batch(&bookmarks, None, Some("Updated"), None, None, None)
+            .unwrap();
 
-        // Batch delete all three bookmarks
-        let deleted_count = db.delete_rec_batch(&[id1, id2, id3]).unwrap();
-        assert_eq!(deleted_count, 3);
+        // Third batch: Delete bookmarks
+        db.delete_rec_batch(&[id1, id2, id3]).unwrap();
 
-        // Verify all were deleted
+        // Verify all are deleted
         assert!(db.get_rec_by_id(id1).unwrap().is_none());
         assert!(db.get_rec_by_id(id2).unwrap().is_none());
         assert!(db.get_rec_by_id(id3).unwrap().is_none());
 
-        // Undo once - should restore all three
-        let undo_result = db.undo_last().unwrap();
-        assert_eq!(undo_result, Some(("DELETE".to_string(), 3)));
-
-        // Verify all three are restored with original data
-        let restored1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        let restored2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        let restored3 = db.get_rec_by_id(id3).unwrap().unwrap();
+        // First undo: Restore delete (should bring back all 3 with "Updated" title)
+        let undo1 = db.undo_last().unwrap();
+        assert_eq!(undo1, Some(("DELETE".to_string(), 3)));
+        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().title, "Updated");
+        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().title, "Updated");
+        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().title, "Updated");
 
-        assert_eq!(restored1.url, orig1.url);
-        assert_eq!(restored1.title, orig1.title);
-        assert_eq!(restored1.tags, orig1.tags);
-        assert_eq!(restored1.description, orig1.description);
+        // Second undo: Revert update (should restore original titles)
+        let undo2 = db.undo_last().unwrap();
+        assert_eq!(undo2, Some(("UPDATE".to_string(), 3)));
+        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().title, "Example 1");
+        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().title, "Example 2");
+        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().title, "Example 3");
 
-        assert_eq!(restored2.url, orig2.url);
-        assert_eq!(restored2.title, orig2.title);
-        assert_eq!(restored2.tags, orig2.tags);
-        assert_eq!(restored2.description, orig2.description);
+        // Third undo: Remove all adds (should delete all 3)
+        let undo3 = db.undo_last().unwrap();
+        assert_eq!(undo3, Some(("ADD".to_string(), 1)));
+        let undo4 = db.undo_last().unwrap();
+        assert_eq!(undo4, Some(("ADD".to_string(), 1)));
+        let undo5 = db.undo_last().unwrap();
+        assert_eq!(undo5, Some(("ADD".to_string(), 1)));
 
-        assert_eq!(restored3.url, orig3.url);
-        assert_eq!(restored3.title, orig3.title);
-        assert_eq!(restored3.tags, orig3.tags);
-        assert_eq!(restored3.description, orig3.description);
+        assert!(db.get_rec_by_id(id1).unwrap().is_none());
+        assert!(db.get_rec_by_id(id2).unwrap().is_none());
+        assert!(db.get_rec_by_id(id3).unwrap().is_none());
     }
 
     #[test]
-    fn test_batch_delete_partial() {
+    fn test_empty_batch_operations() {
         let db = setup_test_db();
 
-        // Create bookmarks where only some IDs exist
-        let id1 = db
-            .add_rec(
-                "https://example1.com",
-                "Example 1",
-                ",test,",
-                "Desc 1",
-                None,
-            )
+        // Test empty batch delete
+        let result = db.delete_rec_batch(&[]);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 0);
+
+        // Test empty batch update
+        let result = db.update_rec_batch(&[], None, None, None, None, None);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), (0, 0));
+
+        // Test empty batch update with tags
+        let result = db.update_rec_batch_with_tags(&[], None, None, None, None, None);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), (0, 0));
+    }
+
+    fn parsed(url: &str, title: &str, tags: &str, desc: &str) -> ParsedBookmark {
+        ParsedBookmark {
+            url: url.to_string(),
+            title: title.to_string(),
+            tags: tags.to_string(),
+            desc: desc.to_string(),
+            parent_id: None,
+            created_at: None,
+            flags: 0,
+        }
+    }
+
+    #[rstest]
+    #[case(DuplicateStrategy::Skip, "", ",rust,", "Kept desc", 0, 1)]
+    #[case(DuplicateStrategy::Merge, "Imported", ",rust,web,", "Kept desc", 1, 0)]
+    #[case(DuplicateStrategy::Overwrite, "Imported", ",web,", "", 1, 0)]
+    fn test_import_rec_batch_duplicates(
+        #[case] strategy: DuplicateStrategy,
+        #[case] expected_title: &str,
+        #[case] expected_tags: &str,
+        #[case] expected_desc: &str,
+        #[case] expected_updated: usize,
+        #[case] expected_skipped: usize,
+    ) {
+        let db = setup_test_db();
+        let id = db
+            .add_rec("https://example.com", "", ",rust,", "Kept desc", None)
             .unwrap();
-        let id2 = db
-            .add_rec(
-                "https://example2.com",
-                "Example 2",
-                ",test,",
-                "Desc 2",
-                None,
+
+        let records = vec![
+            parsed("https://example.com", "Imported", ",web,", ""),
+            parsed("https://new.com", "New", ",", ""),
+        ];
+        let stats = db.import_rec_batch(&records, strategy, |_| {}).unwrap();
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.updated, expected_updated);
+        assert_eq!(stats.skipped, expected_skipped);
+
+        let bookmark = db.get_rec_by_id(id).unwrap().unwrap();
+        assert_eq!(bookmark.title, expected_title);
+        assert_eq!(bookmark.tags, expected_tags);
+        assert_eq!(bookmark.description, expected_desc);
+    }
+
+    #[rstest]
+    #[case(DuplicateStrategy::Skip, ReportReason::Duplicate)]
+    #[case(DuplicateStrategy::Merge, ReportReason::Merged)]
+    #[case(DuplicateStrategy::Overwrite, ReportReason::Overwritten)]
+    fn test_import_rec_batch_reports_skips_and_merges(
+        #[case] strategy: DuplicateStrategy,
+        #[case] reason: ReportReason,
+    ) {
+        let db = setup_test_db();
+        let id = db
+            .add_rec("https://example.com", "", ",rust,", "", None)
+            .unwrap();
+
+        let records = vec![
+            parsed("https://example.com", "Imported", ",web,", ""),
+            parsed("https://new.com", "New", ",", ""),
+            parsed("javascript:void(0)", "Bookmarklet", ",", ""),
+            parsed("", "Empty", ",", ""),
+        ];
+        let mut entries = Vec::new();
+        let stats = db
+            .import_rec_batch_reported(
+                &records,
+                strategy,
+                &CancellationToken::new(),
+                |_| {},
+                |entry| entries.push(entry),
             )
+            .unwrap()
             .unwrap();
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.updated + stats.skipped, 3);
 
-        // Try to delete including a non-existent ID
-        let deleted_count = db.delete_rec_batch(&[id1, 999, id2]).unwrap();
-        assert_eq!(deleted_count, 2); // Only the two valid ones should be deleted
+        let reported: Vec<(ReportReason, &str, Option<usize>)> = entries
+            .iter()
+            .map(|e| (e.reason, e.url.as_str(), e.existing_id))
+            .collect();
+        assert_eq!(
+            reported,
+            vec![
+                (reason, "https://example.com", Some(id)),
+                (ReportReason::InvalidUrl, "javascript:void(0)", None),
+                (ReportReason::InvalidUrl, "", None),
+            ]
+        );
+        assert_eq!(db.get_rec_all().unwrap().len(), 2);
+    }
 
-        // Verify the valid ones were deleted
-        assert!(db.get_rec_by_id(id1).unwrap().is_none());
-        assert!(db.get_rec_by_id(id2).unwrap().is_none());
+    #[test]
+    fn test_import_rec_batch_cancel_rolls_back() {
+        let db = setup_test_db();
+        let records = vec![
+            parsed("https://a.com", "A", ",", ""),
+            parsed("https://b.com", "B", ",", ""),
+            parsed("https://c.com", "C", ",", ""),
+        ];
+
+        let cancel = CancellationToken::new();
+        let mut seen = 0;
+        let stats = db
+            .import_rec_batch_cancellable(&records, DuplicateStrategy::Skip, &cancel, |_| {
+                seen += 1;
+                if seen == 2 {
+                    cancel.cancel();
+                }
+            })
+            .unwrap();
+
+        assert_eq!(stats, None);
+        assert!(db.get_rec_all().unwrap().is_empty());
+        assert!(db.undo_last().unwrap().is_none());
     }
 
     #[test]
-    fn test_batch_update_with_tags_and_undo() {
+    fn test_import_rec_batch_undo_reverts_whole_import() {
         let db = setup_test_db();
+        let id = db
+            .add_rec("https://example.com", "Example", ",rust,", "", None)
+            .unwrap();
 
-        // Create multiple bookmarks with different tags
-        let id1 = db
-            .add_rec(
-                "https://example1.com",
-                "Example 1",
-                ",tag1,tag2,",
-                "Desc 1",
-                None,
-            )
+        let records = vec![
+            parsed("https://new.com", "New", ",", ""),
+            parsed("https://example.com", "Example", ",web,", ""),
+        ];
+        db.import_rec_batch(&records, DuplicateStrategy::Merge, |_| {})
             .unwrap();
-        let id2 = db
-            .add_rec(
-                "https://example2.com",
-                "Example 2",
-                ",tag3,",
-                "Desc 2",
-                None,
-            )
+        assert_eq!(db.get_rec_all().unwrap().len(), 2);
+
+        let op = db.undo_last().unwrap();
+        assert_eq!(op, Some(("UPDATE".to_string(), 2)));
+        assert_eq!(db.get_rec_all().unwrap().len(), 1);
+        assert_eq!(db.get_rec_by_id(id).unwrap().unwrap().tags, ",rust,");
+    }
+
+    #[test]
+    fn test_get_rec_recent_orders_newest_first() {
+        let db = setup_test_db();
+        let old = db.add_rec("https://old.com", "Old", ",", "", None).unwrap();
+        let new = db.add_rec("https://new.com", "New", ",", "", None).unwrap();
+        let undated = db
+            .add_rec("https://undated.com", "Undated", ",", "", None)
             .unwrap();
-        let id3 = db
-            .add_rec(
-                "https://example3.com",
-                "Example 3",
-                ",tag1,tag3,",
-                "Desc 3",
-                None,
-            )
+        db.execute("UPDATE bookmarks SET created_at = 100 WHERE id = ?1", [old])
+            .unwrap();
+        db.execute("UPDATE bookmarks SET created_at = 200 WHERE id = ?1", [new])
             .unwrap();
+        db.execute(
+            "UPDATE bookmarks SET created_at = 0 WHERE id = ?1",
+            [undated],
+        )
+        .unwrap();
 
-        // Store original tags
-        let orig1_tags = db.get_rec_by_id(id1).unwrap().unwrap().tags;
-        let orig2_tags = db.get_rec_by_id(id2).unwrap().unwrap().tags;
-        let orig3_tags = db.get_rec_by_id(id3).unwrap().unwrap().tags;
+        let recent = db.get_rec_recent(2).unwrap();
+        let ids: Vec<_> = recent.iter().map(|(b, ts)| (b.id, *ts)).collect();
+        assert_eq!(ids, vec![(new, 200), (old, 100)]);
+    }
 
-        // Create bookmarks with updated tags
-        let mut bm1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        bm1.tags = ",newtag1,newtag2,".to_string();
-        let mut bm2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        bm2.tags = ",newtag3,".to_string();
-        let mut bm3 = db.get_rec_by_id(id3).unwrap().unwrap();
-        bm3.tags = ",newtag1,newtag3,".to_string();
+    #[test]
+    fn test_get_tags_for_domain_matches_host_exactly() {
+        let db = setup_test_db();
+        db.add_rec("https://github.com/a", "", ",code,", "", None)
+            .unwrap();
+        db.add_rec("https://www.github.com/b", "", ",git,", "", None)
+            .unwrap();
+        db.add_rec("https://gist.github.com/c", "", ",gist,", "", None)
+            .unwrap();
+        db.add_rec("https://example.com/?u=github.com", "", ",other,", "", None)
+            .unwrap();
+        db.add_rec("https://github.com/untagged", "", ",", "", None)
+            .unwrap();
 
-        // Batch update with tags
-        let result = db.update_rec_batch_with_tags(&[bm1, bm2, bm3], None, None, None, None);
-        assert!(result.is_ok());
-        let (success, _fail) = result.unwrap();
-        assert_eq!(success, 3);
+        let mut tags = db.get_tags_for_domain("github.com").unwrap();
+        tags.sort();
+        assert_eq!(tags, vec![",code,", ",git,"]);
+    }
 
-        // Verify all tags were updated
+    #[rstest]
+    #[case(&["github.com"], &[], vec![1, 2, 3])]
+    #[case(&["github.com"], &["gist.github.com"], vec![1, 2])]
+    #[case(&[], &["github.com"], vec![4, 5])]
+    #[case(&["100%_sure.org"], &[], vec![5])]
+    fn test_get_ids_on_sites(
+        #[case] include: &[&str],
+        #[case] exclude: &[&str],
+        #[case] expected: Vec<usize>,
+    ) {
+        let db = setup_test_db();
+        for url in [
+            "https://github.com/a",
+            "http://www.github.com/b",
+            "https://gist.github.com/c",
+            "https://example.com/?u=github.com",
+            "https://100%_sure.org",
+        ] {
+            db.add_rec(url, "", ",", "", None).unwrap();
+        }
+
+        let filter = SiteFilter {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+        };
+        let mut ids: Vec<usize> = db.get_ids_on_sites(&filter).unwrap().into_iter().collect();
+        ids.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_get_domain_counts() {
+        let db = setup_test_db();
+        for url in [
+            "https://github.com/a",
+            "https://www.github.com/b",
+            "https://docs.rs",
+            "https://crates.io",
+            "file:///tmp/notes.txt",
+        ] {
+            db.add_rec(url, "", ",", "", None).unwrap();
+        }
         assert_eq!(
-            db.get_rec_by_id(id1).unwrap().unwrap().tags,
-            ",newtag1,newtag2,"
+            db.get_domain_counts().unwrap(),
+            vec![
+                ("github.com".to_string(), 2),
+                ("crates.io".to_string(), 1),
+                ("docs.rs".to_string(), 1),
+            ]
         );
-        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().tags, ",newtag3,");
+    }
+
+    #[rstest]
+    #[case(None, None, vec![1, 2, 3])]
+    #[case(Some(200), None, vec![2, 3])]
+    #[case(None, Some(250), vec![1, 3])]
+    #[case(Some(200), Some(250), vec![3])]
+    fn test_get_ids_in_timeframe(
+        #[case] added_after: Option<i64>,
+        #[case] not_visited_since: Option<i64>,
+        #[case] expected: Vec<usize>,
+    ) {
+        let db = setup_test_db();
+        for (i, created_at) in [100, 200, 300].into_iter().enumerate() {
+            let id = db
+                .add_rec(&format!("https://{}.com", i), "", ",", "", None)
+                .unwrap();
+            db.execute(
+                "UPDATE bookmarks SET created_at = ?2 WHERE id = ?1",
+                (id, created_at),
+            )
+            .unwrap();
+        }
+        // Opened 1 long ago and 2 recently; 3 was never opened
+        db.execute("UPDATE bookmarks SET visited_at = 150 WHERE id = 1", [])
+            .unwrap();
+        db.execute("UPDATE bookmarks SET visited_at = 400 WHERE id = 2", [])
+            .unwrap();
+
+        let mut ids: Vec<usize> = db
+            .get_ids_in_timeframe(added_after, not_visited_since)
+            .unwrap()
+            .into_iter()
+            .collect();
+        ids.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_record_visits_sets_visited_at() {
+        let db = setup_test_db();
+        let id = db.add_rec("https://a.com", "", ",", "", None).unwrap();
+        db.record_visits(&[id]).unwrap();
+        let visited_at: Option<i64> = db
+            .conn
+            .query_row(
+                "SELECT visited_at FROM bookmarks WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(visited_at.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_import_rec_batch_applies_autotag_rules() {
+        let mut db = setup_test_db();
+        let mut rules = AutotagRules::new();
+        rules.insert("github.com", "code");
+        db.set_autotag_rules(rules);
+
+        let records = vec![
+            ParsedBookmark {
+                url: "https://github.com/a".to_string(),
+                title: "A".to_string(),
+                tags: ",rust,".to_string(),
+                desc: String::new(),
+                parent_id: None,
+                created_at: None,
+                flags: 0,
+            },
+            ParsedBookmark {
+                url: "https://example.com".to_string(),
+                title: "B".to_string(),
+                tags: ",".to_string(),
+                desc: String::new(),
+                parent_id: None,
+                created_at: None,
+                flags: 0,
+            },
+        ];
+        db.import_rec_batch(&records, DuplicateStrategy::Skip, |_| {})
+            .unwrap();
+
+        let tags: Vec<String> = db
+            .get_rec_all()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.tags)
+            .collect();
+        assert_eq!(tags, vec![",rust,code,", ","]);
+    }
+
+    #[test]
+    fn test_audit_log_records_changes() {
+        use crate::audit::{AuditOperation as Op, AuditSource};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let mut db = setup_test_db();
+        db.set_audit_log(Some(AuditLog::new(&path, AuditSource::Shell)));
+
+        let id1 = db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+        let id2 = db.add_rec("https://b.com", "B", ",", "", None).unwrap();
+        db.update_rec_partial(id1, None, Some("A2"), None, None, None)
+            .unwrap();
+        db.delete_rec_batch(&[id1, id2]).unwrap();
+        db.undo_last().unwrap();
+        // Nothing deleted, nothing recorded
+        db.delete_rec_batch(&[999]).unwrap();
+
+        let entries = AuditLog::read(&path).unwrap();
+        let ops: Vec<(Op, Vec<usize>)> = entries
+            .iter()
+            .map(|e| (e.operation, e.ids.clone()))
+            .collect();
         assert_eq!(
-            db.get_rec_by_id(id3).unwrap().unwrap().tags,
-            ",newtag1,newtag3,"
+            ops,
+            vec![
+                (Op::Add, vec![id1]),
+                (Op::Add, vec![id2]),
+                (Op::Update, vec![id1]),
+                (Op::Delete, vec![id1, id2]),
+                (Op::Undo, vec![id1, id2]),
+            ]
         );
+        assert!(entries.iter().all(|e| e.source == AuditSource::Shell));
 
-        // Undo once - should revert all three tags
-        let undo_result = db.undo_last().unwrap();
-        assert_eq!(undo_result, Some(("UPDATE".to_string(), 3)));
-
-        // Verify all tags are reverted to original
-        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().tags, orig1_tags);
-        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().tags, orig2_tags);
-        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().tags, orig3_tags);
+        db.set_audit_log(None);
+        db.add_rec("https://c.com", "C", ",", "", None).unwrap();
+        assert_eq!(AuditLog::read(&path).unwrap().len(), 5);
     }
 
     #[test]
-    fn test_batch_update_with_mixed_fields_and_undo() {
+    fn test_pending_fetch_queue() {
         let db = setup_test_db();
-
-        // Create bookmarks
-        let id1 = db
-            .add_rec("https://example1.com", "Title 1", ",tag1,", "Desc 1", None)
+        let titled = db
+            .add_rec("https://titled.com", "Mine", ",", "", None)
             .unwrap();
-        let id2 = db
-            .add_rec("https://example2.com", "Title 2", ",tag2,", "Desc 2", None)
+        let bare = db.add_rec("https://bare.com", "", ",", "", None).unwrap();
+        let done = db.add_rec("https://done.com", "", ",", "", None).unwrap();
+        db.add_rec("https://complete.com", "Title", ",", "Desc", None)
             .unwrap();
 
-        // Store original values
-        let orig1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        let orig2 = db.get_rec_by_id(id2).unwrap().unwrap();
+        let queued = db
+            .queue_fetch(&[
+                "https://titled.com",
+                "https://bare.com",
+                "https://done.com",
+                "https://complete.com",
+            ])
+            .unwrap();
+        assert_eq!(queued, 3);
+        // Queuing again is a no-op
+        assert_eq!(db.queue_fetch(&["https://bare.com"]).unwrap(), 0);
+
+        db.record_fetch_failure(done, "timeout").unwrap();
+        let pending: Vec<usize> = db
+            .get_pending_fetch()
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(pending, vec![titled, bare, done]);
+
+        let fetched = vec![
+            Bookmark::new(
+                titled,
+                "https://titled.com".into(),
+                "Fetched".into(),
+                ",".into(),
+                "Desc".into(),
+            ),
+            Bookmark::new(
+                bare,
+                "https://bare.com".into(),
+                "Bare".into(),
+                ",".into(),
+                "".into(),
+            ),
+        ];
+        assert_eq!(db.apply_fetched_metadata(&fetched).unwrap(), 2);
 
-        // Update with tags and other fields
-        let mut bm1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        bm1.tags = ",updated,".to_string();
-        let mut bm2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        bm2.tags = ",updated,".to_string();
+        // User-given title kept, empty fields filled
+        let rec = db.get_rec_by_id(titled).unwrap().unwrap();
+        assert_eq!(
+            (rec.title.as_str(), rec.description.as_str()),
+            ("Mine", "Desc")
+        );
+        assert_eq!(db.get_rec_by_id(bare).unwrap().unwrap().title, "Bare");
 
-        // Batch update with title, desc, and tags
-        let result = db.update_rec_batch_with_tags(
-            &[bm1, bm2],
+        let pending: Vec<usize> = db
+            .get_pending_fetch()
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(pending, vec![done]);
+
+        // One undo reverts the whole fetch
+        db.undo_last().unwrap();
+        assert_eq!(db.get_rec_by_id(bare).unwrap().unwrap().title, "");
+        assert_eq!(db.get_rec_by_id(titled).unwrap().unwrap().description, "");
+    }
+
+    /// A database searching as it would on a SQLite without FTS5
+    fn setup_like_db() -> BukuDb {
+        let mut db = setup_test_db();
+        db.search_backend = SearchBackend::Like;
+        db.setup_tables().unwrap();
+        db.add_rec(
+            "https://rust-lang.org",
+            "Rust",
+            ",programming,rust,",
+            "Systems language\nfast",
             None,
-            Some("Updated Title"),
-            Some("Updated Desc"),
+        )
+        .unwrap();
+        db.add_rec(
+            "https://python.org",
+            "Python",
+            ",programming,python,",
+            "100% readable",
             None,
-        );
-        assert!(result.is_ok());
-        let (success, _fail) = result.unwrap();
-        assert_eq!(success, 2);
+        )
+        .unwrap();
+        db
+    }
 
-        // Verify all fields were updated
-        let updated1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        assert_eq!(updated1.title, "Updated Title");
-        assert_eq!(updated1.description, "Updated Desc");
-        assert_eq!(updated1.tags, ",updated,");
+    fn ids(bookmarks: &[Bookmark]) -> Vec<usize> {
+        bookmarks.iter().map(|b| b.id).collect()
+    }
 
-        let updated2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        assert_eq!(updated2.title, "Updated Title");
-        assert_eq!(updated2.description, "Updated Desc");
-        assert_eq!(updated2.tags, ",updated,");
+    #[test]
+    fn test_search_backend_detected() {
+        let expected = if cfg!(feature = "fts") {
+            SearchBackend::Fts5
+        } else {
+            SearchBackend::Like
+        };
+        assert_eq!(setup_test_db().search_backend(), expected);
+        assert_eq!(SearchBackend::Like.to_string(), "like");
+    }
 
-        // Undo - should revert all fields
-        let undo_result = db.undo_last().unwrap();
-        assert_eq!(undo_result, Some(("UPDATE".to_string(), 2)));
+    #[rstest]
+    #[case(&["rust"], true, vec![1])]
+    #[case(&["RUST"], true, vec![1])]
+    #[case(&["rust", "python"], true, vec![1, 2])]
+    #[case(&["rust", "python"], false, vec![])]
+    #[case(&["programming", "org"], false, vec![1, 2])]
+    #[case(&["\"rust\" OR \"python\""], false, vec![1, 2])]
+    #[case(&["100%"], true, vec![2])]
+    #[case(&["1_0"], true, vec![])]
+    #[case(&["pyth*"], true, vec![2])]
+    fn test_search_like_fallback(
+        #[case] keywords: &[&str],
+        #[case] any: bool,
+        #[case] expected: Vec<usize>,
+    ) {
+        let db = setup_like_db();
+        let keywords: Vec<String> = keywords.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            ids(&db.search(&keywords, any, false, false).unwrap()),
+            expected
+        );
+    }
 
-        // Verify all fields are reverted
-        let reverted1 = db.get_rec_by_id(id1).unwrap().unwrap();
-        assert_eq!(reverted1.title, orig1.title);
-        assert_eq!(reverted1.description, orig1.description);
-        assert_eq!(reverted1.tags, orig1.tags);
+    #[rstest]
+    #[case(&["cafe"], vec![1])]
+    #[case(&["CAFÉ"], vec![1])]
+    #[case(&["naive"], vec![2])]
+    #[case(&["東京タワー"], vec![3])]
+    #[case(&["東京*"], vec![3])]
+    fn test_search_ignores_case_and_diacritics(
+        #[case] keywords: &[&str],
+        #[case] expected: Vec<usize>,
+        #[values(false, true)] like: bool,
+    ) {
+        let mut db = setup_test_db();
+        if like {
+            db.search_backend = SearchBackend::Like;
+            db.setup_tables().unwrap();
+        }
+        db.add_rec("https://a.com", "Café de Flore", ",paris,", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Article", ",naïve,", "", None)
+            .unwrap();
+        db.add_rec("https://c.com", "東京タワー", ",", "展望台", None)
+            .unwrap();
 
-        let reverted2 = db.get_rec_by_id(id2).unwrap().unwrap();
-        assert_eq!(reverted2.title, orig2.title);
-        assert_eq!(reverted2.description, orig2.description);
-        assert_eq!(reverted2.tags, orig2.tags);
+        let keywords: Vec<String> = keywords.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            ids(&db.search(&keywords, true, false, false).unwrap()),
+            expected
+        );
     }
 
     #[test]
-    fn test_multiple_batch_operations_undo_order() {
+    fn test_titles_and_tags_stored_composed() {
         let db = setup_test_db();
+        // "e" followed by a combining acute accent
+        let id = db
+            .add_rec("https://a.com", "Cafe\u{301}", ",cafe\u{301},", "", None)
+            .unwrap();
+        let bookmark = db.get_rec_by_id(id).unwrap().unwrap();
+        assert_eq!(bookmark.title, "Caf\u{e9}");
+        assert_eq!(bookmark.tags, ",caf\u{e9},");
 
-        // First batch: Add bookmarks
-        let id1 = db
-            .add_rec(
-                "https://example1.com",
-                "Example 1",
-                ",test,",
-                "Desc 1",
-                None,
-            )
+        db.update_rec_partial(id, None, Some("Nai\u{308}ve"), None, None, None)
             .unwrap();
-        let id2 = db
-            .add_rec(
-                "https://example2.com",
-                "Example 2",
-                ",test,",
-                "Desc 2",
-                None,
+        assert_eq!(db.get_rec_by_id(id).unwrap().unwrap().title, "Na\u{ef}ve");
+
+        // Every write path stores the same form
+        let mut bookmark = db.get_rec_by_id(id).unwrap().unwrap();
+        bookmark.tags = ",e\u{301}te\u{301},".to_string();
+        db.update_rec_batch_full(&[bookmark]).unwrap();
+        assert_eq!(
+            db.get_rec_by_id(id).unwrap().unwrap().tags,
+            ",\u{e9}t\u{e9},"
+        );
+        db.import_rec_batch(
+            &[parsed("https://b.com", "Cre\u{300}me", ",", "")],
+            DuplicateStrategy::Skip,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(db.get_rec_by_id(2).unwrap().unwrap().title, "Cr\u{e8}me");
+    }
+
+    #[test]
+    fn test_fts_index_without_diacritics_folding_is_rebuilt() {
+        let db = setup_test_db();
+        db.add_rec("https://a.com", "Café", ",", "", None).unwrap();
+        db.conn
+            .execute_batch(
+                "DROP TRIGGER bookmarks_ai;
+                 DROP TRIGGER bookmarks_au;
+                 DROP TRIGGER bookmarks_ad;
+                 DROP TABLE bookmarks_fts;
+                 CREATE VIRTUAL TABLE bookmarks_fts USING fts5(
+                     url, metadata, tags, desc, tokenize = 'unicode61'
+                 );",
             )
             .unwrap();
-        let id3 = db
-            .add_rec(
-                "https://example3.com",
-                "Example 3",
-                ",test,",
-                "Desc 3",
-                None,
-            )
+
+        db.setup_tables().unwrap();
+        assert_eq!(
+            ids(&db
+                .search(&["cafe".to_string()], true, false, false)
+                .unwrap()),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_search_tags_and_notes_like_fallback() {
+        let db = setup_like_db();
+        let tags = |t: &[&str]| {
+            let t: Vec<String> = t.iter().map(|s| s.to_string()).collect();
+            ids(&db.search_tags(&t).unwrap())
+        };
+        assert_eq!(tags(&["programming"]), vec![1, 2]);
+        assert_eq!(tags(&["rust", "python"]), vec![1, 2]);
+        // Whole tags only
+        assert_eq!(tags(&["prog"]), Vec::<usize>::new());
+
+        let notes = db.search_notes(&["fast".to_string()], true, false).unwrap();
+        assert_eq!(ids(&notes), vec![1]);
+        let notes = db.search_notes(&["rust".to_string()], true, false).unwrap();
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_index_rebuilt_after_writes_without_fts() {
+        if !cfg!(feature = "fts") {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        BukuDb::init(&path).unwrap();
+
+        // A build without FTS5 drops the triggers, so its writes skip the index
+        let mut db = BukuDb::init(&path).unwrap();
+        db.search_backend = SearchBackend::Like;
+        db.setup_tables().unwrap();
+        db.add_rec("https://rust-lang.org", "Rust", ",rust,", "", None)
             .unwrap();
+        drop(db);
 
-        // Second batch: Update bookmarks
-        let bookmarks = vec![
-            db.get_rec_by_id(id1).unwrap().unwrap(),
-            db.get_rec_by_id(id2).unwrap().unwrap(),
-            db.get_rec_by_id(id3).unwrap().unwrap(),
-        ];
-        db.update_rec_batch(&bookmarks, None, Some("Updated"), None, None, None)
+        let db = BukuDb::init(&path).unwrap();
+        assert_eq!(db.search_backend(), SearchBackend::Fts5);
+        let found = db
+            .search(&["rust".to_string()], true, false, false)
             .unwrap();
+        assert_eq!(ids(&found), vec![1]);
+    }
 
-        // Third batch: Delete bookmarks
-        db.delete_rec_batch(&[id1, id2, id3]).unwrap();
+    #[test]
+    fn test_needs_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let options = ConnectionOptions::default();
+        assert!(BukuDb::needs_migration(&path, &options).unwrap());
+
+        let db = BukuDb::init(&path).unwrap();
+        assert!(!BukuDb::needs_migration(&path, &options).unwrap());
+        db.conn
+            .execute("UPDATE schema_version SET version = version - 1", [])
+            .unwrap();
+        assert!(BukuDb::needs_migration(&path, &options).unwrap());
+    }
 
-        // Verify all are deleted
-        assert!(db.get_rec_by_id(id1).unwrap().is_none());
-        assert!(db.get_rec_by_id(id2).unwrap().is_none());
-        assert!(db.get_rec_by_id(id3).unwrap().is_none());
+    #[test]
+    fn test_reopen_keeps_schema_and_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let db = BukuDb::init(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        db.add_rec("https://rust-lang.org", "Rust", ",rust,", "", None)
+            .unwrap();
+        drop(db);
 
-        // First undo: Restore delete (should bring back all 3 with "Updated" title)
-        let undo1 = db.undo_last().unwrap();
-        assert_eq!(undo1, Some(("DELETE".to_string(), 3)));
-        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().title, "Updated");
-        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().title, "Updated");
-        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().title, "Updated");
+        // Index rows survive reopening in any build profile
+        let db = BukuDb::init(&path).unwrap();
+        let indexed: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM bookmarks_fts", [], |row| row.get(0))
+            .unwrap_or(0);
+        assert_eq!(
+            indexed,
+            i64::from(db.search_backend() == SearchBackend::Fts5)
+        );
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(db.get_rec_all().unwrap().len(), 1);
+    }
 
-        // Second undo: Revert update (should restore original titles)
-        let undo2 = db.undo_last().unwrap();
-        assert_eq!(undo2, Some(("UPDATE".to_string(), 3)));
-        assert_eq!(db.get_rec_by_id(id1).unwrap().unwrap().title, "Example 1");
-        assert_eq!(db.get_rec_by_id(id2).unwrap().unwrap().title, "Example 2");
-        assert_eq!(db.get_rec_by_id(id3).unwrap().unwrap().title, "Example 3");
+    #[test]
+    fn test_saved_searches() {
+        use crate::search::SearchRank;
 
-        // Third undo: Remove all adds (should delete all 3)
-        let undo3 = db.undo_last().unwrap();
-        assert_eq!(undo3, Some(("ADD".to_string(), 1)));
-        let undo4 = db.undo_last().unwrap();
-        assert_eq!(undo4, Some(("ADD".to_string(), 1)));
-        let undo5 = db.undo_last().unwrap();
-        assert_eq!(undo5, Some(("ADD".to_string(), 1)));
+        let db = BukuDb::init_in_memory().unwrap();
+        let mut search = SavedSearch {
+            name: "weekly-rust".to_string(),
+            keywords: vec!["rust".to_string(), "async".to_string()],
+            all: true,
+            ..SavedSearch::default()
+        };
+        assert!(!db.save_search(&search).unwrap());
+        assert!(db
+            .save_search(&SavedSearch {
+                name: "two words".to_string(),
+                ..search.clone()
+            })
+            .is_err());
+        search.rank = Some(SearchRank::Recent);
+        assert!(db.save_search(&search).unwrap());
+        db.save_search(&SavedSearch {
+            name: "go".to_string(),
+            keywords: vec!["golang".to_string()],
+            ..SavedSearch::default()
+        })
+        .unwrap();
 
-        assert!(db.get_rec_by_id(id1).unwrap().is_none());
-        assert!(db.get_rec_by_id(id2).unwrap().is_none());
-        assert!(db.get_rec_by_id(id3).unwrap().is_none());
+        assert_eq!(db.get_saved_search("weekly-rust").unwrap(), Some(search));
+        let names: Vec<String> = db
+            .get_saved_searches()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["go", "weekly-rust"]);
+
+        assert!(db.delete_saved_search("go").unwrap());
+        assert!(!db.delete_saved_search("go").unwrap());
+        assert_eq!(db.get_saved_search("go").unwrap(), None);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("two words")]
+    #[case("a,b")]
+    #[case("42")]
+    #[case("1-5")]
+    fn test_set_alias_rejects_invalid(#[case] alias: &str) {
+        let db = BukuDb::init_in_memory().unwrap();
+        let id = db
+            .add_rec("https://github.com", "GitHub", ",", "", None)
+            .unwrap();
+        assert!(db.set_alias(id, Some(alias)).is_err());
     }
 
     #[test]
-    fn test_empty_batch_operations() {
-        let db = setup_test_db();
+    fn test_aliases() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let github = db
+            .add_rec("https://github.com", "GitHub", ",", "", None)
+            .unwrap();
+        let docs = db
+            .add_rec("https://docs.rs", "Docs", ",", "", None)
+            .unwrap();
 
-        // Test empty batch delete
-        let result = db.delete_rec_batch(&[]);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 0);
+        db.set_alias(github, Some("gh")).unwrap();
+        // Setting it again is fine, taking it for another bookmark isn't
+        db.set_alias(github, Some("gh")).unwrap();
+        assert!(db.set_alias(docs, Some("gh")).is_err());
+        db.set_alias(docs, Some("docs")).unwrap();
+        assert!(db.set_alias(99, Some("nope")).is_err());
+
+        assert_eq!(db.get_rec_by_alias("gh").unwrap().unwrap().id, github);
+        assert_eq!(db.get_alias(docs).unwrap().as_deref(), Some("docs"));
+        let aliases: Vec<(String, usize)> = db
+            .get_aliases()
+            .unwrap()
+            .into_iter()
+            .map(|(alias, b)| (alias, b.id))
+            .collect();
+        assert_eq!(
+            aliases,
+            vec![("docs".to_string(), docs), ("gh".to_string(), github)]
+        );
 
-        // Test empty batch update
-        let result = db.update_rec_batch(&[], None, None, None, None, None);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), (0, 0));
+        db.set_alias(github, None).unwrap();
+        assert_eq!(db.get_rec_by_alias("gh").unwrap(), None);
+        assert_eq!(db.get_alias(github).unwrap(), None);
+    }
 
-        // Test empty batch update with tags
-        let result = db.update_rec_batch_with_tags(&[], None, None, None, None);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), (0, 0));
+    #[test]
+    fn test_collections() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let reading = Collection {
+            name: "reading".to_string(),
+            query: "tag:toread AND NOT tag:done".to_string(),
+        };
+        assert!(!db.save_collection(&reading).unwrap());
+        assert!(db.save_collection(&reading).unwrap());
+        for invalid in [
+            Collection {
+                name: "two words".to_string(),
+                ..reading.clone()
+            },
+            Collection {
+                name: "broken".to_string(),
+                query: "(tag:a".to_string(),
+            },
+        ] {
+            assert!(db.save_collection(&invalid).is_err());
+        }
+        db.save_collection(&Collection {
+            name: "archive".to_string(),
+            query: "tag:done".to_string(),
+        })
+        .unwrap();
+
+        assert_eq!(db.get_collection("reading").unwrap(), Some(reading));
+        let names: Vec<String> = db
+            .get_collections()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["archive", "reading"]);
+
+        assert!(db.delete_collection("archive").unwrap());
+        assert!(!db.delete_collection("archive").unwrap());
+        assert_eq!(db.get_collection("broken").unwrap(), None);
+    }
+
+    #[test]
+    fn test_monitors() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let a = db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+        let b = db.add_rec("https://b.com", "B", ",", "", None).unwrap();
+        assert!(db.add_monitor(99, "x", 1).is_err());
+        assert!(!db.add_monitor(a, "a1", 1).unwrap());
+        assert!(!db.add_monitor(b, "b1", 1).unwrap());
+
+        assert!(!db.record_monitor_hash(a, "a1", 2).unwrap());
+        assert!(db.record_monitor_hash(b, "b2", 2).unwrap());
+        db.record_monitor_failure(a, "HTTP 500", 3).unwrap();
+
+        let monitors = db.get_monitors().unwrap();
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(
+            (
+                monitors[0].hash.as_str(),
+                monitors[0].changed,
+                monitors[0].checked_at
+            ),
+            ("a1", false, 3)
+        );
+        assert_eq!(monitors[0].last_error.as_deref(), Some("HTTP 500"));
+        assert_eq!(
+            (
+                monitors[1].hash.as_str(),
+                monitors[1].changed,
+                monitors[1].changed_at
+            ),
+            ("b2", true, Some(2))
+        );
+
+        // A failed check keeps the flagged change; clearing it doesn't forget when it happened
+        db.record_monitor_failure(b, "timeout", 4).unwrap();
+        assert_eq!(db.clear_monitor_changes(&[a, b]).unwrap(), 1);
+        let monitor = &db.get_monitors().unwrap()[1];
+        assert!(!monitor.changed);
+        assert_eq!(monitor.changed_at, Some(2));
+
+        // Monitoring again replaces the hash
+        assert!(db.add_monitor(b, "b3", 5).unwrap());
+        assert_eq!(db.get_monitors().unwrap()[1].hash, "b3");
+
+        // Deleted bookmarks stop being monitored
+        db.delete_rec(a).unwrap();
+        assert_eq!(db.get_monitors().unwrap().len(), 1);
+        assert!(db.remove_monitor(b).unwrap());
+        assert!(!db.remove_monitor(b).unwrap());
+        assert!(db.get_monitors().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_history_is_most_recent_first() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for query in ["rust", "go", "rust async", "go"] {
+            db.record_search_query(query).unwrap();
+        }
+        assert_eq!(
+            db.search_history().unwrap(),
+            vec!["go", "rust async", "rust"]
+        );
+
+        for i in 0..SEARCH_HISTORY_LIMIT {
+            db.record_search_query(&format!("query {}", i)).unwrap();
+        }
+        let history = db.search_history().unwrap();
+        assert_eq!(history.len(), SEARCH_HISTORY_LIMIT);
+        assert!(!history.contains(&"rust".to_string()));
     }
 }
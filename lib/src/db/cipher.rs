@@ -0,0 +1,298 @@
+//! SQLCipher: databases that stay encrypted while in use
+//!
+//! With `database.encrypted` set, every connection gives SQLCipher the key
+//! before touching the file, so bookmarks are never written to disk in the
+//! clear (unlike `lock`/`unlock`, which encrypt a copy of a plain file). The
+//! key is taken from `BUKURS_DB_KEY`, then the output of
+//! `database.key_command` (e.g. a keyring lookup), then the caller's prompt.
+//!
+//! SQLCipher is only there when bukurs is built with the `sqlcipher` feature;
+//! [`available`] tells at run time, and keys are refused without it rather
+//! than silently ignored by plain SQLite.
+
+use crate::error::{BukursError, Result};
+use rusqlite::{ffi, Connection};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable holding the key of an encrypted database
+pub const KEY_ENV: &str = "BUKURS_DB_KEY";
+
+/// First bytes of every plaintext SQLite database
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// The key of a SQLCipher database; never printed
+#[derive(Clone, PartialEq, Eq)]
+pub struct DbKey(String);
+
+impl DbKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+impl fmt::Debug for DbKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DbKey(..)")
+    }
+}
+
+/// Whether this build's SQLite is SQLCipher
+pub fn available(conn: &Connection) -> bool {
+    conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+        .is_ok()
+}
+
+/// Whether the file at `path` is a plaintext SQLite database
+///
+/// Missing and empty files count as plaintext: SQLite creates them either way.
+pub fn is_plaintext(path: &Path) -> Result<bool> {
+    let mut header = [0u8; 16];
+    let read = match fs::File::open(path) {
+        Ok(mut file) => file.read(&mut header)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(read == 0 || &header == SQLITE_HEADER)
+}
+
+/// Give `conn` the key of its database, before anything else reads it
+pub(super) fn apply_key(conn: &Connection, key: &DbKey) -> rusqlite::Result<()> {
+    if !available(conn) {
+        return Err(rusqlite::Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_MISUSE),
+            Some("encrypted databases need bukurs built with the `sqlcipher` feature".to_string()),
+        ));
+    }
+    conn.pragma_update(None, "key", &key.0)?;
+    // The key is only checked on first read; a wrong one fails here with
+    // "file is not a database"
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+}
+
+/// The key from `BUKURS_DB_KEY`, else `key_command`'s output, else `prompt`
+pub fn find_key(
+    key_command: Option<&str>,
+    prompt: impl FnOnce() -> Result<String>,
+) -> Result<DbKey> {
+    if let Ok(key) = std::env::var(KEY_ENV) {
+        if !key.is_empty() {
+            return Ok(DbKey(key));
+        }
+    }
+    if let Some(command) = key_command {
+        return run_key_command(command);
+    }
+    let key = prompt()?;
+    if key.is_empty() {
+        return Err(BukursError::InvalidInput(
+            "The database key can't be empty".to_string(),
+        ));
+    }
+    Ok(DbKey(key))
+}
+
+/// Run `command` through the shell and take its first line as the key
+fn run_key_command(command: &str) -> Result<DbKey> {
+    let output = if cfg!(target_os = "windows") {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").arg("-c").arg(command).output()
+    }
+    .map_err(|e| BukursError::Config(format!("database.key_command: {}", e)))?;
+    if !output.status.success() {
+        return Err(BukursError::Config(format!(
+            "database.key_command failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout).map_err(|_| {
+        BukursError::Config("database.key_command printed a key that isn't UTF-8".to_string())
+    })?;
+    match stdout.lines().next() {
+        Some(key) if !key.is_empty() => Ok(DbKey(key.to_string())),
+        _ => Err(BukursError::Config(
+            "database.key_command printed no key".to_string(),
+        )),
+    }
+}
+
+/// Rewrite the plaintext database at `path` encrypted with `key`
+pub fn encrypt_file(path: &Path, key: &DbKey) -> Result<()> {
+    if !is_plaintext(path)? {
+        return Err(BukursError::InvalidInput(format!(
+            "{} is encrypted already",
+            path.display()
+        )));
+    }
+    export(path, None, key)
+}
+
+/// Rewrite the database at `path`, encrypted with `key`, as plaintext
+pub fn decrypt_file(path: &Path, key: &DbKey) -> Result<()> {
+    if is_plaintext(path)? {
+        return Err(BukursError::InvalidInput(format!(
+            "{} isn't encrypted",
+            path.display()
+        )));
+    }
+    export(path, Some(key), &DbKey(String::new()))
+}
+
+/// Copy the database at `path` (opened with `from`) into a new file keyed
+/// with `to` (empty for plaintext), then put the copy in its place
+///
+/// The original is only replaced once the copy is complete, by a rename, so
+/// an interrupted migration leaves it untouched.
+fn export(path: &Path, from: Option<&DbKey>, to: &DbKey) -> Result<()> {
+    if !path.is_file() {
+        return Err(BukursError::InvalidInput(format!(
+            "{} doesn't exist",
+            path.display()
+        )));
+    }
+    let target = temp_path(path);
+    let target_str = target.to_str().ok_or_else(|| {
+        BukursError::InvalidInput(format!("{} isn't valid UTF-8", target.display()))
+    })?;
+    let _ = fs::remove_file(&target);
+
+    let result = (|| -> Result<()> {
+        let conn = Connection::open(path)?;
+        match from {
+            Some(key) => apply_key(&conn, key)?,
+            None if !available(&conn) => {
+                return Err(BukursError::Config(
+                    "encrypting needs bukurs built with the `sqlcipher` feature".to_string(),
+                ))
+            }
+            None => {}
+        }
+        conn.execute(
+            "ATTACH DATABASE ?1 AS target KEY ?2",
+            [target_str, to.0.as_str()],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('target')", [], |_| Ok(()))?;
+        conn.execute("DETACH DATABASE target", [])?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&target);
+        return Err(e);
+    }
+    fs::rename(&target, path)?;
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".cipher-tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        assert!(is_plaintext(&path).unwrap());
+
+        Connection::open(&path)
+            .unwrap()
+            .execute("CREATE TABLE t (x)", [])
+            .unwrap();
+        assert!(is_plaintext(&path).unwrap());
+
+        fs::write(&path, [0x5au8; 64]).unwrap();
+        assert!(!is_plaintext(&path).unwrap());
+    }
+
+    #[test]
+    fn test_key_is_not_printed() {
+        assert_eq!(format!("{:?}", DbKey::new("hunter2")), "DbKey(..)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_command() {
+        assert_eq!(
+            run_key_command("printf 'secret\\nrest'").unwrap(),
+            DbKey::new("secret")
+        );
+        assert!(matches!(
+            run_key_command("exit 3"),
+            Err(BukursError::Config(_))
+        ));
+        assert!(matches!(
+            run_key_command("true"),
+            Err(BukursError::Config(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unlock_only_when_encrypted() {
+        use crate::db::ConnectionOptions;
+
+        let mut options = ConnectionOptions {
+            key_command: Some("echo secret".to_string()),
+            ..ConnectionOptions::default()
+        };
+        options.unlock(|| panic!("not encrypted")).unwrap();
+        assert_eq!(options.key, None);
+
+        options.encrypted = true;
+        options
+            .unlock(|| panic!("key_command gives the key"))
+            .unwrap();
+        assert!(options.key.is_some());
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn test_encrypt_and_decrypt_round_trip() {
+        use crate::db::{BukuDb, ConnectionOptions};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        BukuDb::init(&path)
+            .unwrap()
+            .add_rec("https://rust-lang.org", "Rust", ",", "", None)
+            .unwrap();
+        let key = DbKey::new("correct horse");
+
+        encrypt_file(&path, &key).unwrap();
+        assert!(!is_plaintext(&path).unwrap());
+        let options = ConnectionOptions {
+            key: Some(key.clone()),
+            ..ConnectionOptions::default()
+        };
+        let db = BukuDb::init_with_options(&path, &options).unwrap();
+        assert_eq!(db.get_rec_all().unwrap().len(), 1);
+        drop(db);
+        let wrong = ConnectionOptions {
+            key: Some(DbKey::new("wrong")),
+            ..ConnectionOptions::default()
+        };
+        assert!(BukuDb::init_with_options(&path, &wrong).is_err());
+
+        decrypt_file(&path, &key).unwrap();
+        assert!(is_plaintext(&path).unwrap());
+        assert_eq!(BukuDb::init(&path).unwrap().get_rec_all().unwrap().len(), 1);
+    }
+
+    #[cfg(not(feature = "sqlcipher"))]
+    #[test]
+    fn test_keys_refused_without_sqlcipher() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(!available(&conn));
+        assert!(apply_key(&conn, &DbKey::new("k")).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Advisory lock that makes bukurs processes take turns changing a database
+//!
+//! SQLite keeps each transaction safe on its own; this lock covers whole
+//! commands, so a shell session and a cron job don't interleave their undo
+//! entries or race through a schema migration. It is an OS file lock on
+//! `<db>.lock` (`bookmarks.db.lock`), released when the holder exits, even
+//! if it crashes.
+//!
+//! A thread holding the lock can take it again: code that locks around its
+//! own writes works the same whether or not its caller locked already.
+
+use crate::error::{BukursError, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often a waiting process retries the lock
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    /// Lock files this thread holds, with how many [`DbLock`]s share each
+    static HELD: RefCell<HashMap<PathBuf, (File, usize)>> = RefCell::new(HashMap::new());
+}
+
+/// Exclusive hold on a database's lock file, released when the thread's
+/// last hold on it is dropped
+#[derive(Debug)]
+pub struct DbLock {
+    path: PathBuf,
+    // Counted in this thread's `HELD`, so it must be dropped there
+    _not_send: PhantomData<*const ()>,
+}
+
+impl DbLock {
+    /// Lock file guarding `db_path`
+    pub fn path_for(db_path: &Path) -> PathBuf {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+
+    /// Wait up to `timeout` for the lock on `db_path`
+    ///
+    /// Fails with [`BukursError::Locked`] when another process still holds it.
+    pub fn acquire(db_path: &Path, timeout: Duration) -> Result<Self> {
+        let path = Self::path_for(db_path);
+        let held = HELD.with(|held| match held.borrow_mut().get_mut(&path) {
+            Some((_, count)) => {
+                *count += 1;
+                true
+            }
+            None => false,
+        });
+        if held {
+            return Ok(Self::new(path));
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    HELD.with(|held| held.borrow_mut().insert(path.clone(), (file, 1)));
+                    return Ok(Self::new(path));
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => return Err(BukursError::Locked(path)),
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for DbLock {
+    fn drop(&mut self) {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some((_, count)) = held.get_mut(&self.path) {
+                *count -= 1;
+                if *count == 0 {
+                    // Closing the file releases the lock
+                    held.remove(&self.path);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_path() {
+        assert_eq!(
+            DbLock::path_for(Path::new("/data/bookmarks.db")),
+            PathBuf::from("/data/bookmarks.db.lock")
+        );
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+
+        let held = DbLock::acquire(&db_path, Duration::ZERO).unwrap();
+        let other_thread = {
+            let db_path = db_path.clone();
+            move || DbLock::acquire(&db_path, Duration::from_millis(120)).map(drop)
+        };
+        assert!(matches!(
+            std::thread::spawn(other_thread.clone()).join().unwrap(),
+            Err(BukursError::Locked(path)) if path == DbLock::path_for(&db_path)
+        ));
+
+        drop(held);
+        std::thread::spawn(other_thread).join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_in_its_thread() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("bookmarks.db");
+        let try_other_thread = || {
+            let db_path = db_path.clone();
+            std::thread::spawn(move || DbLock::acquire(&db_path, Duration::ZERO).is_ok())
+                .join()
+                .unwrap()
+        };
+
+        let outer = DbLock::acquire(&db_path, Duration::ZERO).unwrap();
+        let inner = DbLock::acquire(&db_path, Duration::ZERO).unwrap();
+        drop(outer);
+        assert!(!try_other_thread(), "released while the inner hold remains");
+        drop(inner);
+        assert!(try_other_thread());
+    }
+}
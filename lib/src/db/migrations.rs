@@ -0,0 +1,425 @@
+//! Versioned schema migrations
+//!
+//! The `schema_version` table holds the number of the last step applied.
+//! Opening a database applies the steps after it in order, each in its own
+//! transaction together with the version bump, so an interrupted upgrade
+//! resumes where it stopped.
+//!
+//! Databases from before the table existed start at version 0. Their schema
+//! may already have some of the early steps' columns, so those steps check
+//! before adding anything. New steps go at the end of [`MIGRATIONS`] and are
+//! never edited once released.
+//!
+//! The FTS5 index is not part of the schema: it is derived from `bookmarks`
+//! and depends on the SQLite in use, so `BukuDb` manages it separately.
+
+use rusqlite::{Connection, OptionalExtension, Result, Transaction, TransactionBehavior};
+
+/// One schema change
+pub(super) struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Transaction) -> Result<()>,
+}
+
+/// Every schema change, in the order they are applied
+pub(super) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "bookmarks and undo_log tables",
+        apply: create_base_tables,
+    },
+    Migration {
+        version: 2,
+        description: "undo_log.batch_id",
+        apply: |tx| add_column(tx, "undo_log", "batch_id", "text"),
+    },
+    Migration {
+        version: 3,
+        description: "bookmarks.parent_id",
+        apply: |tx| add_column(tx, "bookmarks", "parent_id", "INTEGER DEFAULT NULL"),
+    },
+    Migration {
+        version: 4,
+        description: "bookmarks.flags",
+        apply: |tx| add_column(tx, "bookmarks", "flags", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        // Bookmarks added before the migration keep 0 (unknown)
+        version: 5,
+        description: "bookmarks.created_at",
+        apply: |tx| add_column(tx, "bookmarks", "created_at", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        version: 6,
+        description: "pending_fetch table",
+        apply: create_pending_fetch,
+    },
+    Migration {
+        version: 7,
+        description: "index on bookmarks.tags",
+        apply: |tx| {
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_bookmarks_tags ON bookmarks(tags)",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 8,
+        description: "bookmarks.pinned",
+        apply: |tx| add_column(tx, "bookmarks", "pinned", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        // NULL until a bookmark is moved; those sort after the ones with a position
+        version: 9,
+        description: "bookmarks.position",
+        apply: |tx| add_column(tx, "bookmarks", "position", "INTEGER DEFAULT NULL"),
+    },
+    Migration {
+        // Unix seconds; NULL for bookmarks that never expire
+        version: 10,
+        description: "bookmarks.expires_at",
+        apply: |tx| add_column(tx, "bookmarks", "expires_at", "INTEGER DEFAULT NULL"),
+    },
+    Migration {
+        // How often a bookmark was opened through bukurs, for `search --rank visits`
+        version: 11,
+        description: "bookmarks.visits",
+        apply: |tx| add_column(tx, "bookmarks", "visits", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        version: 12,
+        description: "events table",
+        apply: create_events,
+    },
+    Migration {
+        version: 13,
+        description: "saved_searches and search_history tables",
+        apply: create_saved_searches,
+    },
+    Migration {
+        version: 14,
+        description: "collections table",
+        apply: create_collections,
+    },
+    Migration {
+        // Unique short names for bookmarks (`alias set`); NULL for most
+        version: 15,
+        description: "bookmarks.alias",
+        apply: |tx| {
+            add_column(tx, "bookmarks", "alias", "text DEFAULT NULL")?;
+            tx.execute(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_bookmarks_alias ON bookmarks(alias)",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        // Stars from `rate` and `star`, 1 to 5; 0 for unrated bookmarks
+        version: 16,
+        description: "bookmarks.rating",
+        apply: |tx| add_column(tx, "bookmarks", "rating", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        // Words in the page's main text, for reading times; 0 when not measured
+        version: 17,
+        description: "bookmarks.word_count",
+        apply: |tx| add_column(tx, "bookmarks", "word_count", "INTEGER DEFAULT 0"),
+    },
+    Migration {
+        // ISO 639-1 code of the page's language; NULL when unknown
+        version: 18,
+        description: "bookmarks.lang",
+        apply: |tx| add_column(tx, "bookmarks", "lang", "TEXT DEFAULT NULL"),
+    },
+    Migration {
+        // When bukurs last opened the bookmark, for `--not-visited-since`; NULL when never
+        version: 19,
+        description: "bookmarks.visited_at",
+        apply: |tx| add_column(tx, "bookmarks", "visited_at", "INTEGER DEFAULT NULL"),
+    },
+    Migration {
+        version: 20,
+        description: "monitors table",
+        apply: create_monitors,
+    },
+];
+
+/// Schema version this build creates and understands
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Version recorded in the database; 0 when it predates versioning or is new
+pub(super) fn current_version(conn: &Connection) -> Result<u32> {
+    let has_table: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_table {
+        return Ok(0);
+    }
+    let version: Option<u32> = conn
+        .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+        .optional()?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply every step after the database's version; returns the version reached
+pub(super) fn migrate(conn: &Connection) -> Result<u32> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (version integer NOT NULL)",
+        [],
+    )?;
+
+    for migration in MIGRATIONS {
+        // Immediate, so a second process waits here and then sees the new version
+        let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+        if current_version(&tx)? >= migration.version {
+            continue;
+        }
+        log::info!(
+            "Migrating database schema to version {}: {}",
+            migration.version,
+            migration.description
+        );
+        (migration.apply)(&tx)?;
+        tx.execute("DELETE FROM schema_version", [])?;
+        tx.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [migration.version],
+        )?;
+        tx.commit()?;
+    }
+
+    let version = current_version(conn)?;
+    if version > SCHEMA_VERSION {
+        log::warn!(
+            "Database schema version {} is newer than this bukurs knows ({}); consider upgrading",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(version)
+}
+
+fn has_column(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Add a column unless the database already has it (see the module docs)
+fn add_column(tx: &Transaction, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(tx, table, column)? {
+        tx.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_base_tables(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists bookmarks (
+            id integer PRIMARY KEY,
+            URL text NOT NULL UNIQUE,
+            metadata text default '',
+            tags text default ',',
+            desc text default ''
+        )",
+        [],
+    )?;
+
+    tx.execute(
+        "CREATE TABLE if not exists undo_log (
+            id integer PRIMARY KEY AUTOINCREMENT,
+            timestamp integer,
+            operation text,
+            bookmark_id integer,
+            -- Bookmark fields for undo
+            url text,
+            title text,
+            tags text,
+            desc text,
+            parent_id integer,
+            flags integer
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Bookmarks added without metadata (offline or failed fetch), for `fetch-pending`
+fn create_pending_fetch(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists pending_fetch (
+            bookmark_id integer PRIMARY KEY,
+            queued_at integer,
+            attempts integer default 0,
+            last_error text
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Recent changes, for watchers in other processes (see `crate::events`)
+fn create_events(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists events (
+            seq integer PRIMARY KEY AUTOINCREMENT,
+            timestamp integer,
+            operation text,
+            -- JSON array of bookmark IDs
+            ids text,
+            source text
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Named searches (`search --save`) and recent queries for shell completion
+fn create_saved_searches(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists saved_searches (
+            name text PRIMARY KEY,
+            -- JSON of the search's keywords and options
+            query text NOT NULL
+        )",
+        [],
+    )?;
+    tx.execute(
+        "CREATE TABLE if not exists search_history (
+            query text PRIMARY KEY,
+            last_used integer
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn create_collections(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists collections (
+            name text PRIMARY KEY,
+            query text NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Content hashes of pages watched for changes (`monitor add`, see `crate::monitor`)
+fn create_monitors(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "CREATE TABLE if not exists monitors (
+            bookmark_id integer PRIMARY KEY,
+            hash text NOT NULL,
+            checked_at integer,
+            -- Last check that found a different hash; NULL when never
+            changed_at integer,
+            -- 1 until the change is cleared with `monitor changes --clear`
+            changed integer default 0,
+            last_error text
+        )",
+        [],
+    )?;
+    // A deleted bookmark stops being monitored, even if its ID is reused
+    tx.execute(
+        "CREATE TRIGGER IF NOT EXISTS monitors_bookmark_ad AFTER DELETE ON bookmarks BEGIN
+            DELETE FROM monitors WHERE bookmark_id = old.id;
+        END",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("PRAGMA table_info({})", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get(1))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_versions_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_new_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(columns(&conn, "bookmarks").contains(&"created_at".to_string()));
+        assert!(columns(&conn, "undo_log").contains(&"batch_id".to_string()));
+
+        // Nothing left to do the second time
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_database_keeps_data() {
+        // Schema from before versioning, with some of the later columns already added
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE bookmarks (
+                id integer PRIMARY KEY,
+                URL text NOT NULL UNIQUE,
+                metadata text default '',
+                tags text default ',',
+                desc text default '',
+                flags integer default 0
+            );
+            CREATE TABLE undo_log (
+                id integer PRIMARY KEY AUTOINCREMENT,
+                timestamp integer,
+                operation text,
+                bookmark_id integer,
+                batch_id text,
+                url text,
+                title text,
+                tags text,
+                desc text,
+                parent_id integer,
+                flags integer
+            );
+            INSERT INTO bookmarks (URL, metadata) VALUES ('https://a.com', 'A');",
+        )
+        .unwrap();
+
+        assert_eq!(migrate(&conn).unwrap(), SCHEMA_VERSION);
+        let bookmarks = columns(&conn, "bookmarks");
+        assert!(bookmarks.contains(&"parent_id".to_string()));
+        assert!(bookmarks.contains(&"created_at".to_string()));
+        let (title, created_at): (String, i64) = conn
+            .query_row("SELECT metadata, created_at FROM bookmarks", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((title.as_str(), created_at), ("A", 0));
+    }
+}
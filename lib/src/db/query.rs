@@ -0,0 +1,224 @@
+//! Arbitrary SQL against the bookmark database (`bukurs query`)
+//!
+//! An escape hatch for questions the commands don't answer, without needing
+//! the `sqlite3` binary. SQLite reports whether a prepared statement writes,
+//! so read-only runs reject `INSERT`, `UPDATE`, `DELETE` and schema changes
+//! before anything executes. They also run with `PRAGMA query_only` on, and
+//! refuse the statements that never count as writes but change the
+//! connection: `ATTACH`, `DETACH`, transactions and savepoints. Allowed
+//! writes run in one transaction but skip the undo log and the audit trail.
+
+use super::BukuDb;
+use crate::error::{BukursError, Result};
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::types::ValueRef;
+use rusqlite::{ErrorCode, Statement};
+use serde_json::Value;
+
+/// Columns and rows returned by a query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    /// One value per column: null, a number or a string (blobs as hex)
+    pub rows: Vec<Vec<Value>>,
+    /// Rows changed by a statement that writes and returns no columns
+    pub changed: Option<usize>,
+}
+
+impl BukuDb {
+    /// Run one SQL statement and collect what it returns
+    ///
+    /// Unless `allow_write` is set, statements that would change the database
+    /// are refused without running. Several statements separated by `;` are
+    /// an error.
+    pub fn run_query(&self, sql: &str, allow_write: bool) -> Result<QueryResult> {
+        if !allow_write {
+            let query_only: bool = self
+                .conn
+                .pragma_query_value(None, "query_only", |row| row.get(0))?;
+            self.conn.pragma_update(None, "query_only", true)?;
+            self.conn.authorizer(Some(authorize_read));
+            let result = self.run_read_query(sql);
+            self.conn
+                .authorizer(None::<fn(AuthContext) -> Authorization>);
+            self.conn.pragma_update(None, "query_only", query_only)?;
+            return result;
+        }
+
+        let tx = self.write_transaction()?;
+        let result = {
+            let mut stmt = tx.prepare(sql)?;
+            let writes = !stmt.readonly();
+            let mut result = collect(&mut stmt)?;
+            if writes && result.columns.is_empty() {
+                result.changed = Some(tx.changes() as usize);
+            }
+            result
+        };
+        tx.commit()?;
+        Ok(result)
+    }
+
+    fn run_read_query(&self, sql: &str) -> Result<QueryResult> {
+        let mut stmt = self.conn.prepare(sql).map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == ErrorCode::AuthorizationForStatementDenied =>
+            {
+                BukursError::InvalidInput(
+                    "Attaching databases, transactions and query_only aren't allowed in read-only queries".to_string(),
+                )
+            }
+            e => e.into(),
+        })?;
+        if !stmt.readonly() {
+            return Err(BukursError::InvalidInput(
+                "Query changes the database; allow writes to run it (--allow-write)".to_string(),
+            ));
+        }
+        Ok(collect(&mut stmt)?)
+    }
+}
+
+/// Refuse what a read-only query could use to change the connection rather
+/// than the database, which `query_only` doesn't cover
+fn authorize_read(ctx: AuthContext) -> Authorization {
+    match ctx.action {
+        AuthAction::Attach { .. }
+        | AuthAction::Detach { .. }
+        | AuthAction::Transaction { .. }
+        | AuthAction::Savepoint { .. } => Authorization::Deny,
+        AuthAction::Pragma { pragma_name, .. }
+            if pragma_name.eq_ignore_ascii_case("query_only") =>
+        {
+            Authorization::Deny
+        }
+        _ => Authorization::Allow,
+    }
+}
+
+/// Step through `stmt`, converting every value to JSON
+fn collect(stmt: &mut Statement) -> rusqlite::Result<QueryResult> {
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = Vec::new();
+    let mut cursor = stmt.query([])?;
+    while let Some(row) = cursor.next()? {
+        let values = (0..columns.len())
+            .map(|i| row.get_ref(i).map(json_value))
+            .collect::<rusqlite::Result<_>>()?;
+        rows.push(values);
+    }
+    Ok(QueryResult {
+        columns,
+        rows,
+        changed: None,
+    })
+}
+
+fn json_value(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => {
+            Value::String(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust-lang.org", "Rust", ",lang,", "", None)
+            .unwrap();
+        db.add_rec("https://example.com", "Example", ",", "", None)
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_run_query_select() {
+        let db = setup();
+        let result = db
+            .run_query(
+                "SELECT id, URL AS url, metadata, NULL AS empty, 1.5 AS half, x'ff00' AS raw
+                 FROM bookmarks ORDER BY id",
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            result.columns,
+            vec!["id", "url", "metadata", "empty", "half", "raw"]
+        );
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![
+                    json!(1),
+                    json!("https://rust-lang.org"),
+                    json!("Rust"),
+                    Value::Null,
+                    json!(1.5),
+                    json!("ff00")
+                ],
+                vec![
+                    json!(2),
+                    json!("https://example.com"),
+                    json!("Example"),
+                    Value::Null,
+                    json!(1.5),
+                    json!("ff00")
+                ],
+            ]
+        );
+        assert_eq!(result.changed, None);
+    }
+
+    #[rstest]
+    #[case("UPDATE bookmarks SET metadata = 'x'")]
+    #[case("DELETE FROM bookmarks")]
+    #[case("DROP TABLE bookmarks")]
+    #[case("CREATE TABLE notes (body TEXT)")]
+    #[case("PRAGMA user_version = 3")]
+    #[case("PRAGMA query_only = OFF")]
+    #[case("ATTACH ':memory:' AS other")]
+    #[case("BEGIN")]
+    #[case("SAVEPOINT before")]
+    fn test_run_query_refuses_writes(#[case] sql: &str) {
+        let db = setup();
+        assert!(matches!(
+            db.run_query(sql, false),
+            Err(BukursError::InvalidInput(_))
+        ));
+        assert_eq!(db.get_rec_all().unwrap().len(), 2);
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().title, "Rust");
+        // The connection is writable again afterwards
+        assert!(db.conn.is_autocommit());
+        db.add_rec("https://docs.rs", "", ",", "", None).unwrap();
+    }
+
+    #[test]
+    fn test_run_query_allowed_write() {
+        let db = setup();
+        let result = db
+            .run_query("UPDATE bookmarks SET metadata = upper(metadata)", true)
+            .unwrap();
+        assert!(result.columns.is_empty());
+        assert_eq!(result.changed, Some(2));
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().title, "RUST");
+    }
+
+    #[test]
+    fn test_run_query_single_statement() {
+        let db = setup();
+        assert!(db
+            .run_query("SELECT 1; DELETE FROM bookmarks", true)
+            .is_err());
+        assert_eq!(db.get_rec_all().unwrap().len(), 2);
+    }
+}
@@ -5,6 +5,7 @@
 #[derive(Debug, thiserror::Error)]
 pub enum BukursError {
     /// Database-related errors (SQLite)
+    #[cfg(feature = "full")]
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
@@ -13,8 +14,14 @@ pub enum BukursError {
     Io(#[from] std::io::Error),
 
     /// HTTP request errors
+    #[cfg(feature = "full")]
     #[error("HTTP request error: {0}")]
-    Http(#[from] reqwest::Error),
+    Http(#[source] reqwest::Error),
+
+    /// Certificate or handshake failures, including an unreadable `tls_ca_file`
+    #[cfg(feature = "full")]
+    #[error("TLS error: {0}")]
+    Tls(String),
 
     /// URL parsing errors
     #[error("Invalid URL: {0}")]
@@ -28,10 +35,22 @@ pub enum BukursError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Malformed bookmark index, range or selector
+    #[error("Invalid selector: {0}")]
+    InvalidSelector(#[from] crate::operations::SelectorError),
+
     /// Crypto/encryption errors
     #[error("Encryption error: {0}")]
     Crypto(String),
 
+    /// The password doesn't open an encrypted database
+    #[error("Wrong password")]
+    WrongPassword,
+
+    /// An encrypted database failed its integrity check or is truncated
+    #[error("Encrypted file is corrupted: {0}")]
+    Corrupted(String),
+
     /// Configuration errors
     #[error("Configuration error: {0}")]
     Config(String),
@@ -60,6 +79,18 @@ pub enum BukursError {
     #[error("JSON error: {0}")]
     Json(String),
 
+    /// Another bukurs process kept the database lock past the timeout
+    #[error("Database is busy in another bukurs process (lock file {})", .0.display())]
+    Locked(std::path::PathBuf),
+
+    /// Network access was needed while offline (`default_offline` in the config)
+    #[error("Network access is off (default_offline or BUKURS_OFFLINE is set)")]
+    Offline,
+
+    /// A long-running operation stopped early at the user's request
+    #[error("Operation cancelled")]
+    Cancelled,
+
     /// Generic error for cases that don't fit other categories
     #[error("{0}")]
     Other(String),
@@ -80,6 +111,30 @@ impl From<&str> for BukursError {
     }
 }
 
+/// Request errors caused by TLS become [`BukursError::Tls`] so they aren't
+/// reported as generic connection failures
+#[cfg(feature = "full")]
+impl From<reqwest::Error> for BukursError {
+    fn from(err: reqwest::Error) -> Self {
+        let mut source = std::error::Error::source(&err);
+        while let Some(cause) = source {
+            let reason = cause.to_string();
+            let lower = reason.to_lowercase();
+            if ["certificate", "tls", "handshake"]
+                .iter()
+                .any(|k| lower.contains(k))
+            {
+                return match err.url() {
+                    Some(url) => BukursError::Tls(format!("{}: {}", url, reason)),
+                    None => BukursError::Tls(reason),
+                };
+            }
+            source = cause.source();
+        }
+        BukursError::Http(err)
+    }
+}
+
 impl From<serde_yaml::Error> for BukursError {
     fn from(err: serde_yaml::Error) -> Self {
         BukursError::Yaml(err.to_string())
@@ -92,6 +147,7 @@ impl From<serde_json::Error> for BukursError {
     }
 }
 
+#[cfg(feature = "full")]
 impl From<simd_json::Error> for BukursError {
     fn from(err: simd_json::Error) -> Self {
         BukursError::Json(err.to_string())
@@ -0,0 +1,196 @@
+//! Change notifications
+//!
+//! Every change to the bookmarks table (add, update, delete, import, fetch,
+//! undo) produces one [`BookmarkEvent`] listing the bookmarks it touched,
+//! whether or not the audit log is on. A `BukuDb` hands it to the
+//! subscribers of its [`EventBus`] in the same process and appends it to the
+//! database's `events` table, where an [`EventWatcher`] finds changes made
+//! by any process; `bukurs watch` and the gRPC server's `Watch` use one. The
+//! table keeps the last [`EVENT_RETENTION`] events, so a watcher that stops
+//! polling for long enough misses the oldest.
+
+use crate::audit::{AuditOperation, AuditSource};
+use crate::cancel::CancellationToken;
+use crate::db::BukuDb;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Events kept in the `events` table
+pub const EVENT_RETENTION: i64 = 10_000;
+
+/// How often [`EventWatcher::wait`] looks for new events
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// One change to the bookmarks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkEvent {
+    /// Position in the database's event sequence, increasing
+    pub seq: i64,
+    /// Unix seconds
+    pub timestamp: i64,
+    pub operation: AuditOperation,
+    /// Bookmarks the change touched
+    pub ids: Vec<usize>,
+    /// Frontend that made the change
+    pub source: AuditSource,
+}
+
+/// Subscribers to the changes made through a database handle in this process
+///
+/// Clones share their subscribers, so one bus can be given to every
+/// connection of a pool.
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<BookmarkEvent>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every event published from now on; drop the receiver to unsubscribe
+    pub fn subscribe(&self) -> Receiver<BookmarkEvent> {
+        let (sender, receiver) = channel();
+        self.lock().push(sender);
+        receiver
+    }
+
+    /// Send `event` to every subscriber still listening
+    pub fn publish(&self, event: &BookmarkEvent) {
+        self.lock()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Sender<BookmarkEvent>>> {
+        // A panicking subscriber can't leave the list inconsistent
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Reads the events other processes add to a database's `events` table
+#[derive(Debug, Clone)]
+pub struct EventWatcher {
+    last_seq: i64,
+}
+
+impl EventWatcher {
+    /// Watch for events after the ones already in `db`
+    pub fn new(db: &BukuDb) -> Result<Self> {
+        Ok(Self::after(db.last_event_seq()?))
+    }
+
+    /// Watch for events after `seq`; 0 starts with the oldest one kept
+    pub fn after(seq: i64) -> Self {
+        Self { last_seq: seq }
+    }
+
+    /// Sequence number of the last event seen
+    pub fn last_seq(&self) -> i64 {
+        self.last_seq
+    }
+
+    /// Events added since the last call, oldest first
+    pub fn poll(&mut self, db: &BukuDb) -> Result<Vec<BookmarkEvent>> {
+        let events = db.events_since(self.last_seq)?;
+        if let Some(last) = events.last() {
+            self.last_seq = last.seq;
+        }
+        Ok(events)
+    }
+
+    /// Wait for new events; returns none only once `cancel` is cancelled
+    pub fn wait(&mut self, db: &BukuDb, cancel: &CancellationToken) -> Result<Vec<BookmarkEvent>> {
+        while !cancel.is_cancelled() {
+            let events = self.poll(db)?;
+            if !events.is_empty() {
+                return Ok(events);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_delivers_changes_to_subscribers() {
+        let mut db = BukuDb::init_in_memory().unwrap();
+        db.set_source(AuditSource::Shell);
+        let events = db.events().subscribe();
+
+        let id = db
+            .add_rec("https://example.com", "Example", ",a,", "", None)
+            .unwrap();
+        db.delete_rec(id).unwrap();
+
+        let added = events.try_recv().unwrap();
+        assert_eq!(added.operation, AuditOperation::Add);
+        assert_eq!(added.ids, vec![id]);
+        assert_eq!(added.source, AuditSource::Shell);
+        let deleted = events.try_recv().unwrap();
+        assert_eq!(deleted.operation, AuditOperation::Delete);
+        assert!(deleted.seq > added.seq);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_dropped_subscribers_are_removed() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+
+        let event = BookmarkEvent {
+            seq: 1,
+            timestamp: 0,
+            operation: AuditOperation::Update,
+            ids: vec![3],
+            source: AuditSource::Cli,
+        };
+        bus.publish(&event);
+        assert_eq!(bus.lock().len(), 1);
+        assert_eq!(kept.try_recv().unwrap(), event);
+    }
+
+    #[test]
+    fn test_watcher_sees_other_connections_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let writer = BukuDb::init(&path).unwrap();
+        writer
+            .add_rec("https://old.example", "Old", ",", "", None)
+            .unwrap();
+
+        let reader = BukuDb::init(&path).unwrap();
+        let mut watcher = EventWatcher::new(&reader).unwrap();
+        assert!(watcher.poll(&reader).unwrap().is_empty());
+
+        let id = writer
+            .add_rec("https://new.example", "New", ",", "", None)
+            .unwrap();
+        writer
+            .update_rec_partial(id, None, Some("Newer"), None, None, None)
+            .unwrap();
+
+        let events = watcher.poll(&reader).unwrap();
+        let operations: Vec<_> = events.iter().map(|e| e.operation).collect();
+        assert_eq!(
+            operations,
+            vec![AuditOperation::Add, AuditOperation::Update]
+        );
+        assert!(events.iter().all(|e| e.ids == vec![id]));
+        assert!(watcher.poll(&reader).unwrap().is_empty());
+
+        // From the start, the earlier add is there too
+        assert_eq!(EventWatcher::after(0).poll(&reader).unwrap().len(), 3);
+    }
+}
@@ -1,23 +1,335 @@
+use crate::error::BukursError;
+use crate::resource::{filename_title, pdf_title, ResourceKind, MAX_PDF_BYTES};
+use crate::tags::{format_tags, parse_tags};
+use crate::utils::decode_entities;
 use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_TYPE};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tl::ParserOptions;
 
-#[derive(Debug, PartialEq)]
+pub mod enrich;
+pub mod language;
+pub mod readability;
+pub mod throttle;
+
+#[derive(Debug, Default, PartialEq)]
 pub struct FetchResult {
     pub url: String,
     pub title: Arc<String>,
     pub desc: Arc<String>,
     pub keywords: Arc<String>,
+    pub kind: ResourceKind,
+    /// Tags added by a site enricher, e.g. `github`
+    pub tags: Vec<String>,
+    /// Words in the page's main text when `readability` is on; 0 when not measured
+    pub word_count: usize,
+    /// Language code such as `de`, declared by the page or guessed from its text
+    pub lang: Option<String>,
+}
+
+impl FetchResult {
+    /// Add the kind's tag and any enricher tags to a `,tag1,tag2,` string, or
+    /// `None` if there is nothing to add
+    pub fn apply_tags(&self, tags: &str) -> Option<String> {
+        let mut all = parse_tags(tags);
+        let before = all.len();
+        for tag in self
+            .kind
+            .tag()
+            .into_iter()
+            .chain(self.tags.iter().map(String::as_str))
+        {
+            if !all.iter().any(|t| t == tag) {
+                all.push(tag.to_string());
+            }
+        }
+        (all.len() > before).then(|| format_tags(&all))
+    }
 }
 
 const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) \
     AppleWebKit/605.1.15 (KHTML, like Gecko) \
     Version/18.5 Safari/605.1.15";
 
-pub fn fetch_data(url: &str, user_agent: Option<&str>) -> crate::error::Result<FetchResult> {
-    let ua = user_agent.unwrap_or(USER_AGENT);
-    let client = Client::builder().user_agent(ua).build()?;
-    let resp = client.get(url).send()?;
+/// Network settings for metadata fetching, configured under `fetch` in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FetchOptions {
+    /// User-agent string; the built-in browser user-agent when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Total time allowed per request, in seconds (0 disables the timeout)
+    pub timeout_secs: u64,
+    /// Redirects followed before giving up (0 disables redirects)
+    pub max_redirects: usize,
+    /// Proxy for all requests, e.g. `http://127.0.0.1:8080`; when unset,
+    /// `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` are used. Either way hosts
+    /// in `NO_PROXY` are reached directly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM bundle of certificate authorities trusted besides the built-in ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_ca_file: Option<PathBuf>,
+    /// `Accept-Language` header sent with requests, e.g. `en-US,en;q=0.8`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_language: Option<String>,
+    /// Ask GitHub, YouTube and arXiv for details their pages don't carry
+    pub enrich: bool,
+    /// GitHub token, raising the API's rate limit for repository details
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+    /// YouTube Data API key, needed for video durations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub youtube_api_key: Option<String>,
+    /// Extract each page's main text to measure its reading time
+    pub readability: bool,
+    /// Milliseconds between page requests to the same host (0 disables the delay)
+    pub host_delay_ms: u64,
+    /// Retries for 429 and 503 responses, after `Retry-After` or a backoff
+    pub max_retries: u32,
+    /// Pages fetched at once by bulk operations (0 means one per CPU)
+    pub concurrency: usize,
+    /// Refuse network access (`default_offline` in the config, not set here)
+    #[serde(skip)]
+    pub offline: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            timeout_secs: 30,
+            max_redirects: 10,
+            proxy: None,
+            tls_ca_file: None,
+            accept_language: None,
+            enrich: true,
+            github_token: None,
+            youtube_api_key: None,
+            readability: false,
+            host_delay_ms: 500,
+            max_retries: 2,
+            concurrency: 8,
+            offline: false,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Build an HTTP client honoring these options
+    ///
+    /// Every request goes through a client from here, so offline this fails
+    /// with [`BukursError::Offline`](crate::error::BukursError::Offline).
+    pub fn client(&self) -> crate::error::Result<Client> {
+        if self.offline {
+            return Err(BukursError::Offline);
+        }
+        let mut builder = Client::builder()
+            .user_agent(self.user_agent.as_deref().unwrap_or(USER_AGENT))
+            .redirect(if self.max_redirects == 0 {
+                Policy::none()
+            } else {
+                Policy::limited(self.max_redirects)
+            })
+            .timeout(if self.timeout_secs == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(self.timeout_secs))
+            });
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| BukursError::Config(format!("Invalid proxy '{}': {}", proxy, e)))?;
+            builder = builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_env()));
+        }
+        if let Some(path) = &self.tls_ca_file {
+            for cert in ca_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if let Some(lang) = &self.accept_language {
+            let value = HeaderValue::from_str(lang)
+                .map_err(|e| format!("Invalid accept_language '{}': {}", lang, e))?;
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT_LANGUAGE, value);
+            builder = builder.default_headers(headers);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// The certificates in the PEM bundle at `path`
+fn ca_certificates(path: &Path) -> crate::error::Result<Vec<reqwest::Certificate>> {
+    let tls_error = |reason: String| BukursError::Tls(format!("{}: {}", path.display(), reason));
+    let pem = std::fs::read(path).map_err(|e| tls_error(e.to_string()))?;
+    let certs =
+        reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| tls_error(e.to_string()))?;
+    if certs.is_empty() {
+        return Err(tls_error("no PEM certificates found".to_string()));
+    }
+    Ok(certs)
+}
+
+/// Characters of page text enough to guess its language from
+const LANGUAGE_SAMPLE_LEN: usize = 2000;
+
+/// oEmbed endpoints for sites that render their metadata with JavaScript
+const OEMBED_PROVIDERS: &[(&[&str], &str)] = &[
+    (
+        &["youtube.com", "m.youtube.com", "youtu.be"],
+        "https://www.youtube.com/oembed",
+    ),
+    (
+        &["twitter.com", "mobile.twitter.com", "x.com"],
+        "https://publish.twitter.com/oembed",
+    ),
+    (&["vimeo.com"], "https://vimeo.com/api/oembed.json"),
+];
+
+/// Fetch metadata for a URL from the page itself and, for known providers, their oEmbed endpoint
+///
+/// With `enrich` set, the enricher for the URL's site (see [`enrich`]) then
+/// adds details from the site's API.
+pub fn fetch_data(url: &str, options: &FetchOptions) -> crate::error::Result<FetchResult> {
+    let client = options.client()?;
+
+    let page = fetch_page(&client, options, url);
+    let mut result = match oembed_endpoint(url) {
+        None => page?,
+        // Either source may fail on its own; use whatever came back
+        Some(endpoint) => match (page, fetch_oembed(&client, endpoint, url)) {
+            (Ok(page), Ok(oembed)) => merge_metadata(page, oembed),
+            (Ok(page), Err(_)) => page,
+            (Err(_), Ok(mut oembed)) => {
+                oembed.url = url.to_string();
+                oembed
+            }
+            (Err(e), Err(_)) => return Err(e),
+        },
+    };
+
+    if options.enrich {
+        enrich::enrich(&client, options, url, &mut result);
+    }
+    Ok(result)
+}
+
+/// Check that a URL still resolves to a page, without parsing it
+///
+/// Sends a HEAD request, falling back to GET for servers that don't allow
+/// HEAD. Redirects are followed per the client's policy, and requests are
+/// spaced out per host (see [`throttle`]).
+pub fn check_link(client: &Client, options: &FetchOptions, url: &str) -> crate::error::Result<()> {
+    let mut status = throttle::send(options, url, client.head(url))?.status();
+    if matches!(status.as_u16(), 403 | 405 | 501) {
+        status = throttle::send(options, url, client.get(url))?.status();
+    }
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status).into())
+    }
+}
+
+/// A link found on a page, with its anchor text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageLink {
+    pub url: String,
+    /// Text of the link, whitespace collapsed; empty for image links
+    pub text: String,
+}
+
+/// Fetch a page and list the links on it (see [`parse_links`])
+pub fn fetch_links(url: &str, options: &FetchOptions) -> crate::error::Result<Vec<PageLink>> {
+    let resp = throttle::send(options, url, options.client()?.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("Fetching {} failed (Status: {})", url, resp.status()).into());
+    }
+    let final_url = resp.url().to_string();
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if ResourceKind::classify(content_type.as_deref(), &final_url) != ResourceKind::Html {
+        return Err(format!("{} is not an HTML page", url).into());
+    }
+    parse_links(&resp.text()?, &final_url)
+}
+
+/// The http(s) links of an HTML page, in page order
+///
+/// Relative links are resolved against the page's `<base href>` or `base`,
+/// fragments are dropped, and links back to the page itself are skipped. A
+/// URL linked several times is listed once, with its first non-empty text.
+pub fn parse_links(html: &str, base: &str) -> crate::error::Result<Vec<PageLink>> {
+    let dom = tl::parse(html, ParserOptions::default())?;
+    let parser = dom.parser();
+    let page = Url::parse(base).map_err(|e| format!("Invalid URL '{}': {}", base, e))?;
+    let base = extract_attribute(&dom, parser, "base", "href")
+        .and_then(|href| page.join(&decode_entities(&href)).ok())
+        .unwrap_or_else(|| page.clone());
+    let without_fragment = |mut url: Url| {
+        url.set_fragment(None);
+        url
+    };
+    let page = without_fragment(page);
+
+    let mut links: Vec<PageLink> = Vec::new();
+    let mut seen = HashSet::new();
+    for node in dom
+        .query_selector("a")
+        .into_iter()
+        .flatten()
+        .filter_map(|handle| handle.get(parser))
+    {
+        let Some(href) = node
+            .as_tag()
+            .and_then(|tag| tag.attributes().get("href").flatten())
+            .map(|href| decode_entities(href.as_utf8_str().trim()))
+        else {
+            continue;
+        };
+        let Ok(url) = base.join(&href).map(without_fragment) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") || url == page {
+            continue;
+        }
+
+        let text = decode_entities(&node.inner_text(parser))
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let url = url.to_string();
+        if seen.insert(url.clone()) {
+            links.push(PageLink { url, text });
+        } else if !text.is_empty() {
+            if let Some(link) = links.iter_mut().find(|l| l.url == url && l.text.is_empty()) {
+                link.text = text;
+            }
+        }
+    }
+    Ok(links)
+}
+
+/// Fetch a page and parse its metadata according to its content type
+///
+/// With `readability` set, HTML pages also get their main text's word count.
+fn fetch_page(
+    client: &Client,
+    options: &FetchOptions,
+    url: &str,
+) -> crate::error::Result<FetchResult> {
+    let resp = throttle::send(options, url, client.get(url))?;
 
     // Check HTTP status code
     let status = resp.status();
@@ -41,56 +353,273 @@ pub fn fetch_data(url: &str, user_agent: Option<&str>) -> crate::error::Result<F
     }
 
     let final_url = resp.url().to_string();
-    let body = resp.text()?;
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let kind = ResourceKind::classify(content_type.as_deref(), &final_url);
+    let content_language = resp
+        .headers()
+        .get(CONTENT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
 
-    let mut result = parse_html(&body)?;
-    result.url = final_url;
+    let mut result = match kind {
+        ResourceKind::Html => {
+            let html = resp.text()?;
+            let mut result = parse_page(&html, content_language.as_deref())?;
+            if options.readability {
+                result.word_count = readability::extract(&html)
+                    .map(|article| article.word_count())
+                    .unwrap_or(0);
+            }
+            result
+        }
+        ResourceKind::Pdf => {
+            let mut body = Vec::new();
+            resp.take(MAX_PDF_BYTES).read_to_end(&mut body)?;
+            let title = pdf_title(&body).unwrap_or_else(|| filename_title(&final_url));
+            FetchResult {
+                title: Arc::new(title),
+                kind,
+                lang: content_language.as_deref().and_then(language::normalize),
+                ..Default::default()
+            }
+        }
+        // Don't download binaries just to name them
+        _ => FetchResult {
+            title: Arc::new(filename_title(&final_url)),
+            kind,
+            ..Default::default()
+        },
+    };
+    // Prefer the page's canonical URL over wherever redirects ended up
+    if result.url.is_empty() {
+        result.url = final_url;
+    }
     Ok(result)
 }
 
+/// Query an oEmbed endpoint for `url`
+fn fetch_oembed(client: &Client, endpoint: &str, url: &str) -> crate::error::Result<FetchResult> {
+    let resp = client
+        .get(endpoint)
+        .query(&[("url", url), ("format", "json")])
+        .send()?;
+    if !resp.status().is_success() {
+        return Err(format!("oEmbed request failed (Status: {})", resp.status()).into());
+    }
+    parse_oembed(&resp.text()?)
+}
+
+/// oEmbed endpoint of the provider hosting `url`, if it is a known one
+fn oembed_endpoint(url: &str) -> Option<&'static str> {
+    let domain = crate::utils::url_domain(url)?;
+    OEMBED_PROVIDERS
+        .iter()
+        .find(|(domains, _)| domains.contains(&domain.as_str()))
+        .map(|(_, endpoint)| *endpoint)
+}
+
+/// Parse an oEmbed JSON response
+///
+/// Twitter's response has no title; the post text is taken from its embed HTML instead.
+pub fn parse_oembed(json: &str) -> crate::error::Result<FetchResult> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let field = |name: &str| {
+        value
+            .get(name)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    let mut title = field("title");
+    let mut desc = field("description");
+    let author = field("author_name");
+
+    if desc.is_empty() {
+        desc = first_paragraph(&field("html"));
+    }
+    if title.is_empty() && !author.is_empty() {
+        title = format!("Post by {}", author);
+    }
+
+    Ok(FetchResult {
+        url: String::new(),
+        title: Arc::new(title),
+        desc: Arc::new(desc),
+        keywords: Arc::new(String::new()),
+        kind: ResourceKind::Html,
+        tags: Vec::new(),
+        word_count: 0,
+        lang: None,
+    })
+}
+
+/// Text of the first `<p>` in an HTML fragment
+fn first_paragraph(html: &str) -> String {
+    let Ok(dom) = tl::parse(html, ParserOptions::default()) else {
+        return String::new();
+    };
+    let parser = dom.parser();
+    dom.query_selector("p")
+        .and_then(|mut iter| iter.next())
+        .and_then(|handle| handle.get(parser))
+        .map(|node| node.inner_text(parser).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Combine page and oEmbed metadata: the provider's own title wins, and the
+/// longer description is kept
+fn merge_metadata(page: FetchResult, oembed: FetchResult) -> FetchResult {
+    let title = if oembed.title.is_empty() {
+        page.title
+    } else {
+        oembed.title
+    };
+    let desc = if oembed.desc.len() > page.desc.len() {
+        oembed.desc
+    } else {
+        page.desc
+    };
+
+    FetchResult {
+        url: page.url,
+        title,
+        desc,
+        keywords: page.keywords,
+        kind: page.kind,
+        tags: page.tags,
+        word_count: page.word_count,
+        lang: page.lang,
+    }
+}
+
 /// Parse HTML content and extract metadata
+///
+/// OpenGraph and Twitter card tags are preferred for the title, since `<title>` is often
+/// missing or generic on script-rendered pages; the longest available description is used.
+/// `url` is the page's `<link rel="canonical">` if it has an absolute one, otherwise empty.
 pub fn parse_html(html: &str) -> crate::error::Result<FetchResult> {
+    parse_page(html, None)
+}
+
+/// [`parse_html`] for a page served with a `Content-Language` header
+///
+/// The language is the one the page declares (`<html lang>`, then
+/// `og:locale`), then the header's, and only then guessed from the text.
+fn parse_page(html: &str, content_language: Option<&str>) -> crate::error::Result<FetchResult> {
     let dom = tl::parse(html, ParserOptions::default())?;
     let parser = dom.parser();
 
     // Extract title
-    let title = dom
+    let page_title = dom
         .query_selector("title")
         .and_then(|mut iter| iter.next())
         .and_then(|handle| handle.get(parser))
         .map(|node| node.inner_text(parser).to_string())
         .unwrap_or_default();
 
-    // Extract meta description
-    let desc = extract_meta_content(&dom, parser, "description");
+    let title = [
+        extract_meta_property(&dom, parser, "og:title"),
+        extract_meta_property(&dom, parser, "twitter:title"),
+    ]
+    .into_iter()
+    .find(|t| !t.trim().is_empty())
+    .unwrap_or(page_title);
+
+    // Extract the richest description
+    let desc = [
+        extract_meta_content(&dom, parser, "description"),
+        extract_meta_property(&dom, parser, "og:description"),
+        extract_meta_property(&dom, parser, "twitter:description"),
+    ]
+    .into_iter()
+    .reduce(|best, d| if d.len() > best.len() { d } else { best })
+    .unwrap_or_default();
 
     // Extract meta keywords
     let keywords = extract_meta_content(&dom, parser, "keywords");
 
+    let canonical = extract_attribute(&dom, parser, "link[rel='canonical']", "href")
+        .filter(|href| href.starts_with("http://") || href.starts_with("https://"))
+        .unwrap_or_default();
+
+    let lang = extract_attribute(&dom, parser, "html", "lang")
+        .into_iter()
+        .chain([extract_meta_property(&dom, parser, "og:locale")])
+        .chain(content_language.map(str::to_string))
+        .find_map(|tag| language::normalize(&tag))
+        .or_else(|| {
+            let mut sample = format!("{}\n{}", title, desc);
+            for paragraph in dom
+                .query_selector("p")
+                .into_iter()
+                .flatten()
+                .filter_map(|handle| handle.get(parser))
+            {
+                if sample.len() > LANGUAGE_SAMPLE_LEN {
+                    break;
+                }
+                sample.push('\n');
+                sample.push_str(&decode_entities(&paragraph.inner_text(parser)));
+            }
+            language::detect(&sample).map(str::to_string)
+        });
+
     Ok(FetchResult {
-        url: String::new(), // Will be set by fetch_data
+        url: canonical, // fetch_data falls back to the final URL
         title: Arc::new(title),
         desc: Arc::new(desc),
         keywords: Arc::new(keywords),
+        kind: ResourceKind::Html,
+        tags: Vec::new(),
+        word_count: 0,
+        lang,
     })
 }
 
-/// Helper function to extract content from meta tags
-fn extract_meta_content(dom: &tl::VDom, parser: &tl::Parser, name: &str) -> String {
-    dom.query_selector(&format!("meta[name='{}']", name))
+/// Helper function to extract an attribute of the first element matching a selector
+fn extract_attribute(
+    dom: &tl::VDom,
+    parser: &tl::Parser,
+    selector: &str,
+    attribute: &str,
+) -> Option<String> {
+    dom.query_selector(selector)
         .and_then(|mut iter| iter.next())
         .and_then(|handle| handle.get(parser))
         .and_then(|node| {
             if let Some(tag) = node.as_tag() {
                 tag.attributes()
-                    .get("content")
+                    .get(attribute)
                     .flatten()
                     .map(|v| v.as_utf8_str().to_string())
             } else {
                 None
             }
         })
-        .unwrap_or_default()
+}
+
+/// Helper function to extract content from meta tags
+fn extract_meta_content(dom: &tl::VDom, parser: &tl::Parser, name: &str) -> String {
+    extract_attribute(dom, parser, &format!("meta[name='{}']", name), "content").unwrap_or_default()
+}
+
+/// Extract an OpenGraph/Twitter card meta tag, which sites declare with either
+/// `property=` or `name=`
+fn extract_meta_property(dom: &tl::VDom, parser: &tl::Parser, property: &str) -> String {
+    extract_attribute(
+        dom,
+        parser,
+        &format!("meta[property='{}']", property),
+        "content",
+    )
+    .filter(|c| !c.is_empty())
+    .unwrap_or_else(|| extract_meta_content(dom, parser, property))
 }
 
 #[cfg(test)]
@@ -249,4 +778,242 @@ mod tests {
         let result = parse_html(&html).unwrap();
         assert_eq!(result.title.as_str(), expected);
     }
+
+    #[test]
+    fn test_parse_html_opengraph() {
+        let html = r#"<html><head>
+            <title>Loading...</title>
+            <meta property="og:title" content="Real Title">
+            <meta name="description" content="Short">
+            <meta property="og:description" content="A much longer OpenGraph description">
+        </head></html>"#;
+
+        let result = parse_html(html).unwrap();
+        assert_eq!(result.title.as_str(), "Real Title");
+        assert_eq!(result.desc.as_str(), "A much longer OpenGraph description");
+    }
+
+    #[test]
+    fn test_parse_html_twitter_card() {
+        let html = r#"<html><head>
+            <meta name="twitter:title" content="Card Title">
+            <meta name="twitter:description" content="Card description">
+        </head></html>"#;
+
+        let result = parse_html(html).unwrap();
+        assert_eq!(result.title.as_str(), "Card Title");
+        assert_eq!(result.desc.as_str(), "Card description");
+    }
+
+    #[rstest]
+    #[case(
+        r#"<link rel="canonical" href="https://example.com/article">"#,
+        "https://example.com/article"
+    )]
+    #[case(r#"<link rel="canonical" href="/relative">"#, "")]
+    #[case("", "")]
+    fn test_parse_html_canonical(#[case] link: &str, #[case] expected: &str) {
+        let html = format!("<html><head><title>T</title>{}</head></html>", link);
+        let result = parse_html(&html).unwrap();
+        assert_eq!(result.url, expected);
+    }
+
+    #[rstest]
+    #[case(
+        "https://www.youtube.com/watch?v=abc",
+        Some("https://www.youtube.com/oembed")
+    )]
+    #[case("https://youtu.be/abc", Some("https://www.youtube.com/oembed"))]
+    #[case(
+        "https://x.com/user/status/1",
+        Some("https://publish.twitter.com/oembed")
+    )]
+    #[case(
+        "https://twitter.com/user/status/1",
+        Some("https://publish.twitter.com/oembed")
+    )]
+    #[case("https://vimeo.com/123", Some("https://vimeo.com/api/oembed.json"))]
+    #[case("https://example.com/youtube.com", None)]
+    fn test_oembed_endpoint(#[case] url: &str, #[case] expected: Option<&str>) {
+        assert_eq!(oembed_endpoint(url), expected);
+    }
+
+    #[test]
+    fn test_parse_oembed_youtube() {
+        let json = r#"{"title":"Never Gonna Give You Up","author_name":"Rick Astley","type":"video","html":"<iframe></iframe>"}"#;
+        let result = parse_oembed(json).unwrap();
+        assert_eq!(result.title.as_str(), "Never Gonna Give You Up");
+        assert_eq!(result.desc.as_str(), "");
+    }
+
+    #[test]
+    fn test_parse_oembed_twitter() {
+        let json = r#"{"author_name":"Rust Language","html":"<blockquote class=\"twitter-tweet\"><p lang=\"en\" dir=\"ltr\">Rust 2.0 is not happening</p>&mdash; Rust Language (@rustlang)</blockquote>"}"#;
+        let result = parse_oembed(json).unwrap();
+        assert_eq!(result.title.as_str(), "Post by Rust Language");
+        assert_eq!(result.desc.as_str(), "Rust 2.0 is not happening");
+    }
+
+    #[test]
+    fn test_parse_oembed_vimeo() {
+        let json = r#"{"title":"A Film","description":"About the film","author_name":"Director"}"#;
+        let result = parse_oembed(json).unwrap();
+        assert_eq!(result.title.as_str(), "A Film");
+        assert_eq!(result.desc.as_str(), "About the film");
+    }
+
+    #[test]
+    fn test_merge_metadata_prefers_oembed_title_and_longer_desc() {
+        let page = FetchResult {
+            url: "https://youtube.com/watch?v=abc".to_string(),
+            title: Arc::new("Before you continue to YouTube".to_string()),
+            desc: Arc::new("A longer page description".to_string()),
+            keywords: Arc::new("video".to_string()),
+            kind: ResourceKind::Html,
+            tags: Vec::new(),
+            word_count: 0,
+            lang: None,
+        };
+        let oembed = parse_oembed(r#"{"title":"Video","description":"Short"}"#).unwrap();
+
+        let merged = merge_metadata(page, oembed);
+        assert_eq!(merged.title.as_str(), "Video");
+        assert_eq!(merged.desc.as_str(), "A longer page description");
+        assert_eq!(merged.keywords.as_str(), "video");
+        assert_eq!(merged.url, "https://youtube.com/watch?v=abc");
+    }
+
+    #[test]
+    fn test_parse_links() {
+        let html = r##"<html><body>
+            <a href="#top">Top</a>
+            <a href="/docs/intro.html">Intro
+                to   the docs</a>
+            <a href="https://other.org/page?a=1&amp;b=2#part">Fish &amp; Chips</a>
+            <a href="https://other.org/page?a=1&b=2"><img src="x.png"></a>
+            <a href="mailto:me@example.com">Mail</a>
+            <a href="https://example.com/guide/">This page</a>
+            <a href="../img.png"></a>
+        </body></html>"##;
+        let links = parse_links(html, "https://example.com/guide/").unwrap();
+        assert_eq!(
+            links,
+            vec![
+                PageLink {
+                    url: "https://example.com/docs/intro.html".into(),
+                    text: "Intro to the docs".into(),
+                },
+                PageLink {
+                    url: "https://other.org/page?a=1&b=2".into(),
+                    text: "Fish & Chips".into(),
+                },
+                PageLink {
+                    url: "https://example.com/img.png".into(),
+                    text: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_links_honors_base_href() {
+        let html = r#"<head><base href="https://cdn.example.com/v2/"></head>
+            <a href="api.html">API</a>"#;
+        let links = parse_links(html, "https://example.com/").unwrap();
+        assert_eq!(links[0].url, "https://cdn.example.com/v2/api.html");
+    }
+
+    #[rstest]
+    #[case("a &amp; b", "a & b")]
+    #[case("&lt;p&gt; &#39;x&#x27;", "<p> 'x'")]
+    #[case("AT&T &bogus; &", "AT&T &bogus; &")]
+    fn test_decode_entities(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(decode_entities(input), expected);
+    }
+
+    #[rstest]
+    #[case(
+        r#"<html lang="de-DE"><head><title>Start</title></head></html>"#,
+        Some("de")
+    )]
+    #[case(
+        r#"<html><head><meta property="og:locale" content="fr_FR"></head></html>"#,
+        Some("fr")
+    )]
+    #[case(
+        "<html><body><p>Der Hund ist nicht auf dem Sofa, sondern mit der Katze im Garten.</p></body></html>",
+        Some("de")
+    )]
+    #[case("<html><head><title>Home</title></head></html>", None)]
+    fn test_parse_html_language(#[case] html: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_html(html).unwrap().lang.as_deref(), expected);
+    }
+
+    #[test]
+    fn test_parse_page_content_language_before_guessing() {
+        let html = "<p>The cat is on the roof and it is not coming down to the garden.</p>";
+        let result = parse_page(html, Some("nl")).unwrap();
+        assert_eq!(result.lang.as_deref(), Some("nl"));
+        let declared = parse_page(r#"<html lang="en">"#, Some("nl")).unwrap();
+        assert_eq!(declared.lang.as_deref(), Some("en"));
+    }
+
+    #[rstest]
+    #[case(ResourceKind::Html, &[], ",rust,", None)]
+    #[case(ResourceKind::Pdf, &["paper"], ",science,", Some(",science,pdf,paper,"))]
+    #[case(ResourceKind::Html, &["github"], ",github,rust,", None)]
+    #[case(ResourceKind::Html, &["video"], ",", Some(",video,"))]
+    fn test_apply_tags(
+        #[case] kind: ResourceKind,
+        #[case] extra: &[&str],
+        #[case] tags: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let result = FetchResult {
+            kind,
+            tags: extra.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(result.apply_tags(tags).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_fetch_options_from_yaml() {
+        let options: FetchOptions =
+            serde_yaml::from_str("timeout_secs: 5\naccept_language: en-US\n").unwrap();
+        assert_eq!(options.timeout_secs, 5);
+        assert_eq!(options.max_redirects, 10);
+        assert_eq!(options.accept_language.as_deref(), Some("en-US"));
+        assert!(options.enrich);
+        assert!(options.client().is_ok());
+    }
+
+    #[rstest]
+    #[case(Some("not a proxy url"), None)]
+    #[case(None, Some("bad\nvalue"))]
+    fn test_fetch_options_invalid(#[case] proxy: Option<&str>, #[case] lang: Option<&str>) {
+        let options = FetchOptions {
+            proxy: proxy.map(String::from),
+            accept_language: lang.map(String::from),
+            ..FetchOptions::default()
+        };
+        assert!(options.client().is_err());
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some("not a certificate\n"))]
+    fn test_tls_ca_file_invalid(#[case] contents: Option<&str>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        if let Some(contents) = contents {
+            std::fs::write(&path, contents).unwrap();
+        }
+        let options: FetchOptions =
+            serde_yaml::from_str(&format!("tls_ca_file: {}\n", path.display())).unwrap();
+        assert_eq!(options.tls_ca_file.as_deref(), Some(path.as_path()));
+        let err = options.client().unwrap_err();
+        assert!(matches!(err, BukursError::Tls(_)), "{}", err);
+        assert!(err.to_string().contains("ca.pem"));
+    }
 }
@@ -0,0 +1,576 @@
+//! Site-specific metadata enrichers
+//!
+//! Some sites say little in their pages' meta tags but a lot through their
+//! APIs. After a page is fetched, the enricher for its site looks up those
+//! details: a GitHub repository's language and stars, a YouTube video's
+//! channel and duration, an arXiv paper's authors and abstract. The bookmark
+//! is also tagged with what it is (`github`, `video`, `paper`), even when the
+//! API can't be reached.
+
+use super::{FetchOptions, FetchResult};
+use crate::error::Result;
+use reqwest::blocking::Client;
+use reqwest::Url;
+use std::sync::Arc;
+use tl::ParserOptions;
+
+/// Details an enricher found for a URL; empty fields leave the page's own
+#[derive(Debug, Default, PartialEq)]
+pub struct Enrichment {
+    pub title: String,
+    /// Short facts put in front of the description, e.g. `Rust · ★ 1,204`
+    pub summary: String,
+    /// Replaces the page's description
+    pub desc: String,
+}
+
+/// Looks up extra metadata for the URLs of one site
+pub trait Enricher: Send + Sync {
+    /// Tag given to every bookmark this enricher handles
+    fn tag(&self) -> &'static str;
+
+    /// Whether `url` is something this enricher knows about
+    fn handles(&self, url: &Url) -> bool;
+
+    /// Ask the site's API about `url`
+    fn enrich(&self, client: &Client, options: &FetchOptions, url: &Url) -> Result<Enrichment>;
+}
+
+/// The built-in enrichers, tried in order
+pub static ENRICHERS: &[&dyn Enricher] = &[&GitHub, &YouTube, &Arxiv];
+
+/// Run the first enricher handling `url` and merge what it finds into `result`
+///
+/// API failures are ignored: the page's metadata is kept and only the tag is added.
+pub fn enrich(client: &Client, options: &FetchOptions, url: &str, result: &mut FetchResult) {
+    let Ok(parsed) = Url::parse(url) else {
+        return;
+    };
+    let Some(enricher) = ENRICHERS.iter().find(|e| e.handles(&parsed)) else {
+        return;
+    };
+    if let Ok(enrichment) = enricher.enrich(client, options, &parsed) {
+        apply(result, enrichment);
+    }
+    result.tags.push(enricher.tag().to_string());
+}
+
+/// Merge an enrichment into fetched metadata
+fn apply(result: &mut FetchResult, enrichment: Enrichment) {
+    if !enrichment.title.is_empty() {
+        result.title = Arc::new(enrichment.title);
+    }
+    let desc = if enrichment.desc.is_empty() {
+        result.desc.to_string()
+    } else {
+        enrichment.desc
+    };
+    let desc = match (enrichment.summary.is_empty(), desc.is_empty()) {
+        (true, _) => desc,
+        (false, true) => enrichment.summary,
+        (false, false) => format!("{} — {}", enrichment.summary, desc),
+    };
+    result.desc = Arc::new(desc);
+}
+
+/// Host of `url` without a leading `www.` or `m.`
+fn host(url: &Url) -> &str {
+    let host = url.host_str().unwrap_or("");
+    host.strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(host)
+}
+
+/// Non-empty path segments of `url`
+fn segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// A string field of a JSON object, trimmed
+fn json_str<'a>(value: &'a serde_json::Value, name: &str) -> &'a str {
+    value
+        .get(name)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+}
+
+/// Join the non-empty parts with ` · `
+fn join_facts(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .filter(|p| !p.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Send a GET request and return the body, failing on non-success statuses
+fn get_text(request: reqwest::blocking::RequestBuilder) -> Result<String> {
+    let resp = request.send()?;
+    if !resp.status().is_success() {
+        return Err(format!("API request failed (Status: {})", resp.status()).into());
+    }
+    Ok(resp.text()?)
+}
+
+/// GitHub repositories: description, language and stars from the REST API
+pub struct GitHub;
+
+/// First path segments on github.com that aren't users or organizations
+const GITHUB_RESERVED: &[&str] = &[
+    "about",
+    "collections",
+    "enterprise",
+    "explore",
+    "features",
+    "marketplace",
+    "orgs",
+    "settings",
+    "sponsors",
+    "topics",
+    "trending",
+];
+
+impl GitHub {
+    /// `owner/repo` of a repository URL
+    fn repo(url: &Url) -> Option<String> {
+        if host(url) != "github.com" {
+            return None;
+        }
+        match segments(url).as_slice() {
+            [owner, repo, ..] if !GITHUB_RESERVED.contains(owner) => {
+                Some(format!("{}/{}", owner, repo.trim_end_matches(".git")))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Enricher for GitHub {
+    fn tag(&self) -> &'static str {
+        "github"
+    }
+
+    fn handles(&self, url: &Url) -> bool {
+        Self::repo(url).is_some()
+    }
+
+    fn enrich(&self, client: &Client, options: &FetchOptions, url: &Url) -> Result<Enrichment> {
+        let repo = Self::repo(url).ok_or("Not a GitHub repository")?;
+        let mut request = client
+            .get(format!("https://api.github.com/repos/{}", repo))
+            .header("Accept", "application/vnd.github+json");
+        if let Some(token) = &options.github_token {
+            request = request.bearer_auth(token);
+        }
+        parse_github_repo(&get_text(request)?)
+    }
+}
+
+/// Parse a GitHub `GET /repos/{owner}/{repo}` response
+pub fn parse_github_repo(json: &str) -> Result<Enrichment> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let name = json_str(&value, "full_name");
+    if name.is_empty() {
+        return Err("Not a GitHub repository response".into());
+    }
+    let stars = value
+        .get("stargazers_count")
+        .and_then(|v| v.as_u64())
+        .map(|n| format!("★ {}", thousands(n)))
+        .unwrap_or_default();
+    let description = json_str(&value, "description");
+
+    Ok(Enrichment {
+        title: if description.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}: {}", name, description)
+        },
+        summary: join_facts(&[json_str(&value, "language"), &stars]),
+        desc: description.to_string(),
+    })
+}
+
+/// `n` with thousands separators, e.g. `12,345`
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// YouTube videos: channel and, with an API key, duration
+///
+/// Without `fetch.youtube_api_key` the channel comes from oEmbed.
+pub struct YouTube;
+
+impl YouTube {
+    /// Video ID of a watch, short, embed or `youtu.be` URL
+    fn video_id(url: &Url) -> Option<String> {
+        let id = match (host(url), segments(url).as_slice()) {
+            ("youtu.be", [id, ..]) => Some(id.to_string()),
+            ("youtube.com", ["watch"]) => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, id)| id.into_owned()),
+            ("youtube.com", ["shorts" | "embed" | "live", id, ..]) => Some(id.to_string()),
+            _ => None,
+        }?;
+        Some(id).filter(|id| !id.is_empty())
+    }
+}
+
+impl Enricher for YouTube {
+    fn tag(&self) -> &'static str {
+        "video"
+    }
+
+    fn handles(&self, url: &Url) -> bool {
+        Self::video_id(url).is_some()
+    }
+
+    fn enrich(&self, client: &Client, options: &FetchOptions, url: &Url) -> Result<Enrichment> {
+        let id = Self::video_id(url).ok_or("Not a YouTube video")?;
+        match &options.youtube_api_key {
+            Some(key) => {
+                let request = client
+                    .get("https://www.googleapis.com/youtube/v3/videos")
+                    .query(&[
+                        ("part", "snippet,contentDetails"),
+                        ("id", id.as_str()),
+                        ("key", key.as_str()),
+                    ]);
+                parse_youtube_video(&get_text(request)?)
+            }
+            None => {
+                let request = client
+                    .get("https://www.youtube.com/oembed")
+                    .query(&[("url", url.as_str()), ("format", "json")]);
+                let value: serde_json::Value = serde_json::from_str(&get_text(request)?)?;
+                Ok(Enrichment {
+                    summary: json_str(&value, "author_name").to_string(),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+/// Parse a YouTube Data API `videos` response for a single video
+pub fn parse_youtube_video(json: &str) -> Result<Enrichment> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let video = value
+        .get("items")
+        .and_then(|items| items.get(0))
+        .ok_or("Video not found")?;
+    let snippet = video.get("snippet").ok_or("Video has no snippet")?;
+    let duration = video
+        .get("contentDetails")
+        .map(|details| json_str(details, "duration"))
+        .and_then(iso8601_duration)
+        .unwrap_or_default();
+
+    Ok(Enrichment {
+        title: json_str(snippet, "title").to_string(),
+        summary: join_facts(&[json_str(snippet, "channelTitle"), &duration]),
+        desc: json_str(snippet, "description").to_string(),
+    })
+}
+
+/// Format an ISO 8601 duration such as `PT1H2M3S` as `1:02:03`
+fn iso8601_duration(duration: &str) -> Option<String> {
+    let rest = duration.strip_prefix('P')?;
+    let (days, time) = match rest.split_once('T') {
+        Some((days, time)) => (days, time),
+        None => (rest, ""),
+    };
+    let mut seconds = 0u64;
+    for (part, units) in [
+        (days, &[('D', 86_400)][..]),
+        (time, &[('H', 3_600), ('M', 60), ('S', 1)][..]),
+    ] {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+            } else {
+                let (_, scale) = units.iter().find(|(unit, _)| *unit == c)?;
+                seconds += number.parse::<u64>().ok()? * scale;
+                number.clear();
+            }
+        }
+        if !number.is_empty() {
+            return None;
+        }
+    }
+
+    let (hours, minutes, secs) = (seconds / 3_600, seconds / 60 % 60, seconds % 60);
+    Some(if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    })
+}
+
+/// arXiv papers: title, authors and abstract from the export API
+pub struct Arxiv;
+
+impl Arxiv {
+    /// Paper ID of an abstract or PDF URL, e.g. `2101.00001v2` or `hep-th/9901001`
+    fn paper_id(url: &Url) -> Option<String> {
+        if host(url) != "arxiv.org" {
+            return None;
+        }
+        match segments(url).as_slice() {
+            ["abs" | "pdf", id @ ..] if !id.is_empty() => {
+                let id = id.join("/");
+                Some(id.trim_end_matches(".pdf").to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Enricher for Arxiv {
+    fn tag(&self) -> &'static str {
+        "paper"
+    }
+
+    fn handles(&self, url: &Url) -> bool {
+        Self::paper_id(url).is_some()
+    }
+
+    fn enrich(&self, client: &Client, _options: &FetchOptions, url: &Url) -> Result<Enrichment> {
+        let id = Self::paper_id(url).ok_or("Not an arXiv paper")?;
+        let request = client
+            .get("https://export.arxiv.org/api/query")
+            .query(&[("id_list", id.as_str())]);
+        parse_arxiv_entry(&get_text(request)?)
+    }
+}
+
+/// Most authors listed before shortening to `First Author et al.`
+const MAX_AUTHORS: usize = 3;
+
+/// Parse the Atom feed the arXiv API returns for a single paper
+pub fn parse_arxiv_entry(xml: &str) -> Result<Enrichment> {
+    // The feed has its own <title>; only look inside the entry
+    let entry = xml
+        .split_once("<entry>")
+        .and_then(|(_, rest)| rest.split_once("</entry>"))
+        .map(|(entry, _)| entry)
+        .ok_or("Paper not found")?;
+    let dom = tl::parse(entry, ParserOptions::default())?;
+    let parser = dom.parser();
+    let texts = |selector: &str| -> Vec<String> {
+        dom.query_selector(selector)
+            .into_iter()
+            .flatten()
+            .filter_map(|handle| handle.get(parser))
+            .map(|node| {
+                super::decode_entities(&node.inner_text(parser))
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    };
+
+    // Unknown IDs come back as an entry describing the error
+    if texts("id").iter().any(|id| id.contains("/api/errors")) {
+        return Err("Paper not found".into());
+    }
+    let authors = texts("name");
+    let authors = if authors.len() > MAX_AUTHORS {
+        format!("{} et al.", authors[0])
+    } else {
+        authors.join(", ")
+    };
+
+    Ok(Enrichment {
+        title: texts("title").into_iter().next().unwrap_or_default(),
+        summary: authors,
+        desc: texts("summary").into_iter().next().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[rstest]
+    #[case("https://github.com/rust-lang/rust", Some("rust-lang/rust"))]
+    #[case("https://www.github.com/rust-lang/rust.git", Some("rust-lang/rust"))]
+    #[case("https://github.com/rust-lang/rust/issues/1", Some("rust-lang/rust"))]
+    #[case("https://github.com/rust-lang", None)]
+    #[case("https://github.com/topics/rust", None)]
+    #[case("https://gitlab.com/a/b", None)]
+    fn test_github_repo(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(GitHub::repo(&url(input)).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("https://www.youtube.com/watch?v=abc123&t=10", Some("abc123"))]
+    #[case("https://m.youtube.com/watch?v=abc123", Some("abc123"))]
+    #[case("https://youtu.be/abc123", Some("abc123"))]
+    #[case("https://youtube.com/shorts/abc123", Some("abc123"))]
+    #[case("https://www.youtube.com/embed/abc123", Some("abc123"))]
+    #[case("https://www.youtube.com/@channel", None)]
+    #[case("https://www.youtube.com/watch", None)]
+    fn test_youtube_video_id(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(YouTube::video_id(&url(input)).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("https://arxiv.org/abs/2101.00001v2", Some("2101.00001v2"))]
+    #[case("https://arxiv.org/pdf/2101.00001.pdf", Some("2101.00001"))]
+    #[case("https://arxiv.org/abs/hep-th/9901001", Some("hep-th/9901001"))]
+    #[case("https://arxiv.org/list/cs.AI/recent", None)]
+    #[case("https://example.com/abs/2101.00001", None)]
+    fn test_arxiv_paper_id(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(Arxiv::paper_id(&url(input)).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_enrichers_pick_tags() {
+        let tag = |s: &str| {
+            ENRICHERS
+                .iter()
+                .find(|e| e.handles(&url(s)))
+                .map(|e| e.tag())
+        };
+        assert_eq!(tag("https://github.com/a/b"), Some("github"));
+        assert_eq!(tag("https://youtu.be/x"), Some("video"));
+        assert_eq!(tag("https://arxiv.org/abs/1234.5678"), Some("paper"));
+        assert_eq!(tag("https://example.com/"), None);
+    }
+
+    #[test]
+    fn test_parse_github_repo() {
+        let json = r#"{"full_name":"rust-lang/rust","description":"Empowering everyone",
+            "language":"Rust","stargazers_count":98765,"topics":["compiler"]}"#;
+        let enrichment = parse_github_repo(json).unwrap();
+        assert_eq!(enrichment.title, "rust-lang/rust: Empowering everyone");
+        assert_eq!(enrichment.summary, "Rust · ★ 98,765");
+        assert_eq!(enrichment.desc, "Empowering everyone");
+
+        let bare = parse_github_repo(r#"{"full_name":"a/b","description":null}"#).unwrap();
+        assert_eq!(bare.title, "a/b");
+        assert_eq!(bare.summary, "");
+
+        assert!(parse_github_repo(r#"{"message":"Not Found"}"#).is_err());
+    }
+
+    #[rstest]
+    #[case(0, "0")]
+    #[case(999, "999")]
+    #[case(1000, "1,000")]
+    #[case(1234567, "1,234,567")]
+    fn test_thousands(#[case] n: u64, #[case] expected: &str) {
+        assert_eq!(thousands(n), expected);
+    }
+
+    #[test]
+    fn test_parse_youtube_video() {
+        let json = r#"{"items":[{"snippet":{"title":"A Talk","channelTitle":"Conf",
+            "description":"Slides linked below"},"contentDetails":{"duration":"PT1H2M3S"}}]}"#;
+        let enrichment = parse_youtube_video(json).unwrap();
+        assert_eq!(enrichment.title, "A Talk");
+        assert_eq!(enrichment.summary, "Conf · 1:02:03");
+        assert_eq!(enrichment.desc, "Slides linked below");
+
+        assert!(parse_youtube_video(r#"{"items":[]}"#).is_err());
+    }
+
+    #[rstest]
+    #[case("PT4M13S", Some("4:13"))]
+    #[case("PT45S", Some("0:45"))]
+    #[case("PT2H", Some("2:00:00"))]
+    #[case("P1DT30M", Some("24:30:00"))]
+    #[case("P0D", Some("0:00"))]
+    #[case("4:13", None)]
+    #[case("PT4X", None)]
+    fn test_iso8601_duration(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(iso8601_duration(input).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_parse_arxiv_entry() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title type="html">ArXiv Query: id_list=1706.03762</title>
+              <entry>
+                <id>http://arxiv.org/abs/1706.03762v7</id>
+                <title>Attention Is All
+                  You Need</title>
+                <summary>  The dominant sequence transduction models &amp; more.
+                </summary>
+                <author><name>Ashish Vaswani</name></author>
+                <author><name>Noam Shazeer</name></author>
+              </entry>
+            </feed>"#;
+        let enrichment = parse_arxiv_entry(xml).unwrap();
+        assert_eq!(enrichment.title, "Attention Is All You Need");
+        assert_eq!(enrichment.summary, "Ashish Vaswani, Noam Shazeer");
+        assert_eq!(
+            enrichment.desc,
+            "The dominant sequence transduction models & more."
+        );
+    }
+
+    #[test]
+    fn test_parse_arxiv_entry_many_authors_and_errors() {
+        let authors: String = (1..=5)
+            .map(|i| format!("<author><name>Author {}</name></author>", i))
+            .collect();
+        let xml = format!("<feed><entry><title>T</title>{}</entry></feed>", authors);
+        assert_eq!(parse_arxiv_entry(&xml).unwrap().summary, "Author 1 et al.");
+
+        let error = "<feed><entry><id>http://arxiv.org/api/errors#bad_id</id>\
+            <title>Error</title></entry></feed>";
+        assert!(parse_arxiv_entry(error).is_err());
+        assert!(parse_arxiv_entry("<feed></feed>").is_err());
+    }
+
+    #[rstest]
+    #[case("", "", "Page desc", "Page desc")]
+    #[case("Rust · ★ 5", "", "Page desc", "Rust · ★ 5 — Page desc")]
+    #[case("Conf", "API desc", "Page desc", "Conf — API desc")]
+    #[case("Conf", "", "", "Conf")]
+    fn test_apply(
+        #[case] summary: &str,
+        #[case] desc: &str,
+        #[case] page_desc: &str,
+        #[case] expected: &str,
+    ) {
+        let mut result = FetchResult {
+            title: Arc::new("Page".to_string()),
+            desc: Arc::new(page_desc.to_string()),
+            ..Default::default()
+        };
+        let enrichment = Enrichment {
+            summary: summary.to_string(),
+            desc: desc.to_string(),
+            ..Default::default()
+        };
+        apply(&mut result, enrichment);
+        assert_eq!(result.title.as_str(), "Page");
+        assert_eq!(result.desc.as_str(), expected);
+    }
+}
@@ -0,0 +1,206 @@
+//! Page language detection
+//!
+//! Pages usually declare their language (`<html lang>`, `og:locale`, the
+//! `Content-Language` header); when they don't, the text is guessed from its
+//! script or, for Latin-script text, from how many of each language's most
+//! common words it uses. Languages are stored as lowercase ISO 639-1 codes
+//! without region, so `en-GB` and `en_US` both become `en`.
+
+/// Texts with fewer words than this are too short to guess from
+const MIN_WORDS: usize = 5;
+
+/// Matches of a language's common words needed before guessing it
+const MIN_HITS: usize = 2;
+
+/// The most frequent short words of Latin-script languages
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "in", "is", "that", "for", "it", "with", "as", "was", "on",
+            "are", "this", "by", "be", "from", "or", "have", "an", "which", "you", "not", "your",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "sich",
+            "auf", "für", "dem", "des", "von", "auch", "wie", "oder", "wird", "ich", "sie", "es",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "du", "que", "pour", "dans", "qui",
+            "pas", "sur", "au", "avec", "ce", "sont", "plus", "vous", "nous", "il", "elle", "aux",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "y", "que", "del", "en", "por", "con", "una", "para", "es", "su",
+            "al", "lo", "como", "más", "pero", "sus", "le", "ya", "muy", "también", "está",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "è", "per", "una", "della", "non", "sono", "gli", "con", "del",
+            "le", "si", "da", "nel", "alla", "anche", "come", "più", "questo", "ma", "lo", "dei",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "e", "que", "do", "da", "em", "um", "uma", "para", "com", "não", "por",
+            "dos", "das", "se", "na", "no", "mais", "como", "ao", "ou", "é", "são",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "niet", "op", "te", "zijn", "voor",
+            "met", "die", "ook", "er", "maar", "om", "aan", "wordt", "als", "bij", "naar", "je",
+        ],
+    ),
+];
+
+/// A language code from a `lang` attribute, `og:locale` or `Content-Language`
+///
+/// The primary subtag, lowercased: `en-US` and `pt_BR` give `en` and `pt`.
+/// Anything that isn't a two- or three-letter code gives `None`.
+pub fn normalize(tag: &str) -> Option<String> {
+    let primary = tag
+        .trim()
+        .split(['-', '_', ','])
+        .next()?
+        .to_ascii_lowercase();
+    let valid = (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_lowercase());
+    // `und` marks an undetermined language
+    (valid && primary != "und").then_some(primary)
+}
+
+/// Guess the language of `text`
+///
+/// Scripts used by few languages decide on their own; Latin-script text goes
+/// to the language whose common words it uses most. `None` when the text is
+/// too short or no language stands out.
+pub fn detect(text: &str) -> Option<&'static str> {
+    if let Some(lang) = detect_script(text) {
+        return Some(lang);
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.len() < MIN_WORDS {
+        return None;
+    }
+    let mut scores: Vec<(&str, usize)> = STOPWORDS
+        .iter()
+        .map(|(lang, common)| {
+            let hits = words
+                .iter()
+                .filter(|w| common.contains(&w.as_str()))
+                .count();
+            (*lang, hits)
+        })
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+    match scores.as_slice() {
+        [(lang, best), (_, second), ..] if *best >= MIN_HITS && best > second => Some(*lang),
+        _ => None,
+    }
+}
+
+/// The language of text mostly in a script used by one language
+fn detect_script(text: &str) -> Option<&'static str> {
+    let mut letters = 0;
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let lang = match c as u32 {
+            0x3040..=0x30FF => "ja", // Hiragana and Katakana
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x4E00..=0x9FFF => "zh",
+            0x0400..=0x04FF => "ru",
+            0x0370..=0x03FF => "el",
+            0x0590..=0x05FF => "he",
+            0x0600..=0x06FF => "ar",
+            0x0E00..=0x0E7F => "th",
+            _ => continue,
+        };
+        match counts.iter_mut().find(|(l, _)| *l == lang) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((lang, 1)),
+        }
+    }
+
+    // Japanese mixes kana with Han characters; any kana at all settles it
+    let count = |lang| {
+        counts
+            .iter()
+            .find(|(l, _)| *l == lang)
+            .map_or(0, |(_, n)| *n)
+    };
+    if count("ja") > 0 && count("ja") + count("zh") > letters / 2 {
+        return Some("ja");
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, n)| *n)
+        .filter(|(_, n)| *n > letters / 2)
+        .map(|(lang, _)| lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("en", Some("en"))]
+    #[case("en-US", Some("en"))]
+    #[case("pt_BR", Some("pt"))]
+    #[case(" DE ", Some("de"))]
+    #[case("de-DE, en;q=0.5", Some("de"))]
+    #[case("und", None)]
+    #[case("", None)]
+    #[case("english", None)]
+    fn test_normalize(#[case] tag: &str, #[case] expected: Option<&str>) {
+        assert_eq!(normalize(tag).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "The quick brown fox jumps over the lazy dog and runs to the forest",
+        Some("en")
+    )]
+    #[case(
+        "Die Katze ist nicht auf dem Dach, sondern mit der Maus im Haus",
+        Some("de")
+    )]
+    #[case(
+        "Le chat est sur la table avec les enfants et pour le dîner",
+        Some("fr")
+    )]
+    #[case(
+        "El gato está en la casa con los niños y por la tarde duerme",
+        Some("es")
+    )]
+    #[case(
+        "De kat zit op het dak en is niet van plan om naar beneden te komen",
+        Some("nl")
+    )]
+    #[case("Привет, как дела? Это тестовая страница", Some("ru"))]
+    #[case("東京は日本の首都です。とても大きな都市です", Some("ja"))]
+    #[case("北京是中国的首都", Some("zh"))]
+    #[case("한국어 페이지입니다", Some("ko"))]
+    #[case("Rust", None)]
+    #[case("Lorem ipsum dolor sit amet consectetur adipiscing", None)]
+    fn test_detect(#[case] text: &str, #[case] expected: Option<&str>) {
+        assert_eq!(detect(text), expected);
+    }
+}
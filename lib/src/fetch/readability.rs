@@ -0,0 +1,300 @@
+//! Readability-style article extraction
+//!
+//! Reader modes find a page's main text by scoring the elements that hold
+//! its paragraphs; this does the same in a simple form. The container with
+//! the most paragraph text wins, nudged by class and id names that usually
+//! mean content (`article`, `post`) or clutter (`sidebar`, `comments`). Its
+//! headings, paragraphs, list items, quotes and code blocks make up the
+//! article, and their word count gives the reading time shown in listings.
+
+use super::{decode_entities, parse_html, throttle, FetchOptions};
+use crate::error::Result;
+use crate::resource::ResourceKind;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use tl::{Node, Parser, ParserOptions};
+
+/// Reading speed used for reading times
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Paragraphs shorter than this (in characters) don't count towards a container's score
+const MIN_PARAGRAPH_LEN: usize = 25;
+
+/// Elements that never hold article text
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "nav", "aside", "footer", "form", "button",
+    "iframe", "select",
+];
+
+/// Class and id fragments of containers that usually hold the content
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "content", "entry", "main", "post", "story", "text",
+];
+
+/// Class and id fragments of containers that usually hold clutter
+const NEGATIVE_HINTS: &[&str] = &[
+    "comment", "sidebar", "footer", "menu", "nav", "related", "share", "social", "promo",
+];
+
+/// One block of an article's text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "text", rename_all = "lowercase")]
+pub enum Block {
+    Heading(String),
+    Paragraph(String),
+    /// A list item
+    Item(String),
+    Quote(String),
+    /// Preformatted text, line breaks kept
+    Code(String),
+}
+
+impl Block {
+    /// The block's text, whatever its kind
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Heading(text)
+            | Self::Paragraph(text)
+            | Self::Item(text)
+            | Self::Quote(text)
+            | Self::Code(text) => text,
+        }
+    }
+}
+
+/// The main text of a page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Article {
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+impl Article {
+    /// Words in the article's text
+    pub fn word_count(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|b| b.text().split_whitespace().count())
+            .sum()
+    }
+}
+
+/// Minutes needed to read `words` words, rounded up; 0 for no words
+pub fn reading_minutes(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE)
+}
+
+/// Reading time as shown in listings, e.g. `~12 min`
+pub fn reading_time(words: usize) -> String {
+    format!("~{} min", reading_minutes(words))
+}
+
+/// Fetch a page and extract its article (see [`extract`])
+pub fn fetch_article(url: &str, options: &FetchOptions) -> Result<Article> {
+    let resp = throttle::send(options, url, options.client()?.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("Fetching {} failed (Status: {})", url, resp.status()).into());
+    }
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if ResourceKind::classify(content_type.as_deref(), resp.url().as_str()) != ResourceKind::Html {
+        return Err(format!("{} is not an HTML page", url).into());
+    }
+    extract(&resp.text()?)
+}
+
+/// Extract the main text of an HTML page
+///
+/// Falls back to the whole `<body>` when no container stands out; a page
+/// with no readable text gives an article without blocks.
+pub fn extract(html: &str) -> Result<Article> {
+    let title = parse_html(html)?.title.trim().to_string();
+    let dom = tl::parse(html, ParserOptions::default())?;
+    let parser = dom.parser();
+
+    let best = dom
+        .nodes()
+        .iter()
+        .filter_map(|node| Some((node, score(node, parser)?)))
+        .filter(|(_, score)| *score > 0.0)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(node, _)| node);
+    let root = best.or_else(|| {
+        dom.query_selector("body")
+            .and_then(|mut iter| iter.next())
+            .and_then(|handle| handle.get(parser))
+    });
+
+    let mut blocks = Vec::new();
+    if let Some(root) = root {
+        collect_blocks(root, parser, &mut blocks);
+    }
+    Ok(Article { title, blocks })
+}
+
+/// How likely a container is to be the article: the length of the
+/// paragraphs directly inside it, weighted by its name, class and id
+fn score(node: &Node, parser: &Parser) -> Option<f64> {
+    let tag = node.as_tag()?;
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+    let weight = match name.as_str() {
+        "article" | "main" => 1.5,
+        "div" | "section" | "td" | "body" => 1.0,
+        _ => return None,
+    };
+
+    let text: usize = tag
+        .children()
+        .top()
+        .iter()
+        .filter_map(|handle| handle.get(parser))
+        .filter(|child| {
+            child.as_tag().is_some_and(|t| {
+                matches!(
+                    t.name().as_utf8_str().to_ascii_lowercase().as_str(),
+                    "p" | "pre" | "blockquote"
+                )
+            })
+        })
+        .map(|child| collapse_whitespace(&child.inner_text(parser)).len())
+        .filter(|len| *len >= MIN_PARAGRAPH_LEN)
+        .sum();
+
+    let attributes = tag.attributes();
+    let hints = ["class", "id"]
+        .iter()
+        .filter_map(|attr| attributes.get(*attr).flatten())
+        .map(|value| value.as_utf8_str().to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let weight = if NEGATIVE_HINTS.iter().any(|h| hints.contains(h)) {
+        weight * 0.2
+    } else if POSITIVE_HINTS.iter().any(|h| hints.contains(h)) {
+        weight * 1.5
+    } else {
+        weight
+    };
+    Some(text as f64 * weight)
+}
+
+/// Append the text blocks under `node`, in document order
+fn collect_blocks(node: &Node, parser: &Parser, blocks: &mut Vec<Block>) {
+    let Some(tag) = node.as_tag() else {
+        return;
+    };
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+    if SKIPPED_TAGS.contains(&name.as_str()) {
+        return;
+    }
+
+    let text = || collapse_whitespace(&node.inner_text(parser));
+    let block = match name.as_str() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Block::Heading(text()),
+        "p" => Block::Paragraph(text()),
+        "li" => Block::Item(text()),
+        "blockquote" => Block::Quote(text()),
+        "pre" => Block::Code(
+            decode_entities(&node.inner_text(parser))
+                .trim_matches('\n')
+                .trim_end()
+                .to_string(),
+        ),
+        _ => {
+            for child in tag.children().top().iter() {
+                if let Some(child) = child.get(parser) {
+                    collect_blocks(child, parser, blocks);
+                }
+            }
+            return;
+        }
+    };
+    if !block.text().trim().is_empty() {
+        blocks.push(block);
+    }
+}
+
+/// Decode character references and collapse runs of whitespace
+fn collapse_whitespace(text: &str) -> String {
+    decode_entities(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const PAGE: &str = r#"<html><head><title>A Post</title></head><body>
+        <nav><ul><li><a href="/">Home</a></li><li><a href="/about">About</a></li></ul></nav>
+        <div class="sidebar">
+            <p>Subscribe to the newsletter for more posts like this one.</p>
+        </div>
+        <div class="post-content">
+            <h1>A Post</h1>
+            <p>The first paragraph has enough words to count as real text.</p>
+            <p>The second paragraph talks about &amp; explains the topic.</p>
+            <ul><li>One point</li><li>Another point</li></ul>
+            <blockquote>Someone said something quotable.</blockquote>
+            <pre>fn main() {
+    println!("hi");
+}</pre>
+            <script>track();</script>
+        </div>
+        <footer><p>Copyright notice that is long enough to be a paragraph.</p></footer>
+    </body></html>"#;
+
+    #[test]
+    fn test_extract_finds_main_content() {
+        let article = extract(PAGE).unwrap();
+        assert_eq!(article.title, "A Post");
+        assert_eq!(
+            article.blocks,
+            vec![
+                Block::Heading("A Post".to_string()),
+                Block::Paragraph(
+                    "The first paragraph has enough words to count as real text.".to_string()
+                ),
+                Block::Paragraph(
+                    "The second paragraph talks about & explains the topic.".to_string()
+                ),
+                Block::Item("One point".to_string()),
+                Block::Item("Another point".to_string()),
+                Block::Quote("Someone said something quotable.".to_string()),
+                Block::Code("fn main() {\n    println!(\"hi\");\n}".to_string()),
+            ]
+        );
+        assert_eq!(article.word_count(), 35);
+    }
+
+    #[test]
+    fn test_extract_falls_back_to_body() {
+        let article = extract("<html><body><h2>Short</h2><p>Tiny.</p></body></html>").unwrap();
+        assert_eq!(
+            article.blocks,
+            vec![
+                Block::Heading("Short".to_string()),
+                Block::Paragraph("Tiny.".to_string())
+            ]
+        );
+
+        let empty = extract("<html><body><script>x()</script></body></html>").unwrap();
+        assert!(empty.blocks.is_empty());
+        assert_eq!(empty.word_count(), 0);
+    }
+
+    #[rstest]
+    #[case(0, 0, "~0 min")]
+    #[case(1, 1, "~1 min")]
+    #[case(200, 1, "~1 min")]
+    #[case(201, 2, "~2 min")]
+    #[case(2400, 12, "~12 min")]
+    fn test_reading_time(#[case] words: usize, #[case] minutes: usize, #[case] shown: &str) {
+        assert_eq!(reading_minutes(words), minutes);
+        assert_eq!(reading_time(words), shown);
+    }
+}
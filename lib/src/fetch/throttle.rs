@@ -0,0 +1,177 @@
+//! Politeness towards the sites being fetched
+//!
+//! Page requests to the same host are spaced `fetch.host_delay_ms` apart,
+//! across every thread of the process, so bulk operations over hundreds of
+//! bookmarks on one site don't hammer it. Responses asking to slow down
+//! (429 Too Many Requests, 503 Service Unavailable) are retried up to
+//! `fetch.max_retries` times, after the server's `Retry-After` or an
+//! exponential backoff.
+
+use super::FetchOptions;
+use crate::error::Result;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Wait before the first retry when the server doesn't say; doubled for each retry
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait before a retry, whatever `Retry-After` asks for
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Hosts remembered before the ones free to contact again are forgotten
+const MAX_TRACKED_HOSTS: usize = 1024;
+
+/// When each host may next be contacted
+fn next_slots() -> &'static Mutex<HashMap<String, Instant>> {
+    static SLOTS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    SLOTS.get_or_init(Default::default)
+}
+
+/// Take the next free slot for `host`, returning how long to wait for it
+fn reserve(
+    slots: &mut HashMap<String, Instant>,
+    host: &str,
+    delay: Duration,
+    now: Instant,
+) -> Duration {
+    if slots.len() >= MAX_TRACKED_HOSTS {
+        slots.retain(|_, next| *next > now);
+    }
+    let slot = slots.get(host).map_or(now, |next| (*next).max(now));
+    slots.insert(host.to_string(), slot + delay);
+    slot - now
+}
+
+/// Block until `url`'s host may be contacted again
+pub fn wait_for_host(url: &str, options: &FetchOptions) {
+    if options.host_delay_ms == 0 {
+        return;
+    }
+    let Some(host) = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+    else {
+        return;
+    };
+    let wait = {
+        let mut slots = next_slots().lock().unwrap_or_else(|e| e.into_inner());
+        reserve(
+            &mut slots,
+            &host,
+            Duration::from_millis(options.host_delay_ms),
+            Instant::now(),
+        )
+    };
+    if !wait.is_zero() {
+        std::thread::sleep(wait);
+    }
+}
+
+/// How long to wait before retry number `attempt` (from 0)
+///
+/// A `Retry-After` in seconds wins; HTTP dates there fall back to the backoff.
+fn retry_delay(retry_after: Option<&str>, attempt: u32) -> Duration {
+    retry_after
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(MAX_BACKOFF)
+}
+
+/// Send a request to `url` once its host is free, retrying when the server
+/// asks to slow down
+///
+/// The last response is returned as is, so a 429 or 503 that outlasts the
+/// retries reaches the caller like any other status.
+pub fn send(options: &FetchOptions, url: &str, request: RequestBuilder) -> Result<Response> {
+    let mut request = request;
+    let mut attempt = 0;
+    loop {
+        wait_for_host(url, options);
+        let retry = (attempt < options.max_retries)
+            .then(|| request.try_clone())
+            .flatten();
+        let resp = request.send()?;
+        let status = resp.status();
+        let Some(next) = retry.filter(|_| {
+            status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+        }) else {
+            return Ok(resp);
+        };
+        let retry_after = resp
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok());
+        let delay = retry_delay(retry_after, attempt);
+        log::debug!("{} answered {}; retrying in {:?}", url, status, delay);
+        drop(resp);
+        std::thread::sleep(delay);
+        request = next;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_reserve_spaces_requests_per_host() {
+        let mut slots = HashMap::new();
+        let delay = Duration::from_millis(500);
+        let now = Instant::now();
+
+        assert_eq!(
+            reserve(&mut slots, "github.com", delay, now),
+            Duration::ZERO
+        );
+        assert_eq!(reserve(&mut slots, "github.com", delay, now), delay);
+        assert_eq!(reserve(&mut slots, "github.com", delay, now), delay * 2);
+        // Other hosts aren't held up
+        assert_eq!(
+            reserve(&mut slots, "example.com", delay, now),
+            Duration::ZERO
+        );
+        // A host left alone long enough is free again
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            reserve(&mut slots, "github.com", delay, later),
+            Duration::ZERO
+        );
+    }
+
+    #[rstest]
+    #[case(None, 0, 1)]
+    #[case(None, 1, 2)]
+    #[case(None, 2, 4)]
+    #[case(None, 10, 60)]
+    #[case(Some("7"), 0, 7)]
+    #[case(Some(" 3 "), 2, 3)]
+    #[case(Some("3600"), 0, 60)]
+    #[case(Some("Wed, 21 Oct 2015 07:28:00 GMT"), 1, 2)]
+    fn test_retry_delay(
+        #[case] retry_after: Option<&str>,
+        #[case] attempt: u32,
+        #[case] secs: u64,
+    ) {
+        assert_eq!(retry_delay(retry_after, attempt), Duration::from_secs(secs));
+    }
+
+    #[test]
+    fn test_wait_for_host_without_delay_is_immediate() {
+        let options = FetchOptions {
+            host_delay_ms: 0,
+            ..FetchOptions::default()
+        };
+        let start = Instant::now();
+        for _ in 0..3 {
+            wait_for_host("https://throttle-test.invalid/", &options);
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
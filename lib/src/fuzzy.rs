@@ -1,7 +1,225 @@
+use std::collections::HashSet;
+use std::fmt;
 use std::sync::{Arc, OnceLock};
 
 use crate::models::bookmark::Bookmark;
+use crate::tags::parse_tags;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use nucleo_picker::event::{keybind_default, Event};
 use nucleo_picker::{Picker, Render};
+use serde::{Deserialize, Serialize};
+
+/// Picker settings (`picker` in the config)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PickerOptions {
+    /// The built-in picker or an external one such as fzf (`--picker`)
+    pub kind: PickerKind,
+    /// The external picker's command line; `{query}` is replaced by the search
+    pub command: String,
+    /// Keys acting on the picked bookmarks from inside the built-in picker
+    pub keys: PickerKeys,
+}
+
+impl Default for PickerOptions {
+    fn default() -> Self {
+        Self {
+            kind: PickerKind::default(),
+            command: DEFAULT_PICKER_COMMAND.to_string(),
+            keys: PickerKeys::default(),
+        }
+    }
+}
+
+/// fzf showing everything but the ID column; skim takes the same options
+pub const DEFAULT_PICKER_COMMAND: &str =
+    "fzf --multi --delimiter='\\t' --with-nth=2.. --query={query}";
+
+/// Which picker chooses bookmarks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PickerKind {
+    #[default]
+    Builtin,
+    /// `picker.command`, reading candidates on stdin and printing the chosen lines
+    External,
+}
+
+impl PickerKind {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "builtin" => Some(Self::Builtin),
+            "external" => Some(Self::External),
+            _ => None,
+        }
+    }
+}
+
+/// Keys the bookmark picker acts on; Enter picks and Tab marks regardless
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PickerKeys {
+    /// Open the picked bookmarks in the browser
+    pub open: KeyBinding,
+    /// Copy the picked bookmarks' URLs to the clipboard
+    pub copy_url: KeyBinding,
+    /// Edit the picked bookmarks in the editor
+    pub edit: KeyBinding,
+    /// Delete the picked bookmarks, after confirmation
+    pub delete: KeyBinding,
+    /// Show or hide each entry's full URL, tags and description
+    pub preview: KeyBinding,
+}
+
+impl Default for PickerKeys {
+    fn default() -> Self {
+        let key = |s: &str| KeyBinding::parse(s).expect("valid default key");
+        Self {
+            open: key("ctrl-o"),
+            copy_url: key("ctrl-y"),
+            edit: key("alt-e"),
+            delete: key("alt-d"),
+            preview: key("alt-p"),
+        }
+    }
+}
+
+impl PickerKeys {
+    /// The action bound to `key`, if any
+    fn action(&self, key: &KeyEvent) -> Option<PickAction> {
+        [
+            (&self.open, PickAction::Open),
+            (&self.copy_url, PickAction::CopyUrl),
+            (&self.edit, PickAction::Edit),
+            (&self.delete, PickAction::Delete),
+        ]
+        .into_iter()
+        .find(|(binding, _)| binding.matches(key))
+        .map(|(_, action)| action)
+    }
+}
+
+/// A key with modifiers, written like `ctrl-o`, `alt-e`, `ctrl-alt-x` or `f2`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+/// Named keys, as written in key bindings
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl KeyBinding {
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        let mut parts: Vec<&str> = s.split('-').collect();
+        let key = parts.pop()?;
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match KEY_NAMES.iter().find(|(name, _)| *name == key) {
+                Some((_, code)) => *code,
+                None => KeyCode::F(
+                    key.strip_prefix('f')?
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=12).contains(n))?,
+                ),
+            },
+        };
+        Some(Self { code, modifiers })
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        key.kind == KeyEventKind::Press && key.code == self.code && key.modifiers == self.modifiers
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+            .ok_or_else(|| format!("invalid key '{}' (expected e.g. ctrl-o, alt-e or f2)", s))
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(key: KeyBinding) -> Self {
+        key.to_string()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => {
+                let name = KEY_NAMES
+                    .iter()
+                    .find(|(_, c)| *c == code)
+                    .map_or("?", |(name, _)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// What to do with the bookmarks picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickAction {
+    /// Picked with Enter: whatever the command picks for
+    Select,
+    Open,
+    CopyUrl,
+    Edit,
+    Delete,
+}
+
+/// The bookmarks picked and the key they were picked with
+#[derive(Debug, Clone)]
+pub struct Picked {
+    pub action: PickAction,
+    pub bookmarks: Vec<Bookmark>,
+}
 
 /// Wrapper for rendering bookmarks in the picker
 /// Stores only the ID and display string, bookmark can be looked up after selection
@@ -51,6 +269,36 @@ impl BookmarkItem {
             display,
         }
     }
+
+    /// Prefix the display with a selection mark for the multi-select picker
+    fn marked(mut self, marked: bool) -> Self {
+        let mark = if marked { "\x1b[1;33m*\x1b[0m" } else { " " };
+        self.display = format!("{} {}", mark, self.display);
+        self
+    }
+
+    /// Follow the display with the full URL, the tags and the description
+    fn with_preview(mut self, bookmark: &Bookmark) -> Self {
+        self.display.push_str(&preview(bookmark));
+        self
+    }
+}
+
+/// The preview lines under an entry, each starting with a newline
+fn preview(bookmark: &Bookmark) -> String {
+    let mut lines = format!("\n      \x1b[33m{}\x1b[0m", bookmark.url);
+    let tags = parse_tags(&bookmark.tags);
+    if !tags.is_empty() {
+        lines.push_str(&format!("\n      \x1b[34m# {}\x1b[0m", tags.join(", ")));
+    }
+    for line in bookmark
+        .description
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+    {
+        lines.push_str(&format!("\n      {}", line.trim_end()));
+    }
+    lines
 }
 
 /// Renderer for bookmark items
@@ -64,39 +312,153 @@ impl Render<BookmarkItem> for BookmarkRenderer {
     }
 }
 
+/// Bookmarks marked in a multi-select picker session
+#[derive(Debug, Default)]
+struct MultiSelection {
+    marked: HashSet<usize>,
+}
+
+impl MultiSelection {
+    fn toggle(&mut self, id: usize) {
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+    }
+
+    fn is_marked(&self, id: usize) -> bool {
+        self.marked.contains(&id)
+    }
+
+    /// Marked bookmarks in their original order, or the highlighted one if nothing is marked
+    fn resolve(&self, bookmarks: &[Bookmark], highlighted: usize) -> Vec<Bookmark> {
+        if self.marked.is_empty() {
+            return bookmarks
+                .iter()
+                .filter(|b| b.id == highlighted)
+                .cloned()
+                .collect();
+        }
+        bookmarks
+            .iter()
+            .filter(|b| self.marked.contains(&b.id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Tab (without modifiers) toggles the mark on the highlighted bookmark
+fn is_toggle_key(key: &KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && key.modifiers == KeyModifiers::NONE
+        && key.code == KeyCode::Tab
+}
+
+/// Why the picker returned
+enum Exit {
+    Picked(PickAction),
+    Toggled,
+    Preview,
+}
+
+/// Run the bookmark picker
+///
+/// Tab marks or unmarks the highlighted bookmark. Enter, or one of the action
+/// keys in `keys`, picks the marked bookmarks (or the highlighted one if none
+/// are marked); the preview key shows or hides every entry's full URL, tags
+/// and description. Returns None if the picker is dismissed.
 pub fn run_fuzzy_search(
     bookmarks: &[Bookmark],
-    _query: Option<String>,
-) -> crate::error::Result<Option<Bookmark>> {
+    query: Option<String>,
+    keys: &PickerKeys,
+) -> crate::error::Result<Option<Picked>> {
+    pick(bookmarks, query, keys, true)
+}
+
+/// Run the picker for commands that only pick: Tab marks/unmarks the highlighted
+/// bookmark, Enter returns the marked bookmarks (or the highlighted one if none are marked)
+///
+/// Returns an empty list if the picker is dismissed.
+pub fn run_fuzzy_search_multi(
+    bookmarks: &[Bookmark],
+    query: Option<String>,
+) -> crate::error::Result<Vec<Bookmark>> {
+    let picked = pick(bookmarks, query, &PickerKeys::default(), false)?;
+    Ok(picked.map(|p| p.bookmarks).unwrap_or_default())
+}
+
+fn pick(
+    bookmarks: &[Bookmark],
+    query: Option<String>,
+    keys: &PickerKeys,
+    actions: bool,
+) -> crate::error::Result<Option<Picked>> {
     if bookmarks.is_empty() {
         return Ok(None);
     }
 
-    // Calculate the width needed for the largest ID
     let max_id_width = bookmarks
         .iter()
         .map(|b| b.id.to_string().len())
         .max()
         .unwrap_or(1);
 
-    // Create picker
     let mut picker = Picker::new(BookmarkRenderer);
-
-    // Inject all bookmarks (only store ID and display string)
-    let injector = picker.injector();
-    for bookmark in bookmarks {
-        let item = BookmarkItem::new(bookmark, max_id_width);
-        injector.push(item);
+    if let Some(query) = query {
+        picker.update_query(query);
     }
+    let mut selection = MultiSelection::default();
+    let mut preview = false;
 
-    // Run picker and get selection
-    match picker.pick() {
-        Ok(Some(item)) => {
-            // Look up the full bookmark by ID to avoid cloning all bookmarks upfront
-            Ok(bookmarks.iter().find(|b| b.id == item.id).cloned())
+    loop {
+        // Items cache their display text, so re-inject after every toggle to redraw them
+        let injector = picker.injector();
+        for bookmark in bookmarks {
+            let item =
+                BookmarkItem::new(bookmark, max_id_width).marked(selection.is_marked(bookmark.id));
+            injector.push(if preview {
+                item.with_preview(bookmark)
+            } else {
+                item
+            });
         }
-        Ok(None) => Ok(None),
-        Err(e) => Err(crate::error::BukursError::FuzzySearch(e.to_string())),
+
+        let mut exit = Exit::Picked(PickAction::Select);
+        let picked = picker.pick_with_keybind(|key| {
+            if is_toggle_key(&key) {
+                exit = Exit::Toggled;
+                Some(Event::Select)
+            } else if keys.preview.matches(&key) {
+                exit = Exit::Preview;
+                Some(Event::Select)
+            } else if let Some(action) = keys.action(&key).filter(|_| actions) {
+                exit = Exit::Picked(action);
+                Some(Event::Select)
+            } else {
+                keybind_default(key)
+            }
+        });
+
+        let highlighted = match picked {
+            Ok(Some(item)) => item.id,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(crate::error::BukursError::FuzzySearch(e.to_string())),
+        };
+
+        match exit {
+            Exit::Picked(action) => {
+                return Ok(Some(Picked {
+                    action,
+                    bookmarks: selection.resolve(bookmarks, highlighted),
+                }))
+            }
+            Exit::Toggled => selection.toggle(highlighted),
+            Exit::Preview => preview = !preview,
+        }
+
+        // Keep the user's query across the restart, which clears it
+        let query = picker.query().to_owned();
+        picker.restart();
+        picker.update_query(query);
     }
 }
 
@@ -138,3 +500,131 @@ pub fn run_fuzzy_tag_search(tags: &[String]) -> crate::error::Result<Option<Stri
         Err(e) => Err(crate::error::BukursError::FuzzySearch(e.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn bookmarks() -> Vec<Bookmark> {
+        (1..=3)
+            .map(|id| {
+                Bookmark::new(
+                    id,
+                    format!("https://example.com/{}", id),
+                    format!("Example {}", id),
+                    ",".to_string(),
+                    String::new(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_selection_falls_back_to_highlighted() {
+        let selection = MultiSelection::default();
+        let picked = selection.resolve(&bookmarks(), 2);
+        assert_eq!(picked.iter().map(|b| b.id).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn test_multi_selection_keeps_original_order() {
+        let mut selection = MultiSelection::default();
+        selection.toggle(3);
+        selection.toggle(1);
+        selection.toggle(2);
+        selection.toggle(2);
+
+        let picked = selection.resolve(&bookmarks(), 2);
+        assert_eq!(picked.iter().map(|b| b.id).collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[rstest]
+    #[case("ctrl-o", KeyCode::Char('o'), KeyModifiers::CONTROL)]
+    #[case("Alt-E", KeyCode::Char('e'), KeyModifiers::ALT)]
+    #[case("ctrl-alt-space", KeyCode::Char(' '), KeyModifiers::CONTROL.union(KeyModifiers::ALT))]
+    #[case("f2", KeyCode::F(2), KeyModifiers::NONE)]
+    #[case("shift-delete", KeyCode::Delete, KeyModifiers::SHIFT)]
+    fn test_key_binding_parse(
+        #[case] s: &str,
+        #[case] code: KeyCode,
+        #[case] modifiers: KeyModifiers,
+    ) {
+        let key = KeyBinding::parse(s).unwrap();
+        assert_eq!(key, KeyBinding { code, modifiers });
+        assert_eq!(KeyBinding::parse(&key.to_string()), Some(key.clone()));
+        assert!(key.matches(&KeyEvent::new(code, modifiers)));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("ctrl-")]
+    #[case("meta-o")]
+    #[case("f13")]
+    #[case("ctrl-oo")]
+    fn test_key_binding_rejects(#[case] s: &str) {
+        assert_eq!(KeyBinding::parse(s), None);
+    }
+
+    #[test]
+    fn test_picker_keys_actions() {
+        let keys: PickerKeys = serde_yaml::from_str("open: ctrl-x\ndelete: f8").unwrap();
+        let press = |code, modifiers| keys.action(&KeyEvent::new(code, modifiers));
+        assert_eq!(
+            press(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            Some(PickAction::Open)
+        );
+        assert_eq!(
+            press(KeyCode::F(8), KeyModifiers::NONE),
+            Some(PickAction::Delete)
+        );
+        assert_eq!(
+            press(KeyCode::Char('y'), KeyModifiers::CONTROL),
+            Some(PickAction::CopyUrl)
+        );
+        assert_eq!(press(KeyCode::Char('o'), KeyModifiers::CONTROL), None);
+        assert!(serde_yaml::from_str::<PickerKeys>("edit: hyper-e").is_err());
+    }
+
+    #[test]
+    fn test_picker_options_kind() {
+        let options: PickerOptions = serde_yaml::from_str("kind: external").unwrap();
+        assert_eq!(options.kind, PickerKind::External);
+        assert_eq!(options.command, DEFAULT_PICKER_COMMAND);
+        assert_eq!(
+            PickerKind::from_string("Builtin"),
+            Some(PickerKind::Builtin)
+        );
+        assert_eq!(PickerKind::from_string("fzf"), None);
+        assert!(serde_yaml::from_str::<PickerOptions>("kind: fzf").is_err());
+    }
+
+    #[test]
+    fn test_preview_lines() {
+        let bookmark = Bookmark::new(
+            1,
+            "https://example.com/a/very/long/path".to_string(),
+            "Example".to_string(),
+            ",rust,cli,".to_string(),
+            "First line\n\nSecond line".to_string(),
+        );
+        let display = BookmarkItem::new(&bookmark, 1)
+            .with_preview(&bookmark)
+            .display;
+        let lines: Vec<&str> = display.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].contains("https://example.com/a/very/long/path"));
+        assert!(lines[2].contains("# rust, cli"));
+        assert_eq!(lines[3], "      First line");
+        assert_eq!(lines[4], "      Second line");
+    }
+
+    #[test]
+    fn test_marked_display() {
+        let bookmark = &bookmarks()[0];
+        let marked = BookmarkItem::new(bookmark, 1).marked(true);
+        let unmarked = BookmarkItem::new(bookmark, 1).marked(false);
+        assert!(marked.display.starts_with("\x1b[1;33m*"));
+        assert!(unmarked.display.starts_with("  \x1b[1;36m[1]"));
+    }
+}
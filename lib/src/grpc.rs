@@ -0,0 +1,458 @@
+//! gRPC server (the `grpc` feature), run by `bukurs serve --grpc`
+//!
+//! The service is defined in `proto/bukurs.proto` (package `bukurs.v1`); the
+//! generated messages, client and server live in [`proto`]. Requests go
+//! through [`BookmarkService`] on a connection pool, and changes take the
+//! database lock like the CLI's mutating commands, so a running server and
+//! the CLI can share a database. `Watch` streams the database's change
+//! events (see [`crate::events`]), including those made by other processes.
+
+use crate::async_api::{AsyncBukuDb, DEFAULT_POOL_SIZE};
+use crate::audit::AuditOperation;
+use crate::cancel::CancellationToken;
+use crate::db::{BukuDb, BukuDbPool};
+use crate::error::{BukursError, Result};
+use crate::events::{BookmarkEvent, POLL_INTERVAL};
+use crate::fetch::FetchOptions;
+use crate::models::bookmark::Bookmark;
+use crate::search::{SearchOptions, SearchRank};
+use crate::service::{AddRequest, BookmarkService, FieldChanges, SearchQuery};
+use crate::tags::parse_tags;
+use proto::bookmarks_server::{Bookmarks, BookmarksServer};
+use proto::Operation;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// Generated from `proto/bukurs.proto`
+pub mod proto {
+    tonic::include_proto!("bukurs.v1");
+}
+
+/// Address `serve --grpc` listens on without `--addr`
+pub const DEFAULT_ADDR: &str = "127.0.0.1:50051";
+
+/// Events queued for each `Watch` client that isn't keeping up
+pub const WATCH_BUFFER: usize = 256;
+
+/// How often the server checks whether it was asked to stop
+const SHUTDOWN_POLL: Duration = Duration::from_millis(200);
+
+impl From<BukursError> for Status {
+    fn from(err: BukursError) -> Self {
+        match err {
+            BukursError::BookmarkNotFound(_) => Status::not_found(err.to_string()),
+            BukursError::InvalidInput(_)
+            | BukursError::InvalidSelector(_)
+            | BukursError::UrlParse(_) => Status::invalid_argument(err.to_string()),
+            BukursError::Locked(_) | BukursError::Offline | BukursError::Tls(_) => {
+                Status::unavailable(err.to_string())
+            }
+            _ => Status::internal(err.to_string()),
+        }
+    }
+}
+
+impl From<Bookmark> for proto::Bookmark {
+    fn from(bookmark: Bookmark) -> Self {
+        Self {
+            id: bookmark.id as u64,
+            tags: parse_tags(&bookmark.tags),
+            url: bookmark.url,
+            title: bookmark.title,
+            description: bookmark.description,
+        }
+    }
+}
+
+impl From<BookmarkEvent> for proto::ChangeEvent {
+    fn from(event: BookmarkEvent) -> Self {
+        let operation = match event.operation {
+            AuditOperation::Add => Operation::Add,
+            AuditOperation::Update => Operation::Update,
+            AuditOperation::Delete => Operation::Delete,
+            AuditOperation::Import => Operation::Import,
+            AuditOperation::Fetch => Operation::Fetch,
+            AuditOperation::Undo => Operation::Undo,
+        };
+        Self {
+            operation: operation.into(),
+            ids: ids(&event.ids),
+            timestamp: event.timestamp,
+            source: event.source.to_string(),
+            seq: event.seq,
+        }
+    }
+}
+
+fn bookmark_list(bookmarks: Vec<Bookmark>) -> proto::BookmarkList {
+    proto::BookmarkList {
+        bookmarks: bookmarks.into_iter().map(proto::Bookmark::from).collect(),
+    }
+}
+
+fn ids(ids: &[usize]) -> Vec<u64> {
+    ids.iter().map(|&id| id as u64).collect()
+}
+
+/// The `bukurs.v1.Bookmarks` service over one database
+pub struct GrpcService {
+    db: AsyncBukuDb,
+    fetch: FetchOptions,
+    search: SearchOptions,
+    /// Set when the server shuts down, which ends the `Watch` streams
+    closing: Arc<watch::Sender<bool>>,
+}
+
+impl GrpcService {
+    pub fn new(db: BukuDb, fetch: FetchOptions, search: SearchOptions) -> Self {
+        let (closing, _) = watch::channel(false);
+        Self {
+            db: AsyncBukuDb::from_pool(BukuDbPool::from_db(db, DEFAULT_POOL_SIZE)),
+            fetch,
+            search,
+            closing: Arc::new(closing),
+        }
+    }
+
+    /// Run a read on the blocking thread pool
+    async fn read<T, F>(&self, read: F) -> Result<T>
+    where
+        F: FnOnce(BookmarkService<'_>) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let fetch = self.fetch.clone();
+        self.db
+            .call(move |db| read(BookmarkService::new(db, fetch)))
+            .await
+    }
+
+    /// Run a change under the database lock, like the CLI's mutating commands
+    async fn write<T, F>(&self, change: F) -> Result<T>
+    where
+        F: FnOnce(BookmarkService<'_>) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let fetch = self.fetch.clone();
+        self.db
+            .call(move |db| {
+                let _lock = db.connection_options().lock_database(db.get_path())?;
+                change(BookmarkService::new(db, fetch))
+            })
+            .await
+    }
+}
+
+#[tonic::async_trait]
+impl Bookmarks for GrpcService {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> std::result::Result<Response<proto::BookmarkList>, Status> {
+        let request = request.into_inner();
+        let rank = match request.rank.as_str() {
+            "" => self.search.rank,
+            rank => Some(SearchRank::from_string(rank).ok_or_else(|| {
+                Status::invalid_argument(format!(
+                    "Unknown rank '{}' (expected relevance, recent, visits or rating)",
+                    rank
+                ))
+            })?),
+        };
+        let query = SearchQuery {
+            keywords: request.keywords,
+            all: request.all,
+            regex: request.regex,
+            limit: (request.limit > 0).then_some(request.limit as usize),
+            rank,
+            weights: self.search.weights,
+            keyword_match: self.search.keyword_match,
+            ..SearchQuery::default()
+        };
+        let found = self
+            .read(move |service| service.search_query(&query))
+            .await?;
+        Ok(Response::new(bookmark_list(found)))
+    }
+
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> std::result::Result<Response<proto::Bookmark>, Status> {
+        let id = request.into_inner().id as usize;
+        let bookmark = self
+            .read(move |service| {
+                service
+                    .db()
+                    .get_rec_by_id(id)?
+                    .ok_or(BukursError::BookmarkNotFound(id))
+            })
+            .await?;
+        Ok(Response::new(bookmark.into()))
+    }
+
+    async fn add(
+        &self,
+        request: Request<proto::AddRequest>,
+    ) -> std::result::Result<Response<proto::Bookmark>, Status> {
+        let request = request.into_inner();
+        let fetch = request.fetch;
+        let add = AddRequest {
+            url: request.url,
+            title: request.title,
+            tags: (!request.tags.is_empty()).then_some(request.tags),
+            desc: request.description,
+            ..AddRequest::default()
+        };
+        let bookmark = self
+            .write(move |service| {
+                let outcome = if fetch {
+                    service.add_with_fetch(&add)?
+                } else {
+                    service.add(&add, None)?
+                };
+                service
+                    .db()
+                    .get_rec_by_id(outcome.id)?
+                    .ok_or(BukursError::BookmarkNotFound(outcome.id))
+            })
+            .await?;
+        Ok(Response::new(bookmark.into()))
+    }
+
+    async fn update(
+        &self,
+        request: Request<proto::UpdateRequest>,
+    ) -> std::result::Result<Response<proto::UpdateResponse>, Status> {
+        let request = request.into_inner();
+        let selector = request.selector;
+        let changes = FieldChanges {
+            url: request.url,
+            title: request.title,
+            desc: request.description,
+            tags: (!request.tags.is_empty()).then_some(request.tags),
+            immutable: None,
+        };
+        let outcome = self
+            .write(move |service| service.update_many(&selector, &changes))
+            .await?;
+        if let Some(error) = outcome.errors.first() {
+            return Err(Status::invalid_argument(error.clone()));
+        }
+        Ok(Response::new(proto::UpdateResponse {
+            ids: ids(&outcome.ids),
+        }))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<proto::DeleteRequest>,
+    ) -> std::result::Result<Response<proto::BookmarkList>, Status> {
+        let selector = request.into_inner().selector;
+        let outcome = self
+            .write(move |service| service.delete_by_selector(&selector))
+            .await?;
+        Ok(Response::new(bookmark_list(outcome.deleted)))
+    }
+
+    async fn list_tags(
+        &self,
+        _request: Request<proto::ListTagsRequest>,
+    ) -> std::result::Result<Response<proto::TagList>, Status> {
+        let tags = self
+            .read(|service| Ok(service.db().get_all_tags()?))
+            .await?;
+        Ok(Response::new(proto::TagList { tags }))
+    }
+
+    type WatchStream = ReceiverStream<std::result::Result<proto::ChangeEvent, Status>>;
+
+    async fn watch(
+        &self,
+        _request: Request<proto::WatchRequest>,
+    ) -> std::result::Result<Response<Self::WatchStream>, Status> {
+        let db = self.db.clone();
+        let mut last_seq = db.call(|db| Ok(db.last_event_seq()?)).await?;
+        let mut closing = self.closing.subscribe();
+        let (sender, receiver) = mpsc::channel(WATCH_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    _ = closing.changed() => break,
+                }
+                let after = last_seq;
+                let events = match db.call(move |db| Ok(db.events_since(after)?)).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        let _ = sender.send(Err(e.into())).await;
+                        break;
+                    }
+                };
+                for event in events {
+                    last_seq = event.seq;
+                    if sender.send(Ok(event.into())).await.is_err() {
+                        // The client went away
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Serve `service` on `addr` until `cancel` is cancelled
+///
+/// Blocks the calling thread on a runtime of its own. Open `Watch` streams
+/// end when the server stops.
+pub fn serve(service: GrpcService, addr: SocketAddr, cancel: &CancellationToken) -> Result<()> {
+    let closing = Arc::clone(&service.closing);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let shutdown = async {
+            while !cancel.is_cancelled() {
+                tokio::time::sleep(SHUTDOWN_POLL).await;
+            }
+            let _ = closing.send(true);
+        };
+        tonic::transport::Server::builder()
+            .add_service(BookmarksServer::new(service))
+            .serve_with_shutdown(addr, shutdown)
+            .await
+            .map_err(|e| BukursError::Other(format!("gRPC server on {}: {}", addr, e)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn service() -> GrpcService {
+        let db = BukuDb::init_in_memory().unwrap();
+        GrpcService::new(db, FetchOptions::default(), SearchOptions::default())
+    }
+
+    fn add_request(url: &str, tags: &[&str]) -> Request<proto::AddRequest> {
+        Request::new(proto::AddRequest {
+            url: url.to_string(),
+            title: Some(format!("Title of {}", url)),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_crud_round_trip() {
+        let service = service();
+
+        let added = service
+            .add(add_request("https://rust-lang.org", &["rust", "lang"]))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(added.url, "https://rust-lang.org");
+        assert!(added.tags.contains(&"rust".to_string()));
+
+        let found = service
+            .search(Request::new(proto::SearchRequest {
+                keywords: vec!["rust".to_string()],
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(found.bookmarks, vec![added.clone()]);
+
+        let updated = service
+            .update(Request::new(proto::UpdateRequest {
+                selector: vec![added.id.to_string()],
+                title: Some("Rust".to_string()),
+                tags: vec!["-lang".to_string()],
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(updated.ids, vec![added.id]);
+
+        let fetched = service
+            .get(Request::new(proto::GetRequest { id: added.id }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(fetched.title, "Rust");
+        assert!(!fetched.tags.contains(&"lang".to_string()));
+
+        let tags = service
+            .list_tags(Request::new(proto::ListTagsRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(tags.tags.contains(&"rust".to_string()));
+
+        let deleted = service
+            .delete(Request::new(proto::DeleteRequest {
+                selector: vec![added.id.to_string()],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(deleted.bookmarks.len(), 1);
+
+        let missing = service
+            .get(Request::new(proto::GetRequest { id: added.id }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_bad_rank_is_invalid_argument() {
+        let status = service()
+            .search(Request::new(proto::SearchRequest {
+                keywords: vec!["rust".to_string()],
+                rank: "best".to_string(),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_watch_streams_changes() {
+        let service = service();
+        let mut stream = service
+            .watch(Request::new(proto::WatchRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let added = service
+            .add(add_request("https://example.com", &[]))
+            .await
+            .unwrap()
+            .into_inner();
+        service
+            .delete(Request::new(proto::DeleteRequest {
+                selector: vec![added.id.to_string()],
+            }))
+            .await
+            .unwrap();
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.operation(), Operation::Add);
+        assert_eq!(event.ids, vec![added.id]);
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.operation(), Operation::Delete);
+        assert_eq!(event.ids, vec![added.id]);
+    }
+}
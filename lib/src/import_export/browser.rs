@@ -1,11 +1,19 @@
-use super::import::BookmarkImporter;
+use super::import::{
+    preview_import, BookmarkImporter, DuplicateStrategy, ImportPreview, ImportStats, ParsedBookmark,
+};
 use crate::db::BukuDb;
+use crate::error::BukursError;
+use crate::progress::{NoProgress, ProgressSink};
+use crate::tags::{format_tags, parse_tags};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 /// Detected browser type
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BrowserType {
     Chrome,
     Firefox,
@@ -48,6 +56,59 @@ impl BrowserProfile {
     pub fn display_string(&self) -> String {
         format!("{} ({})", self.browser.display_name(), self.profile_name)
     }
+
+    /// Profile for a bookmarks file outside the detected locations, such as a
+    /// backup copy or a file from another machine
+    ///
+    /// `path` may be the file itself or a profile directory containing
+    /// `places.sqlite` or `Bookmarks`. Without `browser`, SQLite files are read as
+    /// Firefox's `places.sqlite` and anything else as Chrome's `Bookmarks` JSON.
+    pub fn from_path(path: &Path, browser: Option<BrowserType>) -> crate::error::Result<Self> {
+        let file = if path.is_dir() {
+            ["places.sqlite", "Bookmarks"]
+                .iter()
+                .map(|name| path.join(name))
+                .find(|file| file.is_file())
+                .ok_or_else(|| {
+                    BukursError::Browser(format!(
+                        "No places.sqlite or Bookmarks file in {}",
+                        path.display()
+                    ))
+                })?
+        } else if path.is_file() {
+            path.to_path_buf()
+        } else {
+            return Err(BukursError::Browser(format!(
+                "{} not found",
+                path.display()
+            )));
+        };
+
+        let browser = match browser {
+            Some(BrowserType::Safari) => {
+                return Err(BukursError::Browser(
+                    "Importing Safari bookmarks is not supported".to_string(),
+                ))
+            }
+            Some(browser) => browser,
+            None if is_sqlite_file(&file)? => BrowserType::Firefox,
+            None => BrowserType::Chrome,
+        };
+
+        Ok(Self {
+            browser,
+            profile_name: file.display().to_string(),
+            path: file,
+        })
+    }
+}
+
+/// Whether a file starts with the SQLite header
+fn is_sqlite_file(path: &Path) -> crate::error::Result<bool> {
+    const HEADER: &[u8] = b"SQLite format 3\0";
+    let mut buf = [0u8; 16];
+    let mut file = fs::File::open(path)?;
+    Ok(file.read_exact(&mut buf).is_ok() && buf == HEADER)
 }
 
 /// Chrome bookmark structure (JSON)
@@ -57,6 +118,8 @@ struct ChromeBookmark {
     node_type: String,
     name: Option<String>,
     url: Option<String>,
+    /// Microseconds since 1601-01-01 (the WebKit epoch), as a string
+    date_added: Option<String>,
     children: Option<Vec<ChromeBookmark>>,
 }
 
@@ -90,92 +153,143 @@ pub fn detect_browsers() -> Vec<BrowserProfile> {
 
 /// Detect all Chrome profile locations
 fn detect_all_chrome_profiles() -> Vec<BrowserProfile> {
-    let mut profiles = Vec::new();
     let home = match std::env::var("HOME") {
         Ok(h) => h,
-        Err(_) => return profiles,
+        Err(_) => return Vec::new(),
     };
 
     #[cfg(target_os = "macos")]
-    let chrome_base = format!("{}/Library/Application Support/Google/Chrome", home);
+    let bases = [(
+        format!("{}/Library/Application Support/Google/Chrome", home),
+        "",
+    )];
 
+    // Chromium and the Flatpak/Snap packages keep their own config directories
     #[cfg(target_os = "linux")]
-    let chrome_base = format!("{}/.config/google-chrome", home);
+    let bases = [
+        (format!("{}/.config/google-chrome", home), ""),
+        (format!("{}/.config/chromium", home), "Chromium "),
+        (
+            format!("{}/.var/app/com.google.Chrome/config/google-chrome", home),
+            "Flatpak ",
+        ),
+        (
+            format!("{}/.var/app/org.chromium.Chromium/config/chromium", home),
+            "Flatpak Chromium ",
+        ),
+        (
+            format!("{}/snap/chromium/common/chromium", home),
+            "Snap Chromium ",
+        ),
+    ];
 
     #[cfg(target_os = "windows")]
-    let chrome_base = format!("{}\\AppData\\Local\\Google\\Chrome\\User Data", home);
-
-    let base_path = PathBuf::from(&chrome_base);
-    if !base_path.exists() {
-        return profiles;
-    }
+    let bases = [(
+        format!("{}\\AppData\\Local\\Google\\Chrome\\User Data", home),
+        "",
+    )];
 
-    // Common profile directories to check
-    let profile_names = vec![
-        "Default",
-        "Profile 1",
-        "Profile 2",
-        "Profile 3",
-        "Profile 4",
-    ];
+    bases
+        .iter()
+        .flat_map(|(base, label)| {
+            chrome_family_profiles(BrowserType::Chrome, Path::new(base), label)
+        })
+        .collect()
+}
 
-    for profile_name in &profile_names {
-        let bookmarks_path = base_path.join(profile_name).join("Bookmarks");
-        if bookmarks_path.exists() {
-            profiles.push(BrowserProfile {
-                browser: BrowserType::Chrome,
-                profile_name: profile_name.to_string(),
+/// `Bookmarks` files of every profile under a Chrome-style user data directory
+///
+/// Profiles live in `Default` and `Profile <n>` directories; each is shown by
+/// the name set in Chrome, read from its `Preferences`, with the directory.
+fn chrome_family_profiles(browser: BrowserType, base: &Path, label: &str) -> Vec<BrowserProfile> {
+    let Ok(entries) = fs::read_dir(base) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_str()?.to_string();
+            let is_profile = dir_name == "Default" || dir_name.starts_with("Profile ");
+            let bookmarks_path = entry.path().join("Bookmarks");
+            (is_profile && bookmarks_path.is_file()).then_some((dir_name, bookmarks_path))
+        })
+        .collect();
+    dirs.sort_by_key(|(dir_name, _)| chrome_profile_order(dir_name));
+
+    dirs.into_iter()
+        .map(|(dir_name, bookmarks_path)| {
+            let name = bookmarks_path
+                .parent()
+                .and_then(|dir| chrome_profile_name(&dir.join("Preferences")))
+                .filter(|name| *name != dir_name);
+            let profile_name = match name {
+                Some(name) => format!("{}{} [{}]", label, name, dir_name),
+                None => format!("{}{}", label, dir_name),
+            };
+            BrowserProfile {
+                browser: browser.clone(),
+                profile_name,
                 path: bookmarks_path,
-            });
-        }
-    }
-
-    // Also check for Chromium on Linux
-    #[cfg(target_os = "linux")]
-    {
-        let chromium_base = format!("{}/.config/chromium", home);
-        let chromium_path = PathBuf::from(&chromium_base);
-        if chromium_path.exists() {
-            for profile_name in &profile_names {
-                let bookmarks_path = chromium_path.join(profile_name).join("Bookmarks");
-                if bookmarks_path.exists() {
-                    profiles.push(BrowserProfile {
-                        browser: BrowserType::Chrome,
-                        profile_name: format!("Chromium {}", profile_name),
-                        path: bookmarks_path,
-                    });
-                }
             }
-        }
+        })
+        .collect()
+}
+
+/// Sort key putting `Default` first, then `Profile <n>` in numeric order
+fn chrome_profile_order(dir_name: &str) -> (u8, u64, String) {
+    match dir_name.strip_prefix("Profile ").map(str::parse::<u64>) {
+        _ if dir_name == "Default" => (0, 0, String::new()),
+        Some(Ok(n)) => (1, n, String::new()),
+        _ => (2, 0, dir_name.to_string()),
     }
+}
 
-    profiles
+/// Profile name shown in Chrome (`profile.name` in its `Preferences` file)
+fn chrome_profile_name(preferences: &Path) -> Option<String> {
+    let contents = fs::read(preferences).ok()?;
+    let prefs: serde_json::Value = serde_json::from_slice(&contents).ok()?;
+    let name = prefs.get("profile")?.get("name")?.as_str()?.trim();
+    (!name.is_empty()).then(|| name.to_string())
 }
 
 /// Detect all Firefox profile locations
 fn detect_all_firefox_profiles() -> Vec<BrowserProfile> {
-    let mut profiles = Vec::new();
     let home = match std::env::var("HOME") {
         Ok(h) => h,
-        Err(_) => return profiles,
+        Err(_) => return Vec::new(),
     };
 
     #[cfg(target_os = "macos")]
-    let firefox_base = format!("{}/Library/Application Support/Firefox/Profiles", home);
+    let bases = [(
+        format!("{}/Library/Application Support/Firefox/Profiles", home),
+        "",
+    )];
 
     #[cfg(target_os = "linux")]
-    let firefox_base = format!("{}/.mozilla/firefox", home);
+    let bases = [
+        (format!("{}/.mozilla/firefox", home), ""),
+        (
+            format!("{}/.var/app/org.mozilla.firefox/.mozilla/firefox", home),
+            "Flatpak ",
+        ),
+        (
+            format!("{}/snap/firefox/common/.mozilla/firefox", home),
+            "Snap ",
+        ),
+    ];
 
     #[cfg(target_os = "windows")]
-    let firefox_base = format!("{}\\AppData\\Roaming\\Mozilla\\Firefox\\Profiles", home);
+    let bases = [(
+        format!("{}\\AppData\\Roaming\\Mozilla\\Firefox\\Profiles", home),
+        "",
+    )];
 
-    let base_path = PathBuf::from(firefox_base);
-    if !base_path.exists() {
-        return profiles;
-    }
-
-    // Find all profile directories with places.sqlite
-    if let Ok(entries) = fs::read_dir(&base_path) {
+    let mut profiles = Vec::new();
+    for (base, label) in &bases {
+        // Find all profile directories with places.sqlite
+        let Ok(entries) = fs::read_dir(base) else {
+            continue;
+        };
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_dir() {
@@ -184,11 +298,10 @@ fn detect_all_firefox_profiles() -> Vec<BrowserProfile> {
                     let profile_name = path
                         .file_name()
                         .and_then(|n| n.to_str())
-                        .unwrap_or("Unknown")
-                        .to_string();
+                        .unwrap_or("Unknown");
                     profiles.push(BrowserProfile {
                         browser: BrowserType::Firefox,
-                        profile_name,
+                        profile_name: format!("{}{}", label, profile_name),
                         path: places,
                     });
                 }
@@ -201,10 +314,9 @@ fn detect_all_firefox_profiles() -> Vec<BrowserProfile> {
 
 /// Detect all Edge profile locations (uses Chrome format)
 fn detect_all_edge_profiles() -> Vec<BrowserProfile> {
-    let mut profiles = Vec::new();
     let home = match std::env::var("HOME") {
         Ok(h) => h,
-        Err(_) => return profiles,
+        Err(_) => return Vec::new(),
     };
 
     #[cfg(target_os = "macos")]
@@ -216,239 +328,350 @@ fn detect_all_edge_profiles() -> Vec<BrowserProfile> {
     #[cfg(target_os = "windows")]
     let edge_base = format!("{}\\AppData\\Local\\Microsoft\\Edge\\User Data", home);
 
-    let base_path = PathBuf::from(&edge_base);
-    if !base_path.exists() {
-        return profiles;
-    }
-
-    // Common profile directories to check
-    let profile_names = vec![
-        "Default",
-        "Profile 1",
-        "Profile 2",
-        "Profile 3",
-        "Profile 4",
-    ];
-
-    for profile_name in profile_names {
-        let bookmarks_path = base_path.join(profile_name).join("Bookmarks");
-        if bookmarks_path.exists() {
-            profiles.push(BrowserProfile {
-                browser: BrowserType::Edge,
-                profile_name: profile_name.to_string(),
-                path: bookmarks_path,
-            });
-        }
-    }
-
-    profiles
+    chrome_family_profiles(BrowserType::Edge, Path::new(&edge_base), "")
 }
 
 /// Chrome JSON bookmark importer
 pub struct ChromeImporter;
 
 impl super::import::BookmarkImporter for ChromeImporter {
-    fn import(&self, db: &BukuDb, path: &Path) -> crate::error::Result<usize> {
-        import_chrome_with_progress(db, path, |_url| {})
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
+        let bookmarks = parse_chrome_bookmarks(path)?;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
     }
 }
 
-fn import_chrome_with_progress<F>(
-    db: &BukuDb,
-    path: &Path,
-    mut progress_callback: F,
-) -> crate::error::Result<usize>
-where
-    F: FnMut(&str),
-{
+/// Parse a Chrome/Edge `Bookmarks` JSON file without touching the database
+pub fn parse_chrome_bookmarks(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
     let mut json_content = fs::read(path)?;
     let chrome_data: ChromeBookmarkFile = simd_json::serde::from_slice(&mut json_content)?;
 
-    let mut imported_count = 0;
+    let mut bookmarks = Vec::new();
 
-    // Import from bookmark bar
-    imported_count += import_chrome_folder_with_progress(
-        db,
+    // Bookmark bar, other bookmarks, and synced (if exists)
+    collect_chrome_folder(
         &chrome_data.roots.bookmark_bar,
         "bookmark_bar",
-        &mut progress_callback,
-    )?;
-
-    // Import from other bookmarks
-    imported_count += import_chrome_folder_with_progress(
-        db,
-        &chrome_data.roots.other,
-        "other",
-        &mut progress_callback,
-    )?;
-
-    // Import from synced (if exists)
+        &mut bookmarks,
+    );
+    collect_chrome_folder(&chrome_data.roots.other, "other", &mut bookmarks);
     if let Some(ref synced) = chrome_data.roots.synced {
-        imported_count +=
-            import_chrome_folder_with_progress(db, synced, "synced", &mut progress_callback)?;
+        collect_chrome_folder(synced, "synced", &mut bookmarks);
     }
 
-    Ok(imported_count)
+    Ok(bookmarks)
 }
 
-fn import_chrome_folder_with_progress<F>(
-    db: &BukuDb,
+fn collect_chrome_folder(
     folder: &ChromeBookmark,
     parent_tags: &str,
-    progress_callback: &mut F,
-) -> crate::error::Result<usize>
-where
-    F: FnMut(&str),
-{
-    let mut count = 0;
-
+    bookmarks: &mut Vec<ParsedBookmark>,
+) {
     if let Some(ref children) = folder.children {
         for child in children {
             match child.node_type.as_str() {
                 "url" => {
                     if let (Some(ref url), Some(ref name)) = (&child.url, &child.name) {
-                        progress_callback(url);
-                        let tags = format!(",{},", parent_tags);
-                        match db.add_rec(url, name, &tags, "", None) {
-                            Ok(_) => count += 1,
-                            Err(rusqlite::Error::SqliteFailure(err, _))
-                                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
-                            {
-                                // Skip duplicates
-                                continue;
-                            }
-                            Err(e) => return Err(e.into()),
-                        }
+                        bookmarks.push(ParsedBookmark {
+                            url: url.clone(),
+                            title: name.clone(),
+                            tags: format!(",{},", parent_tags),
+                            desc: String::new(),
+                            parent_id: None,
+                            created_at: child.date_added.as_deref().and_then(chrome_time),
+                            flags: 0,
+                        });
                     }
                 }
                 "folder" => {
                     if let Some(ref name) = child.name {
                         let new_tags = format!("{},{}", parent_tags, name);
-                        count += import_chrome_folder_with_progress(
-                            db,
-                            child,
-                            &new_tags,
-                            progress_callback,
-                        )?;
+                        collect_chrome_folder(child, &new_tags, bookmarks);
                     }
                 }
                 _ => {}
             }
         }
     }
+}
+
+/// Seconds between the WebKit epoch (1601-01-01) and the Unix epoch
+const WEBKIT_EPOCH_OFFSET: i64 = 11_644_473_600;
 
-    Ok(count)
+/// Unix seconds for a Chrome `date_added`; None for missing or zero dates
+fn chrome_time(date_added: &str) -> Option<i64> {
+    let micros: i64 = date_added.trim().parse().ok()?;
+    (micros > 0).then(|| micros / 1_000_000 - WEBKIT_EPOCH_OFFSET)
+}
+
+/// Unix seconds for a Firefox `dateAdded` (microseconds since the Unix epoch)
+fn firefox_time(date_added: Option<i64>) -> Option<i64> {
+    date_added
+        .filter(|&micros| micros > 0)
+        .map(|micros| micros / 1_000_000)
 }
 
 /// Firefox SQLite bookmark importer
 pub struct FirefoxImporter;
 
 impl super::import::BookmarkImporter for FirefoxImporter {
-    fn import(&self, db: &BukuDb, path: &Path) -> crate::error::Result<usize> {
-        import_firefox_with_progress(db, path, |_url| {})
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
+        let bookmarks = parse_firefox_bookmarks(path)?;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
     }
 }
 
-fn import_firefox_with_progress<F>(
-    db: &BukuDb,
-    path: &Path,
-    mut progress_callback: F,
-) -> crate::error::Result<usize>
-where
-    F: FnMut(&str),
-{
+/// GUIDs of Firefox's built-in folders and the tags bookmarks in them get
+const FIREFOX_ROOTS: &[(&str, &str)] = &[
+    ("menu________", "menu"),
+    ("toolbar_____", "toolbar"),
+    ("unfiled_____", "unfiled"),
+    ("mobile______", "mobile"),
+];
+const FIREFOX_ROOT_GUID: &str = "root________";
+/// Firefox stores tags as folders under this root, each holding a copy of the
+/// tagged bookmarks
+const FIREFOX_TAGS_GUID: &str = "tags________";
+
+struct FirefoxFolder {
+    parent: Option<i64>,
+    title: String,
+    guid: String,
+}
+
+/// Read bookmarks from a Firefox `places.sqlite` database without touching our database
+///
+/// Bookmarks are tagged `firefox`, with the folders they're in and their Firefox
+/// tags; a keyword is kept in the description.
+pub fn parse_firefox_bookmarks(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
     let conn = rusqlite::Connection::open(path)?;
 
+    let mut folders = HashMap::new();
+    {
+        let mut stmt =
+            conn.prepare("SELECT id, parent, title, guid FROM moz_bookmarks WHERE type = 2")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                FirefoxFolder {
+                    parent: row.get(1)?,
+                    title: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    guid: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                },
+            ))
+        })?;
+        for row in rows {
+            let (id, folder) = row?;
+            folders.insert(id, folder);
+        }
+    }
+    let tags_root = folders
+        .iter()
+        .find(|(_, f)| f.guid == FIREFOX_TAGS_GUID)
+        .map(|(id, _)| *id);
+    let keywords = firefox_keywords(&conn);
+
     let mut stmt = conn.prepare(
-        "SELECT moz_places.url, moz_bookmarks.title
+        "SELECT moz_bookmarks.parent, moz_bookmarks.title, moz_places.id, moz_places.url,
+                moz_bookmarks.dateAdded
          FROM moz_bookmarks
          JOIN moz_places ON moz_bookmarks.fk = moz_places.id
-         WHERE moz_bookmarks.type = 1 AND moz_places.url IS NOT NULL",
+         WHERE moz_bookmarks.type = 1 AND moz_places.url IS NOT NULL
+         ORDER BY moz_bookmarks.id",
     )?;
-
-    let bookmarks = stmt.query_map([], |row| {
-        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<i64>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+        ))
     })?;
 
-    let mut count = 0;
-    for bookmark_result in bookmarks {
-        let (url, title_opt) = bookmark_result?;
-        let title = title_opt.as_deref().unwrap_or(&url);
-
-        progress_callback(&url);
+    // Entries inside a tag folder only mark their place as tagged
+    let mut place_tags: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut entries = Vec::new();
+    for row in rows {
+        let (parent, title, place_id, url, date_added) = row?;
+        let tag_folder = parent
+            .and_then(|p| folders.get(&p))
+            .filter(|f| tags_root.is_some() && f.parent == tags_root);
+        match tag_folder {
+            Some(folder) => place_tags
+                .entry(place_id)
+                .or_default()
+                .push(folder.title.clone()),
+            None => entries.push((parent, title, place_id, url, date_added)),
+        }
+    }
 
-        match db.add_rec(&url, title, ",firefox,", "", None) {
-            Ok(_) => count += 1,
-            Err(rusqlite::Error::SqliteFailure(err, _))
-                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
-            {
-                // Skip duplicates
-                continue;
+    let mut bookmarks = Vec::with_capacity(entries.len());
+    for (parent, title, place_id, url, date_added) in entries {
+        let mut tags = vec!["firefox".to_string()];
+        let folder_tags = parent.map(|p| firefox_folder_path(&folders, p));
+        let extra = folder_tags
+            .into_iter()
+            .flatten()
+            .chain(place_tags.get(&place_id).into_iter().flatten().cloned());
+        for tag in extra.flat_map(parse_tags) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
             }
-            Err(e) => return Err(e.into()),
         }
+
+        bookmarks.push(ParsedBookmark {
+            title: title
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| url.clone()),
+            url,
+            tags: format_tags(&tags),
+            desc: keywords
+                .get(&place_id)
+                .map(|k| format!("Keyword: {}", k))
+                .unwrap_or_default(),
+            parent_id: None,
+            created_at: firefox_time(date_added),
+            flags: 0,
+        });
     }
 
-    Ok(count)
+    Ok(bookmarks)
+}
+
+/// Folder names from the top-level folder down to `id`
+fn firefox_folder_path(folders: &HashMap<i64, FirefoxFolder>, id: i64) -> Vec<String> {
+    let mut path = Vec::new();
+    let mut current = Some(id);
+    // The depth limit guards against a corrupt parent cycle
+    for _ in 0..=folders.len() {
+        let Some(folder) = current.and_then(|id| folders.get(&id)) else {
+            break;
+        };
+        if folder.guid == FIREFOX_ROOT_GUID {
+            break;
+        }
+        match FIREFOX_ROOTS.iter().find(|(guid, _)| *guid == folder.guid) {
+            Some((_, name)) => path.push(name.to_string()),
+            None if !folder.title.is_empty() => path.push(folder.title.clone()),
+            None => {}
+        }
+        current = folder.parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Keywords by place id; empty for profiles older than `moz_keywords.place_id`
+fn firefox_keywords(conn: &rusqlite::Connection) -> HashMap<i64, String> {
+    let read = || -> rusqlite::Result<HashMap<i64, String>> {
+        let mut stmt =
+            conn.prepare("SELECT place_id, keyword FROM moz_keywords WHERE place_id IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    };
+    read().unwrap_or_else(|e| {
+        log::debug!("Not importing Firefox keywords: {}", e);
+        HashMap::new()
+    })
+}
+
+/// Parse all bookmarks of a detected browser profile without touching the database
+pub fn parse_browser_profile(
+    profile: &BrowserProfile,
+) -> crate::error::Result<Vec<ParsedBookmark>> {
+    match profile.browser {
+        BrowserType::Chrome | BrowserType::Edge => parse_chrome_bookmarks(&profile.path),
+        BrowserType::Firefox => parse_firefox_bookmarks(&profile.path),
+        // Safari uses plist format - not implemented yet
+        BrowserType::Safari => Ok(Vec::new()),
+    }
 }
 
 /// Import bookmarks directly from Chrome JSON file
-pub fn import_from_chrome(db: &BukuDb, bookmarks_path: &Path) -> crate::error::Result<usize> {
+pub fn import_from_chrome(
+    db: &BukuDb,
+    bookmarks_path: &Path,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
     let importer = ChromeImporter;
-    importer.import(db, bookmarks_path)
+    importer.import(db, bookmarks_path, on_duplicate)
 }
 
 /// Import bookmarks directly from Firefox SQLite database
-pub fn import_from_firefox(db: &BukuDb, places_path: &Path) -> crate::error::Result<usize> {
+pub fn import_from_firefox(
+    db: &BukuDb,
+    places_path: &Path,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
     let importer = FirefoxImporter;
-    importer.import(db, places_path)
+    importer.import(db, places_path, on_duplicate)
 }
 
 /// Auto-import from all detected browsers
-pub fn auto_import_all(db: &BukuDb) -> crate::error::Result<usize> {
-    auto_import_all_with_progress(db, |_profile, _current, _total, _url| {})
+pub fn auto_import_all(
+    db: &BukuDb,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
+    auto_import_all_with_progress(db, on_duplicate, &NoProgress)
 }
 
-/// Auto-import from all detected browsers with progress callback
-/// The progress_callback receives: (profile, current_profile_idx, total_profiles, current_url)
-pub fn auto_import_all_with_progress<F>(
+/// Auto-import from all detected browsers, reporting progress per profile
+pub fn auto_import_all_with_progress(
     db: &BukuDb,
-    mut progress_callback: F,
-) -> crate::error::Result<usize>
-where
-    F: FnMut(&BrowserProfile, usize, usize, Option<&str>),
-{
+    on_duplicate: DuplicateStrategy,
+    progress: &dyn ProgressSink,
+) -> crate::error::Result<ImportStats> {
     let profiles = detect_browsers();
-    let mut total_count = 0;
+    import_profiles_with_progress(db, &profiles, on_duplicate, progress)
+}
+
+/// Import each profile as its own undoable batch
+/// Each profile is a progress phase labelled `[n/total] <profile>`
+pub fn import_profiles_with_progress(
+    db: &BukuDb,
+    profiles: &[BrowserProfile],
+    on_duplicate: DuplicateStrategy,
+    progress: &dyn ProgressSink,
+) -> crate::error::Result<ImportStats> {
+    let mut total = ImportStats::default();
     let total_profiles = profiles.len();
 
     for (idx, profile) in profiles.iter().enumerate() {
-        progress_callback(profile, idx, total_profiles, None);
-
-        let count = match profile.browser {
-            BrowserType::Chrome | BrowserType::Edge => {
-                import_chrome_with_progress(db, &profile.path, |url| {
-                    progress_callback(profile, idx, total_profiles, Some(url));
-                })?
-            }
-            BrowserType::Firefox => import_firefox_with_progress(db, &profile.path, |url| {
-                progress_callback(profile, idx, total_profiles, Some(url));
-            })?,
-            BrowserType::Safari => {
-                // Safari uses plist format - not implemented yet
-                0
-            }
-        };
+        let bookmarks = parse_browser_profile(profile)?;
+        progress.start(
+            &format!(
+                "[{}/{}] {}",
+                idx + 1,
+                total_profiles,
+                profile.display_string()
+            ),
+            Some(bookmarks.len() as u64),
+        );
+        let stats = db.import_rec_batch(&bookmarks, on_duplicate, |url| {
+            progress.advance(1, Some(url));
+        })?;
+        progress.finish();
 
         eprintln!(
             "✓ Imported {} bookmarks from {}",
-            count,
+            stats.added,
             profile.display_string()
         );
-        total_count += count;
+        total += stats;
     }
 
-    Ok(total_count)
+    Ok(total)
 }
 
 /// List all detected browser profiles
@@ -456,28 +679,10 @@ pub fn list_detected_browsers() -> Vec<BrowserProfile> {
     detect_browsers()
 }
 
-/// Import bookmarks from selected browsers
-pub fn import_from_selected_browsers(
-    db: &BukuDb,
-    browser_names: &[String],
-) -> crate::error::Result<usize> {
-    import_from_selected_browsers_with_progress(
-        db,
-        browser_names,
-        |_profile, _current, _total, _url| {},
-    )
-}
-
-/// Import bookmarks from selected browsers with progress callback
-/// The progress_callback receives: (profile, current_profile_idx, total_profiles, current_url)
-pub fn import_from_selected_browsers_with_progress<F>(
-    db: &BukuDb,
+/// Detected profiles belonging to the named browsers
+pub(crate) fn select_profiles(
     browser_names: &[String],
-    mut progress_callback: F,
-) -> crate::error::Result<usize>
-where
-    F: FnMut(&BrowserProfile, usize, usize, Option<&str>),
-{
+) -> crate::error::Result<Vec<BrowserProfile>> {
     let all_profiles = detect_browsers();
 
     // Parse browser names
@@ -500,41 +705,62 @@ where
         return Err("No matching browser profiles found".into());
     }
 
-    let mut total_count = 0;
-    let total_profiles = selected_profiles.len();
-
-    for (idx, profile) in selected_profiles.iter().enumerate() {
-        progress_callback(profile, idx, total_profiles, None);
+    Ok(selected_profiles)
+}
 
-        let count = match profile.browser {
-            BrowserType::Chrome | BrowserType::Edge => {
-                import_chrome_with_progress(db, &profile.path, |url| {
-                    progress_callback(profile, idx, total_profiles, Some(url));
-                })?
-            }
-            BrowserType::Firefox => import_firefox_with_progress(db, &profile.path, |url| {
-                progress_callback(profile, idx, total_profiles, Some(url));
-            })?,
-            BrowserType::Safari => {
-                // Safari uses plist format - not implemented yet
-                0
-            }
-        };
+/// Preview importing from browsers without touching the database
+/// `None` previews all detected browsers, otherwise only the named ones
+pub fn preview_browsers(
+    db: &BukuDb,
+    browser_names: Option<&[String]>,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportPreview> {
+    let profiles = match browser_names {
+        Some(names) => select_profiles(names)?,
+        None => detect_browsers(),
+    };
+    preview_profiles(db, &profiles, on_duplicate)
+}
 
-        eprintln!(
-            "✓ Imported {} bookmarks from {}",
-            count,
-            profile.display_string()
-        );
-        total_count += count;
+/// Preview importing the given profiles without touching the database
+pub fn preview_profiles(
+    db: &BukuDb,
+    profiles: &[BrowserProfile],
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportPreview> {
+    // Parse every profile first so duplicates across profiles are detected too
+    let mut bookmarks = Vec::new();
+    for profile in profiles {
+        bookmarks.extend(parse_browser_profile(profile)?);
     }
 
-    Ok(total_count)
+    preview_import(db, bookmarks, on_duplicate)
+}
+
+/// Import bookmarks from selected browsers
+pub fn import_from_selected_browsers(
+    db: &BukuDb,
+    browser_names: &[String],
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
+    import_from_selected_browsers_with_progress(db, browser_names, on_duplicate, &NoProgress)
+}
+
+/// Import bookmarks from selected browsers, reporting progress per profile
+pub fn import_from_selected_browsers_with_progress(
+    db: &BukuDb,
+    browser_names: &[String],
+    on_duplicate: DuplicateStrategy,
+    progress: &dyn ProgressSink,
+) -> crate::error::Result<ImportStats> {
+    let selected_profiles = select_profiles(browser_names)?;
+    import_profiles_with_progress(db, &selected_profiles, on_duplicate, progress)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn test_detect_browsers() {
@@ -581,6 +807,193 @@ mod tests {
         assert_eq!(BrowserType::Safari.display_name(), "Safari");
     }
 
+    /// A `places.sqlite` with the parts of Firefox's schema the importer reads
+    fn firefox_places(path: &Path) {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT);
+             CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                 parent INTEGER, title TEXT, guid TEXT, dateAdded INTEGER);
+             CREATE TABLE moz_keywords (id INTEGER PRIMARY KEY, keyword TEXT, place_id INTEGER);
+             INSERT INTO moz_places VALUES
+                 (1, 'https://www.rust-lang.org/'), (2, 'https://github.com/'), (3, 'https://example.com/');
+             INSERT INTO moz_bookmarks (id, type, fk, parent, title, guid) VALUES
+                 (1, 2, NULL, 0, '', 'root________'),
+                 (2, 2, NULL, 1, 'menu', 'menu________'),
+                 (3, 2, NULL, 1, 'toolbar', 'toolbar_____'),
+                 (4, 2, NULL, 1, 'tags', 'tags________'),
+                 (5, 2, NULL, 3, 'Dev', 'folder000001'),
+                 (6, 2, NULL, 5, 'Lang', 'folder000002'),
+                 (7, 1, 1, 6, 'Rust', 'bookmark0001'),
+                 (8, 1, 2, 3, 'GitHub', 'bookmark0002'),
+                 (9, 1, 3, 2, NULL, 'bookmark0003'),
+                 (10, 2, NULL, 4, 'code', 'tagfolder001'),
+                 (11, 1, 1, 10, NULL, 'tagentry0001'),
+                 (12, 1, 2, 10, NULL, 'tagentry0002'),
+                 (13, 3, NULL, 3, NULL, 'separator001');
+             UPDATE moz_bookmarks SET dateAdded = 1600000000123456 WHERE id = 7;
+             INSERT INTO moz_keywords VALUES (1, 'gh', 2);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_firefox_import_folders_tags_keywords() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_places(&places);
+
+        let bookmarks = parse_firefox_bookmarks(&places).unwrap();
+        let summary: Vec<(&str, &str, &str, &str)> = bookmarks
+            .iter()
+            .map(|b| {
+                (
+                    b.url.as_str(),
+                    b.title.as_str(),
+                    b.tags.as_str(),
+                    b.desc.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "https://www.rust-lang.org/",
+                    "Rust",
+                    ",firefox,toolbar,Dev,Lang,code,",
+                    ""
+                ),
+                (
+                    "https://github.com/",
+                    "GitHub",
+                    ",firefox,toolbar,code,",
+                    "Keyword: gh"
+                ),
+                (
+                    "https://example.com/",
+                    "https://example.com/",
+                    ",firefox,menu,",
+                    ""
+                ),
+            ]
+        );
+
+        // dateAdded is kept; bookmarks without one get the import time
+        let created: Vec<Option<i64>> = bookmarks.iter().map(|b| b.created_at).collect();
+        assert_eq!(created, vec![Some(1_600_000_000), None, None]);
+    }
+
+    #[test]
+    fn test_firefox_import_without_keywords_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_places(&places);
+        rusqlite::Connection::open(&places)
+            .unwrap()
+            .execute_batch("DROP TABLE moz_keywords")
+            .unwrap();
+
+        let bookmarks = parse_firefox_bookmarks(&places).unwrap();
+        assert_eq!(bookmarks.len(), 3);
+        assert!(bookmarks.iter().all(|b| b.desc.is_empty()));
+    }
+
+    #[test]
+    fn test_chrome_family_profiles_enumerates_and_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for (profile, prefs) in [
+            ("Profile 12", Some(r#"{"profile": {"name": "Work"}}"#)),
+            ("Default", Some(r#"{"profile": {"name": "Person 1"}}"#)),
+            ("Profile 2", None),
+            ("Profile 5", Some("not json")),
+            ("System Profile", None),
+        ] {
+            let profile_dir = dir.path().join(profile);
+            fs::create_dir(&profile_dir).unwrap();
+            fs::write(profile_dir.join("Bookmarks"), "{}").unwrap();
+            if let Some(prefs) = prefs {
+                fs::write(profile_dir.join("Preferences"), prefs).unwrap();
+            }
+        }
+        // No bookmarks file, not a profile to import
+        fs::create_dir(dir.path().join("Profile 3")).unwrap();
+
+        let profiles = chrome_family_profiles(BrowserType::Chrome, dir.path(), "Flatpak ");
+        let names: Vec<&str> = profiles.iter().map(|p| p.profile_name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Flatpak Person 1 [Default]",
+                "Flatpak Profile 2",
+                "Flatpak Profile 5",
+                "Flatpak Work [Profile 12]",
+            ]
+        );
+        assert_eq!(
+            profiles[3].path,
+            dir.path().join("Profile 12").join("Bookmarks")
+        );
+        assert_eq!(
+            profiles[0].display_string(),
+            "Chrome (Flatpak Person 1 [Default])"
+        );
+
+        assert!(chrome_family_profiles(BrowserType::Edge, &dir.path().join("none"), "").is_empty());
+    }
+
+    #[test]
+    fn test_profile_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_places(&places);
+        let chrome = dir.path().join("bookmarks-backup.json");
+        fs::write(&chrome, r#"{"roots": {}}"#).unwrap();
+
+        // Format detected from the file contents, not its name
+        let profile = BrowserProfile::from_path(&places, None).unwrap();
+        assert_eq!(profile.browser, BrowserType::Firefox);
+        assert_eq!(profile.path, places);
+        let profile = BrowserProfile::from_path(&chrome, None).unwrap();
+        assert_eq!(profile.browser, BrowserType::Chrome);
+
+        // A profile directory resolves to its bookmarks file
+        let profile = BrowserProfile::from_path(dir.path(), None).unwrap();
+        assert_eq!(profile.path, places);
+        assert_eq!(profile.browser, BrowserType::Firefox);
+
+        let profile = BrowserProfile::from_path(&chrome, Some(BrowserType::Edge)).unwrap();
+        assert_eq!(profile.browser, BrowserType::Edge);
+
+        assert!(BrowserProfile::from_path(&places, Some(BrowserType::Safari)).is_err());
+        assert!(BrowserProfile::from_path(&dir.path().join("missing"), None).is_err());
+        let empty = tempfile::tempdir().unwrap();
+        assert!(BrowserProfile::from_path(empty.path(), None).is_err());
+    }
+
+    #[test]
+    fn test_import_profile_from_copied_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places-copy.sqlite");
+        firefox_places(&places);
+        let db = BukuDb::init_in_memory().unwrap();
+
+        let profile = BrowserProfile::from_path(&places, None).unwrap();
+        let preview =
+            preview_profiles(&db, std::slice::from_ref(&profile), DuplicateStrategy::Skip).unwrap();
+        assert_eq!(preview.added, 3);
+        assert!(db.get_rec_all().unwrap().is_empty());
+
+        let stats = import_profiles_with_progress(
+            &db,
+            &[profile],
+            DuplicateStrategy::Skip,
+            &crate::progress::NoProgress,
+        )
+        .unwrap();
+        assert_eq!(stats.added, 3);
+    }
+
     #[test]
     fn test_chrome_import_parsing() {
         use crate::db::BukuDb;
@@ -608,7 +1021,7 @@ mod tests {
                         {
                             "children": [
                                 {
-                                    "date_added": "13245678900000000",
+                                    "date_added": "13231651200000000",
                                     "id": "3",
                                     "name": "Rust",
                                     "type": "url",
@@ -651,8 +1064,8 @@ mod tests {
         write!(bookmark_file, "{}", json_content).unwrap();
 
         // Test import
-        let count = import_from_chrome(&db, bookmark_file.path()).unwrap();
-        assert_eq!(count, 2);
+        let stats = import_from_chrome(&db, bookmark_file.path(), DuplicateStrategy::Skip).unwrap();
+        assert_eq!(stats.added, 2);
 
         // Verify bookmarks in DB
         let bookmarks = db.search(&[], false, false, false).unwrap();
@@ -671,5 +1084,29 @@ mod tests {
             .unwrap();
         assert_eq!(rust.title, "Rust");
         assert!(rust.tags.contains(",bookmark_bar,Dev,"));
+
+        // date_added (WebKit epoch) becomes the bookmark's creation time
+        let created: Vec<(String, i64)> = db
+            .get_rec_recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|(b, created_at)| (b.url, created_at))
+            .collect();
+        assert_eq!(
+            created,
+            vec![
+                ("https://www.google.com/".to_string(), 1_601_205_300),
+                ("https://www.rust-lang.org/".to_string(), 1_587_177_600),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("13245678900000000", Some(1_601_205_300))]
+    #[case("11644473600000000", Some(0))]
+    #[case("0", None)]
+    #[case("", None)]
+    fn test_chrome_time(#[case] date_added: &str, #[case] expected: Option<i64>) {
+        assert_eq!(chrome_time(date_added), expected);
     }
 }
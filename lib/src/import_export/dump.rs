@@ -0,0 +1,357 @@
+//! Full database dumps (`dump` / `restore`)
+//!
+//! A dump is a JSON document describing itself: the dump format version, the
+//! schema version and bukurs version that wrote it, every bookmark with all
+//! of its fields, the tag list, and optionally the undo history and a config
+//! snapshot. Fields are named rather than tied to the SQLite layout, so a
+//! dump restores into a database of any later schema, and fields a newer
+//! bukurs adds are ignored by older ones.
+//!
+//! Encrypted bookmarks are dumped as they are stored, so restoring them needs
+//! the same passphrase.
+
+use crate::config::Config;
+use crate::db::{BukuDb, SCHEMA_VERSION};
+use crate::error::{BukursError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Value of [`Dump::format`], telling dumps apart from other JSON files
+pub const DUMP_FORMAT: &str = "bukurs-dump";
+
+/// Version of the dump layout; bumped only for changes older readers can't ignore
+pub const DUMP_VERSION: u32 = 1;
+
+/// A bookmark with every stored field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpedBookmark {
+    pub id: usize,
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default = "empty_tags")]
+    pub tags: String,
+    #[serde(default)]
+    pub desc: String,
+    #[serde(default)]
+    pub parent_id: Option<usize>,
+    #[serde(default)]
+    pub flags: i32,
+    /// Unix seconds; 0 when unknown
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub position: Option<i64>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub visits: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Stars, 1 to 5; 0 when unrated
+    #[serde(default)]
+    pub rating: u8,
+    /// Words in the page's main text; 0 when not measured
+    #[serde(default)]
+    pub word_count: usize,
+    /// Language code of the page, e.g. `en`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Unix seconds of the last visit through bukurs; None when never opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visited_at: Option<i64>,
+}
+
+fn empty_tags() -> String {
+    ",".to_string()
+}
+
+/// One undo log entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub timestamp: i64,
+    pub operation: String,
+    pub bookmark_id: usize,
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub desc: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<usize>,
+    #[serde(default)]
+    pub flags: Option<i32>,
+}
+
+/// A whole database, as written by `bukurs dump`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dump {
+    pub format: String,
+    pub format_version: u32,
+    /// Schema version of the database the dump was taken from
+    pub schema_version: u32,
+    pub bukurs_version: String,
+    /// When the dump was taken (Unix seconds)
+    pub created_at: i64,
+    pub bookmarks: Vec<DumpedBookmark>,
+    /// Every tag in use, for reading the dump; restoring uses the bookmarks' tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_log: Option<Vec<UndoEntry>>,
+    /// The config file in effect when the dump was taken, as YAML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+}
+
+/// What to include in a dump
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DumpOptions {
+    pub undo_log: bool,
+}
+
+/// How to restore a dump
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreOptions {
+    /// Delete the existing bookmarks and undo history first; without this,
+    /// restoring into a database with bookmarks fails
+    pub replace: bool,
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RestoreStats {
+    pub bookmarks: usize,
+    pub undo_entries: usize,
+}
+
+impl Dump {
+    /// Take a dump of `db`, with a snapshot of `config` when given
+    ///
+    /// The snapshot leaves out API tokens and keys, so sharing a dump never
+    /// shares credentials; they are set again after restoring the config.
+    pub fn create(db: &BukuDb, options: DumpOptions, config: Option<&Config>) -> Result<Self> {
+        let config = config
+            .map(Config::to_yaml_without_secrets)
+            .transpose()
+            .map_err(|e| BukursError::InvalidInput(format!("Cannot snapshot config: {}", e)))?;
+        Ok(Self {
+            format: DUMP_FORMAT.to_string(),
+            format_version: DUMP_VERSION,
+            schema_version: db.schema_version()?,
+            bukurs_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+            bookmarks: db.dump_bookmarks()?,
+            tags: db.get_all_tags()?,
+            undo_log: if options.undo_log {
+                Some(db.dump_undo_log()?)
+            } else {
+                None
+            },
+            config,
+        })
+    }
+
+    /// Read a dump, checking that it is one this build understands
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let dump: Self = serde_json::from_str(&contents)
+            .map_err(|e| BukursError::Json(format!("{}: {}", path.display(), e)))?;
+        if dump.format != DUMP_FORMAT {
+            return Err(BukursError::InvalidInput(format!(
+                "{} is not a bukurs dump",
+                path.display()
+            )));
+        }
+        if dump.format_version > DUMP_VERSION {
+            return Err(BukursError::InvalidInput(format!(
+                "{} uses dump format {}, but this bukurs reads up to {}; upgrade bukurs to restore it",
+                path.display(),
+                dump.format_version,
+                DUMP_VERSION
+            )));
+        }
+        Ok(dump)
+    }
+
+    /// Write the dump through a temporary file, so a failed dump never leaves half a file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| BukursError::Json(e.to_string()))?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Whether the dump comes from a newer schema than this build knows; its
+    /// extra fields are dropped on restore
+    pub fn is_from_newer_schema(&self) -> bool {
+        self.schema_version > SCHEMA_VERSION
+    }
+
+    /// Restore the dump into `db`, keeping bookmark IDs, as one transaction
+    pub fn restore(&self, db: &BukuDb, options: RestoreOptions) -> Result<RestoreStats> {
+        if !options.replace && !db.get_rec_all()?.is_empty() {
+            return Err(BukursError::InvalidInput(
+                "The database already has bookmarks; restore into an empty one or use --replace"
+                    .to_string(),
+            ));
+        }
+        db.restore_dump(&self.bookmarks, self.undo_log.as_deref())?;
+        Ok(RestoreStats {
+            bookmarks: self.bookmarks.len(),
+            undo_entries: self.undo_log.as_ref().map_or(0, Vec::len),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust-lang.org", "Rust", ",lang,", "Notes", None)
+            .unwrap();
+        db.add_rec("https://example.com", "Example", ",", "", None)
+            .unwrap();
+        db.add_rec("https://github.com", "GitHub", ",code,", "", None)
+            .unwrap();
+        db.delete_rec(2).unwrap();
+        db.set_pinned(&[3], true).unwrap();
+        db.set_expiry(1, Some(2_000_000_000)).unwrap();
+        db.set_alias(3, Some("gh")).unwrap();
+        db.set_rating(&[1], 4).unwrap();
+        db.set_word_counts(&[("https://github.com", 1200)]).unwrap();
+        db.set_languages(&[("https://github.com", "en".to_string())])
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_dump_round_trip_keeps_ids_and_fields() {
+        let db = setup();
+        let dump = Dump::create(&db, DumpOptions { undo_log: true }, None).unwrap();
+        assert_eq!(dump.schema_version, SCHEMA_VERSION);
+        assert_eq!(dump.tags, vec!["code", "lang"]);
+        assert_eq!(dump.bookmarks.len(), 2);
+        assert_eq!(dump.bookmarks[1].alias.as_deref(), Some("gh"));
+        assert_eq!(dump.bookmarks[0].rating, 4);
+        assert_eq!(dump.bookmarks[1].word_count, 1200);
+        assert_eq!(dump.bookmarks[1].lang.as_deref(), Some("en"));
+        assert_eq!(dump.bookmarks[0].lang, None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.bukurs");
+        dump.write(&path).unwrap();
+        let read = Dump::read(&path).unwrap();
+        assert_eq!(read, dump);
+
+        let restored = BukuDb::init_in_memory().unwrap();
+        let stats = read.restore(&restored, RestoreOptions::default()).unwrap();
+        assert_eq!(stats.bookmarks, 2);
+        assert_eq!(restored.dump_bookmarks().unwrap(), dump.bookmarks);
+        assert_eq!(restored.dump_undo_log().unwrap(), dump.undo_log.unwrap());
+
+        // New bookmarks continue after the restored IDs
+        let id = restored
+            .add_rec("https://docs.rs", "", ",", "", None)
+            .unwrap();
+        assert_eq!(id, 4);
+    }
+
+    #[test]
+    fn test_restore_refuses_non_empty_database_unless_replacing() {
+        let dump = Dump::create(&setup(), DumpOptions::default(), None).unwrap();
+        assert!(dump.undo_log.is_none());
+
+        let target = BukuDb::init_in_memory().unwrap();
+        target
+            .add_rec("https://old.example.com", "", ",", "", None)
+            .unwrap();
+        assert!(dump.restore(&target, RestoreOptions::default()).is_err());
+
+        dump.restore(&target, RestoreOptions { replace: true })
+            .unwrap();
+        let urls: Vec<String> = target
+            .get_rec_all()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.url)
+            .collect();
+        assert_eq!(urls, vec!["https://rust-lang.org", "https://github.com"]);
+        // The replaced database's history doesn't apply to the restored bookmarks
+        assert!(target.dump_undo_log().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_older_and_foreign_dumps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.bukurs");
+
+        // Fields added after a dump was written take their defaults
+        fs::write(
+            &path,
+            r#"{"format": "bukurs-dump", "format_version": 1, "schema_version": 5,
+                "bukurs_version": "0.1.0", "created_at": 0,
+                "bookmarks": [{"id": 7, "url": "https://example.com"}]}"#,
+        )
+        .unwrap();
+        let dump = Dump::read(&path).unwrap();
+        assert_eq!(dump.bookmarks[0].tags, ",");
+        assert!(!dump.bookmarks[0].pinned);
+
+        fs::write(
+            &path,
+            r#"{"format": "something-else", "format_version": 1}"#,
+        )
+        .unwrap();
+        assert!(Dump::read(&path).is_err());
+
+        fs::write(
+            &path,
+            r#"{"format": "bukurs-dump", "format_version": 99, "schema_version": 99,
+                "bukurs_version": "9.0.0", "created_at": 0, "bookmarks": []}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            Dump::read(&path),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_config_snapshot_leaves_out_secrets() {
+        let mut config = Config::default();
+        config.fetch.github_token = Some("ghp_secret".to_string());
+        config.fetch.youtube_api_key = Some("yt-secret".to_string());
+        config.fetch.timeout_secs = 5;
+
+        let dump = Dump::create(&setup(), DumpOptions::default(), Some(&config)).unwrap();
+        let json = serde_json::to_string(&dump).unwrap();
+        assert!(!json.contains("ghp_secret"));
+        assert!(!json.contains("yt-secret"));
+
+        // The rest of the snapshot still loads as a config
+        let restored: Config = serde_yaml::from_str(dump.config.as_deref().unwrap()).unwrap();
+        assert_eq!(restored.fetch.timeout_secs, 5);
+        assert_eq!(restored.fetch.github_token, None);
+        assert_eq!(restored.fetch.youtube_api_key, None);
+        // Saving the config itself still keeps them
+        assert!(serde_yaml::to_string(&config)
+            .unwrap()
+            .contains("ghp_secret"));
+    }
+}
@@ -1,7 +1,18 @@
+#[cfg(feature = "full")]
+use super::feed::{export_feed_selection, FeedFormat, DEFAULT_FEED_LIMIT};
+#[cfg(feature = "full")]
 use crate::db::BukuDb;
-use crate::models::bookmark::Bookmark;
+use crate::models::bookmark::{Bookmark, CanonicalBookmark};
+#[cfg(feature = "full")]
+use crate::operations::BookmarkFilter;
+#[cfg(feature = "full")]
+use crate::progress::{NoProgress, ProgressSink};
+use crate::tags::parse_tags;
+use std::collections::HashMap;
+#[cfg(feature = "full")]
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 
 /// Trait for exporting bookmarks to different formats
@@ -9,38 +20,113 @@ pub trait BookmarkExporter {
     fn export(&self, bookmarks: &[Bookmark], path: &Path) -> crate::error::Result<()>;
 }
 
+/// Create `path` and write to it with `write`
+fn export_to_file(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> crate::error::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write(&mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Write `records` as a Netscape bookmark file
+pub fn write_html(records: &[Bookmark], out: &mut dyn Write) -> io::Result<()> {
+    write_html_with_aliases(records, &HashMap::new(), out)
+}
+
+/// Write `records` as a Netscape bookmark file, giving aliased bookmarks a
+/// `SHORTCUTURL` attribute (the browsers' keyword field)
+pub fn write_html_with_aliases(
+    records: &[Bookmark],
+    aliases: &HashMap<usize, String>,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+    writeln!(out, "<!-- This is an automatically generated file.")?;
+    writeln!(out, "     It will be read and overwritten.")?;
+    writeln!(out, "     DO NOT EDIT! -->")?;
+    writeln!(
+        out,
+        "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">"
+    )?;
+    writeln!(out, "<TITLE>Bookmarks</TITLE>")?;
+    writeln!(out, "<H1>Bookmarks</H1>")?;
+    writeln!(out, "<DL><p>")?;
+
+    for bookmark in records {
+        let shortcut = aliases
+            .get(&bookmark.id)
+            .map(|alias| format!(" SHORTCUTURL=\"{}\"", alias))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "    <DT><A HREF=\"{}\" TAGS=\"{}\"{} ADD_DATE=\"0\">{}</A>",
+            bookmark.url, bookmark.tags, shortcut, bookmark.title
+        )?;
+        if !bookmark.description.is_empty() {
+            writeln!(out, "    <DD>{}", bookmark.description)?;
+        }
+    }
+
+    writeln!(out, "</DL><p>")
+}
+
 /// HTML/Netscape Bookmark File exporter
-pub struct HtmlExporter;
+#[derive(Debug, Default)]
+pub struct HtmlExporter {
+    /// Bookmark aliases by id, written as `SHORTCUTURL`
+    pub aliases: HashMap<usize, String>,
+}
 
 impl BookmarkExporter for HtmlExporter {
     fn export(&self, records: &[Bookmark], path: &Path) -> crate::error::Result<()> {
-        let mut file = File::create(path)?;
-        writeln!(file, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
-        writeln!(file, "<!-- This is an automatically generated file.")?;
-        writeln!(file, "     It will be read and overwritten.")?;
-        writeln!(file, "     DO NOT EDIT! -->")?;
-        writeln!(
-            file,
-            "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">"
-        )?;
-        writeln!(file, "<TITLE>Bookmarks</TITLE>")?;
-        writeln!(file, "<H1>Bookmarks</H1>")?;
-        writeln!(file, "<DL><p>")?;
-
-        for bookmark in records {
-            writeln!(
-                file,
-                "    <DT><A HREF=\"{}\" TAGS=\"{}\" ADD_DATE=\"0\">{}</A>",
-                bookmark.url, bookmark.tags, bookmark.title
-            )?;
+        export_to_file(path, |out| {
+            write_html_with_aliases(records, &self.aliases, out)
+        })
+    }
+}
+
+/// Group bookmarks by tag set, untagged first, otherwise in order of first appearance
+/// Markdown and Org exports write each group under a heading listing its tags
+fn group_by_tags(records: &[Bookmark]) -> Vec<(Vec<String>, Vec<&Bookmark>)> {
+    let mut groups: Vec<(Vec<String>, Vec<&Bookmark>)> = vec![(Vec::new(), Vec::new())];
+    for bookmark in records {
+        let tags = parse_tags(&bookmark.tags);
+        match groups.iter_mut().find(|(t, _)| *t == tags) {
+            Some((_, members)) => members.push(bookmark),
+            None => groups.push((tags, vec![bookmark])),
+        }
+    }
+    groups.retain(|(_, members)| !members.is_empty());
+    groups
+}
+
+/// Write `records` as a Markdown link list
+pub fn write_markdown(records: &[Bookmark], out: &mut dyn Write) -> io::Result<()> {
+    for (i, (tags, members)) in group_by_tags(records).into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        if !tags.is_empty() {
+            writeln!(out, "# {}", tags.join(", "))?;
+            writeln!(out)?;
+        }
+        for bookmark in members {
+            let title = bookmark
+                .title
+                .replace('\\', "\\\\")
+                .replace('[', "\\[")
+                .replace(']', "\\]");
+            write!(out, "- [{}]({})", title, bookmark.url)?;
             if !bookmark.description.is_empty() {
-                writeln!(file, "    <DD>{}", bookmark.description)?;
+                write!(out, " - {}", bookmark.description)?;
             }
+            writeln!(out)?;
         }
-
-        writeln!(file, "</DL><p>")?;
-        Ok(())
     }
+    Ok(())
 }
 
 /// Markdown exporter
@@ -48,16 +134,29 @@ pub struct MarkdownExporter;
 
 impl BookmarkExporter for MarkdownExporter {
     fn export(&self, records: &[Bookmark], path: &Path) -> crate::error::Result<()> {
-        let mut file = File::create(path)?;
-        for bookmark in records {
-            writeln!(
-                file,
-                "[{}]({}) <!-- {} -->",
-                bookmark.title, bookmark.url, bookmark.tags
-            )?;
+        export_to_file(path, |out| write_markdown(records, out))
+    }
+}
+
+/// Write `records` as an Org-mode link list
+pub fn write_org(records: &[Bookmark], out: &mut dyn Write) -> io::Result<()> {
+    for (tags, members) in group_by_tags(records) {
+        if !tags.is_empty() {
+            writeln!(out, "* {}", tags.join(", "))?;
+        }
+        for bookmark in members {
+            if bookmark.title.is_empty() {
+                write!(out, "- [[{}]]", bookmark.url)?;
+            } else {
+                write!(out, "- [[{}][{}]]", bookmark.url, bookmark.title)?;
+            }
+            if !bookmark.description.is_empty() {
+                write!(out, " :: {}", bookmark.description)?;
+            }
+            writeln!(out)?;
         }
-        Ok(())
     }
+    Ok(())
 }
 
 /// Org-mode exporter
@@ -65,36 +164,287 @@ pub struct OrgExporter;
 
 impl BookmarkExporter for OrgExporter {
     fn export(&self, records: &[Bookmark], path: &Path) -> crate::error::Result<()> {
-        let mut file = File::create(path)?;
-        for bookmark in records {
-            let org_tags = if bookmark.tags.is_empty() {
-                "".to_string()
-            } else {
-                format!(" :{}", bookmark.tags.replace(",", ":"))
-            };
-            writeln!(
-                file,
-                "* [[{}][{}]] {}:",
-                bookmark.url, bookmark.title, org_tags
-            )?;
-        }
-        Ok(())
+        export_to_file(path, |out| write_org(records, out))
+    }
+}
+
+/// Write `records` as a JSON array of canonical bookmarks
+pub fn write_json(records: &[CanonicalBookmark], out: &mut dyn Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, records)?;
+    writeln!(out)
+}
+
+/// Canonical JSON exporter
+#[derive(Debug, Default)]
+pub struct JsonExporter {
+    /// Columns the model leaves out, by id: flags, parent and creation time
+    pub stored: HashMap<usize, (i32, Option<usize>, Option<i64>)>,
+}
+
+impl BookmarkExporter for JsonExporter {
+    fn export(&self, records: &[Bookmark], path: &Path) -> crate::error::Result<()> {
+        let canonical: Vec<CanonicalBookmark> = records
+            .iter()
+            .map(|bookmark| {
+                let mut record = CanonicalBookmark::from(bookmark);
+                if let Some(&(flags, parent, created_at)) = self.stored.get(&bookmark.id) {
+                    record.flags = flags;
+                    record.parent = parent;
+                    record.created_at = created_at;
+                }
+                record
+            })
+            .collect();
+        export_to_file(path, |out| write_json(&canonical, out))
     }
 }
 
 /// Export bookmarks to a file in the specified format
+#[cfg(feature = "full")]
 pub fn export_bookmarks(db: &BukuDb, file_path: &str) -> crate::error::Result<()> {
+    export_bookmarks_with_progress(db, file_path, &NoProgress)
+}
+
+/// Export only the bookmarks that pass `filter`, e.g. those tagged `public`
+#[cfg(feature = "full")]
+pub fn export_filtered(
+    db: &BukuDb,
+    file_path: &str,
+    filter: &BookmarkFilter,
+) -> crate::error::Result<()> {
+    let only: HashSet<usize> = filter
+        .apply(db.get_rec_all()?)?
+        .iter()
+        .map(|b| b.id)
+        .collect();
+    export_selection_with_progress(db, file_path, Some(&only), &NoProgress)
+}
+
+/// Export bookmarks, reporting them to `progress` once the file is written
+#[cfg(feature = "full")]
+pub fn export_bookmarks_with_progress(
+    db: &BukuDb,
+    file_path: &str,
+    progress: &dyn ProgressSink,
+) -> crate::error::Result<()> {
+    export_selection_with_progress(db, file_path, None, progress)
+}
+
+/// Like [`export_bookmarks_with_progress`], writing only the bookmarks in
+/// `only` when given (a collection, say)
+#[cfg(feature = "full")]
+pub fn export_selection_with_progress(
+    db: &BukuDb,
+    file_path: &str,
+    only: Option<&HashSet<usize>>,
+    progress: &dyn ProgressSink,
+) -> crate::error::Result<()> {
     let path = Path::new(file_path);
     let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
-    let records = db.get_rec_all()?;
+    if let Some(format) = FeedFormat::from_string(extension) {
+        progress.start(&format!("Exporting to {}", file_path), None);
+        export_feed_selection(db, file_path, format, DEFAULT_FEED_LIMIT, only)?;
+        progress.finish();
+        return Ok(());
+    }
+
+    let mut records = db.get_rec_all()?;
+    if let Some(ids) = only {
+        records.retain(|bookmark| ids.contains(&bookmark.id));
+    }
 
     let exporter: Box<dyn BookmarkExporter> = match extension {
-        "html" => Box::new(HtmlExporter),
-        "md" => Box::new(MarkdownExporter),
+        "html" => Box::new(HtmlExporter {
+            aliases: db
+                .get_aliases()?
+                .into_iter()
+                .map(|(alias, bookmark)| (bookmark.id, alias))
+                .collect(),
+        }),
+        "md" | "markdown" => Box::new(MarkdownExporter),
         "org" => Box::new(OrgExporter),
+        "json" => Box::new(JsonExporter {
+            stored: db
+                .dump_bookmarks()?
+                .into_iter()
+                .map(|b| {
+                    let created_at = (b.created_at != 0).then_some(b.created_at);
+                    (b.id, (b.flags, b.parent_id, created_at))
+                })
+                .collect(),
+        }),
         _ => return Err(format!("Unsupported export format: {}", extension).into()),
     };
 
-    exporter.export(&records, path)
+    progress.start(
+        &format!("Exporting to {}", file_path),
+        Some(records.len() as u64),
+    );
+    exporter.export(&records, path)?;
+    progress.advance(records.len() as u64, None);
+    progress.finish();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "full")]
+    use crate::import_export::import::{import_bookmarks, DuplicateStrategy};
+    use rstest::rstest;
+
+    #[test]
+    fn test_write_markdown_groups_by_tags() {
+        let records = vec![
+            Bookmark::new(
+                1,
+                "https://rust-lang.org".into(),
+                "Rust [book]".into(),
+                ",rust,".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                2,
+                "https://example.com".into(),
+                "Example".into(),
+                ",".into(),
+                "Notes".into(),
+            ),
+        ];
+        let mut out = Vec::new();
+        write_markdown(&records, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "- [Example](https://example.com) - Notes\n\n# rust\n\n- [Rust \\[book\\]](https://rust-lang.org)\n"
+        );
+    }
+
+    #[test]
+    fn test_write_html_aliases() {
+        let records = vec![
+            Bookmark::new(
+                1,
+                "https://github.com".into(),
+                "GitHub".into(),
+                ",code,".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                2,
+                "https://example.com".into(),
+                "Example".into(),
+                ",".into(),
+                "".into(),
+            ),
+        ];
+        let aliases = HashMap::from([(1, "gh".to_string())]);
+        let mut out = Vec::new();
+        write_html_with_aliases(&records, &aliases, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains(
+            "<DT><A HREF=\"https://github.com\" TAGS=\",code,\" SHORTCUTURL=\"gh\" ADD_DATE=\"0\">GitHub</A>"
+        ));
+        assert!(html
+            .contains("<DT><A HREF=\"https://example.com\" TAGS=\",\" ADD_DATE=\"0\">Example</A>"));
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn test_json_round_trip() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://untagged.com", "Untagged", ",", "", None)
+            .unwrap();
+        db.add_rec(
+            "https://rust-lang.org",
+            "Rust",
+            ",rust,systems programming,",
+            "Line one\nLine two",
+            None,
+        )
+        .unwrap();
+        let docs = db
+            .add_rec("https://docs.rs", "Docs", ",rust,", "", None)
+            .unwrap();
+        let docs = db.get_rec_by_id(docs).unwrap().unwrap();
+        db.update_rec_batch(&[docs], None, None, None, None, Some(1))
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.json");
+        let path = path.to_str().unwrap();
+        export_bookmarks(&db, path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(
+            json[1]["tags"],
+            serde_json::json!(["rust", "systems programming"])
+        );
+
+        let imported = BukuDb::init_in_memory().unwrap();
+        let stats = import_bookmarks(&imported, path, DuplicateStrategy::Skip).unwrap();
+        assert_eq!(stats.added, 3);
+
+        let strip = |db: &BukuDb| -> Vec<_> {
+            db.dump_bookmarks()
+                .unwrap()
+                .into_iter()
+                .map(|b| {
+                    (
+                        b.url,
+                        b.title,
+                        b.tags,
+                        b.desc,
+                        b.flags,
+                        b.parent_id,
+                        b.created_at,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(strip(&db), strip(&imported));
+    }
+
+    #[cfg(feature = "full")]
+    #[rstest]
+    #[case("bookmarks.md")]
+    #[case("bookmarks.org")]
+    fn test_markup_round_trip(#[case] file_name: &str) {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://untagged.com", "Untagged", ",", "", None)
+            .unwrap();
+        db.add_rec(
+            "https://rust-lang.org",
+            "Rust",
+            ",rust,lang,",
+            "Systems",
+            None,
+        )
+        .unwrap();
+        db.add_rec("https://docs.rs", "", ",rust,lang,", "", None)
+            .unwrap();
+        db.add_rec("https://python.org", "Python [3]", ",python,", "", None)
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file_name);
+        let path = path.to_str().unwrap();
+        export_bookmarks(&db, path).unwrap();
+
+        let imported = BukuDb::init_in_memory().unwrap();
+        let stats = import_bookmarks(&imported, path, DuplicateStrategy::Skip).unwrap();
+        assert_eq!(stats.added, 4);
+
+        let mut original = db.get_rec_all().unwrap();
+        let mut restored = imported.get_rec_all().unwrap();
+        original.sort_by(|a, b| a.url.cmp(&b.url));
+        restored.sort_by(|a, b| a.url.cmp(&b.url));
+        for (a, b) in original.iter().zip(&restored) {
+            assert_eq!(
+                (&a.url, &a.title, &a.tags, &a.description),
+                (&b.url, &b.title, &b.tags, &b.description)
+            );
+        }
+    }
 }
@@ -0,0 +1,316 @@
+use super::markup::escape_markup;
+use crate::db::BukuDb;
+use crate::models::bookmark::Bookmark;
+use crate::tags::parse_tags;
+use crate::utils::{format_rfc2822, format_rfc3339};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Number of bookmarks in a feed when no limit is given
+pub const DEFAULT_FEED_LIMIT: usize = 50;
+
+const GENERATOR: &str = "bukurs";
+const GENERATOR_URI: &str = "https://github.com/PongPong/bukurs";
+
+/// Syndication feed flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "atom" => Some(FeedFormat::Atom),
+            "rss" => Some(FeedFormat::Rss),
+            _ => None,
+        }
+    }
+}
+
+/// Entry title, falling back to the URL for untitled bookmarks
+fn entry_title(bookmark: &Bookmark) -> &str {
+    if bookmark.title.is_empty() {
+        &bookmark.url
+    } else {
+        &bookmark.title
+    }
+}
+
+/// Stable feed ID derived from the database path, so re-exports keep the same identity
+fn feed_id(db: &BukuDb) -> String {
+    let digest = Sha256::digest(db.get_path().to_string_lossy().as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    format!("urn:uuid:{}", uuid::Uuid::from_bytes(bytes))
+}
+
+/// Write an Atom 1.0 feed of bookmarks, newest first
+fn write_atom<W: Write>(
+    out: &mut W,
+    id: &str,
+    entries: &[(Bookmark, i64)],
+    now: i64,
+) -> std::io::Result<()> {
+    let updated = entries.first().map(|(_, ts)| *ts).filter(|ts| *ts > 0);
+
+    writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(out, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#)?;
+    writeln!(out, "  <title>Bookmarks</title>")?;
+    writeln!(out, "  <id>{}</id>", id)?;
+    writeln!(
+        out,
+        "  <updated>{}</updated>",
+        format_rfc3339(updated.unwrap_or(now))
+    )?;
+    writeln!(out, "  <author><name>{}</name></author>", GENERATOR)?;
+    writeln!(
+        out,
+        r#"  <generator uri="{}">{}</generator>"#,
+        GENERATOR_URI, GENERATOR
+    )?;
+
+    for (bookmark, created_at) in entries {
+        // Undated bookmarks still need an <updated>; use the feed's own time
+        let ts = if *created_at > 0 { *created_at } else { now };
+        writeln!(out, "  <entry>")?;
+        writeln!(
+            out,
+            "    <title>{}</title>",
+            escape_markup(entry_title(bookmark))
+        )?;
+        writeln!(
+            out,
+            r#"    <link href="{}"/>"#,
+            escape_markup(&bookmark.url)
+        )?;
+        writeln!(out, "    <id>{}</id>", escape_markup(&bookmark.url))?;
+        writeln!(out, "    <updated>{}</updated>", format_rfc3339(ts))?;
+        if !bookmark.description.is_empty() {
+            writeln!(
+                out,
+                "    <summary>{}</summary>",
+                escape_markup(&bookmark.description)
+            )?;
+        }
+        for tag in parse_tags(&bookmark.tags) {
+            writeln!(out, r#"    <category term="{}"/>"#, escape_markup(&tag))?;
+        }
+        writeln!(out, "  </entry>")?;
+    }
+
+    writeln!(out, "</feed>")
+}
+
+/// Write an RSS 2.0 feed of bookmarks, newest first
+fn write_rss<W: Write>(out: &mut W, entries: &[(Bookmark, i64)], now: i64) -> std::io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(out, r#"<rss version="2.0">"#)?;
+    writeln!(out, "  <channel>")?;
+    writeln!(out, "    <title>Bookmarks</title>")?;
+    writeln!(out, "    <link>{}</link>", GENERATOR_URI)?;
+    writeln!(
+        out,
+        "    <description>Recently added bookmarks</description>"
+    )?;
+    writeln!(
+        out,
+        "    <lastBuildDate>{}</lastBuildDate>",
+        format_rfc2822(now)
+    )?;
+    writeln!(out, "    <generator>{}</generator>", GENERATOR)?;
+
+    for (bookmark, created_at) in entries {
+        writeln!(out, "    <item>")?;
+        writeln!(
+            out,
+            "      <title>{}</title>",
+            escape_markup(entry_title(bookmark))
+        )?;
+        writeln!(out, "      <link>{}</link>", escape_markup(&bookmark.url))?;
+        writeln!(
+            out,
+            r#"      <guid isPermaLink="true">{}</guid>"#,
+            escape_markup(&bookmark.url)
+        )?;
+        if *created_at > 0 {
+            writeln!(
+                out,
+                "      <pubDate>{}</pubDate>",
+                format_rfc2822(*created_at)
+            )?;
+        }
+        if !bookmark.description.is_empty() {
+            writeln!(
+                out,
+                "      <description>{}</description>",
+                escape_markup(&bookmark.description)
+            )?;
+        }
+        for tag in parse_tags(&bookmark.tags) {
+            writeln!(out, "      <category>{}</category>", escape_markup(&tag))?;
+        }
+        writeln!(out, "    </item>")?;
+    }
+
+    writeln!(out, "  </channel>")?;
+    writeln!(out, "</rss>")
+}
+
+/// Export the `limit` most recently added bookmarks as an Atom or RSS feed
+pub fn export_feed(
+    db: &BukuDb,
+    file_path: &str,
+    format: FeedFormat,
+    limit: usize,
+) -> crate::error::Result<()> {
+    export_feed_selection(db, file_path, format, limit, None)
+}
+
+/// Like [`export_feed`], taking only the bookmarks in `only` when given
+pub fn export_feed_selection(
+    db: &BukuDb,
+    file_path: &str,
+    format: FeedFormat,
+    limit: usize,
+    only: Option<&HashSet<usize>>,
+) -> crate::error::Result<()> {
+    let entries = match only {
+        None => db.get_rec_recent(limit)?,
+        Some(ids) => db
+            // Past every row, as SQLite reads a negative LIMIT
+            .get_rec_recent(usize::MAX)?
+            .into_iter()
+            .filter(|(bookmark, _)| ids.contains(&bookmark.id))
+            .take(limit)
+            .collect(),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as i64;
+
+    let mut out = BufWriter::new(File::create(Path::new(file_path))?);
+    match format {
+        FeedFormat::Atom => write_atom(&mut out, &feed_id(db), &entries, now)?,
+        FeedFormat::Rss => write_rss(&mut out, &entries, now)?,
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn entries() -> Vec<(Bookmark, i64)> {
+        vec![
+            (
+                Bookmark::new(
+                    2,
+                    "https://example.com/?a=1&b=2".to_string(),
+                    "Fish & <Chips>".to_string(),
+                    ",food,uk,".to_string(),
+                    "Tasty".to_string(),
+                ),
+                1_714_564_800,
+            ),
+            (
+                Bookmark::new(
+                    1,
+                    "https://untitled.com".to_string(),
+                    String::new(),
+                    ",".to_string(),
+                    String::new(),
+                ),
+                0,
+            ),
+        ]
+    }
+
+    #[rstest]
+    #[case(0, "1970-01-01T00:00:00Z", "Thu, 01 Jan 1970 00:00:00 +0000")]
+    #[case(
+        1_714_564_800,
+        "2024-05-01T12:00:00Z",
+        "Wed, 01 May 2024 12:00:00 +0000"
+    )]
+    #[case(951_782_400, "2000-02-29T00:00:00Z", "Tue, 29 Feb 2000 00:00:00 +0000")]
+    fn test_date_formats(#[case] ts: i64, #[case] rfc3339: &str, #[case] rfc2822: &str) {
+        assert_eq!(format_rfc3339(ts), rfc3339);
+        assert_eq!(format_rfc2822(ts), rfc2822);
+    }
+
+    #[test]
+    fn test_write_atom() {
+        let mut out = Vec::new();
+        write_atom(&mut out, "urn:uuid:test", &entries(), 1_800_000_000).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+
+        assert!(xml.contains("<updated>2024-05-01T12:00:00Z</updated>"));
+        assert!(xml.contains("<title>Fish &amp; &lt;Chips&gt;</title>"));
+        assert!(xml.contains(r#"<link href="https://example.com/?a=1&amp;b=2"/>"#));
+        assert!(xml.contains(r#"<category term="uk"/>"#));
+        // Untitled bookmarks are titled by URL
+        assert!(xml.contains("<title>https://untitled.com</title>"));
+        assert_eq!(xml.matches("<entry>").count(), 2);
+    }
+
+    #[test]
+    fn test_write_rss() {
+        let mut out = Vec::new();
+        write_rss(&mut out, &entries(), 1_800_000_000).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+
+        assert!(xml.contains("<pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate>"));
+        assert_eq!(xml.matches("<pubDate>").count(), 1);
+        assert!(xml.contains("<category>food</category>"));
+        assert_eq!(xml.matches("<item>").count(), 2);
+    }
+
+    #[test]
+    fn test_export_feed_limit() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 0..3 {
+            db.add_rec(&format!("https://example.com/{}", i), "", ",", "", None)
+                .unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.xml");
+        export_feed(&db, path.to_str().unwrap(), FeedFormat::Atom, 2).unwrap();
+
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert_eq!(xml.matches("<entry>").count(), 2);
+        // Newest first
+        assert!(xml.find("example.com/2").unwrap() < xml.find("example.com/1").unwrap());
+    }
+
+    #[test]
+    fn test_export_feed_selection() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 0..4 {
+            db.add_rec(&format!("https://example.com/{}", i), "", ",", "", None)
+                .unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.xml");
+        let only = HashSet::from([1, 2, 4]);
+        export_feed_selection(&db, path.to_str().unwrap(), FeedFormat::Rss, 2, Some(&only))
+            .unwrap();
+
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert_eq!(xml.matches("<item>").count(), 2);
+        assert!(xml.contains("example.com/3"));
+        assert!(xml.contains("example.com/1"));
+        assert!(!xml.contains("example.com/2"));
+    }
+}
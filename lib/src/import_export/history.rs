@@ -0,0 +1,410 @@
+//! Browser history as a source of bookmarks
+//!
+//! Reads visit counts from Firefox's `places.sqlite` or Chrome/Edge's `History`
+//! so frequently visited pages can be bookmarked in bulk. A running browser
+//! keeps writing to these files, so they are copied (with their `-wal`, which
+//! holds the latest visits) and the copy is read.
+
+use super::browser::{select_profiles, BrowserProfile, BrowserType};
+use super::import::ParsedBookmark;
+use crate::db::BukuDb;
+use crate::error::BukursError;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Microseconds between 1601-01-01 (Chrome's epoch) and the Unix epoch
+const CHROME_EPOCH_OFFSET_MICROS: i64 = 11_644_473_600_000_000;
+
+/// A visited page and how often it was visited in the requested period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: String,
+    pub visits: u64,
+    /// Unix seconds of the latest visit
+    pub last_visit: i64,
+}
+
+/// Which history entries to read
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistoryFilter {
+    /// Only count visits at or after this Unix timestamp
+    pub since: Option<i64>,
+    /// Drop pages visited fewer times than this
+    pub min_visits: u64,
+}
+
+/// The history database belonging to a bookmarks profile
+///
+/// Firefox keeps both in `places.sqlite`; Chrome and Edge keep `History` next
+/// to `Bookmarks`.
+pub fn history_path(profile: &BrowserProfile) -> PathBuf {
+    match profile.browser {
+        BrowserType::Firefox => profile.path.clone(),
+        _ => profile.path.with_file_name("History"),
+    }
+}
+
+/// Detected profiles of the named browsers that have a history database
+pub fn detect_history(
+    browser_names: &[String],
+) -> crate::error::Result<Vec<(BrowserProfile, PathBuf)>> {
+    Ok(select_profiles(browser_names)?
+        .into_iter()
+        .map(|profile| {
+            let path = history_path(&profile);
+            (profile, path)
+        })
+        .filter(|(_, path)| path.is_file())
+        .collect())
+}
+
+/// Browser and history database for a file or profile directory given by the user
+///
+/// A directory is searched for `places.sqlite` (Firefox) or `History`
+/// (Chrome/Edge). Without `browser`, a file's format is told by its tables.
+pub fn history_source_from_path(
+    path: &Path,
+    browser: Option<BrowserType>,
+) -> crate::error::Result<(BrowserType, PathBuf)> {
+    let file = if path.is_dir() {
+        ["places.sqlite", "History"]
+            .iter()
+            .map(|name| path.join(name))
+            .find(|file| file.is_file())
+            .ok_or_else(|| {
+                BukursError::Browser(format!(
+                    "No places.sqlite or History file in {}",
+                    path.display()
+                ))
+            })?
+    } else {
+        path.to_path_buf()
+    };
+
+    let browser = match browser {
+        Some(browser) => browser,
+        None => {
+            let copy = HistoryCopy::open(&file)?;
+            let firefox: bool = copy.conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'moz_places')",
+                [],
+                |row| row.get(0),
+            )?;
+            if firefox {
+                BrowserType::Firefox
+            } else {
+                BrowserType::Chrome
+            }
+        }
+    };
+    Ok((browser, file))
+}
+
+/// A private copy of a history database, deleted when dropped
+///
+/// Reading the live file while the browser writes to it can return wrong
+/// results, and misses the visits still in its write-ahead log.
+struct HistoryCopy {
+    conn: Connection,
+    // Dropped after `conn`
+    _dir: tempfile::TempDir,
+}
+
+impl HistoryCopy {
+    fn open(path: &Path) -> crate::error::Result<Self> {
+        if !path.is_file() {
+            return Err(BukursError::Browser(format!(
+                "No history database at {}",
+                path.display()
+            )));
+        }
+        let dir = tempfile::tempdir()?;
+        let copy = dir.path().join("history.sqlite");
+        fs::copy(path, &copy)?;
+        let wal = with_suffix(path, "-wal");
+        if wal.is_file() {
+            fs::copy(&wal, with_suffix(&copy, "-wal"))?;
+        }
+        // Opened writable so SQLite can apply the copied log to the copy
+        let conn = Connection::open(&copy)?;
+        Ok(Self { conn, _dir: dir })
+    }
+}
+
+/// `path` with `suffix` appended to its file name, like SQLite's `-wal` files
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Read a browser's history database, most visited first
+///
+/// Only http(s) pages are returned.
+pub fn read_history(
+    browser: &BrowserType,
+    path: &Path,
+    filter: HistoryFilter,
+) -> crate::error::Result<Vec<HistoryEntry>> {
+    let copy = HistoryCopy::open(path)?;
+    let since = filter.since.unwrap_or(0);
+    let min_visits = filter.min_visits.max(1) as i64;
+
+    // Both browsers store visit times in microseconds, from different epochs
+    let (sql, since_micros, to_unix): (&str, i64, fn(i64) -> i64) = match browser {
+        BrowserType::Firefox => (
+            "SELECT p.url, p.title, COUNT(*), MAX(v.visit_date)
+             FROM moz_historyvisits v JOIN moz_places p ON v.place_id = p.id
+             WHERE v.visit_date >= ?1
+             GROUP BY p.id HAVING COUNT(*) >= ?2
+             ORDER BY COUNT(*) DESC, MAX(v.visit_date) DESC",
+            since * 1_000_000,
+            |micros| micros / 1_000_000,
+        ),
+        BrowserType::Chrome | BrowserType::Edge => (
+            "SELECT u.url, u.title, COUNT(*), MAX(v.visit_time)
+             FROM visits v JOIN urls u ON v.url = u.id
+             WHERE v.visit_time >= ?1
+             GROUP BY u.id HAVING COUNT(*) >= ?2
+             ORDER BY COUNT(*) DESC, MAX(v.visit_time) DESC",
+            since * 1_000_000 + CHROME_EPOCH_OFFSET_MICROS,
+            |micros| (micros - CHROME_EPOCH_OFFSET_MICROS) / 1_000_000,
+        ),
+        BrowserType::Safari => {
+            return Err(BukursError::Browser(
+                "Reading Safari history is not supported".to_string(),
+            ))
+        }
+    };
+
+    let mut stmt = copy.conn.prepare(sql)?;
+    let rows = stmt.query_map((since_micros, min_visits), |row| {
+        Ok(HistoryEntry {
+            url: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            visits: row.get::<_, i64>(2)? as u64,
+            last_visit: to_unix(row.get(3)?),
+        })
+    })?;
+
+    let mut entries = Vec::new();
+    for entry in rows {
+        let entry = entry?;
+        if entry.url.starts_with("http://") || entry.url.starts_with("https://") {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Drop entries for URLs already in the database
+pub fn without_bookmarked(
+    db: &BukuDb,
+    entries: Vec<HistoryEntry>,
+) -> crate::error::Result<Vec<HistoryEntry>> {
+    let bookmarked: HashSet<String> = db.get_rec_all()?.into_iter().map(|b| b.url).collect();
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !bookmarked.contains(&entry.url))
+        .collect())
+}
+
+/// Bookmarks for history entries, tagged `history` and with the visit count noted
+pub fn to_bookmarks(entries: &[HistoryEntry]) -> Vec<ParsedBookmark> {
+    entries
+        .iter()
+        .map(|entry| ParsedBookmark {
+            url: entry.url.clone(),
+            title: entry.title.clone(),
+            tags: ",history,".to_string(),
+            desc: String::new(),
+            parent_id: None,
+            created_at: None,
+            flags: 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import_export::import::DuplicateStrategy;
+    use crate::utils::parse_duration;
+    use rstest::rstest;
+
+    const DAY_MICROS: i64 = 86_400 * 1_000_000;
+
+    fn firefox_history(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+             CREATE TABLE moz_historyvisits (id INTEGER PRIMARY KEY, place_id INTEGER, visit_date INTEGER);
+             INSERT INTO moz_places VALUES (1, 'https://often.com/', 'Often'), (2, 'https://once.com/', 'Once'),
+                                           (3, 'about:config', NULL), (4, 'https://old.com/', NULL);",
+        )
+        .unwrap();
+        let visits: &[(i64, i64)] = &[
+            (1, 100),
+            (1, 101),
+            (1, 102),
+            (2, 100),
+            (3, 100),
+            (3, 101),
+            (4, 1),
+            (4, 2),
+        ];
+        for (place, day) in visits {
+            conn.execute(
+                "INSERT INTO moz_historyvisits (place_id, visit_date) VALUES (?1, ?2)",
+                (place, day * DAY_MICROS),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_firefox_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_history(&places);
+        let all = read_history(&BrowserType::Firefox, &places, HistoryFilter::default()).unwrap();
+        let urls: Vec<&str> = all.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://often.com/",
+                "https://old.com/",
+                "https://once.com/"
+            ]
+        );
+        assert_eq!(all[0].visits, 3);
+        assert_eq!(all[0].last_visit, 102 * 86_400);
+
+        let filter = HistoryFilter {
+            since: Some(50 * 86_400),
+            min_visits: 2,
+        };
+        let recent = read_history(&BrowserType::Firefox, &places, filter).unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].title, "Often");
+    }
+
+    #[test]
+    fn test_read_chrome_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = dir.path().join("History");
+        let conn = Connection::open(&history).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE urls (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+             CREATE TABLE visits (id INTEGER PRIMARY KEY, url INTEGER, visit_time INTEGER);
+             INSERT INTO urls VALUES (1, 'https://chrome.com/', 'Chrome page');",
+        )
+        .unwrap();
+        for day in [10, 11] {
+            conn.execute(
+                "INSERT INTO visits (url, visit_time) VALUES (1, ?1)",
+                [day * DAY_MICROS + CHROME_EPOCH_OFFSET_MICROS],
+            )
+            .unwrap();
+        }
+        drop(conn);
+
+        let profile = BrowserProfile {
+            browser: BrowserType::Chrome,
+            profile_name: "Default".to_string(),
+            path: dir.path().join("Bookmarks"),
+        };
+        assert_eq!(history_path(&profile), history);
+        assert_eq!(
+            history_source_from_path(dir.path(), None).unwrap(),
+            (BrowserType::Chrome, history.clone())
+        );
+
+        let entries = read_history(
+            &BrowserType::Chrome,
+            &history,
+            HistoryFilter {
+                since: None,
+                min_visits: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![HistoryEntry {
+                url: "https://chrome.com/".to_string(),
+                title: "Chrome page".to_string(),
+                visits: 2,
+                last_visit: 11 * 86_400,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_read_history_in_write_ahead_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_history(&places);
+        // A running browser: the latest visits are only in places.sqlite-wal
+        let browser = Connection::open(&places).unwrap();
+        browser
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA wal_autocheckpoint = 0;
+                 INSERT INTO moz_places VALUES (5, 'https://new.com/', 'New');",
+            )
+            .unwrap();
+        browser
+            .execute(
+                "INSERT INTO moz_historyvisits (place_id, visit_date) VALUES (5, ?1)",
+                [103 * DAY_MICROS],
+            )
+            .unwrap();
+        assert!(with_suffix(&places, "-wal").is_file());
+
+        let entries =
+            read_history(&BrowserType::Firefox, &places, HistoryFilter::default()).unwrap();
+        assert!(entries.iter().any(|e| e.url == "https://new.com/"));
+        drop(browser);
+    }
+
+    #[test]
+    fn test_history_to_bookmarks_skips_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        firefox_history(&places);
+        assert_eq!(
+            history_source_from_path(&places, None).unwrap().0,
+            BrowserType::Firefox
+        );
+        let entries =
+            read_history(&BrowserType::Firefox, &places, HistoryFilter::default()).unwrap();
+
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://often.com/", "Often", ",", "", None)
+            .unwrap();
+        let fresh = without_bookmarked(&db, entries).unwrap();
+        assert_eq!(fresh.len(), 2);
+
+        let stats = db
+            .import_rec_batch(&to_bookmarks(&fresh), DuplicateStrategy::Skip, |_| {})
+            .unwrap();
+        assert_eq!(stats.added, 2);
+        assert_eq!(db.get_rec_by_id(2).unwrap().unwrap().tags, ",history,");
+    }
+
+    #[rstest]
+    #[case("30d", Some(30 * 86_400))]
+    #[case("30", Some(30 * 86_400))]
+    #[case("12h", Some(12 * 3600))]
+    #[case("2W", Some(14 * 86_400))]
+    #[case("1y", Some(365 * 86_400))]
+    #[case("d", None)]
+    #[case("3 fortnights", None)]
+    fn test_parse_duration(#[case] s: &str, #[case] expected: Option<u64>) {
+        assert_eq!(parse_duration(s), expected);
+    }
+}
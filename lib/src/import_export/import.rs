@@ -1,254 +1,1004 @@
+use super::markup;
+use super::netscape;
+#[cfg(feature = "full")]
+use crate::cancel::CancellationToken;
+#[cfg(feature = "full")]
 use crate::db::BukuDb;
-use crate::utils;
+use crate::models::bookmark::{Bookmark, CanonicalBookmark};
+#[cfg(feature = "full")]
+use crate::progress::{NoProgress, ProgressSink};
+use crate::tags::{format_tags, parse_tags};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "full")]
+use std::collections::HashMap;
+#[cfg(feature = "full")]
+use std::io::Write;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "full")]
+use std::sync::Mutex;
+#[cfg(feature = "full")]
 use std::thread;
 
 /// Trait for importing bookmarks from different formats
+#[cfg(feature = "full")]
 pub trait BookmarkImporter {
-    fn import(&self, db: &BukuDb, path: &Path) -> crate::error::Result<usize>;
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats>;
+}
+
+/// What to do when an imported URL already exists in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateStrategy {
+    /// Leave the existing bookmark untouched
+    #[default]
+    Skip,
+    /// Union the tags and fill in an empty title or description
+    Merge,
+    /// Replace title, tags and description with the imported values
+    Overwrite,
+}
+
+impl DuplicateStrategy {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "skip" => Some(DuplicateStrategy::Skip),
+            "merge" => Some(DuplicateStrategy::Merge),
+            "overwrite" => Some(DuplicateStrategy::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+/// Counts of what an import did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImportStats {
+    /// New bookmarks inserted
+    pub added: usize,
+    /// Existing bookmarks merged or overwritten
+    pub updated: usize,
+    /// Bookmarks left out: duplicates left untouched and URLs that can't be bookmarked
+    pub skipped: usize,
+    /// The import was cancelled; the counts cover only what was committed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+impl std::ops::AddAssign for ImportStats {
+    fn add_assign(&mut self, other: Self) {
+        self.added += other.added;
+        self.updated += other.updated;
+        self.skipped += other.skipped;
+        self.cancelled |= other.cancelled;
+    }
+}
+
+/// Why a bookmark wasn't simply added (see [`ReportEntry`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    /// The URL already exists and was left untouched
+    Duplicate,
+    /// The URL already exists and the imported tags, title or description were merged in
+    Merged,
+    /// The URL already exists and was overwritten
+    Overwritten,
+    /// The URL can't be bookmarked (see [`is_importable_url`])
+    InvalidUrl,
+}
+
+/// A bookmark an import skipped or merged, as one line of `import --report`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportEntry {
+    pub reason: ReportReason,
+    pub url: String,
+    pub title: String,
+    /// ID of the existing bookmark with the same URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<usize>,
+}
+
+impl ReportEntry {
+    pub fn new(
+        reason: ReportReason,
+        bookmark: &ParsedBookmark,
+        existing_id: Option<usize>,
+    ) -> Self {
+        Self {
+            reason,
+            url: bookmark.url.clone(),
+            title: bookmark.title.clone(),
+            existing_id,
+        }
+    }
+}
+
+/// Writes each [`ReportEntry`] of an import as a JSON line; shared by the
+/// workers of a parallel import
+#[cfg(feature = "full")]
+pub struct ImportReport {
+    inner: Mutex<ReportWriter>,
+}
+
+#[cfg(feature = "full")]
+struct ReportWriter {
+    out: Box<dyn Write + Send>,
+    written: usize,
+    /// The first write that failed; later entries are dropped
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "full")]
+impl ImportReport {
+    /// Report to a new file at `path`, replacing any file there
+    pub fn create(path: &Path) -> crate::error::Result<Self> {
+        let file = std::fs::File::create(path)?;
+        Ok(Self::new(std::io::BufWriter::new(file)))
+    }
+
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self {
+            inner: Mutex::new(ReportWriter {
+                out: Box::new(out),
+                written: 0,
+                error: None,
+            }),
+        }
+    }
+
+    pub fn record(&self, entry: &ReportEntry) {
+        let mut writer = self.inner.lock().unwrap();
+        if writer.error.is_some() {
+            return;
+        }
+        let result = serde_json::to_writer(&mut writer.out, entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.out.write_all(b"\n"));
+        match result {
+            Ok(()) => writer.written += 1,
+            Err(e) => writer.error = Some(e),
+        }
+    }
+
+    /// Flush the report; returns how many entries it holds
+    pub fn finish(self) -> crate::error::Result<usize> {
+        let mut writer = self.inner.into_inner().unwrap();
+        if let Some(e) = writer.error.take() {
+            return Err(e.into());
+        }
+        writer.out.flush()?;
+        Ok(writer.written)
+    }
+}
+
+/// Whether an imported URL can be bookmarked; empty URLs, bookmarklets
+/// (`javascript:`) and Firefox's smart folders (`place:`) can't
+pub fn is_importable_url(url: &str) -> bool {
+    let url = url.trim();
+    !url.is_empty() && !url.starts_with("javascript:") && !url.starts_with("place:")
+}
+
+/// Apply a duplicate strategy to an existing bookmark
+/// Returns the updated bookmark, or None if nothing would change
+pub fn resolve_duplicate(
+    existing: &Bookmark,
+    incoming: &ParsedBookmark,
+    strategy: DuplicateStrategy,
+) -> Option<Bookmark> {
+    let mut resolved = existing.clone();
+
+    match strategy {
+        DuplicateStrategy::Skip => return None,
+        DuplicateStrategy::Merge => {
+            let mut tags = parse_tags(&existing.tags);
+            for tag in parse_tags(&incoming.tags) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            resolved.tags = format_tags(&tags);
+            if resolved.title.is_empty() {
+                resolved.title = incoming.title.clone();
+            }
+            if resolved.description.is_empty() {
+                resolved.description = incoming.desc.clone();
+            }
+        }
+        DuplicateStrategy::Overwrite => {
+            resolved.title = incoming.title.clone();
+            resolved.tags = incoming.tags.clone();
+            resolved.description = incoming.desc.clone();
+        }
+    }
+
+    (resolved != *existing).then_some(resolved)
 }
 
 /// Parsed bookmark ready for import
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ParsedBookmark {
     pub url: String,
     pub title: String,
     pub tags: String,
     pub desc: String,
     pub parent_id: Option<usize>,
+    /// When the source says the bookmark was added (Unix seconds); new
+    /// bookmarks get the import time when this is None
+    pub created_at: Option<i64>,
+    /// Flags for a new bookmark (1 keeps the title from being refreshed)
+    pub flags: i32,
 }
 
-use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::mpsc::SyncSender;
 
 /// Parse HTML bookmarks and stream them to a channel
 pub fn parse_html_bookmarks_stream(
     path: &Path,
     tx: SyncSender<ParsedBookmark>,
 ) -> crate::error::Result<()> {
-    let html = std::fs::read_to_string(path)?;
-    let dom = tl::parse(&html, tl::ParserOptions::default())?;
-    let parser = dom.parser();
-
-    let mut folder_stack: Vec<String> = Vec::new();
-
-    // Parse HTML nodes
-    for node in dom.nodes() {
-        if let Some(tag) = node.as_tag() {
-            let tag_name = tag.name().as_utf8_str();
-
-            match tag_name.as_ref() {
-                // H3 tags represent folder names
-                "H3" | "h3" => {
-                    if let Some(folder_name) =
-                        utils::trim_both_simd(tag.inner_text(parser).as_ref())
-                            .to_string()
-                            .into()
-                    {
-                        if !folder_name.is_empty() {
-                            folder_stack.push(folder_name);
-                        }
-                    }
-                }
-                // A tags are bookmarks
-                "A" | "a" => {
-                    if let Some(href) = tag
-                        .attributes()
-                        .get("HREF")
-                        .or_else(|| tag.attributes().get("href"))
-                    {
-                        let url = href
-                            .map(|h| h.as_utf8_str().to_string())
-                            .unwrap_or_default();
-
-                        // Skip empty URLs or special URLs
-                        if url.is_empty()
-                            || url.starts_with("place:")
-                            || url.starts_with("javascript:")
-                        {
-                            continue;
-                        }
+    let html = read_html(path)?;
+    // Stop parsing once the receiver is dropped
+    for_each_html_bookmark(&html, |bookmark| tx.send(bookmark).is_ok())
+}
 
-                        let title =
-                            utils::trim_both_simd(tag.inner_text(parser).as_ref()).to_string();
-
-                        // Extract tags from TAGS attribute or use folder path
-                        let tags = if let Some(tags_attr) = tag
-                            .attributes()
-                            .get("TAGS")
-                            .or_else(|| tag.attributes().get("tags"))
-                        {
-                            tags_attr
-                                .map(|t| format!(",{},", t.as_utf8_str().trim_matches(',')))
-                                .unwrap_or_else(|| {
-                                    if folder_stack.is_empty() {
-                                        ",".to_string()
-                                    } else {
-                                        format!(",{},", folder_stack.join(","))
-                                    }
-                                })
-                        } else if folder_stack.is_empty() {
-                            ",".to_string()
-                        } else {
-                            format!(",{},", folder_stack.join(","))
-                        };
-
-                        let bookmark = ParsedBookmark {
-                            url,
-                            title,
-                            tags,
-                            desc: String::new(),
-                            parent_id: None, // Default to None for now
-                        };
-
-                        // Send to channel, blocking if full
-                        if tx.send(bookmark).is_err() {
-                            // Receiver dropped, stop parsing
-                            return Ok(());
-                        }
-                    }
-                }
-                // /DL closes a folder level
-                "/DL" | "/dl" => {
-                    folder_stack.pop();
-                }
-                _ => {}
-            }
-        }
-    }
+/// Parse a Netscape bookmark file's contents
+pub fn parse_html(html: &str) -> crate::error::Result<Vec<ParsedBookmark>> {
+    let mut bookmarks = Vec::new();
+    for_each_html_bookmark(html, |bookmark| {
+        bookmarks.push(bookmark);
+        true
+    })?;
+    Ok(bookmarks)
+}
 
+/// Hand each bookmark in `html` to `emit`, in document order, until it returns false
+fn for_each_html_bookmark(
+    html: &str,
+    emit: impl FnMut(ParsedBookmark) -> bool,
+) -> crate::error::Result<()> {
+    netscape::for_each_bookmark(html, emit);
     Ok(())
 }
 
 /// Parse HTML bookmarks without inserting into database (non-streaming version for backward compatibility)
 pub fn parse_html_bookmarks(path: &Path) -> Result<Vec<ParsedBookmark>, crate::error::BukursError> {
-    let (tx, rx) = sync_channel(1000);
-    let path_buf = path.to_path_buf();
+    parse_html(&read_html(path)?)
+}
 
-    std::thread::spawn(move || {
-        let _ = parse_html_bookmarks_stream(&path_buf, tx);
-    });
+/// Read a bookmark file in the charset it declares, or UTF-8 and Windows-1252 without one
+fn read_html(path: &Path) -> crate::error::Result<String> {
+    Ok(netscape::decode(&std::fs::read(path)?))
+}
 
-    let mut bookmarks = Vec::new();
-    while let Ok(bookmark) = rx.recv() {
-        bookmarks.push(bookmark);
+impl From<CanonicalBookmark> for ParsedBookmark {
+    fn from(canonical: CanonicalBookmark) -> Self {
+        Self {
+            tags: canonical.stored_tags(),
+            url: canonical.url,
+            title: canonical.title,
+            desc: canonical.desc,
+            parent_id: canonical.parent,
+            created_at: canonical.created_at,
+            flags: canonical.flags,
+        }
     }
+}
 
-    Ok(bookmarks)
+/// Parse a JSON export: an array of bookmarks in canonical form
+///
+/// Bookmark objects one after another, as `-f json` prints them, are read
+/// too, with tags as a list or the stored `,a,b,` string.
+pub fn parse_json(json: &str) -> crate::error::Result<Vec<ParsedBookmark>> {
+    let mut records = Vec::new();
+    for value in serde_json::Deserializer::from_str(json).into_iter::<serde_json::Value>() {
+        match value? {
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    records.push(serde_json::from_value::<CanonicalBookmark>(item)?);
+                }
+            }
+            item => records.push(serde_json::from_value(item)?),
+        }
+    }
+    Ok(records.into_iter().map(ParsedBookmark::from).collect())
+}
+
+/// Parse a JSON export file
+pub fn parse_json_bookmarks(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
+    parse_json(&std::fs::read_to_string(path)?)
+}
+
+/// Canonical JSON importer
+#[cfg(feature = "full")]
+pub struct JsonImporter;
+
+#[cfg(feature = "full")]
+impl BookmarkImporter for JsonImporter {
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
+        let bookmarks = parse_json_bookmarks(path)?;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
+    }
 }
 
+/// Number of bookmarks each worker commits per transaction during parallel import
+pub const IMPORT_CHUNK_SIZE: usize = 500;
+
 /// Import bookmarks in parallel using multiple threads and streaming
+#[cfg(feature = "full")]
 pub fn import_bookmarks_parallel(
     db: &BukuDb,
     file_path: &str,
     num_threads: usize,
-) -> crate::error::Result<usize> {
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
+    import_bookmarks_parallel_with_progress(
+        db,
+        file_path,
+        num_threads,
+        on_duplicate,
+        &NoProgress,
+        &CancellationToken::new(),
+        None,
+    )
+}
+
+/// Import bookmarks in parallel with progress callback
+/// Each worker commits chunks of `IMPORT_CHUNK_SIZE` bookmarks in their own transaction,
+/// so an interrupted import keeps every chunk committed before it (and undo reverts one chunk)
+/// Progress advances by each committed chunk; the total isn't known while streaming
+/// Cancelling stops the workers, rolling back the chunks they were writing;
+/// the returned stats then cover the committed chunks and have `cancelled` set
+/// `report` gets the skipped and merged bookmarks of each committed chunk
+#[cfg(feature = "full")]
+pub fn import_bookmarks_parallel_with_progress(
+    db: &BukuDb,
+    file_path: &str,
+    num_threads: usize,
+    on_duplicate: DuplicateStrategy,
+    progress: &dyn ProgressSink,
+    cancel: &CancellationToken,
+    report: Option<&ImportReport>,
+) -> crate::error::Result<ImportStats> {
     let path = Path::new(file_path).to_path_buf();
     // Create a bounded channel for backpressure (buffer size 100)
-    let (tx, rx) = sync_channel::<ParsedBookmark>(100);
+    let (tx, rx) = std::sync::mpsc::sync_channel::<ParsedBookmark>(100);
 
     // Spawn Producer (Parser) in a separate thread
     thread::spawn(move || {
-        if let Err(e) = parse_html_bookmarks_stream(&path, tx) {
+        let result = if is_html(&path) {
+            parse_html_bookmarks_stream(&path, tx)
+        } else {
+            // Text formats are small enough to parse up front
+            parse_bookmarks_file(&path).map(|bookmarks| {
+                for bookmark in bookmarks {
+                    if tx.send(bookmark).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+        if let Err(e) = result {
             eprintln!("Error parsing bookmarks: {}", e);
         }
     });
 
     let num_threads = num_threads.max(1);
-    let rx = Arc::new(Mutex::new(rx));
-    let imported_count = Arc::new(Mutex::new(0));
-    let db_path = db.get_path().to_path_buf();
-
-    // Spawn Consumers (Workers)
-    let handles: Vec<_> = (0..num_threads)
-        .map(|_| {
-            let rx = Arc::clone(&rx);
-            let imported = Arc::clone(&imported_count);
-            let db_path = db_path.clone();
-
-            thread::spawn(move || {
-                // Each thread opens its own DB connection
-                if let Ok(thread_db) = BukuDb::open(&db_path) {
-                    let mut local_count = 0;
-
-                    loop {
-                        // Critical section: get next item from channel
-                        let bookmark = {
-                            let lock = rx.lock().unwrap();
-                            match lock.recv() {
-                                Ok(b) => b,
-                                Err(_) => break, // Channel closed and empty
-                            }
-                        };
-
-                        // Insert into DB (outside lock)
-                        match thread_db.add_rec(
-                            &bookmark.url,
-                            &bookmark.title,
-                            &bookmark.tags,
-                            &bookmark.desc,
-                            bookmark.parent_id,
-                        ) {
-                            Ok(_) => local_count += 1,
-                            Err(rusqlite::Error::SqliteFailure(err, _))
-                                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
-                            {
-                                // Skip duplicates
-                            }
-                            Err(_) => {} // Skip errors but continue
-                        }
-                    }
-
-                    *imported.lock().unwrap() += local_count;
-                }
-            })
-        })
-        .collect();
+    let rx = Mutex::new(rx);
+    let total = Mutex::new(ImportStats::default());
+    let db_path = db.get_path();
+    let autotag = db.autotag_rules();
+    let audit = db.audit_log();
+    let options = db.connection_options();
+
+    progress.start(&format!("Importing from {}", file_path), None);
+
+    // Commit the parsed bookmarks to `db` chunk by chunk until the parser is done
+    let drain = |db: &BukuDb| {
+        while !cancel.is_cancelled() {
+            // Critical section: drain the next chunk from the channel
+            let chunk: Vec<ParsedBookmark> = {
+                let lock = rx.lock().unwrap();
+                lock.iter().take(IMPORT_CHUNK_SIZE).collect()
+            };
+            if chunk.is_empty() {
+                break; // Channel closed and empty
+            }
 
-    // Wait for all threads to complete
-    for handle in handles {
-        handle.join().unwrap();
+            // Insert into DB (outside lock)
+            let result = db.import_rec_batch_reported(
+                &chunk,
+                on_duplicate,
+                cancel,
+                |_url| {},
+                |entry| record(report, &entry),
+            );
+            match result {
+                Ok(Some(stats)) => *total.lock().unwrap() += stats,
+                Ok(None) => break,
+                Err(e) => eprintln!("Error importing bookmarks: {}", e),
+            }
+            progress.advance(chunk.len() as u64, None);
+        }
+    };
+
+    if db.is_in_memory() {
+        // Other connections would open a new, empty database; import on this one
+        drain(db);
+    } else {
+        // Spawn Consumers (Workers)
+        thread::scope(|scope| {
+            for _ in 0..num_threads {
+                scope.spawn(|| {
+                    // Each thread opens its own DB connection
+                    let Ok(mut thread_db) = BukuDb::open_with_options(db_path, options) else {
+                        return;
+                    };
+                    thread_db.set_autotag_rules(autotag.clone());
+                    thread_db.set_audit_log(audit.cloned());
+                    drain(&thread_db);
+                });
+            }
+        });
     }
 
-    let count = *imported_count.lock().unwrap();
-    Ok(count)
+    progress.finish();
+    let mut stats = *total.lock().unwrap();
+    stats.cancelled = cancel.is_cancelled();
+    Ok(stats)
 }
 
 /// HTML/Netscape Bookmark File importer
+#[cfg(feature = "full")]
 pub struct HtmlImporter;
 
+#[cfg(feature = "full")]
 impl BookmarkImporter for HtmlImporter {
-    fn import(&self, db: &BukuDb, path: &Path) -> crate::error::Result<usize> {
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
         // Use the new parsing function
         let bookmarks = parse_html_bookmarks(path)?;
-        let mut imported_count = 0;
-
-        for bookmark in bookmarks {
-            match db.add_rec(
-                &bookmark.url,
-                &bookmark.title,
-                &bookmark.tags,
-                &bookmark.desc,
-                bookmark.parent_id,
-            ) {
-                Ok(_) => imported_count += 1,
-                Err(rusqlite::Error::SqliteFailure(err, _))
-                    if err.code == rusqlite::ErrorCode::ConstraintViolation =>
-                {
-                    // Skip duplicate URLs
-                    continue;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+/// Anything that isn't Markdown, Org or JSON is treated as a Netscape HTML export
+#[cfg(feature = "full")]
+fn is_html(path: &Path) -> bool {
+    !matches!(extension(path).as_str(), "md" | "markdown" | "org" | "json")
+}
+
+/// Parse a bookmark file, choosing the format by file extension
+pub fn parse_bookmarks_file(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
+    match extension(path).as_str() {
+        "md" | "markdown" => markup::parse_markdown_bookmarks(path),
+        "org" => markup::parse_org_bookmarks(path),
+        "json" => parse_json_bookmarks(path),
+        _ => parse_html_bookmarks(path),
+    }
+}
+
+/// Import bookmarks from a file (single-threaded)
+/// The format is chosen by extension: `.md`/`.markdown`, `.org`, `.json`, otherwise HTML
+#[cfg(feature = "full")]
+pub fn import_bookmarks(
+    db: &BukuDb,
+    file_path: &str,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportStats> {
+    let path = Path::new(file_path);
+
+    let importer: Box<dyn BookmarkImporter> = match extension(path).as_str() {
+        "md" | "markdown" => Box::new(markup::MarkdownImporter),
+        "org" => Box::new(markup::OrgImporter),
+        "json" => Box::new(JsonImporter),
+        _ => Box::new(HtmlImporter),
+    };
+
+    importer.import(db, path, on_duplicate)
+}
+
+/// Import bookmarks from a file (single-threaded), reporting each bookmark to
+/// `progress` and the skipped and merged ones to `report`
+#[cfg(feature = "full")]
+pub fn import_bookmarks_with_progress(
+    db: &BukuDb,
+    file_path: &str,
+    on_duplicate: DuplicateStrategy,
+    progress: &dyn ProgressSink,
+    report: Option<&ImportReport>,
+) -> crate::error::Result<ImportStats> {
+    let bookmarks = parse_bookmarks_file(Path::new(file_path))?;
+    progress.start(
+        &format!("Importing from {}", file_path),
+        Some(bookmarks.len() as u64),
+    );
+    let stats = db.import_rec_batch_reported(
+        &bookmarks,
+        on_duplicate,
+        &CancellationToken::new(),
+        |url| progress.advance(1, Some(url)),
+        |entry| record(report, &entry),
+    )?;
+    progress.finish();
+    Ok(stats.unwrap_or_default())
+}
+
+#[cfg(feature = "full")]
+fn record(report: Option<&ImportReport>, entry: &ReportEntry) {
+    if let Some(report) = report {
+        report.record(entry);
+    }
+}
+
+/// What an import would do with a single parsed bookmark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    /// The URL is new and would be inserted
+    Add,
+    /// The URL already exists (in the database or earlier in the source) and would be skipped
+    Skip,
+    /// The URL already exists and would have its tags merged
+    Merge,
+    /// The URL already exists and would be overwritten
+    Overwrite,
+    /// The URL can't be bookmarked and would be skipped
+    Invalid,
+}
+
+/// A single line of an import preview
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewEntry {
+    pub action: ImportAction,
+    /// ID of the existing bookmark with the same URL, if any
+    pub existing_id: Option<usize>,
+    #[serde(flatten)]
+    pub bookmark: ParsedBookmark,
+}
+
+/// Result of a dry-run import: what would happen without touching the database
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ImportPreview {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub entries: Vec<PreviewEntry>,
+}
+
+/// Classify parsed bookmarks against the database without writing anything
+#[cfg(feature = "full")]
+pub fn preview_import(
+    db: &BukuDb,
+    bookmarks: Vec<ParsedBookmark>,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportPreview> {
+    // Working copy of the database so repeats within the source see earlier changes
+    let mut existing: HashMap<String, Bookmark> = db
+        .get_rec_all()?
+        .into_iter()
+        .map(|b| (b.url.clone(), b))
+        .collect();
+
+    let mut preview = ImportPreview::default();
+
+    for bookmark in bookmarks {
+        if !is_importable_url(&bookmark.url) {
+            preview.skipped += 1;
+            preview.entries.push(PreviewEntry {
+                action: ImportAction::Invalid,
+                existing_id: None,
+                bookmark,
+            });
+            continue;
+        }
+
+        let existing_id;
+        let action = match existing.get_mut(&bookmark.url) {
+            Some(current) => {
+                existing_id = (current.id != 0).then_some(current.id);
+                match resolve_duplicate(current, &bookmark, on_duplicate) {
+                    Some(resolved) => {
+                        *current = resolved;
+                        preview.updated += 1;
+                        match on_duplicate {
+                            DuplicateStrategy::Overwrite => ImportAction::Overwrite,
+                            _ => ImportAction::Merge,
+                        }
+                    }
+                    None => {
+                        preview.skipped += 1;
+                        ImportAction::Skip
+                    }
                 }
-                Err(e) => return Err(e.into()),
             }
-        }
+            None => {
+                existing_id = None;
+                // ID 0 marks a bookmark that only exists earlier in the source
+                existing.insert(
+                    bookmark.url.clone(),
+                    Bookmark::new(
+                        0,
+                        bookmark.url.clone(),
+                        bookmark.title.clone(),
+                        bookmark.tags.clone(),
+                        bookmark.desc.clone(),
+                    ),
+                );
+                preview.added += 1;
+                ImportAction::Add
+            }
+        };
 
-        Ok(imported_count)
+        preview.entries.push(PreviewEntry {
+            action,
+            existing_id,
+            bookmark,
+        });
     }
+
+    Ok(preview)
 }
 
-/// Import bookmarks from browser HTML export file (single-threaded)
-pub fn import_bookmarks(db: &BukuDb, file_path: &str) -> crate::error::Result<usize> {
-    let path = Path::new(file_path);
-    let importer = HtmlImporter;
-    importer.import(db, path)
+/// Preview importing a bookmark file
+#[cfg(feature = "full")]
+pub fn preview_bookmarks(
+    db: &BukuDb,
+    file_path: &str,
+    on_duplicate: DuplicateStrategy,
+) -> crate::error::Result<ImportPreview> {
+    let bookmarks = parse_bookmarks_file(Path::new(file_path))?;
+    preview_import(db, bookmarks, on_duplicate)
+}
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressEvent;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_html(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parse_html_keeps_folders_as_tags() {
+        let bookmarks = parse_html(
+            r#"<DL><p>
+            <DT><H3>Dev</H3>
+            <DL><p>
+                <DT><A HREF="https://rust-lang.org">Rust</A>
+                <DT><A HREF="javascript:void(0)">Bookmarklet</A>
+            </DL><p>
+            <DT><A HREF="https://example.com" TAGS="misc">Example</A>
+            </DL><p>"#,
+        )
+        .unwrap();
+
+        let parsed: Vec<(&str, &str, &str)> = bookmarks
+            .iter()
+            .map(|b| (b.url.as_str(), b.title.as_str(), b.tags.as_str()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("https://rust-lang.org", "Rust", ",Dev,"),
+                // Left for the import to skip and report
+                ("javascript:void(0)", "Bookmarklet", ",Dev,"),
+                ("https://example.com", "Example", ",misc,"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let parsed = parse_json(
+            r#"[
+                {"id": 4, "url": "https://rust-lang.org", "title": "Rust", "tags": ["lang", "web, dev"], "flags": 1, "created_at": 1700000000},
+                {"url": "https://example.com"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].tags, ",lang,web,dev,");
+        assert_eq!(
+            (parsed[0].flags, parsed[0].created_at),
+            (1, Some(1_700_000_000))
+        );
+        assert_eq!(
+            (
+                parsed[1].title.as_str(),
+                parsed[1].tags.as_str(),
+                parsed[1].created_at
+            ),
+            ("", ",", None)
+        );
+
+        // `-f json` output, with raw tags from `--raw-tags`
+        let parsed = parse_json(
+            r#"{"id": 1, "url": "https://a.com", "title": "A", "tags": ["x"], "description": "Notes"}
+            {"id": 2, "url": "https://b.com", "title": "B", "tags": ",y,z,", "description": ""}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|b| (b.url.as_str(), b.tags.as_str(), b.desc.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("https://a.com", ",x,", "Notes"),
+                ("https://b.com", ",y,z,", "")
+            ]
+        );
+        assert!(parse_json(r#"[{"title": "No URL"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_preview_does_not_write() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://existing.com", "Existing", ",", "", None)
+            .unwrap();
+
+        let file = write_html(
+            r#"<DL><p>
+            <DT><A HREF="https://existing.com">Existing</A>
+            <DT><A HREF="https://new.com">New</A>
+            <DT><A HREF="https://new.com">New again</A>
+            <DT><A HREF="place:sort=8">Recent Tags</A>
+            </DL><p>"#,
+        );
+
+        let preview =
+            preview_bookmarks(&db, file.path().to_str().unwrap(), DuplicateStrategy::Skip).unwrap();
+        assert_eq!(preview.added, 1);
+        assert_eq!(preview.skipped, 3);
+        assert_eq!(preview.entries[0].action, ImportAction::Skip);
+        assert_eq!(preview.entries[0].existing_id, Some(1));
+        assert_eq!(preview.entries[1].action, ImportAction::Add);
+        assert_eq!(preview.entries[2].action, ImportAction::Skip);
+        assert_eq!(preview.entries[3].action, ImportAction::Invalid);
+
+        // Nothing was inserted
+        assert_eq!(db.get_rec_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_preview_serializes_action() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let file = write_html(r#"<DT><A HREF="https://rust-lang.org">Rust</A>"#);
+
+        let preview =
+            preview_bookmarks(&db, file.path().to_str().unwrap(), DuplicateStrategy::Skip).unwrap();
+        let json = serde_json::to_string(&preview).unwrap();
+        assert!(json.contains("\"action\":\"add\""));
+        assert!(json.contains("\"url\":\"https://rust-lang.org\""));
+    }
+
+    #[test]
+    fn test_preview_merge_reports_updates() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://existing.com", "Existing", ",rust,", "", None)
+            .unwrap();
+
+        let file = write_html(
+            r#"<DL><p>
+            <DT><A HREF="https://existing.com" TAGS="web">Existing</A>
+            <DT><A HREF="https://existing.com" TAGS="rust">Existing</A>
+            </DL><p>"#,
+        );
+
+        let preview =
+            preview_bookmarks(&db, file.path().to_str().unwrap(), DuplicateStrategy::Merge)
+                .unwrap();
+        assert_eq!(preview.updated, 1);
+        assert_eq!(preview.skipped, 1);
+        assert_eq!(preview.entries[0].action, ImportAction::Merge);
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().tags, ",rust,");
+    }
+
+    #[test]
+    fn test_parallel_import_commits_chunks_with_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BukuDb::init(&dir.path().join("bookmarks.db")).unwrap();
+        db.add_rec("https://example.com/0", "Existing", ",", "", None)
+            .unwrap();
+
+        let total = IMPORT_CHUNK_SIZE * 2 + 7;
+        let links: String = (0..total)
+            .map(|i| format!("<DT><A HREF=\"https://example.com/{}\">{}</A>\n", i, i))
+            .collect();
+        let file = write_html(&format!("<DL><p>\n{}</DL><p>", links));
+
+        let processed = std::sync::atomic::AtomicU64::new(0);
+        let stats = import_bookmarks_parallel_with_progress(
+            &db,
+            file.path().to_str().unwrap(),
+            4,
+            DuplicateStrategy::Skip,
+            &|event: ProgressEvent<'_>| {
+                if let ProgressEvent::Advance { count, .. } = event {
+                    processed.fetch_add(count, std::sync::atomic::Ordering::Relaxed);
+                }
+            },
+            &CancellationToken::new(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(stats.added, total - 1);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(processed.into_inner(), total as u64);
+        assert_eq!(db.get_rec_all().unwrap().len(), total);
+    }
+
+    #[test]
+    fn test_parallel_import_into_memory() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let links: String = (0..IMPORT_CHUNK_SIZE + 3)
+            .map(|i| format!("<DT><A HREF=\"https://example.com/{}\">{}</A>\n", i, i))
+            .collect();
+        let file = write_html(&format!("<DL><p>\n{}</DL><p>", links));
+
+        let stats = import_bookmarks_parallel_with_progress(
+            &db,
+            file.path().to_str().unwrap(),
+            4,
+            DuplicateStrategy::Skip,
+            &NoProgress,
+            &CancellationToken::new(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(stats.added, IMPORT_CHUNK_SIZE + 3);
+        assert_eq!(db.get_rec_all().unwrap().len(), IMPORT_CHUNK_SIZE + 3);
+    }
+
+    #[test]
+    fn test_import_reports_each_bookmark() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let file = write_html(
+            "<DL><p>\n<DT><A HREF=\"https://a.com\">A</A>\n<DT><A HREF=\"https://b.com\">B</A>\n</DL><p>",
+        );
+
+        let events = Mutex::new(Vec::new());
+        let stats = import_bookmarks_with_progress(
+            &db,
+            file.path().to_str().unwrap(),
+            DuplicateStrategy::Skip,
+            &|event: ProgressEvent<'_>| {
+                events.lock().unwrap().push(match event {
+                    ProgressEvent::Start { total, .. } => format!("start {:?}", total),
+                    ProgressEvent::Advance { item, .. } => item.unwrap_or_default().to_string(),
+                    ProgressEvent::Finish => "finish".to_string(),
+                });
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(stats.added, 2);
+        assert_eq!(
+            events.into_inner().unwrap(),
+            vec!["start Some(2)", "https://a.com", "https://b.com", "finish"]
+        );
+    }
+
+    #[test]
+    fn test_import_writes_report() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "A", ",", "", None).unwrap();
+        let file = write_html(
+            r#"<DL><p>
+            <DT><A HREF="https://a.com">A again</A>
+            <DT><A HREF="https://b.com">B</A>
+            <DT><A HREF="javascript:void(0)">Bookmarklet</A>
+            </DL><p>"#,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let report_path = dir.path().join("skipped.jsonl");
+
+        let report = ImportReport::create(&report_path).unwrap();
+        let stats = import_bookmarks_with_progress(
+            &db,
+            file.path().to_str().unwrap(),
+            DuplicateStrategy::Skip,
+            &NoProgress,
+            Some(&report),
+        )
+        .unwrap();
+        assert_eq!((stats.added, stats.skipped), (1, 2));
+        assert_eq!(report.finish().unwrap(), 2);
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&report_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({"reason": "duplicate", "url": "https://a.com", "title": "A again", "existing_id": 1}),
+                serde_json::json!({"reason": "invalid_url", "url": "javascript:void(0)", "title": "Bookmarklet"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parallel_import_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BukuDb::init(&dir.path().join("bookmarks.db")).unwrap();
+        let file = write_html("<DL><p>\n<DT><A HREF=\"https://a.com\">A</A>\n</DL><p>");
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let stats = import_bookmarks_parallel_with_progress(
+            &db,
+            file.path().to_str().unwrap(),
+            2,
+            DuplicateStrategy::Skip,
+            &NoProgress,
+            &cancel,
+            None,
+        )
+        .unwrap();
+
+        assert!(stats.cancelled);
+        assert_eq!(stats.added, 0);
+        assert!(db.get_rec_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parallel_import_is_audited() {
+        use crate::audit::{AuditLog, AuditOperation, AuditSource};
+
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("audit.jsonl");
+        let mut db = BukuDb::init(&dir.path().join("bookmarks.db")).unwrap();
+        db.set_audit_log(Some(AuditLog::new(&audit_path, AuditSource::Cli)));
+
+        let file = write_html(
+            r#"<DL><p>
+            <DT><A HREF="https://a.com">A</A>
+            <DT><A HREF="https://b.com">B</A>
+            </DL><p>"#,
+        );
+        import_bookmarks_parallel(
+            &db,
+            file.path().to_str().unwrap(),
+            2,
+            DuplicateStrategy::Skip,
+        )
+        .unwrap();
+
+        let entries = AuditLog::read(&audit_path).unwrap();
+        assert!(entries
+            .iter()
+            .all(|e| e.operation == AuditOperation::Import));
+        let mut ids: Vec<usize> = entries.into_iter().flat_map(|e| e.ids).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2]);
+    }
 }
@@ -0,0 +1,391 @@
+use super::import::ParsedBookmark;
+#[cfg(feature = "full")]
+use super::import::{BookmarkImporter, DuplicateStrategy, ImportStats};
+#[cfg(feature = "full")]
+use crate::db::BukuDb;
+use crate::tags::{format_tags, parse_tags};
+use std::path::Path;
+
+/// Headings seen so far, as (level, tags); the enclosing headings of a link become its tags
+#[derive(Default)]
+struct HeadingStack(Vec<(usize, Vec<String>)>);
+
+impl HeadingStack {
+    fn push(&mut self, level: usize, tags: Vec<String>) {
+        while self.0.last().is_some_and(|(l, _)| *l >= level) {
+            self.0.pop();
+        }
+        self.0.push((level, tags));
+    }
+
+    fn tags(&self) -> String {
+        let mut tags: Vec<&String> = Vec::new();
+        for tag in self.0.iter().flat_map(|(_, t)| t) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        format_tags(&tags)
+    }
+}
+
+/// Only absolute links are bookmarks; anchors and relative paths are skipped
+fn is_bookmark_url(url: &str) -> bool {
+    url.contains("://")
+}
+
+/// Strip a Markdown list marker (`-`, `*`, `+`, `1.` or `1)`)
+fn strip_list_marker(line: &str) -> Option<&str> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return Some(rest.trim_start());
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim_start)
+}
+
+/// Parse `[title](url) rest`, returning (title, url, rest)
+fn parse_markdown_link(s: &str) -> Option<(String, String, &str)> {
+    let s = s.strip_prefix('[')?;
+
+    // Title runs to the matching `]`, honouring nested brackets and backslash escapes
+    let mut title = String::new();
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut title_end = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => {
+                title.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '[' => {
+                depth += 1;
+                title.push(c);
+            }
+            ']' if depth == 0 => {
+                title_end = Some(i);
+                break;
+            }
+            ']' => {
+                depth -= 1;
+                title.push(c);
+            }
+            _ => title.push(c),
+        }
+    }
+    let s = s[title_end? + 1..].strip_prefix('(')?;
+
+    // URL runs to the matching `)`, so links like Wikipedia's `Foo_(bar)` survive
+    let mut depth = 0;
+    let mut url_end = None;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                url_end = Some(i);
+                break;
+            }
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    let url_end = url_end?;
+
+    // Drop an optional link title: [text](url "title")
+    let target = s[..url_end].split_whitespace().next().unwrap_or("");
+    let url = target.trim_start_matches('<').trim_end_matches('>');
+
+    Some((title.trim().to_string(), url.to_string(), &s[url_end + 1..]))
+}
+
+/// Text after a link, minus the separator that introduces it
+fn trailing_desc(rest: &str) -> String {
+    rest.trim()
+        .trim_start_matches(['-', ':', '—', '–'])
+        .trim()
+        .to_string()
+}
+
+/// Parse a Markdown list of `- [title](url)` links; headings become tags
+pub fn parse_markdown(content: &str) -> Vec<ParsedBookmark> {
+    let mut headings = HeadingStack::default();
+    let mut bookmarks = Vec::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            let text = line[level..].trim().trim_end_matches('#').trim();
+            headings.push(level, parse_tags(text));
+            continue;
+        }
+
+        let Some(item) = strip_list_marker(line) else {
+            continue;
+        };
+        let Some((title, url, rest)) = parse_markdown_link(item) else {
+            continue;
+        };
+        if !is_bookmark_url(&url) {
+            continue;
+        }
+
+        bookmarks.push(ParsedBookmark {
+            url,
+            title,
+            tags: headings.tags(),
+            desc: trailing_desc(rest),
+            parent_id: None,
+            created_at: None,
+            flags: 0,
+        });
+    }
+
+    bookmarks
+}
+
+/// Split an org heading into its text and trailing `:tag1:tag2:` tags
+fn split_org_heading(text: &str) -> (&str, Vec<String>) {
+    if let Some((head, last)) = text.rsplit_once(char::is_whitespace) {
+        if last.len() > 1 && last.starts_with(':') && last.ends_with(':') {
+            return (head.trim_end(), parse_tags(last.replace(':', ",")));
+        }
+    }
+    (text, Vec::new())
+}
+
+/// Parse org-mode `[[url][title]]` links; headings and their `:tags:` become tags
+pub fn parse_org(content: &str) -> Vec<ParsedBookmark> {
+    let mut headings = HeadingStack::default();
+    let mut bookmarks = Vec::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let lower = line.trim_start().to_lowercase();
+        if lower.starts_with("#+begin_") {
+            in_block = true;
+            continue;
+        }
+        if lower.starts_with("#+end_") {
+            in_block = false;
+            continue;
+        }
+        if in_block {
+            continue;
+        }
+
+        let level = line.chars().take_while(|c| *c == '*').count();
+        if level > 0 && line[level..].starts_with(' ') {
+            let (text, org_tags) = split_org_heading(line[level..].trim());
+            let mut tags = parse_tags(text);
+            tags.extend(org_tags);
+            headings.push(level, tags);
+            continue;
+        }
+
+        // A description list item (`- [[url][title]] :: desc`) describes its link
+        let desc = line
+            .split_once(" :: ")
+            .map(|(_, d)| d.trim().to_string())
+            .unwrap_or_default();
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let Some(mut len) = rest[start..].find("]]") else {
+                break;
+            };
+            // A description may itself end in `]`, as in `[[url][Python [3]]]`
+            while rest[start + len + 2..].starts_with(']') {
+                len += 1;
+            }
+            let inner = &rest[start + 2..start + len];
+            rest = &rest[start + len + 2..];
+
+            let (url, title) = inner.split_once("][").unwrap_or((inner, ""));
+            if !is_bookmark_url(url) {
+                continue;
+            }
+
+            bookmarks.push(ParsedBookmark {
+                url: url.trim().to_string(),
+                title: title.trim().to_string(),
+                tags: headings.tags(),
+                desc: desc.clone(),
+                parent_id: None,
+                created_at: None,
+                flags: 0,
+            });
+        }
+    }
+
+    bookmarks
+}
+
+/// Parse a Markdown link list file
+pub fn parse_markdown_bookmarks(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
+    Ok(parse_markdown(&std::fs::read_to_string(path)?))
+}
+
+/// Parse an org-mode file
+pub fn parse_org_bookmarks(path: &Path) -> crate::error::Result<Vec<ParsedBookmark>> {
+    Ok(parse_org(&std::fs::read_to_string(path)?))
+}
+
+/// Markdown link list importer
+#[cfg(feature = "full")]
+pub struct MarkdownImporter;
+
+#[cfg(feature = "full")]
+impl BookmarkImporter for MarkdownImporter {
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
+        let bookmarks = parse_markdown_bookmarks(path)?;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
+    }
+}
+
+/// Org-mode link importer
+#[cfg(feature = "full")]
+pub struct OrgImporter;
+
+#[cfg(feature = "full")]
+impl BookmarkImporter for OrgImporter {
+    fn import(
+        &self,
+        db: &BukuDb,
+        path: &Path,
+        on_duplicate: DuplicateStrategy,
+    ) -> crate::error::Result<ImportStats> {
+        let bookmarks = parse_org_bookmarks(path)?;
+        Ok(db.import_rec_batch(&bookmarks, on_duplicate, |_url| {})?)
+    }
+}
+
+/// Escape text for HTML or XML element content and quoted attribute values
+#[cfg(feature = "full")]
+pub(crate) fn escape_markup(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[cfg(feature = "full")]
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case(
+        r#"Fish & "Chips" <it's>"#,
+        "Fish &amp; &quot;Chips&quot; &lt;it&#39;s&gt;"
+    )]
+    fn test_escape_markup(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(escape_markup(text), expected);
+    }
+
+    #[rstest]
+    #[case("[Rust](https://rust-lang.org)", "Rust", "https://rust-lang.org", "")]
+    #[case(
+        "[Foo](https://en.wikipedia.org/wiki/Foo_(bar)) - disambiguation",
+        "Foo",
+        "https://en.wikipedia.org/wiki/Foo_(bar)",
+        " - disambiguation"
+    )]
+    #[case(r"[a \] b](https://x.com)", "a ] b", "https://x.com", "")]
+    #[case("[Docs](<https://docs.rs> \"title\")", "Docs", "https://docs.rs", "")]
+    fn test_parse_markdown_link(
+        #[case] input: &str,
+        #[case] title: &str,
+        #[case] url: &str,
+        #[case] rest: &str,
+    ) {
+        let (t, u, r) = parse_markdown_link(input).unwrap();
+        assert_eq!((t.as_str(), u.as_str(), r), (title, url, rest));
+    }
+
+    #[test]
+    fn test_parse_markdown_headings_become_tags() {
+        let content = "\
+- [Top](https://top.com)
+
+# Dev
+## Rust, Web
+- [Rust](https://rust-lang.org) - The language
+* [Relative](./notes.md)
+
+# Reading
+1. [Blog](https://blog.com)
+
+```
+- [Code](https://code.com)
+```
+";
+        let bookmarks = parse_markdown(content);
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].tags, ",");
+        assert_eq!(bookmarks[1].tags, ",Dev,Rust,Web,");
+        assert_eq!(bookmarks[1].desc, "The language");
+        assert_eq!(bookmarks[2].url, "https://blog.com");
+        assert_eq!(bookmarks[2].tags, ",Reading,");
+    }
+
+    #[test]
+    fn test_parse_org_headings_and_tags() {
+        let content = "\
+* Dev :rust:
+** Tools
+- [[https://crates.io][Crates]] :: Package registry
+- See [[https://docs.rs]] and [[https://lib.rs][Lib.rs [beta]]]
+#+BEGIN_SRC
+[[https://ignored.com][Ignored]]
+#+END_SRC
+* Misc
+[[file:notes.org][Notes]]
+";
+        let bookmarks = parse_org(content);
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].title, "Crates");
+        assert_eq!(bookmarks[0].tags, ",Dev,rust,Tools,");
+        assert_eq!(bookmarks[0].desc, "Package registry");
+        assert_eq!(bookmarks[1].url, "https://docs.rs");
+        assert_eq!(bookmarks[1].title, "");
+        assert_eq!(bookmarks[2].title, "Lib.rs [beta]");
+    }
+}
@@ -1,12 +1,45 @@
+#[cfg(feature = "full")]
 pub mod browser;
+#[cfg(feature = "full")]
+pub mod dump;
 pub mod export;
+#[cfg(feature = "full")]
+pub mod feed;
+#[cfg(feature = "full")]
+pub mod history;
 pub mod import;
+pub mod markup;
+mod netscape;
+#[cfg(feature = "full")]
+pub mod site;
 
 // Re-export main functions for convenience
-pub use export::export_bookmarks;
-pub use import::{import_bookmarks, import_bookmarks_parallel};
+#[cfg(feature = "full")]
+pub use dump::{Dump, DumpOptions, RestoreOptions, RestoreStats};
+#[cfg(feature = "full")]
+pub use export::{
+    export_bookmarks, export_bookmarks_with_progress, export_filtered,
+    export_selection_with_progress,
+};
+pub use export::{write_html, write_html_with_aliases, write_json, write_markdown, write_org};
+#[cfg(feature = "full")]
+pub use feed::{export_feed, export_feed_selection, FeedFormat, DEFAULT_FEED_LIMIT};
+#[cfg(feature = "full")]
+pub use import::{
+    import_bookmarks, import_bookmarks_parallel, import_bookmarks_parallel_with_progress,
+    import_bookmarks_with_progress, preview_bookmarks, ImportReport,
+};
+pub use import::{
+    is_importable_url, parse_html, parse_json, DuplicateStrategy, ImportAction, ImportPreview,
+    ImportStats, ReportEntry, ReportReason,
+};
+pub use markup::{parse_markdown, parse_org};
+#[cfg(feature = "full")]
+pub use site::{publish_site, DEFAULT_SITE_TITLE};
 // Re-export browser detection and import functions (used by CLI)
+#[cfg(feature = "full")]
 pub use browser::{
     auto_import_all, auto_import_all_with_progress, import_from_selected_browsers,
-    import_from_selected_browsers_with_progress, list_detected_browsers,
+    import_from_selected_browsers_with_progress, import_profiles_with_progress,
+    list_detected_browsers, preview_browsers, preview_profiles, BrowserProfile, BrowserType,
 };
@@ -0,0 +1,551 @@
+//! Reading Netscape bookmark files, the HTML browsers export bookmarks as
+//!
+//! Files are parsed with html5ever, the HTML5 parser browsers' own rules
+//! come from, so exports in the wild parse however broken they are: links
+//! and folders left unclosed, stray `<` in text, attributes without quotes.
+//! The markup is loose (`<DT>` and `<p>` are never closed) and the tree the
+//! parser builds doesn't always nest folders inside their entries, so the
+//! tree is walked in document order. A folder is an `<H3>` and the `<DL>`
+//! list after it; a `<DD>` right after a link holds the link's description.
+//!
+//! Files are decoded with encoding_rs, in the charset they declare or as
+//! UTF-8 with Windows-1252 for any bytes that aren't, so one bad entry
+//! doesn't stop the import.
+
+use super::import::ParsedBookmark;
+use crate::tags::{format_tags, parse_tags};
+use ego_tree::iter::Edge;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use scraper::{ElementRef, Html, Node};
+
+/// Elements that make up the bookmark tree
+const ENTRY_TAGS: [&str; 5] = ["dl", "dt", "dd", "h3", "a"];
+
+/// Elements of a list, which never belong to the text of what they're nested in
+const LIST_TAGS: [&str; 3] = ["dl", "dt", "dd"];
+
+/// Elements an unclosed link ends at
+const LINK_CLOSING_TAGS: [&str; 4] = ["dl", "dt", "dd", "h3"];
+
+/// How much of a file is searched for a `<meta>` charset, as browsers do
+const CHARSET_PRESCAN_BYTES: usize = 1024;
+
+/// Decode a bookmark file's bytes
+///
+/// A byte order mark decides the encoding, then a charset declared by a
+/// `<meta>` tag near the top (Latin-1 is read as Windows-1252, as browsers
+/// read it). Anything else is read as UTF-8, with bytes that aren't valid
+/// UTF-8 taken as Windows-1252, which covers legacy exports without a
+/// declaration and files mixing the two.
+pub(crate) fn decode(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return encoding
+            .decode_without_bom_handling(&bytes[bom_len..])
+            .0
+            .into_owned();
+    }
+    if let Some(encoding) = declared_encoding(bytes).filter(|&encoding| encoding != UTF_8) {
+        return encoding.decode_without_bom_handling(bytes).0.into_owned();
+    }
+
+    let mut out = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                out.push_str(valid);
+                return out;
+            }
+            Err(err) => {
+                let (valid, after) = rest.split_at(err.valid_up_to());
+                out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                let bad = err.error_len().unwrap_or(after.len());
+                out.push_str(&WINDOWS_1252.decode_without_bom_handling(&after[..bad]).0);
+                rest = &after[bad..];
+            }
+        }
+    }
+}
+
+/// The encoding a `<meta charset>` or `<meta http-equiv="Content-Type">` tag
+/// at the top of the file declares
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(CHARSET_PRESCAN_BYTES)]);
+    let document = Html::parse_document(&head);
+    let label = document
+        .root_element()
+        .descendent_elements()
+        .filter(|element| element.value().name() == "meta")
+        .find_map(|meta| {
+            meta.attr("charset").or_else(|| {
+                meta.attr("http-equiv")
+                    .filter(|name| name.eq_ignore_ascii_case("content-type"))
+                    .and(meta.attr("content"))
+                    .and_then(content_charset)
+            })
+        })?;
+    // A page can't declare itself UTF-16 in ASCII, so those labels mean UTF-8
+    Encoding::for_label(label.trim().as_bytes()).map(Encoding::output_encoding)
+}
+
+/// The charset parameter of a `Content-Type` value like `text/html; charset=UTF-8`
+fn content_charset(content: &str) -> Option<&str> {
+    content.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+/// Hand each bookmark in `html` to `emit`, in document order, until it returns false
+///
+/// Links take their tags from a `TAGS` attribute, otherwise from the folders
+/// they sit in, outermost first. `ADD_DATE`, or `LAST_MODIFIED` without it,
+/// becomes the time the bookmark was added.
+pub(crate) fn for_each_bookmark(html: &str, mut emit: impl FnMut(ParsedBookmark) -> bool) {
+    let document = Html::parse_document(&close_links(html));
+    // The folder of each open list; None for lists outside a folder
+    let mut lists: Vec<Option<String>> = Vec::new();
+    // A folder name waiting for its list
+    let mut folder: Option<String> = None;
+    // The last link, held back until we know whether a description follows
+    let mut pending: Option<ParsedBookmark> = None;
+
+    for edge in document.tree.root().traverse() {
+        let (opened, node) = match edge {
+            Edge::Open(node) => (true, node),
+            Edge::Close(node) => (false, node),
+        };
+        let Some(element) = ElementRef::wrap(node) else {
+            continue;
+        };
+        let name = element.value().name();
+        if !ENTRY_TAGS.contains(&name) || !(opened || name == "dl") {
+            continue;
+        }
+        if name == "dd" {
+            if let Some(mut bookmark) = pending.take() {
+                bookmark.desc = text(element);
+                if !emit(bookmark) {
+                    return;
+                }
+            }
+            continue;
+        }
+        if let Some(bookmark) = pending.take() {
+            if !emit(bookmark) {
+                return;
+            }
+        }
+        match (name, opened) {
+            ("dl", true) => lists.push(folder.take()),
+            ("dl", false) => {
+                lists.pop();
+            }
+            ("h3", _) => {
+                // Tags can't contain commas
+                folder = Some(text(element).replace(',', "")).filter(|name| !name.is_empty());
+            }
+            ("a", _) => {
+                let Some(url) = element.attr("href") else {
+                    continue;
+                };
+                let tags = match element.attr("tags") {
+                    Some(tags) => format_tags(&parse_tags(tags)),
+                    None => format_tags(&lists.iter().flatten().collect::<Vec<_>>()),
+                };
+                let created_at = ["add_date", "last_modified"]
+                    .iter()
+                    .find_map(|name| element.attr(name).and_then(timestamp));
+                // URLs that can't be bookmarked are left for the import to
+                // skip and report
+                pending = Some(ParsedBookmark {
+                    url: url.trim().to_string(),
+                    title: text(element),
+                    tags,
+                    desc: String::new(),
+                    parent_id: None,
+                    created_at,
+                    flags: 0,
+                });
+            }
+            _ => {}
+        }
+    }
+    if let Some(bookmark) = pending {
+        emit(bookmark);
+    }
+}
+
+/// `html` with a closing `</a>` before every entry, list and folder tag
+///
+/// The parser reopens a link left unclosed around whatever follows the entry
+/// it was in, as a copy with the same attributes that can't be told apart
+/// from a bookmark listed twice. Closing tags with no open link are ignored.
+fn close_links(html: &str) -> String {
+    let mut closed = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(at) = rest.find('<') {
+        let (text, tag) = rest.split_at(at);
+        closed.push_str(text);
+        let name: String = tag[1..]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        if LINK_CLOSING_TAGS.contains(&name.to_ascii_lowercase().as_str()) {
+            closed.push_str("</a>");
+        }
+        closed.push('<');
+        rest = &tag[1..];
+    }
+    closed.push_str(rest);
+    closed
+}
+
+/// The trimmed text of an element, leaving out any list entries the parser
+/// nested inside it, as it does for a folder's `<H3>` left unclosed
+fn text(element: ElementRef) -> String {
+    let mut text = String::new();
+    // How many list elements the current node is inside
+    let mut nested = 0;
+    for edge in element.traverse().skip(1) {
+        match edge {
+            Edge::Open(node) => match node.value() {
+                Node::Element(child) if LIST_TAGS.contains(&child.name()) => nested += 1,
+                Node::Text(chunk) if nested == 0 => text.push_str(chunk),
+                _ => {}
+            },
+            Edge::Close(node) => {
+                if node
+                    .value()
+                    .as_element()
+                    .is_some_and(|child| LIST_TAGS.contains(&child.name()))
+                {
+                    nested -= 1;
+                }
+            }
+        }
+    }
+    text.trim().to_string()
+}
+
+/// An `ADD_DATE` value as Unix seconds
+///
+/// Some browsers write milliseconds or microseconds; 0 means unknown.
+fn timestamp(value: &str) -> Option<i64> {
+    let mut ts: i64 = value.trim().parse().ok()?;
+    // Seconds stay below 10^11 until the year 5138
+    while ts >= 100_000_000_000 {
+        ts /= 1000;
+    }
+    (ts > 0).then_some(ts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::path::Path;
+
+    fn parse(html: &str) -> Vec<ParsedBookmark> {
+        let mut bookmarks = Vec::new();
+        for_each_bookmark(html, |bookmark| {
+            bookmarks.push(bookmark);
+            true
+        });
+        bookmarks
+    }
+
+    /// A file from `lib/testdata/html`, written by hand in the format each
+    /// browser exports, with the quirks and damage imports have to survive
+    fn corpus_bytes(name: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/html")
+            .join(name);
+        std::fs::read(path).unwrap()
+    }
+
+    fn corpus(name: &str) -> String {
+        decode(&corpus_bytes(name))
+    }
+
+    #[rstest]
+    #[case("firefox.html", &[
+        ("https://support.mozilla.org/products/firefox", "Get Help", ",Mozilla Firefox,"),
+        ("https://www.mozilla.org/about/", "About Us", ",Mozilla Firefox,"),
+        ("place:type=6&sort=14&maxResults=10", "Recent Tags", ","),
+        ("https://doc.rust-lang.org/book/", "The Rust Programming Language", ",rust,docs,"),
+        ("https://lwn.net/", "LWN.net", ",Bookmarks Toolbar,News,"),
+        ("javascript:void(document.title)", "Bookmarklet", ","),
+    ])]
+    #[case("chrome.html", &[
+        ("https://github.com/", "GitHub", ",Bookmarks bar,"),
+        ("https://example.com/dashboard?a=1&b=2", "Dashboard — Q3", ",Bookmarks bar,Work,"),
+        ("https://news.ycombinator.com/", "Hacker News", ",Other bookmarks,"),
+    ])]
+    #[case("safari.html", &[
+        ("https://www.apple.com/", "Apple", ",Favourites,"),
+        ("https://webkit.org/blog/", "WebKit Blog", ",Reading List,"),
+    ])]
+    #[case("windows-1252.html", &[
+        ("https://cafe.example/menu", "Café – Menü", ",Lecture,"),
+    ])]
+    #[case("malformed.html", &[
+        ("https://a.example/", "No closing tag", ",Unclosed,"),
+        ("https://b.example/", "Unquoted attributes", ",Unclosed,"),
+        ("https://c.example/", "Quoted > in an attribute", ",Unclosed,"),
+        ("https://d.example/", "Inside bold text", ",Unclosed,"),
+        ("https://e.example/", "After the folder", ","),
+    ])]
+    fn test_corpus(#[case] file: &str, #[case] expected: &[(&str, &str, &str)]) {
+        let bookmarks = parse(&corpus(file));
+        let parsed: Vec<(&str, &str, &str)> = bookmarks
+            .iter()
+            .map(|b| (b.url.as_str(), b.title.as_str(), b.tags.as_str()))
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_corpus_descriptions_and_dates() {
+        let firefox = parse(&corpus("firefox.html"));
+        assert_eq!(firefox[3].desc, "The official book & a great start");
+        assert_eq!(firefox[3].created_at, Some(1_700_000_101));
+        assert_eq!(firefox.iter().filter(|b| !b.desc.is_empty()).count(), 1);
+
+        let legacy = parse(&corpus("windows-1252.html"));
+        assert_eq!(legacy[0].desc, "Crème brûlée “du jour”");
+        assert_eq!(legacy[0].created_at, Some(1_500_000_001));
+
+        let malformed = parse(&corpus("malformed.html"));
+        assert_eq!(malformed[1].created_at, Some(1_700_000_000));
+        assert_eq!(malformed[2].desc, "Description with a stray < sign");
+
+        assert!(parse(&corpus("safari.html"))
+            .iter()
+            .all(|b| b.created_at.is_none()));
+    }
+
+    /// Files cut short or with their markup mangled anywhere still parse,
+    /// without finding bookmarks that aren't there
+    #[rstest]
+    fn test_corpus_damaged(
+        #[values(
+            "firefox.html",
+            "chrome.html",
+            "safari.html",
+            "windows-1252.html",
+            "malformed.html"
+        )]
+        file: &str,
+    ) {
+        let html = corpus(file);
+        let full = parse(&html).len();
+        for cut in (0..=html.len()).filter(|&i| html.is_char_boundary(i)) {
+            assert!(parse(&html[..cut]).len() <= full, "cut at {}", cut);
+        }
+        for mangled in [
+            html.replace('>', ""),
+            html.replace('"', ""),
+            html.replace("</", "<"),
+            html.replace('<', "&lt;"),
+        ] {
+            parse(&mangled);
+        }
+
+        let bytes = corpus_bytes(file);
+        for cut in 0..=bytes.len() {
+            decode(&bytes[..cut]);
+        }
+    }
+
+    #[rstest]
+    #[case(b"\xEF\xBB\xBFcaf\xC3\xA9", "caf\u{e9}")]
+    #[case(b"\xFF\xFEc\0a\0f\0\xE9\0", "caf\u{e9}")]
+    #[case(b"caf\xE9 \x96 \x80", "caf\u{e9} \u{2013} \u{20ac}")]
+    #[case(b"caf\xC3\xA9 \x93ok\x94", "caf\u{e9} \u{201c}ok\u{201d}")]
+    #[case(
+        b"<meta charset=\"windows-1252\">\xC3\xA9",
+        "<meta charset=\"windows-1252\">\u{c3}\u{a9}"
+    )]
+    #[case(
+        b"<META HTTP-EQUIV=Content-Type CONTENT='text/html; charset=ISO-8859-1'>\x80",
+        "<META HTTP-EQUIV=Content-Type CONTENT='text/html; charset=ISO-8859-1'>\u{20ac}"
+    )]
+    #[case(
+        b"<meta charset=Shift_JIS>\x93\xFA\x96\x7B",
+        "<meta charset=Shift_JIS>\u{65e5}\u{672c}"
+    )]
+    #[case(b"<meta charset=utf-16>caf\xC3\xA9", "<meta charset=utf-16>caf\u{e9}")]
+    #[case(
+        b"<title>charset=windows-1252</title>\xC3\xA9",
+        "<title>charset=windows-1252</title>\u{e9}"
+    )]
+    fn test_decode(#[case] bytes: &[u8], #[case] expected: &str) {
+        assert_eq!(decode(bytes), expected);
+    }
+
+    /// Random markup and randomly mutated exports decode and parse without panicking
+    #[test]
+    fn test_fuzz_random_and_mutated_input() {
+        // xorshift64, seeded so a failure reproduces
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        const ALPHABET: &[u8] = b"<>/=\"' &#;!-?adhlpt3ADHLT\n\x80\x93\xA9\xC3\xEF\xBB\xBF\xFE\xFF";
+        let corpus: Vec<Vec<u8>> = [
+            "firefox.html",
+            "chrome.html",
+            "safari.html",
+            "windows-1252.html",
+            "malformed.html",
+        ]
+        .iter()
+        .map(|file| corpus_bytes(file))
+        .collect();
+
+        for round in 0..400 {
+            let mut bytes: Vec<u8> = match round % 4 {
+                0 => (0..next(512))
+                    .map(|_| ALPHABET[next(ALPHABET.len())])
+                    .collect(),
+                _ => corpus[next(corpus.len())].clone(),
+            };
+            for _ in 0..=next(16) {
+                let at = next(bytes.len() + 1);
+                match next(4) {
+                    0 => bytes.insert(at, ALPHABET[next(ALPHABET.len())]),
+                    1 if at < bytes.len() => {
+                        bytes.remove(at);
+                    }
+                    2 if at < bytes.len() => bytes[at] = next(256) as u8,
+                    _ => {
+                        let end = bytes.len().min(at + next(64));
+                        let repeat = bytes[at..end].to_vec();
+                        bytes.splice(at..at, repeat);
+                    }
+                }
+            }
+            parse(&decode(&bytes));
+        }
+    }
+
+    #[test]
+    fn test_nested_folders_become_tags() {
+        let bookmarks = parse(
+            r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- <DT><A HREF="https://commented.out">No</A> -->
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000">Dev</H3>
+    <DD>Folder notes
+    <DL><p>
+        <DT><H3>Rust, mostly</H3>
+        <DL><p>
+            <DT><A HREF="https://rust-lang.org">Rust</A>
+        </DL><p>
+        <DT><A HREF="https://go.dev">Go</A>
+    </DL><p>
+    <DT><A HREF="https://example.com">Example</A>
+</DL><p>"#,
+        );
+
+        let parsed: Vec<(&str, &str, &str)> = bookmarks
+            .iter()
+            .map(|b| (b.url.as_str(), b.title.as_str(), b.tags.as_str()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("https://rust-lang.org", "Rust", ",Dev,Rust mostly,"),
+                ("https://go.dev", "Go", ",Dev,"),
+                ("https://example.com", "Example", ","),
+            ]
+        );
+        assert!(bookmarks.iter().all(|b| b.desc.is_empty()));
+    }
+
+    #[test]
+    fn test_descriptions_dates_and_attributes() {
+        let bookmarks = parse(
+            r#"<DL><p>
+    <DT><A HREF="https://a.com/?x=1&amp;y=2" ADD_DATE="1700000000" TAGS="rust,cli">A &amp; B</A>
+    <DD>First line
+second line &lt;ok&gt;
+    <DT><a href='https://b.com' last_modified="1600000000" title="a > b">B</a>
+    <DT><A HREF="https://c.com" ADD_DATE="1700000000123456" TAGS="">C</A>
+    <DD>
+</DL><p>"#,
+        );
+
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].url, "https://a.com/?x=1&y=2");
+        assert_eq!(bookmarks[0].title, "A & B");
+        assert_eq!(bookmarks[0].tags, ",rust,cli,");
+        assert_eq!(bookmarks[0].desc, "First line\nsecond line <ok>");
+        assert_eq!(bookmarks[0].created_at, Some(1_700_000_000));
+        assert_eq!(bookmarks[1].title, "B");
+        assert_eq!(bookmarks[1].desc, "");
+        assert_eq!(bookmarks[1].created_at, Some(1_600_000_000));
+        assert_eq!(bookmarks[2].tags, ",");
+        assert_eq!(bookmarks[2].desc, "");
+        assert_eq!(bookmarks[2].created_at, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_unclosed_link_is_not_repeated() {
+        let bookmarks = parse(
+            r#"<DL><DT><A HREF="https://a.com" ADD_DATE="1">A
+<DT>Loose text <b>in bold</b>
+<DT><A HREF="https://a.com" ADD_DATE="2">A again</A></DL>"#,
+        );
+        let parsed: Vec<(&str, &str)> = bookmarks
+            .iter()
+            .map(|b| (b.url.as_str(), b.title.as_str()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![("https://a.com", "A"), ("https://a.com", "A again")]
+        );
+    }
+
+    #[rstest]
+    #[case(r#"<DL><DT><A HREF="https://a.com">A</A><DT><A HREF="https://a.com">A</A></DL>"#)]
+    #[case("<DL><DT><A HREF=\"https://a.com\">A\n<DT><A HREF=\"https://a.com\">A\n</DL>")]
+    fn test_repeated_link_is_kept(#[case] html: &str) {
+        let parsed: Vec<(String, String)> =
+            parse(html).into_iter().map(|b| (b.url, b.title)).collect();
+        let link = ("https://a.com".to_string(), "A".to_string());
+        assert_eq!(parsed, vec![link.clone(), link]);
+    }
+
+    #[test]
+    fn test_stops_when_emit_returns_false() {
+        let mut seen = 0;
+        for_each_bookmark(
+            r#"<DT><A HREF="https://a.com">A</A><DT><A HREF="https://b.com">B</A>"#,
+            |_| {
+                seen += 1;
+                false
+            },
+        );
+        assert_eq!(seen, 1);
+    }
+
+    #[rstest]
+    #[case("1700000000", Some(1_700_000_000))]
+    #[case("1700000000123", Some(1_700_000_000))]
+    #[case("1700000000123456", Some(1_700_000_000))]
+    #[case("0", None)]
+    #[case("-5", None)]
+    #[case("soon", None)]
+    fn test_timestamp(#[case] value: &str, #[case] expected: Option<i64>) {
+        assert_eq!(timestamp(value), expected);
+    }
+}
@@ -0,0 +1,232 @@
+use super::export::BookmarkExporter;
+use super::markup::escape_markup;
+use crate::db::BukuDb;
+use crate::models::bookmark::Bookmark;
+use crate::operations::BookmarkFilter;
+use crate::tags::parse_tags;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Page title when none is given
+pub const DEFAULT_SITE_TITLE: &str = "Bookmarks";
+
+/// Heading for bookmarks with no tags besides the selection tags
+const UNTAGGED: &str = "Untagged";
+
+/// Filters the list as you type; sections with no visible links are hidden
+const SEARCH_SCRIPT: &str = r#"<script>
+document.getElementById('q').addEventListener('input', function () {
+  var terms = this.value.toLowerCase().split(/\s+/).filter(Boolean);
+  document.querySelectorAll('section').forEach(function (section) {
+    var shown = 0;
+    section.querySelectorAll('li').forEach(function (li) {
+      var text = li.textContent.toLowerCase();
+      var match = terms.every(function (t) { return text.indexOf(t) !== -1; });
+      li.hidden = !match;
+      if (match) shown++;
+    });
+    section.hidden = shown === 0;
+  });
+});
+</script>"#;
+
+const STYLE: &str = "<style>\
+body{font-family:system-ui,sans-serif;max-width:50rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+input{width:100%;font-size:1rem;padding:.4rem;box-sizing:border-box}\
+h2{border-bottom:1px solid #ccc}\
+li{margin:.3rem 0}\
+.desc{color:#555;font-size:.9rem}\
+</style>";
+
+/// Group bookmarks under each of their tags, sorted by tag
+///
+/// The selection tags are left out (every bookmark has one), and bookmarks
+/// without any other tag go under "Untagged", listed last.
+fn group_by_tag<'a>(
+    bookmarks: &'a [Bookmark],
+    selection: &[String],
+) -> Vec<(String, Vec<&'a Bookmark>)> {
+    let mut groups: BTreeMap<String, Vec<&Bookmark>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for bookmark in bookmarks {
+        let tags: Vec<String> = parse_tags(&bookmark.tags)
+            .into_iter()
+            .filter(|t| !selection.iter().any(|s| s.eq_ignore_ascii_case(t)))
+            .collect();
+        if tags.is_empty() {
+            untagged.push(bookmark);
+        }
+        for tag in tags {
+            groups.entry(tag).or_default().push(bookmark);
+        }
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    if !untagged.is_empty() {
+        groups.push((UNTAGGED.to_string(), untagged));
+    }
+    groups
+}
+
+/// Render a self-contained index.html listing bookmarks by tag, with a search box
+pub fn render_site(bookmarks: &[Bookmark], selection: &[String], title: &str) -> String {
+    let title = escape_markup(title);
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html lang=\"en\">");
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, "<meta charset=\"utf-8\">");
+    let _ = writeln!(
+        html,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    );
+    let _ = writeln!(html, "<meta name=\"generator\" content=\"bukurs\">");
+    let _ = writeln!(html, "<title>{}</title>", title);
+    let _ = writeln!(html, "{}", STYLE);
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(html, "<body>");
+    let _ = writeln!(html, "<h1>{}</h1>", title);
+    let _ = writeln!(
+        html,
+        "<input id=\"q\" type=\"search\" placeholder=\"Search {} bookmarks\" autofocus>",
+        bookmarks.len()
+    );
+
+    for (tag, members) in group_by_tag(bookmarks, selection) {
+        let _ = writeln!(html, "<section>");
+        let _ = writeln!(html, "<h2>{}</h2>", escape_markup(&tag));
+        let _ = writeln!(html, "<ul>");
+        for bookmark in members {
+            let label = if bookmark.title.is_empty() {
+                &bookmark.url
+            } else {
+                &bookmark.title
+            };
+            let _ = write!(
+                html,
+                "<li><a href=\"{}\">{}</a>",
+                escape_markup(&bookmark.url),
+                escape_markup(label)
+            );
+            if !bookmark.description.is_empty() {
+                let _ = write!(
+                    html,
+                    "<div class=\"desc\">{}</div>",
+                    escape_markup(&bookmark.description)
+                );
+            }
+            let _ = writeln!(html, "</li>");
+        }
+        let _ = writeln!(html, "</ul>");
+        let _ = writeln!(html, "</section>");
+    }
+
+    let _ = writeln!(html, "{}", SEARCH_SCRIPT);
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    html
+}
+
+/// Static site exporter; `export` treats its path as the output directory
+///
+/// Writes an `index.html` and an empty `.nojekyll`, so the directory can be
+/// pushed to GitHub Pages as is.
+pub struct SiteExporter {
+    /// Tags the bookmarks were selected by, left out of the grouping
+    pub selection: Vec<String>,
+    pub title: String,
+}
+
+impl BookmarkExporter for SiteExporter {
+    fn export(&self, bookmarks: &[Bookmark], path: &Path) -> crate::error::Result<()> {
+        fs::create_dir_all(path)?;
+        fs::write(
+            path.join("index.html"),
+            render_site(bookmarks, &self.selection, &self.title),
+        )?;
+        fs::write(path.join(".nojekyll"), "")?;
+        Ok(())
+    }
+}
+
+/// Publish the bookmarks tagged with any of `tags` (all when empty) as a static site
+/// Returns the number of bookmarks published
+pub fn publish_site(
+    db: &BukuDb,
+    out_dir: &Path,
+    tags: &[String],
+    title: &str,
+) -> crate::error::Result<usize> {
+    let bookmarks = BookmarkFilter::tagged(tags).apply(db.get_rec_all()?)?;
+
+    let exporter = SiteExporter {
+        selection: tags.to_vec(),
+        title: title.to_string(),
+    };
+    exporter.export(&bookmarks, out_dir)?;
+    Ok(bookmarks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(id: usize, url: &str, title: &str, tags: &str, desc: &str) -> Bookmark {
+        Bookmark::new(
+            id,
+            url.to_string(),
+            title.to_string(),
+            tags.to_string(),
+            desc.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_render_site_groups_by_tag() {
+        let bookmarks = vec![
+            bookmark(1, "https://rust-lang.org", "Rust", ",public,rust,lang,", ""),
+            bookmark(2, "https://a.com/?x=1&y=<2>", "", ",public,", "A & B"),
+            bookmark(3, "https://python.org", "Python", ",lang,public,", ""),
+        ];
+        let html = render_site(&bookmarks, &["public".to_string()], "My <Links>");
+
+        assert!(html.contains("<title>My &lt;Links&gt;</title>"));
+        assert!(html.contains("placeholder=\"Search 3 bookmarks\""));
+        // Sorted by tag, untagged last, and the selection tag is not a section
+        let lang = html.find("<h2>lang</h2>").unwrap();
+        let rust = html.find("<h2>rust</h2>").unwrap();
+        let untagged = html.find("<h2>Untagged</h2>").unwrap();
+        assert!(lang < rust && rust < untagged);
+        assert!(!html.contains("<h2>public</h2>"));
+        // A bookmark appears under each of its tags
+        assert_eq!(html.matches(">Rust</a>").count(), 2);
+        assert!(html.contains(
+            "<a href=\"https://a.com/?x=1&amp;y=&lt;2&gt;\">https://a.com/?x=1&amp;y=&lt;2&gt;</a>"
+        ));
+        assert!(html.contains("<div class=\"desc\">A &amp; B</div>"));
+        assert!(html.contains("getElementById('q')"));
+    }
+
+    #[test]
+    fn test_publish_site_selects_by_tag() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://public.com", "Public", ",Public,web,", "", None)
+            .unwrap();
+        db.add_rec("https://private.com", "Private", ",web,", "", None)
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("site");
+        let count = publish_site(&db, &out, &["public".to_string()], "Links").unwrap();
+
+        assert_eq!(count, 1);
+        let html = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(html.contains("https://public.com"));
+        assert!(!html.contains("https://private.com"));
+        assert!(out.join(".nojekyll").exists());
+
+        assert_eq!(publish_site(&db, &out, &[], "Links").unwrap(), 2);
+    }
+}
@@ -1,16 +1,69 @@
+//! bukurs, a bookmark manager library
+//!
+//! With default features this is the whole library. Building with
+//! `default-features = false, features = ["core"]` leaves only the parts that
+//! need neither SQLite, the network nor a terminal (bookmark models, tags,
+//! selectors, keyword matching and the import/export parsers), which also
+//! build for `wasm32-unknown-unknown`.
+
+#[cfg(feature = "async")]
+pub mod async_api;
+#[cfg(feature = "full")]
+pub mod audit;
+pub mod autotag;
+#[cfg(feature = "full")]
+pub mod bookmarklet;
+#[cfg(feature = "full")]
 pub mod browser;
+pub mod cancel;
+pub mod collection;
+#[cfg(feature = "full")]
 pub mod commands;
+#[cfg(feature = "full")]
+pub mod composite;
+#[cfg(feature = "full")]
 pub mod config;
+#[cfg(feature = "full")]
 pub mod crypto;
+#[cfg(feature = "full")]
+pub mod daemon;
+#[cfg(feature = "full")]
 pub mod db;
 pub mod error;
+#[cfg(feature = "full")]
+pub mod events;
+#[cfg(feature = "full")]
 pub mod fetch;
+#[cfg(feature = "full")]
 pub mod fuzzy;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod import_export;
 pub mod models;
+#[cfg(feature = "full")]
+pub mod monitor;
 pub mod operations;
+#[cfg(feature = "full")]
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "full")]
+pub mod resource;
+#[cfg(feature = "full")]
+pub mod schedule;
+pub mod search;
+#[cfg(feature = "full")]
+pub mod service;
+#[cfg(feature = "full")]
+pub mod similar;
+#[cfg(feature = "full")]
+pub mod suggest;
+pub mod tag_map;
 pub mod tags;
 pub mod utils;
+#[cfg(feature = "full")]
+pub mod workspace;
 
 // Re-export error types for convenience
 pub use error::BukursError;
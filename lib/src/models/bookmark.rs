@@ -1,5 +1,9 @@
+use crate::tags::{format_tags, parse_tags};
 use serde::{Deserialize, Serialize};
 
+/// JSON Schema of [`CanonicalBookmark`], as shipped in `lib/schema/`
+pub const CANONICAL_JSON_SCHEMA: &str = include_str!("../../schema/bookmark.schema.json");
+
 /// Represents a bookmark with all its metadata
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Bookmark {
@@ -21,6 +25,119 @@ impl Bookmark {
             description,
         }
     }
+
+    /// First line of the description for list output, with `[+notes]` when
+    /// more lines follow; `None` when there is no description
+    pub fn description_summary(&self) -> Option<String> {
+        let mut lines = self.description.lines().filter(|l| !l.trim().is_empty());
+        let first = lines.next()?.trim();
+        if lines.next().is_some() {
+            Some(format!("{} [+notes]", first))
+        } else {
+            Some(first.to_string())
+        }
+    }
+}
+
+/// A bookmark in canonical JSON form (see [`CANONICAL_JSON_SCHEMA`])
+///
+/// The interchange shape for JSON export and import: tags are an array
+/// rather than the stored `,a,b,` string, and the columns the [`Bookmark`]
+/// model leaves out (flags, parent, creation time) come along so a round
+/// trip loses nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonicalBookmark {
+    #[serde(default)]
+    pub id: usize,
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    /// Also read from the stored `,a,b,` string that older output printed
+    #[serde(default, deserialize_with = "tags_list_or_string")]
+    pub tags: Vec<String>,
+    /// Also read from `description`, as `-f json` prints it
+    #[serde(default, alias = "description")]
+    pub desc: String,
+    #[serde(default)]
+    pub flags: i32,
+    /// Index of the folder bookmark this one was imported under
+    #[serde(default)]
+    pub parent: Option<usize>,
+    /// Unix seconds; None when unknown
+    #[serde(default)]
+    pub created_at: Option<i64>,
+}
+
+/// Tags given as a list or as one comma-separated string
+fn tags_list_or_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Raw(String),
+    }
+    Ok(match Tags::deserialize(deserializer)? {
+        Tags::List(tags) => tags,
+        Tags::Raw(tags) => parse_tags(tags),
+    })
+}
+
+impl CanonicalBookmark {
+    pub fn to_canonical_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Parse one bookmark; missing fields take their schema defaults
+    pub fn from_canonical_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Tags in the stored `,a,b,` form; commas inside a tag split it
+    pub fn stored_tags(&self) -> String {
+        let tags: Vec<String> = self.tags.iter().flat_map(parse_tags).collect();
+        format_tags(&tags)
+    }
+}
+
+impl From<&Bookmark> for CanonicalBookmark {
+    /// The model's fields only; flags, parent and creation time stay unset
+    fn from(bookmark: &Bookmark) -> Self {
+        Self {
+            id: bookmark.id,
+            url: bookmark.url.clone(),
+            title: bookmark.title.clone(),
+            tags: parse_tags(&bookmark.tags),
+            desc: bookmark.description.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<&CanonicalBookmark> for Bookmark {
+    fn from(canonical: &CanonicalBookmark) -> Self {
+        Bookmark::new(
+            canonical.id,
+            canonical.url.clone(),
+            canonical.title.clone(),
+            canonical.stored_tags(),
+            canonical.desc.clone(),
+        )
+    }
+}
+
+impl Bookmark {
+    /// This bookmark as canonical JSON, tags as an array
+    pub fn to_canonical_json(&self) -> serde_json::Result<String> {
+        CanonicalBookmark::from(self).to_canonical_json()
+    }
+
+    /// A bookmark from canonical JSON, dropping flags, parent and creation time
+    pub fn from_canonical_json(json: &str) -> serde_json::Result<Self> {
+        CanonicalBookmark::from_canonical_json(json).map(|c| Bookmark::from(&c))
+    }
 }
 
 #[cfg(test)]
@@ -59,4 +176,81 @@ mod tests {
         let deserialized: Bookmark = serde_json::from_str(&json).unwrap();
         assert_eq!(bookmark, deserialized);
     }
+
+    #[test]
+    fn test_description_summary() {
+        let mut bookmark = Bookmark::new(
+            1,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",".to_string(),
+            String::new(),
+        );
+        assert_eq!(bookmark.description_summary(), None);
+
+        bookmark.description = "One line".to_string();
+        assert_eq!(bookmark.description_summary().as_deref(), Some("One line"));
+
+        bookmark.description = "\n# Heading\n\n- point one\n- point two\n".to_string();
+        assert_eq!(
+            bookmark.description_summary().as_deref(),
+            Some("# Heading [+notes]")
+        );
+    }
+
+    #[test]
+    fn test_canonical_json_tags_are_an_array() {
+        let bookmark = Bookmark::new(
+            3,
+            "https://example.com".to_string(),
+            "Example".to_string(),
+            ",rust,web dev,".to_string(),
+            "Notes".to_string(),
+        );
+        assert_eq!(
+            bookmark.to_canonical_json().unwrap(),
+            r#"{"id":3,"url":"https://example.com","title":"Example","tags":["rust","web dev"],"desc":"Notes","flags":0,"parent":null,"created_at":null}"#
+        );
+        let back = Bookmark::from_canonical_json(&bookmark.to_canonical_json().unwrap()).unwrap();
+        assert_eq!(back, bookmark);
+    }
+
+    #[test]
+    fn test_canonical_json_defaults_and_stored_tags() {
+        let canonical =
+            CanonicalBookmark::from_canonical_json(r#"{"url":"https://example.com"}"#).unwrap();
+        assert_eq!(canonical.url, "https://example.com");
+        assert_eq!(canonical.stored_tags(), ",");
+
+        let canonical = CanonicalBookmark::from_canonical_json(
+            r#"{"url":"https://example.com","tags":[" a ","b,c",""],"flags":1,"parent":2,"created_at":1700000000}"#,
+        )
+        .unwrap();
+        assert_eq!(canonical.stored_tags(), ",a,b,c,");
+        assert_eq!(
+            (canonical.flags, canonical.parent, canonical.created_at),
+            (1, Some(2), Some(1_700_000_000))
+        );
+        assert!(CanonicalBookmark::from_canonical_json(r#"{"title":"No URL"}"#).is_err());
+
+        let canonical = CanonicalBookmark::from_canonical_json(
+            r#"{"url":"https://example.com","tags":",rust,cli,","description":"Notes"}"#,
+        )
+        .unwrap();
+        assert_eq!(canonical.tags, vec!["rust", "cli"]);
+        assert_eq!(canonical.desc, "Notes");
+    }
+
+    #[test]
+    fn test_schema_matches_serialized_fields() {
+        let schema: serde_json::Value = serde_json::from_str(CANONICAL_JSON_SCHEMA).unwrap();
+        let mut properties: Vec<&String> =
+            schema["properties"].as_object().unwrap().keys().collect();
+        let serialized = serde_json::to_value(CanonicalBookmark::default()).unwrap();
+        let mut fields: Vec<&String> = serialized.as_object().unwrap().keys().collect();
+        properties.sort();
+        fields.sort();
+        assert_eq!(properties, fields);
+        assert_eq!(schema["required"], serde_json::json!(["url"]));
+    }
 }
@@ -0,0 +1,257 @@
+//! Page-change monitoring
+//!
+//! `monitor add` stores a hash of a bookmark's page; `monitor check` and the
+//! daemon's `monitor` job fetch every monitored page again and flag the ones
+//! whose hash differs. A flagged change stays listed by `monitor changes`
+//! until it is cleared, however many checks run in between.
+//!
+//! HTML pages are hashed by their main text (see [`readability::extract`]),
+//! so scripts, navigation and rotating ads around it don't count as changes.
+//! Other resources are hashed byte for byte.
+
+use crate::cancel::CancellationToken;
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::fetch::{readability, throttle, FetchOptions};
+use crate::progress::ProgressSink;
+use crate::resource::ResourceKind;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Checked monitors recorded per hold of the database lock; the fetches
+/// between batches run without it
+const CHECK_BATCH: usize = 16;
+
+/// A monitored bookmark and what its last check found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Monitor {
+    pub id: usize,
+    pub url: String,
+    pub title: String,
+    /// Hex SHA-256 of the page content at the last successful check
+    pub hash: String,
+    /// Unix seconds of the last check, successful or not
+    pub checked_at: i64,
+    /// Unix seconds of the last check that found the page changed
+    pub changed_at: Option<i64>,
+    /// Whether the page changed since the changes were last cleared
+    pub changed: bool,
+    /// Why the last check failed; `None` when it succeeded
+    pub last_error: Option<String>,
+}
+
+/// What [`check`] found
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckOutcome {
+    /// Monitored bookmarks fetched successfully
+    pub checked: usize,
+    /// IDs of the bookmarks whose page changed
+    pub changed: Vec<usize>,
+    /// IDs of the bookmarks that couldn't be fetched, with the error
+    pub failed: Vec<(usize, String)>,
+    /// The check stopped early at the user's request
+    pub cancelled: bool,
+}
+
+/// Hash of a resource's content; `html` bodies are reduced to their main text
+pub fn content_hash(body: &[u8], html: bool) -> String {
+    if html {
+        let text = String::from_utf8_lossy(body);
+        if let Ok(article) = readability::extract(&text) {
+            if !article.blocks.is_empty() {
+                let mut hasher = Sha256::new();
+                for block in &article.blocks {
+                    for word in block.text().split_whitespace() {
+                        hasher.update(word);
+                        hasher.update(b" ");
+                    }
+                    hasher.update(b"\n");
+                }
+                return format!("{:x}", hasher.finalize());
+            }
+        }
+    }
+    format!("{:x}", Sha256::digest(body))
+}
+
+/// Fetch `url` and hash its content (see [`content_hash`])
+pub fn fetch_hash(url: &str, options: &FetchOptions) -> Result<String> {
+    let mut resp = throttle::send(options, url, options.client()?.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("Fetching {} failed (Status: {})", url, resp.status()).into());
+    }
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if ResourceKind::classify(content_type.as_deref(), resp.url().as_str()) == ResourceKind::Html {
+        return Ok(content_hash(&resp.bytes()?, true));
+    }
+    // Files may be large; hash them as they arrive
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut resp, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Start monitoring bookmark `id` from its page's current content
+///
+/// Returns whether it was monitored already; its stored hash is replaced
+/// and any flagged change cleared.
+pub fn add(db: &BukuDb, id: usize, options: &FetchOptions) -> Result<bool> {
+    let bookmark = db
+        .get_rec_by_id(id)?
+        .ok_or(BukursError::BookmarkNotFound(id))?;
+    let hash = fetch_hash(&bookmark.url, options)?;
+    let _lock = db.write_lock()?;
+    db.add_monitor(id, &hash, now())
+}
+
+/// Fetch every monitored page again and flag the ones whose content changed
+///
+/// Failures are recorded on the monitor rather than aborting the check; the
+/// stored hash is kept, so a page that was briefly unreachable doesn't count
+/// as changed. The database lock is only held while results are recorded.
+pub fn check(
+    db: &BukuDb,
+    options: &FetchOptions,
+    progress: &dyn ProgressSink,
+    cancel: &CancellationToken,
+) -> Result<CheckOutcome> {
+    // Fail once rather than record every monitor as failed
+    if options.offline {
+        return Err(BukursError::Offline);
+    }
+    let monitors = db.get_monitors()?;
+    let mut outcome = CheckOutcome::default();
+    let mut results = Vec::with_capacity(CHECK_BATCH);
+    progress.start("Checking", Some(monitors.len() as u64));
+
+    for monitor in &monitors {
+        if cancel.is_cancelled() {
+            outcome.cancelled = true;
+            break;
+        }
+        progress.advance(0, Some(&monitor.url));
+        results.push((monitor.id, fetch_hash(&monitor.url, options), now()));
+        if results.len() == CHECK_BATCH {
+            record(db, &mut results, &mut outcome)?;
+        }
+        progress.advance(1, None);
+    }
+    record(db, &mut results, &mut outcome)?;
+
+    progress.finish();
+    Ok(outcome)
+}
+
+/// Record and clear a batch of fetch results under one hold of the lock
+fn record(
+    db: &BukuDb,
+    results: &mut Vec<(usize, Result<String>, i64)>,
+    outcome: &mut CheckOutcome,
+) -> Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+    let _lock = db.write_lock()?;
+    for (id, result, checked_at) in results.drain(..) {
+        match result {
+            Ok(hash) => {
+                outcome.checked += 1;
+                if db.record_monitor_hash(id, &hash, checked_at)? {
+                    outcome.changed.push(id);
+                }
+            }
+            Err(e) => {
+                db.record_monitor_failure(id, &e.to_string(), checked_at)?;
+                outcome.failed.push((id, e.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "<html><body><nav>Home · About</nav><article>\
+        <p>The first paragraph of the article is long enough to count as text.</p>\
+        <p>And the second paragraph, which is also long enough to be scored.</p>\
+        </article><footer>Generated at 12:00</footer></body></html>";
+
+    #[test]
+    fn test_content_hash_ignores_markup_outside_main_text() {
+        let base = content_hash(ARTICLE.as_bytes(), true);
+        let restyled = ARTICLE
+            .replace("12:00", "12:05")
+            .replace("<p>", "<p class=\"x\">  ");
+        assert_eq!(content_hash(restyled.as_bytes(), true), base);
+
+        let edited = ARTICLE.replace("second paragraph", "revised paragraph");
+        assert_ne!(content_hash(edited.as_bytes(), true), base);
+    }
+
+    #[test]
+    fn test_content_hash_of_files_is_byte_for_byte() {
+        let hash = content_hash(b"%PDF-1.7 body", false);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, content_hash(b"%PDF-1.7 body", false));
+        assert_ne!(hash, content_hash(b"%PDF-1.7 body ", false));
+    }
+
+    #[test]
+    fn test_check_records_failures_without_flagging_changes() {
+        let db = BukuDb::init_in_memory().unwrap();
+        // .invalid never resolves, so the fetch fails without network access
+        let id = db
+            .add_rec("https://nonexistent.invalid/", "", ",", "", None)
+            .unwrap();
+        db.add_monitor(id, "abc", 1).unwrap();
+
+        let options = FetchOptions {
+            timeout_secs: 5,
+            ..FetchOptions::default()
+        };
+        let outcome = check(
+            &db,
+            &options,
+            &crate::progress::NoProgress,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(outcome.checked, 0);
+        assert!(outcome.changed.is_empty());
+        assert_eq!(outcome.failed.len(), 1);
+
+        let monitor = &db.get_monitors().unwrap()[0];
+        assert_eq!(monitor.hash, "abc");
+        assert!(!monitor.changed);
+        assert!(monitor.last_error.is_some());
+    }
+
+    #[test]
+    fn test_check_offline_fails_once() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let options = FetchOptions {
+            offline: true,
+            ..FetchOptions::default()
+        };
+        let result = check(
+            &db,
+            &options,
+            &crate::progress::NoProgress,
+            &CancellationToken::new(),
+        );
+        assert!(matches!(result, Err(BukursError::Offline)));
+    }
+}
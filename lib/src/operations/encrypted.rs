@@ -0,0 +1,207 @@
+//! Encrypted bookmarks
+//!
+//! Unlike `lock`, which encrypts the whole database file, bookmarks carrying
+//! a chosen tag can have their URL, title and description encrypted in
+//! place (see [`crypto::FieldCipher`]). Tags stay readable so the bookmarks
+//! can still be selected, while the rest is kept out of the search index.
+
+use crate::crypto::{self, FieldCipher, FieldDecryptor, Kdf};
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use crate::tags::parse_tags;
+
+/// Outcome of [`decrypt_tagged`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecryptSummary {
+    pub decrypted: usize,
+    /// Encrypted under another password, left as they are
+    pub other_password: usize,
+}
+
+/// Whether a bookmark's fields are encrypted
+pub fn is_encrypted(bookmark: &Bookmark) -> bool {
+    crypto::is_encrypted_field(&bookmark.url)
+}
+
+/// Bookmarks carrying exactly `tag`, encrypted or not
+fn tagged(db: &BukuDb, tag: &str) -> Result<Vec<Bookmark>> {
+    Ok(db
+        .get_rec_tagged_by_age(tag)?
+        .into_iter()
+        .map(|(bookmark, _)| bookmark)
+        // LIKE matching is case-insensitive; keep exact tags only
+        .filter(|b| parse_tags(&b.tags).iter().any(|t| t == tag))
+        .collect())
+}
+
+/// Encrypt the bookmarks tagged `tag` that aren't already; returns how many changed
+///
+/// Their undo history holds the plaintext, so it is dropped and the change
+/// can't be undone; `decrypt_tagged` reverses it instead.
+pub fn encrypt_tagged(db: &BukuDb, tag: &str, kdf: Kdf, password: &str) -> Result<usize> {
+    let plain: Vec<Bookmark> = tagged(db, tag)?
+        .into_iter()
+        .filter(|b| !is_encrypted(b))
+        .collect();
+    if plain.is_empty() {
+        return Ok(0);
+    }
+
+    let cipher = FieldCipher::new(kdf, password)?;
+    let encrypted: Vec<Bookmark> = plain
+        .into_iter()
+        .map(|b| Bookmark {
+            url: cipher.encrypt(&b.url),
+            title: cipher.encrypt(&b.title),
+            description: cipher.encrypt(&b.description),
+            ..b
+        })
+        .collect();
+    Ok(db.update_rec_batch_forget_history(&encrypted)?)
+}
+
+/// Decrypt a bookmark's fields
+pub fn decrypt_bookmark(bookmark: &Bookmark, decryptor: &mut FieldDecryptor) -> Result<Bookmark> {
+    Ok(Bookmark {
+        url: decryptor.decrypt(&bookmark.url)?,
+        title: decryptor.decrypt(&bookmark.title)?,
+        description: decryptor.decrypt(&bookmark.description)?,
+        ..bookmark.clone()
+    })
+}
+
+/// Decrypt the encrypted bookmarks tagged `tag`, or all of them, as one undoable batch
+///
+/// Bookmarks encrypted under another password are skipped and counted; when
+/// none matched the password it fails with [`BukursError::WrongPassword`].
+pub fn decrypt_tagged(db: &BukuDb, tag: Option<&str>, password: &str) -> Result<DecryptSummary> {
+    let candidates = match tag {
+        Some(tag) => tagged(db, tag)?,
+        None => db.get_rec_all()?,
+    };
+
+    let mut decryptor = FieldDecryptor::new(password);
+    let mut decrypted = Vec::new();
+    let mut summary = DecryptSummary::default();
+    for bookmark in candidates.iter().filter(|b| is_encrypted(b)) {
+        match decrypt_bookmark(bookmark, &mut decryptor) {
+            Ok(plain) => decrypted.push(plain),
+            Err(BukursError::WrongPassword) => summary.other_password += 1,
+            Err(e) => return Err(e),
+        }
+    }
+    if decrypted.is_empty() && summary.other_password > 0 {
+        return Err(BukursError::WrongPassword);
+    }
+
+    summary.decrypted = db.update_rec_batch_full(&decrypted)?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KDF: Kdf = Kdf::Sha256 { iterations: 2 };
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec(
+            "https://bank.example",
+            "My Bank",
+            ",banking,",
+            "account",
+            None,
+        )
+        .unwrap();
+        db.add_rec("https://news.example", "News", ",news,", "daily", None)
+            .unwrap();
+        db.add_rec("https://card.example", "Card", ",Banking-old,", "", None)
+            .unwrap();
+        db
+    }
+
+    fn search(db: &BukuDb, keyword: &str) -> usize {
+        db.search(&[keyword.to_string()], true, false, false)
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_tagged() {
+        let db = setup();
+        assert_eq!(encrypt_tagged(&db, "banking", KDF, "pw").unwrap(), 1);
+        // Already encrypted bookmarks are left alone
+        assert_eq!(encrypt_tagged(&db, "banking", KDF, "pw").unwrap(), 0);
+
+        let stored = db.get_rec_by_id(1).unwrap().unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(crypto::is_encrypted_field(&stored.title));
+        assert_eq!(stored.tags, ",banking,");
+        assert!(!is_encrypted(&db.get_rec_by_id(3).unwrap().unwrap()));
+
+        assert!(matches!(
+            decrypt_tagged(&db, Some("banking"), "wrong"),
+            Err(BukursError::WrongPassword)
+        ));
+        let summary = decrypt_tagged(&db, None, "pw").unwrap();
+        assert_eq!(summary.decrypted, 1);
+        assert_eq!(summary.other_password, 0);
+        let restored = db.get_rec_by_id(1).unwrap().unwrap();
+        assert_eq!(restored.url, "https://bank.example");
+        assert_eq!(restored.title, "My Bank");
+        assert_eq!(restored.description, "account");
+    }
+
+    #[test]
+    fn test_encrypted_bookmarks_leave_search_index() {
+        let db = setup();
+        assert_eq!(search(&db, "bank.example"), 1);
+
+        encrypt_tagged(&db, "banking", KDF, "pw").unwrap();
+        assert_eq!(search(&db, "bank.example"), 0);
+        assert_eq!(search(&db, "account"), 0);
+        assert_eq!(search(&db, "news"), 1);
+
+        decrypt_tagged(&db, Some("banking"), "pw").unwrap();
+        assert_eq!(search(&db, "bank.example"), 1);
+    }
+
+    #[test]
+    fn test_encrypt_drops_plaintext_history() {
+        let db = setup();
+        encrypt_tagged(&db, "banking", KDF, "pw").unwrap();
+
+        // Only the other two additions are left to undo
+        let mut undone = 0;
+        while db.undo_last().unwrap().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, 2);
+        assert!(is_encrypted(&db.get_rec_by_id(1).unwrap().unwrap()));
+
+        // Decryption is undoable, which encrypts again
+        decrypt_tagged(&db, None, "pw").unwrap();
+        db.undo_last().unwrap();
+        assert!(is_encrypted(&db.get_rec_by_id(1).unwrap().unwrap()));
+    }
+
+    #[test]
+    fn test_decrypt_skips_other_passwords() {
+        let db = setup();
+        encrypt_tagged(&db, "banking", KDF, "one").unwrap();
+        encrypt_tagged(&db, "news", KDF, "two").unwrap();
+
+        let summary = decrypt_tagged(&db, None, "two").unwrap();
+        assert_eq!(
+            summary,
+            DecryptSummary {
+                decrypted: 1,
+                other_password: 1
+            }
+        );
+        assert!(is_encrypted(&db.get_rec_by_id(1).unwrap().unwrap()));
+        assert_eq!(db.get_rec_by_id(2).unwrap().unwrap().title, "News");
+    }
+}
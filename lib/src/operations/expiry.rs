@@ -0,0 +1,129 @@
+//! Expiring bookmarks
+//!
+//! A bookmark added with `--expires 30d` records when it expires. Once that
+//! time has passed it is left out of default listings (`print`, the picker)
+//! but stays in the database until `expired delete` or a daemon `expire` job
+//! removes it, so nothing disappears without a trace.
+
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use crate::utils;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A bookmark past its expiry
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiredEntry {
+    pub bookmark: Bookmark,
+    /// When the bookmark expired (Unix seconds)
+    pub expired_at: i64,
+}
+
+/// Current time in Unix seconds
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Expiry for a bookmark that lasts `duration` (e.g. `30d`, `12h`) from `now`
+pub fn expires_at(duration: &str, now: i64) -> Result<i64> {
+    let secs = utils::parse_duration(duration).ok_or_else(|| {
+        BukursError::InvalidInput(format!(
+            "Invalid duration '{}' (expected e.g. 30d, 12h or 2w)",
+            duration
+        ))
+    })?;
+    Ok(now.saturating_add(secs.min(i64::MAX as u64) as i64))
+}
+
+/// Bookmarks that have expired by `now`, soonest first
+pub fn expired(db: &BukuDb, now: i64) -> Result<Vec<ExpiredEntry>> {
+    Ok(db
+        .get_rec_expired(now)?
+        .into_iter()
+        .map(|(bookmark, expired_at)| ExpiredEntry {
+            bookmark,
+            expired_at,
+        })
+        .collect())
+}
+
+/// `bookmarks` without the ones that have expired by `now`
+pub fn hide_expired(db: &BukuDb, bookmarks: Vec<Bookmark>, now: i64) -> Result<Vec<Bookmark>> {
+    let expired: HashSet<usize> = db
+        .get_rec_expired(now)?
+        .into_iter()
+        .map(|(bookmark, _)| bookmark.id)
+        .collect();
+    if expired.is_empty() {
+        return Ok(bookmarks);
+    }
+    Ok(bookmarks
+        .into_iter()
+        .filter(|b| !expired.contains(&b.id))
+        .collect())
+}
+
+/// Delete every bookmark that has expired by `now` as one undoable batch
+pub fn delete_expired(db: &BukuDb, now: i64) -> Result<usize> {
+    let ids: Vec<usize> = db
+        .get_rec_expired(now)?
+        .into_iter()
+        .map(|(bookmark, _)| bookmark.id)
+        .collect();
+    Ok(db.delete_rec_batch(&ids)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=3 {
+            db.add_rec(&format!("https://{}.com", i), "", ",", "", None)
+                .unwrap();
+        }
+        db.set_expiry(1, Some(NOW - 60)).unwrap();
+        db.set_expiry(2, Some(NOW + 86_400)).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_expires_at() {
+        assert_eq!(expires_at("30d", NOW).unwrap(), NOW + 30 * 86_400);
+        assert_eq!(expires_at("12h", NOW).unwrap(), NOW + 12 * 3600);
+        assert!(expires_at("soon", NOW).is_err());
+    }
+
+    #[test]
+    fn test_expired_bookmarks_are_hidden() {
+        let db = setup();
+        let listed = hide_expired(&db, db.get_rec_all().unwrap(), NOW).unwrap();
+        let ids: Vec<usize> = listed.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+
+        let entries = expired(&db, NOW).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].bookmark.id, 1);
+        assert_eq!(entries[0].expired_at, NOW - 60);
+
+        // A day later the second one has expired too
+        assert_eq!(expired(&db, NOW + 86_400).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_delete_expired_is_undoable() {
+        let db = setup();
+        assert_eq!(delete_expired(&db, NOW).unwrap(), 1);
+        assert!(db.get_rec_by_id(1).unwrap().is_none());
+        assert_eq!(delete_expired(&db, NOW).unwrap(), 0);
+
+        db.undo_last().unwrap();
+        assert!(db.get_rec_by_id(1).unwrap().is_some());
+    }
+}
@@ -0,0 +1,144 @@
+//! Narrowing the library down to a subset (`export --tag/--search/--ids`)
+//!
+//! IDs and keywords select the way `print` does (see [`Selector`]); tags
+//! keep bookmarks carrying any of them, as `publish --tags` does.
+
+use super::selector::Selector;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use crate::tags::parse_tags;
+use serde::{Deserialize, Serialize};
+
+/// Which bookmarks to keep; each filter given must match, an empty one keeps everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkFilter {
+    /// Keep bookmarks with any of these tags (case-insensitive)
+    pub tags: Vec<String>,
+    /// Keep bookmarks whose title, URL, tags or description contain any of these keywords
+    pub search: Vec<String>,
+    /// Keep these IDs, ranges and relative indices (`5`, `1-10`, `-3..-1`)
+    pub ids: Vec<String>,
+}
+
+impl BookmarkFilter {
+    /// Tags only, as `publish` selects
+    pub fn tagged(tags: &[String]) -> Self {
+        Self {
+            tags: tags.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.search.is_empty() && self.ids.is_empty()
+    }
+
+    /// The bookmarks of `all` (the whole library) that pass, by ID
+    ///
+    /// Relative indices count back from the most recent of `all`, before the
+    /// other filters narrow it.
+    pub fn apply(&self, all: Vec<Bookmark>) -> Result<Vec<Bookmark>> {
+        let mut bookmarks = all;
+        if !self.ids.is_empty() {
+            let selector = Selector::parse(&self.ids)?;
+            if matches!(selector, Selector::Keywords(_)) {
+                return Err(BukursError::InvalidInput(format!(
+                    "Not IDs or ranges: {} (use --search for keywords)",
+                    self.ids.join(" ")
+                )));
+            }
+            bookmarks = selector.select(bookmarks).bookmarks;
+        }
+        if !self.search.is_empty() {
+            bookmarks = Selector::Keywords(self.search.clone())
+                .select(bookmarks)
+                .bookmarks;
+        }
+        bookmarks.retain(|b| self.has_any_tag(b));
+        bookmarks.sort_by_key(|b| b.id);
+        Ok(bookmarks)
+    }
+
+    /// Whether `bookmark` has one of the filter's tags; true without tags
+    pub fn has_any_tag(&self, bookmark: &Bookmark) -> bool {
+        if self.tags.is_empty() {
+            return true;
+        }
+        let own = parse_tags(&bookmark.tags);
+        self.tags
+            .iter()
+            .any(|tag| own.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn library() -> Vec<Bookmark> {
+        [
+            (1, "https://rust-lang.org", "Rust", ",lang,public,"),
+            (2, "https://go.dev", "Go", ",lang,"),
+            (3, "https://example.com", "Example", ",Public,"),
+            (4, "https://blog.rust-lang.org", "Rust Blog", ",news,"),
+        ]
+        .into_iter()
+        .map(|(id, url, title, tags)| {
+            Bookmark::new(
+                id,
+                url.to_string(),
+                title.to_string(),
+                tags.to_string(),
+                String::new(),
+            )
+        })
+        .collect()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case(&[], &[], &[], vec![1, 2, 3, 4])]
+    #[case(&["public"], &[], &[], vec![1, 3])]
+    #[case(&["news", "lang"], &[], &[], vec![1, 2, 4])]
+    #[case(&[], &["rust"], &[], vec![1, 4])]
+    #[case(&[], &[], &["2-3"], vec![2, 3])]
+    #[case(&[], &[], &["-2"], vec![3])]
+    #[case(&[], &[], &["-2..-1"], vec![3, 4])]
+    #[case(&["public"], &["rust"], &[], vec![1])]
+    #[case(&["lang"], &[], &["2-4"], vec![2])]
+    fn test_apply(
+        #[case] tags: &[&str],
+        #[case] search: &[&str],
+        #[case] ids: &[&str],
+        #[case] expected: Vec<usize>,
+    ) {
+        let filter = BookmarkFilter {
+            tags: strings(tags),
+            search: strings(search),
+            ids: strings(ids),
+        };
+        let kept: Vec<usize> = filter
+            .apply(library())
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(kept, expected);
+    }
+
+    #[test]
+    fn test_apply_rejects_keywords_as_ids() {
+        let filter = BookmarkFilter {
+            ids: strings(&["rust"]),
+            ..BookmarkFilter::default()
+        };
+        assert!(matches!(
+            filter.apply(library()),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+}
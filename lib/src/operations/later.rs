@@ -0,0 +1,153 @@
+//! Read-later queue
+//!
+//! A bookmark is unread while it carries the `unread` tag, so the queue shows
+//! up in tag searches and exports too. The queue is ordered by when bookmarks
+//! were added, oldest first.
+
+use crate::db::BukuDb;
+use crate::error::Result;
+use crate::models::bookmark::Bookmark;
+use crate::tags::{format_tags, parse_tags};
+
+/// Tag marking a bookmark as unread
+pub const UNREAD_TAG: &str = "unread";
+
+/// A bookmark waiting to be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaterEntry {
+    pub bookmark: Bookmark,
+    /// When the bookmark was added (Unix seconds, 0 if unknown)
+    pub added_at: i64,
+}
+
+/// Unread bookmarks, oldest first
+pub fn queue(db: &BukuDb) -> Result<Vec<LaterEntry>> {
+    Ok(db
+        .get_rec_tagged_by_age(UNREAD_TAG)?
+        .into_iter()
+        // LIKE matching is case-insensitive; keep exact tags only
+        .filter(|(bookmark, _)| is_unread(bookmark))
+        .map(|(bookmark, added_at)| LaterEntry { bookmark, added_at })
+        .collect())
+}
+
+/// Number of unread bookmarks
+pub fn count(db: &BukuDb) -> Result<usize> {
+    Ok(queue(db)?.len())
+}
+
+/// Whether a bookmark is in the read-later queue
+pub fn is_unread(bookmark: &Bookmark) -> bool {
+    parse_tags(&bookmark.tags).iter().any(|t| t == UNREAD_TAG)
+}
+
+/// Stored tags with the unread tag added
+pub fn with_unread_tag(tags: &str) -> String {
+    let mut all = parse_tags(tags);
+    if !all.iter().any(|t| t == UNREAD_TAG) {
+        all.push(UNREAD_TAG.to_string());
+    }
+    format_tags(&all)
+}
+
+/// Add bookmarks to the queue as one undoable batch; returns how many changed
+pub fn mark_unread(db: &BukuDb, bookmarks: &[Bookmark]) -> Result<usize> {
+    let changed: Vec<Bookmark> = bookmarks
+        .iter()
+        .filter(|b| !is_unread(b))
+        .map(|b| Bookmark {
+            tags: with_unread_tag(&b.tags),
+            ..b.clone()
+        })
+        .collect();
+    apply(db, &changed)
+}
+
+/// Remove bookmarks from the queue as one undoable batch; returns how many changed
+pub fn mark_read(db: &BukuDb, bookmarks: &[Bookmark]) -> Result<usize> {
+    let changed: Vec<Bookmark> = bookmarks
+        .iter()
+        .filter(|b| is_unread(b))
+        .map(|b| {
+            let tags: Vec<String> = parse_tags(&b.tags)
+                .into_iter()
+                .filter(|t| t != UNREAD_TAG)
+                .collect();
+            Bookmark {
+                tags: format_tags(&tags),
+                ..b.clone()
+            }
+        })
+        .collect();
+    apply(db, &changed)
+}
+
+/// Take the oldest unread bookmark off the queue and return it
+pub fn pop(db: &BukuDb) -> Result<Option<Bookmark>> {
+    let Some(oldest) = queue(db)?.into_iter().next() else {
+        return Ok(None);
+    };
+    mark_read(db, std::slice::from_ref(&oldest.bookmark))?;
+    Ok(Some(oldest.bookmark))
+}
+
+fn apply(db: &BukuDb, changed: &[Bookmark]) -> Result<usize> {
+    let (updated, _) = db.update_rec_batch_with_tags(changed, None, None, None, None, None)?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "A", ",rust,", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "B", ",", "", None).unwrap();
+        db.add_rec("https://c.com", "C", ",Unread-ish,", "", None)
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_mark_unread_and_queue_order() {
+        let db = setup();
+        let all = db.get_rec_all().unwrap();
+
+        assert_eq!(mark_unread(&db, &all[..2]).unwrap(), 2);
+        // Already queued bookmarks are left alone
+        assert_eq!(
+            mark_unread(&db, &db.get_rec_all().unwrap()[..1]).unwrap(),
+            0
+        );
+
+        let ids: Vec<usize> = queue(&db).unwrap().iter().map(|e| e.bookmark.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().tags, ",rust,unread,");
+        assert_eq!(count(&db).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_pop_takes_oldest() {
+        let db = setup();
+        mark_unread(&db, &db.get_rec_all().unwrap()).unwrap();
+
+        assert_eq!(pop(&db).unwrap().unwrap().id, 1);
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().tags, ",rust,");
+        assert_eq!(pop(&db).unwrap().unwrap().id, 2);
+        assert_eq!(pop(&db).unwrap().unwrap().id, 3);
+        assert_eq!(pop(&db).unwrap(), None);
+    }
+
+    #[test]
+    fn test_mark_read_is_undoable() {
+        let db = setup();
+        mark_unread(&db, &db.get_rec_all().unwrap()).unwrap();
+        assert_eq!(mark_read(&db, &db.get_rec_all().unwrap()).unwrap(), 3);
+        assert_eq!(count(&db).unwrap(), 0);
+
+        db.undo_last().unwrap();
+        assert_eq!(count(&db).unwrap(), 3);
+    }
+}
@@ -1,6 +1,25 @@
+#[cfg(feature = "full")]
+pub mod encrypted;
+#[cfg(feature = "full")]
+pub mod expiry;
+pub mod filter;
+#[cfg(feature = "full")]
+pub mod later;
+#[cfg(feature = "full")]
+pub mod ordering;
+#[cfg(feature = "full")]
+pub mod rating;
+pub mod refine;
+pub mod selector;
+pub mod timeframe;
+
+#[cfg(feature = "full")]
 use crate::db::BukuDb;
 use crate::models::bookmark::Bookmark;
-use crate::utils;
+
+pub use filter::BookmarkFilter;
+pub use refine::ResultSet;
+pub use selector::{Index, Selector, SelectorError, SelectorTerm};
 
 /// Selection modes supported by the application
 #[derive(Debug, Clone, PartialEq)]
@@ -23,152 +42,68 @@ pub struct BookmarkSelection {
     pub bookmarks: Vec<Bookmark>,
 }
 
-/// Check if input looks like an ID or range (numeric), not a keyword
-pub fn is_id_or_range(input: &str) -> bool {
-    let input = utils::trim_both_simd(input);
-
-    // Wildcard is considered ID-like
-    if input == "*" {
-        return true;
-    }
-
-    // Range format: "5-10"
-    if utils::has_char(b'-', input) {
-        let parts: Vec<&str> = input.split('-').collect();
-        if parts.len() == 2 {
-            return parts[0].parse::<usize>().is_ok() && parts[1].parse::<usize>().is_ok();
+impl BookmarkSelection {
+    /// Selection of bookmarks picked explicitly, e.g. marked in the fuzzy picker
+    pub fn from_picked(bookmarks: Vec<Bookmark>) -> Self {
+        let selected_ids: Vec<usize> = bookmarks.iter().map(|b| b.id).collect();
+        Self {
+            mode: SelectionMode::ByIds(selected_ids.clone()),
+            selected_ids,
+            bookmarks,
         }
-        return false;
     }
+}
 
-    // Single ID: "5"
-    input.parse::<usize>().is_ok()
+/// Check if input looks like an ID, range or relative index rather than a keyword
+pub fn is_id_or_range(input: &str) -> bool {
+    // Malformed indices ("0", "9-3") still count; parsing them reports the error
+    !matches!(SelectorTerm::parse(input), Ok(None))
 }
 
-/// Parse range syntax into individual IDs
+/// Parse selector syntax into the IDs of existing bookmarks
 /// Supports:
 /// - "*" for all bookmarks
 /// - Single IDs: "5"
 /// - Ranges: "1-5"
+/// - Most recent: "-1"
+/// - Relative ranges: "-3..-1"
 /// - Multiple: "1 3 5-7"
+#[cfg(feature = "full")]
 pub fn parse_ranges(
     inputs: &[String],
     db: &BukuDb,
 ) -> Result<Vec<usize>, crate::error::BukursError> {
-    let mut ids = Vec::new();
-
-    // Get all bookmarks to find valid IDs
-    let all_records = db.get_rec_all()?;
-    if all_records.is_empty() {
-        return Ok(ids);
-    }
-
-    let all_ids: Vec<usize> = all_records.iter().map(|b| b.id).collect();
-
-    for input in inputs {
-        let input = utils::trim_both_simd(input);
-
-        if input == "*" {
-            // Wildcard - return all IDs
-            return Ok(all_ids);
-        } else if utils::has_char(b'-', input) {
-            // Range: "5-10"
-            let parts: Vec<&str> = input.split('-').collect();
-            if parts.len() == 2 {
-                if let (Ok(start), Ok(end)) = (parts[0].parse::<usize>(), parts[1].parse::<usize>())
-                {
-                    for id in start..=end {
-                        if all_ids.contains(&id) {
-                            ids.push(id);
-                        }
-                    }
-                } else {
-                    eprintln!("Warning: Invalid range format: {}", input);
-                }
-            } else {
-                eprintln!("Warning: Invalid range format: {}", input);
-            }
-        } else {
-            // Single ID
-            if let Ok(id) = input.parse::<usize>() {
-                if all_ids.contains(&id) {
-                    ids.push(id);
-                }
-            } else {
-                eprintln!("Warning: Invalid ID: {}", input);
-            }
-        }
-    }
-
-    // Remove duplicates
-    ids.sort();
-    ids.dedup();
-
-    Ok(ids)
+    let mut existing: Vec<usize> = db.get_rec_all()?.iter().map(|b| b.id).collect();
+    existing.sort_unstable();
+    Ok(Selector::parse(inputs)?.ids_in(&existing))
 }
 
 /// Resolve bookmarks by analyzing inputs and fetching matching bookmarks
 /// This is interface-agnostic and doesn't prompt or print
 /// Can be used for delete, print, or any other operation that needs to select bookmarks
+#[cfg(feature = "full")]
 pub fn resolve_bookmarks(
     inputs: &[String],
     db: &BukuDb,
 ) -> crate::error::Result<BookmarkSelection> {
-    // Determine selection mode and get IDs
-    let (mode, selected_ids) = if inputs.is_empty() {
-        // No args → select all bookmarks
-        let all_records = db.get_rec_all()?;
-        let all_ids: Vec<usize> = all_records.iter().map(|b| b.id).collect();
-        (SelectionMode::All, all_ids)
-    } else if inputs.iter().all(|s| is_id_or_range(s)) {
-        // All inputs are IDs/ranges → select by IDs
-        let ids = parse_ranges(inputs, db)?;
-        (SelectionMode::ByIds(ids.clone()), ids)
-    } else {
-        // Inputs are keywords → search for matching bookmarks
-        let all_bookmarks = db.get_rec_all()?;
-        let matching: Vec<usize> = all_bookmarks
-            .iter()
-            .filter(|b| {
-                inputs.iter().any(|keyword| {
-                    let kw_lower = keyword.to_lowercase();
-                    b.title.to_lowercase().contains(&kw_lower)
-                        || b.description.to_lowercase().contains(&kw_lower)
-                        || b.tags.to_lowercase().contains(&kw_lower)
-                        || b.url.to_lowercase().contains(&kw_lower)
-                })
-            })
-            .map(|b| b.id)
-            .collect();
-
-        (SelectionMode::ByKeywords(inputs.to_vec()), matching)
-    };
-
-    // Fetch the actual bookmark data
-    let bookmarks: Vec<Bookmark> = selected_ids
-        .iter()
-        .filter_map(|id| db.get_rec_by_id(*id).ok().flatten())
-        .collect();
-
-    Ok(BookmarkSelection {
-        mode,
-        selected_ids,
-        bookmarks,
-    })
+    Selector::parse(inputs)?.resolve(db)
 }
 
 /// Prepare a delete operation (wrapper around resolve_bookmarks for backward compatibility)
+#[cfg(feature = "full")]
 pub fn prepare_delete(ids: &[String], db: &BukuDb) -> crate::error::Result<BookmarkSelection> {
     resolve_bookmarks(ids, db)
 }
 
 /// Prepare a print operation (wrapper around resolve_bookmarks)
+#[cfg(feature = "full")]
 pub fn prepare_print(ids: &[String], db: &BukuDb) -> crate::error::Result<BookmarkSelection> {
     resolve_bookmarks(ids, db)
 }
 
 /// Execute a delete operation
 /// Returns the number of bookmarks deleted
+#[cfg(feature = "full")]
 pub fn execute_delete(operation: &BookmarkSelection, db: &BukuDb) -> crate::error::Result<usize> {
     // For multiple bookmarks, use batch delete to enable batch undo
     if operation.selected_ids.len() > 1 {
@@ -187,6 +122,18 @@ pub fn execute_delete(operation: &BookmarkSelection, db: &BukuDb) -> crate::erro
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_selection_from_picked() {
+        let picked = vec![
+            Bookmark::new(7, "https://a.com".into(), "A".into(), ",".into(), "".into()),
+            Bookmark::new(3, "https://b.com".into(), "B".into(), ",".into(), "".into()),
+        ];
+        let selection = BookmarkSelection::from_picked(picked);
+        assert_eq!(selection.selected_ids, vec![7, 3]);
+        assert_eq!(selection.mode, SelectionMode::ByIds(vec![7, 3]));
+        assert_eq!(selection.bookmarks.len(), 2);
+    }
+
     #[test]
     fn test_is_id_or_range_single_id() {
         assert!(is_id_or_range("5"));
@@ -0,0 +1,131 @@
+//! Pinned bookmarks and manual ordering
+//!
+//! Pinned bookmarks are listed before all others. Within that, bookmarks
+//! follow the order set with `move`, and bookmarks that were never moved come
+//! last, oldest first. Moving a bookmark numbers every bookmark in the new
+//! order, so positions stay unique.
+
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use std::collections::HashMap;
+
+/// Where to move a bookmark, relative to another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Before(usize),
+    After(usize),
+}
+
+/// Sort bookmarks into display order (see the module docs)
+pub fn sort_for_display(db: &BukuDb, bookmarks: &mut [Bookmark]) -> Result<()> {
+    let rank: HashMap<usize, usize> = db
+        .get_display_order()?
+        .into_iter()
+        .enumerate()
+        .map(|(rank, id)| (id, rank))
+        .collect();
+    bookmarks.sort_by_key(|b| rank.get(&b.id).copied().unwrap_or(usize::MAX));
+    Ok(())
+}
+
+/// Pin (or unpin) bookmarks; returns how many changed
+pub fn pin(db: &BukuDb, ids: &[usize], pinned: bool) -> Result<usize> {
+    Ok(db.set_pinned(ids, pinned)?)
+}
+
+/// Move bookmark `id` next to another one
+pub fn move_bookmark(db: &BukuDb, id: usize, placement: Placement) -> Result<()> {
+    let target = match placement {
+        Placement::Before(target) | Placement::After(target) => target,
+    };
+    if id == target {
+        return Err(BukursError::InvalidInput(
+            "Cannot move a bookmark relative to itself".to_string(),
+        ));
+    }
+
+    let order = moved(db.get_display_order()?, id, placement)?;
+    db.set_positions(&order)?;
+    Ok(())
+}
+
+/// `order` with `id` taken out and put next to the placement's target
+fn moved(mut order: Vec<usize>, id: usize, placement: Placement) -> Result<Vec<usize>> {
+    let from = order
+        .iter()
+        .position(|&i| i == id)
+        .ok_or(BukursError::BookmarkNotFound(id))?;
+    order.remove(from);
+
+    let (target, offset) = match placement {
+        Placement::Before(target) => (target, 0),
+        Placement::After(target) => (target, 1),
+    };
+    let to = order
+        .iter()
+        .position(|&i| i == target)
+        .ok_or(BukursError::BookmarkNotFound(target))?;
+    order.insert(to + offset, id);
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(bookmarks: &[Bookmark]) -> Vec<usize> {
+        bookmarks.iter().map(|b| b.id).collect()
+    }
+
+    fn setup(count: usize) -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=count {
+            db.add_rec(&format!("https://{}.com", i), "", ",", "", None)
+                .unwrap();
+        }
+        db
+    }
+
+    fn display(db: &BukuDb) -> Vec<usize> {
+        let mut all = db.get_rec_all().unwrap();
+        sort_for_display(db, &mut all).unwrap();
+        ids(&all)
+    }
+
+    #[test]
+    fn test_pinned_bookmarks_come_first() {
+        let db = setup(4);
+        assert_eq!(pin(&db, &[3, 4], true).unwrap(), 2);
+        assert_eq!(display(&db), vec![3, 4, 1, 2]);
+
+        // Pinning again changes nothing
+        assert_eq!(pin(&db, &[3], true).unwrap(), 0);
+        assert_eq!(pin(&db, &[3], false).unwrap(), 1);
+        assert_eq!(display(&db), vec![4, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_move_before_and_after() {
+        let db = setup(4);
+        move_bookmark(&db, 4, Placement::Before(2)).unwrap();
+        assert_eq!(display(&db), vec![1, 4, 2, 3]);
+
+        move_bookmark(&db, 1, Placement::After(3)).unwrap();
+        assert_eq!(display(&db), vec![4, 2, 3, 1]);
+
+        // New bookmarks go after the ordered ones
+        db.add_rec("https://5.com", "", ",", "", None).unwrap();
+        assert_eq!(display(&db), vec![4, 2, 3, 1, 5]);
+    }
+
+    #[test]
+    fn test_move_rejects_missing_or_same_bookmark() {
+        let db = setup(2);
+        assert!(matches!(
+            move_bookmark(&db, 1, Placement::Before(9)),
+            Err(BukursError::BookmarkNotFound(9))
+        ));
+        assert!(move_bookmark(&db, 2, Placement::After(2)).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Star ratings
+//!
+//! A bookmark is rated from one to five stars, or unrated. Starring a
+//! bookmark gives it the top rating, so starred bookmarks lead lists sorted
+//! by rating.
+
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use std::cmp::Reverse;
+
+/// Highest rating, given by `star`
+pub const MAX_RATING: u8 = 5;
+
+/// Rate bookmarks from 1 to [`MAX_RATING`], or clear their rating with 0;
+/// returns how many changed
+pub fn rate(db: &BukuDb, ids: &[usize], rating: u8) -> Result<usize> {
+    if rating > MAX_RATING {
+        return Err(BukursError::InvalidInput(format!(
+            "Rating must be between 0 and {}, got {}",
+            MAX_RATING, rating
+        )));
+    }
+    Ok(db.set_rating(ids, rating)?)
+}
+
+/// `rating` as filled and empty stars, e.g. `★★★☆☆`; empty when unrated
+pub fn stars(rating: u8) -> String {
+    if rating == 0 {
+        return String::new();
+    }
+    let filled = rating.min(MAX_RATING) as usize;
+    "★".repeat(filled) + &"☆".repeat(MAX_RATING as usize - filled)
+}
+
+/// Keep the bookmarks rated at least `min`
+pub fn at_least(db: &BukuDb, mut bookmarks: Vec<Bookmark>, min: u8) -> Result<Vec<Bookmark>> {
+    let ratings = db.get_ratings()?;
+    bookmarks.retain(|b| ratings.get(&b.id).copied().unwrap_or(0) >= min);
+    Ok(bookmarks)
+}
+
+/// Sort bookmarks highest rated first, keeping their order within a rating
+pub fn sort_by_rating(db: &BukuDb, bookmarks: &mut [Bookmark]) -> Result<()> {
+    let ratings = db.get_ratings()?;
+    bookmarks.sort_by_key(|b| Reverse(ratings.get(&b.id).copied().unwrap_or(0)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn setup() -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=4 {
+            db.add_rec(&format!("https://{}.com", i), "", ",", "", None)
+                .unwrap();
+        }
+        db
+    }
+
+    fn ids(bookmarks: &[Bookmark]) -> Vec<usize> {
+        bookmarks.iter().map(|b| b.id).collect()
+    }
+
+    #[rstest]
+    #[case(0, "")]
+    #[case(1, "★☆☆☆☆")]
+    #[case(5, "★★★★★")]
+    fn test_stars(#[case] rating: u8, #[case] expected: &str) {
+        assert_eq!(stars(rating), expected);
+    }
+
+    #[test]
+    fn test_rate_filter_and_sort() {
+        let db = setup();
+        assert_eq!(rate(&db, &[2, 4], 3).unwrap(), 2);
+        assert_eq!(rate(&db, &[3], MAX_RATING).unwrap(), 1);
+        // Rating again changes nothing
+        assert_eq!(rate(&db, &[2], 3).unwrap(), 0);
+        assert!(rate(&db, &[1], 6).is_err());
+
+        let rated = at_least(&db, db.get_rec_all().unwrap(), 3).unwrap();
+        assert_eq!(ids(&rated), vec![2, 3, 4]);
+        assert_eq!(ids(&at_least(&db, rated, 4).unwrap()), vec![3]);
+
+        let mut all = db.get_rec_all().unwrap();
+        sort_by_rating(&db, &mut all).unwrap();
+        assert_eq!(ids(&all), vec![3, 2, 4, 1]);
+
+        // 0 clears a rating
+        assert_eq!(rate(&db, &[3], 0).unwrap(), 1);
+        assert_eq!(db.get_ratings().unwrap().get(&3), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Narrowing a search to the results of the previous one
+//!
+//! Each search remembers the IDs it returned in a file next to the database
+//! (`bookmarks.results.json` for `bookmarks.db`), so the shell and one-shot
+//! `search --within-last` can refine it: the new search runs as usual and
+//! only bookmarks that were in the last result set are kept.
+
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// IDs returned by a search, in the order they were shown
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultSet {
+    /// Keywords of the search that produced the set (for messages)
+    pub keywords: Vec<String>,
+    pub ids: Vec<usize>,
+}
+
+impl ResultSet {
+    pub fn new(keywords: &[String], bookmarks: &[Bookmark]) -> Self {
+        Self {
+            keywords: keywords.to_vec(),
+            ids: bookmarks.iter().map(|b| b.id).collect(),
+        }
+    }
+
+    /// File holding the last result set for the database at `db_path`
+    pub fn path_for(db_path: &Path) -> PathBuf {
+        db_path.with_extension("results.json")
+    }
+
+    /// Read a saved result set; None when no search has been saved yet
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|e| BukursError::Json(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save the result set through a temporary file, so readers never see half of it
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_string(self).map_err(|e| BukursError::Json(e.to_string()))?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Keep the bookmarks of `results` that are in this set, in their new order
+    pub fn refine(&self, results: Vec<Bookmark>) -> Vec<Bookmark> {
+        let previous: HashSet<usize> = self.ids.iter().copied().collect();
+        results
+            .into_iter()
+            .filter(|b| previous.contains(&b.id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(id: usize) -> Bookmark {
+        Bookmark::new(
+            id,
+            format!("https://{}.com", id),
+            "".into(),
+            ",".into(),
+            "".into(),
+        )
+    }
+
+    #[test]
+    fn test_refine_keeps_previous_results_only() {
+        let previous = ResultSet::new(
+            &["rust".to_string()],
+            &[bookmark(1), bookmark(4), bookmark(7)],
+        );
+        let refined = previous.refine(vec![bookmark(7), bookmark(2), bookmark(1)]);
+        let ids: Vec<usize> = refined.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![7, 1]);
+    }
+
+    #[test]
+    fn test_result_set_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ResultSet::path_for(&dir.path().join("bookmarks.db"));
+        assert_eq!(path, dir.path().join("bookmarks.results.json"));
+        assert_eq!(ResultSet::read(&path).unwrap(), None);
+
+        let set = ResultSet::new(&["async".to_string()], &[bookmark(3), bookmark(9)]);
+        set.write(&path).unwrap();
+        assert_eq!(ResultSet::read(&path).unwrap(), Some(set));
+    }
+}
@@ -0,0 +1,365 @@
+//! Bookmark selectors shared by every command that takes IDs
+//!
+//! A selector is a list of terms, each of which is one of:
+//! - `*` for every bookmark
+//! - `5` for a single index
+//! - `1-5` for an inclusive range of indices
+//! - `-1` for the most recently added bookmark (`-2` the one before, ...)
+//! - `-3..-1` for a range whose ends may be relative, e.g. the last three
+//!   bookmarks (`5..-1` is everything from index 5 to the most recent)
+//!
+//! Several terms may be given as separate arguments or in one argument
+//! separated by spaces or commas. Input that isn't made up entirely of such
+//! terms is treated as search keywords instead.
+
+use super::{BookmarkSelection, SelectionMode};
+#[cfg(feature = "full")]
+use crate::db::BukuDb;
+use crate::models::bookmark::Bookmark;
+
+/// Why a selector term that looks like an index is invalid
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SelectorError {
+    #[error("index {0:?} is out of range (indices start at 1)")]
+    ZeroIndex(String),
+    #[error("index {0:?} is too large")]
+    Overflow(String),
+    #[error("range {0:?} ends before it starts")]
+    ReversedRange(String),
+}
+
+/// A single selector term
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorTerm {
+    /// `*`
+    All,
+    /// `5`
+    Id(usize),
+    /// `1-5`, inclusive
+    Range(usize, usize),
+    /// `-n`: the n-th most recently added bookmark
+    Last(usize),
+    /// `a..b`, inclusive, where either end may be relative
+    Between(Index, Index),
+}
+
+/// One end of a `..` range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Index {
+    /// `5`
+    Id(usize),
+    /// `-n`: the n-th most recently added bookmark
+    Last(usize),
+}
+
+impl Index {
+    fn parse(token: &str, whole: &str) -> Result<Option<Self>, SelectorError> {
+        Ok(match token.strip_prefix('-') {
+            Some(digits) => parse_index(digits, whole)?.map(Self::Last),
+            None => parse_index(token, whole)?.map(Self::Id),
+        })
+    }
+
+    /// Position in `existing` (sorted ascending) of the first ID at or after this index
+    fn lower(self, existing: &[usize]) -> usize {
+        match self {
+            Self::Id(id) => existing.partition_point(|&e| e < id),
+            Self::Last(n) => existing.len().saturating_sub(n),
+        }
+    }
+
+    /// Position in `existing` just past the last ID at or before this index
+    fn upper(self, existing: &[usize]) -> usize {
+        match self {
+            Self::Id(id) => existing.partition_point(|&e| e <= id),
+            Self::Last(n) => (existing.len() + 1).saturating_sub(n),
+        }
+    }
+}
+
+/// A parsed selector: indices, or keywords when the input isn't all indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    /// No input; selects every bookmark
+    Empty,
+    Terms(Vec<SelectorTerm>),
+    Keywords(Vec<String>),
+}
+
+/// Parse a whole number, distinguishing "not a number" from "too large"
+fn parse_index(digits: &str, token: &str) -> Result<Option<usize>, SelectorError> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(None);
+    }
+    match digits.parse::<usize>() {
+        Ok(0) => Err(SelectorError::ZeroIndex(token.to_string())),
+        Ok(n) => Ok(Some(n)),
+        Err(_) => Err(SelectorError::Overflow(token.to_string())),
+    }
+}
+
+impl SelectorTerm {
+    /// Parse one term; `Ok(None)` means the token isn't index-like (a keyword)
+    pub fn parse(token: &str) -> Result<Option<Self>, SelectorError> {
+        let token = token.trim();
+        if token == "*" {
+            return Ok(Some(Self::All));
+        }
+        if let Some((start, end)) = token.split_once("..") {
+            let (Some(start), Some(end)) = (Index::parse(start, token)?, Index::parse(end, token)?)
+            else {
+                return Ok(None);
+            };
+            let reversed = match (start, end) {
+                (Index::Id(a), Index::Id(b)) => b < a,
+                (Index::Last(a), Index::Last(b)) => a < b,
+                // Mixed ends depend on how many bookmarks there are
+                _ => false,
+            };
+            if reversed {
+                return Err(SelectorError::ReversedRange(token.to_string()));
+            }
+            return Ok(Some(Self::Between(start, end)));
+        }
+        if let Some(digits) = token.strip_prefix('-') {
+            return Ok(parse_index(digits, token)?.map(Self::Last));
+        }
+        if let Some((start, end)) = token.split_once('-') {
+            let (Some(start), Some(end)) = (parse_index(start, token)?, parse_index(end, token)?)
+            else {
+                return Ok(None);
+            };
+            if end < start {
+                return Err(SelectorError::ReversedRange(token.to_string()));
+            }
+            return Ok(Some(Self::Range(start, end)));
+        }
+        Ok(parse_index(token, token)?.map(Self::Id))
+    }
+}
+
+impl Selector {
+    /// Parse command arguments into a selector
+    ///
+    /// Index-like terms that are malformed (`0`, `9-3`) are errors rather than
+    /// keywords, so a typo doesn't silently turn into a search.
+    pub fn parse<S: AsRef<str>>(inputs: &[S]) -> Result<Self, SelectorError> {
+        let tokens: Vec<&str> = inputs
+            .iter()
+            .flat_map(|input| input.as_ref().split([' ', '\t', ',']))
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tokens.is_empty() {
+            return Ok(Self::Empty);
+        }
+
+        let mut terms = Vec::with_capacity(tokens.len());
+        for token in &tokens {
+            match SelectorTerm::parse(token)? {
+                Some(term) => terms.push(term),
+                None => {
+                    let keywords = inputs.iter().map(|s| s.as_ref().to_string()).collect();
+                    return Ok(Self::Keywords(keywords));
+                }
+            }
+        }
+        Ok(Self::Terms(terms))
+    }
+
+    /// IDs this selector picks out of `existing`, which must be sorted ascending
+    ///
+    /// Indices that don't exist are skipped. The result is sorted and deduplicated.
+    pub fn ids_in(&self, existing: &[usize]) -> Vec<usize> {
+        let terms = match self {
+            Self::Empty => return existing.to_vec(),
+            Self::Keywords(_) => return Vec::new(),
+            Self::Terms(terms) => terms,
+        };
+
+        let mut ids = Vec::new();
+        for term in terms {
+            match *term {
+                SelectorTerm::All => return existing.to_vec(),
+                SelectorTerm::Id(id) => {
+                    if existing.binary_search(&id).is_ok() {
+                        ids.push(id);
+                    }
+                }
+                SelectorTerm::Range(start, end) => {
+                    let from = existing.partition_point(|&id| id < start);
+                    let to = existing.partition_point(|&id| id <= end);
+                    ids.extend_from_slice(&existing[from..to]);
+                }
+                SelectorTerm::Last(n) => {
+                    if let Some(i) = existing.len().checked_sub(n) {
+                        ids.push(existing[i]);
+                    }
+                }
+                SelectorTerm::Between(start, end) => {
+                    let from = start.lower(existing);
+                    let to = end.upper(existing);
+                    if from < to {
+                        ids.extend_from_slice(&existing[from..to]);
+                    }
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Resolve the selector against the database
+    #[cfg(feature = "full")]
+    pub fn resolve(&self, db: &BukuDb) -> crate::error::Result<BookmarkSelection> {
+        Ok(self.select(db.get_rec_all()?))
+    }
+
+    /// Pick the bookmarks the selector refers to out of `all`, the whole library
+    pub fn select(&self, mut all: Vec<Bookmark>) -> BookmarkSelection {
+        all.sort_by_key(|b| b.id);
+
+        let (mode, bookmarks): (SelectionMode, Vec<Bookmark>) = match self {
+            Self::Keywords(keywords) => {
+                let lowered: Vec<String> = keywords.iter().map(|k| k.to_lowercase()).collect();
+                let matching = all
+                    .into_iter()
+                    .filter(|b| {
+                        lowered.iter().any(|kw| {
+                            b.title.to_lowercase().contains(kw)
+                                || b.description.to_lowercase().contains(kw)
+                                || b.tags.to_lowercase().contains(kw)
+                                || b.url.to_lowercase().contains(kw)
+                        })
+                    })
+                    .collect();
+                (SelectionMode::ByKeywords(keywords.clone()), matching)
+            }
+            Self::Empty => (SelectionMode::All, all),
+            Self::Terms(terms) if terms.contains(&SelectorTerm::All) => (SelectionMode::All, all),
+            Self::Terms(_) => {
+                let existing: Vec<usize> = all.iter().map(|b| b.id).collect();
+                let ids = self.ids_in(&existing);
+                let picked = all
+                    .into_iter()
+                    .filter(|b| ids.binary_search(&b.id).is_ok())
+                    .collect();
+                (SelectionMode::ByIds(ids), picked)
+            }
+        };
+
+        BookmarkSelection {
+            mode,
+            selected_ids: bookmarks.iter().map(|b| b.id).collect(),
+            bookmarks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("*", Some(SelectorTerm::All))]
+    #[case("5", Some(SelectorTerm::Id(5)))]
+    #[case(" 12 ", Some(SelectorTerm::Id(12)))]
+    #[case("1-5", Some(SelectorTerm::Range(1, 5)))]
+    #[case("3-3", Some(SelectorTerm::Range(3, 3)))]
+    #[case("-1", Some(SelectorTerm::Last(1)))]
+    #[case("-10", Some(SelectorTerm::Last(10)))]
+    #[case("-3..-1", Some(SelectorTerm::Between(Index::Last(3), Index::Last(1))))]
+    #[case("2..4", Some(SelectorTerm::Between(Index::Id(2), Index::Id(4))))]
+    #[case("5..-1", Some(SelectorTerm::Between(Index::Id(5), Index::Last(1))))]
+    #[case("rust", None)]
+    #[case("rust2024", None)]
+    #[case("1..x", None)]
+    #[case("1-abc", None)]
+    #[case("1-2-3", None)]
+    #[case("c++", None)]
+    #[case("-", None)]
+    #[case("--force", None)]
+    fn test_parse_term(#[case] token: &str, #[case] expected: Option<SelectorTerm>) {
+        assert_eq!(SelectorTerm::parse(token), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("0", SelectorError::ZeroIndex("0".to_string()))]
+    #[case("-0", SelectorError::ZeroIndex("-0".to_string()))]
+    #[case("0-4", SelectorError::ZeroIndex("0-4".to_string()))]
+    #[case("9-3", SelectorError::ReversedRange("9-3".to_string()))]
+    #[case("-1..-3", SelectorError::ReversedRange("-1..-3".to_string()))]
+    #[case("4..2", SelectorError::ReversedRange("4..2".to_string()))]
+    #[case("-0..-1", SelectorError::ZeroIndex("-0..-1".to_string()))]
+    #[case(
+        "99999999999999999999999",
+        SelectorError::Overflow("99999999999999999999999".to_string())
+    )]
+    fn test_parse_term_errors(#[case] token: &str, #[case] expected: SelectorError) {
+        assert_eq!(SelectorTerm::parse(token), Err(expected));
+    }
+
+    #[rstest]
+    #[case(vec![], Selector::Empty)]
+    #[case(vec!["1", "3 5-7"], Selector::Terms(vec![
+        SelectorTerm::Id(1),
+        SelectorTerm::Id(3),
+        SelectorTerm::Range(5, 7),
+    ]))]
+    #[case(vec!["1,-1"], Selector::Terms(vec![SelectorTerm::Id(1), SelectorTerm::Last(1)]))]
+    #[case(vec!["rust", "5"], Selector::Keywords(vec!["rust".to_string(), "5".to_string()]))]
+    fn test_parse_selector(#[case] inputs: Vec<&str>, #[case] expected: Selector) {
+        assert_eq!(Selector::parse(&inputs), Ok(expected));
+    }
+
+    #[rstest]
+    #[case(vec!["2", "4-7"], vec![2, 4, 7])]
+    #[case(vec!["-1"], vec![11])]
+    #[case(vec!["-2", "-1", "9"], vec![9, 11])]
+    #[case(vec!["-6"], vec![])]
+    #[case(vec!["100"], vec![])]
+    #[case(vec!["*"], vec![2, 4, 7, 9, 11])]
+    #[case(vec!["-3..-1"], vec![7, 9, 11])]
+    #[case(vec!["-10..-4"], vec![2, 4])]
+    #[case(vec!["-10..-6"], vec![])]
+    #[case(vec!["3..8"], vec![4, 7])]
+    #[case(vec!["5..-1"], vec![7, 9, 11])]
+    #[case(vec!["-2..9"], vec![9])]
+    #[case(vec!["10..-3"], vec![])]
+    fn test_ids_in(#[case] inputs: Vec<&str>, #[case] expected: Vec<usize>) {
+        let existing = [2, 4, 7, 9, 11];
+        assert_eq!(
+            Selector::parse(&inputs).unwrap().ids_in(&existing),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn test_resolve() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for (url, title) in [
+            ("https://rust-lang.org", "Rust"),
+            ("https://python.org", "Python"),
+            ("https://go.dev", "Go"),
+        ] {
+            db.add_rec(url, title, ",", "", None).unwrap();
+        }
+
+        let last = Selector::parse(&["-1"]).unwrap().resolve(&db).unwrap();
+        assert_eq!(last.mode, SelectionMode::ByIds(vec![3]));
+        assert_eq!(last.bookmarks[0].title, "Go");
+
+        let keywords = Selector::parse(&["python"]).unwrap().resolve(&db).unwrap();
+        assert_eq!(
+            keywords.mode,
+            SelectionMode::ByKeywords(vec!["python".to_string()])
+        );
+        assert_eq!(keywords.selected_ids, vec![2]);
+
+        let all = Selector::parse::<&str>(&[]).unwrap().resolve(&db).unwrap();
+        assert_eq!(all.mode, SelectionMode::All);
+        assert_eq!(all.selected_ids, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,157 @@
+//! Filtering by when bookmarks were added and last opened
+//! (`--added-after`, `--added-within`, `--not-visited-since`)
+//!
+//! Dates are `YYYY-MM-DD` in UTC; durations count back from now (`7d`, `1y`).
+//! Visits are only known for bookmarks opened through bukurs.
+
+#[cfg(feature = "full")]
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+#[cfg(feature = "full")]
+use crate::models::bookmark::Bookmark;
+use crate::utils;
+use serde::{Deserialize, Serialize};
+
+/// Time bounds a bookmark must fall within; an empty filter keeps everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeFilter {
+    /// Keep bookmarks added on or after this date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_after: Option<String>,
+    /// Keep bookmarks added within this long before now
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_within: Option<String>,
+    /// Keep bookmarks not opened for at least this long
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_visited_since: Option<String>,
+}
+
+impl TimeFilter {
+    pub fn is_empty(&self) -> bool {
+        self.added_after.is_none()
+            && self.added_within.is_none()
+            && self.not_visited_since.is_none()
+    }
+
+    /// The earliest time added and the time since which a bookmark must not
+    /// have been visited, as Unix seconds relative to `now`
+    ///
+    /// `--added-after` and `--added-within` together keep the later bound.
+    pub fn cutoffs(&self, now: i64) -> Result<(Option<i64>, Option<i64>)> {
+        let after = self.added_after.as_deref().map(date).transpose()?;
+        let within = self
+            .added_within
+            .as_deref()
+            .map(|d| ago(d, now))
+            .transpose()?;
+        let added_after = after.into_iter().chain(within).max();
+        let not_visited_since = self
+            .not_visited_since
+            .as_deref()
+            .map(|d| ago(d, now))
+            .transpose()?;
+        Ok((added_after, not_visited_since))
+    }
+
+    /// Keep the bookmarks that pass, in their order
+    #[cfg(feature = "full")]
+    pub fn apply(
+        &self,
+        db: &BukuDb,
+        mut bookmarks: Vec<Bookmark>,
+        now: i64,
+    ) -> Result<Vec<Bookmark>> {
+        if self.is_empty() {
+            return Ok(bookmarks);
+        }
+        let (added_after, not_visited_since) = self.cutoffs(now)?;
+        let ids = db.get_ids_in_timeframe(added_after, not_visited_since)?;
+        bookmarks.retain(|b| ids.contains(&b.id));
+        Ok(bookmarks)
+    }
+}
+
+fn date(s: &str) -> Result<i64> {
+    utils::parse_date(s).ok_or_else(|| {
+        BukursError::InvalidInput(format!("Invalid date '{}' (expected YYYY-MM-DD)", s))
+    })
+}
+
+/// `duration` before `now`
+fn ago(duration: &str, now: i64) -> Result<i64> {
+    let secs = utils::parse_duration(duration).ok_or_else(|| {
+        BukursError::InvalidInput(format!(
+            "Invalid duration '{}' (expected e.g. 7d, 2w or 1y)",
+            duration
+        ))
+    })?;
+    Ok(now.saturating_sub(secs.min(i64::MAX as u64) as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const DAY: i64 = 86_400;
+
+    fn filter(after: Option<&str>, within: Option<&str>, unvisited: Option<&str>) -> TimeFilter {
+        TimeFilter {
+            added_after: after.map(str::to_string),
+            added_within: within.map(str::to_string),
+            not_visited_since: unvisited.map(str::to_string),
+        }
+    }
+
+    #[rstest]
+    #[case(filter(None, None, None), (None, None))]
+    #[case(filter(Some("1970-01-03"), None, None), (Some(2 * DAY), None))]
+    #[case(filter(None, Some("7d"), None), (Some(3 * DAY), None))]
+    #[case(filter(Some("1970-01-02"), Some("7d"), None), (Some(3 * DAY), None))]
+    #[case(filter(Some("1970-01-09"), Some("7d"), Some("1w")), (Some(8 * DAY), Some(3 * DAY)))]
+    fn test_cutoffs(#[case] filter: TimeFilter, #[case] expected: (Option<i64>, Option<i64>)) {
+        assert_eq!(filter.cutoffs(10 * DAY).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(filter(Some("2024-13-01"), None, None))]
+    #[case(filter(Some("yesterday"), None, None))]
+    #[case(filter(None, Some("7x"), None))]
+    #[case(filter(None, None, Some("")))]
+    fn test_cutoffs_rejects_bad_input(#[case] filter: TimeFilter) {
+        assert!(matches!(
+            filter.cutoffs(0),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn test_apply() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=3 {
+            db.add_rec(&format!("https://{}.com", i), "", ",", "", None)
+                .unwrap();
+            db.execute(
+                "UPDATE bookmarks SET created_at = ?2 WHERE id = ?1",
+                (i, i * DAY),
+            )
+            .unwrap();
+        }
+        db.record_visits(&[1]).unwrap();
+        let all = db.get_rec_all().unwrap();
+
+        let ids = |f: TimeFilter| -> Vec<usize> {
+            f.apply(&db, all.clone(), 10 * DAY)
+                .unwrap()
+                .iter()
+                .map(|b| b.id)
+                .collect()
+        };
+        assert_eq!(ids(TimeFilter::default()), vec![1, 2, 3]);
+        assert_eq!(ids(filter(None, Some("8d"), None)), vec![2, 3]);
+        // 1 was just opened, after the cutoff; 2 and 3 never were
+        assert_eq!(ids(filter(None, None, Some("7d"))), vec![2, 3]);
+    }
+}
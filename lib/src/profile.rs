@@ -0,0 +1,254 @@
+//! Comparing and copying bookmarks between databases
+//!
+//! A profile is a named database from `profiles` in the config (see
+//! [`Config::profile_path`](crate::config::Config::profile_path)). Bookmarks
+//! are matched across databases by URL, since IDs are local to each one.
+
+use crate::cancel::CancellationToken;
+use crate::crypto::is_encrypted_field;
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::import_export::import::{DuplicateStrategy, ImportStats, ParsedBookmark, ReportReason};
+use crate::models::bookmark::Bookmark;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// How two databases' bookmarks differ, compared by URL
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileDiff {
+    /// Bookmarks whose URL only the left database has
+    pub only_left: Vec<Bookmark>,
+    /// Bookmarks whose URL only the right database has
+    pub only_right: Vec<Bookmark>,
+    /// Bookmarks of both databases with different tags, left then right
+    pub differing_tags: Vec<(Bookmark, Bookmark)>,
+    /// URLs both databases have with the same tags
+    pub common: usize,
+}
+
+/// Outcome of a [`copy`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CopyStats {
+    /// What the target did with the copies
+    pub import: ImportStats,
+    /// Originals deleted from the source (with `remove`)
+    pub removed: usize,
+    /// Originals `remove` left in the source because the target skipped their copy
+    pub kept: Vec<usize>,
+}
+
+/// The tags of a stored `,a,b,` string, ignoring order
+fn tag_set(tags: &str) -> BTreeSet<&str> {
+    tags.split(',').filter(|t| !t.is_empty()).collect()
+}
+
+/// Compare the bookmarks of `left` and `right` by URL
+pub fn diff(left: &BukuDb, right: &BukuDb) -> Result<ProfileDiff> {
+    let mut right_by_url: HashMap<String, Bookmark> = right
+        .get_rec_all()?
+        .into_iter()
+        .map(|b| (b.url.clone(), b))
+        .collect();
+
+    let mut diff = ProfileDiff::default();
+    for bookmark in left.get_rec_all()? {
+        match right_by_url.remove(&bookmark.url) {
+            None => diff.only_left.push(bookmark),
+            Some(other) if tag_set(&bookmark.tags) != tag_set(&other.tags) => {
+                diff.differing_tags.push((bookmark, other))
+            }
+            Some(_) => diff.common += 1,
+        }
+    }
+    diff.only_right = right_by_url.into_values().collect();
+    diff.only_right.sort_by_key(|b| b.id);
+    Ok(diff)
+}
+
+/// Copy bookmarks `ids` of `from` into `to`, keeping their added date
+///
+/// The copies are added in one transaction (one `undo` in `to`), holding
+/// `to`'s database lock; a URL `to` has already is handled by `on_duplicate`. With `remove`, the originals
+/// whose copy was added or merged are deleted from `from` once the copy has
+/// committed, so a failure never loses bookmarks: at worst they end up in
+/// both databases. Originals whose copy was skipped, being duplicates left
+/// untouched by [`DuplicateStrategy::Skip`] or URLs that can't be bookmarked,
+/// stay where they are.
+pub fn copy(
+    from: &BukuDb,
+    to: &BukuDb,
+    ids: &[usize],
+    on_duplicate: DuplicateStrategy,
+    remove: bool,
+) -> Result<CopyStats> {
+    let same_file = match (
+        std::fs::canonicalize(from.get_path()),
+        std::fs::canonicalize(to.get_path()),
+    ) {
+        (Ok(from), Ok(to)) => from == to,
+        _ => false,
+    };
+    if same_file {
+        return Err(BukursError::InvalidInput(
+            "The source and target profile are the same database".to_string(),
+        ));
+    }
+    let mut by_id: HashMap<usize, _> = from
+        .dump_bookmarks()?
+        .into_iter()
+        .map(|b| (b.id, b))
+        .collect();
+
+    let mut records = Vec::with_capacity(ids.len());
+    for &id in ids {
+        let bookmark = by_id.remove(&id).ok_or(BukursError::BookmarkNotFound(id))?;
+        if is_encrypted_field(&bookmark.url) {
+            return Err(BukursError::InvalidInput(format!(
+                "Bookmark {} is encrypted; decrypt it before copying",
+                id
+            )));
+        }
+        records.push(ParsedBookmark {
+            url: bookmark.url,
+            title: bookmark.title,
+            tags: bookmark.tags,
+            desc: bookmark.desc,
+            // Folder IDs mean nothing in the other database
+            parent_id: None,
+            created_at: (bookmark.created_at > 0).then_some(bookmark.created_at),
+            flags: bookmark.flags,
+        });
+    }
+
+    let mut skipped = HashSet::new();
+    // Callers lock the database they opened, which is `from`
+    let target_lock = to.write_lock()?;
+    let import = to
+        .import_rec_batch_reported(
+            &records,
+            on_duplicate,
+            &CancellationToken::new(),
+            |_| {},
+            |entry| {
+                // A merge that changes nothing is reported as a duplicate too,
+                // but then the target already has everything the original does
+                let left_out = match entry.reason {
+                    ReportReason::InvalidUrl => true,
+                    ReportReason::Duplicate => on_duplicate == DuplicateStrategy::Skip,
+                    ReportReason::Merged | ReportReason::Overwritten => false,
+                };
+                if left_out {
+                    skipped.insert(entry.url);
+                }
+            },
+        )?
+        .unwrap_or_default();
+    drop(target_lock);
+
+    let mut stats = CopyStats {
+        import,
+        ..CopyStats::default()
+    };
+    if remove {
+        let mut moved = Vec::with_capacity(ids.len());
+        for (&id, record) in ids.iter().zip(&records) {
+            if skipped.contains(&record.url) {
+                stats.kept.push(id);
+            } else {
+                moved.push(id);
+            }
+        }
+        stats.removed = from.delete_rec_batch(&moved)?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_with(bookmarks: &[(&str, &str)]) -> BukuDb {
+        let db = BukuDb::init_in_memory().unwrap();
+        for (url, tags) in bookmarks {
+            db.add_rec(url, "", tags, "", None).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn test_diff_by_url_and_tags() {
+        let work = db_with(&[
+            ("https://a.com", ",x,y,"),
+            ("https://b.com", ",x,"),
+            ("https://c.com", ","),
+        ]);
+        let personal = db_with(&[
+            ("https://a.com", ",y,x,"),
+            ("https://b.com", ",z,"),
+            ("https://d.com", ","),
+        ]);
+
+        let diff = diff(&work, &personal).unwrap();
+        let urls = |bookmarks: &[Bookmark]| -> Vec<String> {
+            bookmarks.iter().map(|b| b.url.clone()).collect()
+        };
+        assert_eq!(urls(&diff.only_left), ["https://c.com"]);
+        assert_eq!(urls(&diff.only_right), ["https://d.com"]);
+        assert_eq!(diff.differing_tags.len(), 1);
+        assert_eq!(diff.differing_tags[0].0.url, "https://b.com");
+        assert_eq!(diff.differing_tags[0].1.tags, ",z,");
+        assert_eq!(diff.common, 1);
+    }
+
+    #[test]
+    fn test_copy_and_move() {
+        let work = db_with(&[("https://a.com", ",x,"), ("https://b.com", ",y,")]);
+        let personal = db_with(&[("https://b.com", ",z,")]);
+
+        let stats = copy(&work, &personal, &[1, 2], DuplicateStrategy::Merge, false).unwrap();
+        assert_eq!((stats.import.added, stats.import.updated), (1, 1));
+        assert_eq!((stats.removed, stats.kept.len()), (0, 0));
+        assert_eq!(work.get_rec_all().unwrap().len(), 2);
+        let merged = personal.get_rec_by_id(1).unwrap().unwrap();
+        assert_eq!(tag_set(&merged.tags), BTreeSet::from(["y", "z"]));
+
+        copy(&work, &personal, &[1], DuplicateStrategy::Merge, true).unwrap();
+        assert!(work.get_rec_by_id(1).unwrap().is_none());
+        assert_eq!(personal.get_rec_all().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_move_keeps_skipped_bookmarks() {
+        let work = db_with(&[
+            ("https://a.com", ",x,"),
+            ("https://b.com", ",y,"),
+            ("javascript:void(0)", ","),
+        ]);
+        let personal = db_with(&[("https://b.com", ",z,")]);
+
+        let stats = copy(&work, &personal, &[1, 2, 3], DuplicateStrategy::Skip, true).unwrap();
+        assert_eq!((stats.import.added, stats.import.skipped), (1, 2));
+        assert_eq!(stats.removed, 1);
+        assert_eq!(stats.kept, vec![2, 3]);
+        let left: Vec<usize> = work.get_rec_all().unwrap().iter().map(|b| b.id).collect();
+        assert_eq!(left, vec![2, 3]);
+        // The skipped duplicate in the target is untouched
+        assert_eq!(personal.get_rec_by_id(1).unwrap().unwrap().tags, ",z,");
+
+        // A merge that changes nothing still moves the original
+        let home = db_with(&[("https://b.com", ",z,")]);
+        let stats = copy(&home, &personal, &[1], DuplicateStrategy::Merge, true).unwrap();
+        assert_eq!((stats.import.skipped, stats.removed), (1, 1));
+        assert!(home.get_rec_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_copy_unknown_id_changes_nothing() {
+        let work = db_with(&[("https://a.com", ",")]);
+        let personal = db_with(&[]);
+
+        let result = copy(&work, &personal, &[1, 7], DuplicateStrategy::Skip, true);
+        assert!(matches!(result, Err(BukursError::BookmarkNotFound(7))));
+        assert_eq!(work.get_rec_all().unwrap().len(), 1);
+        assert!(personal.get_rec_all().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+//! Progress reporting for long-running operations
+//!
+//! Imports, exports and metadata refreshes report what they are doing to a
+//! [`ProgressSink`], so a front-end can draw a progress bar (the CLI uses
+//! indicatif) without the library knowing how. Any
+//! `Fn(ProgressEvent) + Sync` closure is a sink; [`NoProgress`] ignores events.
+
+/// Something a long-running operation reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressEvent<'a> {
+    /// A phase began, e.g. importing one browser profile
+    Start {
+        label: &'a str,
+        /// Number of items, when known up front
+        total: Option<u64>,
+    },
+    /// `count` more items were processed; `item` names the latest (usually its URL)
+    Advance { count: u64, item: Option<&'a str> },
+    /// The current phase is over
+    Finish,
+}
+
+/// Receiver of progress events
+///
+/// Events may arrive from several worker threads at once, hence `Sync`.
+pub trait ProgressSink: Sync {
+    fn event(&self, event: ProgressEvent<'_>);
+
+    fn start(&self, label: &str, total: Option<u64>) {
+        self.event(ProgressEvent::Start { label, total });
+    }
+
+    fn advance(&self, count: u64, item: Option<&str>) {
+        self.event(ProgressEvent::Advance { count, item });
+    }
+
+    fn finish(&self) {
+        self.event(ProgressEvent::Finish);
+    }
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent<'_>) + Sync,
+{
+    fn event(&self, event: ProgressEvent<'_>) {
+        self(event)
+    }
+}
+
+/// Sink that ignores every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn event(&self, _event: ProgressEvent<'_>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_closure_sink_receives_events() {
+        let events = Mutex::new(Vec::new());
+        let sink = |event: ProgressEvent<'_>| {
+            events.lock().unwrap().push(format!("{:?}", event));
+        };
+
+        sink.start("Importing", Some(2));
+        sink.advance(1, Some("https://a.com"));
+        sink.advance(1, None);
+        sink.finish();
+        NoProgress.advance(1, None);
+
+        assert_eq!(
+            events.into_inner().unwrap(),
+            vec![
+                r#"Start { label: "Importing", total: Some(2) }"#,
+                r#"Advance { count: 1, item: Some("https://a.com") }"#,
+                "Advance { count: 1, item: None }",
+                "Finish",
+            ]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Python bindings (the `python` feature), built into the `bukurs` Python
+//! module by the `bukurs-py` crate in `py/`
+//!
+//! `BukuDb` opens the same database and reads the same config file as the
+//! CLI, and its methods go through [`BookmarkService`], so scripts get the
+//! same tag rules, selectors, undo history and audit log:
+//!
+//! ```python
+//! import bukurs
+//!
+//! db = bukurs.BukuDb()  # or BukuDb("bookmarks.db"), BukuDb(":memory:")
+//! id = db.add("https://rust-lang.org", title="Rust", tags=["lang"])
+//! for bookmark in db.search(["rust"]):
+//!     print(bookmark.id, bookmark.url, bookmark.tags)
+//! db.update(id, tags=["+systems"])
+//! db.delete("1-3")
+//! db.undo()
+//! ```
+//!
+//! Selectors take an ID, a selector string (`"1-5"`, `"-1"`, `"*"`,
+//! keywords) or a list of either. Errors are raised as `KeyError` (no such
+//! bookmark), `ValueError` (bad input), `OSError` or `RuntimeError`.
+
+use crate::audit::AuditSource;
+use crate::config::Config;
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::fetch::FetchOptions;
+use crate::import_export::{export_bookmarks, import_bookmarks, DuplicateStrategy};
+use crate::models::bookmark::Bookmark;
+use crate::search::{SearchOptions, SearchRank};
+use crate::service::{AddRequest, BookmarkService, FieldChanges, SearchQuery};
+use crate::tags::parse_tags;
+use pyo3::exceptions::{PyKeyError, PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+impl From<BukursError> for PyErr {
+    fn from(err: BukursError) -> Self {
+        match err {
+            BukursError::BookmarkNotFound(_) => PyKeyError::new_err(err.to_string()),
+            BukursError::InvalidInput(_)
+            | BukursError::InvalidSelector(_)
+            | BukursError::UrlParse(_) => PyValueError::new_err(err.to_string()),
+            BukursError::Io(_) => PyOSError::new_err(err.to_string()),
+            _ => PyRuntimeError::new_err(err.to_string()),
+        }
+    }
+}
+
+/// A bookmark as returned to Python
+#[pyclass(name = "Bookmark", module = "bukurs", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyBookmark {
+    pub id: usize,
+    pub url: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub description: String,
+}
+
+impl From<Bookmark> for PyBookmark {
+    fn from(bookmark: Bookmark) -> Self {
+        Self {
+            id: bookmark.id,
+            tags: parse_tags(&bookmark.tags),
+            url: bookmark.url,
+            title: bookmark.title,
+            description: bookmark.description,
+        }
+    }
+}
+
+#[pymethods]
+impl PyBookmark {
+    fn __repr__(&self) -> String {
+        format!(
+            "Bookmark(id={}, url={:?}, title={:?}, tags={:?})",
+            self.id, self.url, self.title, self.tags
+        )
+    }
+}
+
+fn bookmarks(records: Vec<Bookmark>) -> Vec<PyBookmark> {
+    records.into_iter().map(PyBookmark::from).collect()
+}
+
+/// Selector arguments from an ID, a selector string or a list of either
+fn selector(value: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    let term = |item: &Bound<'_, PyAny>| {
+        item.extract::<usize>()
+            .map(|id| id.to_string())
+            .or_else(|_| item.extract::<String>())
+    };
+    if let Ok(term) = term(value) {
+        return Ok(vec![term]);
+    }
+    value.try_iter()?.map(|item| term(&item?)).collect()
+}
+
+/// A bookmark database
+#[pyclass(name = "BukuDb", module = "bukurs")]
+pub struct PyBukuDb {
+    db: Mutex<BukuDb>,
+    fetch: FetchOptions,
+    search: SearchOptions,
+}
+
+impl PyBukuDb {
+    fn db(&self) -> PyResult<MutexGuard<'_, BukuDb>> {
+        self.db
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("The database is unusable after a panic"))
+    }
+
+    /// Run a change under the database lock, like the CLI's mutating commands
+    fn write<T>(&self, change: impl FnOnce(BookmarkService<'_>) -> Result<T>) -> PyResult<T> {
+        let db = self.db()?;
+        let _lock = db.connection_options().lock_database(db.get_path())?;
+        Ok(change(BookmarkService::new(&db, self.fetch.clone()))?)
+    }
+}
+
+#[pymethods]
+impl PyBukuDb {
+    /// Open (creating if needed) the database at `path`, by default the one
+    /// the CLI uses; settings come from the bukurs config file
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<PathBuf>) -> PyResult<Self> {
+        let config = Config::load();
+        let db = config.open_db(path.as_deref(), AuditSource::Python)?;
+        Ok(Self {
+            db: Mutex::new(db),
+            fetch: config.fetch_options(),
+            search: config.search,
+        })
+    }
+
+    /// Path of the database file, or `:memory:`
+    #[getter]
+    fn path(&self) -> PyResult<PathBuf> {
+        Ok(self.db()?.get_path().to_path_buf())
+    }
+
+    /// Add a bookmark and return its ID; with `fetch`, unset fields are
+    /// filled from the page, otherwise it is queued for `fetch-pending`
+    #[pyo3(signature = (url, title=None, tags=None, desc=None, fetch=false))]
+    fn add(
+        &self,
+        url: String,
+        title: Option<String>,
+        tags: Option<Vec<String>>,
+        desc: Option<String>,
+        fetch: bool,
+    ) -> PyResult<usize> {
+        let request = AddRequest {
+            url,
+            title,
+            tags,
+            desc,
+            ..AddRequest::default()
+        };
+        self.write(|service| {
+            let outcome = if fetch {
+                service.add_with_fetch(&request)?
+            } else {
+                service.add(&request, None)?
+            };
+            Ok(outcome.id)
+        })
+    }
+
+    /// The bookmark with `id`, or None
+    fn get(&self, id: usize) -> PyResult<Option<PyBookmark>> {
+        let bookmark = self.db()?.get_rec_by_id(id).map_err(BukursError::from)?;
+        Ok(bookmark.map(PyBookmark::from))
+    }
+
+    /// Every bookmark, in index order
+    fn all(&self) -> PyResult<Vec<PyBookmark>> {
+        Ok(bookmarks(
+            self.db()?.get_rec_all().map_err(BukursError::from)?,
+        ))
+    }
+
+    /// Every tag in use, sorted
+    fn tags(&self) -> PyResult<Vec<String>> {
+        Ok(self.db()?.get_all_tags().map_err(BukursError::from)?)
+    }
+
+    /// Change the selected bookmarks and return their IDs; `tags` takes tag
+    /// operations (`+tag`, `-tag`, `~old:new`) or bare tags to add
+    #[pyo3(signature = (selection, url=None, title=None, tags=None, desc=None))]
+    fn update(
+        &self,
+        selection: &Bound<'_, PyAny>,
+        url: Option<String>,
+        title: Option<String>,
+        tags: Option<Vec<String>>,
+        desc: Option<String>,
+    ) -> PyResult<Vec<usize>> {
+        let selector = selector(selection)?;
+        let changes = FieldChanges {
+            url,
+            title,
+            desc,
+            tags,
+            immutable: None,
+        };
+        self.write(|service| {
+            let outcome = service.update_many(&selector, &changes)?;
+            match outcome.errors.first() {
+                Some(error) => Err(BukursError::InvalidInput(error.clone())),
+                None => Ok(outcome.ids),
+            }
+        })
+    }
+
+    /// Delete the selected bookmarks and return them
+    fn delete(&self, selection: &Bound<'_, PyAny>) -> PyResult<Vec<PyBookmark>> {
+        let selector = selector(selection)?;
+        let outcome = self.write(|service| service.delete_by_selector(&selector))?;
+        Ok(bookmarks(outcome.deleted))
+    }
+
+    /// Full-text search; `rank` is "relevance", "recent", "visits" or "rating"
+    #[pyo3(signature = (keywords, all=false, regex=false, limit=None, rank=None))]
+    fn search(
+        &self,
+        keywords: Vec<String>,
+        all: bool,
+        regex: bool,
+        limit: Option<usize>,
+        rank: Option<String>,
+    ) -> PyResult<Vec<PyBookmark>> {
+        let rank = match rank {
+            Some(rank) => Some(SearchRank::from_string(&rank).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Unknown rank '{}' (expected relevance, recent, visits or rating)",
+                    rank
+                ))
+            })?),
+            None => self.search.rank,
+        };
+        let query = SearchQuery {
+            keywords,
+            all,
+            regex,
+            limit,
+            rank,
+            weights: self.search.weights,
+            keyword_match: self.search.keyword_match,
+            ..SearchQuery::default()
+        };
+        let db = self.db()?;
+        let service = BookmarkService::new(&db, self.fetch.clone());
+        Ok(bookmarks(service.search_query(&query)?))
+    }
+
+    /// Bookmarks carrying any of `tags`
+    fn search_tags(&self, tags: Vec<String>) -> PyResult<Vec<PyBookmark>> {
+        Ok(bookmarks(
+            self.db()?.search_tags(&tags).map_err(BukursError::from)?,
+        ))
+    }
+
+    /// Undo the last change; returns the operation and bookmark ID, or None
+    fn undo(&self) -> PyResult<Option<(String, usize)>> {
+        self.write(|service| Ok(service.db().undo_last()?))
+    }
+
+    /// Import an HTML, Markdown or Org file; `on_duplicate` is "skip",
+    /// "merge" or "overwrite". Returns the added, updated and skipped counts
+    #[pyo3(signature = (path, on_duplicate="skip"))]
+    fn import_file<'py>(
+        &self,
+        py: Python<'py>,
+        path: PathBuf,
+        on_duplicate: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let strategy = DuplicateStrategy::from_string(on_duplicate).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Unknown duplicate strategy '{}' (expected skip, merge or overwrite)",
+                on_duplicate
+            ))
+        })?;
+        let stats = self
+            .write(|service| import_bookmarks(service.db(), &path.to_string_lossy(), strategy))?;
+
+        let counts = PyDict::new(py);
+        counts.set_item("added", stats.added)?;
+        counts.set_item("updated", stats.updated)?;
+        counts.set_item("skipped", stats.skipped)?;
+        Ok(counts)
+    }
+
+    /// Export every bookmark; the format follows the extension (html, md,
+    /// org, rss or atom)
+    fn export_file(&self, path: PathBuf) -> PyResult<()> {
+        Ok(export_bookmarks(&self.db()?, &path.to_string_lossy())?)
+    }
+
+    /// Save the whole database to `path`, e.g. to keep an in-memory one
+    fn snapshot_to(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.db()?.snapshot_to(&path)?)
+    }
+}
+
+/// Add the bindings to the `bukurs` Python module
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyBukuDb>()?;
+    module.add_class::<PyBookmark>()?;
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,321 @@
+//! Metadata for resources that aren't HTML pages: PDFs, images, archives and other files
+
+use crate::tags::{format_tags, parse_tags};
+use crate::utils::{percent_decode, url_domain};
+use serde::{Deserialize, Serialize};
+
+/// Largest PDF body read when looking for its title
+pub const MAX_PDF_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Kind of resource a URL points to, from its Content-Type (or extension when there is none)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    #[default]
+    Html,
+    Pdf,
+    Image,
+    Archive,
+    Other,
+}
+
+const ARCHIVE_TYPES: &[&str] = &[
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-tar",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/vnd.rar",
+    "application/zstd",
+];
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "ico", "tif", "tiff", "avif",
+];
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "gz", "tgz", "tar", "bz2", "xz", "7z", "rar", "zst"];
+
+impl ResourceKind {
+    /// Classify a resource by its MIME type, falling back to the URL's extension
+    /// when the server sends none or a generic binary type
+    pub fn classify(content_type: Option<&str>, url: &str) -> Self {
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|m| m.trim().to_lowercase())
+            .unwrap_or_default();
+
+        match mime.as_str() {
+            "" | "application/octet-stream" => Self::from_extension(url),
+            "text/html" | "application/xhtml+xml" => Self::Html,
+            "application/pdf" => Self::Pdf,
+            m if m.starts_with("image/") => Self::Image,
+            m if ARCHIVE_TYPES.contains(&m) => Self::Archive,
+            _ => Self::Other,
+        }
+    }
+
+    fn from_extension(url: &str) -> Self {
+        let name = file_name(url).unwrap_or_default().to_lowercase();
+        let Some((_, ext)) = name.rsplit_once('.') else {
+            return Self::Html;
+        };
+        match ext {
+            "pdf" => Self::Pdf,
+            e if IMAGE_EXTENSIONS.contains(&e) => Self::Image,
+            e if ARCHIVE_EXTENSIONS.contains(&e) => Self::Archive,
+            "html" | "htm" | "xhtml" | "php" | "asp" | "aspx" | "jsp" => Self::Html,
+            _ => Self::Other,
+        }
+    }
+
+    /// Tag added to bookmarks of this kind
+    pub fn tag(self) -> Option<&'static str> {
+        match self {
+            Self::Pdf => Some("pdf"),
+            Self::Image => Some("image"),
+            _ => None,
+        }
+    }
+
+    /// Add this kind's tag to a `,tag1,tag2,` string, or `None` if there is nothing to add
+    pub fn apply_tag(self, tags: &str) -> Option<String> {
+        let tag = self.tag()?;
+        let mut all = parse_tags(tags);
+        if all.iter().any(|t| t == tag) {
+            return None;
+        }
+        all.push(tag.to_string());
+        Some(format_tags(&all))
+    }
+}
+
+/// Last path segment of a URL, without query or fragment
+fn file_name(url: &str) -> Option<String> {
+    let path = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let (_, path) = path.split_once('/')?;
+    let name = path.trim_end_matches('/').rsplit('/').next()?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(percent_decode(name))
+    }
+}
+
+/// Title for a file derived from its URL: the decoded file name, or the domain
+pub fn filename_title(url: &str) -> String {
+    file_name(url)
+        .or_else(|| url_domain(url))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Decode a PDF text string: UTF-16BE with a byte order mark, otherwise Latin-1/PDFDocEncoding
+fn decode_pdf_text(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(utf8).into_owned();
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Parse a PDF literal string `(...)` starting after the opening parenthesis
+fn parse_literal_string(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'\\' if i + 1 < data.len() => {
+                i += 1;
+                match data[i] {
+                    b'n' => out.push(b'\n'),
+                    b'r' => out.push(b'\r'),
+                    b't' => out.push(b'\t'),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0C),
+                    b'\r' | b'\n' => {} // Line continuation
+                    d @ b'0'..=b'7' => {
+                        let mut value = u32::from(d - b'0');
+                        for _ in 0..2 {
+                            match data.get(i + 1) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(d - b'0');
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        out.push(value as u8);
+                    }
+                    c => out.push(c), // \( \) \\ and unknown escapes
+                }
+            }
+            b'(' => {
+                depth += 1;
+                out.push(b'(');
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                out.push(b')');
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Parse a PDF hex string `<...>` starting after the opening angle bracket
+fn parse_hex_string(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter(|b| b.is_ascii_hexdigit())
+        .copied()
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16).unwrap_or(0);
+            let lo = pair
+                .get(1)
+                .and_then(|&b| (b as char).to_digit(16))
+                .unwrap_or(0);
+            (hi * 16 + lo) as u8
+        })
+        .collect()
+}
+
+/// `/Title` entry of the document information dictionary
+fn info_title(data: &[u8]) -> Option<String> {
+    let needle = b"/Title";
+    // The last entry wins: incremental updates append newer info dictionaries
+    let pos = data.windows(needle.len()).rposition(|w| w == needle)?;
+    let rest = &data[pos + needle.len()..];
+    let start = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+    let raw = match rest[start] {
+        b'(' => parse_literal_string(&rest[start + 1..]),
+        b'<' => parse_hex_string(&rest[start + 1..]),
+        _ => return None,
+    };
+    Some(decode_pdf_text(&raw))
+}
+
+/// `dc:title` from the document's XMP metadata stream
+fn xmp_title(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    let start = text.find("<dc:title")?;
+    let section = &text[start..];
+    let end = section.find("</dc:title>")?;
+    let section = &section[..end];
+    // The title sits in the first <rdf:li> of an <rdf:Alt>
+    let li = section.find("<rdf:li")?;
+    let content = &section[li..];
+    let open_end = content.find('>')?;
+    let content = &content[open_end + 1..];
+    let close = content.find("</rdf:li>")?;
+    Some(content[..close].to_string())
+}
+
+/// Extract a PDF's document title from its info dictionary or XMP metadata
+///
+/// This is a byte scan rather than a full PDF parser, so titles inside
+/// compressed object streams are not found.
+pub fn pdf_title(data: &[u8]) -> Option<String> {
+    [info_title(data), xmp_title(data)]
+        .into_iter()
+        .flatten()
+        .map(|t| t.trim().to_string())
+        .find(|t| !t.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Some("text/html; charset=utf-8"), "https://a.com/", ResourceKind::Html)]
+    #[case(Some("application/pdf"), "https://a.com/doc", ResourceKind::Pdf)]
+    #[case(Some("image/png"), "https://a.com/x", ResourceKind::Image)]
+    #[case(Some("application/zip"), "https://a.com/x", ResourceKind::Archive)]
+    #[case(Some("text/plain"), "https://a.com/notes.txt", ResourceKind::Other)]
+    #[case(
+        Some("application/octet-stream"),
+        "https://a.com/paper.PDF",
+        ResourceKind::Pdf
+    )]
+    #[case(None, "https://a.com/photo.jpg?size=large", ResourceKind::Image)]
+    #[case(None, "https://a.com/release.tar.gz", ResourceKind::Archive)]
+    #[case(None, "https://a.com/page", ResourceKind::Html)]
+    fn test_classify(
+        #[case] content_type: Option<&str>,
+        #[case] url: &str,
+        #[case] expected: ResourceKind,
+    ) {
+        assert_eq!(ResourceKind::classify(content_type, url), expected);
+    }
+
+    #[rstest]
+    #[case("https://a.com/files/My%20Report.pdf", "My Report.pdf")]
+    #[case("https://a.com/img/cat.jpg?w=100#top", "cat.jpg")]
+    #[case("https://a.com/dir/", "dir")]
+    #[case("https://a.com", "a.com")]
+    #[case("https://a.com/100%", "100%")]
+    fn test_filename_title(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(filename_title(url), expected);
+    }
+
+    #[rstest]
+    #[case(b"<< /Title (A Simple Title) >>".as_slice(), Some("A Simple Title"))]
+    #[case(b"/Title (Nested (parens) and \\) escape)".as_slice(), Some("Nested (parens) and ) escape"))]
+    #[case(b"/Title (Caf\\351)".as_slice(), Some("Caf\u{e9}"))]
+    #[case(b"/Title <48656C6C6F>".as_slice(), Some("Hello"))]
+    #[case(b"/Title <FEFF00480069>".as_slice(), Some("Hi"))]
+    #[case(b"/Title (Old) ... /Title (New)".as_slice(), Some("New"))]
+    #[case(b"/Title ()".as_slice(), None)]
+    #[case(b"%PDF-1.7 no metadata".as_slice(), None)]
+    fn test_pdf_info_title(#[case] data: &[u8], #[case] expected: Option<&str>) {
+        assert_eq!(pdf_title(data).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(ResourceKind::Pdf, ",", Some(",pdf,"))]
+    #[case(ResourceKind::Image, ",photos,", Some(",photos,image,"))]
+    #[case(ResourceKind::Pdf, ",pdf,papers,", None)]
+    #[case(ResourceKind::Html, ",web,", None)]
+    #[case(ResourceKind::Archive, ",", None)]
+    fn test_apply_tag(
+        #[case] kind: ResourceKind,
+        #[case] tags: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(kind.apply_tag(tags).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_pdf_xmp_title() {
+        let data = br#"%PDF-1.6
+<x:xmpmeta><rdf:RDF><rdf:Description>
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">XMP Title</rdf:li></rdf:Alt></dc:title>
+</rdf:Description></rdf:RDF></x:xmpmeta>"#;
+        assert_eq!(pdf_title(data).as_deref(), Some("XMP Title"));
+    }
+
+    #[test]
+    fn test_pdf_utf16_literal_title() {
+        let mut data = b"/Title (".to_vec();
+        data.extend_from_slice(&[0xFE, 0xFF, 0x00, b'O', 0x00, b'K']);
+        data.push(b')');
+        assert_eq!(pdf_title(&data).as_deref(), Some("OK"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Cron-like schedules for `bukurs daemon` jobs
+//!
+//! A schedule has the five classic cron fields, `minute hour day-of-month
+//! month day-of-week`, each `*`, a number, a range `a-b`, a step `*/n` or
+//! `a-b/n`, or a comma-separated list of those. Day of week runs from 0
+//! (Sunday) to 6, with 7 also meaning Sunday. The shortcuts `@hourly`,
+//! `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted too.
+//!
+//! Times are UTC.
+
+use crate::error::{BukursError, Result};
+use crate::utils::split_timestamp;
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead to look for the next run before deciding a schedule never fires
+const MAX_LOOKAHEAD_SECS: i64 = 5 * 366 * 86_400;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    source: String,
+    /// Bit n set when minute n matches
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    /// Bit 0 is Sunday
+    weekdays: u8,
+    /// Whether day of month and day of week were `*`; when both are
+    /// restricted a day matches if either does, as in cron
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Parse one field into a bit set of the values it matches
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64> {
+    let invalid = || {
+        BukursError::InvalidInput(format!(
+            "Invalid {} field '{}' (expected {}-{}, *, ranges, steps or lists)",
+            name, field, min, max
+        ))
+    };
+    let number = |s: &str| -> Result<u32> {
+        s.parse::<u32>()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(invalid)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // `5/15` means every 15 starting at 5
+                None if part.contains('/') => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl FromStr for Schedule {
+    type Err = BukursError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(BukursError::InvalidInput(format!(
+                "Invalid schedule '{}' (expected 5 cron fields or @hourly, @daily, @weekly, @monthly, @yearly)",
+                source
+            )));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "day-of-week")?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & 0x7f;
+        }
+        Ok(Schedule {
+            source: source.to_string(),
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")? as u32,
+            days: parse_field(day, 1, 31, "day-of-month")? as u32,
+            months: parse_field(month, 1, 12, "month")? as u16,
+            weekdays: weekdays as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Schedule {
+    fn day_matches(&self, day: u32, weekday: usize) -> bool {
+        let by_day = self.days & (1 << day) != 0;
+        let by_weekday = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => by_day || by_weekday,
+            _ => by_day && by_weekday,
+        }
+    }
+
+    /// First time strictly after `ts` (whole minutes, Unix seconds) the schedule fires
+    ///
+    /// None when it never does, e.g. `0 0 31 2 *`.
+    pub fn next_after(&self, ts: i64) -> Option<i64> {
+        let mut t = (ts.div_euclid(60) + 1) * 60;
+        let limit = t + MAX_LOOKAHEAD_SECS;
+        while t < limit {
+            let ((_, month, day), (hour, minute, _), weekday) = split_timestamp(t);
+            if self.months & (1 << month) == 0 || !self.day_matches(day, weekday) {
+                t = (t.div_euclid(86_400) + 1) * 86_400;
+            } else if self.hours & (1 << hour) == 0 {
+                t = (t.div_euclid(3600) + 1) * 3600;
+            } else if self.minutes & (1 << minute) == 0 {
+                t += 60;
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::format_rfc3339;
+    use rstest::rstest;
+
+    /// 2024-05-01T12:00:00Z, a Wednesday
+    const NOON: i64 = 1_714_564_800;
+
+    fn next(schedule: &str, from: i64) -> String {
+        let schedule: Schedule = schedule.parse().unwrap();
+        format_rfc3339(schedule.next_after(from).unwrap())
+    }
+
+    #[rstest]
+    #[case("* * * * *", "2024-05-01T12:01:00Z")]
+    #[case("@hourly", "2024-05-01T13:00:00Z")]
+    #[case("@daily", "2024-05-02T00:00:00Z")]
+    #[case("30 3 * * *", "2024-05-02T03:30:00Z")]
+    #[case("*/15 12 * * *", "2024-05-01T12:15:00Z")]
+    #[case("5/20 * * * *", "2024-05-01T12:05:00Z")]
+    #[case("0 9-17/4 * * *", "2024-05-01T13:00:00Z")]
+    #[case("@weekly", "2024-05-05T00:00:00Z")]
+    #[case("0 0 * * 7", "2024-05-05T00:00:00Z")]
+    #[case("0 8 * * 1,5", "2024-05-03T08:00:00Z")]
+    #[case("@monthly", "2024-06-01T00:00:00Z")]
+    #[case("0 0 29 2 *", "2028-02-29T00:00:00Z")]
+    // Both day fields restricted: either matches
+    #[case("0 0 15 * 4", "2024-05-02T00:00:00Z")]
+    fn test_next_after(#[case] schedule: &str, #[case] expected: &str) {
+        assert_eq!(next(schedule, NOON), expected);
+    }
+
+    #[test]
+    fn test_next_after_is_strictly_later() {
+        let schedule: Schedule = "0 12 * * *".parse().unwrap();
+        assert_eq!(schedule.next_after(NOON), Some(NOON + 86_400));
+        assert_eq!(schedule.next_after(NOON - 1), Some(NOON));
+    }
+
+    #[test]
+    fn test_never_fires() {
+        let schedule: Schedule = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(schedule.next_after(NOON), None);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("* * * *")]
+    #[case("60 * * * *")]
+    #[case("* 24 * * *")]
+    #[case("* * 0 * *")]
+    #[case("* * * 13 *")]
+    #[case("* * * * 8")]
+    #[case("*/0 * * * *")]
+    #[case("5-1 * * * *")]
+    #[case("@often")]
+    fn test_parse_errors(#[case] schedule: &str) {
+        assert!(schedule.parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn test_display_keeps_source() {
+        let schedule: Schedule = " @daily ".parse().unwrap();
+        assert_eq!(schedule.to_string(), "@daily");
+    }
+}
@@ -0,0 +1,537 @@
+//! Keyword matching and ranking of search results
+//!
+//! Bare keywords match whole words; `prog*` matches words starting with
+//! "prog", and the `keyword_match` setting can make that the default. Fuzzy
+//! matching falls back to comparing the trigrams of keywords and words when
+//! nothing matches, which forgives most typos.
+//!
+//! Full-text matches are scored with FTS5's bm25, weighting each indexed
+//! column: by default a match in the title or tags counts for more than one
+//! in the URL or description, so a bookmark *about* a keyword ranks above one
+//! that merely mentions it. Results can instead be ordered by when bookmarks
+//! were added, how often they were opened through bukurs or how they were rated.
+//!
+//! `site:github.com` among the keywords keeps bookmarks on that domain or its
+//! subdomains, and `-site:reddit.com` drops them; see [`SiteFilter`].
+
+#[cfg(feature = "full")]
+use crate::db::BukuDb;
+#[cfg(feature = "full")]
+use crate::error::Result;
+use crate::models::bookmark::Bookmark;
+#[cfg(feature = "full")]
+use crate::operations::encrypted::is_encrypted;
+use crate::operations::timeframe::TimeFilter;
+use crate::utils::{domain_matches, fold_diacritics, url_domain};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Lowest trigram similarity (0 to 1) at which a word fuzzily matches a keyword
+const FUZZY_THRESHOLD: f64 = 0.3;
+
+/// How keywords without `*` or FTS5 syntax match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordMatch {
+    /// Whole words
+    #[default]
+    Exact,
+    /// Word prefixes, as if each keyword ended in `*`
+    Prefix,
+    /// Word prefixes, falling back to trigram matching when nothing matches
+    Fuzzy,
+}
+
+/// Order of ranked search results, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchRank {
+    /// Highest bm25 score first
+    Relevance,
+    /// Most recently added first
+    Recent,
+    /// Most often opened first
+    Visits,
+    /// Most stars first, then by relevance
+    Rating,
+}
+
+impl SearchRank {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "relevance" => Some(Self::Relevance),
+            "recent" => Some(Self::Recent),
+            "visits" => Some(Self::Visits),
+            "rating" => Some(Self::Rating),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SearchRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Relevance => "relevance",
+            Self::Recent => "recent",
+            Self::Visits => "visits",
+            Self::Rating => "rating",
+        })
+    }
+}
+
+/// bm25 weight of a match in each column; only their ratios matter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchWeights {
+    pub url: f64,
+    pub title: f64,
+    pub tags: f64,
+    pub desc: f64,
+}
+
+impl Default for SearchWeights {
+    fn default() -> Self {
+        Self {
+            url: 1.0,
+            title: 10.0,
+            tags: 5.0,
+            desc: 2.0,
+        }
+    }
+}
+
+/// The `search` section of the config file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Ranking used when `--rank` isn't given; unset keeps results in index order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<SearchRank>,
+    pub weights: SearchWeights,
+    /// Matching of bare keywords when `--fuzzy` isn't given
+    pub keyword_match: KeywordMatch,
+}
+
+/// A search kept under a name (`search --save`, `saved run`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSearch {
+    /// Key of the saved search, stored apart from the rest
+    #[serde(skip)]
+    pub name: String,
+    pub keywords: Vec<String>,
+    /// Require every keyword instead of any
+    pub all: bool,
+    pub regex: bool,
+    /// Match descriptions (notes) only
+    pub notes: bool,
+    /// Ranking to use instead of the configured one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<SearchRank>,
+    pub fuzzy: bool,
+    /// Only keep bookmarks rated at least this many stars
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_rating: Option<u8>,
+    /// Only keep bookmarks whose page is in this language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Bounds on when bookmarks were added or last visited; durations count
+    /// back from when the search runs
+    #[serde(flatten)]
+    pub time: TimeFilter,
+}
+
+/// A search result with its relevance score
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoredBookmark {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    /// Weighted bm25 score, higher is better; `None` when the search couldn't
+    /// score matches (regex, no keywords, or no FTS5)
+    pub score: Option<f64>,
+}
+
+/// `site:` and `-site:` operators taken out of search keywords
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SiteFilter {
+    /// Keep bookmarks on any of these domains or their subdomains
+    pub include: Vec<String>,
+    /// Drop bookmarks on these domains or their subdomains
+    pub exclude: Vec<String>,
+}
+
+impl SiteFilter {
+    /// Split the operators from the other keywords, which keep their order
+    ///
+    /// Sites may be given as URLs (`site:https://www.github.com/`); only the
+    /// host counts.
+    pub fn extract(keywords: &[String]) -> (Vec<String>, SiteFilter) {
+        let mut filter = SiteFilter::default();
+        let mut rest = Vec::new();
+        for keyword in keywords {
+            let (list, site) = if let Some(site) = keyword.strip_prefix("-site:") {
+                (&mut filter.exclude, site)
+            } else if let Some(site) = keyword.strip_prefix("site:") {
+                (&mut filter.include, site)
+            } else {
+                rest.push(keyword.clone());
+                continue;
+            };
+            match site_domain(site) {
+                Some(domain) => list.push(domain),
+                None => rest.push(keyword.clone()),
+            }
+        }
+        (rest, filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a bookmark at `url` passes
+    pub fn matches(&self, url: &str) -> bool {
+        let domain = url_domain(url).unwrap_or_default();
+        let on = |sites: &[String]| sites.iter().any(|site| domain_matches(&domain, site));
+        (self.include.is_empty() || on(&self.include)) && !on(&self.exclude)
+    }
+}
+
+/// The host of a `site:` value, lowercased and without `www.`
+fn site_domain(site: &str) -> Option<String> {
+    let site = site.trim();
+    let host = site.split_once("://").map_or(site, |(_, rest)| rest);
+    let host = host
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Keywords as the full-text search should see them under `mode`
+///
+/// With prefix and fuzzy matching, plain words get a trailing `*`; quoted
+/// phrases, FTS5 expressions and keywords that already end in `*` are left alone.
+pub fn expand_keywords(keywords: &[String], mode: KeywordMatch) -> Vec<String> {
+    keywords
+        .iter()
+        .map(|k| {
+            let plain = !k.is_empty()
+                && !k.ends_with('*')
+                && !k.contains('"')
+                && !k.contains(char::is_whitespace);
+            if mode != KeywordMatch::Exact && plain {
+                format!("{}*", k)
+            } else {
+                k.clone()
+            }
+        })
+        .collect()
+}
+
+/// Bookmarks with a word similar to any (or every) keyword, in index order
+///
+/// Encrypted bookmarks are skipped, as in every search; see [`fuzzy_filter`].
+#[cfg(feature = "full")]
+pub fn fuzzy_search(
+    db: &BukuDb,
+    keywords: &[String],
+    any: bool,
+    notes: bool,
+) -> Result<Vec<Bookmark>> {
+    let bookmarks = db.get_rec_all()?.into_iter().filter(|b| !is_encrypted(b));
+    Ok(fuzzy_filter(bookmarks, keywords, any, notes))
+}
+
+/// The `bookmarks` with a word similar to any (or every) keyword
+///
+/// Similarity is the share of trigrams two words have in common, so
+/// "progrmming" still finds "programming". With `notes`, only descriptions
+/// are compared.
+pub fn fuzzy_filter(
+    bookmarks: impl IntoIterator<Item = Bookmark>,
+    keywords: &[String],
+    any: bool,
+    notes: bool,
+) -> Vec<Bookmark> {
+    let keywords: Vec<HashSet<String>> = keywords
+        .iter()
+        .flat_map(|k| words(k))
+        .map(|k| trigrams(&k))
+        .collect();
+    if keywords.is_empty() {
+        return Vec::new();
+    }
+
+    let matches = |b: &Bookmark| {
+        let text = if notes {
+            b.description.clone()
+        } else {
+            format!("{} {} {} {}", b.url, b.title, b.tags, b.description)
+        };
+        let candidates: Vec<HashSet<String>> =
+            words(&text).into_iter().map(|w| trigrams(&w)).collect();
+        let matches_keyword = |k: &HashSet<String>| {
+            candidates
+                .iter()
+                .any(|w| similarity(k, w) >= FUZZY_THRESHOLD)
+        };
+        if any {
+            keywords.iter().any(matches_keyword)
+        } else {
+            keywords.iter().all(matches_keyword)
+        }
+    };
+    bookmarks.into_iter().filter(|b| matches(b)).collect()
+}
+
+/// Alphanumeric words of `text`, lowercased and without diacritics
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(fold_diacritics)
+        .collect()
+}
+
+/// Trigrams of a word padded with two spaces in front and one behind, as in
+/// PostgreSQL's pg_trgm, so short words and word starts count
+fn trigrams(word: &str) -> HashSet<String> {
+    let padded: Vec<char> = format!("  {} ", word).chars().collect();
+    padded.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+/// Jaccard similarity of two trigram sets
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+/// Sort `results` best first by `rank`; ties keep their current order
+#[cfg(feature = "full")]
+pub fn rank_results(db: &BukuDb, results: &mut [ScoredBookmark], rank: SearchRank) -> Result<()> {
+    match rank {
+        SearchRank::Relevance => results.sort_by(|a, b| compare_scores(a.score, b.score)),
+        SearchRank::Rating => {
+            let ratings = db.get_ratings()?;
+            let rating = |r: &ScoredBookmark| ratings.get(&r.bookmark.id).copied().unwrap_or(0);
+            results.sort_by(|a, b| {
+                rating(b)
+                    .cmp(&rating(a))
+                    .then_with(|| compare_scores(a.score, b.score))
+            });
+        }
+        SearchRank::Recent | SearchRank::Visits => {
+            let keys = db.get_rank_keys()?;
+            let key = |r: &ScoredBookmark| keys.get(&r.bookmark.id).copied().unwrap_or((0, 0));
+            results.sort_by(|a, b| {
+                let ((a_added, a_visits), (b_added, b_visits)) = (key(a), key(b));
+                if rank == SearchRank::Visits {
+                    b_visits.cmp(&a_visits).then(b_added.cmp(&a_added))
+                } else {
+                    b_added.cmp(&a_added)
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Higher scores first, unscored results last
+fn compare_scores(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(&["rust", "site:github.com"], &["rust"], &["github.com"], &[])]
+    #[case(&["-site:Reddit.com", "async"], &["async"], &[], &["reddit.com"])]
+    #[case(&["site:https://www.docs.rs/std"], &[], &["docs.rs"], &[])]
+    #[case(&["site:", "web"], &["site:", "web"], &[], &[])]
+    fn test_site_filter_extract(
+        #[case] keywords: &[&str],
+        #[case] rest: &[&str],
+        #[case] include: &[&str],
+        #[case] exclude: &[&str],
+    ) {
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+        let (kept, filter) = SiteFilter::extract(&keywords);
+        assert_eq!(kept, rest);
+        assert_eq!(filter.include, include);
+        assert_eq!(filter.exclude, exclude);
+    }
+
+    #[rstest]
+    #[case("https://github.com/rust-lang", true)]
+    #[case("https://gist.github.com/x", true)]
+    #[case("https://notgithub.com", false)]
+    #[case("https://old.reddit.com/r/rust", false)]
+    fn test_site_filter_matches(#[case] url: &str, #[case] expected: bool) {
+        let filter = SiteFilter {
+            include: vec!["github.com".to_string(), "reddit.com".to_string()],
+            exclude: vec!["old.reddit.com".to_string()],
+        };
+        assert_eq!(filter.matches(url), expected);
+    }
+
+    #[cfg(feature = "full")]
+    fn scored(db: &BukuDb, keyword: &str, weights: &SearchWeights) -> Vec<ScoredBookmark> {
+        db.search_scored(&[keyword.to_string()], true, false, weights)
+            .unwrap()
+            .into_iter()
+            .map(|(bookmark, score)| ScoredBookmark { bookmark, score })
+            .collect()
+    }
+
+    #[cfg(feature = "full")]
+    fn ids(results: &[ScoredBookmark]) -> Vec<usize> {
+        results.iter().map(|r| r.bookmark.id).collect()
+    }
+
+    #[test]
+    #[cfg(feature = "full")]
+    fn test_title_and_tag_matches_outrank_url_and_description() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust.example.com", "Example", ",", "", None)
+            .unwrap();
+        db.add_rec("https://a.com", "Cooking", ",", "Not about rust", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Rust Book", ",", "", None)
+            .unwrap();
+        db.add_rec("https://c.com", "Crates", ",rust,", "", None)
+            .unwrap();
+
+        let results = scored(&db, "rust", &SearchWeights::default());
+        assert_eq!(&ids(&results)[..2], &[3, 4]);
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|r| r.score.is_some()));
+
+        // Flipping the weights flips the order
+        let url_first = SearchWeights {
+            url: 10.0,
+            title: 1.0,
+            tags: 1.0,
+            desc: 1.0,
+        };
+        assert_eq!(ids(&scored(&db, "rust", &url_first))[0], 1);
+    }
+
+    #[cfg(feature = "full")]
+    #[rstest]
+    #[case(SearchRank::Relevance, vec![2, 1, 3])]
+    #[case(SearchRank::Recent, vec![3, 2, 1])]
+    #[case(SearchRank::Visits, vec![1, 3, 2])]
+    #[case(SearchRank::Rating, vec![3, 1, 2])]
+    fn test_rank_results(#[case] rank: SearchRank, #[case] expected: Vec<usize>) {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "Docs", ",", "rust", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Rust", ",", "", None).unwrap();
+        db.add_rec("https://c.com", "Other", ",", "", None).unwrap();
+        db.record_visits(&[1, 1, 3]).unwrap();
+        db.set_rating(&[3], 4).unwrap();
+        db.set_rating(&[1], 2).unwrap();
+
+        let mut results: Vec<ScoredBookmark> = db
+            .get_rec_all()
+            .unwrap()
+            .into_iter()
+            .map(|bookmark| {
+                let score = match bookmark.id {
+                    1 => Some(1.0),
+                    2 => Some(5.0),
+                    _ => None,
+                };
+                ScoredBookmark { bookmark, score }
+            })
+            .collect();
+        // created_at has second resolution; spread the bookmarks out
+        for id in 1..=3 {
+            db.execute(
+                "UPDATE bookmarks SET created_at = ?1 WHERE id = ?2",
+                (id as i64 * 100, id),
+            )
+            .unwrap();
+        }
+        rank_results(&db, &mut results, rank).unwrap();
+        assert_eq!(ids(&results), expected);
+    }
+
+    #[rstest]
+    #[case(KeywordMatch::Exact, &["prog", "rust*"], &["prog", "rust*"])]
+    #[case(KeywordMatch::Prefix, &["prog", "rust*"], &["prog*", "rust*"])]
+    #[case(KeywordMatch::Fuzzy, &["prog", "\"exact phrase\""], &["prog*", "\"exact phrase\""])]
+    #[case(KeywordMatch::Prefix, &["rust OR go"], &["rust OR go"])]
+    fn test_expand_keywords(
+        #[case] mode: KeywordMatch,
+        #[case] keywords: &[&str],
+        #[case] expected: &[&str],
+    ) {
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+        assert_eq!(expand_keywords(&keywords, mode), expected);
+    }
+
+    #[rstest]
+    #[case(&["progrmming"], true, false, vec![1])]
+    #[case(&["pythn"], true, false, vec![2])]
+    #[case(&["progrmming", "pythn"], false, false, vec![])]
+    #[case(&["progrmming", "pythn"], true, false, vec![1, 2])]
+    #[case(&["progrmming"], true, true, vec![])]
+    #[case(&["xyzzy"], true, false, vec![])]
+    fn test_fuzzy_filter(
+        #[case] keywords: &[&str],
+        #[case] any: bool,
+        #[case] notes: bool,
+        #[case] expected: Vec<usize>,
+    ) {
+        let bookmarks = vec![
+            Bookmark::new(
+                1,
+                "https://a.com".into(),
+                "Programming Rust".into(),
+                ",".into(),
+                "".into(),
+            ),
+            Bookmark::new(
+                2,
+                "https://b.com".into(),
+                "Python".into(),
+                ",".into(),
+                "Scripting".into(),
+            ),
+        ];
+        let keywords: Vec<String> = keywords.iter().map(|k| k.to_string()).collect();
+        let found: Vec<usize> = fuzzy_filter(bookmarks, &keywords, any, notes)
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[rstest]
+    #[case("relevance", Some(SearchRank::Relevance))]
+    #[case("Recent", Some(SearchRank::Recent))]
+    #[case("visits", Some(SearchRank::Visits))]
+    #[case("rating", Some(SearchRank::Rating))]
+    #[case("popular", None)]
+    fn test_search_rank_from_string(#[case] input: &str, #[case] expected: Option<SearchRank>) {
+        assert_eq!(SearchRank::from_string(input), expected);
+    }
+}
@@ -0,0 +1,1421 @@
+//! High-level bookmark operations shared by the CLI and library users
+//!
+//! `BukuDb` is a thin storage layer; the rules layered on top of it (tag
+//! merging, autotag rules, selectors, duplicate handling, fetch queueing)
+//! live here so every front-end behaves the same.
+
+use crate::cancel::CancellationToken;
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::fetch::{self, FetchOptions, FetchResult};
+use crate::import_export::ImportStats;
+use crate::models::bookmark::Bookmark;
+use crate::operations::encrypted::is_encrypted;
+use crate::operations::timeframe::TimeFilter;
+use crate::operations::{self, later, BookmarkSelection, ResultSet, SelectionMode};
+use crate::progress::ProgressSink;
+use crate::search::{self, KeywordMatch, ScoredBookmark, SearchRank, SearchWeights, SiteFilter};
+use crate::tag_map::TagMap;
+use crate::tags::{
+    apply_tag_ops, combine_tags, format_tags, parse_tag_ops, parse_tags, TagMerge,
+    TagNormalization, TagSetOp,
+};
+use crate::utils;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Refreshed bookmarks written per hold of the database lock; the fetches
+/// between batches run without it
+const REFRESH_BATCH: usize = 16;
+
+/// A bookmark to add; unset fields are filled from fetched metadata
+#[derive(Debug, Clone, Default)]
+pub struct AddRequest {
+    pub url: String,
+    pub title: Option<String>,
+    /// Tags to store; the page's keywords are used when `None`
+    pub tags: Option<Vec<String>>,
+    pub desc: Option<String>,
+    /// Put the bookmark in the read-later queue
+    pub later: bool,
+    /// When the bookmark expires (Unix seconds), see [`operations::expiry`]
+    pub expires_at: Option<i64>,
+}
+
+/// Result of adding a bookmark
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddOutcome {
+    pub id: usize,
+    /// Tags as stored, after autotag rules and resource type tags
+    pub tags: String,
+    /// Why fetching metadata failed, if it was attempted and did
+    pub fetch_error: Option<String>,
+    /// Added without metadata and queued for `fetch-pending`
+    pub queued: bool,
+}
+
+/// Field changes applied to every selected bookmark
+#[derive(Debug, Clone, Default)]
+pub struct FieldChanges {
+    pub url: Option<String>,
+    pub title: Option<String>,
+    pub desc: Option<String>,
+    /// Tag operations: `+tag`, `-tag`, `~old:new` or a bare tag to add, several
+    /// to an item when separated by commas (see [`parse_tag_ops`])
+    pub tags: Option<Vec<String>>,
+    pub immutable: Option<u8>,
+}
+
+impl FieldChanges {
+    pub fn is_empty(&self) -> bool {
+        self.url.is_none()
+            && self.title.is_none()
+            && self.desc.is_none()
+            && self.tags.is_none()
+            && self.immutable.is_none()
+    }
+}
+
+/// Result of updating a selection of bookmarks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateOutcome {
+    /// Bookmarks the selector matched
+    pub matched: usize,
+    pub updated: usize,
+    pub failed: usize,
+    /// IDs of the bookmarks updated
+    pub ids: Vec<usize>,
+    /// Per-bookmark failure messages, where known
+    pub errors: Vec<String>,
+}
+
+/// Result of normalizing the tags of every bookmark
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizeOutcome {
+    /// Bookmarks whose tags were (or would be) rewritten
+    pub ids: Vec<usize>,
+    /// Tags merged or renamed
+    pub merges: Vec<TagMerge>,
+}
+
+/// A bookmark's tags before and after a [`TagMap`] rewrite
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagChange {
+    pub id: usize,
+    pub url: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Result of deleting the bookmarks a selector matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteOutcome {
+    pub mode: SelectionMode,
+    /// The bookmarks as they were before deletion
+    pub deleted: Vec<Bookmark>,
+}
+
+/// Result of refreshing bookmarks' metadata
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RefreshOutcome {
+    pub refreshed: usize,
+    /// IDs of the bookmarks refreshed
+    pub ids: Vec<usize>,
+    /// Bookmarks that couldn't be fetched or updated, with the error
+    pub failed: Vec<(usize, String)>,
+    /// Cancelled before every bookmark was tried
+    pub cancelled: bool,
+}
+
+/// What a command that changes bookmarks did, in one shape for every command
+///
+/// The CLI prints this for `--output json`; other front-ends can return it as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChangeSummary {
+    /// The command: `add`, `update`, `refresh`, `delete`, `import`, `tag` or `links`
+    pub command: String,
+    /// IDs of the bookmarks added, updated or deleted (imports report counts only)
+    pub ids: Vec<usize>,
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Duplicates left untouched, and imported URLs that can't be bookmarked
+    pub skipped: usize,
+    /// Bookmarks added without metadata and queued for `fetch-pending`
+    pub queued: usize,
+    /// Failures that didn't stop the command
+    pub errors: Vec<String>,
+    /// The command was interrupted; the rest covers only what was done
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+impl ChangeSummary {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&AddOutcome> for ChangeSummary {
+    fn from(outcome: &AddOutcome) -> Self {
+        Self {
+            ids: vec![outcome.id],
+            added: 1,
+            queued: usize::from(outcome.queued),
+            ..Self::new("add")
+        }
+    }
+}
+
+impl From<&UpdateOutcome> for ChangeSummary {
+    fn from(outcome: &UpdateOutcome) -> Self {
+        Self {
+            ids: outcome.ids.clone(),
+            updated: outcome.updated,
+            errors: outcome.errors.clone(),
+            ..Self::new("update")
+        }
+    }
+}
+
+impl From<&RefreshOutcome> for ChangeSummary {
+    fn from(outcome: &RefreshOutcome) -> Self {
+        Self {
+            ids: outcome.ids.clone(),
+            updated: outcome.refreshed,
+            errors: outcome
+                .failed
+                .iter()
+                .map(|(id, error)| format!("Bookmark {}: {}", id, error))
+                .collect(),
+            cancelled: outcome.cancelled,
+            ..Self::new("refresh")
+        }
+    }
+}
+
+impl From<&DeleteOutcome> for ChangeSummary {
+    fn from(outcome: &DeleteOutcome) -> Self {
+        Self {
+            ids: outcome.deleted.iter().map(|b| b.id).collect(),
+            deleted: outcome.deleted.len(),
+            ..Self::new("delete")
+        }
+    }
+}
+
+impl From<&ImportStats> for ChangeSummary {
+    fn from(stats: &ImportStats) -> Self {
+        Self {
+            added: stats.added,
+            updated: stats.updated,
+            skipped: stats.skipped,
+            cancelled: stats.cancelled,
+            ..Self::new("import")
+        }
+    }
+}
+
+/// Search parameters for [`BookmarkService::search_query`]
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub keywords: Vec<String>,
+    /// Require every keyword instead of any
+    pub all: bool,
+    /// Treat the first keyword as a regular expression
+    pub regex: bool,
+    /// Keep only the last `limit` results, or the best `limit` when ranked
+    pub limit: Option<usize>,
+    /// Match keywords against descriptions (notes) only
+    pub notes: bool,
+    /// Only keep bookmarks from a previous search (see [`ResultSet::refine`])
+    pub within: Option<ResultSet>,
+    /// Order results best first; unranked results keep index order
+    pub rank: Option<SearchRank>,
+    /// Column weights for relevance scores
+    pub weights: SearchWeights,
+    /// How keywords without `*` or FTS5 syntax match
+    pub keyword_match: KeywordMatch,
+    /// Only keep bookmarks rated at least this many stars
+    pub min_rating: Option<u8>,
+    /// Only keep bookmarks whose page is in this language, e.g. `de`
+    pub lang: Option<String>,
+    /// Only keep bookmarks added or last visited within these bounds
+    pub time: TimeFilter,
+}
+
+/// High-level operations on a bookmark database
+pub struct BookmarkService<'a> {
+    db: &'a BukuDb,
+    fetch: FetchOptions,
+}
+
+impl<'a> BookmarkService<'a> {
+    pub fn new(db: &'a BukuDb, fetch: FetchOptions) -> Self {
+        Self { db, fetch }
+    }
+
+    pub fn db(&self) -> &'a BukuDb {
+        self.db
+    }
+
+    /// Fetch metadata for `url` with this service's fetch options
+    pub fn fetch(&self, url: &str) -> Result<FetchResult> {
+        fetch::fetch_data(url, &self.fetch)
+    }
+
+    /// Fetch the page's metadata and add it as a bookmark
+    ///
+    /// A failed fetch doesn't fail the add: the bookmark is stored with what
+    /// the request provides and queued for a later fetch.
+    pub fn add_with_fetch(&self, request: &AddRequest) -> Result<AddOutcome> {
+        match self.fetch(&request.url) {
+            Ok(fetched) => self.add(request, Some(&fetched)),
+            Err(e) => {
+                let mut outcome = self.add(request, None)?;
+                outcome.fetch_error = Some(e.to_string());
+                Ok(outcome)
+            }
+        }
+    }
+
+    /// Add a bookmark using already fetched metadata, or none (queued for later)
+    pub fn add(&self, request: &AddRequest, fetched: Option<&FetchResult>) -> Result<AddOutcome> {
+        let given: Vec<String> = request.tags.iter().flatten().flat_map(parse_tags).collect();
+        if let Some(tag) = given.iter().find(|t| utils::has_spaces(t)) {
+            return Err(BukursError::InvalidInput(format!(
+                "Invalid tag name: '{}' (tags cannot contain spaces)",
+                tag
+            )));
+        }
+
+        let tags = match (&request.tags, fetched) {
+            (Some(_), _) => format_tags(&given),
+            (None, Some(f)) => format_tags(&parse_tags(f.keywords.as_str())),
+            (None, None) => format_tags::<&str>(&[]),
+        };
+        let tags = self
+            .db
+            .autotag_rules()
+            .apply(&request.url, &tags)
+            .unwrap_or(tags);
+        let tags = match fetched.and_then(|f| f.apply_tags(&tags)) {
+            Some(typed) => typed,
+            None => tags,
+        };
+        let tags = if request.later {
+            later::with_unread_tag(&tags)
+        } else {
+            tags
+        };
+
+        let title = request
+            .title
+            .as_deref()
+            .or(fetched.map(|f| f.title.as_str()))
+            .unwrap_or("");
+        let desc = request
+            .desc
+            .as_deref()
+            .or(fetched.map(|f| f.desc.as_str()))
+            .unwrap_or("");
+
+        let id = self
+            .db
+            .add_rec(&request.url, title, &tags, desc, None)
+            .map_err(|e| duplicate_url_error(e, &request.url))?;
+        if request.expires_at.is_some() {
+            self.db.set_expiry(id, request.expires_at)?;
+        }
+        if let Some(words) = fetched.map(|f| f.word_count).filter(|&w| w > 0) {
+            self.db.set_word_counts(&[(&request.url, words)])?;
+        }
+        if let Some(lang) = fetched.and_then(|f| f.lang.clone()) {
+            self.db.set_languages(&[(&request.url, lang)])?;
+        }
+
+        let queued = fetched.is_none() && self.db.queue_fetch(&[&request.url])? > 0;
+        Ok(AddOutcome {
+            id,
+            tags,
+            fetch_error: None,
+            queued,
+        })
+    }
+
+    /// Resolve a selector (IDs, ranges, `*` or keywords) to bookmarks
+    pub fn select(&self, selector: &[String]) -> Result<BookmarkSelection> {
+        operations::resolve_bookmarks(selector, self.db)
+    }
+
+    /// Apply `changes` to every bookmark the selector matches
+    ///
+    /// Several bookmarks are updated in one transaction (and one undo batch);
+    /// if it fails nothing is changed and the error is returned.
+    pub fn update_many(
+        &self,
+        selector: &[String],
+        changes: &FieldChanges,
+    ) -> Result<UpdateOutcome> {
+        let bookmarks = self.select(selector)?.bookmarks;
+        self.update_bookmarks(&bookmarks, changes)
+    }
+
+    /// Apply `changes` to the given bookmarks
+    pub fn update_bookmarks(
+        &self,
+        bookmarks: &[Bookmark],
+        changes: &FieldChanges,
+    ) -> Result<UpdateOutcome> {
+        let mut outcome = UpdateOutcome {
+            matched: bookmarks.len(),
+            ..Default::default()
+        };
+        if bookmarks.is_empty() {
+            return Ok(outcome);
+        }
+
+        let url = changes.url.as_deref();
+        let title = changes.title.as_deref();
+        let desc = changes.desc.as_deref();
+        let tag_ops = changes.tags.as_deref().map(parse_tag_ops).transpose()?;
+
+        if let [bookmark] = bookmarks {
+            let tags = tag_ops
+                .as_ref()
+                .map(|ops| apply_tag_ops(&bookmark.tags, ops));
+            match self.db.update_rec_partial(
+                bookmark.id,
+                url,
+                title,
+                tags.as_deref(),
+                desc,
+                None, // parent_id
+            ) {
+                Ok(()) => {
+                    outcome.updated = 1;
+                    outcome.ids.push(bookmark.id);
+                }
+                Err(e) => {
+                    outcome.failed = 1;
+                    outcome.errors.push(if is_unique_violation(&e) {
+                        format!("Bookmark {}: URL already exists", bookmark.id)
+                    } else {
+                        format!("Bookmark {}: {}", bookmark.id, e)
+                    });
+                }
+            }
+            return Ok(outcome);
+        }
+
+        let (updated, failed) = if let Some(ops) = &tag_ops {
+            self.db.update_rec_batch_with_tags(
+                bookmarks,
+                Some(ops.as_slice()),
+                url,
+                title,
+                desc,
+                changes.immutable,
+            )?
+        } else {
+            self.db
+                .update_rec_batch(bookmarks, url, title, None, desc, changes.immutable)?
+        };
+        outcome.updated = updated;
+        outcome.failed = failed;
+        // The batch is one transaction, so it either updated every bookmark or none
+        if updated > 0 {
+            outcome.ids = bookmarks.iter().map(|b| b.id).collect();
+        }
+        Ok(outcome)
+    }
+
+    /// Combine `tags` with the tags of each of `bookmarks` by `op`
+    ///
+    /// Every changed bookmark's tags are normalized (see
+    /// [`crate::tags::normalize_tags`]) and written in one transaction, so one undo
+    /// reverts them all; bookmarks the change leaves alone aren't touched.
+    pub fn retag(
+        &self,
+        bookmarks: &[Bookmark],
+        tags: &[String],
+        op: TagSetOp,
+        lowercase: bool,
+    ) -> Result<UpdateOutcome> {
+        if let Some(tag) = tags.iter().find(|tag| utils::has_spaces(tag)) {
+            return Err(BukursError::InvalidInput(format!(
+                "Tag '{}' contains spaces",
+                tag
+            )));
+        }
+        let changed: Vec<Bookmark> = bookmarks
+            .iter()
+            .filter_map(|bookmark| {
+                let combined = combine_tags(&bookmark.tags, tags, op, lowercase);
+                (combined != bookmark.tags).then(|| Bookmark {
+                    tags: combined,
+                    ..bookmark.clone()
+                })
+            })
+            .collect();
+        let (updated, failed) = self
+            .db
+            .update_rec_batch_with_tags(&changed, None, None, None, None, None)?;
+        Ok(UpdateOutcome {
+            matched: bookmarks.len(),
+            updated,
+            failed,
+            ids: changed.iter().map(|b| b.id).collect(),
+            errors: Vec::new(),
+        })
+    }
+
+    /// Rewrite every bookmark's tags by `normalization`, or with `dry_run`
+    /// only report what would change
+    ///
+    /// The changes are one undo batch, and the search index follows them.
+    /// Encrypted bookmarks are left alone.
+    pub fn normalize_tags(
+        &self,
+        normalization: &TagNormalization,
+        dry_run: bool,
+    ) -> Result<NormalizeOutcome> {
+        let bookmarks: Vec<Bookmark> = self
+            .db
+            .get_rec_all()?
+            .into_iter()
+            .filter(|bookmark| !is_encrypted(bookmark))
+            .collect();
+        let merges = normalization.merges(bookmarks.iter().map(|b| b.tags.as_str()));
+        let changed: Vec<Bookmark> = bookmarks
+            .into_iter()
+            .filter_map(|bookmark| {
+                let tags = normalization.apply(&bookmark.tags);
+                (tags != bookmark.tags).then_some(Bookmark { tags, ..bookmark })
+            })
+            .collect();
+        if !dry_run {
+            self.db
+                .update_rec_batch_with_tags(&changed, None, None, None, None, None)?;
+        }
+        Ok(NormalizeOutcome {
+            ids: changed.iter().map(|b| b.id).collect(),
+            merges,
+        })
+    }
+
+    /// Rewrite every bookmark's tags by the rules of `map`, or with `dry_run`
+    /// only report what would change
+    ///
+    /// All rewrites are one transaction and one undo batch. Encrypted
+    /// bookmarks are left alone.
+    pub fn apply_tag_map(&self, map: &TagMap, dry_run: bool) -> Result<Vec<TagChange>> {
+        let mut changes = Vec::new();
+        let mut changed = Vec::new();
+        for bookmark in self.db.get_rec_all()? {
+            if is_encrypted(&bookmark) {
+                continue;
+            }
+            if let Some(tags) = map.apply(&bookmark.url, &bookmark.tags) {
+                changes.push(TagChange {
+                    id: bookmark.id,
+                    url: bookmark.url.clone(),
+                    before: bookmark.tags.clone(),
+                    after: tags.clone(),
+                });
+                changed.push(Bookmark { tags, ..bookmark });
+            }
+        }
+        if !dry_run {
+            self.db
+                .update_rec_batch_with_tags(&changed, None, None, None, None, None)?;
+        }
+        Ok(changes)
+    }
+
+    /// Re-fetch each bookmark's title and description, keeping the stored value
+    /// for any field the page doesn't provide
+    ///
+    /// Failures are collected rather than aborting the refresh. Each bookmark is
+    /// saved as soon as it's fetched, so cancelling keeps the ones already done.
+    pub fn refresh(
+        &self,
+        bookmarks: &[Bookmark],
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<RefreshOutcome> {
+        let mut outcome = RefreshOutcome::default();
+        let mut fetched = Vec::with_capacity(REFRESH_BATCH);
+        progress.start("Refreshing", Some(bookmarks.len() as u64));
+
+        for bookmark in bookmarks {
+            if cancel.is_cancelled() {
+                outcome.cancelled = true;
+                break;
+            }
+            progress.advance(0, Some(&bookmark.url));
+            match self.fetch(&bookmark.url) {
+                Ok(result) => fetched.push((bookmark, result)),
+                Err(e) => outcome.failed.push((bookmark.id, e.to_string())),
+            }
+            if fetched.len() == REFRESH_BATCH {
+                self.write_refreshed(&mut fetched, &mut outcome)?;
+            }
+            progress.advance(1, None);
+        }
+        // Pages fetched before a cancellation are still saved
+        self.write_refreshed(&mut fetched, &mut outcome)?;
+
+        progress.finish();
+        Ok(outcome)
+    }
+
+    /// Store and clear a batch of fetched metadata under one hold of the
+    /// database lock
+    fn write_refreshed(
+        &self,
+        fetched: &mut Vec<(&Bookmark, FetchResult)>,
+        outcome: &mut RefreshOutcome,
+    ) -> Result<()> {
+        if fetched.is_empty() {
+            return Ok(());
+        }
+        let _lock = self.db.write_lock()?;
+        for (bookmark, fetched) in fetched.drain(..) {
+            let result = (|| -> Result<()> {
+                let title = Some(fetched.title.as_str()).filter(|t| !t.is_empty());
+                let desc = Some(fetched.desc.as_str()).filter(|d| !d.is_empty());
+                self.db
+                    .update_rec_partial(bookmark.id, None, title, None, desc, None)?;
+                if fetched.word_count > 0 {
+                    self.db
+                        .set_word_counts(&[(&bookmark.url, fetched.word_count)])?;
+                }
+                if let Some(lang) = fetched.lang {
+                    self.db.set_languages(&[(&bookmark.url, lang)])?;
+                }
+                Ok(())
+            })();
+            match result {
+                Ok(()) => {
+                    outcome.refreshed += 1;
+                    outcome.ids.push(bookmark.id);
+                }
+                Err(e) => outcome.failed.push((bookmark.id, e.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a resolved selection; several bookmarks are undone together
+    pub fn delete(&self, selection: &BookmarkSelection) -> Result<usize> {
+        operations::execute_delete(selection, self.db)
+    }
+
+    /// Delete every bookmark the selector matches, without confirmation
+    pub fn delete_by_selector(&self, selector: &[String]) -> Result<DeleteOutcome> {
+        let selection = self.select(selector)?;
+        self.delete(&selection)?;
+        Ok(DeleteOutcome {
+            mode: selection.mode,
+            deleted: selection.bookmarks,
+        })
+    }
+
+    /// Full-text search, optionally limited to the last `limit` results
+    pub fn search_query(&self, query: &SearchQuery) -> Result<Vec<Bookmark>> {
+        Ok(self
+            .search_scored(query)?
+            .into_iter()
+            .map(|r| r.bookmark)
+            .collect())
+    }
+
+    /// Run a search, keeping each result's relevance score
+    ///
+    /// Scores are only computed for ranked full-text searches; notes searches
+    /// and unranked ones report `None`.
+    pub fn search_scored(&self, query: &SearchQuery) -> Result<Vec<ScoredBookmark>> {
+        let (words, sites) = if query.regex {
+            (query.keywords.clone(), SiteFilter::default())
+        } else {
+            SiteFilter::extract(&query.keywords)
+        };
+        let keywords = if query.regex {
+            words.clone()
+        } else {
+            search::expand_keywords(&words, query.keyword_match)
+        };
+        let unscored = |bookmarks: Vec<Bookmark>| -> Vec<ScoredBookmark> {
+            bookmarks
+                .into_iter()
+                .map(|bookmark| ScoredBookmark {
+                    bookmark,
+                    score: None,
+                })
+                .collect()
+        };
+        let mut records = if query.notes {
+            unscored(self.db.search_notes(&keywords, !query.all, query.regex)?)
+        } else if query.rank.is_some() {
+            self.db
+                .search_scored(&keywords, !query.all, query.regex, &query.weights)?
+                .into_iter()
+                .map(|(bookmark, score)| ScoredBookmark { bookmark, score })
+                .collect()
+        } else {
+            unscored(self.db.search(&keywords, !query.all, false, query.regex)?)
+        };
+        if records.is_empty()
+            && query.keyword_match == KeywordMatch::Fuzzy
+            && !query.regex
+            && !words.is_empty()
+        {
+            records = unscored(search::fuzzy_search(
+                self.db,
+                &words,
+                !query.all,
+                query.notes,
+            )?);
+        }
+        // A keyword that is a bookmark's alias finds the bookmark too
+        if !query.regex && !query.notes && (!query.all || words.len() == 1) {
+            let mut aliased = false;
+            for keyword in &words {
+                if let Some(bookmark) = self.db.get_rec_by_alias(keyword)? {
+                    if !records.iter().any(|r| r.bookmark.id == bookmark.id) {
+                        records.push(ScoredBookmark {
+                            bookmark,
+                            score: None,
+                        });
+                        aliased = true;
+                    }
+                }
+            }
+            if aliased && query.rank.is_none() {
+                records.sort_by_key(|r| r.bookmark.id);
+            }
+        }
+        if let Some(previous) = &query.within {
+            let ids: HashSet<usize> = previous.ids.iter().copied().collect();
+            records.retain(|r| ids.contains(&r.bookmark.id));
+        }
+        if !sites.is_empty() {
+            let ids = self.db.get_ids_on_sites(&sites)?;
+            records.retain(|r| ids.contains(&r.bookmark.id));
+        }
+        if !query.time.is_empty() {
+            let (added_after, not_visited_since) = query.time.cutoffs(operations::expiry::now())?;
+            let ids = self
+                .db
+                .get_ids_in_timeframe(added_after, not_visited_since)?;
+            records.retain(|r| ids.contains(&r.bookmark.id));
+        }
+        if let Some(min) = query.min_rating {
+            let ratings = self.db.get_ratings()?;
+            records.retain(|r| ratings.get(&r.bookmark.id).copied().unwrap_or(0) >= min);
+        }
+        if let Some(lang) = &query.lang {
+            let wanted = fetch::language::normalize(lang)
+                .ok_or_else(|| format!("Invalid language code: {}", lang))?;
+            let languages = self.db.get_languages()?;
+            records.retain(|r| languages.get(&r.bookmark.id) == Some(&wanted));
+        }
+        if let Some(rank) = query.rank {
+            search::rank_results(self.db, &mut records, rank)?;
+        }
+        if let Some(limit) = query.limit {
+            if query.rank.is_some() {
+                records.truncate(limit);
+            } else {
+                let start = records.len().saturating_sub(limit);
+                records.drain(..start);
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn is_unique_violation(e: &rusqlite::Error) -> bool {
+    matches!(
+        e,
+        rusqlite::Error::SqliteFailure(err, _)
+            if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+    )
+}
+
+/// Report a UNIQUE violation on insert as a duplicate URL
+fn duplicate_url_error(e: rusqlite::Error, url: &str) -> BukursError {
+    if is_unique_violation(&e) {
+        BukursError::InvalidInput(format!("Duplicate URL: {}", url))
+    } else {
+        BukursError::Database(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autotag::AutotagRules;
+    use crate::resource::ResourceKind;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn service(db: &BukuDb) -> BookmarkService<'_> {
+        BookmarkService::new(db, FetchOptions::default())
+    }
+
+    fn ids(selector: &[&str]) -> Vec<String> {
+        selector.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_refresh_collects_failures() {
+        use crate::progress::ProgressEvent;
+        use std::sync::Mutex;
+
+        let db = BukuDb::init_in_memory().unwrap();
+        // Nothing listens on port 1, so the fetch fails without leaving the host
+        let id = db
+            .add_rec("http://127.0.0.1:1/", "Old", ",", "", None)
+            .unwrap();
+        let bookmarks = db.get_rec_all().unwrap();
+
+        let advanced = Mutex::new(0);
+        let outcome = service(&db)
+            .refresh(
+                &bookmarks,
+                &|event: ProgressEvent<'_>| {
+                    if let ProgressEvent::Advance { count, .. } = event {
+                        *advanced.lock().unwrap() += count;
+                    }
+                },
+                &CancellationToken::new(),
+            )
+            .unwrap();
+
+        assert_eq!(outcome.refreshed, 0);
+        assert_eq!(outcome.failed.len(), 1);
+        assert_eq!(outcome.failed[0].0, id);
+        assert_eq!(advanced.into_inner().unwrap(), 1);
+        assert_eq!(db.get_rec_by_id(id).unwrap().unwrap().title, "Old");
+        assert!(!outcome.cancelled);
+    }
+
+    #[test]
+    fn test_refresh_stops_when_cancelled() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("http://127.0.0.1:1/", "Old", ",", "", None)
+            .unwrap();
+        let bookmarks = db.get_rec_all().unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let outcome = service(&db)
+            .refresh(&bookmarks, &crate::progress::NoProgress, &cancel)
+            .unwrap();
+
+        assert!(outcome.cancelled);
+        assert_eq!(outcome.refreshed, 0);
+        assert!(outcome.failed.is_empty());
+    }
+
+    #[test]
+    fn test_add_without_metadata_is_queued() {
+        let mut db = BukuDb::init_in_memory().unwrap();
+        let mut rules = AutotagRules::new();
+        rules.insert("github.com", "code");
+        db.set_autotag_rules(rules);
+
+        let request = AddRequest {
+            url: "https://github.com/rust-lang/rust".to_string(),
+            tags: Some(vec!["rust,lang".to_string()]),
+            ..Default::default()
+        };
+        let outcome = service(&db).add(&request, None).unwrap();
+
+        assert_eq!(outcome.tags, ",rust,lang,code,");
+        assert!(outcome.queued);
+        assert_eq!(db.get_pending_fetch().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_add_uses_fetched_metadata() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let fetched = FetchResult {
+            url: "https://a.com/paper.pdf".to_string(),
+            title: Arc::new("A Paper".to_string()),
+            desc: Arc::new("Abstract".to_string()),
+            keywords: Arc::new("science, physics".to_string()),
+            kind: ResourceKind::Pdf,
+            tags: vec!["paper".to_string()],
+            word_count: 4200,
+            lang: Some("en".to_string()),
+        };
+        let request = AddRequest {
+            url: "https://a.com/paper.pdf".to_string(),
+            desc: Some("My notes".to_string()),
+            ..Default::default()
+        };
+        let outcome = service(&db).add(&request, Some(&fetched)).unwrap();
+
+        assert_eq!(outcome.tags, ",science,physics,pdf,paper,");
+        assert!(!outcome.queued);
+        let rec = db.get_rec_by_id(outcome.id).unwrap().unwrap();
+        assert_eq!(rec.title, "A Paper");
+        assert_eq!(rec.description, "My notes");
+        assert_eq!(db.get_word_counts().unwrap().get(&outcome.id), Some(&4200));
+        assert_eq!(
+            db.get_languages()
+                .unwrap()
+                .get(&outcome.id)
+                .map(String::as_str),
+            Some("en")
+        );
+    }
+
+    #[test]
+    fn test_add_later_tags_unread() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let request = AddRequest {
+            url: "https://a.com".to_string(),
+            tags: Some(vec!["rust".to_string()]),
+            later: true,
+            ..Default::default()
+        };
+        let outcome = service(&db).add(&request, None).unwrap();
+
+        assert_eq!(outcome.tags, ",rust,unread,");
+        assert_eq!(later::count(&db).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_add_with_expiry() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let request = AddRequest {
+            url: "https://event.example.com".to_string(),
+            expires_at: Some(1_000),
+            ..Default::default()
+        };
+        let outcome = service(&db).add(&request, None).unwrap();
+
+        let expired = operations::expiry::expired(&db, 1_000).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].bookmark.id, outcome.id);
+        assert!(operations::expiry::expired(&db, 999).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_rejects_duplicates_and_bad_tags() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let service = service(&db);
+        let request = AddRequest {
+            url: "https://a.com".to_string(),
+            ..Default::default()
+        };
+        service.add(&request, None).unwrap();
+
+        let err = service.add(&request, None).unwrap_err();
+        assert!(err.to_string().contains("Duplicate URL"));
+
+        let spaced = AddRequest {
+            url: "https://b.com".to_string(),
+            tags: Some(vec!["two words".to_string()]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.add(&spaced, None),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_many() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=3 {
+            db.add_rec(&format!("https://{}.com", i), "", ",todo,", "", None)
+                .unwrap();
+        }
+        let changes = FieldChanges {
+            tags: Some(ids(&["~todo:done", "+web"])),
+            ..Default::default()
+        };
+
+        let outcome = service(&db).update_many(&ids(&["1-2"]), &changes).unwrap();
+        assert_eq!(
+            (outcome.matched, outcome.updated, outcome.failed),
+            (2, 2, 0)
+        );
+
+        let tags: Vec<String> = db
+            .get_rec_all()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.tags)
+            .collect();
+        assert!(tags[0].contains("done") && tags[0].contains("web"));
+        assert!(tags[1].contains("done"));
+        assert_eq!(tags[2], ",todo,");
+
+        // A bad operation fails before anything changes
+        let changes = FieldChanges {
+            tags: Some(ids(&["+ok,~broken"])),
+            ..Default::default()
+        };
+        assert!(matches!(
+            service(&db).update_many(&ids(&["*"]), &changes),
+            Err(BukursError::InvalidInput(_))
+        ));
+        assert_eq!(db.get_rec_by_id(3).unwrap().unwrap().tags, ",todo,");
+    }
+
+    #[test]
+    fn test_retag_is_one_undo_batch() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://1.com", "", ",Web,todo,", "", None)
+            .unwrap();
+        db.add_rec("https://2.com", "", ",rust,", "", None).unwrap();
+        db.add_rec("https://3.com", "", ",async,rust,", "", None)
+            .unwrap();
+        let bookmarks = db.get_rec_all().unwrap();
+        let service = service(&db);
+
+        let outcome = service
+            .retag(&bookmarks, &ids(&["rust", "async"]), TagSetOp::Add, true)
+            .unwrap();
+        // The third already had both tags
+        assert_eq!((outcome.matched, outcome.updated), (3, 2));
+        assert_eq!(outcome.ids, vec![1, 2]);
+        let tags: Vec<String> = db
+            .get_rec_all()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.tags)
+            .collect();
+        assert_eq!(
+            tags,
+            vec![",async,rust,todo,web,", ",async,rust,", ",async,rust,"]
+        );
+
+        assert_eq!(db.undo_last().unwrap(), Some(("UPDATE".to_string(), 2)));
+        assert_eq!(db.get_rec_by_id(1).unwrap().unwrap().tags, ",Web,todo,");
+        assert_eq!(db.get_rec_by_id(2).unwrap().unwrap().tags, ",rust,");
+
+        assert!(service
+            .retag(&bookmarks, &ids(&["two words"]), TagSetOp::Set, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://1.com", "", ",Rust,web,", "", None)
+            .unwrap();
+        db.add_rec("https://2.com", "", ", rust,rust,", "", None)
+            .unwrap();
+        db.add_rec("https://3.com", "", ",rust,", "", None).unwrap();
+        let service = service(&db);
+        let tags = || -> Vec<String> {
+            db.get_rec_all()
+                .unwrap()
+                .into_iter()
+                .map(|b| b.tags)
+                .collect()
+        };
+
+        let dry_run = service
+            .normalize_tags(&TagNormalization::all(), true)
+            .unwrap();
+        assert_eq!(dry_run.ids, vec![1, 2]);
+        assert_eq!(dry_run.merges.len(), 1);
+        assert_eq!(tags()[0], ",Rust,web,");
+
+        let outcome = service
+            .normalize_tags(&TagNormalization::all(), false)
+            .unwrap();
+        assert_eq!(outcome, dry_run);
+        assert_eq!(tags(), vec![",rust,web,", ",rust,", ",rust,"]);
+        // The search index follows the rewritten tags
+        let found = db.search(&ids(&["rust"]), true, false, false).unwrap();
+        assert_eq!(found.len(), 3);
+
+        assert_eq!(db.undo_last().unwrap(), Some(("UPDATE".to_string(), 2)));
+        assert_eq!(tags()[1], ", rust,rust,");
+    }
+
+    #[test]
+    fn test_apply_tag_map() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://1.com", "", ",Programming,web,", "", None)
+            .unwrap();
+        db.add_rec("https://github.com/a", "", ",Bookmarks Bar,", "", None)
+            .unwrap();
+        db.add_rec("https://3.com", "", ",web,", "", None).unwrap();
+        let map = TagMap::parse("Programming,code\nBookmarks Bar,\nhttps://github.com/*,github\n")
+            .unwrap();
+        let service = service(&db);
+        let tags = || -> Vec<String> {
+            db.get_rec_all()
+                .unwrap()
+                .into_iter()
+                .map(|b| b.tags)
+                .collect()
+        };
+
+        let preview = service.apply_tag_map(&map, true).unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[0].before, ",Programming,web,");
+        assert_eq!(preview[0].after, ",code,web,");
+        assert_eq!(preview[1].after, ",github,");
+        assert_eq!(tags()[0], ",Programming,web,");
+
+        assert_eq!(service.apply_tag_map(&map, false).unwrap(), preview);
+        assert_eq!(tags(), vec![",code,web,", ",github,", ",web,"]);
+
+        assert_eq!(db.undo_last().unwrap(), Some(("UPDATE".to_string(), 2)));
+        assert_eq!(tags()[1], ",Bookmarks Bar,");
+    }
+
+    #[test]
+    fn test_search_finds_aliases() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let github = db
+            .add_rec("https://github.com", "GitHub", ",code,", "", None)
+            .unwrap();
+        db.add_rec("https://gitlab.com", "GitLab", ",code,gh,", "", None)
+            .unwrap();
+        db.set_alias(github, Some("gh")).unwrap();
+
+        let query = SearchQuery {
+            keywords: ids(&["gh"]),
+            ..Default::default()
+        };
+        let found: Vec<usize> = service(&db)
+            .search_query(&query)
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_min_rating() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for (url, rating) in [
+            ("https://a.com", 2),
+            ("https://b.com", 5),
+            ("https://c.com", 0),
+        ] {
+            let id = db.add_rec(url, "Rust", ",", "", None).unwrap();
+            db.set_rating(&[id], rating).unwrap();
+        }
+
+        let query = SearchQuery {
+            keywords: ids(&["rust"]),
+            min_rating: Some(2),
+            ..Default::default()
+        };
+        let found: Vec<usize> = service(&db)
+            .search_query(&query)
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_time() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for (url, created_at) in [
+            ("https://a.com", 0),
+            ("https://b.com", operations::expiry::now()),
+        ] {
+            let id = db.add_rec(url, "Rust", ",", "", None).unwrap();
+            db.execute(
+                "UPDATE bookmarks SET created_at = ?2 WHERE id = ?1",
+                (id, created_at),
+            )
+            .unwrap();
+        }
+
+        let found = |time: TimeFilter| -> Vec<usize> {
+            let query = SearchQuery {
+                keywords: ids(&["rust"]),
+                time,
+                ..Default::default()
+            };
+            service(&db)
+                .search_query(&query)
+                .unwrap()
+                .iter()
+                .map(|b| b.id)
+                .collect()
+        };
+        let within = TimeFilter {
+            added_within: Some("7d".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(found(within), vec![2]);
+        let after = TimeFilter {
+            added_after: Some("2000-01-01".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(found(after), vec![2]);
+        let unvisited = TimeFilter {
+            not_visited_since: Some("1y".to_string()),
+            ..Default::default()
+        };
+        // Neither was ever opened, however recently it was added
+        assert_eq!(found(unvisited), vec![1, 2]);
+    }
+
+    #[rstest]
+    #[case("de", vec![2])]
+    #[case("en-GB", vec![1])]
+    #[case("fr", vec![])]
+    fn test_search_lang(#[case] lang: &str, #[case] expected: Vec<usize>) {
+        let db = BukuDb::init_in_memory().unwrap();
+        for url in ["https://a.com", "https://b.de", "https://c.com"] {
+            db.add_rec(url, "Rust", ",", "", None).unwrap();
+        }
+        db.set_languages(&[
+            ("https://a.com", "en".to_string()),
+            ("https://b.de", "de".to_string()),
+        ])
+        .unwrap();
+
+        let query = SearchQuery {
+            keywords: ids(&["rust"]),
+            lang: Some(lang.to_string()),
+            ..Default::default()
+        };
+        let found: Vec<usize> = service(&db)
+            .search_query(&query)
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[rstest]
+    #[case(&["rust", "site:github.com"], vec![1, 2])]
+    #[case(&["rust", "-site:github.com"], vec![3])]
+    #[case(&["site:github.com", "-site:gist.github.com"], vec![1])]
+    #[case(&["site:docs.rs"], vec![4])]
+    fn test_search_sites(#[case] keywords: &[&str], #[case] expected: Vec<usize>) {
+        let db = BukuDb::init_in_memory().unwrap();
+        for (url, title) in [
+            ("https://github.com/rust-lang/rust", "Rust"),
+            ("https://gist.github.com/rust", "Rust gist"),
+            ("https://reddit.com/r/rust", "Rust subreddit"),
+            ("https://docs.rs", "Docs"),
+        ] {
+            db.add_rec(url, title, ",", "", None).unwrap();
+        }
+
+        let query = SearchQuery {
+            keywords: ids(keywords),
+            ..Default::default()
+        };
+        let found: Vec<usize> = service(&db)
+            .search_query(&query)
+            .unwrap()
+            .iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_update_single_duplicate_url() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "", ",", "", None).unwrap();
+        db.add_rec("https://b.com", "", ",", "", None).unwrap();
+        let changes = FieldChanges {
+            url: Some("https://a.com".to_string()),
+            ..Default::default()
+        };
+
+        let outcome = service(&db).update_many(&ids(&["2"]), &changes).unwrap();
+        assert_eq!(outcome.failed, 1);
+        assert_eq!(outcome.errors, vec!["Bookmark 2: URL already exists"]);
+    }
+
+    #[test]
+    fn test_change_summaries() {
+        let db = BukuDb::init_in_memory().unwrap();
+        let request = AddRequest {
+            url: "https://example.com".to_string(),
+            ..Default::default()
+        };
+        let added = ChangeSummary::from(&service(&db).add(&request, None).unwrap());
+        assert_eq!(
+            (added.command.as_str(), added.ids, added.added, added.queued),
+            ("add", vec![1], 1, 1)
+        );
+
+        db.add_rec("https://b.com", "", ",", "", None).unwrap();
+        let changes = FieldChanges {
+            tags: Some(ids(&["+web"])),
+            ..Default::default()
+        };
+        let outcome = service(&db).update_many(&ids(&["1-2"]), &changes).unwrap();
+        let updated = ChangeSummary::from(&outcome);
+        assert_eq!((updated.ids, updated.updated), (vec![1, 2], 2));
+
+        let deleted = ChangeSummary::from(&service(&db).delete_by_selector(&ids(&["2"])).unwrap());
+        assert_eq!((deleted.ids, deleted.deleted), (vec![2], 1));
+
+        let json = serde_json::to_value(ChangeSummary::from(&ImportStats {
+            added: 3,
+            skipped: 1,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(json["command"], "import");
+        assert_eq!(json["added"], 3);
+        assert_eq!(json["skipped"], 1);
+        assert!(json.get("cancelled").is_none());
+    }
+
+    #[test]
+    fn test_delete_by_selector() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://rust-lang.org", "Rust", ",", "", None)
+            .unwrap();
+        db.add_rec("https://python.org", "Python", ",", "", None)
+            .unwrap();
+
+        let outcome = service(&db).delete_by_selector(&ids(&["rust"])).unwrap();
+        assert_eq!(outcome.mode, SelectionMode::ByKeywords(ids(&["rust"])));
+        assert_eq!(outcome.deleted.len(), 1);
+        assert_eq!(outcome.deleted[0].title, "Rust");
+        assert_eq!(db.get_rec_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_query_limit() {
+        let db = BukuDb::init_in_memory().unwrap();
+        for i in 1..=5 {
+            db.add_rec(
+                &format!("https://{}.example.com", i),
+                "Example",
+                ",",
+                "",
+                None,
+            )
+            .unwrap();
+        }
+        let query = SearchQuery {
+            keywords: ids(&["example"]),
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        let results = service(&db).search_query(&query).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].id, 5);
+    }
+
+    #[test]
+    fn test_search_query_within_previous_results() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "Rust async book", ",", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Rust embedded", ",", "", None)
+            .unwrap();
+        db.add_rec("https://c.com", "Python async", ",", "", None)
+            .unwrap();
+
+        let first = service(&db)
+            .search_query(&SearchQuery {
+                keywords: ids(&["rust"]),
+                ..Default::default()
+            })
+            .unwrap();
+        let refined = service(&db)
+            .search_query(&SearchQuery {
+                keywords: ids(&["async"]),
+                within: Some(ResultSet::new(&ids(&["rust"]), &first)),
+                ..Default::default()
+            })
+            .unwrap();
+        let urls: Vec<&str> = refined.iter().map(|b| b.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.com"]);
+    }
+
+    #[rstest]
+    #[case(KeywordMatch::Exact, vec![])]
+    #[case(KeywordMatch::Prefix, vec![1])]
+    #[case(KeywordMatch::Fuzzy, vec![1])]
+    fn test_search_keyword_match(
+        #[case] keyword_match: KeywordMatch,
+        #[case] expected: Vec<usize>,
+    ) {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "Programming in Rust", ",", "", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Cooking", ",", "", None)
+            .unwrap();
+
+        let search = |keywords: &[&str]| -> Vec<usize> {
+            service(&db)
+                .search_query(&SearchQuery {
+                    keywords: ids(keywords),
+                    keyword_match,
+                    ..Default::default()
+                })
+                .unwrap()
+                .iter()
+                .map(|b| b.id)
+                .collect()
+        };
+        assert_eq!(search(&["prog"]), expected);
+        // A typo only matches with the fuzzy fallback
+        let typo = if keyword_match == KeywordMatch::Fuzzy {
+            vec![1]
+        } else {
+            vec![]
+        };
+        assert_eq!(search(&["progrmming"]), typo);
+    }
+
+    #[test]
+    fn test_search_ranked_keeps_best_results() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com", "Cooking", ",", "Mentions rust once", None)
+            .unwrap();
+        db.add_rec("https://b.com", "Rust", ",rust,", "", None)
+            .unwrap();
+        db.add_rec("https://c.com", "Baking", ",", "", None)
+            .unwrap();
+
+        let results = service(&db)
+            .search_scored(&SearchQuery {
+                keywords: ids(&["rust"]),
+                rank: Some(SearchRank::Relevance),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].bookmark.id, 2);
+        assert!(results[0].score.unwrap() > 0.0);
+
+        // Without a rank, results stay in index order and carry no score
+        let unranked = service(&db)
+            .search_scored(&SearchQuery {
+                keywords: ids(&["rust"]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(unranked[0].bookmark.id, 1);
+        assert!(unranked.iter().all(|r| r.score.is_none()));
+    }
+}
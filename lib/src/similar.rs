@@ -0,0 +1,205 @@
+//! Find bookmarks related to a given one
+//!
+//! Bookmarks are ranked by a weighted sum of three signals: shared tags and
+//! shared title words (both as Jaccard similarity of the two sets), and being
+//! on the same domain. Pairs that share most of their tags are also good
+//! candidates for merging tags.
+
+use crate::db::BukuDb;
+use crate::error::{BukursError, Result};
+use crate::models::bookmark::Bookmark;
+use crate::tags::parse_tags;
+use crate::utils::url_domain;
+use std::collections::HashSet;
+
+/// Number of similar bookmarks shown when no limit is given
+pub const DEFAULT_SIMILAR_LIMIT: usize = 10;
+
+/// Weight of tag overlap
+const TAG_WEIGHT: f64 = 0.5;
+/// Weight of title word overlap
+const TITLE_WEIGHT: f64 = 0.3;
+/// Weight of being on the same domain
+const DOMAIN_WEIGHT: f64 = 0.2;
+
+/// Shortest title word that counts
+const MIN_WORD_LEN: usize = 3;
+
+/// Words too common in titles to say anything about a page
+const STOP_WORDS: &[&str] = &[
+    "and", "are", "for", "from", "how", "the", "this", "that", "with", "what", "why", "you",
+    "your", "home", "page", "welcome",
+];
+
+/// A bookmark ranked by how similar it is to the target
+#[derive(Debug, Clone)]
+pub struct SimilarBookmark {
+    pub bookmark: Bookmark,
+    /// Between 0 and 1; higher is more similar
+    pub score: f64,
+    /// Tags both bookmarks have, lowercased
+    pub shared_tags: Vec<String>,
+    pub same_domain: bool,
+}
+
+/// Lowercased tags of a bookmark
+fn tag_set(bookmark: &Bookmark) -> HashSet<String> {
+    parse_tags(&bookmark.tags)
+        .into_iter()
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+/// Lowercased title words, without short and common words
+fn title_words(title: &str) -> HashSet<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= MIN_WORD_LEN && !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Size of the intersection over the size of the union; 0 when both are empty
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Rank `candidates` by similarity to `target`, most similar first
+///
+/// The target itself and bookmarks with nothing in common are left out; at
+/// most `limit` are returned. Ties are broken by bookmark id.
+pub fn find_similar(
+    target: &Bookmark,
+    candidates: &[Bookmark],
+    limit: usize,
+) -> Vec<SimilarBookmark> {
+    let tags = tag_set(target);
+    let words = title_words(&target.title);
+    let domain = url_domain(&target.url);
+
+    let mut ranked: Vec<SimilarBookmark> = candidates
+        .iter()
+        .filter(|b| b.id != target.id)
+        .filter_map(|bookmark| {
+            let other_tags = tag_set(bookmark);
+            let same_domain = domain.is_some() && url_domain(&bookmark.url) == domain;
+            let score = TAG_WEIGHT * jaccard(&tags, &other_tags)
+                + TITLE_WEIGHT * jaccard(&words, &title_words(&bookmark.title))
+                + if same_domain { DOMAIN_WEIGHT } else { 0.0 };
+            if score <= 0.0 {
+                return None;
+            }
+            let mut shared_tags: Vec<String> = tags.intersection(&other_tags).cloned().collect();
+            shared_tags.sort();
+            Some(SimilarBookmark {
+                bookmark: bookmark.clone(),
+                score,
+                shared_tags,
+                same_domain,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.bookmark.id.cmp(&b.bookmark.id))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Bookmarks most similar to bookmark `id`
+pub fn similar_to(db: &BukuDb, id: usize, limit: usize) -> Result<Vec<SimilarBookmark>> {
+    let target = db
+        .get_rec_by_id(id)?
+        .ok_or(BukursError::BookmarkNotFound(id))?;
+    Ok(find_similar(&target, &db.get_rec_all()?, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn bookmark(id: usize, url: &str, title: &str, tags: &str) -> Bookmark {
+        Bookmark::new(
+            id,
+            url.to_string(),
+            title.to_string(),
+            tags.to_string(),
+            String::new(),
+        )
+    }
+
+    #[rstest]
+    #[case("The Rust Book", &["rust", "book"])]
+    #[case("How to: write a CLI, in Rust!", &["write", "cli", "rust"])]
+    #[case("Home", &[])]
+    fn test_title_words(#[case] title: &str, #[case] expected: &[&str]) {
+        let expected: HashSet<String> = expected.iter().map(|w| w.to_string()).collect();
+        assert_eq!(title_words(title), expected);
+    }
+
+    #[test]
+    fn test_find_similar_ranking() {
+        let target = bookmark(
+            1,
+            "https://doc.rust-lang.org/book",
+            "The Rust Book",
+            ",rust,docs,",
+        );
+        let candidates = vec![
+            target.clone(),
+            bookmark(2, "https://example.com", "Unrelated", ",cooking,"),
+            bookmark(3, "https://rust-lang.org", "Rust Language", ",Rust,lang,"),
+            bookmark(
+                4,
+                "https://www.doc.rust-lang.org/std",
+                "Standard library",
+                ",rust,docs,",
+            ),
+            bookmark(5, "https://doc.rust-lang.org/nomicon", "Nomicon", ""),
+        ];
+
+        let similar = find_similar(&target, &candidates, 10);
+        let ids: Vec<usize> = similar.iter().map(|s| s.bookmark.id).collect();
+        assert_eq!(ids, vec![4, 3, 5]);
+
+        assert_eq!(similar[0].shared_tags, vec!["docs", "rust"]);
+        assert!(similar[0].same_domain);
+        assert_eq!(similar[1].shared_tags, vec!["rust"]);
+        assert!(!similar[1].same_domain);
+        assert!(similar.iter().all(|s| s.score > 0.0 && s.score <= 1.0));
+
+        assert_eq!(find_similar(&target, &candidates, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_similar_to() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://a.com/1", "Rust async", ",rust,", "", None)
+            .unwrap();
+        db.add_rec(
+            "https://b.com",
+            "Async Rust patterns",
+            ",rust,async,",
+            "",
+            None,
+        )
+        .unwrap();
+
+        let similar = similar_to(&db, 1, DEFAULT_SIMILAR_LIMIT).unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].bookmark.id, 2);
+
+        assert!(matches!(
+            similar_to(&db, 99, DEFAULT_SIMILAR_LIMIT),
+            Err(BukursError::BookmarkNotFound(99))
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::db::BukuDb;
+use crate::tags::parse_tags;
+use crate::utils::url_domain;
+use std::collections::HashMap;
+
+/// Number of tags suggested when no limit is given
+pub const DEFAULT_SUGGEST_LIMIT: usize = 5;
+
+/// Weight of a tag already used on a bookmark from the same domain
+const NEIGHBOUR_WEIGHT: u32 = 2;
+/// Weight of a page keyword or the site name
+const KEYWORD_WEIGHT: u32 = 1;
+
+/// Longest keyword accepted as a tag
+const MAX_TAG_LEN: usize = 32;
+
+/// Second-level labels under country TLDs, as in `bbc.co.uk`
+const SECOND_LEVEL: &[&str] = &["ac", "co", "com", "edu", "gov", "net", "org"];
+
+/// Simple frequency model for tag suggestions
+///
+/// Each signal adds weight to a candidate tag; tags already used on the same
+/// domain count more than page keywords, since they reflect the user's own tagging.
+#[derive(Debug, Default)]
+pub struct TagSuggester {
+    scores: HashMap<String, u32>,
+}
+
+/// Normalize a keyword into a tag: lowercase, words joined by `-`
+fn normalize(keyword: &str) -> Option<String> {
+    let tag = keyword
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    if tag.is_empty() || tag.len() > MAX_TAG_LEN {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+/// Site name of a domain: `github` for `github.com`, `bbc` for `news.bbc.co.uk`
+fn site_name(domain: &str) -> Option<&str> {
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 || labels.iter().all(|l| l.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let mut idx = labels.len() - 2;
+    if idx > 0 && labels[labels.len() - 1].len() == 2 && SECOND_LEVEL.contains(&labels[idx]) {
+        idx -= 1;
+    }
+    Some(labels[idx])
+}
+
+impl TagSuggester {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bump(&mut self, tag: String, weight: u32) {
+        *self.scores.entry(tag).or_insert(0) += weight;
+    }
+
+    /// Suggest the site name of the URL's domain
+    pub fn add_domain(&mut self, url: &str) {
+        if let Some(name) = url_domain(url).as_deref().and_then(site_name) {
+            if let Some(tag) = normalize(name) {
+                self.bump(tag, KEYWORD_WEIGHT);
+            }
+        }
+    }
+
+    /// Suggest comma-separated page keywords (e.g. from `<meta name="keywords">`)
+    pub fn add_keywords(&mut self, keywords: &str) {
+        for tag in parse_tags(keywords).iter().filter_map(|k| normalize(k)) {
+            self.bump(tag, KEYWORD_WEIGHT);
+        }
+    }
+
+    /// Suggest the tags of another bookmark on the same domain
+    pub fn add_neighbour_tags(&mut self, tags: &str) {
+        for tag in parse_tags(tags) {
+            self.bump(tag.to_lowercase(), NEIGHBOUR_WEIGHT);
+        }
+    }
+
+    /// Highest scoring tags not in `exclude`, best first (ties broken alphabetically)
+    pub fn suggest(&self, exclude: &[String], limit: usize) -> Vec<String> {
+        let mut ranked: Vec<(&String, u32)> = self
+            .scores
+            .iter()
+            .filter(|(tag, _)| !exclude.iter().any(|e| e.eq_ignore_ascii_case(tag)))
+            .map(|(tag, score)| (tag, *score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(tag, _)| tag.clone())
+            .collect()
+    }
+}
+
+/// Suggest tags for a new bookmark from its domain, fetched keywords and
+/// the tags of existing bookmarks on the same domain
+pub fn suggest_tags(
+    db: &BukuDb,
+    url: &str,
+    keywords: &str,
+    exclude: &[String],
+    limit: usize,
+) -> crate::error::Result<Vec<String>> {
+    let mut suggester = TagSuggester::new();
+    suggester.add_domain(url);
+    suggester.add_keywords(keywords);
+    if let Some(domain) = url_domain(url) {
+        for tags in db.get_tags_for_domain(&domain)? {
+            suggester.add_neighbour_tags(&tags);
+        }
+    }
+    Ok(suggester.suggest(exclude, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://github.com/rust-lang", Some("github.com"))]
+    #[case("http://www.Example.COM:8080/path?q=1", Some("example.com"))]
+    #[case("https://user@docs.rs#top", Some("docs.rs"))]
+    #[case("example.com/no-scheme", None)]
+    #[case("file:///etc/hosts", None)]
+    fn test_url_domain(#[case] url: &str, #[case] expected: Option<&str>) {
+        assert_eq!(url_domain(url).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("github.com", Some("github"))]
+    #[case("blog.rust-lang.org", Some("rust-lang"))]
+    #[case("news.bbc.co.uk", Some("bbc"))]
+    #[case("docs.rs", Some("docs"))]
+    #[case("localhost", None)]
+    #[case("127.0.0.1", None)]
+    fn test_site_name(#[case] domain: &str, #[case] expected: Option<&str>) {
+        assert_eq!(site_name(domain), expected);
+    }
+
+    #[test]
+    fn test_suggest_ranks_neighbour_tags_first() {
+        let mut suggester = TagSuggester::new();
+        suggester.add_domain("https://github.com/foo");
+        suggester.add_keywords("Open Source, rust, code");
+        suggester.add_neighbour_tags(",code,dev,");
+        suggester.add_neighbour_tags(",Code,");
+
+        assert_eq!(
+            suggester.suggest(&[], 10),
+            vec!["code", "dev", "github", "open-source", "rust"]
+        );
+        assert_eq!(
+            suggester.suggest(&["CODE".to_string()], 2),
+            vec!["dev", "github"]
+        );
+    }
+
+    #[test]
+    fn test_suggest_tags_from_db() {
+        let db = BukuDb::init_in_memory().unwrap();
+        db.add_rec("https://github.com/a", "", ",code,", "", None)
+            .unwrap();
+        db.add_rec("https://gitlab.com/b", "", ",elsewhere,", "", None)
+            .unwrap();
+
+        let tags = suggest_tags(&db, "https://github.com/new", "", &[], 5).unwrap();
+        assert_eq!(tags, vec!["code", "github"]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Bulk tag rewrites from a CSV mapping (`tag apply-map`)
+//!
+//! Each row is `old_tag,new_tag` or `url_pattern,tag`:
+//!
+//! ```csv
+//! old_tag,new_tag
+//! Bookmarks Bar,
+//! Programming,code
+//! Rust Stuff,rust
+//! https://github.com/rust-lang/*,rust
+//! ```
+//!
+//! A first column containing `://` is a URL pattern whose bookmarks gain the
+//! tag; `*` matches any run of characters, and a pattern without one matches
+//! URLs starting with it. Any other row renames a tag, or drops it when the
+//! new tag is empty; listing a tag twice splits it in two. Rewrites aren't
+//! chained: `a,b` and `b,c` turn `a` into `b`, not `c`.
+
+use crate::error::{BukursError, Result};
+use crate::tags::{format_tags, normalize_tags, parse_tags};
+
+/// One row of a mapping file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagMapRule {
+    /// Replace tag `from` with `to`, or drop it when `to` is None
+    Rename { from: String, to: Option<String> },
+    /// Tag bookmarks whose URL matches `pattern`
+    Url { pattern: String, tag: String },
+}
+
+/// The rules of a mapping file, in file order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagMap {
+    pub rules: Vec<TagMapRule>,
+}
+
+impl TagMap {
+    /// Parse a mapping file; blank lines, `#` comments and an `old_tag,new_tag`
+    /// or `url_pattern,tag` header are skipped
+    pub fn parse(csv: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        let mut first = true;
+        for (i, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = split_row(line);
+            let [key, value] = fields.as_slice() else {
+                return Err(BukursError::InvalidInput(format!(
+                    "Line {}: expected two columns, got {}",
+                    i + 1,
+                    fields.len()
+                )));
+            };
+            let is_header = first
+                && matches!(
+                    (key.to_lowercase().as_str(), value.to_lowercase().as_str()),
+                    ("old_tag", "new_tag") | ("url_pattern", "tag")
+                );
+            first = false;
+            if is_header {
+                continue;
+            }
+
+            if key.is_empty() {
+                return Err(BukursError::InvalidInput(format!(
+                    "Line {}: the first column is empty",
+                    i + 1
+                )));
+            }
+            if key.contains("://") {
+                if value.is_empty() {
+                    return Err(BukursError::InvalidInput(format!(
+                        "Line {}: no tag for URL pattern {}",
+                        i + 1,
+                        key
+                    )));
+                }
+                rules.push(TagMapRule::Url {
+                    pattern: key.clone(),
+                    tag: value.clone(),
+                });
+            } else {
+                rules.push(TagMapRule::Rename {
+                    from: key.clone(),
+                    to: (!value.is_empty()).then(|| value.clone()),
+                });
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The stored `tags` of a bookmark at `url` with the rules applied,
+    /// deduplicated and sorted; None when no rule changes them
+    pub fn apply(&self, url: &str, tags: &str) -> Option<String> {
+        let current = parse_tags(tags);
+        let mut changed = false;
+        let mut rewritten = Vec::new();
+        for tag in &current {
+            let mut renamed = false;
+            for rule in &self.rules {
+                if let TagMapRule::Rename { from, to } = rule {
+                    if from == tag {
+                        renamed = true;
+                        rewritten.extend(to.clone());
+                    }
+                }
+            }
+            changed |= renamed;
+            if !renamed {
+                rewritten.push(tag.clone());
+            }
+        }
+        for rule in &self.rules {
+            if let TagMapRule::Url { pattern, tag } = rule {
+                if url_matches(url, pattern) && !rewritten.contains(tag) {
+                    changed = true;
+                    rewritten.push(tag.clone());
+                }
+            }
+        }
+        if !changed {
+            return None;
+        }
+        let tags_after = format_tags(&normalize_tags(rewritten, false));
+        (tags_after != tags).then_some(tags_after)
+    }
+}
+
+/// The fields of a CSV row, trimmed; double quotes may wrap a field, with
+/// `""` standing for a quote inside one
+fn split_row(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Whether `url` matches `pattern`, where `*` matches any run of characters
+/// and a pattern without `*` is a prefix
+fn url_matches(url: &str, pattern: &str) -> bool {
+    if !pattern.contains('*') {
+        return url.starts_with(pattern);
+    }
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().expect("split yields a part");
+    let Some(mut remaining) = url.strip_prefix(first) else {
+        return false;
+    };
+    let (last, middle) = rest.split_last().expect("pattern contains *");
+    for part in middle {
+        match remaining.find(part) {
+            Some(i) => remaining = &remaining[i + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const MAP: &str = "\
+old_tag,new_tag
+# folders from the browser import
+Bookmarks Bar,
+Programming,code
+\"Dev Tools\",devtools
+lang,rust
+lang,code
+https://github.com/*,github
+";
+
+    #[test]
+    fn test_parse() {
+        let map = TagMap::parse(MAP).unwrap();
+        assert_eq!(map.rules.len(), 6);
+        assert_eq!(
+            map.rules[0],
+            TagMapRule::Rename {
+                from: "Bookmarks Bar".to_string(),
+                to: None
+            }
+        );
+        assert_eq!(
+            map.rules[2],
+            TagMapRule::Rename {
+                from: "Dev Tools".to_string(),
+                to: Some("devtools".to_string())
+            }
+        );
+        assert_eq!(
+            map.rules[5],
+            TagMapRule::Url {
+                pattern: "https://github.com/*".to_string(),
+                tag: "github".to_string()
+            }
+        );
+    }
+
+    #[rstest]
+    #[case("a,b,c")]
+    #[case("justone")]
+    #[case(",new")]
+    #[case("https://example.com/,")]
+    fn test_parse_rejects_bad_rows(#[case] row: &str) {
+        assert!(matches!(
+            TagMap::parse(row),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+
+    #[rstest]
+    #[case("https://a.com", ",Programming,web,", Some(",code,web,"))]
+    #[case("https://a.com", ",Bookmarks Bar,", Some(","))]
+    #[case("https://a.com", ",lang,", Some(",code,rust,"))]
+    #[case("https://a.com", ",code,Programming,", Some(",code,"))]
+    #[case("https://github.com/x", ",", Some(",github,"))]
+    #[case("https://github.com/x", ",github,", None)]
+    #[case("https://a.com", ",web,b,", None)]
+    fn test_apply(#[case] url: &str, #[case] tags: &str, #[case] expected: Option<&str>) {
+        let map = TagMap::parse(MAP).unwrap();
+        assert_eq!(map.apply(url, tags).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("https://github.com/rust-lang/rust", "https://github.com/", true)]
+    #[case("https://gitlab.com/", "https://github.com/", false)]
+    #[case("https://blog.rust-lang.org/2024", "*://*.rust-lang.org/*", true)]
+    #[case("https://rust-lang.org/", "*://*.rust-lang.org/*", false)]
+    #[case("https://a.com/x.pdf", "https://*.pdf", true)]
+    #[case("https://a.com/x.pdf?dl=1", "https://*.pdf", false)]
+    fn test_url_matches(#[case] url: &str, #[case] pattern: &str, #[case] expected: bool) {
+        assert_eq!(url_matches(url, pattern), expected);
+    }
+}
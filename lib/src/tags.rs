@@ -1,6 +1,21 @@
+use crate::error::{BukursError, Result};
 use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use strs_tools::string;
 
+/// How [`combine_tags`] merges a list of tags into a bookmark's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagSetOp {
+    /// Union: add the tags
+    Add,
+    /// Difference: take the tags away
+    Remove,
+    /// Replace: keep exactly the tags
+    Set,
+}
+
 /// Parse comma-separated tags, filtering empty ones
 /// Note: strs_tools could be used for SIMD, but standard split is efficient for small tag strings
 pub fn parse_tags<S: AsRef<str>>(tags_str: S) -> Vec<String> {
@@ -15,6 +30,205 @@ pub fn parse_tags<S: AsRef<str>>(tags_str: S) -> Vec<String> {
         .collect()
 }
 
+/// Format tags in the stored `,a,b,` form (a lone `,` when there are none)
+pub fn format_tags<S: AsRef<str>>(tags: &[S]) -> String {
+    if tags.is_empty() {
+        ",".to_string()
+    } else {
+        let joined: Vec<&str> = tags.iter().map(|t| t.as_ref()).collect();
+        format!(",{},", joined.join(","))
+    }
+}
+
+/// Tags without repeats, sorted; lowercased first with `lowercase`
+pub fn normalize_tags(tags: impl IntoIterator<Item = String>, lowercase: bool) -> Vec<String> {
+    tags.into_iter()
+        .map(|tag| if lowercase { tag.to_lowercase() } else { tag })
+        .filter(|tag| !tag.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// The stored `existing` tags combined with `tags` by `op`, normalized and
+/// in the stored form
+pub fn combine_tags(existing: &str, tags: &[String], op: TagSetOp, lowercase: bool) -> String {
+    let current = normalize_tags(parse_tags(existing), lowercase);
+    let given = normalize_tags(tags.iter().cloned(), lowercase);
+    let combined = match op {
+        TagSetOp::Add => normalize_tags(current.into_iter().chain(given), false),
+        TagSetOp::Remove => current
+            .into_iter()
+            .filter(|tag| given.binary_search(tag).is_err())
+            .collect(),
+        TagSetOp::Set => given,
+    };
+    format_tags(&combined)
+}
+
+/// One change to a bookmark's tags, as given to `update --tag`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagOp {
+    /// `+tag`, or a tag without a prefix
+    Add(String),
+    /// `-tag`
+    Remove(String),
+    /// `~old:new`
+    Rename { from: String, to: String },
+}
+
+/// Parse tag operations, each argument holding one or more separated by commas
+/// (`+urgent,-archived,~todo:done`)
+///
+/// Tags with spaces, a missing tag name and a `~` without `:` are errors, so
+/// a typo doesn't half-apply.
+pub fn parse_tag_ops<S: AsRef<str>>(args: &[S]) -> Result<Vec<TagOp>> {
+    let mut ops = Vec::new();
+    for item in args.iter().flat_map(|arg| arg.as_ref().split(',')) {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        let op = if let Some(tag) = item.strip_prefix('+') {
+            TagOp::Add(tag_name(tag, item)?)
+        } else if let Some(tag) = item.strip_prefix('-') {
+            TagOp::Remove(tag_name(tag, item)?)
+        } else if let Some(rename) = item.strip_prefix('~') {
+            let (from, to) = rename.split_once(':').ok_or_else(|| {
+                BukursError::InvalidInput(format!(
+                    "Invalid tag rename '{}' (expected ~old:new)",
+                    item
+                ))
+            })?;
+            TagOp::Rename {
+                from: tag_name(from, item)?,
+                to: tag_name(to, item)?,
+            }
+        } else {
+            TagOp::Add(tag_name(item, item)?)
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+/// A tag named in the tag operation `item`
+fn tag_name(tag: &str, item: &str) -> Result<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(BukursError::InvalidInput(format!(
+            "Missing tag name in '{}'",
+            item
+        )));
+    }
+    if utils::has_spaces(tag) {
+        return Err(BukursError::InvalidInput(format!(
+            "Tag '{}' contains spaces",
+            tag
+        )));
+    }
+    Ok(utils::nfc(tag).into_owned())
+}
+
+/// The stored `existing` tags with `ops` applied in order, normalized and in
+/// the stored form
+///
+/// Removing or renaming a tag the bookmark doesn't have changes nothing; a
+/// rename onto a tag it already has merges the two.
+pub fn apply_tag_ops(existing: &str, ops: &[TagOp]) -> String {
+    let mut tags = parse_tags(existing);
+    for op in ops {
+        match op {
+            TagOp::Add(tag) => {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            TagOp::Remove(tag) => tags.retain(|t| t != tag),
+            TagOp::Rename { from, to } => {
+                if tags.contains(from) {
+                    tags.retain(|t| t != from && t != to);
+                    tags.push(to.clone());
+                }
+            }
+        }
+    }
+    format_tags(&normalize_tags(tags, false))
+}
+
+/// Which fixes `tag normalize` makes to stored tags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagNormalization {
+    pub lowercase: bool,
+    /// Strip whitespace around each tag
+    pub trim: bool,
+    /// Drop repeats of a tag within a bookmark
+    pub dedupe: bool,
+}
+
+/// Tags that become one tag, or are renamed, under a [`TagNormalization`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagMerge {
+    pub tag: String,
+    /// The spellings found, sorted
+    pub from: Vec<String>,
+}
+
+impl TagNormalization {
+    /// Every fix
+    pub fn all() -> Self {
+        Self {
+            lowercase: true,
+            trim: true,
+            dedupe: true,
+        }
+    }
+
+    /// `tag` as the normalization writes it
+    pub fn tag(&self, tag: &str) -> String {
+        let tag = if self.trim { tag.trim() } else { tag };
+        if self.lowercase {
+            tag.to_lowercase()
+        } else {
+            tag.to_string()
+        }
+    }
+
+    /// The stored tags `tags` normalized, in the stored form
+    ///
+    /// Unlike [`parse_tags`], this sees the tags as stored, surrounding
+    /// whitespace included.
+    pub fn apply(&self, tags: &str) -> String {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags.split(',').map(|tag| self.tag(tag)) {
+            if tag.trim().is_empty() || (self.dedupe && normalized.contains(&tag)) {
+                continue;
+            }
+            normalized.push(tag);
+        }
+        format_tags(&normalized)
+    }
+
+    /// The tags across `tag_strings` (stored forms) that the normalization
+    /// merges or renames, by resulting tag
+    pub fn merges<'a>(&self, tag_strings: impl IntoIterator<Item = &'a str>) -> Vec<TagMerge> {
+        let mut spellings: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for tag in tag_strings.into_iter().flat_map(|tags| tags.split(',')) {
+            if !tag.trim().is_empty() {
+                spellings.entry(self.tag(tag)).or_default().insert(tag);
+            }
+        }
+        spellings
+            .into_iter()
+            .filter(|(tag, from)| from.len() > 1 || !from.contains(tag.as_str()))
+            .map(|(tag, from)| TagMerge {
+                tag,
+                from: from.into_iter().map(String::from).collect(),
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,4 +262,126 @@ mod tests {
         let result = parse_tags(",rust,测试,программирование,");
         assert_eq!(result, vec!["rust", "测试", "программирование"]);
     }
+
+    #[rstest]
+    #[case(vec![], ",")]
+    #[case(vec!["rust"], ",rust,")]
+    #[case(vec!["rust", "web"], ",rust,web,")]
+    fn test_format_tags(#[case] tags: Vec<&str>, #[case] expected: &str) {
+        assert_eq!(format_tags(&tags), expected);
+    }
+
+    #[rstest]
+    #[case(vec!["b", "a", "b", ""], false, vec!["a", "b"])]
+    #[case(vec!["Rust", "rust", "Web"], false, vec!["Rust", "Web", "rust"])]
+    #[case(vec!["Rust", "rust", "Web"], true, vec!["rust", "web"])]
+    fn test_normalize_tags(
+        #[case] tags: Vec<&str>,
+        #[case] lowercase: bool,
+        #[case] expected: Vec<&str>,
+    ) {
+        let tags = tags.into_iter().map(String::from);
+        assert_eq!(normalize_tags(tags, lowercase), expected);
+    }
+
+    #[rstest]
+    #[case(",web,rust,web,", vec!["async", "rust"], TagSetOp::Add, false, ",async,rust,web,")]
+    #[case(",", vec!["rust"], TagSetOp::Add, false, ",rust,")]
+    #[case(",web,rust,", vec!["rust", "gone"], TagSetOp::Remove, false, ",web,")]
+    #[case(",Rust,", vec!["rust"], TagSetOp::Remove, false, ",Rust,")]
+    #[case(",Rust,", vec!["rust"], TagSetOp::Remove, true, ",")]
+    #[case(",web,rust,", vec!["c", "b", "a"], TagSetOp::Set, false, ",a,b,c,")]
+    #[case(",web,", vec![], TagSetOp::Set, false, ",")]
+    #[case(",Web,", vec!["Rust"], TagSetOp::Add, true, ",rust,web,")]
+    fn test_combine_tags(
+        #[case] existing: &str,
+        #[case] tags: Vec<&str>,
+        #[case] op: TagSetOp,
+        #[case] lowercase: bool,
+        #[case] expected: &str,
+    ) {
+        let tags: Vec<String> = tags.into_iter().map(String::from).collect();
+        assert_eq!(combine_tags(existing, &tags, op, lowercase), expected);
+    }
+
+    #[rstest]
+    #[case(&["+foo"], vec![TagOp::Add("foo".into())])]
+    #[case(&["-bar"], vec![TagOp::Remove("bar".into())])]
+    #[case(&["~old:new"], vec![TagOp::Rename { from: "old".into(), to: "new".into() }])]
+    #[case(&["simple"], vec![TagOp::Add("simple".into())])]
+    #[case(&["+a,-b", "~c:d"], vec![
+        TagOp::Add("a".into()),
+        TagOp::Remove("b".into()),
+        TagOp::Rename { from: "c".into(), to: "d".into() },
+    ])]
+    #[case(&[",+a,, b ,"], vec![TagOp::Add("a".into()), TagOp::Add("b".into())])]
+    #[case(&[], vec![])]
+    fn test_parse_tag_ops(#[case] args: &[&str], #[case] expected: Vec<TagOp>) {
+        assert_eq!(parse_tag_ops(args).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("~nocolon")]
+    #[case("~old:")]
+    #[case("~:new")]
+    #[case("+")]
+    #[case("-")]
+    #[case("two words")]
+    #[case("+ok,-two words")]
+    #[case("~old space:new")]
+    fn test_parse_tag_ops_rejects(#[case] arg: &str) {
+        assert!(matches!(
+            parse_tag_ops(&[arg]),
+            Err(BukursError::InvalidInput(_))
+        ));
+    }
+
+    #[rstest]
+    #[case(",", &["new"], ",new,")]
+    #[case(",existing,", &["+new"], ",existing,new,")]
+    #[case(",foo,bar,", &["-bar"], ",foo,")]
+    #[case(",foo,bar,baz,", &["~bar:qux"], ",baz,foo,qux,")]
+    #[case(",foo,", &["foo"], ",foo,")]
+    #[case(",foo,bar,", &["-baz"], ",bar,foo,")]
+    #[case(",foo,bar,", &["~baz:qux"], ",bar,foo,")]
+    #[case(",todo,done,", &["~todo:done"], ",done,")]
+    #[case(",rust,tech,old,", &["+new,-tech,~old:fresh"], ",fresh,new,rust,")]
+    #[case(",a,", &["+b", "-b"], ",a,")]
+    #[case(",a,", &["~a:b", "~b:c"], ",c,")]
+    fn test_apply_tag_ops(#[case] existing: &str, #[case] args: &[&str], #[case] expected: &str) {
+        let ops = parse_tag_ops(args).unwrap();
+        assert_eq!(apply_tag_ops(existing, &ops), expected);
+    }
+
+    #[rstest]
+    #[case(",Rust, rust,web,rust,", TagNormalization::all(), ",rust,web,")]
+    #[case(",Rust, rust,web,rust,", TagNormalization { trim: true, ..Default::default() }, ",Rust,rust,web,rust,")]
+    #[case(",Rust, rust,", TagNormalization { lowercase: true, ..Default::default() }, ",rust, rust,")]
+    #[case(",a,a,", TagNormalization { dedupe: true, ..Default::default() }, ",a,")]
+    #[case("a,b", TagNormalization::default(), ",a,b,")]
+    #[case(", ,", TagNormalization::all(), ",")]
+    fn test_normalization_apply(
+        #[case] tags: &str,
+        #[case] normalization: TagNormalization,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(normalization.apply(tags), expected);
+    }
+
+    #[test]
+    fn test_normalization_merges() {
+        let merges = TagNormalization::all().merges([",Rust,web,", ", rust,", ",rust,web,"]);
+        assert_eq!(
+            merges,
+            vec![TagMerge {
+                tag: "rust".to_string(),
+                from: vec![" rust".to_string(), "Rust".to_string(), "rust".to_string()],
+            }]
+        );
+
+        // A lone spelling that changes is a rename
+        let merges = TagNormalization::all().merges([",Go,"]);
+        assert_eq!(merges[0].from, vec!["Go"]);
+        assert!(TagNormalization::all().merges([",go,"]).is_empty());
+    }
 }
@@ -1,5 +1,7 @@
 use memchr::memchr;
+use std::borrow::Cow;
 use std::path::PathBuf;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
 
 pub fn get_default_dbdir() -> PathBuf {
     if let Ok(path) = std::env::var("BUKU_DEFAULT_DBDIR") {
@@ -128,3 +130,242 @@ pub fn split_colon_no_space(s: &str) -> Option<(&str, &str)> {
         None
     }
 }
+
+/// `s` in Unicode normalization form C, so "café" has one representation
+/// however it was typed; borrowed when it already is
+pub fn nfc(s: &str) -> Cow<'_, str> {
+    if is_nfc_quick(s.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(s.nfc().collect())
+    }
+}
+
+/// `s` lowercased with its diacritics removed ("Café" -> "cafe"), for
+/// matching text the way the FTS5 index does
+///
+/// Like FTS5's `remove_diacritics`, only the combining accents Latin letters
+/// decompose into are dropped; other scripts' marks (e.g. Japanese dakuten) stay.
+pub fn fold_diacritics(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !('\u{300}'..='\u{36f}').contains(c))
+        .flat_map(char::to_lowercase)
+        .nfc()
+        .collect()
+}
+
+/// Host of a URL, lowercased and without a leading `www.` or port
+/// Returns None for URLs without a `scheme://host` part
+pub fn url_domain(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.rsplit('@').next().unwrap_or("");
+    let host = host.split(':').next().unwrap_or("").to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_string())
+    }
+}
+
+/// Whether `domain` (as [`url_domain`] gives it) is `site` or one of its subdomains
+pub fn domain_matches(domain: &str, site: &str) -> bool {
+    let site = site.trim().trim_start_matches("www.").to_lowercase();
+    domain == site
+        || domain
+            .strip_suffix(site.as_str())
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = (
+                (bytes[i + 1] as char).to_digit(16),
+                (bytes[i + 2] as char).to_digit(16),
+            );
+            if let (Some(hi), Some(lo)) = hex {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Replace the character references common in links and their text
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                entity => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                    .and_then(|code| code.ok())
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days_from_civil inverse, valid for the proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) civil date to days since the Unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parse a `YYYY-MM-DD` date into the Unix timestamp of its start (UTC)
+pub fn parse_date(s: &str) -> Option<i64> {
+    let mut parts = s.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=9999).contains(&year) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Reject dates like 2024-02-30 that don't round-trip
+    (civil_from_days(days) == (year, month, day)).then_some(days * 86_400)
+}
+
+/// Split a Unix timestamp into its civil date, time of day and weekday (0 = Sunday)
+pub(crate) fn split_timestamp(ts: i64) -> ((i64, u32, u32), (i64, i64, i64), usize) {
+    let days = ts.div_euclid(86_400);
+    let secs = ts.rem_euclid(86_400);
+    let weekday = (days + 4).rem_euclid(7) as usize;
+    (
+        civil_from_days(days),
+        (secs / 3600, secs % 3600 / 60, secs % 60),
+        weekday,
+    )
+}
+
+/// Format a Unix timestamp as an RFC 3339 UTC date-time, e.g. `2024-05-01T12:00:00Z`
+pub fn format_rfc3339(ts: i64) -> String {
+    let ((y, mo, d), (h, mi, s), _) = split_timestamp(ts);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, mo, d, h, mi, s)
+}
+
+/// Format a Unix timestamp as an RFC 2822 UTC date, e.g. `Wed, 01 May 2024 12:00:00 +0000`
+pub fn format_rfc2822(ts: i64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let ((y, mo, d), (h, mi, s), wd) = split_timestamp(ts);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[wd],
+        d,
+        MONTHS[mo as usize - 1],
+        y,
+        h,
+        mi,
+        s
+    )
+}
+
+/// Parse a size such as `256MB`, `64M`, `1G` or `512K` into bytes
+///
+/// Units are binary (`1K` = 1024 bytes), with or without a trailing `B`
+/// or `iB`; a bare number is taken as bytes.
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let number: u64 = number.parse().ok()?;
+    let unit = unit.trim().to_uppercase();
+    let unit = unit
+        .strip_suffix("IB")
+        .or_else(|| unit.strip_suffix('B'))
+        .unwrap_or(&unit);
+    let shift = match unit {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return None,
+    };
+    number.checked_mul(1 << shift)
+}
+
+/// Parse a duration such as `30d`, `12h`, `2w` or `1y` into seconds
+///
+/// A bare number is taken as days.
+pub fn parse_duration(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "d"),
+    };
+    let number: u64 = number.parse().ok()?;
+    let unit_secs = match unit.to_lowercase().as_str() {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        "y" => 365 * 86_400,
+        _ => return None,
+    };
+    number.checked_mul(unit_secs)
+}
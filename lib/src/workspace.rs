@@ -0,0 +1,106 @@
+//! Project-local databases
+//!
+//! A `.bukurs.toml` file marks a directory as a workspace, the way `.git`
+//! marks a repository. Commands run anywhere below it use the workspace's
+//! database instead of the default location, so a team can keep a shared
+//! link collection versioned next to its code:
+//!
+//! ```toml
+//! # .bukurs.toml
+//! db = "docs/links.db"   # relative to this file; "bookmarks.db" when left out
+//! ```
+//!
+//! `db` from the config, `BUKURS_DB`, `--db` and `--profile` still win.
+
+use crate::error::{BukursError, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Name of the file marking a workspace
+pub const WORKSPACE_FILE: &str = ".bukurs.toml";
+
+/// Database file of a workspace that doesn't name one
+const DEFAULT_WORKSPACE_DB: &str = "bookmarks.db";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFile {
+    db: Option<PathBuf>,
+}
+
+/// A directory with a [`WORKSPACE_FILE`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    /// The directory holding the workspace file
+    pub root: PathBuf,
+    /// The workspace's database
+    pub db: PathBuf,
+}
+
+impl Workspace {
+    /// Read the workspace file in `root`
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(WORKSPACE_FILE);
+        let contents = std::fs::read_to_string(&path)?;
+        let file: WorkspaceFile = toml::from_str(&contents)
+            .map_err(|e| BukursError::Config(format!("{}: {}", path.display(), e)))?;
+        let db = file
+            .db
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_WORKSPACE_DB));
+        Ok(Self {
+            root: root.to_path_buf(),
+            // join keeps absolute paths as they are
+            db: root.join(db),
+        })
+    }
+}
+
+/// The workspace `start` or one of its parents is in, the nearest first
+pub fn find(start: &Path) -> Result<Option<Workspace>> {
+    for dir in start.ancestors() {
+        if dir.join(WORKSPACE_FILE).is_file() {
+            return Workspace::load(dir).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", "bookmarks.db")]
+    #[case("db = \"docs/links.db\"", "docs/links.db")]
+    fn test_find_walks_up_to_workspace(#[case] contents: &str, #[case] db: &str) {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("module");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join(WORKSPACE_FILE), contents).unwrap();
+
+        let workspace = find(&nested).unwrap().unwrap();
+        assert_eq!(workspace.root, dir.path());
+        assert_eq!(workspace.db, dir.path().join(db));
+    }
+
+    #[test]
+    fn test_nearest_workspace_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = dir.path().join("inner");
+        std::fs::create_dir_all(&inner).unwrap();
+        std::fs::write(dir.path().join(WORKSPACE_FILE), "db = \"outer.db\"").unwrap();
+        std::fs::write(inner.join(WORKSPACE_FILE), "db = \"inner.db\"").unwrap();
+
+        assert_eq!(find(&inner).unwrap().unwrap().db, inner.join("inner.db"));
+    }
+
+    #[test]
+    fn test_invalid_workspace_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(WORKSPACE_FILE), "dbb = \"x.db\"").unwrap();
+
+        let result = find(dir.path());
+        assert!(matches!(result, Err(BukursError::Config(ref m)) if m.contains(WORKSPACE_FILE)));
+    }
+}
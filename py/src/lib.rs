@@ -0,0 +1,12 @@
+//! The `bukurs` Python module
+//!
+//! The bindings live in the library behind its `python` feature (see
+//! `bukurs::python`), so they change together with the API they wrap.
+
+use pyo3::prelude::*;
+
+#[pymodule]
+#[pyo3(name = "bukurs")]
+fn bukurs_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    bukurs::python::register(module)
+}